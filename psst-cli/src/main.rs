@@ -3,16 +3,18 @@ use psst_core::{
         equalizer::{EqualizerConfig, EqualizerPreset},
         normalize::NormalizationLevel,
         output::{AudioOutput, AudioSink, DefaultAudioOutput},
+        processor::DspConfig,
     },
     cache::{Cache, CacheHandle},
     cdn::{Cdn, CdnHandle},
     connection::Credentials,
     error::Error,
+    history::{export_schema_markdown, ExportFormat, PlayHistory},
     item_id::{ItemId, ItemIdType},
-    player::{item::PlaybackItem, PlaybackConfig, Player, PlayerCommand, PlayerEvent},
+    player::{item::PlaybackItem, PlaybackConfig, Player, PlayerCommand},
     session::{SessionConfig, SessionService},
 };
-use std::{env, fmt, io, io::BufRead, path::PathBuf, thread};
+use std::{env, fmt, io, io::BufRead, path::PathBuf, thread, time::Duration};
 
 const TEST_MODE_ENV: &str = "PSST_CLI_TEST_MODE";
 
@@ -30,6 +32,12 @@ fn run() -> Result<(), CliError> {
     let _binary = args.next();
 
     let track_id = args.next().ok_or(CliError::MissingTrackId)?;
+    if track_id == "cache" {
+        return run_cache_command(args.next());
+    }
+    if track_id == "history" {
+        return run_history_command(args.collect());
+    }
     let eq_preset_name = args.next();
 
     let username = env::var("SPOTIFY_USERNAME").map_err(|_| CliError::MissingUsername)?;
@@ -53,11 +61,50 @@ fn run() -> Result<(), CliError> {
     let playback_item = PlaybackItem {
         item_id,
         norm_level: NormalizationLevel::Track,
+        group: Default::default(),
+        skip_intro: Duration::ZERO,
     };
 
     start(playback_item, session, equalizer).map_err(CliError::Core)
 }
 
+/// Maintenance of the local cache, without logging in.
+fn run_cache_command(command: Option<String>) -> Result<(), CliError> {
+    match command.as_deref() {
+        Some("verify") => {
+            let cache = Cache::new(PathBuf::from("cache")).map_err(CliError::Core)?;
+            let report = cache.verify().map_err(|err| CliError::Core(err.into()))?;
+            println!(
+                "Checked {} audio files, removed {} broken ({} bytes), {} without a cached key",
+                report.checked, report.removed, report.removed_bytes, report.unverified
+            );
+            Ok(())
+        }
+        _ => Err(CliError::UnknownCacheCommand(command.unwrap_or_default())),
+    }
+}
+
+/// Export of the local listening history, without logging in.  Exports are
+/// written to the standard output.
+fn run_history_command(args: Vec<String>) -> Result<(), CliError> {
+    let args: Vec<_> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["export", format, path] => {
+            let format = ExportFormat::from_name(format)
+                .ok_or_else(|| CliError::UnknownExportFormat(format.to_string()))?;
+            let history = PlayHistory::open(PathBuf::from(*path)).map_err(CliError::Core)?;
+            history
+                .export(format, io::stdout().lock())
+                .map_err(CliError::Core)
+        }
+        ["schema"] => {
+            print!("{}", export_schema_markdown());
+            Ok(())
+        }
+        _ => Err(CliError::UnknownHistoryCommand(args.join(" "))),
+    }
+}
+
 fn configure_equalizer(preset: Option<&str>) -> EqualizerConfig {
     let mut equalizer = EqualizerConfig::default();
 
@@ -91,7 +138,7 @@ fn start(
     session: SessionService,
     equalizer: EqualizerConfig,
 ) -> Result<(), Error> {
-    let cdn = Cdn::new(session.clone(), None)?;
+    let cdn = Cdn::new(session.clone())?;
     let cache = Cache::new(PathBuf::from("cache"))?;
 
     play_item(session, cdn, cache, playback_item, equalizer)
@@ -106,49 +153,42 @@ fn play_item(
 ) -> Result<(), Error> {
     let output = DefaultAudioOutput::open()?;
     let config = PlaybackConfig {
-        equalizer,
+        dsp: DspConfig {
+            equalizer,
+            ..DspConfig::default()
+        },
         ..PlaybackConfig::default()
     };
 
     let mut player = Player::new(session, cdn, cache, config, &output);
 
     let _ui_thread = thread::spawn({
-        let player_sender = player.sender();
+        let player_sender = player.command_sender();
 
         player_sender
-            .send(PlayerEvent::Command(PlayerCommand::LoadQueue {
+            .send(PlayerCommand::LoadQueue {
                 items: vec![item, item, item],
                 position: 0,
-            }))
+            })
             .unwrap();
 
         move || {
             for line in io::stdin().lock().lines() {
                 match line.as_ref().map(|s| s.as_str()) {
                     Ok("p") => {
-                        player_sender
-                            .send(PlayerEvent::Command(PlayerCommand::Pause))
-                            .unwrap();
+                        player_sender.send(PlayerCommand::Pause).unwrap();
                     }
                     Ok("r") => {
-                        player_sender
-                            .send(PlayerEvent::Command(PlayerCommand::Resume))
-                            .unwrap();
+                        player_sender.send(PlayerCommand::Resume).unwrap();
                     }
                     Ok("s") => {
-                        player_sender
-                            .send(PlayerEvent::Command(PlayerCommand::Stop))
-                            .unwrap();
+                        player_sender.send(PlayerCommand::Stop).unwrap();
                     }
                     Ok("<") => {
-                        player_sender
-                            .send(PlayerEvent::Command(PlayerCommand::Previous))
-                            .unwrap();
+                        player_sender.send(PlayerCommand::Previous).unwrap();
                     }
                     Ok(">") => {
-                        player_sender
-                            .send(PlayerEvent::Command(PlayerCommand::Next))
-                            .unwrap();
+                        player_sender.send(PlayerCommand::Next).unwrap();
                     }
                     _ => log::warn!("unknown command"),
                 }
@@ -170,6 +210,9 @@ enum CliError {
     MissingUsername,
     MissingPassword,
     InvalidTrackId(String),
+    UnknownCacheCommand(String),
+    UnknownHistoryCommand(String),
+    UnknownExportFormat(String),
     Core(Error),
 }
 
@@ -186,6 +229,20 @@ impl fmt::Display for CliError {
             CliError::InvalidTrackId(track) => {
                 write!(f, "Invalid Spotify track id: '{track}'")
             }
+            CliError::UnknownCacheCommand(command) => {
+                write!(f, "Unknown cache command: '{command}', expected 'verify'")
+            }
+            CliError::UnknownHistoryCommand(command) => write!(
+                f,
+                "Unknown history command: '{command}', expected 'export <csv|json> <history file>' \
+                 or 'schema'"
+            ),
+            CliError::UnknownExportFormat(format) => {
+                write!(
+                    f,
+                    "Unknown export format: '{format}', expected 'csv' or 'json'"
+                )
+            }
             CliError::Core(err) => write!(f, "{err}"),
         }
     }
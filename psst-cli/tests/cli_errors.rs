@@ -21,3 +21,89 @@ fn cli_exits_with_error_when_track_id_is_missing() {
         "unexpected stderr: {stderr}"
     );
 }
+
+#[test]
+fn cli_exits_with_error_for_unknown_cache_command() {
+    let binary = env!("CARGO_BIN_EXE_psst-cli");
+
+    let output = Command::new(binary)
+        .args(["cache", "defragment"])
+        .output()
+        .expect("failed to invoke psst-cli");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unknown cache command: 'defragment'"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_verifies_cache_without_credentials() {
+    let binary = env!("CARGO_BIN_EXE_psst-cli");
+    let dir = std::env::temp_dir().join(format!("psst-cli-cache-verify-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+
+    let output = Command::new(binary)
+        .current_dir(&dir)
+        .env_remove("SPOTIFY_USERNAME")
+        .env_remove("SPOTIFY_PASSWORD")
+        .args(["cache", "verify"])
+        .output()
+        .expect("failed to invoke psst-cli");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(output.status.success(), "psst-cli failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Checked 0 audio files"),
+        "unexpected stdout: {stdout}"
+    );
+}
+
+#[test]
+fn cli_exports_history_as_csv_without_credentials() {
+    let binary = env!("CARGO_BIN_EXE_psst-cli");
+    let dir = std::env::temp_dir().join(format!("psst-cli-history-export-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    let history = dir.join("history.jsonl");
+    std::fs::write(
+        &history,
+        r#"{"track_id":"a","name":"Song","artist":"Artist","album":"Album","played_at":10}"#,
+    )
+    .expect("failed to write history");
+
+    let output = Command::new(binary)
+        .env_remove("SPOTIFY_USERNAME")
+        .env_remove("SPOTIFY_PASSWORD")
+        .args(["history", "export", "csv"])
+        .arg(&history)
+        .output()
+        .expect("failed to invoke psst-cli");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(output.status.success(), "psst-cli failed: {output:?}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "track_id,name,artist,album,played_at\r\na,Song,Artist,Album,10\r\n"
+    );
+}
+
+#[test]
+fn cli_history_schema_matches_docs() {
+    let binary = env!("CARGO_BIN_EXE_psst-cli");
+
+    let output = Command::new(binary)
+        .args(["history", "schema"])
+        .output()
+        .expect("failed to invoke psst-cli");
+
+    assert!(output.status.success(), "psst-cli failed: {output:?}");
+    let docs = include_str!("../../docs/HISTORY_EXPORT.md");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        docs,
+        "docs/HISTORY_EXPORT.md is out of date, regenerate it with `psst-cli history schema`"
+    );
+}
@@ -0,0 +1,96 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use super::{
+    equalizer::{BiquadCoefficients, BiquadState},
+    processor::{AudioProcessor, DspConfig},
+};
+
+/// Frequency below which both channels carry the same signal.
+const CROSSOVER: f32 = 120.0;
+
+/// Folds the bass of a stereo signal into mono, like vinyl mastering does.
+/// Low frequencies carry little directional information, and speakers or
+/// headphones that reproduce them out of phase lose bass.
+///
+/// The signal is split into mid and side at a Linkwitz-Riley crossover.  The
+/// side keeps only its highs, while the low and high bands of the mid are
+/// summed back, which only shifts their phase.
+pub struct BassMono {
+    channels: usize,
+    enabled: bool,
+    mid_low: LinkwitzRiley,
+    mid_high: LinkwitzRiley,
+    side_high: LinkwitzRiley,
+}
+
+impl BassMono {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let low = BiquadCoefficients::low_pass(CROSSOVER, FRAC_1_SQRT_2, sample_rate);
+        let high = BiquadCoefficients::high_pass(CROSSOVER, FRAC_1_SQRT_2, sample_rate);
+        Self {
+            channels,
+            enabled: false,
+            mid_low: LinkwitzRiley::new(low),
+            mid_high: LinkwitzRiley::new(high),
+            side_high: LinkwitzRiley::new(high),
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            // Do not ring with whatever was left over from the last time.
+            self.mid_low.reset();
+            self.mid_high.reset();
+            self.side_high.reset();
+        }
+        self.enabled = enabled;
+    }
+}
+
+impl AudioProcessor for BassMono {
+    fn process(&mut self, samples: &mut [f32]) {
+        if !self.enabled || self.channels < 2 {
+            return;
+        }
+        // Only the first two channels form the stereo pair.
+        for frame in samples.chunks_exact_mut(self.channels) {
+            let mid = (frame[0] + frame[1]) / 2.0;
+            let side = (frame[0] - frame[1]) / 2.0;
+            let mid = self.mid_low.process(mid) + self.mid_high.process(mid);
+            let side = self.side_high.process(side);
+            frame[0] = mid + side;
+            frame[1] = mid - side;
+        }
+    }
+
+    fn update_config(&mut self, config: &DspConfig) {
+        self.set_enabled(config.bass_mono);
+    }
+}
+
+/// Fourth order Linkwitz-Riley filter, two cascaded Butterworth sections.
+/// Its low- and high-pass outputs sum up to a flat magnitude response.
+struct LinkwitzRiley {
+    coefficients: BiquadCoefficients,
+    states: [BiquadState; 2],
+}
+
+impl LinkwitzRiley {
+    fn new(coefficients: BiquadCoefficients) -> Self {
+        Self {
+            coefficients,
+            states: [BiquadState::new(); 2],
+        }
+    }
+
+    fn reset(&mut self) {
+        self.states = [BiquadState::new(); 2];
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        let coefficients = &self.coefficients;
+        self.states
+            .iter_mut()
+            .fold(sample, |sample, state| state.process(sample, coefficients))
+    }
+}
@@ -11,8 +11,8 @@ use symphonia::{
         units::TimeStamp,
     },
     default::{
-        codecs::{MpaDecoder, VorbisDecoder},
-        formats::{MpaReader, OggReader},
+        codecs::{FlacDecoder, MpaDecoder, VorbisDecoder},
+        formats::{FlacReader, MpaReader, OggReader},
     },
 };
 
@@ -21,6 +21,7 @@ use crate::{error::Error, util::FileWithConstSize};
 pub enum AudioCodecFormat {
     Mp3,
     OggVorbis,
+    Flac,
 }
 
 impl AudioCodecFormat {
@@ -37,6 +38,10 @@ impl AudioCodecFormat {
                 mss,
                 &FormatOptions::default(),
             )?)),
+            Self::Flac => Ok(Box::new(FlacReader::try_new(
+                mss,
+                &FormatOptions::default(),
+            )?)),
         }
     }
 
@@ -50,6 +55,10 @@ impl AudioCodecFormat {
                 codec_params,
                 &DecoderOptions::default(),
             )?)),
+            Self::Flac => Ok(Box::new(FlacDecoder::try_new(
+                codec_params,
+                &DecoderOptions::default(),
+            )?)),
         }
     }
 }
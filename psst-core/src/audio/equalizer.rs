@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
+use super::processor::{AudioProcessor, DspConfig};
+
 /// Represents a single band in the equalizer with a center frequency and gain.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct EqualizerBand {
@@ -216,7 +218,7 @@ impl EqualizerPreset {
 
 /// Biquad filter coefficients for peaking EQ
 #[derive(Debug, Clone, Copy)]
-struct BiquadCoefficients {
+pub(super) struct BiquadCoefficients {
     b0: f32,
     b1: f32,
     b2: f32,
@@ -252,11 +254,75 @@ impl BiquadCoefficients {
             a2: a2 / a0,
         }
     }
+
+    /// Create low-pass filter coefficients
+    pub(super) fn low_pass(frequency: f32, q: f32, sample_rate: u32) -> Self {
+        let (cos_omega, alpha) = Self::omega(frequency, q, sample_rate);
+        let b1 = 1.0 - cos_omega;
+        Self::pass(b1 / 2.0, b1, cos_omega, alpha)
+    }
+
+    /// Create high-pass filter coefficients
+    pub(super) fn high_pass(frequency: f32, q: f32, sample_rate: u32) -> Self {
+        let (cos_omega, alpha) = Self::omega(frequency, q, sample_rate);
+        let b1 = -(1.0 + cos_omega);
+        Self::pass(-b1 / 2.0, b1, cos_omega, alpha)
+    }
+
+    /// High shelf of the K-weighting of ITU-R BS.1770, modelling the head.
+    pub(super) fn k_weighting_shelf(sample_rate: u32) -> Self {
+        const FREQUENCY: f32 = 1681.974_5;
+        const GAIN_DB: f32 = 3.999_844;
+        const Q: f32 = 0.707_175_2;
+        let k = (PI * FREQUENCY / sample_rate as f32).tan();
+        let vh = 10_f32.powf(GAIN_DB / 20.0);
+        let vb = vh.powf(0.499_666_8);
+        let a0 = 1.0 + k / Q + k * k;
+        Self {
+            b0: (vh + vb * k / Q + k * k) / a0,
+            b1: 2.0 * (k * k - vh) / a0,
+            b2: (vh - vb * k / Q + k * k) / a0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / Q + k * k) / a0,
+        }
+    }
+
+    /// High-pass of the K-weighting of ITU-R BS.1770, without gain at 1 kHz.
+    pub(super) fn k_weighting_high_pass(sample_rate: u32) -> Self {
+        const FREQUENCY: f32 = 38.135_47;
+        const Q: f32 = 0.500_327;
+        let k = (PI * FREQUENCY / sample_rate as f32).tan();
+        let a0 = 1.0 + k / Q + k * k;
+        Self {
+            b0: 1.0,
+            b1: -2.0,
+            b2: 1.0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / Q + k * k) / a0,
+        }
+    }
+
+    fn omega(frequency: f32, q: f32, sample_rate: u32) -> (f32, f32) {
+        let omega = 2.0 * PI * frequency / sample_rate as f32;
+        (omega.cos(), omega.sin() / (2.0 * q))
+    }
+
+    /// Low- and high-pass filters share the poles and `b0 == b2`.
+    fn pass(b0: f32, b1: f32, cos_omega: f32, alpha: f32) -> Self {
+        let a0 = 1.0 + alpha;
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b0 / a0,
+            a1: -2.0 * cos_omega / a0,
+            a2: (1.0 - alpha) / a0,
+        }
+    }
 }
 
 /// Biquad filter state for a single channel
 #[derive(Debug, Clone, Copy)]
-struct BiquadState {
+pub(super) struct BiquadState {
     x1: f32,
     x2: f32,
     y1: f32,
@@ -264,7 +330,7 @@ struct BiquadState {
 }
 
 impl BiquadState {
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             x1: 0.0,
             x2: 0.0,
@@ -273,7 +339,7 @@ impl BiquadState {
         }
     }
 
-    fn process(&mut self, input: f32, coeff: &BiquadCoefficients) -> f32 {
+    pub(super) fn process(&mut self, input: f32, coeff: &BiquadCoefficients) -> f32 {
         let output = coeff.b0 * input + coeff.b1 * self.x1 + coeff.b2 * self.x2
             - coeff.a1 * self.y1
             - coeff.a2 * self.y2;
@@ -377,6 +443,18 @@ impl Equalizer {
     }
 }
 
+impl AudioProcessor for Equalizer {
+    fn process(&mut self, samples: &mut [f32]) {
+        Equalizer::process(self, samples);
+    }
+
+    fn update_config(&mut self, config: &DspConfig) {
+        if self.config != config.equalizer {
+            Equalizer::update_config(self, config.equalizer.clone());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
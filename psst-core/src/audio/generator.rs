@@ -0,0 +1,284 @@
+use std::{f32::consts::PI, thread};
+
+use crossbeam_channel::Receiver;
+
+use super::{source::AudioSource, speech};
+
+/// Level of the generated signals, -12 dBFS, loud enough to hear but leaving
+/// headroom for equalizer boosts.
+const LEVEL: f32 = 0.25;
+/// Time each channel gets during channel identification.
+const CHANNEL_SLOT: f32 = 2.0;
+const BEEP_FREQUENCY: f32 = 880.0;
+const BEEP_LENGTH: f32 = 0.12;
+
+/// Built-in signals for testing the output without playing anything from
+/// the network.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestSignal {
+    Sine {
+        frequency: f32,
+    },
+    WhiteNoise,
+    PinkNoise,
+    /// Names one channel at a time, see `SignalGenerator::with_spoken_channels`.
+    /// Without spoken names it beeps once on the first channel, twice on the
+    /// second, and so on.
+    ChannelIdentification,
+}
+
+/// An endless source playing a `TestSignal`.
+pub struct SignalGenerator {
+    signal: TestSignal,
+    sample_rate: u32,
+    channels: usize,
+    /// Frames written so far.
+    frame: u64,
+    /// State of the noise generator.
+    seed: u32,
+    /// Filter state of the pink noise.
+    pink: [f32; 7],
+    /// Spoken channel names being rendered, see `with_spoken_channels`.
+    pending_names: Option<Receiver<Vec<Option<Vec<f32>>>>>,
+    /// Spoken name of each channel at the output rate, if it could be rendered.
+    names: Vec<Option<Vec<f32>>>,
+}
+
+impl SignalGenerator {
+    pub fn new(signal: TestSignal, sample_rate: u32, channels: usize) -> Self {
+        Self {
+            signal,
+            sample_rate,
+            channels: channels.max(1),
+            frame: 0,
+            seed: 0x2545_f491,
+            pink: [0.0; 7],
+            pending_names: None,
+            names: Vec::new(),
+        }
+    }
+
+    /// Speak the name of each channel during channel identification, once the
+    /// speech synthesizer of the system has rendered them.  Beeps until then,
+    /// and for channels whose name could not be rendered.
+    pub fn with_spoken_channels(mut self) -> Self {
+        if self.signal != TestSignal::ChannelIdentification {
+            return self;
+        }
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        let (channels, sample_rate) = (self.channels, self.sample_rate);
+        let spawned = thread::Builder::new()
+            .name("channel_names".to_string())
+            .spawn(move || {
+                let names = speech::channel_names(channels)
+                    .iter()
+                    .map(|name| match speech::speak(name) {
+                        Ok(speech) => {
+                            // Bring the voice to the level of the other signals.
+                            let mut samples = speech.resampled(sample_rate);
+                            let peak = samples.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
+                            if peak > 0.0 {
+                                samples.iter_mut().for_each(|s| *s *= LEVEL / peak);
+                            }
+                            Some(samples)
+                        }
+                        Err(err) => {
+                            log::warn!("failed to speak {name:?}, beeping instead: {err}");
+                            None
+                        }
+                    })
+                    .collect();
+                let _ = sender.send(names);
+            });
+        match spawned {
+            Ok(_) => self.pending_names = Some(receiver),
+            Err(err) => log::error!("failed to spawn the channel names thread: {err}"),
+        }
+        self
+    }
+
+    fn white(&mut self) -> f32 {
+        // Numerical Recipes LCG, plenty for audible noise.
+        self.seed = self
+            .seed
+            .wrapping_mul(1_664_525)
+            .wrapping_add(1_013_904_223);
+        (self.seed >> 8) as f32 / (1 << 23) as f32 - 1.0
+    }
+
+    /// Paul Kellet's filter, -3 dB per octave within 0.05 dB above 9 Hz.
+    fn pink(&mut self) -> f32 {
+        let white = self.white();
+        let p = &mut self.pink;
+        p[0] = 0.99886 * p[0] + white * 0.055_517_9;
+        p[1] = 0.99332 * p[1] + white * 0.075_075_9;
+        p[2] = 0.96900 * p[2] + white * 0.153_852;
+        p[3] = 0.86650 * p[3] + white * 0.310_485_6;
+        p[4] = 0.55000 * p[4] + white * 0.532_952_2;
+        p[5] = -0.7616 * p[5] - white * 0.016_898;
+        let pink = p[0] + p[1] + p[2] + p[3] + p[4] + p[5] + p[6] + white * 0.5362;
+        p[6] = white * 0.115_926;
+        // The filter has a gain of about 5 to 6.
+        pink * 0.18
+    }
+
+    /// Channel that is beeping at `time`, if any.
+    fn beeping_channel(&self, time: f32) -> Option<usize> {
+        let slot = (time / CHANNEL_SLOT) as usize;
+        let channel = slot % self.channels;
+        let in_slot = time - slot as f32 * CHANNEL_SLOT;
+        let beep = (in_slot / (2.0 * BEEP_LENGTH)) as usize;
+        let in_beep = in_slot - beep as f32 * 2.0 * BEEP_LENGTH;
+        (beep <= channel && in_beep < BEEP_LENGTH).then_some(channel)
+    }
+}
+
+impl AudioSource for SignalGenerator {
+    fn write(&mut self, output: &mut [f32]) -> usize {
+        if let Some(Ok(names)) = self.pending_names.as_ref().map(Receiver::try_recv) {
+            self.pending_names = None;
+            self.names = names;
+        }
+        let frames = output.len() / self.channels;
+        // Channel identification repeats after all channels had their slot.
+        let period = (CHANNEL_SLOT * self.sample_rate as f32) as u64 * self.channels as u64;
+        for frame in output.chunks_exact_mut(self.channels) {
+            match self.signal {
+                TestSignal::Sine { frequency } => {
+                    // Wrap the phase so it stays precise over long runs.
+                    let cycles = (self.frame as f64 * frequency as f64 / self.sample_rate as f64)
+                        .fract() as f32;
+                    frame.fill((2.0 * PI * cycles).sin() * LEVEL);
+                }
+                TestSignal::WhiteNoise => {
+                    let sample = self.white() * LEVEL;
+                    frame.fill(sample);
+                }
+                TestSignal::PinkNoise => {
+                    let sample = self.pink() * LEVEL;
+                    frame.fill(sample);
+                }
+                TestSignal::ChannelIdentification => {
+                    let time = (self.frame % period) as f32 / self.sample_rate as f32;
+                    frame.fill(0.0);
+                    let slot = (time / CHANNEL_SLOT) as usize;
+                    let channel = slot % self.channels;
+                    if let Some(Some(name)) = self.names.get(channel) {
+                        let index = ((time - slot as f32 * CHANNEL_SLOT) * self.sample_rate as f32)
+                            as usize;
+                        frame[channel] = name.get(index).copied().unwrap_or(0.0);
+                    } else if let Some(channel) = self.beeping_channel(time) {
+                        frame[channel] = (2.0 * PI * BEEP_FREQUENCY * time).sin() * LEVEL;
+                    }
+                }
+            }
+            self.frame += 1;
+        }
+        frames * self.channels
+    }
+
+    fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+/// Level of the chimes, -20 dBFS, quiet next to the music.
+const CHIME_LEVEL: f32 = 0.1;
+/// Time between the starts of the notes of a chime.
+const CHIME_NOTE_SPACING: f32 = 0.12;
+/// Length of each note, by then it has faded out.
+const CHIME_NOTE_LENGTH: f32 = 0.5;
+/// Time constant of the fade of the notes.
+const CHIME_NOTE_DECAY: f32 = 0.08;
+/// Fade in of the notes, so that they start without a click.
+const CHIME_NOTE_ATTACK: f32 = 0.005;
+
+/// Short sounds for notifications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chime {
+    /// A single high note.
+    Ping,
+    /// Two notes going up.
+    Rising,
+    /// Two notes going down.
+    Falling,
+}
+
+impl Chime {
+    /// Frequencies of the notes, played one after the other.
+    fn notes(self) -> &'static [f32] {
+        match self {
+            // E6.
+            Chime::Ping => &[1318.5],
+            // E5 and B5.
+            Chime::Rising => &[659.3, 987.8],
+            // C5 and G4.
+            Chime::Falling => &[523.3, 392.0],
+        }
+    }
+
+    /// Time from the start of the first note to the end of the last one.
+    pub fn duration(self) -> f32 {
+        (self.notes().len() - 1) as f32 * CHIME_NOTE_SPACING + CHIME_NOTE_LENGTH
+    }
+}
+
+/// A source playing a `Chime` once, on all channels.
+pub struct ChimeGenerator {
+    chime: Chime,
+    sample_rate: u32,
+    channels: usize,
+    /// Frames written so far.
+    frame: u64,
+    /// Frames of the whole chime.
+    total_frames: u64,
+}
+
+impl ChimeGenerator {
+    pub fn new(chime: Chime, sample_rate: u32, channels: usize) -> Self {
+        Self {
+            chime,
+            sample_rate,
+            channels: channels.max(1),
+            frame: 0,
+            total_frames: (chime.duration() * sample_rate as f32).ceil() as u64,
+        }
+    }
+
+    fn sample(&self, time: f32) -> f32 {
+        let mut sample = 0.0;
+        for (index, frequency) in self.chime.notes().iter().enumerate() {
+            let t = time - index as f32 * CHIME_NOTE_SPACING;
+            if (0.0..CHIME_NOTE_LENGTH).contains(&t) {
+                let envelope = (t / CHIME_NOTE_ATTACK).min(1.0) * (-t / CHIME_NOTE_DECAY).exp();
+                sample += (2.0 * PI * frequency * t).sin() * envelope;
+            }
+        }
+        sample * CHIME_LEVEL
+    }
+}
+
+impl AudioSource for ChimeGenerator {
+    fn write(&mut self, output: &mut [f32]) -> usize {
+        let remaining = (self.total_frames - self.frame) as usize;
+        let frames = (output.len() / self.channels).min(remaining);
+        for frame in output.chunks_exact_mut(self.channels).take(frames) {
+            let time = self.frame as f32 / self.sample_rate as f32;
+            frame.fill(self.sample(time));
+            self.frame += 1;
+        }
+        frames * self.channels
+    }
+
+    fn channel_count(&self) -> usize {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
@@ -0,0 +1,44 @@
+use super::processor::AudioProcessor;
+
+/// Highest level the limiter lets through, a little below full scale.
+const THRESHOLD: f32 = 0.98;
+/// Time the gain takes to mostly recover after a peak.
+const RELEASE_SECONDS: f32 = 0.1;
+
+/// Peak limiter that keeps boosted signals from clipping.  The gain drops
+/// instantly on a peak and recovers smoothly afterwards, applied to all
+/// channels of a frame alike so that the stereo image stays put.
+pub struct Limiter {
+    channels: usize,
+    release: f32,
+    gain: f32,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            channels: channels.max(1),
+            release: 1.0 - (-1.0 / (RELEASE_SECONDS * sample_rate as f32)).exp(),
+            gain: 1.0,
+        }
+    }
+}
+
+impl AudioProcessor for Limiter {
+    fn process(&mut self, samples: &mut [f32]) {
+        for frame in samples.chunks_exact_mut(self.channels) {
+            let peak = frame.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
+            let target = if peak > THRESHOLD {
+                THRESHOLD / peak
+            } else {
+                1.0
+            };
+            if target < self.gain {
+                self.gain = target;
+            } else {
+                self.gain += (target - self.gain) * self.release;
+            }
+            frame.iter_mut().for_each(|s| *s *= self.gain);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::equalizer::{BiquadCoefficients, BiquadState};
+
+/// Length of the gating blocks, in 100 ms steps.
+const BLOCK_STEPS: usize = 4;
+/// Blocks quieter than this are silence and ignored, in LUFS.
+const ABSOLUTE_GATE: f64 = -70.0;
+/// Blocks more than this below the ungated loudness are ignored, in LU.
+const RELATIVE_GATE: f64 = -10.0;
+
+/// Integrated loudness of ITU-R BS.1770, the measure ReplayGain 2.0 and EBU
+/// R128 are based on.  The signal is K-weighted and cut into overlapping
+/// 400 ms blocks, and the silent and much quieter blocks are gated out, so
+/// that quiet intros or pauses do not pull the loudness down.
+///
+/// All channels are weighted alike, the surround weights of the standard are
+/// left out.
+pub struct LoudnessMeter {
+    channels: usize,
+    shelf: BiquadCoefficients,
+    high_pass: BiquadCoefficients,
+    states: Vec<[BiquadState; 2]>,
+    step_frames: usize,
+    step_position: usize,
+    step_energy: f64,
+    /// Mean square of each 100 ms step, summed over the channels.
+    steps: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        Self {
+            channels,
+            shelf: BiquadCoefficients::k_weighting_shelf(sample_rate),
+            high_pass: BiquadCoefficients::k_weighting_high_pass(sample_rate),
+            states: vec![[BiquadState::new(); 2]; channels],
+            step_frames: (sample_rate as usize / 10).max(1),
+            step_position: 0,
+            step_energy: 0.0,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Measure interleaved `samples`.
+    pub fn process(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (sample, [shelf, high_pass]) in frame.iter().zip(&mut self.states) {
+                let weighted =
+                    high_pass.process(shelf.process(*sample, &self.shelf), &self.high_pass);
+                self.step_energy += f64::from(weighted) * f64::from(weighted);
+            }
+            self.step_position += 1;
+            if self.step_position == self.step_frames {
+                self.steps.push(self.step_energy / self.step_frames as f64);
+                self.step_position = 0;
+                self.step_energy = 0.0;
+            }
+        }
+    }
+
+    /// Gated loudness of everything measured so far, in LUFS.  `None` if it
+    /// was shorter than a block or silent.
+    pub fn integrated(&self) -> Option<f64> {
+        let blocks: Vec<f64> = self
+            .steps
+            .windows(BLOCK_STEPS)
+            .map(|steps| steps.iter().sum::<f64>() / BLOCK_STEPS as f64)
+            .filter(|&energy| loudness(energy) > ABSOLUTE_GATE)
+            .collect();
+        let ungated = mean(blocks.iter().copied())?;
+        let gate = loudness(ungated) + RELATIVE_GATE;
+        let gated = mean(blocks.iter().copied().filter(|&e| loudness(e) > gate))?;
+        Some(loudness(gated))
+    }
+}
+
+fn loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+fn mean(energies: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = energies.fold((0.0, 0), |(sum, count), e| (sum + e, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
@@ -1,8 +1,24 @@
+//! Decoding, processing and output of audio.  The processors, the resampler
+//! and the sources are built without the `native` feature too, the decoders
+//! and outputs need it.
+
+pub mod bass_mono;
+#[cfg(feature = "native")]
 pub mod decode;
+#[cfg(feature = "native")]
 pub mod decrypt;
 pub mod equalizer;
+pub mod generator;
+pub mod limiter;
+pub mod loudness;
 pub mod normalize;
+#[cfg(feature = "native")]
 pub mod output;
+#[cfg(feature = "native")]
 pub mod probe;
+pub mod processor;
 pub mod resample;
 pub mod source;
+pub mod spectrum;
+pub mod speech;
+pub mod stretch;
@@ -1,9 +1,32 @@
+#[cfg(feature = "native")]
+use std::{fs, path::Path};
 use std::{
     io,
     io::{Read, Seek, SeekFrom},
 };
 
 use byteorder::{ReadBytesExt, LE};
+#[cfg(feature = "native")]
+use lofty::{
+    config::WriteOptions,
+    file::TaggedFileExt,
+    tag::{ItemKey, Tag, TagExt},
+};
+#[cfg(feature = "native")]
+use symphonia::core::audio::SampleBuffer;
+
+use crate::audio::processor::AudioProcessor;
+#[cfg(feature = "native")]
+use crate::{
+    audio::{
+        decode::{AudioCodecFormat, AudioDecoder},
+        loudness::LoudnessMeter,
+    },
+    error::Error,
+};
+
+/// Loudness ReplayGain 2.0 players normalize to, in dB relative to full scale.
+const REPLAY_GAIN_REFERENCE_DB: f32 = -18.0;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum NormalizationLevel {
@@ -39,6 +62,87 @@ impl NormalizationData {
         })
     }
 
+    /// Data that leaves the volume untouched, for files without a Spotify
+    /// header.
+    pub fn neutral() -> Self {
+        Self {
+            track_gain_db: 0.0,
+            track_peak: 1.0,
+            album_gain_db: 0.0,
+            album_peak: 1.0,
+        }
+    }
+
+    /// Measure the ReplayGain 2.0 values of a fully decoded signal.  A single
+    /// file has no album context, so the album values mirror the track ones.
+    #[cfg(feature = "native")]
+    pub fn analyze(decoder: &mut AudioDecoder) -> Self {
+        const MAX_FRAMES: u64 = 8 * 1024;
+
+        let max_frames = decoder
+            .codec_params()
+            .max_frames_per_packet
+            .unwrap_or(MAX_FRAMES);
+        let signal_spec = decoder.signal_spec();
+        let mut buffer = SampleBuffer::<f32>::new(max_frames, signal_spec);
+        let mut meter = LoudnessMeter::new(signal_spec.rate, signal_spec.channels.count());
+        let mut peak = 0.0_f32;
+        while decoder.read_packet(&mut buffer).is_some() {
+            meter.process(buffer.samples());
+            peak = buffer
+                .samples()
+                .iter()
+                .fold(peak, |peak, sample| peak.max(sample.abs()));
+        }
+
+        let gain_db = meter
+            .integrated()
+            .map_or(0.0, |loudness| REPLAY_GAIN_REFERENCE_DB - loudness as f32);
+        Self {
+            track_gain_db: gain_db,
+            track_peak: peak,
+            album_gain_db: gain_db,
+            album_peak: peak,
+        }
+    }
+
+    /// Write the ReplayGain values into the tags of `audio_path`, the ID3v2
+    /// tag of MP3 files and the Vorbis comment of Ogg and FLAC files.
+    #[cfg(feature = "native")]
+    pub fn write_tags(&self, audio_path: &Path) -> Result<(), Error> {
+        let tag_error = |err: lofty::error::LoftyError| Error::IoError(io::Error::other(err));
+        let mut tagged = lofty::read_from_path(audio_path).map_err(tag_error)?;
+        if tagged.primary_tag().is_none() {
+            tagged.insert_tag(Tag::new(tagged.primary_tag_type()));
+        }
+        let tag = tagged
+            .primary_tag_mut()
+            .expect("primary tag inserted above");
+        let values = [
+            (
+                ItemKey::ReplayGainTrackGain,
+                format!("{:.2} dB", self.track_gain_db),
+            ),
+            (
+                ItemKey::ReplayGainTrackPeak,
+                format!("{:.6}", self.track_peak),
+            ),
+            (
+                ItemKey::ReplayGainAlbumGain,
+                format!("{:.2} dB", self.album_gain_db),
+            ),
+            (
+                ItemKey::ReplayGainAlbumPeak,
+                format!("{:.6}", self.album_peak),
+            ),
+        ];
+        for (key, value) in values {
+            tag.insert_text(key, value);
+        }
+        tag.save_to_path(audio_path, WriteOptions::default())
+            .map_err(tag_error)
+    }
+
     pub fn factor_for_level(&self, level: NormalizationLevel, pregain: f32) -> f32 {
         match level {
             NormalizationLevel::None => 1.0,
@@ -55,3 +159,48 @@ impl NormalizationData {
         nf
     }
 }
+
+/// Applies the normalization factor picked by `NormalizationData`.
+pub struct Normalizer {
+    factor: f32,
+}
+
+impl Normalizer {
+    pub fn new(factor: f32) -> Self {
+        Self { factor }
+    }
+}
+
+impl AudioProcessor for Normalizer {
+    fn process(&mut self, samples: &mut [f32]) {
+        samples.iter_mut().for_each(|s| *s *= self.factor);
+    }
+}
+
+/// Analyze a local MP3, Ogg Vorbis or FLAC file and write its loudness into
+/// its ReplayGain tags, so external players pick the same volume as we do.
+#[cfg(feature = "native")]
+pub fn export_replay_gain(audio_path: &Path) -> Result<(), Error> {
+    let codec = match audio_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("mp3") => AudioCodecFormat::Mp3,
+        Some("ogg") => AudioCodecFormat::OggVorbis,
+        Some("flac") => AudioCodecFormat::Flac,
+        _ => {
+            return Err(Error::IoError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported audio format",
+            )))
+        }
+    };
+    let file = fs::File::open(audio_path)?;
+    let mut decoder = AudioDecoder::new(file, codec)?;
+    let data = NormalizationData::analyze(&mut decoder);
+    data.write_tags(audio_path)?;
+    log::info!("wrote ReplayGain tags of {audio_path:?}");
+    Ok(())
+}
@@ -1,11 +1,22 @@
+use std::{
+    io,
+    ops::RangeInclusive,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use crossbeam_channel::{bounded, Receiver, Sender};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
 use num_traits::Pow;
+use parking_lot::{Mutex, RwLock};
 
 use crate::{
     actor::{Act, Actor, ActorHandle},
     audio::{
-        output::{AudioOutput, AudioSink},
+        output::{frames_to_latency, latency_to_frames, AudioOutput, AudioSink, OutputDevice},
         source::{AudioSource, Empty},
     },
     error::Error,
@@ -14,33 +25,73 @@ use crate::{
 pub struct CpalOutput {
     _handle: ActorHandle<StreamMsg>,
     sink: CpalSink,
+    device: Arc<DeviceState>,
+    device_changes: Receiver<Option<String>>,
 }
 
 impl CpalOutput {
     pub fn open() -> Result<Self, Error> {
-        // Open the default output device.
-        let device = cpal::default_host()
-            .default_output_device()
-            .ok_or(cpal::DefaultStreamConfigError::DeviceNotAvailable)?;
+        Self::open_with_latency(None)
+    }
+
+    /// Open the default output device, asking for an output buffer of `latency`
+    /// instead of the default one of the device.
+    pub fn open_with_latency(latency: Option<Duration>) -> Result<Self, Error> {
+        Self::open_device(None, latency)
+    }
 
-        if let Ok(name) = device.name() {
-            log::info!("using audio device: {name:?}");
+    /// Open the output device with `id`, or the default one if `None` or if the
+    /// device is not connected, asking for an output buffer of `latency`
+    /// instead of the default one of the device.
+    pub fn open_device(id: Option<&str>, latency: Option<Duration>) -> Result<Self, Error> {
+        let device = match id.and_then(find_device) {
+            Some(device) => device,
+            None => default_device()?,
+        };
+
+        let device_id = device_id(&device);
+        if let Some(id) = &device_id {
+            log::info!("using audio device: {id:?}");
         }
 
         // Get the default device config, so we know what sample format and sample rate
         // the device supports.
         let supported = Self::preferred_output_config(&device)?;
 
+        // Remember which other sample rates the device accepts in the same format, so we
+        // can later switch to the rate of the played source instead of resampling.
+        let supported_rates = supported_rates(&device, supported.channels())?;
+
         let (callback_send, callback_recv) = bounded(16);
+        let (change_send, change_recv) = unbounded();
+
+        let mut config = supported.config();
+        let buffer_frames = latency.map(|latency| Self::buffer_frames(&supported, latency));
+        if let Some(frames) = buffer_frames {
+            config.buffer_size = cpal::BufferSize::Fixed(frames);
+            log::info!(
+                "using output buffer of {frames} frames ({:?})",
+                frames_to_latency(frames, config.sample_rate.0)
+            );
+        }
 
+        let state = Arc::new(DeviceState {
+            id: Mutex::new(device_id),
+            supported_rates: RwLock::new(supported_rates),
+        });
         let handle = Stream::spawn_with_default_cap("audio_output", {
-            let config = supported.config();
+            let state = state.clone();
             // TODO: Support additional sample formats.
-            move |this| Stream::open(device, config, callback_recv, this).unwrap()
+            move |this| {
+                Stream::open(device, config, callback_recv, this, state, change_send).unwrap()
+            }
         });
         let sink = CpalSink {
             channel_count: supported.channels(),
-            sample_rate: supported.sample_rate(),
+            sample_rate: Arc::new(AtomicU32::new(supported.sample_rate().0)),
+            device: state.clone(),
+            buffer_frames,
+            volume: Arc::new(AtomicU32::new(1.0_f32.to_bits())),
             stream_send: handle.sender(),
             callback_send,
         };
@@ -48,9 +99,43 @@ impl CpalOutput {
         Ok(Self {
             _handle: handle,
             sink,
+            device: state,
+            device_changes: change_recv,
         })
     }
 
+    /// Output devices of the default host.
+    pub fn devices() -> Result<Vec<OutputDevice>, Error> {
+        Ok(cpal::default_host()
+            .output_devices()?
+            .filter_map(|device| {
+                Some(OutputDevice {
+                    id: device_id(&device)?,
+                    name: device.name().ok()?,
+                })
+            })
+            .collect())
+    }
+
+    /// Number of frames needed for `latency`, clamped to the buffer sizes the
+    /// device supports.
+    fn buffer_frames(supported: &cpal::SupportedStreamConfig, latency: Duration) -> u32 {
+        let requested = latency_to_frames(latency, supported.sample_rate().0);
+        match supported.buffer_size() {
+            cpal::SupportedBufferSize::Range { min, max } => {
+                let frames = requested.clamp(*min, *max);
+                if frames != requested {
+                    log::warn!(
+                        "output buffer of {requested} frames is outside of the device limits \
+                         ({min}..={max}), using {frames} frames"
+                    );
+                }
+                frames
+            }
+            cpal::SupportedBufferSize::Unknown => requested,
+        }
+    }
+
     fn preferred_output_config(
         device: &cpal::Device,
     ) -> Result<cpal::SupportedStreamConfig, Error> {
@@ -78,12 +163,80 @@ impl AudioOutput for CpalOutput {
     fn sink(&self) -> Self::Sink {
         self.sink.clone()
     }
+
+    fn device_id(&self) -> Option<String> {
+        self.device.id.lock().clone()
+    }
+
+    fn switch_device(&self, id: Option<&str>) -> Result<Option<String>, Error> {
+        let (reply_send, reply_recv) = bounded(1);
+        self.sink.stream_send.send(StreamMsg::SwitchDevice {
+            id: id.map(str::to_string),
+            reply: reply_send,
+        })?;
+        reply_recv.recv().map_err(|_| Error::SendError)?
+    }
+
+    fn device_changes(&self) -> Receiver<Option<String>> {
+        self.device_changes.clone()
+    }
+}
+
+/// Identifies `device` across restarts.  Cpal offers nothing better than the
+/// name, so the ID is the name, prefixed with the host.  Windows numbers the
+/// names of otherwise identical devices ("2- Speakers"), and the number changes
+/// with the order the devices were plugged in, so it is left out.
+fn device_id(device: &cpal::Device) -> Option<String> {
+    let name = device.name().ok()?;
+    let name = match name.split_once("- ") {
+        Some((number, rest))
+            if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            rest
+        }
+        _ => &name,
+    };
+    Some(format!("{}:{name}", cpal::default_host().id().name()))
+}
+
+fn find_device(id: &str) -> Option<cpal::Device> {
+    cpal::default_host()
+        .output_devices()
+        .ok()?
+        .find(|device| device_id(device).as_deref() == Some(id))
+}
+
+fn default_device() -> Result<cpal::Device, Error> {
+    Ok(cpal::default_host()
+        .default_output_device()
+        .ok_or(cpal::DefaultStreamConfigError::DeviceNotAvailable)?)
+}
+
+fn supported_rates(
+    device: &cpal::Device,
+    channels: cpal::ChannelCount,
+) -> Result<Vec<RangeInclusive<u32>>, Error> {
+    Ok(device
+        .supported_output_configs()?
+        .filter(|s| s.channels() == channels && s.sample_format() == cpal::SampleFormat::F32)
+        .map(|s| s.min_sample_rate().0..=s.max_sample_rate().0)
+        .collect())
+}
+
+/// The device the stream currently plays to, shared with the output and its
+/// sinks.
+struct DeviceState {
+    id: Mutex<Option<String>>,
+    supported_rates: RwLock<Vec<RangeInclusive<u32>>>,
 }
 
 #[derive(Clone)]
 pub struct CpalSink {
     channel_count: cpal::ChannelCount,
-    sample_rate: cpal::SampleRate,
+    sample_rate: Arc<AtomicU32>,
+    device: Arc<DeviceState>,
+    buffer_frames: Option<u32>,
+    volume: Arc<AtomicU32>,
     callback_send: Sender<CallbackMsg>,
     stream_send: Sender<StreamMsg>,
 }
@@ -108,10 +261,45 @@ impl AudioSink for CpalSink {
     }
 
     fn sample_rate(&self) -> u32 {
-        self.sample_rate.0
+        self.sample_rate.load(Ordering::Acquire)
+    }
+
+    fn negotiate_sample_rate(&self, rate: u32) -> u32 {
+        let current = self.sample_rate();
+        let supported = self
+            .device
+            .supported_rates
+            .read()
+            .iter()
+            .any(|r| r.contains(&rate));
+        if rate == current || !supported {
+            return current;
+        }
+        let (reply_send, reply_recv) = bounded(1);
+        self.send_to_stream(StreamMsg::Reopen {
+            sample_rate: rate,
+            reply: reply_send,
+        });
+        match reply_recv.recv() {
+            Ok(Ok(())) => {
+                self.sample_rate.store(rate, Ordering::Release);
+                rate
+            }
+            Ok(Err(err)) => {
+                log::warn!("failed to switch output to {rate} Hz, resampling instead: {err}");
+                current
+            }
+            Err(_) => current,
+        }
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        self.buffer_frames
+            .map(|frames| frames_to_latency(frames, self.sample_rate()))
     }
 
     fn set_volume(&self, volume: f32) {
+        self.volume.store(volume.to_bits(), Ordering::Release);
         self.send_to_callback(CallbackMsg::SetVolume(volume));
     }
 
@@ -141,7 +329,15 @@ impl AudioSink for CpalSink {
 
 struct Stream {
     stream: cpal::Stream,
-    _device: cpal::Device,
+    device: cpal::Device,
+    config: cpal::StreamConfig,
+    /// Shared by the streams built over time, so that the played source
+    /// survives switching the device.
+    callback: Arc<Mutex<StreamCallback>>,
+    stream_send: Sender<StreamMsg>,
+    state: Arc<DeviceState>,
+    change_send: Sender<Option<String>>,
+    playing: bool,
 }
 
 impl Stream {
@@ -150,31 +346,131 @@ impl Stream {
         config: cpal::StreamConfig,
         callback_recv: Receiver<CallbackMsg>,
         stream_send: Sender<StreamMsg>,
+        state: Arc<DeviceState>,
+        change_send: Sender<Option<String>>,
     ) -> Result<Self, Error> {
-        let mut callback = StreamCallback {
+        let callback = Arc::new(Mutex::new(StreamCallback {
             callback_recv,
-            stream_send,
+            stream_send: stream_send.clone(),
             source: Box::new(Empty),
             volume: 1.0, // We start with the full volume.
             state: CallbackState::Paused,
-        };
+        }));
+        let stream = Self::build(&device, &config, &callback, &stream_send)?;
+
+        Ok(Self {
+            stream,
+            device,
+            config,
+            callback,
+            stream_send,
+            state,
+            change_send,
+            playing: false,
+        })
+    }
+
+    fn build(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        callback: &Arc<Mutex<StreamCallback>>,
+        stream_send: &Sender<StreamMsg>,
+    ) -> Result<cpal::Stream, Error> {
+        let callback = callback.clone();
+        let stream_send = stream_send.clone();
 
         log::info!("opening output stream: {config:?}");
         let stream = device.build_output_stream(
-            &config,
-            move |output, _| {
-                callback.write_samples(output);
+            config,
+            move |output, _| match callback.try_lock() {
+                Some(mut callback) => callback.write_samples(output),
+                // The stream is being replaced, stay silent in the meantime.
+                None => output.iter_mut().for_each(|s| *s = 0.0),
             },
-            |err| {
+            move |err| {
                 log::error!("audio output error: {err}");
+                if let cpal::StreamError::DeviceNotAvailable = err {
+                    let _ = stream_send.send(StreamMsg::DeviceLost);
+                }
             },
             None,
         )?;
 
-        Ok(Self {
-            _device: device,
-            stream,
-        })
+        Ok(stream)
+    }
+
+    fn reopen(&mut self, sample_rate: u32) -> Result<(), Error> {
+        let config = cpal::StreamConfig {
+            sample_rate: cpal::SampleRate(sample_rate),
+            ..self.config.clone()
+        };
+        // Keep the current stream paused while the new one is being built, it gets
+        // dropped once replaced.
+        let _ = self.stream.pause();
+        match Self::build(&self.device, &config, &self.callback, &self.stream_send) {
+            Ok(stream) => {
+                // The current source runs at the old rate, the sink plays one at the
+                // new rate next.
+                let mut callback = self.callback.lock();
+                callback.source = Box::new(Empty);
+                callback.state = CallbackState::Paused;
+                drop(callback);
+                self.stream = stream;
+                self.config = config;
+                Ok(())
+            }
+            Err(err) => {
+                let _ = self.stream.play();
+                Err(err)
+            }
+        }
+    }
+
+    /// Move the stream to the device with `id`, or to the default device if
+    /// `None`, keeping the channels and the rate the played source is made for.
+    fn switch_device(&mut self, id: Option<&str>) -> Result<Option<String>, Error> {
+        let device = match id {
+            Some(id) => {
+                find_device(id).ok_or(cpal::DefaultStreamConfigError::DeviceNotAvailable)?
+            }
+            None => default_device()?,
+        };
+        let supported = device
+            .supported_output_configs()?
+            .find(|s| {
+                s.channels() == self.config.channels
+                    && s.sample_format() == cpal::SampleFormat::F32
+                    && (s.min_sample_rate()..=s.max_sample_rate())
+                        .contains(&self.config.sample_rate)
+            })
+            .ok_or_else(|| {
+                Error::AudioOutputError(Box::new(io::Error::other(format!(
+                    "device does not play {} channels at {} Hz",
+                    self.config.channels, self.config.sample_rate.0
+                ))))
+            })?;
+        let config = cpal::StreamConfig {
+            buffer_size: self.config.buffer_size.clone(),
+            ..supported.with_sample_rate(self.config.sample_rate).config()
+        };
+        let supported_rates = supported_rates(&device, config.channels)?;
+
+        let stream = Self::build(&device, &config, &self.callback, &self.stream_send)?;
+        if self.playing {
+            stream.play()?;
+        } else {
+            stream.pause()?;
+        }
+        let _ = self.stream.pause();
+        self.stream = stream;
+        self.config = config;
+
+        let device_id = device_id(&device);
+        log::info!("switched audio device to {device_id:?}");
+        self.device = device;
+        *self.state.id.lock() = device_id.clone();
+        *self.state.supported_rates.write() = supported_rates;
+        Ok(device_id)
     }
 }
 
@@ -186,6 +482,7 @@ impl Actor for Stream {
         match msg {
             StreamMsg::Pause => {
                 log::debug!("pausing audio output stream");
+                self.playing = false;
                 if let Err(err) = self.stream.pause() {
                     log::error!("failed to stop stream: {err}");
                 }
@@ -193,11 +490,37 @@ impl Actor for Stream {
             }
             StreamMsg::Resume => {
                 log::debug!("resuming audio output stream");
+                self.playing = true;
                 if let Err(err) = self.stream.play() {
                     log::error!("failed to start stream: {err}");
                 }
                 Ok(Act::Continue)
             }
+            StreamMsg::Reopen { sample_rate, reply } => {
+                log::debug!("reopening audio output stream at {sample_rate} Hz");
+                let _ = reply.send(self.reopen(sample_rate));
+                Ok(Act::Continue)
+            }
+            StreamMsg::SwitchDevice { id, reply } => {
+                let _ = reply.send(self.switch_device(id.as_deref()));
+                Ok(Act::Continue)
+            }
+            StreamMsg::DeviceLost => {
+                // Some hosts report short dropouts the same way, only move on if the
+                // device is gone for good.
+                let current = self.state.id.lock().clone();
+                if current.as_deref().and_then(find_device).is_none() {
+                    match self.switch_device(None) {
+                        Ok(id) => {
+                            let _ = self.change_send.send(id);
+                        }
+                        Err(err) => {
+                            log::error!("failed to switch to the default audio device: {err}");
+                        }
+                    }
+                }
+                Ok(Act::Continue)
+            }
             StreamMsg::Close => {
                 log::debug!("closing audio output stream");
                 let _ = self.stream.pause();
@@ -210,6 +533,15 @@ impl Actor for Stream {
 enum StreamMsg {
     Pause,
     Resume,
+    Reopen {
+        sample_rate: u32,
+        reply: Sender<Result<(), Error>>,
+    },
+    SwitchDevice {
+        id: Option<String>,
+        reply: Sender<Result<Option<String>, Error>>,
+    },
+    DeviceLost,
     Close,
 }
 
@@ -281,6 +613,12 @@ impl From<cpal::DefaultStreamConfigError> for Error {
     }
 }
 
+impl From<cpal::DevicesError> for Error {
+    fn from(err: cpal::DevicesError) -> Error {
+        Error::AudioOutputError(Box::new(err))
+    }
+}
+
 impl From<cpal::SupportedStreamConfigsError> for Error {
     fn from(err: cpal::SupportedStreamConfigsError) -> Error {
         Error::AudioOutputError(Box::new(err))
@@ -1,11 +1,20 @@
-use std::{env, ffi::CString};
+use std::{
+    env,
+    ffi::CString,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-use crossbeam_channel::{bounded, Receiver, Sender};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+use parking_lot::Mutex;
 
 use crate::{
     actor::{Act, Actor, ActorHandle},
     audio::{
-        output::{AudioOutput, AudioSink},
+        output::{frames_to_latency, latency_to_frames, AudioOutput, AudioSink, OutputDevice},
         source::{AudioSource, Empty},
     },
     error::Error,
@@ -15,21 +24,73 @@ pub struct CubebOutput {
     #[allow(unused)]
     handle: ActorHandle<StreamMsg>,
     sink: CubebSink,
+    device_id: Arc<Mutex<Option<String>>>,
+    device_changes: Receiver<Option<String>>,
 }
 
 impl CubebOutput {
     pub fn open() -> Result<Self, Error> {
+        Self::open_with_latency(None)
+    }
+
+    /// Open the default output device, asking for an output buffer of `latency`
+    /// instead of the default one.
+    pub fn open_with_latency(latency: Option<Duration>) -> Result<Self, Error> {
+        Self::open_device(None, latency)
+    }
+
+    /// Open the output device with `id`, or follow the default one if `None` or
+    /// if the device is not connected, asking for an output buffer of `latency`
+    /// instead of the default one.
+    pub fn open_device(id: Option<&str>, latency: Option<Duration>) -> Result<Self, Error> {
         let (callback_send, callback_recv) = bounded(16);
+        let (change_send, change_recv) = unbounded();
+
+        let requested_latency = latency
+            .map(|latency| latency_to_frames(latency, SAMPLE_RATE))
+            .unwrap_or(STREAM_LATENCY);
+        let latency_frames = Arc::new(AtomicU32::new(requested_latency));
+        let device_id = Arc::new(Mutex::new(None));
 
         let handle = Stream::spawn_with_default_cap("audio_output", {
-            move |_| Stream::open(callback_recv).unwrap()
+            let latency_frames = latency_frames.clone();
+            let device = DeviceState {
+                chosen: id.map(str::to_string),
+                id: device_id.clone(),
+                change_send,
+            };
+            move |this| Stream::open(callback_recv, latency_frames, device, this).unwrap()
         });
         let sink = CubebSink {
             callback_send,
             stream_send: handle.sender(),
+            sample_rate: Arc::new(AtomicU32::new(SAMPLE_RATE)),
+            latency_frames,
+            volume: Arc::new(AtomicU32::new(1.0_f32.to_bits())),
         };
 
-        Ok(Self { handle, sink })
+        Ok(Self {
+            handle,
+            sink,
+            device_id,
+            device_changes: change_recv,
+        })
+    }
+
+    /// Output devices of the backend.
+    pub fn devices() -> Result<Vec<OutputDevice>, Error> {
+        let ctx = context()?;
+        let devices = ctx.enumerate_devices(cubeb::DeviceType::OUTPUT)?;
+        Ok(devices
+            .iter()
+            .filter_map(|info| {
+                let id = info.device_id()?;
+                Some(OutputDevice {
+                    id: id.to_string(),
+                    name: info.friendly_name().unwrap_or(id).to_string(),
+                })
+            })
+            .collect())
     }
 }
 
@@ -39,6 +100,40 @@ impl AudioOutput for CubebOutput {
     fn sink(&self) -> Self::Sink {
         self.sink.clone()
     }
+
+    fn device_id(&self) -> Option<String> {
+        self.device_id.lock().clone()
+    }
+
+    fn switch_device(&self, id: Option<&str>) -> Result<Option<String>, Error> {
+        let (reply_send, reply_recv) = bounded(1);
+        self.sink.stream_send.send(StreamMsg::SwitchDevice {
+            id: id.map(str::to_string),
+            reply: reply_send,
+        })?;
+        reply_recv.recv().map_err(|_| Error::SendError)?
+    }
+
+    fn device_changes(&self) -> Receiver<Option<String>> {
+        self.device_changes.clone()
+    }
+}
+
+fn context() -> Result<cubeb::Context, Error> {
+    // Call CoInitialize() before any other calls to the API.
+    #[cfg(target_os = "windows")]
+    unsafe {
+        let _ = windows::Win32::System::Com::CoInitialize(0 as *mut _);
+    };
+
+    let backend_name = env::var("CUBEB_BACKEND")
+        .ok()
+        .and_then(|s| CString::new(s).ok());
+    let ctx_name = CString::new("Psst").ok();
+    Ok(cubeb::Context::init(
+        ctx_name.as_deref(),
+        backend_name.as_deref(),
+    )?)
 }
 
 type Frame = cubeb::StereoFrame<f32>;
@@ -47,55 +142,251 @@ const STREAM_CHANNELS: usize = 2;
 const SAMPLE_RATE: u32 = 44_100;
 const STREAM_LATENCY: u32 = 0x1000;
 
+/// Which device the stream plays to.  The backend reports its devices by a
+/// stable string ID, that is what gets stored.
+struct DeviceState {
+    /// Device chosen by the user, `None` follows the default device.
+    chosen: Option<String>,
+    /// Device played to, shared with the output.
+    id: Arc<Mutex<Option<String>>>,
+    change_send: Sender<Option<String>>,
+}
+
 struct Stream {
-    #[allow(unused)]
     ctx: cubeb::Context,
     stream: cubeb::Stream<Frame>,
+    /// Shared by the streams built over time, so that the played source
+    /// survives switching the device.
+    callback: Arc<Mutex<StreamCallback>>,
+    stream_send: Sender<StreamMsg>,
+    device: DeviceState,
+    sample_rate: u32,
+    volume: f32,
+    playing: bool,
+    /// Requested buffer size in frames, the achieved one is stored in
+    /// `latency_frames`.
+    requested_latency: u32,
+    latency_frames: Arc<AtomicU32>,
 }
 
 impl Stream {
-    fn open(callback_recv: Receiver<CallbackMsg>) -> Result<Self, Error> {
-        // Call CoInitialize() before any other calls to the API.
-        #[cfg(target_os = "windows")]
-        unsafe {
-            let _ = windows::Win32::System::Com::CoInitialize(0 as *mut _);
-        };
-
-        let backend_name = env::var("CUBEB_BACKEND")
-            .ok()
-            .and_then(|s| CString::new(s).ok());
-        let ctx_name = CString::new("Psst").ok();
-        let ctx = cubeb::Context::init(ctx_name.as_deref(), backend_name.as_deref())?;
-
-        let mut callback = StreamCallback {
+    fn open(
+        callback_recv: Receiver<CallbackMsg>,
+        latency_frames: Arc<AtomicU32>,
+        device: DeviceState,
+        stream_send: Sender<StreamMsg>,
+    ) -> Result<Self, Error> {
+        let ctx = context()?;
+        let callback = Arc::new(Mutex::new(StreamCallback {
             callback_recv,
             source: Box::new(Empty),
             state: CallbackState::Paused,
             buffer: vec![0.0; 1024 * 1024],
-        };
+        }));
+        let requested_latency = latency_frames.load(Ordering::Acquire);
+        let (stream, id) = Self::build(
+            &ctx,
+            &callback,
+            &stream_send,
+            device.chosen.as_deref(),
+            SAMPLE_RATE,
+            requested_latency,
+            &latency_frames,
+        )?;
+        log::info!("using audio device: {id:?}");
+        *device.id.lock() = id;
+
+        Ok(Self {
+            ctx,
+            stream,
+            callback,
+            stream_send,
+            device,
+            sample_rate: SAMPLE_RATE,
+            volume: 1.0,
+            playing: false,
+            requested_latency,
+            latency_frames,
+        })
+    }
 
+    /// Build a stream playing to the device with `device_id`, or to the default
+    /// device if `None` or if the device is not connected.  Returns the stream
+    /// and the ID of the device it plays to.
+    fn build(
+        ctx: &cubeb::Context,
+        callback: &Arc<Mutex<StreamCallback>>,
+        stream_send: &Sender<StreamMsg>,
+        device_id: Option<&str>,
+        sample_rate: u32,
+        requested_latency: u32,
+        latency_frames: &AtomicU32,
+    ) -> Result<(cubeb::Stream<Frame>, Option<String>), Error> {
         let params = cubeb::StreamParamsBuilder::new()
             .format(cubeb::SampleFormat::Float32NE)
-            .rate(SAMPLE_RATE)
+            .rate(sample_rate)
             .channels(STREAM_CHANNELS as u32)
             .layout(cubeb::ChannelLayout::STEREO)
             .take();
 
+        // Do not go below the minimal latency the backend supports.
+        let latency = match ctx.min_latency(&params) {
+            Ok(min_latency) if min_latency > requested_latency => {
+                log::warn!(
+                    "output buffer of {requested_latency} frames is below the device limit, \
+                     using {min_latency} frames"
+                );
+                min_latency
+            }
+            _ => requested_latency,
+        };
+        latency_frames.store(latency, Ordering::Release);
+        log::info!(
+            "using output buffer of {latency} frames ({:?})",
+            frames_to_latency(latency, sample_rate)
+        );
+
+        // The device handles point into the collection, keep it around until the
+        // stream is built.
+        let devices = ctx.enumerate_devices(cubeb::DeviceType::OUTPUT)?;
+        let chosen = device_id.and_then(|id| devices.iter().find(|i| i.device_id() == Some(id)));
+        let default = devices
+            .iter()
+            .find(|i| i.preferred().contains(cubeb::DevicePref::MULTIMEDIA));
+
         let mut builder = cubeb::StreamBuilder::new();
+        builder.name("Psst");
+        match chosen {
+            Some(info) => builder.output(info.devid(), &params),
+            None => builder.default_output(&params),
+        };
         builder
-            .name("Psst")
-            .default_output(&params)
-            .latency(STREAM_LATENCY)
-            .data_callback(move |_, output| {
-                callback.write_samples(output);
-                output.len() as isize
+            .latency(latency)
+            .data_callback({
+                let callback = callback.clone();
+                move |_, output| {
+                    match callback.try_lock() {
+                        Some(mut callback) => callback.write_samples(output),
+                        // The stream is being replaced, stay silent in the meantime.
+                        None => output.iter_mut().for_each(|s| {
+                            s.l = 0.0;
+                            s.r = 0.0;
+                        }),
+                    }
+                    output.len() as isize
+                }
             })
-            .state_callback(|state| {
-                log::debug!("stream state: {:?}", state);
+            .state_callback({
+                let stream_send = stream_send.clone();
+                move |state| {
+                    log::debug!("stream state: {:?}", state);
+                    if let cubeb::State::Error = state {
+                        let _ = stream_send.send(StreamMsg::DeviceChanged);
+                    }
+                }
+            })
+            .device_changed_cb({
+                let stream_send = stream_send.clone();
+                move || {
+                    let _ = stream_send.send(StreamMsg::DeviceChanged);
+                }
             });
-        let stream = builder.init(&ctx)?;
+        let stream = builder.init(ctx)?;
+        let id = chosen
+            .or(default)
+            .and_then(|info| info.device_id())
+            .map(str::to_string);
+
+        Ok((stream, id))
+    }
 
-        Ok(Self { ctx, stream })
+    fn rebuild(&mut self, sample_rate: u32) -> Result<(), Error> {
+        let _ = self.stream.stop();
+        match Self::build(
+            &self.ctx,
+            &self.callback,
+            &self.stream_send,
+            self.device.chosen.as_deref(),
+            sample_rate,
+            self.requested_latency,
+            &self.latency_frames,
+        ) {
+            Ok((stream, id)) => {
+                self.stream = stream;
+                self.sample_rate = sample_rate;
+                if let Err(err) = self.stream.set_volume(self.volume) {
+                    log::error!("failed to set volume: {}", err);
+                }
+                self.set_device_id(id);
+                Ok(())
+            }
+            Err(err) => {
+                if self.playing {
+                    let _ = self.stream.start();
+                }
+                Err(err)
+            }
+        }
+    }
+
+    fn reopen(&mut self, sample_rate: u32, volume: f32) -> Result<(), Error> {
+        self.volume = volume;
+        self.rebuild(sample_rate)?;
+        // The current source runs at the old rate, the sink plays one at the new
+        // rate next.
+        let mut callback = self.callback.lock();
+        callback.source = Box::new(Empty);
+        callback.state = CallbackState::Paused;
+        Ok(())
+    }
+
+    fn switch_device(&mut self, id: Option<String>) -> Result<Option<String>, Error> {
+        let previous = std::mem::replace(&mut self.device.chosen, id);
+        if let Err(err) = self.rebuild(self.sample_rate) {
+            self.device.chosen = previous;
+            return Err(err);
+        }
+        if self.playing {
+            self.stream.start()?;
+        }
+        Ok(self.device.id.lock().clone())
+    }
+
+    fn device_changed(&mut self) -> Result<(), Error> {
+        let devices = self.ctx.enumerate_devices(cubeb::DeviceType::OUTPUT)?;
+        let chosen_lost = self
+            .device
+            .chosen
+            .as_deref()
+            .is_some_and(|id| devices.iter().all(|info| info.device_id() != Some(id)));
+        if chosen_lost {
+            // Play to the default device until the chosen one comes back, the choice
+            // itself is kept.
+            drop(devices);
+            self.rebuild(self.sample_rate)?;
+            if self.playing {
+                self.stream.start()?;
+            }
+        } else if self.device.chosen.is_none() {
+            // The backend follows the default device on its own, just tell which one
+            // it is now.
+            let id = devices
+                .iter()
+                .find(|i| i.preferred().contains(cubeb::DevicePref::MULTIMEDIA))
+                .and_then(|info| info.device_id())
+                .map(str::to_string);
+            self.set_device_id(id);
+        }
+        Ok(())
+    }
+
+    fn set_device_id(&self, id: Option<String>) {
+        let mut current = self.device.id.lock();
+        if *current != id {
+            log::info!("using audio device: {id:?}");
+            *current = id.clone();
+            let _ = self.device.change_send.send(id);
+        }
     }
 }
 
@@ -104,6 +395,16 @@ enum StreamMsg {
     Resume,
     Close,
     SetVolume(f32),
+    Reopen {
+        sample_rate: u32,
+        volume: f32,
+        reply: Sender<Result<(), Error>>,
+    },
+    SwitchDevice {
+        id: Option<String>,
+        reply: Sender<Result<Option<String>, Error>>,
+    },
+    DeviceChanged,
 }
 
 impl Actor for Stream {
@@ -114,6 +415,7 @@ impl Actor for Stream {
         match msg {
             StreamMsg::Pause => {
                 log::debug!("pausing audio output stream");
+                self.playing = false;
                 if let Err(err) = self.stream.stop() {
                     log::error!("failed to stop stream: {}", err);
                 }
@@ -121,6 +423,7 @@ impl Actor for Stream {
             }
             StreamMsg::Resume => {
                 log::debug!("resuming audio output stream");
+                self.playing = true;
                 if let Err(err) = self.stream.start() {
                     log::error!("failed to start stream: {}", err);
                 }
@@ -133,11 +436,32 @@ impl Actor for Stream {
             }
             StreamMsg::SetVolume(volume) => {
                 log::debug!("setting volume");
+                self.volume = volume;
                 if let Err(err) = self.stream.set_volume(volume) {
                     log::error!("failed to set volume: {}", err);
                 }
                 Ok(Act::Continue)
             }
+            StreamMsg::Reopen {
+                sample_rate,
+                volume,
+                reply,
+            } => {
+                log::debug!("reopening audio output stream at {} Hz", sample_rate);
+                let _ = reply.send(self.reopen(sample_rate, volume));
+                Ok(Act::Continue)
+            }
+            StreamMsg::SwitchDevice { id, reply } => {
+                log::debug!("switching audio output stream to {:?}", id);
+                let _ = reply.send(self.switch_device(id));
+                Ok(Act::Continue)
+            }
+            StreamMsg::DeviceChanged => {
+                if let Err(err) = self.device_changed() {
+                    log::error!("failed to follow the audio device: {}", err);
+                }
+                Ok(Act::Continue)
+            }
         }
     }
 }
@@ -146,6 +470,9 @@ impl Actor for Stream {
 pub struct CubebSink {
     callback_send: Sender<CallbackMsg>,
     stream_send: Sender<StreamMsg>,
+    sample_rate: Arc<AtomicU32>,
+    latency_frames: Arc<AtomicU32>,
+    volume: Arc<AtomicU32>,
 }
 
 impl AudioSink for CubebSink {
@@ -154,10 +481,50 @@ impl AudioSink for CubebSink {
     }
 
     fn sample_rate(&self) -> u32 {
-        SAMPLE_RATE
+        self.sample_rate.load(Ordering::Acquire)
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        Some(frames_to_latency(
+            self.latency_frames.load(Ordering::Acquire),
+            self.sample_rate(),
+        ))
+    }
+
+    fn negotiate_sample_rate(&self, rate: u32) -> u32 {
+        // Cubeb converts to the device rate on its own if needed, so we can always ask
+        // for the rate of the source.
+        let current = self.sample_rate();
+        if rate == current {
+            return current;
+        }
+        let (reply_send, reply_recv) = bounded(1);
+        self.stream_send
+            .send(StreamMsg::Reopen {
+                sample_rate: rate,
+                volume: f32::from_bits(self.volume.load(Ordering::Acquire)),
+                reply: reply_send,
+            })
+            .unwrap();
+        match reply_recv.recv() {
+            Ok(Ok(())) => {
+                self.sample_rate.store(rate, Ordering::Release);
+                rate
+            }
+            Ok(Err(err)) => {
+                log::warn!(
+                    "failed to switch output to {} Hz, resampling instead: {}",
+                    rate,
+                    err
+                );
+                current
+            }
+            Err(_) => current,
+        }
     }
 
     fn set_volume(&self, volume: f32) {
+        self.volume.store(volume.to_bits(), Ordering::Release);
         self.stream_send.send(StreamMsg::SetVolume(volume)).unwrap();
     }
 
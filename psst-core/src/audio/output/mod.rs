@@ -1,9 +1,14 @@
-use crate::audio::source::AudioSource;
+use std::time::Duration;
+
+use crossbeam_channel::Receiver;
+
+use crate::{audio::source::AudioSource, error::Error};
 
 #[cfg(feature = "cpal")]
 pub mod cpal;
 #[cfg(feature = "cubeb")]
 pub mod cubeb;
+pub mod session;
 
 #[cfg(not(any(feature = "cubeb", feature = "cpal")))]
 compile_error!("enable either the `cpal` or `cubeb` feature to build audio output support");
@@ -15,15 +20,41 @@ pub type DefaultAudioOutput = cpal::CpalOutput;
 
 pub type DefaultAudioSink = <DefaultAudioOutput as AudioOutput>::Sink;
 
+/// An output device the user can choose from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputDevice {
+    /// Stable across restarts, see `AudioOutput::device_id`.
+    pub id: String,
+    pub name: String,
+}
+
 pub trait AudioOutput {
     type Sink: AudioSink;
 
     fn sink(&self) -> Self::Sink;
+
+    /// Identifies the device the output plays to, stable across restarts.
+    /// `None` if the backend cannot tell which device it is.
+    fn device_id(&self) -> Option<String>;
+
+    /// Move the output to the device with `id`, or to the default device of
+    /// the system if `None`.  The played source and the volume carry over.
+    /// Returns the ID of the device played to afterwards.
+    fn switch_device(&self, id: Option<&str>) -> Result<Option<String>, Error>;
+
+    /// IDs of the devices the output moved to on its own, because the played
+    /// device went away or the default device of the system changed.
+    fn device_changes(&self) -> Receiver<Option<String>>;
 }
 
 pub trait AudioSink {
     fn channel_count(&self) -> usize;
     fn sample_rate(&self) -> u32;
+    /// Ask the output to switch to `rate`, so a source running at that rate can be
+    /// played without resampling.  Returns the rate the sink runs at afterwards.
+    fn negotiate_sample_rate(&self, rate: u32) -> u32;
+    /// Latency of the output buffer, if known.
+    fn latency(&self) -> Option<Duration>;
     fn set_volume(&self, volume: f32);
     fn play(&self, source: impl AudioSource);
     fn pause(&self);
@@ -31,3 +62,13 @@ pub trait AudioSink {
     fn stop(&self);
     fn close(&self);
 }
+
+/// Convert a buffer `latency` into a number of frames at `sample_rate`.
+pub fn latency_to_frames(latency: Duration, sample_rate: u32) -> u32 {
+    (latency.as_secs_f64() * sample_rate as f64).round() as u32
+}
+
+/// Convert a buffer size in frames at `sample_rate` into its latency.
+pub fn frames_to_latency(frames: u32, sample_rate: u32) -> Duration {
+    Duration::from_secs_f64(frames as f64 / sample_rate as f64)
+}
@@ -0,0 +1,437 @@
+//! Watches the audio system of the platform for other applications that are
+//! playing, so playback can step aside for calls and videos.
+
+use std::collections::HashSet;
+
+use crossbeam_channel::Sender;
+
+/// Listens to the audio system for other applications starting and stopping
+/// their output.  The listeners are removed when dropped.
+pub struct SessionWatch {
+    platform: platform::Watch,
+}
+
+impl SessionWatch {
+    /// Start listening, `changed` is sent to whenever another application
+    /// might have started or stopped playing.  `None` if the platform does not
+    /// tell.
+    pub fn new(changed: Sender<()>) -> Option<Self> {
+        platform::Watch::new(changed).map(|platform| Self { platform })
+    }
+
+    /// Process IDs of the other applications that are playing audio now.
+    /// Processes that appeared since the last call are listened to as well.
+    pub fn playing_processes(&mut self) -> HashSet<u32> {
+        self.platform.playing_processes()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::collections::HashSet;
+
+    use crossbeam_channel::Sender;
+    use windows::{
+        core::{implement, Interface, Ref, Result, BOOL, GUID, PCWSTR},
+        Win32::{
+            Foundation::S_OK,
+            Media::Audio::{
+                eRender, AudioSessionDisconnectReason, AudioSessionState, AudioSessionStateActive,
+                IAudioSessionControl, IAudioSessionControl2, IAudioSessionEvents,
+                IAudioSessionEvents_Impl, IAudioSessionManager2, IAudioSessionNotification,
+                IAudioSessionNotification_Impl, IMMDeviceEnumerator, ISimpleAudioVolume,
+                MMDeviceEnumerator, DEVICE_STATE_ACTIVE,
+            },
+            System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED},
+        },
+    };
+
+    /// Wakes the monitor when a session is created.
+    #[implement(IAudioSessionNotification)]
+    struct SessionCreated(Sender<()>);
+
+    impl IAudioSessionNotification_Impl for SessionCreated_Impl {
+        fn OnSessionCreated(&self, _session: Ref<'_, IAudioSessionControl>) -> Result<()> {
+            let _ = self.0.send(());
+            Ok(())
+        }
+    }
+
+    /// Wakes the monitor when a session starts, stops, or is muted.
+    #[implement(IAudioSessionEvents)]
+    struct SessionChanged(Sender<()>);
+
+    impl SessionChanged_Impl {
+        fn changed(&self) -> Result<()> {
+            let _ = self.0.send(());
+            Ok(())
+        }
+    }
+
+    impl IAudioSessionEvents_Impl for SessionChanged_Impl {
+        fn OnDisplayNameChanged(&self, _name: &PCWSTR, _context: *const GUID) -> Result<()> {
+            Ok(())
+        }
+
+        fn OnIconPathChanged(&self, _path: &PCWSTR, _context: *const GUID) -> Result<()> {
+            Ok(())
+        }
+
+        fn OnSimpleVolumeChanged(
+            &self,
+            _volume: f32,
+            _mute: BOOL,
+            _context: *const GUID,
+        ) -> Result<()> {
+            self.changed()
+        }
+
+        fn OnChannelVolumeChanged(
+            &self,
+            _count: u32,
+            _volumes: *const f32,
+            _channel: u32,
+            _context: *const GUID,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn OnGroupingParamChanged(&self, _param: *const GUID, _context: *const GUID) -> Result<()> {
+            Ok(())
+        }
+
+        fn OnStateChanged(&self, _state: AudioSessionState) -> Result<()> {
+            self.changed()
+        }
+
+        fn OnSessionDisconnected(&self, _reason: AudioSessionDisconnectReason) -> Result<()> {
+            self.changed()
+        }
+    }
+
+    /// Listens to the WASAPI sessions of the output devices that were active
+    /// when the watch started.  Calls show up as sessions of the
+    /// communication apps.
+    pub struct Watch {
+        changed: Sender<()>,
+        managers: Vec<(IAudioSessionManager2, IAudioSessionNotification)>,
+        sessions: Vec<(IAudioSessionControl2, IAudioSessionEvents)>,
+    }
+
+    impl Watch {
+        pub fn new(changed: Sender<()>) -> Option<Self> {
+            let mut managers = Vec::new();
+            unsafe {
+                // Fails harmlessly if COM is already initialized on this thread.
+                let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+                let enumerator: IMMDeviceEnumerator =
+                    CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).ok()?;
+                let devices = enumerator
+                    .EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)
+                    .ok()?;
+                for index in 0..devices.GetCount().ok()? {
+                    let device = devices.Item(index).ok()?;
+                    let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None).ok()?;
+                    let notification: IAudioSessionNotification =
+                        SessionCreated(changed.clone()).into();
+                    manager.RegisterSessionNotification(&notification).ok()?;
+                    managers.push((manager, notification));
+                }
+            }
+            Some(Self {
+                changed,
+                managers,
+                sessions: Vec::new(),
+            })
+        }
+
+        pub fn playing_processes(&mut self) -> HashSet<u32> {
+            let own_pid = std::process::id();
+            let mut playing = HashSet::new();
+            unsafe {
+                // Listen to the sessions as they are now, new ones included.
+                for (control, events) in self.sessions.drain(..) {
+                    let _ = control.UnregisterAudioSessionNotification(&events);
+                }
+                for (manager, _) in &self.managers {
+                    let Ok(sessions) = manager.GetSessionEnumerator() else {
+                        continue;
+                    };
+                    for index in 0..sessions.GetCount().unwrap_or(0) {
+                        let Ok(control) = sessions.GetSession(index) else {
+                            continue;
+                        };
+                        let Ok(control) = control.cast::<IAudioSessionControl2>() else {
+                            continue;
+                        };
+                        let events: IAudioSessionEvents =
+                            SessionChanged(self.changed.clone()).into();
+                        if control.RegisterAudioSessionNotification(&events).is_ok() {
+                            self.sessions.push((control.clone(), events));
+                        }
+                        match control.GetProcessId() {
+                            Ok(pid) if pid != own_pid && is_audible(&control) => {
+                                playing.insert(pid);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            playing
+        }
+    }
+
+    /// Sessions count while they are active, and not muted or turned all the
+    /// way down in the volume mixer.  System sounds never count.
+    unsafe fn is_audible(control: &IAudioSessionControl2) -> bool {
+        if control.GetState().ok() != Some(AudioSessionStateActive)
+            || control.IsSystemSoundsSession() == S_OK
+        {
+            return false;
+        }
+        match control.cast::<ISimpleAudioVolume>() {
+            Ok(volume) => {
+                !volume.GetMute().is_ok_and(|mute| mute.as_bool())
+                    && volume.GetMasterVolume().is_ok_and(|level| level > 0.0)
+            }
+            Err(_) => true,
+        }
+    }
+
+    impl Drop for Watch {
+        fn drop(&mut self) {
+            unsafe {
+                for (control, events) in &self.sessions {
+                    let _ = control.UnregisterAudioSessionNotification(events);
+                }
+                for (manager, notification) in &self.managers {
+                    let _ = manager.UnregisterSessionNotification(notification);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{collections::HashSet, ffi::c_void, mem, ptr};
+
+    use crossbeam_channel::Sender;
+
+    type AudioObjectId = u32;
+    type OsStatus = i32;
+    type AudioObjectPropertyListenerProc = extern "C" fn(
+        object: AudioObjectId,
+        address_count: u32,
+        addresses: *const AudioObjectPropertyAddress,
+        client_data: *mut c_void,
+    ) -> OsStatus;
+
+    #[repr(C)]
+    struct AudioObjectPropertyAddress {
+        selector: u32,
+        scope: u32,
+        element: u32,
+    }
+
+    #[link(name = "CoreAudio", kind = "framework")]
+    extern "C" {
+        fn AudioObjectGetPropertyDataSize(
+            object: AudioObjectId,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            data_size: *mut u32,
+        ) -> OsStatus;
+        fn AudioObjectGetPropertyData(
+            object: AudioObjectId,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_size: u32,
+            qualifier: *const c_void,
+            data_size: *mut u32,
+            data: *mut c_void,
+        ) -> OsStatus;
+        fn AudioObjectAddPropertyListener(
+            object: AudioObjectId,
+            address: *const AudioObjectPropertyAddress,
+            listener: AudioObjectPropertyListenerProc,
+            client_data: *mut c_void,
+        ) -> OsStatus;
+        fn AudioObjectRemovePropertyListener(
+            object: AudioObjectId,
+            address: *const AudioObjectPropertyAddress,
+            listener: AudioObjectPropertyListenerProc,
+            client_data: *mut c_void,
+        ) -> OsStatus;
+    }
+
+    const fn four_cc(code: &[u8; 4]) -> u32 {
+        u32::from_be_bytes(*code)
+    }
+
+    const SYSTEM_OBJECT: AudioObjectId = 1;
+    const SCOPE_GLOBAL: u32 = four_cc(b"glob");
+    const ELEMENT_MAIN: u32 = 0;
+    const PROCESS_OBJECT_LIST: u32 = four_cc(b"prs#");
+    const PROCESS_PID: u32 = four_cc(b"ppid");
+    const PROCESS_IS_RUNNING_OUTPUT: u32 = four_cc(b"piro");
+
+    fn address(selector: u32) -> AudioObjectPropertyAddress {
+        AudioObjectPropertyAddress {
+            selector,
+            scope: SCOPE_GLOBAL,
+            element: ELEMENT_MAIN,
+        }
+    }
+
+    fn property<T: Copy + Default>(object: AudioObjectId, selector: u32) -> Option<T> {
+        let mut value = T::default();
+        let mut size = mem::size_of::<T>() as u32;
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                object,
+                &address(selector),
+                0,
+                ptr::null(),
+                &mut size,
+                &mut value as *mut T as *mut c_void,
+            )
+        };
+        (status == 0).then_some(value)
+    }
+
+    fn object_list(object: AudioObjectId, selector: u32) -> Option<Vec<AudioObjectId>> {
+        let mut size = 0;
+        let status = unsafe {
+            AudioObjectGetPropertyDataSize(object, &address(selector), 0, ptr::null(), &mut size)
+        };
+        if status != 0 {
+            return None;
+        }
+        let mut list = vec![0; size as usize / mem::size_of::<AudioObjectId>()];
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                object,
+                &address(selector),
+                0,
+                ptr::null(),
+                &mut size,
+                list.as_mut_ptr() as *mut c_void,
+            )
+        };
+        (status == 0).then_some(list)
+    }
+
+    extern "C" fn listener(
+        _object: AudioObjectId,
+        _address_count: u32,
+        _addresses: *const AudioObjectPropertyAddress,
+        client_data: *mut c_void,
+    ) -> OsStatus {
+        let changed = unsafe { &*(client_data as *const Sender<()>) };
+        let _ = changed.send(());
+        0
+    }
+
+    /// Listens to the audio process objects of Core Audio, available since
+    /// macOS 14, and to whether each of them is running output.
+    pub struct Watch {
+        /// Boxed, so that the listeners can point to it.
+        changed: Box<Sender<()>>,
+        /// Objects and properties our listener is added to.
+        listening: Vec<(AudioObjectId, u32)>,
+    }
+
+    impl Watch {
+        pub fn new(changed: Sender<()>) -> Option<Self> {
+            let mut watch = Self {
+                changed: Box::new(changed),
+                listening: Vec::new(),
+            };
+            // Fails before macOS 14, which has no process objects.
+            watch
+                .listen(SYSTEM_OBJECT, PROCESS_OBJECT_LIST)
+                .then_some(watch)
+        }
+
+        fn client_data(&self) -> *mut c_void {
+            &*self.changed as *const Sender<()> as *mut c_void
+        }
+
+        fn listen(&mut self, object: AudioObjectId, selector: u32) -> bool {
+            let status = unsafe {
+                AudioObjectAddPropertyListener(
+                    object,
+                    &address(selector),
+                    listener,
+                    self.client_data(),
+                )
+            };
+            if status == 0 {
+                self.listening.push((object, selector));
+            }
+            status == 0
+        }
+
+        pub fn playing_processes(&mut self) -> HashSet<u32> {
+            let own_pid = std::process::id() as i32;
+            let Some(processes) = object_list(SYSTEM_OBJECT, PROCESS_OBJECT_LIST) else {
+                return HashSet::new();
+            };
+            // Objects of processes that are gone have taken their listeners
+            // with them.
+            self.listening
+                .retain(|(object, _)| *object == SYSTEM_OBJECT || processes.contains(object));
+            for &process in &processes {
+                if !self.listening.iter().any(|(object, _)| *object == process) {
+                    self.listen(process, PROCESS_IS_RUNNING_OUTPUT);
+                }
+            }
+            processes
+                .into_iter()
+                .filter(|&process| {
+                    property::<u32>(process, PROCESS_IS_RUNNING_OUTPUT).is_some_and(|r| r != 0)
+                })
+                .filter_map(|process| property::<i32>(process, PROCESS_PID))
+                .filter(|&pid| pid != own_pid)
+                .map(|pid| pid as u32)
+                .collect()
+        }
+    }
+
+    impl Drop for Watch {
+        fn drop(&mut self) {
+            for &(object, selector) in &self.listening {
+                unsafe {
+                    AudioObjectRemovePropertyListener(
+                        object,
+                        &address(selector),
+                        listener,
+                        self.client_data(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::collections::HashSet;
+
+    use crossbeam_channel::Sender;
+
+    pub struct Watch;
+
+    impl Watch {
+        pub fn new(_changed: Sender<()>) -> Option<Self> {
+            None
+        }
+
+        pub fn playing_processes(&mut self) -> HashSet<u32> {
+            HashSet::new()
+        }
+    }
+}
@@ -5,15 +5,47 @@ use std::time::Duration;
 use symphonia::core::codecs::CodecType;
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag};
 use symphonia::core::probe::{Hint, Probe};
-use symphonia::default::formats::{MpaReader, OggReader};
+use symphonia::default::formats::{FlacReader, MpaReader, OggReader};
 
 use crate::error::Error;
 
 pub struct TrackProbe {
     pub codec: CodecType,
     pub duration: Option<Duration>,
+    pub tags: TrackTags,
+}
+
+/// Descriptive tags of a file, as far as they are present.
+#[derive(Clone, Debug, Default)]
+pub struct TrackTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track_number: Option<u32>,
+}
+
+impl TrackTags {
+    fn read(&mut self, tags: &[Tag]) {
+        for tag in tags {
+            let value = tag.value.to_string();
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => self.title = Some(value.to_string()),
+                Some(StandardTagKey::Artist) => self.artist = Some(value.to_string()),
+                Some(StandardTagKey::Album) => self.album = Some(value.to_string()),
+                Some(StandardTagKey::TrackNumber) => {
+                    // Often stored as "3/12".
+                    self.track_number = value.split('/').next().and_then(|n| n.parse().ok());
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
 macro_rules! probe_err {
@@ -35,6 +67,7 @@ impl TrackProbe {
         let mut probe = Probe::default();
         probe.register_all::<MpaReader>();
         probe.register_all::<OggReader>();
+        probe.register_all::<FlacReader>();
 
         let mut hint = Hint::new();
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -47,7 +80,7 @@ impl TrackProbe {
 
         let fmt_opts = FormatOptions::default();
         let meta_opts = MetadataOptions::default();
-        let probe_result = probe
+        let mut probe_result = probe
             .format(&hint, mss, &fmt_opts, &meta_opts)
             .map_err(|_| probe_err!("failed to probe file"))?;
         let track = probe_result
@@ -66,9 +99,23 @@ impl TrackProbe {
                 None
             };
 
+        let codec = params.codec;
+
+        // Tags can be in front of the stream (ID3v2) or inside the container.
+        let mut tags = TrackTags::default();
+        if let Some(metadata) = probe_result.metadata.get() {
+            if let Some(revision) = metadata.current() {
+                tags.read(revision.tags());
+            }
+        }
+        if let Some(revision) = probe_result.format.metadata().current() {
+            tags.read(revision.tags());
+        }
+
         Ok(Self {
-            codec: params.codec,
+            codec,
             duration,
+            tags,
         })
     }
 }
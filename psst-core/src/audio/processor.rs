@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bass_mono::BassMono,
+    equalizer::{Equalizer, EqualizerConfig},
+    limiter::Limiter,
+    normalize::Normalizer,
+};
+
+/// A stage of the DSP chain.  Processes interleaved samples in place.
+///
+/// Adding a stage takes an implementation of this trait, a `DspStage`
+/// variant and a field in `DspChain`.  Settings that change while playing go
+/// into `DspConfig`, each processor picks its own part of it.
+pub trait AudioProcessor: Send {
+    fn process(&mut self, samples: &mut [f32]);
+
+    /// Apply changed settings to a playing stream.
+    fn update_config(&mut self, _config: &DspConfig) {}
+
+    /// Frames the processor delays its output by, for example to look ahead.
+    fn latency(&self) -> usize {
+        0
+    }
+}
+
+/// Settings of the DSP chain that can change while playing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DspConfig {
+    pub order: Vec<DspStage>,
+    pub equalizer: EqualizerConfig,
+    /// Sum the low frequencies of both channels, see `BassMono`.
+    pub bass_mono: bool,
+}
+
+impl Default for DspConfig {
+    fn default() -> Self {
+        Self {
+            order: DspStage::default_order(),
+            equalizer: EqualizerConfig::default(),
+            bass_mono: false,
+        }
+    }
+}
+
+/// Stages of the DSP chain whose order can be configured.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum DspStage {
+    Normalization,
+    BassMono,
+    Equalizer,
+    Limiter,
+}
+
+impl DspStage {
+    pub const ALL: [DspStage; 4] = [
+        Self::Normalization,
+        Self::BassMono,
+        Self::Equalizer,
+        Self::Limiter,
+    ];
+
+    pub fn default_order() -> Vec<DspStage> {
+        Self::ALL.to_vec()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normalization => "Normalization",
+            Self::BassMono => "Bass Mono",
+            Self::Equalizer => "Equalizer",
+            Self::Limiter => "Limiter",
+        }
+    }
+
+    /// `order` without duplicates, with the missing stages appended in their
+    /// default order.
+    pub fn complete_order(order: &[DspStage]) -> Vec<DspStage> {
+        let mut complete = Vec::with_capacity(Self::ALL.len());
+        for &stage in order.iter().chain(&Self::ALL) {
+            if !complete.contains(&stage) {
+                complete.push(stage);
+            }
+        }
+        complete
+    }
+}
+
+/// The processors applied to a decoded stream, run in a configurable order.
+pub struct DspChain {
+    order: Vec<DspStage>,
+    normalizer: Normalizer,
+    bass_mono: BassMono,
+    equalizer: Equalizer,
+    limiter: Limiter,
+}
+
+impl DspChain {
+    pub fn new(config: &DspConfig, norm_factor: f32, sample_rate: u32, channels: usize) -> Self {
+        let mut bass_mono = BassMono::new(sample_rate, channels);
+        bass_mono.update_config(config);
+        Self {
+            order: DspStage::complete_order(&config.order),
+            normalizer: Normalizer::new(norm_factor),
+            bass_mono,
+            equalizer: Equalizer::new(config.equalizer.clone(), sample_rate),
+            limiter: Limiter::new(sample_rate, channels),
+        }
+    }
+
+    pub fn order(&self) -> &[DspStage] {
+        &self.order
+    }
+
+    fn stage(&mut self, stage: DspStage) -> &mut dyn AudioProcessor {
+        match stage {
+            DspStage::Normalization => &mut self.normalizer,
+            DspStage::BassMono => &mut self.bass_mono,
+            DspStage::Equalizer => &mut self.equalizer,
+            DspStage::Limiter => &mut self.limiter,
+        }
+    }
+}
+
+impl AudioProcessor for DspChain {
+    fn process(&mut self, samples: &mut [f32]) {
+        for i in 0..self.order.len() {
+            let stage = self.order[i];
+            self.stage(stage).process(samples);
+        }
+    }
+
+    fn update_config(&mut self, config: &DspConfig) {
+        self.order = DspStage::complete_order(&config.order);
+        for stage in DspStage::ALL {
+            self.stage(stage).update_config(config);
+        }
+    }
+
+    fn latency(&self) -> usize {
+        [
+            self.normalizer.latency(),
+            self.bass_mono.latency(),
+            self.equalizer.latency(),
+            self.limiter.latency(),
+        ]
+        .iter()
+        .sum()
+    }
+}
@@ -1,12 +1,38 @@
+use serde::{Deserialize, Serialize};
+
 use crate::error::Error;
 
-#[derive(Copy, Clone)]
+/// Without the `native` feature, there is no libsamplerate, and every
+/// quality resamples with `LinearResampler`.
+#[cfg(not(feature = "native"))]
+pub use LinearResampler as AudioResampler;
+
+/// The names in the aliases are the ones the GUI used to save.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ResamplingQuality {
-    SincBestQuality = libsamplerate::SRC_SINC_BEST_QUALITY as isize,
-    SincMediumQuality = libsamplerate::SRC_SINC_MEDIUM_QUALITY as isize,
-    SincFastest = libsamplerate::SRC_SINC_FASTEST as isize,
-    ZeroOrderHold = libsamplerate::SRC_ZERO_ORDER_HOLD as isize,
-    Linear = libsamplerate::SRC_LINEAR as isize,
+    #[serde(alias = "High")]
+    SincBestQuality,
+    #[default]
+    #[serde(alias = "Medium")]
+    SincMediumQuality,
+    #[serde(alias = "Fast")]
+    SincFastest,
+    ZeroOrderHold,
+    Linear,
+}
+
+#[cfg(feature = "native")]
+impl ResamplingQuality {
+    fn converter_type(self) -> i32 {
+        let converter = match self {
+            Self::SincBestQuality => libsamplerate::SRC_SINC_BEST_QUALITY,
+            Self::SincMediumQuality => libsamplerate::SRC_SINC_MEDIUM_QUALITY,
+            Self::SincFastest => libsamplerate::SRC_SINC_FASTEST,
+            Self::ZeroOrderHold => libsamplerate::SRC_ZERO_ORDER_HOLD,
+            Self::Linear => libsamplerate::SRC_LINEAR,
+        };
+        converter as i32
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -30,17 +56,19 @@ impl ResamplingSpec {
     }
 }
 
+#[cfg(feature = "native")]
 pub struct AudioResampler {
     pub spec: ResamplingSpec,
     state: *mut libsamplerate::SRC_STATE,
 }
 
+#[cfg(feature = "native")]
 impl AudioResampler {
     pub fn new(quality: ResamplingQuality, spec: ResamplingSpec) -> Result<Self, Error> {
         let mut error_int = 0i32;
         let state = unsafe {
             libsamplerate::src_new(
-                quality as i32,
+                quality.converter_type(),
                 spec.channels as i32,
                 &mut error_int as *mut i32,
             )
@@ -80,10 +108,76 @@ impl AudioResampler {
     }
 }
 
+#[cfg(feature = "native")]
 impl Drop for AudioResampler {
     fn drop(&mut self) {
         unsafe { libsamplerate::src_delete(self.state) };
     }
 }
 
+#[cfg(feature = "native")]
 unsafe impl Send for AudioResampler {}
+
+/// Resamples by interpolating linearly between neighbouring frames.  Worse
+/// than the sinc converters of libsamplerate, but in plain Rust, so it builds
+/// everywhere, WebAssembly included.
+pub struct LinearResampler {
+    pub spec: ResamplingSpec,
+    /// Last consumed frame, interpolated from at the start of the next input.
+    previous: Vec<f32>,
+    /// Position of the next output frame in the input frames, with -1 being
+    /// `previous`.
+    position: f64,
+}
+
+impl LinearResampler {
+    /// Has the same signature as `AudioResampler::new`, the quality is
+    /// ignored.
+    pub fn new(_quality: ResamplingQuality, spec: ResamplingSpec) -> Result<Self, Error> {
+        Ok(Self {
+            previous: vec![0.0; spec.channels],
+            position: 0.0,
+            spec,
+        })
+    }
+
+    /// Returns the count of consumed input samples and of written output
+    /// samples, like `AudioResampler::process`.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> Result<(usize, usize), Error> {
+        let channels = self.spec.channels;
+        if self.spec.input_rate == self.spec.output_rate {
+            let len = input.len().min(output.len());
+            output[..len].copy_from_slice(&input[..len]);
+            return Ok((len, len));
+        }
+        let step = self.spec.input_rate as f64 / self.spec.output_rate as f64;
+        let input_frames = input.len() / channels;
+        let frame = |index: isize| match index {
+            -1 => &self.previous[..],
+            index => &input[index as usize * channels..(index as usize + 1) * channels],
+        };
+
+        let mut written = 0;
+        for out in output.chunks_exact_mut(channels) {
+            let index = self.position.floor() as isize;
+            if index + 1 >= input_frames as isize {
+                break;
+            }
+            let fraction = (self.position - index as f64) as f32;
+            let (a, b) = (frame(index), frame(index + 1));
+            for (channel, sample) in out.iter_mut().enumerate() {
+                *sample = a[channel] + (b[channel] - a[channel]) * fraction;
+            }
+            self.position += step;
+            written += channels;
+        }
+
+        // Drop the frames before the next output frame, keeping the last one.
+        let consumed = (self.position.floor() as isize + 1).clamp(0, input_frames as isize);
+        if consumed > 0 {
+            self.previous = frame(consumed - 1).to_vec();
+            self.position -= consumed as f64;
+        }
+        Ok((consumed as usize * channels, written))
+    }
+}
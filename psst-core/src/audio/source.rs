@@ -1,6 +1,12 @@
+use crossbeam_channel::Receiver;
+
 use crate::audio::resample::ResamplingSpec;
 
-use super::resample::{AudioResampler, ResamplingQuality};
+use super::{
+    processor::{AudioProcessor, DspChain, DspConfig},
+    resample::{AudioResampler, ResamplingQuality},
+    stretch::{SilenceTrimmer, Tempo, TimeStretch},
+};
 
 /// Types that can produce audio samples in `f32` format. `Send`able across
 /// threads.
@@ -163,6 +169,199 @@ where
     }
 }
 
+/// Runs the DSP chain over a source that does not come from a decoder, like
+/// a test signal.  Decoded items are processed in `DecoderSource` instead.
+pub struct ProcessedSource<S> {
+    source: S,
+    dsp: DspChain,
+    dsp_updates: Receiver<DspConfig>,
+}
+
+impl<S> ProcessedSource<S>
+where
+    S: AudioSource,
+{
+    pub fn new(source: S, config: &DspConfig, dsp_updates: Receiver<DspConfig>) -> Self {
+        Self {
+            dsp: DspChain::new(config, 1.0, source.sample_rate(), source.channel_count()),
+            source,
+            dsp_updates,
+        }
+    }
+}
+
+impl<S> AudioSource for ProcessedSource<S>
+where
+    S: AudioSource,
+{
+    fn write(&mut self, output: &mut [f32]) -> usize {
+        while let Ok(config) = self.dsp_updates.try_recv() {
+            self.dsp.update_config(&config);
+        }
+        let written = self.source.write(output);
+        self.dsp.process(&mut output[..written]);
+        written
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+}
+
+/// Changes to a playing `TempoSource`.
+pub enum TempoUpdate {
+    Set(Tempo),
+    /// The source was seeked, the audio buffered from before is dropped.
+    Seek,
+}
+
+/// Plays the source at a different tempo and optionally trims its pauses.
+/// With `Tempo::NORMAL` the source passes through untouched.
+pub struct TempoSource<S> {
+    source: S,
+    tempo: Tempo,
+    tempo_updates: Receiver<TempoUpdate>,
+    stretch: TimeStretch,
+    trimmer: SilenceTrimmer,
+    buffer: Vec<f32>,
+}
+
+impl<S> TempoSource<S>
+where
+    S: AudioSource,
+{
+    pub fn new(source: S, tempo: Tempo, tempo_updates: Receiver<TempoUpdate>) -> Self {
+        const BUFFER_SIZE: usize = 4 * 1024;
+
+        let channels = source.channel_count();
+        let sample_rate = source.sample_rate();
+        Self {
+            stretch: TimeStretch::new(channels, sample_rate, tempo.speed),
+            trimmer: SilenceTrimmer::new(channels, sample_rate),
+            buffer: vec![0.0; BUFFER_SIZE - BUFFER_SIZE % channels.max(1)],
+            source,
+            tempo,
+            tempo_updates,
+        }
+    }
+
+    fn set_tempo(&mut self, tempo: Tempo) {
+        if tempo.is_normal() != self.tempo.is_normal() {
+            self.stretch.reset();
+        }
+        self.stretch.set_speed(tempo.speed);
+        self.tempo = tempo;
+    }
+}
+
+impl<S> AudioSource for TempoSource<S>
+where
+    S: AudioSource,
+{
+    fn write(&mut self, output: &mut [f32]) -> usize {
+        while let Ok(update) = self.tempo_updates.try_recv() {
+            match update {
+                TempoUpdate::Set(tempo) => self.set_tempo(tempo),
+                TempoUpdate::Seek => self.stretch.reset(),
+            }
+        }
+        if self.tempo.is_normal() {
+            return self.source.write(output);
+        }
+
+        let mut total = 0;
+        while total < output.len() {
+            total += self.stretch.read(&mut output[total..]);
+            if total == output.len() {
+                break;
+            }
+            let n = self.source.write(&mut self.buffer);
+            if n == 0 {
+                // Nothing is decoded yet, or the source is over.
+                break;
+            }
+            let n = if self.tempo.skip_silence {
+                self.trimmer.trim(&mut self.buffer[..n])
+            } else {
+                n
+            };
+            self.stretch.push(&self.buffer[..n]);
+        }
+        total
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+}
+
+/// Plays one source after the other, letting the previous one play out on top
+/// of the next.  Sources sent through `incoming` must already match the rate
+/// and channel count of the first one.
+pub struct CrossfadeSource {
+    current: Box<dyn AudioSource>,
+    outgoing: Option<Box<dyn AudioSource>>,
+    incoming: Receiver<Box<dyn AudioSource>>,
+    buffer: Vec<f32>,
+}
+
+impl CrossfadeSource {
+    pub fn new(source: Box<dyn AudioSource>, incoming: Receiver<Box<dyn AudioSource>>) -> Self {
+        Self {
+            current: source,
+            outgoing: None,
+            incoming,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl AudioSource for CrossfadeSource {
+    fn write(&mut self, output: &mut [f32]) -> usize {
+        while let Ok(source) = self.incoming.try_recv() {
+            // A source that was still playing out is cut off by the next one.
+            self.outgoing = Some(std::mem::replace(&mut self.current, source));
+        }
+        let written = self.current.write(output);
+        let Some(outgoing) = &mut self.outgoing else {
+            return written;
+        };
+        if self.buffer.len() < output.len() {
+            self.buffer.resize(output.len(), 0.0);
+        }
+        let mixed = outgoing.write(&mut self.buffer[..output.len()]);
+        if mixed == 0 {
+            // The previous source has played out.
+            self.outgoing = None;
+            return written;
+        }
+        if mixed > written {
+            output[written..mixed].fill(0.0);
+        }
+        output[..mixed]
+            .iter_mut()
+            .zip(&self.buffer[..mixed])
+            .for_each(|(sample, mixed)| *sample += mixed);
+        written.max(mixed)
+    }
+
+    fn channel_count(&self) -> usize {
+        self.current.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.current.sample_rate()
+    }
+}
+
 struct Buf {
     buf: Vec<f32>,
     start: usize,
@@ -0,0 +1,237 @@
+//! Spectrum of the audio that is playing, for the visualizer of the GUI.  The
+//! `SpectrumSource` passes the samples through untouched and, while the
+//! analysis is turned on, publishes the levels of every frame of them.
+
+use std::{
+    f32::consts::PI,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use super::source::AudioSource;
+
+/// Samples in each analysed frame, a power of two for the FFT.
+pub const FFT_SIZE: usize = 2048;
+/// Bands the spectrum is summed up into, spaced logarithmically.
+pub const SPECTRUM_BANDS: usize = 32;
+
+/// Lowest and highest frequencies of the bands, in Hz.
+const MIN_FREQUENCY: f32 = 40.0;
+const MAX_FREQUENCY: f32 = 16_000.0;
+/// Levels at and below this are shown as silence, in dB.
+const FLOOR_DB: f32 = -70.0;
+
+static GLOBAL_SPECTRUM: Lazy<Arc<Spectrum>> = Lazy::new(|| Arc::new(Spectrum::new()));
+
+/// Levels of the latest analysed frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpectrumFrame {
+    /// Level of each band, from 0.0 for silence to 1.0, the lowest band
+    /// first.
+    pub bands: [f32; SPECTRUM_BANDS],
+    /// Number of frames analysed so far, to tell a new frame apart.
+    pub sequence: u64,
+}
+
+/// Latest spectrum, shared between the audio thread and the GUI.
+pub struct Spectrum {
+    enabled: AtomicBool,
+    frame: Mutex<SpectrumFrame>,
+}
+
+impl Spectrum {
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            frame: Mutex::new(SpectrumFrame {
+                bands: [0.0; SPECTRUM_BANDS],
+                sequence: 0,
+            }),
+        }
+    }
+
+    /// Spectrum of the output of the player.
+    pub fn global() -> Arc<Self> {
+        GLOBAL_SPECTRUM.clone()
+    }
+
+    /// Turn the analysis on or off.  It costs nothing while off.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn frame(&self) -> SpectrumFrame {
+        self.frame.lock().clone()
+    }
+
+    /// Called from the audio thread, which must not wait on the lock, so a
+    /// frame is dropped while the GUI is reading the previous one.
+    fn publish(&self, bands: [f32; SPECTRUM_BANDS]) {
+        if let Some(mut frame) = self.frame.try_lock() {
+            frame.bands = bands;
+            frame.sequence += 1;
+        }
+    }
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lowest and highest frequency of `band`, in Hz.
+pub fn band_frequency_range(band: usize) -> (f32, f32) {
+    let ratio = MAX_FREQUENCY / MIN_FREQUENCY;
+    let edge = |index: usize| MIN_FREQUENCY * ratio.powf(index as f32 / SPECTRUM_BANDS as f32);
+    (edge(band), edge(band + 1))
+}
+
+/// Passes `source` through and feeds a mono mix of it to the analysis.
+pub struct SpectrumSource<S> {
+    source: S,
+    spectrum: Arc<Spectrum>,
+    channels: usize,
+    /// FFT bins summed up into each band.
+    band_bins: Vec<Range<usize>>,
+    window: Vec<f32>,
+    /// Mono samples of the frame being collected.
+    samples: Vec<f32>,
+    real: Vec<f32>,
+    imag: Vec<f32>,
+}
+
+impl<S> SpectrumSource<S>
+where
+    S: AudioSource,
+{
+    pub fn new(source: S, spectrum: Arc<Spectrum>) -> Self {
+        let channels = source.channel_count().max(1);
+        let bin_width = source.sample_rate() as f32 / FFT_SIZE as f32;
+        let band_bins = (0..SPECTRUM_BANDS)
+            .map(|band| {
+                let (low, high) = band_frequency_range(band);
+                let start = ((low / bin_width).round() as usize).clamp(1, FFT_SIZE / 2 - 1);
+                let end = ((high / bin_width).round() as usize).clamp(start + 1, FFT_SIZE / 2);
+                start..end
+            })
+            .collect();
+        // Hann window, against the leakage of the frame edges.
+        let window = (0..FFT_SIZE)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / (FFT_SIZE - 1) as f32).cos())
+            .collect();
+        Self {
+            source,
+            spectrum,
+            channels,
+            band_bins,
+            window,
+            samples: Vec::with_capacity(FFT_SIZE),
+            real: vec![0.0; FFT_SIZE],
+            imag: vec![0.0; FFT_SIZE],
+        }
+    }
+
+    fn analyse(&mut self) {
+        for ((real, imag), (sample, weight)) in self
+            .real
+            .iter_mut()
+            .zip(self.imag.iter_mut())
+            .zip(self.samples.iter().zip(&self.window))
+        {
+            *real = sample * weight;
+            *imag = 0.0;
+        }
+        fft(&mut self.real, &mut self.imag);
+
+        // Scaled so that a full-scale sine reads 0 dB, the Hann window halves
+        // the amplitude.
+        let scale = 4.0 / FFT_SIZE as f32;
+        let mut bands = [0.0; SPECTRUM_BANDS];
+        for (level, bins) in bands.iter_mut().zip(&self.band_bins) {
+            let peak = bins
+                .clone()
+                .map(|bin| self.real[bin].hypot(self.imag[bin]) * scale)
+                .fold(0.0_f32, f32::max);
+            let db = 20.0 * peak.max(f32::MIN_POSITIVE).log10();
+            *level = ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0);
+        }
+        self.spectrum.publish(bands);
+    }
+}
+
+impl<S> AudioSource for SpectrumSource<S>
+where
+    S: AudioSource,
+{
+    fn write(&mut self, output: &mut [f32]) -> usize {
+        let written = self.source.write(output);
+        if !self.spectrum.is_enabled() {
+            self.samples.clear();
+            return written;
+        }
+        for frame in output[..written].chunks_exact(self.channels) {
+            self.samples
+                .push(frame.iter().sum::<f32>() / self.channels as f32);
+            if self.samples.len() == FFT_SIZE {
+                self.analyse();
+                self.samples.clear();
+            }
+        }
+        written
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+}
+
+/// In-place radix-2 FFT, the length must be a power of two.
+fn fft(real: &mut [f32], imag: &mut [f32]) {
+    let n = real.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            real.swap(i, j);
+            imag.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let re = real[b] * cos - imag[b] * sin;
+                let im = real[b] * sin + imag[b] * cos;
+                real[b] = real[a] - re;
+                imag[b] = imag[a] - im;
+                real[a] += re;
+                imag[a] += im;
+            }
+        }
+        len <<= 1;
+    }
+}
@@ -0,0 +1,171 @@
+//! Spoken words for the channel test, rendered by the speech synthesizer of
+//! the system.  Psst ships no recordings, so the test falls back to beeps if
+//! no synthesizer is installed.
+
+use std::{fs, io, path::Path, process::Command};
+
+use crate::error::Error;
+
+/// Mono samples of a spoken word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Speech {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl Speech {
+    /// Resample to `sample_rate` with linear interpolation, plenty for a voice.
+    pub fn resampled(&self, sample_rate: u32) -> Vec<f32> {
+        if sample_rate == self.sample_rate || self.samples.is_empty() {
+            return self.samples.clone();
+        }
+        let step = self.sample_rate as f64 / sample_rate as f64;
+        let frames = (self.samples.len() as f64 / step) as usize;
+        (0..frames)
+            .map(|frame| {
+                let position = frame as f64 * step;
+                let index = position as usize;
+                let next = self.samples.get(index + 1).unwrap_or(&self.samples[index]);
+                let fraction = (position - index as f64) as f32;
+                self.samples[index] * (1.0 - fraction) + next * fraction
+            })
+            .collect()
+    }
+}
+
+/// Name of each of `channels` channels, in the WAVE channel order.
+pub fn channel_names(channels: usize) -> Vec<String> {
+    const SURROUND: [&str; 8] = [
+        "Front left",
+        "Front right",
+        "Center",
+        "Subwoofer",
+        "Rear left",
+        "Rear right",
+        "Side left",
+        "Side right",
+    ];
+    match channels {
+        1 => vec!["Mono".to_string()],
+        2 => vec!["Left".to_string(), "Right".to_string()],
+        _ => (0..channels)
+            .map(|channel| match SURROUND.get(channel) {
+                Some(name) => name.to_string(),
+                None => format!("Channel {}", channel + 1),
+            })
+            .collect(),
+    }
+}
+
+/// Speak `text` into a WAVE file with the synthesizer of the system and read
+/// it back.
+pub fn speak(text: &str) -> Result<Speech, Error> {
+    let path = std::env::temp_dir().join(format!(
+        "psst-speech-{}-{}.wav",
+        std::process::id(),
+        text.replace(' ', "-").to_lowercase()
+    ));
+    let result = synthesize(text, &path).and_then(|_| parse_wave(&fs::read(&path)?));
+    let _ = fs::remove_file(&path);
+    result
+}
+
+#[cfg(target_os = "macos")]
+fn synthesize(text: &str, path: &Path) -> Result<(), Error> {
+    run(Command::new("say").arg("-o").arg(path).args([
+        "--file-format=WAVE",
+        "--data-format=LEI16@22050",
+        text,
+    ]))
+}
+
+#[cfg(target_os = "windows")]
+fn synthesize(text: &str, path: &Path) -> Result<(), Error> {
+    let quote = |s: &str| s.replace('\'', "''");
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; \
+         $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+         $s.SetOutputToWaveFile('{}'); $s.Speak('{}'); $s.Dispose()",
+        quote(&path.to_string_lossy()),
+        quote(text)
+    );
+    run(Command::new("powershell").args(["-NoProfile", "-NonInteractive", "-Command", &script]))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn synthesize(text: &str, path: &Path) -> Result<(), Error> {
+    let mut last_error = None;
+    for program in ["espeak-ng", "espeak", "pico2wave"] {
+        match run(Command::new(program).arg("-w").arg(path).arg(text)) {
+            Ok(()) => return Ok(()),
+            Err(err) => last_error = Some(err),
+        }
+    }
+    Err(last_error.unwrap_or(Error::UnexpectedResponse))
+}
+
+fn run(command: &mut Command) -> Result<(), Error> {
+    let output = command.output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::IoError(io::Error::other(format!(
+            "speech synthesizer failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))))
+    }
+}
+
+/// Read 16-bit integer or 32-bit float WAVE data, mixed down to mono.
+pub fn parse_wave(data: &[u8]) -> Result<Speech, Error> {
+    let invalid = |msg: &str| Error::IoError(io::Error::new(io::ErrorKind::InvalidData, msg));
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err(invalid("not a WAVE file"));
+    }
+    let mut format = None;
+    let mut chunks = &data[12..];
+    while chunks.len() >= 8 {
+        let id = &chunks[0..4];
+        let len = u32::from_le_bytes([chunks[4], chunks[5], chunks[6], chunks[7]]) as usize;
+        // Synthesizers writing to a pipe leave the length of the data open.
+        let body = &chunks[8..chunks.len().min(8 + len)];
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let tag = u16::from_le_bytes([body[0], body[1]]);
+                let channels = u16::from_le_bytes([body[2], body[3]]).max(1) as usize;
+                let rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                format = Some((tag, channels, rate, bits));
+            }
+            b"data" => {
+                let Some((tag, channels, sample_rate, bits)) = format else {
+                    return Err(invalid("WAVE data before its format"));
+                };
+                let samples: Vec<f32> = match (tag, bits) {
+                    (1, 16) => body
+                        .chunks_exact(2)
+                        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+                        .collect(),
+                    (3, 32) => body
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                    _ => return Err(invalid("unsupported WAVE sample format")),
+                };
+                let samples = samples
+                    .chunks_exact(channels)
+                    .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                    .collect();
+                return Ok(Speech {
+                    samples,
+                    sample_rate,
+                });
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length.
+        let next = 8 + len + len % 2;
+        chunks = chunks.get(next..).unwrap_or_default();
+    }
+    Err(invalid("WAVE file without data"))
+}
@@ -0,0 +1,249 @@
+use std::f32::consts::PI;
+
+/// Playback rate and silence trimming applied to podcast episodes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tempo {
+    /// Playback rate, `1.0` plays at the original speed.
+    pub speed: f32,
+    /// Shorten long pauses to `MAX_SILENCE`.
+    pub skip_silence: bool,
+}
+
+impl Tempo {
+    pub const NORMAL: Self = Self {
+        speed: 1.0,
+        skip_silence: false,
+    };
+    pub const MIN_SPEED: f32 = 0.5;
+    pub const MAX_SPEED: f32 = 3.0;
+
+    pub fn new(speed: f32, skip_silence: bool) -> Self {
+        Self {
+            speed: speed.clamp(Self::MIN_SPEED, Self::MAX_SPEED),
+            skip_silence,
+        }
+    }
+
+    /// Is the audio passed through untouched?
+    pub fn is_normal(&self) -> bool {
+        self.speed == 1.0 && !self.skip_silence
+    }
+}
+
+impl Default for Tempo {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+/// Length of the analysed segments.  Long enough to contain a couple of
+/// periods of a speaking voice.
+const SEGMENT_LENGTH: f32 = 0.04;
+/// How far a segment may be moved to line it up with the previous one.
+const SEEK_TOLERANCE: f32 = 0.01;
+/// Only every n-th frame is compared while lining up the segments.
+const CORRELATION_STRIDE: usize = 4;
+
+/// Changes the tempo of an interleaved signal without changing its pitch,
+/// using waveform-similarity overlap-add (WSOLA).  Segments are taken from
+/// the input at `speed` times the rate they are written to the output, each
+/// one moved slightly so that it lines up with the end of the previous one.
+pub struct TimeStretch {
+    channels: usize,
+    /// Frames in a segment.
+    segment: usize,
+    /// Frames between the starts of two output segments, half a segment.
+    hop: usize,
+    /// Frames a segment may be moved by, in each direction.
+    tolerance: usize,
+    window: Vec<f32>,
+    speed: f32,
+    /// Input waiting to be processed.
+    input: Vec<f32>,
+    /// Nominal start of the next segment, in frames into `input`.
+    position: f64,
+    /// Where the last segment would have continued, in frames into `input`.
+    natural: Option<usize>,
+    /// Second, windowed half of the last segment.
+    overlap: Vec<f32>,
+    /// Output waiting to be read, starting at `output_start`.
+    output: Vec<f32>,
+    output_start: usize,
+}
+
+impl TimeStretch {
+    pub fn new(channels: usize, sample_rate: u32, speed: f32) -> Self {
+        let hop = ((SEGMENT_LENGTH * sample_rate as f32) as usize / 2).max(1);
+        let segment = hop * 2;
+        let tolerance = (SEEK_TOLERANCE * sample_rate as f32) as usize;
+        // A periodic Hann window, its overlapping halves sum up to one.
+        let window = (0..segment)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / segment as f32).cos())
+            .collect();
+        Self {
+            channels,
+            segment,
+            hop,
+            tolerance,
+            window,
+            speed,
+            input: Vec::new(),
+            position: 0.0,
+            natural: None,
+            overlap: vec![0.0; hop * channels],
+            output: Vec::new(),
+            output_start: 0,
+        }
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Throw away all buffered input and output, for example after a seek.
+    pub fn reset(&mut self) {
+        self.input.clear();
+        self.position = 0.0;
+        self.natural = None;
+        self.overlap.iter_mut().for_each(|s| *s = 0.0);
+        self.output.clear();
+        self.output_start = 0;
+    }
+
+    /// Append interleaved samples to the input.
+    pub fn push(&mut self, input: &[f32]) {
+        self.input.extend_from_slice(input);
+    }
+
+    /// Write stretched samples into `output`, returning how many were written.
+    /// Less than `output.len()` means more input has to be pushed.
+    pub fn read(&mut self, output: &mut [f32]) -> usize {
+        let mut total = 0;
+        while total < output.len() {
+            if self.output_start >= self.output.len() && !self.step() {
+                break;
+            }
+            let ready = &self.output[self.output_start..];
+            let n = ready.len().min(output.len() - total);
+            output[total..total + n].copy_from_slice(&ready[..n]);
+            self.output_start += n;
+            total += n;
+        }
+        total
+    }
+
+    /// Produce the next `hop` frames of output, if there is enough input.
+    fn step(&mut self) -> bool {
+        let ch = self.channels;
+        let target = self.position.round() as usize;
+        if self.input.len() < (target + self.tolerance + self.segment) * ch {
+            return false;
+        }
+        let start = match self.natural {
+            Some(natural) => self.best_start(natural, target),
+            None => target,
+        };
+
+        self.output.clear();
+        self.output_start = 0;
+        let segment = &self.input[start * ch..(start + self.segment) * ch];
+        for (i, frame) in segment.chunks_exact(ch).enumerate() {
+            let gain = self.window[i];
+            for (c, sample) in frame.iter().enumerate() {
+                if i < self.hop {
+                    self.output.push(self.overlap[i * ch + c] + sample * gain);
+                } else {
+                    self.overlap[(i - self.hop) * ch + c] = sample * gain;
+                }
+            }
+        }
+        let natural = start + self.hop;
+        self.position += self.hop as f64 * self.speed as f64;
+
+        // Drop the input that the next segment cannot start in.
+        let consumed = (self.position.floor() as usize)
+            .saturating_sub(self.tolerance)
+            .min(natural);
+        self.input.drain(..consumed * ch);
+        self.position -= consumed as f64;
+        self.natural = Some(natural - consumed);
+        true
+    }
+
+    /// Start of the segment around `target` that is most similar to the
+    /// natural continuation of the previous segment, starting at `natural`.
+    /// Similarity is the cross-correlation normalized by the energy of the
+    /// candidate, so that the natural continuation itself always wins.
+    fn best_start(&self, natural: usize, target: usize) -> usize {
+        let ch = self.channels;
+        let frame_sum =
+            |frame: usize| -> f32 { self.input[frame * ch..(frame + 1) * ch].iter().sum() };
+        let mut best = target;
+        let mut best_score = f32::MIN;
+        for candidate in target.saturating_sub(self.tolerance)..=target + self.tolerance {
+            let (correlation, energy) = (0..self.hop).step_by(CORRELATION_STRIDE).fold(
+                (0.0, 0.0),
+                |(correlation, energy), i| {
+                    let sample = frame_sum(candidate + i);
+                    (
+                        correlation + frame_sum(natural + i) * sample,
+                        energy + sample * sample,
+                    )
+                },
+            );
+            let score = correlation / energy.sqrt().max(f32::EPSILON);
+            if score > best_score {
+                best_score = score;
+                best = candidate;
+            }
+        }
+        best
+    }
+}
+
+/// Level below which a frame counts as silent, about -50 dBFS.
+const SILENCE_THRESHOLD: f32 = 0.003;
+/// Longest pause kept when trimming silence.
+const MAX_SILENCE: f32 = 0.3;
+
+/// Removes the silent frames of pauses longer than `MAX_SILENCE`, keeping the
+/// beginning of each pause so that sentences stay apart.
+pub struct SilenceTrimmer {
+    channels: usize,
+    max_silent_frames: usize,
+    silent_frames: usize,
+}
+
+impl SilenceTrimmer {
+    pub fn new(channels: usize, sample_rate: u32) -> Self {
+        Self {
+            channels,
+            max_silent_frames: (MAX_SILENCE * sample_rate as f32) as usize,
+            silent_frames: 0,
+        }
+    }
+
+    /// Trim the interleaved `samples` in place, returning the length of the
+    /// kept part.
+    pub fn trim(&mut self, samples: &mut [f32]) -> usize {
+        let ch = self.channels;
+        let mut kept = 0;
+        for frame in 0..samples.len() / ch {
+            let range = frame * ch..(frame + 1) * ch;
+            let silent = samples[range.clone()]
+                .iter()
+                .all(|s| s.abs() < SILENCE_THRESHOLD);
+            if silent {
+                self.silent_frames += 1;
+                if self.silent_frames > self.max_silent_frames {
+                    continue;
+                }
+            } else {
+                self.silent_frames = 0;
+            }
+            samples.copy_within(range, kept * ch);
+            kept += 1;
+        }
+        kept * ch
+    }
+}
@@ -1,13 +1,22 @@
 use std::{
-    fs, io,
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    audio::decrypt::AudioKey,
+    audio::decrypt::{AudioDecrypt, AudioKey},
     error::Error,
-    item_id::{FileId, ItemId},
+    item_id::{FileId, ItemId, ItemIdType},
     protocol::metadata::{Episode, Track},
     util::{deserialize_protobuf, serialize_protobuf},
 };
@@ -24,7 +33,10 @@ fn create_cache_dirs(base: &Path) -> io::Result<()> {
     mkdir_if_not_exists(&base.join("track"))?;
     mkdir_if_not_exists(&base.join("episode"))?;
     mkdir_if_not_exists(&base.join("audio"))?;
+    mkdir_if_not_exists(&base.join("audio").join("partial"))?;
     mkdir_if_not_exists(&base.join("key"))?;
+    mkdir_if_not_exists(&base.join("progress"))?;
+    mkdir_if_not_exists(&base.join("speed"))?;
     Ok(())
 }
 
@@ -55,6 +67,215 @@ impl Cache {
         // Re-create the essential directory structure.
         create_cache_dirs(&self.base)
     }
+
+    /// Measures how much space each category of cached data takes up.
+    /// Walks the whole cache directory, so this can take a while, see
+    /// `scan_usage` for a version that can be interrupted.
+    pub fn usage_report(&self) -> io::Result<CacheUsage> {
+        let usage = scan_usage(&self.base, &AtomicBool::new(false), &mut |_| {})?;
+        Ok(usage.unwrap_or_default())
+    }
+
+    /// Measures the usage like `usage_report` on a background thread.  Partial
+    /// counts are reported while the cache is being walked, and the walk stops
+    /// as soon as the returned `CacheScan` is cancelled or dropped.
+    pub fn scan_usage(
+        &self,
+        mut on_update: impl FnMut(CacheScanUpdate) + Send + 'static,
+    ) -> CacheScan {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        thread::spawn({
+            let base = self.base.clone();
+            let cancelled = cancelled.clone();
+            move || {
+                let result = scan_usage(&base, &cancelled, &mut |usage| {
+                    on_update(CacheScanUpdate::Progress(*usage))
+                });
+                match result {
+                    Ok(Some(usage)) => on_update(CacheScanUpdate::Done(Ok(usage))),
+                    Ok(None) => log::debug!("cache scan cancelled"),
+                    Err(err) => on_update(CacheScanUpdate::Done(Err(err))),
+                }
+            }
+        });
+        CacheScan { cancelled }
+    }
+
+    /// Removes all cached data of one category, keeping everything else.
+    pub fn clear_category(&self, category: CacheCategory) -> io::Result<()> {
+        log::info!("clearing {} from cache: {:?}", category.name(), self.base);
+
+        let path = self.base.join(category.dir_name());
+        if path.exists() {
+            fs::remove_dir_all(path)?;
+        }
+        create_cache_dirs(&self.base)
+    }
+}
+
+/// Kinds of data kept in the cache, each in its own directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CacheCategory {
+    Audio,
+    Tracks,
+    Episodes,
+    Images,
+    Keys,
+}
+
+impl CacheCategory {
+    pub const ALL: [Self; 5] = [
+        Self::Audio,
+        Self::Tracks,
+        Self::Episodes,
+        Self::Images,
+        Self::Keys,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Audio => "Audio",
+            Self::Tracks => "Track Metadata",
+            Self::Episodes => "Episode Metadata",
+            Self::Images => "Images",
+            Self::Keys => "Audio Keys",
+        }
+    }
+
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::Audio => "audio",
+            Self::Tracks => "track",
+            Self::Episodes => "episode",
+            // Written by the Web API cache of the GUI.
+            Self::Images => "images",
+            Self::Keys => "key",
+        }
+    }
+}
+
+/// Sizes in bytes of the cached data, by category.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheUsage {
+    pub audio: u64,
+    pub tracks: u64,
+    pub episodes: u64,
+    pub images: u64,
+    pub keys: u64,
+    /// Everything not belonging to a category, like listening progress and
+    /// Web API responses.
+    pub other: u64,
+}
+
+impl CacheUsage {
+    pub fn get(&self, category: CacheCategory) -> u64 {
+        match category {
+            CacheCategory::Audio => self.audio,
+            CacheCategory::Tracks => self.tracks,
+            CacheCategory::Episodes => self.episodes,
+            CacheCategory::Images => self.images,
+            CacheCategory::Keys => self.keys,
+        }
+    }
+
+    fn get_mut(&mut self, category: Option<CacheCategory>) -> &mut u64 {
+        match category {
+            Some(CacheCategory::Audio) => &mut self.audio,
+            Some(CacheCategory::Tracks) => &mut self.tracks,
+            Some(CacheCategory::Episodes) => &mut self.episodes,
+            Some(CacheCategory::Images) => &mut self.images,
+            Some(CacheCategory::Keys) => &mut self.keys,
+            None => &mut self.other,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        CacheCategory::ALL
+            .into_iter()
+            .map(|category| self.get(category))
+            .sum::<u64>()
+            + self.other
+    }
+}
+
+/// Message from a running `Cache::scan_usage`.
+#[derive(Debug)]
+pub enum CacheScanUpdate {
+    /// Usage counted so far.
+    Progress(CacheUsage),
+    /// The scan has finished, no more updates follow.
+    Done(io::Result<CacheUsage>),
+}
+
+/// Handle of a running `Cache::scan_usage`.
+pub struct CacheScan {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CacheScan {
+    /// Stop the scan.  No `CacheScanUpdate::Done` is sent afterwards.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for CacheScan {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// How often a running scan reports partial counts.
+const SCAN_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Walks the cache directory at `base` one entry at a time, so it can stop
+/// at any point.  Returns `None` if `cancelled` got set.
+fn scan_usage(
+    base: &Path,
+    cancelled: &AtomicBool,
+    progress: &mut dyn FnMut(&CacheUsage),
+) -> io::Result<Option<CacheUsage>> {
+    let mut usage = CacheUsage::default();
+    let mut last_progress = Instant::now();
+    // Directories left to walk, with the category of the top-level directory
+    // they are in.
+    let mut pending = vec![(base.to_path_buf(), None)];
+    while let Some((dir, category)) = pending.pop() {
+        let is_base = dir == base;
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // Files can disappear while the cache is in use, skip them.
+            Err(err) if !is_base && err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        for entry in entries {
+            if cancelled.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            let entry = entry?;
+            let category = if is_base {
+                let name = entry.file_name();
+                CacheCategory::ALL
+                    .into_iter()
+                    .find(|category| name == category.dir_name())
+            } else {
+                category
+            };
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push((entry.path(), category));
+            } else {
+                *usage.get_mut(category) += metadata.len();
+            }
+            if last_progress.elapsed() >= SCAN_PROGRESS_INTERVAL {
+                progress(&usage);
+                last_progress = Instant::now();
+            }
+        }
+    }
+    Ok(Some(usage))
 }
 
 // Cache of `Track` protobuf structures.
@@ -73,6 +294,24 @@ impl Cache {
     fn track_path(&self, item_id: ItemId) -> PathBuf {
         self.base.join("track").join(item_id.to_base62())
     }
+
+    /// Tracks and episodes with their metadata in the cache.
+    pub fn cached_item_ids(&self) -> io::Result<Vec<ItemId>> {
+        let mut ids = Vec::new();
+        for (dir, id_type) in [
+            ("track", ItemIdType::Track),
+            ("episode", ItemIdType::Podcast),
+        ] {
+            for entry in fs::read_dir(self.base.join(dir))? {
+                let name = entry?.file_name();
+                ids.extend(
+                    name.to_str()
+                        .and_then(|name| ItemId::from_base62(name, id_type)),
+                );
+            }
+        }
+        Ok(ids)
+    }
 }
 
 // Cache of `Episode` protobuf structures.
@@ -132,6 +371,235 @@ impl Cache {
     }
 }
 
+/// Unfinished download of an audio file.  Files are downloaded from the
+/// start, so the downloaded bytes are the range `0..downloaded`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PartialDownload {
+    pub downloaded: u64,
+    pub total_length: u64,
+}
+
+// Audio files being downloaded, kept in `audio/partial` next to their total
+// length, so that a download cut off by a restart or the network continues
+// where it stopped.
+impl Cache {
+    fn partial_audio_file_path(&self, file_id: FileId) -> PathBuf {
+        self.base
+            .join("audio")
+            .join("partial")
+            .join(file_id.to_base16())
+    }
+
+    fn partial_length_path(&self, file_id: FileId) -> PathBuf {
+        self.partial_audio_file_path(file_id)
+            .with_extension("length")
+    }
+
+    /// The unfinished download of the file, if there is one.
+    pub fn partial_download(&self, file_id: FileId) -> Option<PartialDownload> {
+        let total_length = fs::read_to_string(self.partial_length_path(file_id))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        let downloaded = fs::metadata(self.partial_audio_file_path(file_id))
+            .ok()?
+            .len();
+        (downloaded <= total_length).then_some(PartialDownload {
+            downloaded,
+            total_length,
+        })
+    }
+
+    /// Open the download of the file for appending the next bytes.  The
+    /// download starts over if the file is not `total_length` bytes long
+    /// anymore.
+    pub fn continue_download(&self, file_id: FileId, total_length: u64) -> io::Result<File> {
+        let known = self.partial_download(file_id);
+        let mut options = OpenOptions::new();
+        if known.is_some_and(|partial| partial.total_length == total_length) {
+            options.append(true);
+        } else {
+            fs::write(self.partial_length_path(file_id), total_length.to_string())?;
+            options.write(true).create(true).truncate(true);
+        }
+        options.open(self.partial_audio_file_path(file_id))
+    }
+
+    /// Move a completed download into the cache, after checking that it is
+    /// whole and decrypts with `key`.  Broken downloads are removed.
+    pub fn finish_download(&self, file_id: FileId, key: AudioKey) -> Result<(), Error> {
+        let path = self.partial_audio_file_path(file_id);
+        let complete = self
+            .partial_download(file_id)
+            .is_some_and(|partial| partial.downloaded == partial.total_length);
+        if !complete || !is_intact_audio_file(&path, key)? {
+            log::warn!("removing broken download: {file_id:?}");
+            self.remove_download(file_id);
+            return Err(Error::UnexpectedResponse);
+        }
+        log::debug!("saving downloaded audio file to cache: {file_id:?}");
+        fs::rename(&path, self.audio_file_path(file_id))?;
+        self.remove_download(file_id);
+        Ok(())
+    }
+
+    pub fn remove_download(&self, file_id: FileId) {
+        let _ = fs::remove_file(self.partial_audio_file_path(file_id));
+        let _ = fs::remove_file(self.partial_length_path(file_id));
+    }
+
+    /// Remove the downloads not continued for `max_age`, like the ones of
+    /// playlists no longer kept offline.  Returns the bytes freed.
+    pub fn remove_stale_downloads(&self, max_age: Duration) -> io::Result<u64> {
+        let mut removed_bytes = 0;
+        for entry in fs::read_dir(self.base.join("audio").join("partial"))? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().is_some() {
+                // A length file, removed together with its download.
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+            if age.is_some_and(|age| age > max_age) {
+                log::info!("removing stale download: {:?}", entry.file_name());
+                fs::remove_file(&path)?;
+                let _ = fs::remove_file(path.with_extension("length"));
+                removed_bytes += metadata.len();
+            }
+        }
+        Ok(removed_bytes)
+    }
+}
+
+/// Outcome of `Cache::verify`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheVerification {
+    /// Audio files looked at.
+    pub checked: usize,
+    /// Broken files that got deleted.
+    pub removed: usize,
+    pub removed_bytes: u64,
+    /// Files that could only be checked for being empty, because their key
+    /// is not cached.
+    pub unverified: usize,
+}
+
+// Verification of cached audio files.
+impl Cache {
+    /// Checks the cached audio files for truncation and corruption, and
+    /// deletes the broken ones so they get downloaded again.
+    pub fn verify(&self) -> io::Result<CacheVerification> {
+        log::info!("verifying cache: {:?}", self.base);
+
+        let keys = self.audio_keys_by_file()?;
+        let mut report = CacheVerification::default();
+        for entry in fs::read_dir(self.base.join("audio"))? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                // The downloads in `audio/partial`.
+                continue;
+            }
+            let length = metadata.len();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            report.checked += 1;
+
+            let intact = match name.get(..16).and_then(|prefix| keys.get(prefix)) {
+                Some(&key) => is_intact_audio_file(&path, key)?,
+                None => {
+                    report.unverified += 1;
+                    length > 0
+                }
+            };
+            if !intact {
+                log::info!("removing broken audio file from cache: {name}");
+                fs::remove_file(&path)?;
+                report.removed += 1;
+                report.removed_bytes += length;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Cached audio keys, by the first 16 characters of their file ID.
+    fn audio_keys_by_file(&self) -> io::Result<HashMap<String, AudioKey>> {
+        let mut keys = HashMap::new();
+        for entry in fs::read_dir(self.base.join("key"))? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // See `audio_key_path` for the naming.
+            let Some(file_prefix) = name.get(16..) else {
+                continue;
+            };
+            if let Some(key) = fs::read(entry.path())
+                .ok()
+                .and_then(|buf| AudioKey::from_raw(&buf))
+            {
+                keys.insert(file_prefix.to_owned(), key);
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// Length of the Spotify header preceding the audio in Ogg files.
+const OGG_HEADER_LENGTH: usize = 167;
+
+/// Longest possible Ogg page, a header with 255 segments of 255 bytes each.
+const OGG_MAX_PAGE_LENGTH: u64 = 27 + 255 + 255 * 255;
+
+/// Decrypts the beginning and the end of a cached audio file and checks
+/// that they look like the beginning and the end of an audio stream.
+fn is_intact_audio_file(path: &Path, key: AudioKey) -> io::Result<bool> {
+    let mut file = AudioDecrypt::new(key, File::open(path)?);
+    let length = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let mut head = [0; OGG_HEADER_LENGTH + 4];
+    if file.read_exact(&mut head).is_err() {
+        return Ok(false);
+    }
+    if &head[OGG_HEADER_LENGTH..] == b"OggS" {
+        // A complete stream ends with a page marked as the last one.
+        file.seek(SeekFrom::Start(length.saturating_sub(OGG_MAX_PAGE_LENGTH)))?;
+        let mut tail = Vec::new();
+        file.read_to_end(&mut tail)?;
+        Ok(ends_with_last_ogg_page(&tail))
+    } else {
+        // MP3 and FLAC files have no Spotify header, they start with an ID3
+        // tag, the sync word of the first frame, or the FLAC stream marker.
+        Ok(head.starts_with(b"ID3")
+            || head.starts_with(b"fLaC")
+            || (head[0] == 0xff && head[1] & 0xe0 == 0xe0))
+    }
+}
+
+fn ends_with_last_ogg_page(tail: &[u8]) -> bool {
+    const HEADER_LENGTH: usize = 27;
+    const END_OF_STREAM: u8 = 0x04;
+
+    // The capture pattern can also appear inside of page data, so check every
+    // occurrence.
+    (0..tail.len().saturating_sub(HEADER_LENGTH)).any(|start| {
+        let page = &tail[start..];
+        if !page.starts_with(b"OggS") || page[5] & END_OF_STREAM == 0 {
+            return false;
+        }
+        let segments = page[26] as usize;
+        page.get(HEADER_LENGTH..HEADER_LENGTH + segments)
+            .is_some_and(|table| {
+                let body: usize = table.iter().map(|&length| length as usize).sum();
+                HEADER_LENGTH + segments + body == page.len()
+            })
+    })
+}
+
 // Cache of user country code.
 impl Cache {
     pub fn get_country_code(&self) -> Option<String> {
@@ -148,6 +616,72 @@ impl Cache {
     }
 }
 
+/// How far a podcast episode has been listened to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpisodeProgress {
+    pub position_ms: u64,
+    pub fully_played: bool,
+}
+
+impl EpisodeProgress {
+    pub fn new(position: Duration, fully_played: bool) -> Self {
+        Self {
+            position_ms: position.as_millis() as u64,
+            fully_played,
+        }
+    }
+
+    pub fn position(&self) -> Duration {
+        Duration::from_millis(self.position_ms)
+    }
+}
+
+// Listening progress of podcast episodes.
+impl Cache {
+    pub fn get_episode_progress(&self, item_id: ItemId) -> Option<EpisodeProgress> {
+        let buf = fs::read(self.episode_progress_path(item_id)).ok()?;
+        serde_json::from_slice(&buf).ok()
+    }
+
+    pub fn save_episode_progress(
+        &self,
+        item_id: ItemId,
+        progress: &EpisodeProgress,
+    ) -> Result<(), Error> {
+        log::debug!("saving episode progress to cache: {item_id:?} {progress:?}");
+        fs::write(
+            self.episode_progress_path(item_id),
+            serde_json::to_vec(progress)?,
+        )?;
+        Ok(())
+    }
+
+    fn episode_progress_path(&self, item_id: ItemId) -> PathBuf {
+        self.base.join("progress").join(item_id.to_base62())
+    }
+}
+
+// Playback speed chosen for podcast episodes.
+impl Cache {
+    pub fn get_episode_speed(&self, item_id: ItemId) -> Option<f64> {
+        let buf = fs::read(self.episode_speed_path(item_id)).ok()?;
+        serde_json::from_slice(&buf).ok()
+    }
+
+    pub fn save_episode_speed(&self, item_id: ItemId, speed: f64) -> Result<(), Error> {
+        log::debug!("saving episode speed to cache: {item_id:?} {speed}");
+        fs::write(
+            self.episode_speed_path(item_id),
+            serde_json::to_vec(&speed)?,
+        )?;
+        Ok(())
+    }
+
+    fn episode_speed_path(&self, item_id: ItemId) -> PathBuf {
+        self.base.join("speed").join(item_id.to_base62())
+    }
+}
+
 pub fn mkdir_if_not_exists(path: &Path) -> io::Result<()> {
     fs::create_dir(path).or_else(|err| {
         if err.kind() == io::ErrorKind::AlreadyExists {
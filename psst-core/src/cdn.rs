@@ -4,30 +4,59 @@ use std::{
     time::{Duration, Instant},
 };
 
+use parking_lot::Mutex;
 use serde::Deserialize;
 use ureq::http::StatusCode;
 
 use crate::{
-    error::Error, item_id::FileId, oauth::refresh_access_token, session::SessionService,
-    util::default_ureq_agent_builder,
+    error::Error, item_id::FileId, oauth::refresh_tokens, player::bandwidth::BandwidthMeter,
+    session::SessionService, util::default_ureq_agent_builder,
 };
 
 pub type CdnHandle = Arc<Cdn>;
 
+/// Most requests a streamed file sends to the CDN at once.  The agent keeps as
+/// many idle connections, so the requests don't pay for new TLS handshakes.
+pub const MAX_PARALLEL_REQUESTS: usize = 8;
+
+/// Idle pooled connections are closed after this long.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
 pub struct Cdn {
     session: SessionService,
-    agent: ureq::Agent,
+    /// Agent for requests through the proxy it was built for.
+    agent: Mutex<(Option<String>, ureq::Agent)>,
+    bandwidth: BandwidthMeter,
 }
 
 impl Cdn {
-    pub fn new(session: SessionService, proxy_url: Option<&str>) -> Result<CdnHandle, Error> {
-        let agent = default_ureq_agent_builder(proxy_url).build();
+    /// The CDN is reached through the proxy of the session config, and
+    /// follows it when the config changes.
+    pub fn new(session: SessionService) -> Result<CdnHandle, Error> {
+        let proxy_url = session.proxy_url();
+        let agent = build_agent(proxy_url.as_deref());
         Ok(Arc::new(Self {
             session,
-            agent: agent.into(),
+            agent: Mutex::new((proxy_url, agent)),
+            bandwidth: BandwidthMeter::new(),
         }))
     }
 
+    /// Throughput measured on the downloads of streamed files.
+    pub fn bandwidth(&self) -> &BandwidthMeter {
+        &self.bandwidth
+    }
+
+    fn agent(&self) -> ureq::Agent {
+        let proxy_url = self.session.proxy_url();
+        let mut agent = self.agent.lock();
+        if agent.0 != proxy_url {
+            let rebuilt = build_agent(proxy_url.as_deref());
+            *agent = (proxy_url, rebuilt);
+        }
+        agent.1.clone()
+    }
+
     pub fn resolve_audio_file_url(&self, id: FileId) -> Result<CdnUrl, Error> {
         let locations_uri = format!(
             "https://api.spotify.com/v1/storage-resolve/files/audio/interactive/{}",
@@ -39,8 +68,9 @@ impl Cdn {
             .oauth_bearer()
             .ok_or_else(|| Error::OAuthError("OAuth access token required".to_string()))?;
 
+        let agent = self.agent();
         let call = |token: &str| {
-            self.agent
+            agent
                 .get(&locations_uri)
                 .query("version", "10000000")
                 .query("product", "9")
@@ -57,14 +87,16 @@ impl Cdn {
                 let Some(refresh_token) = self.session.oauth_refresh_token() else {
                     return Err(Error::OAuthError("Missing refresh token".into()));
                 };
-                let (new_access, new_refresh) = refresh_access_token(&refresh_token)
+                let tokens = refresh_tokens(&refresh_token)
                     .map_err(|_| Error::OAuthError("Failed to refresh token".into()))?;
                 // Update session tokens so future requests use the fresh token
-                self.session.set_oauth_bearer(Some(new_access.clone()));
-                if let Some(r) = new_refresh {
+                self.session
+                    .set_oauth_bearer(Some(tokens.access_token.clone()));
+                self.session.set_oauth_expiry(tokens.expires_in);
+                if let Some(r) = tokens.refresh_token {
                     self.session.set_oauth_refresh_token(Some(r));
                 }
-                access_token = new_access;
+                access_token = tokens.access_token;
                 call(&access_token)?
             }
             Err(e) => return Err(Error::AudioFetchingError(Box::new(e))),
@@ -93,7 +125,7 @@ impl Cdn {
         length: u64,
     ) -> Result<(u64, impl Read), Error> {
         let req = self
-            .agent
+            .agent()
             .get(uri)
             .header("Range", &range_header(offset, length));
         match req.call() {
@@ -114,6 +146,15 @@ impl Cdn {
     }
 }
 
+fn build_agent(proxy_url: Option<&str>) -> ureq::Agent {
+    default_ureq_agent_builder(proxy_url)
+        .max_idle_connections(MAX_PARALLEL_REQUESTS * 2)
+        .max_idle_connections_per_host(MAX_PARALLEL_REQUESTS)
+        .max_idle_age(POOL_IDLE_TIMEOUT)
+        .build()
+        .into()
+}
+
 #[derive(Clone)]
 pub struct CdnUrl {
     pub url: String,
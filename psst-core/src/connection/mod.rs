@@ -8,8 +8,10 @@ use std::{
     net::{TcpStream, ToSocketAddrs},
 };
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use byteorder::{ReadBytesExt, BE};
 use hmac::{Hmac, Mac};
+use percent_encoding::percent_decode_str;
 use serde::{Deserialize, Serialize};
 use sha1::Sha1;
 use socks::Socks5Stream;
@@ -188,12 +190,31 @@ impl Transport {
         }))
     }
 
+    /// Check that the access points can be reached, through the proxy at
+    /// `proxy_url` if given, and return the one that accepted the connection.
+    /// Unlike a session, this does not fall back to a default access point.
+    pub fn test_connection(proxy_url: Option<&str>) -> Result<String, Error> {
+        let ap_list = Self::resolve_ap(proxy_url)?;
+        for ap in &ap_list {
+            let stream = match proxy_url {
+                Some(url) => Self::stream_through_proxy(ap, url),
+                None => Self::stream_without_proxy(ap).map_err(Error::from),
+            };
+            match stream {
+                Ok(_) => return Ok(ap.clone()),
+                Err(Error::ProxyUrlInvalid) => return Err(Error::ProxyUrlInvalid),
+                Err(err) => log::warn!("failed to reach AP {ap}: {err}"),
+            }
+        }
+        Err(Error::ConnectionFailed)
+    }
+
     fn stream_through_proxy(ap: &str, url: &str) -> Result<TcpStream, Error> {
         match Url::parse(url) {
             Ok(url) if url.scheme() == "socks" || url.scheme() == "socks5" => {
-                // Currently we only support SOCKS5 proxies.
                 Self::stream_through_socks5_proxy(ap, &url)
             }
+            Ok(url) if url.scheme() == "http" => Self::stream_through_http_proxy(ap, &url),
             _ => {
                 // Proxy URL failed to parse or has unsupported scheme.
                 Err(Error::ProxyUrlInvalid)
@@ -203,17 +224,57 @@ impl Transport {
 
     fn stream_through_socks5_proxy(ap: &str, url: &Url) -> Result<TcpStream, Error> {
         let addrs = url.socket_addrs(|| None)?;
-        let username = url.username();
-        let password = url.password().unwrap_or("");
+        let username = percent_decode_str(url.username()).decode_utf8_lossy();
+        let password = percent_decode_str(url.password().unwrap_or("")).decode_utf8_lossy();
         // TODO: `socks` crate does not support connection timeouts.
         let proxy = if username.is_empty() {
             Socks5Stream::connect(&addrs[..], ap)?
         } else {
-            Socks5Stream::connect_with_password(&addrs[..], ap, username, password)?
+            Socks5Stream::connect_with_password(&addrs[..], ap, &username, &password)?
         };
         Ok(proxy.into_inner())
     }
 
+    /// Open a tunnel to `ap` with the `CONNECT` method of an HTTP proxy.
+    fn stream_through_http_proxy(ap: &str, url: &Url) -> Result<TcpStream, Error> {
+        let host = url.host_str().ok_or(Error::ProxyUrlInvalid)?;
+        let port = url.port_or_known_default().ok_or(Error::ProxyUrlInvalid)?;
+        let mut stream = Self::stream_without_proxy(&format!("{host}:{port}"))?;
+        stream.set_read_timeout(Some(NET_IO_TIMEOUT))?;
+
+        let mut request = format!("CONNECT {ap} HTTP/1.1\r\nHost: {ap}\r\n");
+        if !url.username().is_empty() {
+            let username = percent_decode_str(url.username()).decode_utf8_lossy();
+            let password = percent_decode_str(url.password().unwrap_or("")).decode_utf8_lossy();
+            let credentials = BASE64.encode(format!("{username}:{password}"));
+            request.push_str(&format!("Proxy-Authorization: Basic {credentials}\r\n"));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+
+        // Read the response head byte by byte, so nothing after it is consumed
+        // from the tunnel.
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            if head.len() > 8 * 1024 {
+                return Err(Error::UnexpectedResponse);
+            }
+            head.push(stream.read_u8()?);
+        }
+        let status_line = String::from_utf8_lossy(&head);
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or(Error::UnexpectedResponse)?;
+        if status != 200 {
+            log::warn!("HTTP proxy refused the tunnel with status {status}");
+            return Err(Error::HttpStatus(status));
+        }
+        stream.set_read_timeout(None)?;
+        Ok(stream)
+    }
+
     pub fn exchange_keys(mut stream: TcpStream) -> Result<Self, Error> {
         use crate::protocol::keyexchange::APResponseMessage;
 
@@ -23,6 +23,7 @@ pub enum Error {
     JoinError,
     HttpStatus(u16),
     OAuthError(String),
+    Offline,
 }
 
 impl error::Error for Error {}
@@ -65,6 +66,7 @@ impl fmt::Display for Error {
             Self::JoinError => write!(f, "Failed to join thread"),
             Self::HttpStatus(code) => write!(f, "HTTP status {}", code),
             Self::OAuthError(msg) => write!(f, "OAuth error: {msg}"),
+            Self::Offline => write!(f, "Not available offline"),
         }
     }
 }
@@ -75,6 +77,12 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::IoError(err.into())
+    }
+}
+
 impl<T> From<crossbeam_channel::SendError<T>> for Error {
     fn from(_: crossbeam_channel::SendError<T>) -> Self {
         Error::SendError
@@ -0,0 +1,536 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::Error;
+
+static GLOBAL_HISTORY: OnceCell<Arc<PlayHistory>> = OnceCell::new();
+
+/// A single play of a track, as stored in the history file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayRecord {
+    /// Base62 id of the played track.
+    pub track_id: String,
+    pub name: String,
+    pub artist: String,
+    pub album: String,
+    /// Unix timestamp (in seconds) of the play.
+    pub played_at: u64,
+}
+
+/// A track skipped within `SkipRecord::WINDOW` of its start.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkipRecord {
+    /// Base62 id of the skipped track.
+    pub track_id: String,
+    pub name: String,
+    pub artist: String,
+    /// Playlist the track was played from.
+    pub playlist_id: Option<String>,
+    /// Unix timestamp (in seconds) of the skip.
+    pub skipped_at: u64,
+}
+
+impl SkipRecord {
+    /// Skips later than this into a track are not counted.
+    pub const WINDOW: Duration = Duration::from_secs(20);
+}
+
+/// Description of one field of an exported `PlayRecord`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldDoc {
+    pub name: &'static str,
+    pub kind: &'static str,
+    pub description: &'static str,
+}
+
+impl PlayRecord {
+    /// Fields of the record in the order of the CSV columns.  The names match
+    /// the JSON keys.
+    pub const SCHEMA: &'static [FieldDoc] = &[
+        FieldDoc {
+            name: "track_id",
+            kind: "string",
+            description: "Base62 Spotify id, the track URI is `spotify:track:<track_id>`.",
+        },
+        FieldDoc {
+            name: "name",
+            kind: "string",
+            description: "Name of the track at the time of the play.",
+        },
+        FieldDoc {
+            name: "artist",
+            kind: "string",
+            description: "Name of the first artist of the track.",
+        },
+        FieldDoc {
+            name: "album",
+            kind: "string",
+            description: "Name of the album of the track.",
+        },
+        FieldDoc {
+            name: "played_at",
+            kind: "integer",
+            description: "Unix timestamp (in seconds) when half of the track had been played.",
+        },
+    ];
+
+    fn csv_row(&self) -> [String; 5] {
+        [
+            self.track_id.clone(),
+            self.name.clone(),
+            self.artist.clone(),
+            self.album.clone(),
+            self.played_at.to_string(),
+        ]
+    }
+}
+
+/// File format of an exported history.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A header row with the field names, then one row per play.
+    Csv,
+    /// An array of play objects.
+    Json,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Format matching the extension of `path`, if it is `.csv` or `.json`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_name)
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Markdown documentation of the export formats, generated from
+/// `PlayRecord::SCHEMA`.
+pub fn export_schema_markdown() -> String {
+    let mut doc = String::from(
+        "# Listening history export\n\n\
+         The listening history is recorded locally and is never uploaded. It can be \
+         exported as CSV, with a header row and one row per play, or as JSON, an array \
+         with one object per play. Plays are listed in the order they were recorded.\n\n\
+         Export it in the Diagnostics tab of the preferences, or with \
+         `psst-cli history export <csv|json> <history file>`. This page is generated with \
+         `psst-cli history schema`.\n\n\
+         | Field | Type | Description |\n\
+         |-------|------|-------------|\n",
+    );
+    for field in PlayRecord::SCHEMA {
+        doc.push_str(&format!(
+            "| `{}` | {} | {} |\n",
+            field.name, field.kind, field.description
+        ));
+    }
+    doc
+}
+
+fn write_csv_row<W: Write>(writer: &mut W, values: &[impl AsRef<str>]) -> Result<(), Error> {
+    let row: Vec<_> = values
+        .iter()
+        .map(|value| {
+            let value = value.as_ref();
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_string()
+            }
+        })
+        .collect();
+    // RFC 4180 ends rows with CRLF.
+    write!(writer, "{}\r\n", row.join(","))?;
+    Ok(())
+}
+
+/// File of the user's that every play is appended to as it is recorded, for
+/// listening archives kept independently of Last.fm and of the history.
+pub struct PlayLog {
+    path: PathBuf,
+    format: PlayLogFormat,
+}
+
+/// Format of a `PlayLog`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayLogFormat {
+    /// A header row with the field names, then one row per play.
+    Csv,
+    /// One play object per line.
+    JsonLines,
+}
+
+impl PlayLogFormat {
+    /// CSV for a `.csv` file, JSON Lines for any other.
+    pub fn from_path(path: &Path) -> Self {
+        match ExportFormat::from_path(path) {
+            Some(ExportFormat::Csv) => Self::Csv,
+            _ => Self::JsonLines,
+        }
+    }
+}
+
+impl PlayLog {
+    pub fn new(path: PathBuf) -> Self {
+        let format = PlayLogFormat::from_path(&path);
+        Self { path, format }
+    }
+
+    pub fn format(&self) -> PlayLogFormat {
+        self.format
+    }
+
+    /// Append a play to the log, creating the file, and writing the header of
+    /// a CSV log, if it does not exist yet.
+    pub fn append(&self, record: &PlayRecord) -> Result<(), Error> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        // Written at once, so that a failed write cannot leave the header
+        // without its row.
+        let mut lines = Vec::new();
+        match self.format {
+            PlayLogFormat::Csv => {
+                if file.metadata()?.len() == 0 {
+                    let header: Vec<_> =
+                        PlayRecord::SCHEMA.iter().map(|field| field.name).collect();
+                    write_csv_row(&mut lines, &header)?;
+                }
+                write_csv_row(&mut lines, &record.csv_row())?;
+            }
+            PlayLogFormat::JsonLines => {
+                writeln!(lines, "{}", serde_json::to_string(record)?)?;
+            }
+        }
+        file.write_all(&lines)?;
+        Ok(())
+    }
+}
+
+/// Plays of one track, aggregated over a period of the history.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackStats {
+    pub track_id: String,
+    pub name: String,
+    pub artist: String,
+    pub play_count: usize,
+    /// Unix timestamp (in seconds) of the latest play.
+    pub last_played: u64,
+}
+
+/// Skips of one track, see `PlayHistory::skipped_tracks`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkipStats {
+    pub track_id: String,
+    pub name: String,
+    pub artist: String,
+    pub skip_count: usize,
+}
+
+/// Locally recorded listening history, kept as one JSON record per line so that
+/// new plays can be appended without rewriting the file.  The skips are kept
+/// the same way in a file next to it.
+pub struct PlayHistory {
+    path: Option<PathBuf>,
+    records: Mutex<Vec<PlayRecord>>,
+    skips: Mutex<Vec<SkipRecord>>,
+}
+
+impl PlayHistory {
+    /// History that is only kept in memory.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            records: Mutex::new(Vec::new()),
+            skips: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Load the history stored at `path`, or start an empty one if the file
+    /// does not exist yet.  Lines that cannot be parsed are skipped.
+    pub fn open(path: PathBuf) -> Result<Self, Error> {
+        let records = read_json_lines(&path)?;
+        let skips = read_json_lines(&skips_path(&path))?;
+        Ok(Self {
+            path: Some(path),
+            records: Mutex::new(records),
+            skips: Mutex::new(skips),
+        })
+    }
+
+    pub fn install_as_global(self) {
+        GLOBAL_HISTORY
+            .set(Arc::new(self))
+            .map_err(|_| "Cannot install more than once")
+            .unwrap()
+    }
+
+    pub fn global() -> Arc<Self> {
+        GLOBAL_HISTORY.get().unwrap().clone()
+    }
+
+    /// Append a play to the history.
+    pub fn record(&self, record: PlayRecord) -> Result<(), Error> {
+        let mut records = self.records.lock();
+        if let Some(path) = &self.path {
+            append_json_line(path, &record)?;
+        }
+        records.push(record);
+        Ok(())
+    }
+
+    /// Append a skip to the history.
+    pub fn record_skip(&self, skip: SkipRecord) -> Result<(), Error> {
+        let mut skips = self.skips.lock();
+        if let Some(path) = &self.path {
+            append_json_line(&skips_path(path), &skip)?;
+        }
+        skips.push(skip);
+        Ok(())
+    }
+
+    /// Tracks of the playlist skipped at least `min_skips` times since
+    /// `since`, and not played to the half since their last skip.  Skipped
+    /// the most first.
+    pub fn skipped_tracks(
+        &self,
+        playlist_id: &str,
+        since: u64,
+        min_skips: usize,
+    ) -> Vec<SkipStats> {
+        let mut stats = HashMap::<String, (SkipStats, u64)>::new();
+        for skip in self.skips.lock().iter().filter(|skip| {
+            skip.skipped_at >= since && skip.playlist_id.as_deref() == Some(playlist_id)
+        }) {
+            let (track, last_skipped) = stats.entry(skip.track_id.clone()).or_insert_with(|| {
+                let track = SkipStats {
+                    track_id: skip.track_id.clone(),
+                    name: skip.name.clone(),
+                    artist: skip.artist.clone(),
+                    skip_count: 0,
+                };
+                (track, skip.skipped_at)
+            });
+            track.skip_count += 1;
+            *last_skipped = (*last_skipped).max(skip.skipped_at);
+        }
+        let records = self.records.lock();
+        let mut skipped: Vec<_> = stats
+            .into_values()
+            .filter(|(track, last_skipped)| {
+                track.skip_count >= min_skips
+                    && !records.iter().any(|record| {
+                        record.track_id == track.track_id && record.played_at > *last_skipped
+                    })
+            })
+            .map(|(track, _)| track)
+            .collect();
+        skipped.sort_by(|a, b| b.skip_count.cmp(&a.skip_count).then(a.name.cmp(&b.name)));
+        skipped
+    }
+
+    pub fn records(&self) -> Vec<PlayRecord> {
+        self.records.lock().clone()
+    }
+
+    /// Number of recorded plays.
+    pub fn play_count(&self) -> usize {
+        self.records.lock().len()
+    }
+
+    /// Forget all plays and skips, and remove the files they are kept in.
+    pub fn clear(&self) -> Result<(), Error> {
+        let mut records = self.records.lock();
+        let mut skips = self.skips.lock();
+        if let Some(path) = &self.path {
+            remove_if_exists(path)?;
+            remove_if_exists(&skips_path(path))?;
+        }
+        records.clear();
+        skips.clear();
+        Ok(())
+    }
+
+    /// Write all plays to `writer` in `format`, for analysis in other tools.
+    pub fn export<W: Write>(&self, format: ExportFormat, mut writer: W) -> Result<(), Error> {
+        let records = self.records.lock();
+        match format {
+            ExportFormat::Csv => {
+                let header: Vec<_> = PlayRecord::SCHEMA.iter().map(|field| field.name).collect();
+                write_csv_row(&mut writer, &header)?;
+                for record in records.iter() {
+                    write_csv_row(&mut writer, &record.csv_row())?;
+                }
+            }
+            ExportFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &*records)?;
+                writeln!(writer)?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write all plays to the file at `path`, replacing it.
+    pub fn export_to_file(&self, format: ExportFormat, path: &Path) -> Result<(), Error> {
+        let file = File::create(path)?;
+        self.export(format, BufWriter::new(file))
+    }
+
+    /// Most played tracks since `since`, most played first.  Ties are broken
+    /// by the most recent play.
+    pub fn top_tracks(&self, since: u64, limit: usize) -> Vec<TrackStats> {
+        let mut stats: Vec<_> = self
+            .stats(|record| record.played_at >= since)
+            .into_values()
+            .collect();
+        stats.sort_by(|a, b| {
+            b.play_count
+                .cmp(&a.play_count)
+                .then(b.last_played.cmp(&a.last_played))
+        });
+        stats.truncate(limit);
+        stats
+    }
+
+    /// Tracks that were played before, but not during the `not_played_for`
+    /// period before `now`.  Tracks played the most are listed first.
+    pub fn rediscover(&self, now: u64, not_played_for: Duration, limit: usize) -> Vec<TrackStats> {
+        let cutoff = now.saturating_sub(not_played_for.as_secs());
+        let mut stats: Vec<_> = self
+            .stats(|_| true)
+            .into_values()
+            .filter(|track| track.last_played < cutoff)
+            .collect();
+        stats.sort_by(|a, b| {
+            b.play_count
+                .cmp(&a.play_count)
+                .then(a.last_played.cmp(&b.last_played))
+        });
+        stats.truncate(limit);
+        stats
+    }
+
+    /// Number of plays on each of the `days` days up to the one of `now`, the
+    /// oldest first.  The days start at the local midnight of `utc_offset`
+    /// (in seconds).
+    pub fn daily_play_counts(&self, now: u64, days: usize, utc_offset: i64) -> Vec<usize> {
+        const DAY: i64 = 24 * 60 * 60;
+        let day_of = |timestamp: u64| (timestamp as i64 + utc_offset).div_euclid(DAY);
+        let today = day_of(now);
+        let mut counts = vec![0; days];
+        for record in self.records.lock().iter() {
+            let age = today - day_of(record.played_at);
+            if (0..days as i64).contains(&age) {
+                counts[days - 1 - age as usize] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Number of plays since `since` in each hour of the day, in the time
+    /// zone of `utc_offset` (in seconds).
+    pub fn hourly_play_counts(&self, since: u64, utc_offset: i64) -> [usize; 24] {
+        const HOUR: i64 = 60 * 60;
+        let mut counts = [0; 24];
+        for record in self
+            .records
+            .lock()
+            .iter()
+            .filter(|record| record.played_at >= since)
+        {
+            let hour = (record.played_at as i64 + utc_offset)
+                .div_euclid(HOUR)
+                .rem_euclid(24);
+            counts[hour as usize] += 1;
+        }
+        counts
+    }
+
+    fn stats(&self, filter: impl Fn(&PlayRecord) -> bool) -> HashMap<String, TrackStats> {
+        let mut stats = HashMap::<String, TrackStats>::new();
+        for record in self.records.lock().iter().filter(|record| filter(record)) {
+            let track = stats
+                .entry(record.track_id.clone())
+                .or_insert_with(|| TrackStats {
+                    track_id: record.track_id.clone(),
+                    name: record.name.clone(),
+                    artist: record.artist.clone(),
+                    play_count: 0,
+                    last_played: record.played_at,
+                });
+            track.play_count += 1;
+            track.last_played = track.last_played.max(record.played_at);
+        }
+        stats
+    }
+}
+
+/// File of the skips kept next to the history at `path`.
+fn skips_path(path: &Path) -> PathBuf {
+    path.with_extension("skips.jsonl")
+}
+
+/// Records of the JSON Lines file at `path`, none if the file does not
+/// exist.  Lines that cannot be parsed are skipped.
+fn read_json_lines<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, Error> {
+    let mut records = Vec::new();
+    match File::open(path) {
+        Ok(file) => {
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(&line) {
+                    Ok(record) => records.push(record),
+                    Err(err) => log::warn!("skipping invalid history record: {err}"),
+                }
+            }
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    Ok(records)
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), Error> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+fn append_json_line<T: Serialize>(path: &Path, record: &T) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
@@ -1,9 +1,50 @@
 use crate::error::Error;
 use crate::oauth::listen_for_callback_parameter;
+use crate::util::default_ureq_agent_builder;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
 use rustfm_scrobble::{responses::SessionResponse, Scrobble, Scrobbler, ScrobblerError};
-use std::{net::SocketAddr, time::Duration};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Write},
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 use url::Url;
 
+static GLOBAL_SCROBBLE_QUEUE: OnceCell<Arc<ScrobbleQueue>> = OnceCell::new();
+static GLOBAL_LASTFM_API: OnceCell<Arc<LastFmApi>> = OnceCell::new();
+
+/// Last.fm ignores scrobbles older than this.
+const MAX_SCROBBLE_AGE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// Oldest scrobbles are dropped beyond this, so that a long time offline
+/// cannot grow the store without bounds.
+const MAX_PENDING_SCROBBLES: usize = 10_000;
+
+const API_ROOT: &str = "https://ws.audioscrobbler.com/2.0/";
+
+/// Friends whose recent tracks are fetched, one request each.
+const MAX_FRIENDS: usize = 12;
+
+/// Last.fm asks clients for at most five requests per second.
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the activity of the friends is reused before asking again.
+const FRIENDS_CACHE_TTL: Duration = Duration::from_secs(3 * 60);
+
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// Last.fm errors that turn a scrobble down for good, sending it again cannot
+/// succeed: invalid parameters and invalid resource.
+const REJECTED_SCROBBLE_ERRORS: [u32; 2] = [6, 7];
+
 pub struct LastFmClient;
 
 impl LastFmClient {
@@ -32,6 +73,20 @@ impl LastFmClient {
         scrobbler.scrobble(&song).map(|_| ()).map_err(Error::from)
     }
 
+    /// Scrobble a track played at the time recorded in `scrobble`.
+    pub fn scrobble_pending(
+        scrobbler: &Scrobbler,
+        scrobble: &PendingScrobble,
+    ) -> Result<(), Error> {
+        let mut song = Scrobble::new(
+            &scrobble.artist,
+            &scrobble.title,
+            scrobble.album.as_deref().unwrap_or(""),
+        );
+        song.with_timestamp(scrobble.timestamp);
+        scrobbler.scrobble(&song).map(|_| ()).map_err(Error::from)
+    }
+
     /// Creates an authenticated Last.fm Scrobbler instance with provided credentials.
     /// Note: This assumes the session_key is valid. Validity is checked on first API call.
     pub fn create_scrobbler(
@@ -72,16 +127,25 @@ pub fn generate_lastfm_auth_url(
     Ok(url.to_string())
 }
 
+/// A Last.fm session, and the name of the user it belongs to.
+pub struct LastFmSession {
+    pub key: String,
+    pub username: String,
+}
+
 /// Exchange a token for a Last.fm session key
 pub fn exchange_token_for_session(
     api_key: &str,
     api_secret: &str,
     token: &str,
-) -> Result<String, Error> {
+) -> Result<LastFmSession, Error> {
     let mut scrobbler = Scrobbler::new(api_key, api_secret);
     scrobbler
         .authenticate_with_token(token) // Uses auth.getSession API call internally
-        .map(|response: SessionResponse| response.key) // Extract the session key string
+        .map(|response: SessionResponse| LastFmSession {
+            key: response.key,
+            username: response.name,
+        })
         .map_err(Error::from) // Map ScrobblerError to crate::error::Error
 }
 
@@ -93,3 +157,551 @@ pub fn get_lastfm_token_listener(
     // Use the shared listener function, specifying "token" as the parameter
     listen_for_callback_parameter(socket_address, timeout, "token")
 }
+
+/// A scrobble waiting to be sent to Last.fm.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingScrobble {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    /// Unix timestamp (in seconds) when the track was played.
+    pub timestamp: u64,
+}
+
+/// Delay before the next attempt after `failures` failed flushes in a row,
+/// doubling from 30 seconds up to an hour.
+pub fn retry_backoff(failures: u32) -> Duration {
+    INITIAL_RETRY_BACKOFF
+        .saturating_mul(2_u32.saturating_pow(failures.saturating_sub(1)))
+        .min(MAX_RETRY_BACKOFF)
+}
+
+/// Whether Last.fm turned down the scrobble that failed with `err` for good,
+/// instead of failing to take it right now.
+pub fn is_rejected_scrobble(err: &Error) -> bool {
+    let Error::ScrobblerError(err) = err else {
+        return false;
+    };
+    // The scrobbler passes the error response of Last.fm on as its message.
+    let message = err.to_string();
+    message
+        .find('{')
+        .and_then(|start| serde_json::from_str::<ApiError>(&message[start..]).ok())
+        .is_some_and(|err| REJECTED_SCROBBLE_ERRORS.contains(&err.error))
+}
+
+/// Scrobbles that were not sent yet, because Last.fm could not be reached.  They are kept as one JSON record per line, and sent in the
+/// order they were played once Last.fm is reachable again.
+pub struct ScrobbleQueue {
+    path: Option<PathBuf>,
+    state: Mutex<QueueState>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: VecDeque<PendingScrobble>,
+    /// Failed flushes in a row.
+    failures: u32,
+    /// No flush is attempted before this.
+    retry_at: Option<Instant>,
+    flushing: bool,
+}
+
+impl ScrobbleQueue {
+    /// Queue that is only kept in memory.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            state: Mutex::new(QueueState::default()),
+        }
+    }
+
+    /// Load the scrobbles stored at `path`, or start an empty queue if the
+    /// file does not exist yet.  Lines that cannot be parsed are skipped.
+    pub fn open(path: PathBuf) -> Result<Self, Error> {
+        let mut pending = VecDeque::new();
+        match File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str(&line) {
+                        Ok(scrobble) => pending.push_back(scrobble),
+                        Err(err) => log::warn!("skipping invalid pending scrobble: {err}"),
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        Ok(Self {
+            path: Some(path),
+            state: Mutex::new(QueueState {
+                pending,
+                ..QueueState::default()
+            }),
+        })
+    }
+
+    pub fn install_as_global(self) {
+        GLOBAL_SCROBBLE_QUEUE
+            .set(Arc::new(self))
+            .map_err(|_| "Cannot install more than once")
+            .unwrap()
+    }
+
+    pub fn global() -> Arc<Self> {
+        GLOBAL_SCROBBLE_QUEUE.get().unwrap().clone()
+    }
+
+    /// Add a scrobble to the end of the queue, and store the queue.
+    pub fn push(&self, scrobble: PendingScrobble) -> Result<(), Error> {
+        let mut state = self.state.lock();
+        state.pending.push_back(scrobble);
+        while state.pending.len() > MAX_PENDING_SCROBBLES {
+            state.pending.pop_front();
+        }
+        self.save(&state.pending)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().pending.is_empty()
+    }
+
+    pub fn pending(&self) -> Vec<PendingScrobble> {
+        self.state.lock().pending.iter().cloned().collect()
+    }
+
+    /// Forget the backoff, e.g. because the connection just came back.
+    pub fn retry_now(&self) {
+        let mut state = self.state.lock();
+        state.failures = 0;
+        state.retry_at = None;
+    }
+
+    /// Time left until the next flush is attempted, if any scrobbles wait
+    /// for the backoff to pass.  `None` while a flush is running.
+    pub fn retry_in(&self) -> Option<Duration> {
+        let state = self.state.lock();
+        if state.pending.is_empty() || state.flushing {
+            return None;
+        }
+        let retry_at = state.retry_at?;
+        Some(retry_at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Send the queued scrobbles with `send`, oldest first, until one fails.
+    /// Scrobbles older than Last.fm accepts, compared to `now` (a Unix
+    /// timestamp in seconds), and scrobbles Last.fm rejects for good, see
+    /// `is_rejected_scrobble`, are dropped.  After any other failure, flushes
+    /// are skipped until the backoff has passed, see `retry_in`.  Returns the
+    /// number of sent scrobbles.  `send` is called without holding the lock, so scrobbles
+    /// can be pushed while a flush is running.
+    pub fn flush(
+        &self,
+        now: u64,
+        mut send: impl FnMut(&PendingScrobble) -> Result<(), Error>,
+    ) -> Result<usize, Error> {
+        {
+            let mut state = self.state.lock();
+            let backing_off = state
+                .retry_at
+                .is_some_and(|retry_at| Instant::now() < retry_at);
+            if state.flushing || backing_off || state.pending.is_empty() {
+                return Ok(0);
+            }
+            state.flushing = true;
+        }
+
+        let mut sent = 0;
+        let mut result = Ok(());
+        loop {
+            let Some(next) = self.state.lock().pending.front().cloned() else {
+                break;
+            };
+            let too_old = now.saturating_sub(next.timestamp) > MAX_SCROBBLE_AGE.as_secs();
+            if too_old {
+                log::warn!(
+                    "dropping scrobble of {} - {}, too old for Last.fm",
+                    next.artist,
+                    next.title
+                );
+            } else if let Err(err) = send(&next) {
+                if is_rejected_scrobble(&err) {
+                    log::warn!(
+                        "dropping scrobble of {} - {}, rejected by Last.fm: {err}",
+                        next.artist,
+                        next.title
+                    );
+                } else {
+                    result = Err(err);
+                    break;
+                }
+            } else {
+                sent += 1;
+            }
+            let mut state = self.state.lock();
+            if state.pending.front() == Some(&next) {
+                state.pending.pop_front();
+            }
+        }
+
+        let mut state = self.state.lock();
+        state.flushing = false;
+        match &result {
+            Ok(()) => {
+                state.failures = 0;
+                state.retry_at = None;
+            }
+            Err(_) => {
+                state.failures = state.failures.saturating_add(1);
+                state.retry_at = Some(Instant::now() + retry_backoff(state.failures));
+            }
+        }
+        self.save(&state.pending)?;
+        result.map(|()| sent)
+    }
+
+    fn save(&self, pending: &VecDeque<PendingScrobble>) -> Result<(), Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        for scrobble in pending {
+            writeln!(writer, "{}", serde_json::to_string(scrobble)?)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The latest track of a Last.fm friend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FriendTrack {
+    pub friend: String,
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub now_playing: bool,
+    /// Unix timestamp (in seconds) of the scrobble, `None` while playing.
+    pub played_at: Option<u64>,
+}
+
+/// Calls of the Last.fm API that `rustfm_scrobble` does not cover.  Requests
+/// are spaced to stay within the rate limit of Last.fm.
+pub struct LastFmApi {
+    agent: ureq::Agent,
+    last_request: Mutex<Option<Instant>>,
+    friends_cache: Mutex<Option<CachedActivity>>,
+}
+
+struct CachedActivity {
+    user: String,
+    fetched: Instant,
+    tracks: Vec<FriendTrack>,
+}
+
+impl LastFmApi {
+    pub fn new(proxy_url: Option<&str>) -> Self {
+        Self {
+            agent: default_ureq_agent_builder(proxy_url).build().into(),
+            last_request: Mutex::new(None),
+            friends_cache: Mutex::new(None),
+        }
+    }
+
+    pub fn install_as_global(self) {
+        GLOBAL_LASTFM_API
+            .set(Arc::new(self))
+            .map_err(|_| "Cannot install more than once")
+            .unwrap()
+    }
+
+    pub fn global() -> Arc<Self> {
+        GLOBAL_LASTFM_API.get().unwrap().clone()
+    }
+
+    /// Latest track of each friend of `user`, the ones playing right now
+    /// first, then the most recent.  Friends without any scrobble are left
+    /// out.  The result is cached for a few minutes.
+    pub fn friends_tracks(&self, api_key: &str, user: &str) -> Result<Vec<FriendTrack>, Error> {
+        if let Some(cached) = self.friends_cache.lock().as_ref() {
+            if cached.user == user && cached.fetched.elapsed() < FRIENDS_CACHE_TTL {
+                return Ok(cached.tracks.clone());
+            }
+        }
+
+        let body = self.call(
+            api_key,
+            "user.getfriends",
+            &[("user", user), ("limit", &MAX_FRIENDS.to_string())],
+        )?;
+        let mut tracks = Vec::new();
+        for friend in parse_friends(&body)?.into_iter().take(MAX_FRIENDS) {
+            let body = self.call(
+                api_key,
+                "user.getrecenttracks",
+                &[("user", &friend), ("limit", "1")],
+            );
+            // A friend with a private history should not hide the others.
+            match body.and_then(|body| parse_recent_track(&friend, &body)) {
+                Ok(Some(track)) => tracks.push(track),
+                Ok(None) => {}
+                Err(err) => log::warn!("failed to get recent tracks of {friend}: {err}"),
+            }
+        }
+        tracks.sort_by(|a, b| {
+            b.now_playing
+                .cmp(&a.now_playing)
+                .then(b.played_at.cmp(&a.played_at))
+        });
+
+        *self.friends_cache.lock() = Some(CachedActivity {
+            user: user.to_string(),
+            fetched: Instant::now(),
+            tracks: tracks.clone(),
+        });
+        Ok(tracks)
+    }
+
+    /// Whether `user` loved the track, according to `track.getInfo`.
+    pub fn is_loved(
+        &self,
+        api_key: &str,
+        user: &str,
+        artist: &str,
+        title: &str,
+    ) -> Result<bool, Error> {
+        let body = self.call(
+            api_key,
+            "track.getinfo",
+            &[
+                ("artist", artist),
+                ("track", title),
+                ("username", user),
+                ("autocorrect", "1"),
+            ],
+        )?;
+        parse_user_loved(&body)
+    }
+
+    /// Love or unlove a track on behalf of the user of `session_key`.
+    pub fn set_loved(
+        &self,
+        api_key: &str,
+        api_secret: &str,
+        session_key: &str,
+        artist: &str,
+        title: &str,
+        loved: bool,
+    ) -> Result<(), Error> {
+        let method = if loved { "track.love" } else { "track.unlove" };
+        let mut params = vec![
+            ("api_key", api_key),
+            ("artist", artist),
+            ("method", method),
+            ("sk", session_key),
+            ("track", title),
+        ];
+        let signature = api_signature(&params, api_secret);
+        params.push(("api_sig", &signature));
+        params.push(("format", "json"));
+
+        self.wait_for_turn();
+        let body = match self.agent.post(API_ROOT).send_form(params) {
+            Ok(response) => response.into_body().read_to_string()?,
+            Err(ureq::Error::StatusCode(code)) => return Err(Error::HttpStatus(code)),
+            Err(err) => return Err(err.into()),
+        };
+        check_api_error(&body)
+    }
+
+    fn wait_for_turn(&self) {
+        let mut last_request = self.last_request.lock();
+        if let Some(wait) =
+            last_request.and_then(|last| MIN_REQUEST_INTERVAL.checked_sub(last.elapsed()))
+        {
+            thread::sleep(wait);
+        }
+        *last_request = Some(Instant::now());
+    }
+
+    fn call(&self, api_key: &str, method: &str, params: &[(&str, &str)]) -> Result<String, Error> {
+        self.wait_for_turn();
+        let mut request = self
+            .agent
+            .get(API_ROOT)
+            .query("method", method)
+            .query("api_key", api_key)
+            .query("format", "json");
+        for (name, value) in params {
+            request = request.query(*name, *value);
+        }
+        match request.call() {
+            Ok(response) => Ok(response.into_body().read_to_string()?),
+            Err(ureq::Error::StatusCode(code)) => Err(Error::HttpStatus(code)),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Last.fm sends a single item as an object instead of an array.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    Many(Vec<T>),
+    One(T),
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::Many(items) => items,
+            Self::One(item) => vec![item],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    error: u32,
+    message: String,
+}
+
+/// Fail with the error Last.fm reported in `body`, if any.
+fn check_api_error(body: &str) -> Result<(), Error> {
+    match serde_json::from_str::<ApiError>(body) {
+        Ok(err) => {
+            log::warn!("Last.fm error {}: {}", err.error, err.message);
+            Err(Error::UnexpectedResponse)
+        }
+        Err(_) => Ok(()),
+    }
+}
+
+/// Signature of an authenticated call: the MD5 digest of the parameters,
+/// sorted by name, followed by the API secret.
+pub fn api_signature(params: &[(&str, &str)], api_secret: &str) -> String {
+    let mut params = params.to_vec();
+    params.sort_by_key(|&(name, _)| name);
+    let mut text = String::new();
+    for (name, value) in params {
+        text.push_str(name);
+        text.push_str(value);
+    }
+    text.push_str(api_secret);
+    format!("{:x}", md5::compute(text))
+}
+
+/// Whether the user of a `track.getInfo` request loved the track.
+pub fn parse_user_loved(body: &str) -> Result<bool, Error> {
+    #[derive(Deserialize)]
+    struct Response {
+        track: Track,
+    }
+    #[derive(Deserialize)]
+    struct Track {
+        #[serde(default)]
+        userloved: Option<String>,
+    }
+
+    check_api_error(body)?;
+    let response: Response = serde_json::from_str(body)?;
+    Ok(response.track.userloved.is_some_and(|loved| loved == "1"))
+}
+
+/// Names of the friends in a `user.getFriends` response.
+pub fn parse_friends(body: &str) -> Result<Vec<String>, Error> {
+    #[derive(Deserialize)]
+    struct Response {
+        friends: Friends,
+    }
+    #[derive(Deserialize)]
+    struct Friends {
+        #[serde(default)]
+        user: Option<OneOrMany<Friend>>,
+    }
+    #[derive(Deserialize)]
+    struct Friend {
+        name: String,
+    }
+
+    check_api_error(body)?;
+    let response: Response = serde_json::from_str(body)?;
+    Ok(response
+        .friends
+        .user
+        .map(OneOrMany::into_vec)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|friend| friend.name)
+        .collect())
+}
+
+/// Latest track in a `user.getRecentTracks` response of `friend`.
+pub fn parse_recent_track(friend: &str, body: &str) -> Result<Option<FriendTrack>, Error> {
+    #[derive(Deserialize)]
+    struct Response {
+        recenttracks: RecentTracks,
+    }
+    #[derive(Deserialize)]
+    struct RecentTracks {
+        #[serde(default)]
+        track: Option<OneOrMany<Track>>,
+    }
+    #[derive(Deserialize)]
+    struct Track {
+        name: String,
+        artist: Text,
+        album: Option<Text>,
+        #[serde(rename = "@attr")]
+        attr: Option<Attr>,
+        date: Option<Date>,
+    }
+    #[derive(Deserialize)]
+    struct Text {
+        #[serde(rename = "#text")]
+        text: String,
+    }
+    #[derive(Deserialize)]
+    struct Attr {
+        nowplaying: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct Date {
+        uts: String,
+    }
+
+    check_api_error(body)?;
+    let response: Response = serde_json::from_str(body)?;
+    let Some(track) = response
+        .recenttracks
+        .track
+        .and_then(|tracks| tracks.into_vec().into_iter().next())
+    else {
+        return Ok(None);
+    };
+    Ok(Some(FriendTrack {
+        friend: friend.to_string(),
+        artist: track.artist.text,
+        title: track.name,
+        album: track
+            .album
+            .map(|album| album.text)
+            .filter(|album| !album.is_empty()),
+        now_playing: track
+            .attr
+            .and_then(|attr| attr.nowplaying)
+            .is_some_and(|playing| playing == "true"),
+        played_at: track.date.and_then(|date| date.uts.parse().ok()),
+    }))
+}
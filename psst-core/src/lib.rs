@@ -1,3 +1,10 @@
+//! Spotify session, playback and audio processing of Psst.
+//!
+//! Everything that needs sockets, threads or native libraries is behind the
+//! `native` feature, on by default.  Without it, only the DSP, the resampler,
+//! the errors and the data models like `item_id` are built, and they compile
+//! for `wasm32-unknown-unknown`, for tools running in the browser.
+
 #![allow(clippy::new_without_default)]
 
 use git_version::git_version;
@@ -10,18 +17,35 @@ pub const BUILD_VERSION: &str = match option_env!("RELEASE_VERSION") {
 pub const BUILD_TIME: &str = include!(concat!(env!("OUT_DIR"), "/build-time.txt"));
 pub const REMOTE_URL: &str = include!(concat!(env!("OUT_DIR"), "/remote-url.txt"));
 
-pub mod actor;
 pub mod audio;
+pub mod error;
+pub mod history;
+pub mod item_id;
+
+#[cfg(feature = "native")]
+pub mod actor;
+#[cfg(feature = "native")]
 pub mod cache;
+#[cfg(feature = "native")]
 pub mod cdn;
+#[cfg(feature = "native")]
 pub mod connection;
-pub mod error;
-pub mod item_id;
+#[cfg(feature = "native")]
 pub mod lastfm;
+#[cfg(feature = "native")]
+pub mod local;
+#[cfg(feature = "native")]
 pub mod metadata;
+#[cfg(feature = "native")]
 pub mod oauth;
+#[cfg(feature = "native")]
+pub mod offline;
+#[cfg(feature = "native")]
 pub mod player;
+#[cfg(feature = "native")]
 pub mod session;
+#[cfg(feature = "native")]
 pub mod util;
 
+#[cfg(feature = "native")]
 pub use psst_protocol as protocol;
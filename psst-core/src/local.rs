@@ -0,0 +1,104 @@
+use std::{
+    cmp::Ordering,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{audio::probe::TrackProbe, error::Error, item_id::ItemId};
+
+/// File extensions of the audio files we can play.
+pub const AUDIO_EXTENSIONS: &[&str] = &["mp3", "flac", "ogg", "oga"];
+
+/// An audio file found in one of the local music folders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalTrack {
+    pub path: PathBuf,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track_number: Option<u32>,
+    pub duration: Option<Duration>,
+}
+
+impl LocalTrack {
+    /// Read the tags of the file at `path`.  Files without a title tag are
+    /// named after the file.
+    pub fn load(path: PathBuf) -> Result<Self, Error> {
+        let probe = TrackProbe::new(&path)?;
+        let tags = probe.tags;
+        let title = tags.title.unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+        Ok(Self {
+            path,
+            title,
+            artist: tags.artist,
+            album: tags.album,
+            track_number: tags.track_number,
+            duration: probe.duration,
+        })
+    }
+
+    pub fn item_id(&self) -> ItemId {
+        ItemId::from_local(self.path.clone())
+    }
+}
+
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            AUDIO_EXTENSIONS
+                .iter()
+                .any(|supported| ext.eq_ignore_ascii_case(supported))
+        })
+}
+
+/// Recursively collect the audio files in `folders`, ordered by artist, album
+/// and track number.  Files and folders that cannot be read are skipped.
+pub fn scan(folders: &[PathBuf]) -> Vec<LocalTrack> {
+    let mut tracks = Vec::new();
+    for folder in folders {
+        scan_folder(folder, &mut tracks);
+    }
+    tracks.sort_by(compare_tracks);
+    tracks.dedup_by(|a, b| a.path == b.path);
+    tracks
+}
+
+fn scan_folder(folder: &Path, tracks: &mut Vec<LocalTrack>) {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::warn!("failed to read local folder {folder:?}: {err}");
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Symlinks are not followed, so that link cycles cannot trap the scan.
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            scan_folder(&path, tracks);
+        } else if file_type.is_file() && is_audio_file(&path) {
+            match LocalTrack::load(path) {
+                Ok(track) => tracks.push(track),
+                Err(err) => log::warn!("skipping local file {:?}: {err}", entry.path()),
+            }
+        }
+    }
+}
+
+fn compare_tracks(a: &LocalTrack, b: &LocalTrack) -> Ordering {
+    a.artist
+        .cmp(&b.artist)
+        .then_with(|| a.album.cmp(&b.album))
+        .then_with(|| a.track_number.cmp(&b.track_number))
+        .then_with(|| a.title.cmp(&b.title))
+        .then_with(|| a.path.cmp(&b.path))
+}
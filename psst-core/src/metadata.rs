@@ -5,15 +5,15 @@ use quick_protobuf::MessageRead;
 use crate::{
     error::Error,
     item_id::{FileId, ItemId, ItemIdType},
-    player::file::{AudioFormat, MediaFile, MediaPath},
+    player::file::{AudioFormat, MediaPath, QualityTier},
     protocol::metadata::{AudioFile, Episode, Restriction, Track},
-    session::SessionService,
+    session::{reconnect, SessionService},
 };
 
 pub trait Fetch: MessageRead<'static> {
     fn uri(id: ItemId) -> String;
     fn fetch(session: &SessionService, id: ItemId) -> Result<Self, Error> {
-        session.connected()?.get_mercury_protobuf(Self::uri(id))
+        reconnect::with_retry(session, |handle| handle.get_mercury_protobuf(Self::uri(id)))
     }
 }
 
@@ -32,7 +32,7 @@ impl Fetch for Episode {
 pub trait ToMediaPath {
     fn is_restricted_in_region(&self, country: &str) -> bool;
     fn find_allowed_alternative(&self, country: &str) -> Option<ItemId>;
-    fn to_media_path(&self, preferred_bitrate: usize) -> Option<MediaPath>;
+    fn to_media_path(&self, quality: QualityTier) -> Option<MediaPath>;
 }
 
 impl ToMediaPath for Track {
@@ -50,12 +50,15 @@ impl ToMediaPath for Track {
         ItemId::from_raw(alt_track.gid.as_ref()?, ItemIdType::Track)
     }
 
-    fn to_media_path(&self, preferred_bitrate: usize) -> Option<MediaPath> {
-        let file = select_preferred_file(&self.file, preferred_bitrate)?;
+    fn to_media_path(&self, quality: QualityTier) -> Option<MediaPath> {
+        let file = select_preferred_file(&self.file, quality)?;
+        let format = file.format?;
         Some(MediaPath {
             item_id: ItemId::from_raw(self.gid.as_ref()?, ItemIdType::Track)?,
             file_id: FileId::from_raw(file.file_id.as_ref()?)?,
-            file_format: AudioFormat::from_protocol(file.format?),
+            file_format: AudioFormat::from_protocol(format),
+            quality: QualityTier::of_format(format),
+            bitrate: AudioFormat::bitrate_of(format),
             duration: Duration::from_millis(self.duration? as u64),
         })
     }
@@ -72,25 +75,26 @@ impl ToMediaPath for Episode {
         None
     }
 
-    fn to_media_path(&self, preferred_bitrate: usize) -> Option<MediaPath> {
-        let file = select_preferred_file(&self.file, preferred_bitrate)?;
+    fn to_media_path(&self, quality: QualityTier) -> Option<MediaPath> {
+        let file = select_preferred_file(&self.file, quality)?;
+        let format = file.format?;
         Some(MediaPath {
             item_id: ItemId::from_raw(self.gid.as_ref()?, ItemIdType::Podcast)?,
             file_id: FileId::from_raw(file.file_id.as_ref()?)?,
-            file_format: AudioFormat::from_protocol(file.format?),
+            file_format: AudioFormat::from_protocol(format),
+            quality: QualityTier::of_format(format),
+            bitrate: AudioFormat::bitrate_of(format),
             duration: Duration::from_millis(self.duration? as u64),
         })
     }
 }
 
-fn select_preferred_file(files: &[AudioFile], preferred_bitrate: usize) -> Option<&AudioFile> {
-    MediaFile::supported_audio_formats_for_bitrate(preferred_bitrate)
-        .iter()
-        .find_map(|&preferred_format| {
-            files
-                .iter()
-                .find(|file| file.format == Some(preferred_format))
-        })
+fn select_preferred_file(files: &[AudioFile], quality: QualityTier) -> Option<&AudioFile> {
+    quality.preferred_formats().find_map(|preferred_format| {
+        files
+            .iter()
+            .find(|file| file.format == Some(preferred_format))
+    })
 }
 
 fn is_restricted_in_region(restriction: &Restriction, country: &str) -> bool {
@@ -201,9 +201,28 @@ fn get_scopes() -> Vec<Scope> {
         .collect()
 }
 
+/// Lifetime assumed for an access token when Spotify does not report one.
+pub const DEFAULT_TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// Tokens returned by a refresh.
+#[derive(Clone, Debug)]
+pub struct OAuthTokens {
+    pub access_token: String,
+    /// Only present if Spotify rotated the refresh token.
+    pub refresh_token: Option<String>,
+    pub expires_in: Duration,
+}
+
 /// Refresh an access token using a stored refresh token. Returns the new access token and
 /// an optional new refresh token if Spotify rotates it.
 pub fn refresh_access_token(refresh_token: &str) -> Result<(String, Option<String>), Error> {
+    let tokens = refresh_tokens(refresh_token)?;
+    Ok((tokens.access_token, tokens.refresh_token))
+}
+
+/// Like `refresh_access_token`, but also tells how long the new access token
+/// is valid for.
+pub fn refresh_tokens(refresh_token: &str) -> Result<OAuthTokens, Error> {
     // Reuse the same OAuth client configuration; redirect URI is irrelevant for refresh flow.
     let client = create_spotify_oauth_client(0);
 
@@ -212,9 +231,13 @@ pub fn refresh_access_token(refresh_token: &str) -> Result<(String, Option<Strin
         .request(http_client)
         .map_err(|e| Error::OAuthError(format!("Failed to refresh token: {e}")))?;
 
-    let access = token_response.access_token().secret().to_string();
-    let refresh = token_response
-        .refresh_token()
-        .map(|t| t.secret().to_string());
-    Ok((access, refresh))
+    Ok(OAuthTokens {
+        access_token: token_response.access_token().secret().to_string(),
+        refresh_token: token_response
+            .refresh_token()
+            .map(|t| t.secret().to_string()),
+        expires_in: token_response
+            .expires_in()
+            .unwrap_or(DEFAULT_TOKEN_LIFETIME),
+    })
 }
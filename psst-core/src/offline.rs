@@ -0,0 +1,172 @@
+//! Downloads of whole playlists into the audio cache, so that they keep
+//! playing without a connection.  Playlists are downloaded one at a time on a
+//! background thread, reporting their progress as `SyncEvent`s.  Files cut
+//! off by a restart or the network are continued where they stopped.
+
+use std::{collections::HashSet, sync::Arc, thread, time::Duration};
+
+use crossbeam_channel::{unbounded, Sender};
+use parking_lot::Mutex;
+
+use crate::{
+    cache::CacheHandle,
+    cdn::CdnHandle,
+    item_id::{ItemId, ItemIdType},
+    player::{file::QualityTier, item},
+    session::SessionService,
+};
+
+/// Partial downloads not continued for this long are removed, see
+/// `Cache::remove_stale_downloads`.
+const STALE_DOWNLOAD_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// State of the download of a playlist marked for offline listening.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncStatus {
+    /// Everything in the current version of the playlist is downloaded.
+    Synced,
+    /// A download is running, `done` of `total` items are finished.
+    Syncing { done: usize, total: usize },
+    /// The playlist changed since it was last downloaded, or the download did
+    /// not finish.
+    OutOfDate,
+}
+
+impl SyncStatus {
+    /// Status of a playlist currently at `snapshot`, last downloaded
+    /// completely at `synced_snapshot`, with `progress` of the running
+    /// download.  An empty `snapshot` means the version is not known.
+    pub fn of(
+        synced_snapshot: Option<&str>,
+        snapshot: &str,
+        progress: Option<(usize, usize)>,
+    ) -> Self {
+        match (progress, synced_snapshot) {
+            (Some((done, total)), _) => Self::Syncing { done, total },
+            (None, Some(synced)) if snapshot.is_empty() || synced == snapshot => Self::Synced,
+            (None, _) => Self::OutOfDate,
+        }
+    }
+
+    /// Finished part of a running download, from 0 to 100.
+    pub fn percent(self) -> Option<usize> {
+        match self {
+            Self::Syncing { total: 0, .. } => Some(0),
+            Self::Syncing { done, total } => Some(done.min(total) * 100 / total),
+            _ => None,
+        }
+    }
+}
+
+/// Version of a playlist to download.
+#[derive(Clone, Debug)]
+pub struct SyncJob {
+    pub playlist_id: String,
+    pub snapshot_id: String,
+    pub items: Vec<ItemId>,
+}
+
+#[derive(Clone, Debug)]
+pub enum SyncEvent {
+    Progress {
+        playlist_id: String,
+        done: usize,
+        total: usize,
+    },
+    /// All items of the job were tried, `failed` of them could not be
+    /// downloaded.
+    Finished {
+        playlist_id: String,
+        snapshot_id: String,
+        failed: usize,
+    },
+    /// The job was cancelled before it finished.
+    Cancelled { playlist_id: String },
+}
+
+/// Downloads the queued playlists on a background thread, which exits after
+/// the running job once every clone of the manager is dropped.
+#[derive(Clone)]
+pub struct DownloadManager {
+    sender: Sender<SyncJob>,
+    cancelled: Arc<Mutex<HashSet<String>>>,
+}
+
+impl DownloadManager {
+    pub fn new(
+        session: SessionService,
+        cdn: CdnHandle,
+        cache: CacheHandle,
+        quality: QualityTier,
+        on_event: impl Fn(SyncEvent) + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = unbounded::<SyncJob>();
+        let cancelled = Arc::new(Mutex::new(HashSet::new()));
+        let spawned = thread::Builder::new()
+            .name("offline_downloads".to_string())
+            .spawn({
+                let cancelled = Arc::clone(&cancelled);
+                move || {
+                    match cache.remove_stale_downloads(STALE_DOWNLOAD_AGE) {
+                        Ok(0) => {}
+                        Ok(bytes) => log::info!("removed {bytes} bytes of stale downloads"),
+                        Err(err) => log::warn!("failed to remove stale downloads: {err}"),
+                    }
+                    for job in receiver {
+                        let is_cancelled = || cancelled.lock().remove(&job.playlist_id);
+                        let total = job.items.len();
+                        let mut failed = 0;
+                        let mut finished = true;
+                        for (index, &item_id) in job.items.iter().enumerate() {
+                            if is_cancelled() {
+                                finished = false;
+                                break;
+                            }
+                            on_event(SyncEvent::Progress {
+                                playlist_id: job.playlist_id.clone(),
+                                done: index,
+                                total,
+                            });
+                            // Local files are played from the disk already.
+                            if !matches!(item_id.id_type, ItemIdType::Track | ItemIdType::Podcast) {
+                                continue;
+                            }
+                            if let Err(err) =
+                                item::save_to_cache(item_id, &session, &cdn, &cache, quality)
+                            {
+                                log::warn!("failed to download {item_id:?}: {err}");
+                                failed += 1;
+                            }
+                        }
+                        if finished && !is_cancelled() {
+                            on_event(SyncEvent::Finished {
+                                playlist_id: job.playlist_id,
+                                snapshot_id: job.snapshot_id,
+                                failed,
+                            });
+                        } else {
+                            on_event(SyncEvent::Cancelled {
+                                playlist_id: job.playlist_id,
+                            });
+                        }
+                    }
+                }
+            });
+        if let Err(err) = spawned {
+            log::error!("failed to spawn the offline download thread: {err}");
+        }
+        Self { sender, cancelled }
+    }
+
+    /// Queue `job` after the downloads already running.
+    pub fn sync(&self, job: SyncJob) {
+        self.cancelled.lock().remove(&job.playlist_id);
+        let _ = self.sender.send(job);
+    }
+
+    /// Stop downloading the playlist, the items already in the cache stay
+    /// there.
+    pub fn cancel(&self, playlist_id: &str) {
+        self.cancelled.lock().insert(playlist_id.to_string());
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::item_id::FileId;
+
+use super::file::QualityTier;
+
+/// Weight of the newest measurement in the running estimate.
+const SMOOTHING: f64 = 0.3;
+
+/// Downloads shorter than this are mostly request latency, and would drag the
+/// estimate down.
+const MIN_MEASURED_LENGTH: u64 = 16 * 1024;
+
+/// How many times the estimate has to exceed the bitrate of a tier for the
+/// tier to be picked, leaving room for fluctuations and other traffic.
+const HEADROOM: u64 = 2;
+
+/// Tier picked before anything was measured.
+const INITIAL_TIER: QualityTier = QualityTier::Normal;
+
+/// Running estimate of the CDN throughput, measured on the chunks of streamed
+/// files.  Chunks are downloaded in parallel, so the estimate is the throughput
+/// of a single connection and errs on the low side.
+#[derive(Default)]
+pub struct BandwidthMeter {
+    state: Mutex<MeterState>,
+}
+
+#[derive(Default)]
+struct MeterState {
+    /// In bytes per second.
+    estimate: Option<f64>,
+    /// File that ran out of downloaded data during playback.
+    rebuffered: Option<FileId>,
+    /// File whose reader is waiting for data, until the next chunk of it
+    /// arrives.
+    buffering: Option<FileId>,
+}
+
+impl BandwidthMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Account a download of `length` bytes that took `elapsed`.
+    pub fn record(&self, length: u64, elapsed: Duration) {
+        if length < MIN_MEASURED_LENGTH || elapsed.is_zero() {
+            return;
+        }
+        let measured = length as f64 / elapsed.as_secs_f64();
+        let mut state = self.state.lock();
+        state.estimate = Some(match state.estimate {
+            Some(estimate) => estimate + SMOOTHING * (measured - estimate),
+            None => measured,
+        });
+    }
+
+    /// Estimated throughput in bits per second, `None` until something was
+    /// measured.
+    pub fn estimate(&self) -> Option<u64> {
+        self.state.lock().estimate.map(|bytes| (bytes * 8.0) as u64)
+    }
+
+    /// The reader of `file_id` had to wait for data to be downloaded.
+    pub fn record_rebuffer(&self, file_id: FileId) {
+        let mut state = self.state.lock();
+        state.rebuffered = Some(file_id);
+        state.buffering = Some(file_id);
+    }
+
+    /// The reader of `file_id` waits for the data at a position it seeked to.
+    /// Unlike a rebuffer, this says nothing about the bandwidth.
+    pub fn record_seek(&self, file_id: FileId) {
+        self.state.lock().buffering = Some(file_id);
+    }
+
+    /// A chunk of `file_id` was downloaded, which wakes up its waiting reader.
+    pub fn record_chunk(&self, file_id: FileId) {
+        let mut state = self.state.lock();
+        if state.buffering == Some(file_id) {
+            state.buffering = None;
+        }
+    }
+
+    /// Returns true if the reader of `file_id` is waiting for data.
+    pub fn is_buffering(&self, file_id: FileId) -> bool {
+        self.state.lock().buffering == Some(file_id)
+    }
+
+    /// Returns true if `file_id` rebuffered since the last call.
+    pub fn take_rebuffer(&self, file_id: FileId) -> bool {
+        let mut state = self.state.lock();
+        if state.rebuffered == Some(file_id) {
+            state.rebuffered = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Highest lossy tier up to `ceiling` the `estimate` (in bits per second) can
+/// sustain.  Lossless files are only played when asked for explicitly.
+pub fn adaptive_tier(estimate: Option<u64>, ceiling: QualityTier) -> QualityTier {
+    let Some(estimate) = estimate else {
+        return INITIAL_TIER.min(ceiling);
+    };
+    QualityTier::ALL
+        .into_iter()
+        .filter(|&tier| tier <= ceiling)
+        .filter(|tier| {
+            tier.bitrate()
+                .is_some_and(|kbits| u64::from(kbits) * 1000 * HEADROOM <= estimate)
+        })
+        .max()
+        .unwrap_or(QualityTier::Low)
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+/// Segments quieter than this are considered silence when looking for the
+/// audible end of a track.
+const SILENCE_LOUDNESS_DB: f64 = -40.0;
+
+/// Loudness of a short segment of a track, as reported by an audio analysis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoudnessSegment {
+    pub start: Duration,
+    pub duration: Duration,
+    pub loudness_max: f64,
+}
+
+/// The parts of a track analysis needed to place the fades.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrackAnalysis {
+    /// End of the quiet intro of the track.
+    pub end_of_fade_in: Duration,
+    /// Start of the quiet outro of the track.
+    pub start_of_fade_out: Duration,
+    /// End of the last segment that is not silent.
+    pub end_of_audio: Option<Duration>,
+}
+
+impl TrackAnalysis {
+    pub fn new(
+        end_of_fade_in: Duration,
+        start_of_fade_out: Duration,
+        segments: impl IntoIterator<Item = LoudnessSegment>,
+    ) -> Self {
+        let end_of_audio = segments
+            .into_iter()
+            .filter(|segment| segment.loudness_max > SILENCE_LOUDNESS_DB)
+            .map(|segment| segment.start + segment.duration)
+            .max();
+        Self {
+            end_of_fade_in,
+            start_of_fade_out,
+            end_of_audio,
+        }
+    }
+}
+
+/// Where a track fades in and out, and where its playback ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FadePoints {
+    pub fade_in_end: Duration,
+    pub fade_out_start: Duration,
+    pub end: Duration,
+}
+
+impl FadePoints {
+    /// Fades of a fixed length at both ends of the track.
+    pub fn fixed(duration: Duration, fade: Duration) -> Self {
+        let fade = fade.min(duration / 2);
+        Self {
+            fade_in_end: fade,
+            fade_out_start: duration - fade,
+            end: duration,
+        }
+    }
+
+    /// Fades that stay inside the quiet intro and outro of the track, so that
+    /// vocals and other loud parts are never faded.  Trailing silence is cut
+    /// off.  No fade is longer than `fade`.
+    pub fn from_analysis(analysis: &TrackAnalysis, duration: Duration, fade: Duration) -> Self {
+        let end = analysis.end_of_audio.unwrap_or(duration).min(duration);
+        let fade_in_end = analysis.end_of_fade_in.min(fade).min(end);
+        let fade_out_start = analysis
+            .start_of_fade_out
+            .max(end.saturating_sub(fade))
+            .clamp(fade_in_end, end);
+        Self {
+            fade_in_end,
+            fade_out_start,
+            end,
+        }
+    }
+
+    /// Convert the points into positions in an interleaved sample stream.
+    pub fn envelope(&self, sample_rate: u32, channels: usize) -> FadeEnvelope {
+        let to_samples =
+            |time: Duration| (time.as_secs_f64() * sample_rate as f64) as u64 * channels as u64;
+        FadeEnvelope {
+            fade_in_end: to_samples(self.fade_in_end),
+            fade_out_start: to_samples(self.fade_out_start),
+            end: to_samples(self.end),
+        }
+    }
+}
+
+/// `FadePoints` expressed in samples, used while playing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FadeEnvelope {
+    fade_in_end: u64,
+    fade_out_start: u64,
+    end: u64,
+}
+
+impl FadeEnvelope {
+    /// Sample at which the fade-out starts and the next track can start on top.
+    pub fn fade_out_start(&self) -> u64 {
+        self.fade_out_start
+    }
+
+    /// Sample after which playback should stop.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Gain to apply to the sample at `position`.
+    pub fn gain(&self, position: u64) -> f32 {
+        if position >= self.end {
+            0.0
+        } else if position < self.fade_in_end {
+            position as f32 / self.fade_in_end as f32
+        } else if position > self.fade_out_start {
+            (self.end - position) as f32 / (self.end - self.fade_out_start) as f32
+        } else {
+            1.0
+        }
+    }
+}
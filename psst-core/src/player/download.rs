@@ -0,0 +1,260 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Seek, SeekFrom},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::{
+    cache::CacheHandle,
+    cdn::{CdnHandle, CdnUrl, MAX_PARALLEL_REQUESTS},
+    error::Error,
+    item_id::FileId,
+};
+
+use super::storage::{StreamStorage, StreamWriter};
+
+/// Length of the chunks the requested ranges are split into.  Each chunk is
+/// one request to the CDN.
+pub const CHUNK_LENGTH: u64 = 64 * 1024;
+
+/// Chunks of a file waiting for a download worker, in the order they are
+/// fetched.
+#[derive(Debug, Default)]
+pub struct DownloadQueue {
+    pending: VecDeque<(u64, u64)>,
+    closed: bool,
+}
+
+impl DownloadQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the range `offset..offset + length`, split into chunks of at
+    /// most `CHUNK_LENGTH` bytes.
+    pub fn push(&mut self, offset: u64, length: u64) {
+        let end = offset + length;
+        let mut start = offset;
+        while start < end {
+            let chunk_length = CHUNK_LENGTH.min(end - start);
+            self.pending.push_back((start, chunk_length));
+            start += chunk_length;
+        }
+    }
+
+    /// Move the chunks ending after `offset` to the front of the queue, in
+    /// the order of the file.  A reader blocked at `offset`, e.g. after a
+    /// seek, is then served before the prefetch of the old position.
+    pub fn prioritize(&mut self, offset: u64) {
+        let (mut urgent, rest): (Vec<_>, Vec<_>) = self
+            .pending
+            .drain(..)
+            .partition(|&(start, length)| start + length > offset);
+        urgent.sort_by_key(|&(start, _)| start);
+        self.pending = urgent.into_iter().chain(rest).collect();
+    }
+
+    pub fn pop(&mut self) -> Option<(u64, u64)> {
+        self.pending.pop_front()
+    }
+
+    /// Drop the pending chunks, and let the workers stop.
+    pub fn close(&mut self) {
+        self.pending.clear();
+        self.closed = true;
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+
+/// Downloads the chunks of a streamed file with a fixed number of worker
+/// threads.  The workers share the agent of the CDN, so consecutive requests
+/// reuse its pooled connections instead of opening new ones.
+pub struct Downloader {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+struct Shared {
+    queue: Mutex<DownloadQueue>,
+    queued: Condvar,
+    /// `None` after a failed request, to resolve a new URL for the next one.
+    url: Mutex<Option<CdnUrl>>,
+    cdn: CdnHandle,
+    cache: CacheHandle,
+    file_id: FileId,
+    file_path: PathBuf,
+    saved_to_cache: AtomicBool,
+}
+
+impl Downloader {
+    pub fn new(
+        parallelism: usize,
+        url: CdnUrl,
+        cdn: CdnHandle,
+        cache: CacheHandle,
+        file_id: FileId,
+        storage: &StreamStorage,
+    ) -> Result<Self, Error> {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(DownloadQueue::new()),
+            queued: Condvar::new(),
+            url: Mutex::new(Some(url)),
+            cdn,
+            cache,
+            file_id,
+            file_path: storage.path().to_path_buf(),
+            saved_to_cache: AtomicBool::new(false),
+        });
+        let mut workers = Vec::new();
+        for index in 0..parallelism.clamp(1, MAX_PARALLEL_REQUESTS) {
+            let writer = storage.writer()?;
+            let handle = thread::Builder::new()
+                .name(format!("cdn-{}-{index}", file_id.to_base16()))
+                .spawn({
+                    let shared = shared.clone();
+                    move || shared.work(writer)
+                })?;
+            workers.push(handle);
+        }
+        Ok(Self { shared, workers })
+    }
+
+    pub fn request(&self, offset: u64, length: u64) {
+        self.shared.queue.lock().push(offset, length);
+        self.shared.queued.notify_all();
+    }
+
+    /// A reader seeked to `offset`, download the data there before anything
+    /// else.
+    pub fn seek(&self, offset: u64) {
+        self.shared.queue.lock().prioritize(offset);
+        self.shared.cdn.bandwidth().record_seek(self.shared.file_id);
+    }
+
+    /// A reader is blocked at `offset`, waiting for data.  Also counted as a
+    /// rebuffer by the bandwidth meter of the CDN.
+    pub fn prioritize(&self, offset: u64) {
+        self.shared.queue.lock().prioritize(offset);
+        self.shared
+            .cdn
+            .bandwidth()
+            .record_rebuffer(self.shared.file_id);
+    }
+}
+
+impl Drop for Downloader {
+    fn drop(&mut self) {
+        // Let the workers finish the chunks they are downloading, and wait
+        // for them.
+        self.shared.queue.lock().close();
+        self.shared.queued.notify_all();
+        for handle in self.workers.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Shared {
+    fn work(&self, mut writer: StreamWriter) {
+        while let Some((offset, length)) = self.next_chunk() {
+            self.download(&mut writer, offset, length);
+        }
+    }
+
+    fn next_chunk(&self) -> Option<(u64, u64)> {
+        let mut queue = self.queue.lock();
+        loop {
+            if queue.is_closed() {
+                return None;
+            }
+            if let Some(chunk) = queue.pop() {
+                return Some(chunk);
+            }
+            self.queued.wait(&mut queue);
+        }
+    }
+
+    fn url(&self) -> Result<String, Error> {
+        let mut url = self.url.lock();
+        match url.as_ref() {
+            Some(url) if !url.is_expired() => Ok(url.url.clone()),
+            _ => {
+                let resolved = self.cdn.resolve_audio_file_url(self.file_id)?;
+                let resolved_url = resolved.url.clone();
+                *url = Some(resolved);
+                Ok(resolved_url)
+            }
+        }
+    }
+
+    fn download(&self, writer: &mut StreamWriter, offset: u64, length: u64) {
+        let load = |writer: &mut StreamWriter| {
+            self.url()
+                .and_then(|url| load_range(writer, &self.cdn, &url, offset, length))
+        };
+        let mut result = load(writer);
+        if matches!(result, Err(Error::HttpStatus(401 | 403))) {
+            // The URL might have been revoked before its expiration, try once
+            // more with a new one.
+            self.url.lock().take();
+            result = load(writer);
+        }
+        match result {
+            Ok(()) => {
+                self.cdn.bandwidth().record_chunk(self.file_id);
+                // If the file is completely downloaded, copy it to cache.
+                if writer.is_complete()
+                    && !self.saved_to_cache.swap(true, Ordering::SeqCst)
+                    && !self.cache.audio_file_path(self.file_id).exists()
+                {
+                    if let Err(err) = self
+                        .cache
+                        .save_audio_file(self.file_id, self.file_path.clone())
+                    {
+                        log::warn!("failed to save audio file to cache: {err:?}");
+                    }
+                }
+            }
+            Err(err) => {
+                log::warn!("failed to download {}..{}: {err}", offset, offset + length);
+                self.url.lock().take();
+                writer.mark_as_not_requested(offset, length);
+            }
+        }
+    }
+}
+
+fn load_range(
+    writer: &mut StreamWriter,
+    cdn: &CdnHandle,
+    url: &str,
+    offset: u64,
+    length: u64,
+) -> Result<(), Error> {
+    log::trace!("downloading {}..{}", offset, offset + length);
+    let started = Instant::now();
+
+    // Download range of data from the CDN.  Block until we a have reader of the
+    // request body.
+    let (_total_length, mut reader) = cdn.fetch_file_range(url, offset, length)?;
+
+    // Pipe it into storage. Blocks until fully written, but readers sleeping on
+    // this file should be notified as soon as their offset is covered.  The
+    // body is read to the end, which returns the connection to the pool.
+    writer.seek(SeekFrom::Start(offset))?;
+    let copied = io::copy(&mut reader, writer)?;
+    cdn.bandwidth().record(copied, started.elapsed());
+
+    Ok(())
+}
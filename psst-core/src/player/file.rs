@@ -1,15 +1,7 @@
 use std::{
-    fs, io,
-    io::{Seek, SeekFrom},
-    path::PathBuf,
-    sync::Arc,
-    thread,
-    thread::JoinHandle,
-    time::Duration,
+    fmt, fs, io, iter, path::PathBuf, sync::Arc, thread, thread::JoinHandle, time::Duration,
 };
 
-use parking_lot::Mutex;
-
 use symphonia::core::codecs::CodecType;
 
 use crate::{
@@ -19,20 +11,29 @@ use crate::{
         normalize::NormalizationData,
     },
     cache::CacheHandle,
-    cdn::{CdnHandle, CdnUrl},
+    cdn::CdnHandle,
     error::Error,
     item_id::{FileId, ItemId},
     protocol::metadata::mod_AudioFile::Format,
     util::OffsetFile,
 };
 
-use super::storage::{StreamRequest, StreamStorage, StreamWriter};
+use super::{
+    download::Downloader,
+    storage::{StreamRequest, StreamStorage},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct MediaPath {
     pub item_id: ItemId,
     pub file_id: FileId,
     pub file_format: AudioFormat,
+    /// Tier of the file picked from the ones Spotify offers, `None` for local
+    /// files.
+    pub quality: Option<QualityTier>,
+    /// Nominal bitrate of the picked file in kbit/s, `None` for lossless and
+    /// local files.
+    pub bitrate: Option<u32>,
     pub duration: Duration,
 }
 
@@ -41,6 +42,7 @@ pub struct MediaPath {
 pub enum AudioFormat {
     Mp3,
     OggVorbis,
+    Flac,
     Unsupported,
 }
 
@@ -50,22 +52,111 @@ impl AudioFormat {
         match format {
             MP3_256 | MP3_320 | MP3_160 | MP3_96 | MP3_160_ENC => Self::Mp3,
             OGG_VORBIS_96 | OGG_VORBIS_160 | OGG_VORBIS_320 => Self::OggVorbis,
+            FLAC_FLAC | FLAC_FLAC_24BIT => Self::Flac,
             _ => Self::Unsupported,
         }
     }
 
+    /// Nominal bitrate of files in `format` in kbit/s, `None` for lossless
+    /// ones.
+    pub fn bitrate_of(format: Format) -> Option<u32> {
+        use Format::*;
+        match format {
+            OGG_VORBIS_96 | MP3_96 => Some(96),
+            OGG_VORBIS_160 | MP3_160 | MP3_160_ENC | AAC_160 => Some(160),
+            MP3_256 => Some(256),
+            OGG_VORBIS_320 | MP3_320 | AAC_320 => Some(320),
+            MP4_128 | MP4_128_DUAL => Some(128),
+            XHE_AAC_24 => Some(24),
+            XHE_AAC_16 => Some(16),
+            XHE_AAC_12 => Some(12),
+            FLAC_FLAC | FLAC_FLAC_24BIT | OTHER3 | OTHER5 => None,
+        }
+    }
+
     pub fn from_codec(codec: CodecType) -> Self {
         use symphonia::core::codecs::*;
         if codec == CODEC_TYPE_MP3 {
             Self::Mp3
         } else if codec == CODEC_TYPE_VORBIS {
             Self::OggVorbis
+        } else if codec == CODEC_TYPE_FLAC {
+            Self::Flac
         } else {
             Self::Unsupported
         }
     }
 }
 
+impl fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mp3 => write!(f, "MP3"),
+            Self::OggVorbis => write!(f, "Ogg Vorbis"),
+            Self::Flac => write!(f, "FLAC"),
+            Self::Unsupported => write!(f, "Unsupported"),
+        }
+    }
+}
+
+/// Tiers of audio quality Spotify offers, from the lowest to the highest.
+/// Which of them are available depends on the account and the item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum QualityTier {
+    Low,
+    Normal,
+    High,
+    Lossless,
+}
+
+impl QualityTier {
+    pub const ALL: [Self; 4] = [Self::Low, Self::Normal, Self::High, Self::Lossless];
+
+    /// Formats of the tier we can decode, in order of preference.  The AAC
+    /// and xHE-AAC files are DRM protected, so they are never requested.
+    pub fn formats(self) -> &'static [Format] {
+        match self {
+            Self::Low => &[Format::OGG_VORBIS_96, Format::MP3_96],
+            Self::Normal => &[Format::OGG_VORBIS_160, Format::MP3_160, Format::MP3_160_ENC],
+            Self::High => &[Format::OGG_VORBIS_320, Format::MP3_320, Format::MP3_256],
+            Self::Lossless => &[Format::FLAC_FLAC_24BIT, Format::FLAC_FLAC],
+        }
+    }
+
+    pub fn of_format(format: Format) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|tier| tier.formats().contains(&format))
+    }
+
+    /// Tiers to try when this one is preferred: itself, then the better lossy
+    /// tiers, then the worse ones.  Lossless files are many times larger, so
+    /// they are only picked when asked for.
+    pub fn fallback_order(self) -> impl Iterator<Item = Self> {
+        let better = Self::ALL
+            .into_iter()
+            .filter(move |&tier| tier > self && tier != Self::Lossless);
+        let worse = Self::ALL.into_iter().rev().filter(move |&tier| tier < self);
+        iter::once(self).chain(better).chain(worse)
+    }
+
+    /// All formats to try when this tier is preferred, in order.
+    pub fn preferred_formats(self) -> impl Iterator<Item = Format> {
+        self.fallback_order()
+            .flat_map(|tier| tier.formats().iter().copied())
+    }
+
+    /// Nominal bitrate in kbit/s, `None` for lossless files.
+    pub fn bitrate(self) -> Option<u32> {
+        match self {
+            Self::Low => Some(96),
+            Self::Normal => Some(160),
+            Self::High => Some(320),
+            Self::Lossless => None,
+        }
+    }
+}
+
 pub enum MediaFile {
     Streamed {
         streamed_file: Arc<StreamedFile>,
@@ -80,49 +171,20 @@ pub enum MediaFile {
 }
 
 impl MediaFile {
-    pub fn supported_audio_formats_for_bitrate(bitrate: usize) -> &'static [Format] {
-        match bitrate {
-            96 => &[
-                Format::OGG_VORBIS_96,
-                Format::MP3_96,
-                Format::OGG_VORBIS_160,
-                Format::MP3_160,
-                Format::MP3_160_ENC,
-                Format::MP3_256,
-                Format::OGG_VORBIS_320,
-                Format::MP3_320,
-            ],
-            160 => &[
-                Format::OGG_VORBIS_160,
-                Format::MP3_160,
-                Format::MP3_160_ENC,
-                Format::MP3_256,
-                Format::OGG_VORBIS_320,
-                Format::MP3_320,
-                Format::OGG_VORBIS_96,
-                Format::MP3_96,
-            ],
-            320 => &[
-                Format::OGG_VORBIS_320,
-                Format::MP3_320,
-                Format::MP3_256,
-                Format::OGG_VORBIS_160,
-                Format::MP3_160,
-                Format::MP3_160_ENC,
-                Format::OGG_VORBIS_96,
-                Format::MP3_96,
-            ],
-            _ => unreachable!(),
-        }
-    }
-
-    pub fn open(path: MediaPath, cdn: CdnHandle, cache: CacheHandle) -> Result<Self, Error> {
+    /// Open the file from the cache, or stream it from the CDN with up to
+    /// `parallelism` requests at once.
+    pub fn open(
+        path: MediaPath,
+        cdn: CdnHandle,
+        cache: CacheHandle,
+        parallelism: usize,
+    ) -> Result<Self, Error> {
         let cached_path = cache.audio_file_path(path.file_id);
         if cached_path.exists() {
             let cached_file = CachedFile::open(path, cached_path)?;
             Ok(Self::Cached { cached_file })
         } else {
-            let streamed_file = Arc::new(StreamedFile::open(path, cdn, cache)?);
+            let streamed_file = Arc::new(StreamedFile::open(path, cdn, cache, parallelism)?);
             let servicing_handle = thread::spawn({
                 let streamed_file = Arc::clone(&streamed_file);
                 move || {
@@ -158,6 +220,14 @@ impl MediaFile {
         }
     }
 
+    /// Download the rest of a streamed file in the background, it is saved to
+    /// the cache once complete.
+    pub fn prefetch(&self) {
+        if let Self::Streamed { streamed_file, .. } = self {
+            streamed_file.storage.request_remaining();
+        }
+    }
+
     pub fn remote_audio_source(
         &self,
         key: AudioKey,
@@ -167,18 +237,23 @@ impl MediaFile {
             .expect("storage always set for remote files")
             .reader()?;
         let mut decrypted = AudioDecrypt::new(key, reader);
-        let normalization = NormalizationData::parse(&mut decrypted)?;
+        // Only the Ogg files carry the Spotify header with the normalization
+        // data, the MP3 and FLAC files start right with the audio.
+        let normalization = match self.path().file_format {
+            AudioFormat::OggVorbis => NormalizationData::parse(&mut decrypted)?,
+            _ => NormalizationData::neutral(),
+        };
         let encoded = OffsetFile::new(decrypted, self.header_length())?;
         let decoded = AudioDecoder::new(encoded, self.codec_format())?;
         Ok((decoded, normalization))
     }
 
     pub fn local_audio_source(&self) -> Result<(AudioDecoder, NormalizationData), Error> {
-        let mut reader = fs::File::open(self.path().item_id.to_local())?;
-        let normalization = NormalizationData::parse(&mut reader)?;
-        let encoded = OffsetFile::new(reader, self.header_length())?;
-        let decoded = AudioDecoder::new(encoded, self.codec_format())?;
-        Ok((decoded, normalization))
+        // Local files are plain audio files, without the Spotify header that
+        // carries the normalization data and precedes the audio.
+        let reader = fs::File::open(self.path().item_id.to_local())?;
+        let decoded = AudioDecoder::new(reader, self.codec_format())?;
+        Ok((decoded, NormalizationData::neutral()))
     }
 
     fn header_length(&self) -> u64 {
@@ -192,6 +267,7 @@ impl MediaFile {
         match self.path().file_format {
             AudioFormat::OggVorbis => AudioCodecFormat::OggVorbis,
             AudioFormat::Mp3 => AudioCodecFormat::Mp3,
+            AudioFormat::Flac => AudioCodecFormat::Flac,
             AudioFormat::Unsupported => unreachable!("unsupported codec"),
         }
     }
@@ -200,14 +276,16 @@ impl MediaFile {
 pub struct StreamedFile {
     path: MediaPath,
     storage: StreamStorage,
-    url: CdnUrl,
-    cdn: CdnHandle,
-    cache: CacheHandle,
-    download_threads: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    downloader: Downloader,
 }
 
 impl StreamedFile {
-    fn open(path: MediaPath, cdn: CdnHandle, cache: CacheHandle) -> Result<StreamedFile, Error> {
+    fn open(
+        path: MediaPath,
+        cdn: CdnHandle,
+        cache: CacheHandle,
+        parallelism: usize,
+    ) -> Result<StreamedFile, Error> {
         // First, we need to resolve URL of the file contents.
         let url = cdn.resolve_audio_file_url(path.file_id)?;
         log::debug!("resolved file URL: {:?}", url.url);
@@ -225,122 +303,33 @@ impl StreamedFile {
         // Pipe the initial data from the request body into storage.
         io::copy(&mut initial_data, &mut storage.writer()?)?;
 
+        let downloader = Downloader::new(parallelism, url, cdn, cache, path.file_id, &storage)?;
+
         Ok(StreamedFile {
             path,
             storage,
-            url,
-            cdn,
-            cache,
-            download_threads: Arc::new(Mutex::new(Vec::new())),
+            downloader,
         })
     }
 
     fn service_streaming(&self) -> Result<(), Error> {
-        let mut last_url = self.url.clone();
-        let force_resolve = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
-        let mut fresh_url = || -> Result<CdnUrl, Error> {
-            if last_url.is_expired()
-                || force_resolve.swap(false, std::sync::atomic::Ordering::SeqCst)
-            {
-                last_url = self.cdn.resolve_audio_file_url(self.path.file_id)?;
-            }
-            Ok(last_url.clone())
-        };
-        let mut download_range = |offset, length| -> Result<(), Error> {
-            // Clean up completed download threads to prevent unbounded growth
-            self.cleanup_finished_threads();
-
-            let thread_name = format!(
-                "cdn-{}-{}..{}",
-                self.path.file_id.to_base16(),
-                offset,
-                offset + length
-            );
-            let handle = thread::Builder::new().name(thread_name).spawn({
-                let url = fresh_url()?.url;
-                let cdn = self.cdn.clone();
-                let cache = self.cache.clone();
-                let mut writer = self.storage.writer()?;
-                let file_path = self.storage.path().to_path_buf();
-                let file_id = self.path.file_id;
-                let force_resolve = force_resolve.clone();
-                move || {
-                    match load_range(&mut writer, &cdn, &url, offset, length) {
-                        Ok(_) => {
-                            // If the file is completely downloaded, copy it to cache.
-                            if writer.is_complete() && !cache.audio_file_path(file_id).exists() {
-                                // TODO: We should do this atomically.
-                                if let Err(err) = cache.save_audio_file(file_id, file_path) {
-                                    log::warn!("failed to save audio file to cache: {err:?}");
-                                }
-                            }
-                        }
-                        Err(err) => {
-                            // On auth error, try once to re-resolve the CDN URL and retry; otherwise mark as not requested.
-                            let retry_after_auth = |w: &mut StreamWriter| -> Result<(), ()> {
-                                let new_url = cdn.resolve_audio_file_url(file_id).map_err(|_| ())?;
-                                load_range(w, &cdn, &new_url.url, offset, length).map_err(|_| ())
-                            };
-
-                            let retried_ok = matches!(err, Error::HttpStatus(code) if code == 401 || code == 403)
-                                && retry_after_auth(&mut writer).is_ok();
-
-                            if !retried_ok {
-                                force_resolve.store(true, std::sync::atomic::Ordering::SeqCst);
-                                writer.mark_as_not_requested(offset, length);
-                            }
-                        }
-                    }
-                }
-            })?;
-
-            // Store the handle for tracking
-            self.download_threads.lock().push(handle);
-
-            Ok(())
-        };
-
         while let Ok(req) = self.storage.receiver().recv() {
             match req {
                 StreamRequest::Preload { offset, length } => {
-                    if let Err(err) = download_range(offset, length) {
-                        log::error!("failed to request audio range: {err:?}");
-                    }
+                    self.downloader.request(offset, length);
                 }
                 StreamRequest::Blocked { offset } => {
                     log::info!("blocked at {offset}");
+                    self.downloader.prioritize(offset);
+                }
+                StreamRequest::Seek { offset } => {
+                    log::debug!("seeking to {offset}");
+                    self.downloader.seek(offset);
                 }
             }
         }
         Ok(())
     }
-
-    /// Clean up finished download threads to prevent unbounded accumulation.
-    fn cleanup_finished_threads(&self) {
-        self.download_threads
-            .lock()
-            .retain(|handle| !handle.is_finished());
-    }
-}
-
-impl Drop for StreamedFile {
-    fn drop(&mut self) {
-        // Wait for all download threads to complete when the file is dropped.
-        // This ensures graceful cleanup of resources.
-        let mut threads = self.download_threads.lock();
-        let thread_count = threads.len();
-        if thread_count > 0 {
-            log::debug!(
-                "waiting for {} download thread(s) to finish for file {}",
-                thread_count,
-                self.path.file_id.to_base16()
-            );
-        }
-        while let Some(handle) = threads.pop() {
-            // Join each thread, ignoring any errors (thread may have already finished)
-            let _ = handle.join();
-        }
-    }
 }
 
 pub struct CachedFile {
@@ -356,24 +345,3 @@ impl CachedFile {
         })
     }
 }
-
-fn load_range(
-    writer: &mut StreamWriter,
-    cdn: &CdnHandle,
-    url: &str,
-    offset: u64,
-    length: u64,
-) -> Result<(), Error> {
-    log::trace!("downloading {}..{}", offset, offset + length);
-
-    // Download range of data from the CDN.  Block until we a have reader of the
-    // request body.
-    let (_total_length, mut reader) = cdn.fetch_file_range(url, offset, length)?;
-
-    // Pipe it into storage. Blocks until fully written, but readers sleeping on
-    // this file should be notified as soon as their offset is covered.
-    writer.seek(SeekFrom::Start(offset))?;
-    io::copy(&mut reader, writer)?;
-
-    Ok(())
-}
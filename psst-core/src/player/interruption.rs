@@ -0,0 +1,90 @@
+use std::{collections::HashSet, thread};
+
+use crossbeam_channel::{select, Receiver, Sender};
+
+use crate::audio::output::session::SessionWatch;
+
+use super::PlayerEvent;
+
+/// What to do when another application starts playing audio, or a call
+/// starts.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum InterruptionPolicy {
+    /// Keep playing.
+    #[default]
+    Ignore,
+    /// Pause, and stay paused.
+    Pause,
+    /// Pause, and resume once the other application is quiet again.
+    PauseAndResume,
+}
+
+/// Watches other applications from a background thread and reports changes
+/// in `PlayerEvent::Interruption`.
+pub struct InterruptionMonitor {
+    enabled: Sender<bool>,
+}
+
+impl InterruptionMonitor {
+    /// The thread ends once the monitor is dropped, or right away on platforms
+    /// that do not tell about other applications.
+    pub fn spawn(sender: Sender<PlayerEvent>) -> Self {
+        let (enabled_send, enabled_recv) = crossbeam_channel::unbounded();
+        let spawned = thread::Builder::new()
+            .name("interruption_monitor".to_string())
+            .spawn(move || Self::run(sender, enabled_recv));
+        if let Err(err) = spawned {
+            log::error!("failed to spawn the interruption monitor thread: {err}");
+        }
+        Self {
+            enabled: enabled_send,
+        }
+    }
+
+    /// Wait for the audio system to report a change, and check the other
+    /// applications only then.  Applications that were already playing when
+    /// the monitor was enabled do not interrupt, only ones that start playing
+    /// afterwards do.
+    fn run(sender: Sender<PlayerEvent>, enabled_updates: Receiver<bool>) {
+        let (changed_send, changed_recv) = crossbeam_channel::unbounded();
+        let Some(mut watch) = SessionWatch::new(changed_send) else {
+            log::info!("audio system does not report other applications");
+            return;
+        };
+        let mut enabled = false;
+        let mut already_playing = HashSet::new();
+        let mut interrupted = false;
+        loop {
+            select! {
+                recv(enabled_updates) -> update => match update {
+                    Ok(update) if update && !enabled => {
+                        enabled = true;
+                        already_playing = watch.playing_processes();
+                    }
+                    Ok(update) => enabled = update,
+                    Err(_) => break,
+                },
+                recv(changed_recv) -> _ => {}
+            }
+            let playing = if enabled {
+                watch.playing_processes()
+            } else {
+                HashSet::new()
+            };
+            // Once they stop, they interrupt like any other when they start again.
+            already_playing.retain(|pid| playing.contains(pid));
+            let active = playing.iter().any(|pid| !already_playing.contains(pid));
+            if active != interrupted {
+                interrupted = active;
+                if sender.send(PlayerEvent::Interruption { active }).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Only listen to the audio system while the policy asks for it.
+    pub fn set_policy(&self, policy: InterruptionPolicy) {
+        let _ = self.enabled.send(policy != InterruptionPolicy::Ignore);
+    }
+}
@@ -1,21 +1,21 @@
-use std::time::Duration;
+use std::{collections::HashSet, io, iter, thread, time::Duration};
 
 use crate::{
     audio::{
-        decode::AudioDecoder, decrypt::AudioKey, equalizer::EqualizerConfig,
-        normalize::NormalizationLevel, probe::TrackProbe,
+        decode::AudioDecoder, decrypt::AudioKey, normalize::NormalizationLevel, probe::TrackProbe,
+        processor::DspConfig, resample::ResamplingQuality,
     },
     cache::CacheHandle,
     cdn::CdnHandle,
     error::Error,
-    item_id::{ItemId, ItemIdType, LocalItemRegistry},
+    item_id::{FileId, ItemId, ItemIdType, LocalItemRegistry},
     metadata::{Fetch, ToMediaPath},
     protocol::metadata::{Episode, Track},
-    session::SessionService,
+    session::{reconnect, SessionService},
 };
 
 use super::{
-    file::{AudioFormat, MediaFile, MediaPath},
+    file::{AudioFormat, MediaFile, MediaPath, QualityTier},
     PlaybackConfig,
 };
 
@@ -23,13 +23,26 @@ pub struct LoadedPlaybackItem {
     pub file: MediaFile,
     pub source: AudioDecoder,
     pub norm_factor: f32,
-    pub equalizer_config: EqualizerConfig,
+    pub dsp: DspConfig,
+    pub resampling_quality: ResamplingQuality,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct PlaybackItem {
     pub item_id: ItemId,
     pub norm_level: NormalizationLevel,
+    pub group: ItemGroup,
+    /// Beginning of the item to skip, like the intro of a DJ mix.  Playback
+    /// starts from here and returning to the start returns here.
+    pub skip_intro: Duration,
+}
+
+/// Artist and album an item belongs to.  Smart shuffle uses them to keep
+/// related items apart.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct ItemGroup {
+    pub artist: Option<ItemId>,
+    pub album: Option<ItemId>,
 }
 
 impl PlaybackItem {
@@ -40,7 +53,12 @@ impl PlaybackItem {
         cache: CacheHandle,
         config: &PlaybackConfig,
     ) -> Result<LoadedPlaybackItem, Error> {
-        let path = load_media_path(self.item_id, session, &cache, config)?;
+        let quality = config.quality_for(cdn.bandwidth());
+        let path = if config.offline && self.item_id.id_type != ItemIdType::LocalFile {
+            cached_media_path(self.item_id, &cache, quality).ok_or(Error::Offline)?
+        } else {
+            load_media_path(self.item_id, session, &cache, quality)?
+        };
         let (file, source, norm_data) = match self.item_id.id_type {
             ItemIdType::LocalFile => {
                 let file = MediaFile::local(path);
@@ -49,7 +67,7 @@ impl PlaybackItem {
             }
             _ => {
                 let key = load_audio_key(&path, session, &cache)?;
-                let file = MediaFile::open(path, cdn, cache)?;
+                let file = MediaFile::open(path, cdn, cache, config.download_parallelism)?;
                 let (source, norm_data) = file.remote_audio_source(key)?;
                 (file, source, norm_data)
             }
@@ -59,22 +77,126 @@ impl PlaybackItem {
             file,
             source,
             norm_factor,
-            equalizer_config: config.equalizer.clone(),
+            dsp: config.dsp.clone(),
+            resampling_quality: config.resampling_quality,
         })
     }
 }
 
+/// Download the audio file of `item_id` into the cache, together with the
+/// metadata and the key needed to play it without streaming.  Files already
+/// in the cache are not downloaded again.
+pub fn save_to_cache(
+    item_id: ItemId,
+    session: &SessionService,
+    cdn: &CdnHandle,
+    cache: &CacheHandle,
+    quality: QualityTier,
+) -> Result<(), Error> {
+    let path = load_media_path(item_id, session, cache, quality)?;
+    let key = load_audio_key(&path, session, cache)?;
+    if cache.audio_file_path(path.file_id).exists() {
+        return Ok(());
+    }
+
+    // Attempts at a download cut off by the network, each continuing where
+    // the one before stopped.
+    const MAX_ATTEMPTS: u32 = 3;
+
+    let url = cdn.resolve_audio_file_url(path.file_id)?;
+    let mut attempt = 1;
+    while let Err(err) = download_audio_file(&url.url, path.file_id, cdn, cache) {
+        if attempt == MAX_ATTEMPTS
+            || !matches!(err, Error::AudioFetchingError(_) | Error::IoError(_))
+        {
+            return Err(err);
+        }
+        log::info!(
+            "download of {:?} interrupted, continuing: {err}",
+            path.item_id
+        );
+        thread::sleep(Duration::from_secs(attempt.into()));
+        attempt += 1;
+    }
+    cache.finish_download(path.file_id, key)
+}
+
+/// Download the rest of the file into its partial download, which is kept
+/// when the download fails.
+fn download_audio_file(
+    url: &str,
+    file_id: FileId,
+    cdn: &CdnHandle,
+    cache: &CacheHandle,
+) -> Result<(), Error> {
+    // How many bytes we request in the first chunk, the response tells the
+    // total length of the file.
+    const INITIAL_REQUEST_LENGTH: u64 = 64 * 1024;
+
+    let (mut file, downloaded, total_length) = match cache.partial_download(file_id) {
+        Some(partial) => (
+            cache.continue_download(file_id, partial.total_length)?,
+            partial.downloaded,
+            partial.total_length,
+        ),
+        None => {
+            let (total_length, mut initial_data) =
+                cdn.fetch_file_range(url, 0, INITIAL_REQUEST_LENGTH)?;
+            let mut file = cache.continue_download(file_id, total_length)?;
+            let written = io::copy(&mut initial_data, &mut file)?;
+            (file, written, total_length)
+        }
+    };
+    if downloaded < total_length {
+        let (_, mut rest) = cdn.fetch_file_range(url, downloaded, total_length - downloaded)?;
+        io::copy(&mut rest, &mut file)?;
+    }
+    Ok(())
+}
+
+/// Media path of a file of `item_id` that plays from the cache alone, with
+/// the metadata, the key and the whole audio file there.  A file of the
+/// `quality` tier is preferred, then any other cached one.
+pub fn cached_media_path(
+    item_id: ItemId,
+    cache: &CacheHandle,
+    quality: QualityTier,
+) -> Option<MediaPath> {
+    let item: Box<dyn ToMediaPath> = match item_id.id_type {
+        ItemIdType::Track => Box::new(cache.get_track(item_id)?),
+        ItemIdType::Podcast => Box::new(cache.get_episode(item_id)?),
+        ItemIdType::LocalFile | ItemIdType::Unknown => return None,
+    };
+    iter::once(quality)
+        .chain(QualityTier::ALL)
+        .filter_map(|tier| item.to_media_path(tier))
+        .find(|path| {
+            cache.audio_file_path(path.file_id).exists()
+                && cache.get_audio_key(item_id, path.file_id).is_some()
+        })
+}
+
+/// Tracks and episodes that play from the cache alone, see
+/// `cached_media_path`.
+pub fn cached_items(cache: &CacheHandle, quality: QualityTier) -> io::Result<HashSet<ItemId>> {
+    Ok(cache
+        .cached_item_ids()?
+        .into_iter()
+        .filter(|&item_id| cached_media_path(item_id, cache, quality).is_some())
+        .collect())
+}
+
 fn load_media_path(
     item_id: ItemId,
     session: &SessionService,
     cache: &CacheHandle,
-    config: &PlaybackConfig,
+    quality: QualityTier,
 ) -> Result<MediaPath, Error> {
     match item_id.id_type {
         ItemIdType::Track => {
-            load_media_path_from_track_or_alternative(item_id, session, cache, config)
+            load_media_path_from_track_or_alternative(item_id, session, cache, quality)
         }
-        ItemIdType::Podcast => load_media_path_from_episode(item_id, session, cache, config),
+        ItemIdType::Podcast => load_media_path_from_episode(item_id, session, cache, quality),
         ItemIdType::LocalFile => load_media_path_from_local(item_id),
         ItemIdType::Unknown => unimplemented!(),
     }
@@ -84,7 +206,7 @@ fn load_media_path_from_track_or_alternative(
     item_id: ItemId,
     session: &SessionService,
     cache: &CacheHandle,
-    config: &PlaybackConfig,
+    quality: QualityTier,
 ) -> Result<MediaPath, Error> {
     let track = load_track(item_id, session, cache)?;
     let country = get_country_code(session, cache);
@@ -97,7 +219,7 @@ fn load_media_path_from_track_or_alternative(
                 .ok_or(Error::MediaFileNotFound)?;
             let alt_track = load_track(alt_id, session, cache)?;
             let alt_path = alt_track
-                .to_media_path(config.bitrate)
+                .to_media_path(quality)
                 .ok_or(Error::MediaFileNotFound)?;
             // We've found an alternative track with a fitting audio file.  Let's cheat a
             // little and pretend we've obtained it from the requested track.
@@ -111,7 +233,7 @@ fn load_media_path_from_track_or_alternative(
             // Either we do not have a country code loaded or the track is available, return
             // it.
             track
-                .to_media_path(config.bitrate)
+                .to_media_path(quality)
                 .ok_or(Error::MediaFileNotFound)?
         }
     };
@@ -122,7 +244,7 @@ fn load_media_path_from_episode(
     item_id: ItemId,
     session: &SessionService,
     cache: &CacheHandle,
-    config: &PlaybackConfig,
+    quality: QualityTier,
 ) -> Result<MediaPath, Error> {
     let episode = load_episode(item_id, session, cache)?;
     let country = get_country_code(session, cache);
@@ -132,7 +254,7 @@ fn load_media_path_from_episode(
             return Err(Error::MediaFileNotFound);
         }
         _ => episode
-            .to_media_path(config.bitrate)
+            .to_media_path(quality)
             .ok_or(Error::MediaFileNotFound)?,
     };
     Ok(path)
@@ -145,6 +267,8 @@ fn load_media_path_from_local(item_id: ItemId) -> Result<MediaPath, Error> {
         item_id,
         file_id: Default::default(),
         file_format: AudioFormat::from_codec(probe.codec),
+        quality: None,
+        bitrate: None,
         // It's possible (though unlikely) that we're unable to determine the track
         // duration from the codec params; in that case, default to 0 and let it
         // be calculated at runtime as we play the track.
@@ -204,9 +328,9 @@ fn load_audio_key(
     if let Some(cached_key) = cache.get_audio_key(path.item_id, path.file_id) {
         Ok(cached_key)
     } else {
-        let key = session
-            .connected()?
-            .get_audio_key(path.item_id, path.file_id)?;
+        let key = reconnect::with_retry(session, |handle| {
+            handle.get_audio_key(path.item_id, path.file_id)
+        })?;
         if let Err(err) = cache.save_audio_key(path.item_id, path.file_id, &key) {
             log::warn!("failed to save audio key to cache: {err:?}");
         }
@@ -1,47 +1,131 @@
+pub mod bandwidth;
+pub mod crossfade;
+pub mod download;
 pub mod file;
+pub mod interruption;
 pub mod item;
 pub mod queue;
+pub mod shuffle;
 mod storage;
+mod watchdog;
 mod worker;
 
-use std::{mem, thread, thread::JoinHandle, time::Duration};
+use std::{
+    collections::VecDeque,
+    mem,
+    sync::Arc,
+    thread,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
 
 use crate::{
     audio::{
-        equalizer::EqualizerConfig,
+        generator::TestSignal,
         output::{AudioOutput, AudioSink, DefaultAudioOutput, DefaultAudioSink},
+        processor::DspConfig,
+        resample::ResamplingQuality,
+        stretch::Tempo,
     },
     cache::CacheHandle,
     cdn::CdnHandle,
     error::Error,
+    item_id::{ItemId, ItemIdType},
     session::SessionService,
 };
 
 use self::{
-    file::MediaPath,
+    bandwidth::BandwidthMeter,
+    crossfade::{FadePoints, TrackAnalysis},
+    file::{MediaPath, QualityTier},
+    interruption::{InterruptionMonitor, InterruptionPolicy},
     item::{LoadedPlaybackItem, PlaybackItem},
-    queue::{Queue, QueueBehavior},
+    queue::{
+        CommandQueue, CommandSender, PreviousPresses, Queue, Repeat, Shuffle,
+        COMMAND_QUEUE_CAPACITY,
+    },
+    watchdog::Watchdog,
     worker::PlaybackManager,
 };
 
 const PREVIOUS_TRACK_THRESHOLD: Duration = Duration::from_secs(3);
+const PRELOAD_BEFORE_END_OF_TRACK: Duration = Duration::from_secs(30);
 const STOP_AFTER_CONSECUTIVE_LOADING_FAILURES: usize = 3;
+/// Track analyses kept for placing the fades, the oldest are dropped first.
+const MAX_TRACK_ANALYSES: usize = 8;
+
+/// Parallel downloads of a streamed file, enough to hide the latency of the
+/// CDN without competing with other traffic.
+pub const DEFAULT_DOWNLOAD_PARALLELISM: usize = 4;
+
+/// Part of the current item after which the next one is prefetched.
+pub const DEFAULT_PREFETCH_AT: f32 = 0.5;
 
 #[derive(Clone)]
 pub struct PlaybackConfig {
-    pub bitrate: usize,
+    /// Preferred quality, other tiers are tried when the item is not
+    /// available in it.
+    pub quality: QualityTier,
+    /// Pick the tier of each item from the measured bandwidth, and step down
+    /// mid-item when the playback runs out of data.  `quality` is then the
+    /// highest tier used.
+    pub adaptive_quality: bool,
     pub pregain: f32,
-    pub equalizer: EqualizerConfig,
+    /// Order and settings of the processors applied to the decoded audio.
+    pub dsp: DspConfig,
+    /// Interpolation used when the output device cannot run at the sample rate of
+    /// the played source.
+    pub resampling_quality: ResamplingQuality,
+    /// Longest fade in and out of each track, `None` disables fading.
+    pub crossfade: Option<Duration>,
+    /// Speed and silence trimming of podcast episodes.  Tracks always play at
+    /// their normal tempo.
+    pub podcast_tempo: Tempo,
+    /// Reaction to other applications playing audio.
+    pub interruption_policy: InterruptionPolicy,
+    /// How many chunks of a streamed file are downloaded at once.  Applies to
+    /// the files opened after it is changed.
+    pub download_parallelism: usize,
+    /// Pressing Previous twice in a row goes back to the first item of the
+    /// queue.
+    pub double_press_previous_restarts: bool,
+    /// Part of the current item (0.0 to 1.0) after which the next item is
+    /// preloaded and downloaded completely, with its key.  `None` preloads it
+    /// only shortly before the end, and downloads it as it plays.
+    pub prefetch_at: Option<f32>,
+    /// Play only what is in the cache, without connecting to Spotify.  Other
+    /// items fail to load with `Error::Offline`.
+    pub offline: bool,
 }
 
 impl Default for PlaybackConfig {
     fn default() -> Self {
         Self {
-            bitrate: 320,
+            quality: QualityTier::High,
+            adaptive_quality: false,
             pregain: 3.0,
-            equalizer: EqualizerConfig::default(),
+            dsp: DspConfig::default(),
+            resampling_quality: ResamplingQuality::SincMediumQuality,
+            crossfade: None,
+            podcast_tempo: Tempo::NORMAL,
+            interruption_policy: InterruptionPolicy::Ignore,
+            download_parallelism: DEFAULT_DOWNLOAD_PARALLELISM,
+            double_press_previous_restarts: false,
+            prefetch_at: Some(DEFAULT_PREFETCH_AT),
+            offline: false,
+        }
+    }
+}
+
+impl PlaybackConfig {
+    /// Preferred tier of the next loaded item.
+    pub fn quality_for(&self, bandwidth: &BandwidthMeter) -> QualityTier {
+        if self.adaptive_quality {
+            bandwidth::adaptive_tier(bandwidth.estimate(), self.quality)
+        } else {
+            self.quality
         }
     }
 }
@@ -56,9 +140,30 @@ pub struct Player {
     queue: Queue,
     sender: Sender<PlayerEvent>,
     receiver: Receiver<PlayerEvent>,
+    commands: Arc<CommandQueue>,
     audio_output_sink: DefaultAudioSink,
     playback_mgr: PlaybackManager,
     consecutive_loading_failures: usize,
+    watchdog: Watchdog,
+    /// Item and position to restore after the playback worker has been restarted.
+    recovering: Option<(ItemId, Duration)>,
+    /// Last up-next list sent in `PlayerEvent::QueueChanged`.
+    reported_up_next: Vec<PlaybackItem>,
+    /// Analyses used to place the fades of tracks, see `PlayerCommand::SetTrackAnalysis`.
+    analyses: VecDeque<(ItemId, TrackAnalysis)>,
+    interruption: InterruptionMonitor,
+    /// Playback was paused because another application started playing.
+    paused_by_interruption: bool,
+    /// Item being loaded again in a lower tier, see `adapt_quality`.
+    switching_quality: Option<PlaybackItem>,
+    /// A test signal is playing instead of the queue, see `play_test_signal`.
+    playing_test_signal: bool,
+    /// Item and position that were paused for the test signal.
+    paused_for_test_signal: Option<(PlaybackItem, Duration)>,
+    /// Item and position to load again once the test signal stops, and whether
+    /// to play it or leave it paused.
+    restoring: Option<(ItemId, Duration, bool)>,
+    previous_presses: PreviousPresses,
 }
 
 impl Player {
@@ -70,6 +175,9 @@ impl Player {
         audio_output: &DefaultAudioOutput,
     ) -> Self {
         let (sender, receiver) = unbounded();
+        Watchdog::spawn_ticker(sender.clone());
+        let interruption = InterruptionMonitor::spawn(sender.clone());
+        interruption.set_policy(config.interruption_policy);
         Self {
             playback_mgr: PlaybackManager::new(audio_output.sink(), sender.clone()),
             session,
@@ -78,11 +186,23 @@ impl Player {
             config,
             sender,
             receiver,
+            commands: Arc::new(CommandQueue::new(COMMAND_QUEUE_CAPACITY)),
             audio_output_sink: audio_output.sink(),
             state: PlayerState::Stopped,
             preload: PreloadState::None,
             queue: Queue::new(),
             consecutive_loading_failures: 0,
+            watchdog: Watchdog::new(),
+            recovering: None,
+            reported_up_next: Vec::new(),
+            analyses: VecDeque::new(),
+            interruption,
+            paused_by_interruption: false,
+            switching_quality: None,
+            playing_test_signal: false,
+            paused_for_test_signal: None,
+            restoring: None,
+            previous_presses: PreviousPresses::new(),
         }
     }
 
@@ -94,23 +214,86 @@ impl Player {
         self.receiver.clone()
     }
 
+    /// Handle for sending `PlayerCommand`s to this player from other threads.
+    pub fn command_sender(&self) -> CommandSender {
+        CommandSender::new(self.commands.clone(), self.sender.clone())
+    }
+
+    /// Latency the output device actually gave us, if it reports it.  It
+    /// might differ from the requested one, and change with the sample rate.
+    pub fn output_latency(&self) -> Option<Duration> {
+        self.audio_output_sink.latency()
+    }
+
     pub fn handle(&mut self, event: PlayerEvent) {
         match event {
-            PlayerEvent::Command(cmd) => self.handle_command(cmd),
+            PlayerEvent::CommandsPending => self.handle_pending_commands(),
             PlayerEvent::Loaded { item, result } => self.handle_loaded(item, result),
             PlayerEvent::Preloaded { item, result } => self.handle_preloaded(item, result),
+            PlayerEvent::QualityLoaded { item, result } => self.handle_quality_loaded(item, result),
             PlayerEvent::Position { position, path } => self.handle_position(position, path),
+            PlayerEvent::FadingOut { path } => self.handle_fading_out(path),
             PlayerEvent::EndOfTrack => self.handle_end_of_track(),
+            PlayerEvent::WatchdogTick => self.handle_watchdog_tick(),
+            PlayerEvent::Interruption { active } => self.handle_interruption(active),
             PlayerEvent::Loading { .. }
             | PlayerEvent::Playing { .. }
             | PlayerEvent::Pausing { .. }
             | PlayerEvent::Resuming { .. }
             | PlayerEvent::Stopped
-            | PlayerEvent::Blocked { .. } => {}
+            | PlayerEvent::Blocked { .. }
+            | PlayerEvent::Recovered { .. }
+            | PlayerEvent::QualityChanged { .. }
+            | PlayerEvent::QueueChanged { .. }
+            | PlayerEvent::QueueDuplicates { .. } => return,
         };
+        self.report_up_next();
+    }
+
+    /// Send `PlayerEvent::QueueChanged` if the up-next list differs from the
+    /// one reported last time.
+    fn report_up_next(&mut self) {
+        let up_next = self.queue.up_next();
+        if up_next == self.reported_up_next {
+            return;
+        }
+        self.reported_up_next = up_next.clone();
+        if let Err(e) = self.sender.send(PlayerEvent::QueueChanged { up_next }) {
+            log::error!("failed to send QueueChanged event: {e:?}");
+        }
+    }
+
+    /// Send `PlayerEvent::QueueDuplicates` for items that were skipped, or
+    /// `removed` from the queue, as duplicates.
+    fn report_duplicates(&mut self, count: usize, removed: bool) {
+        if count == 0 && !removed {
+            return;
+        }
+        if let Err(e) = self
+            .sender
+            .send(PlayerEvent::QueueDuplicates { count, removed })
+        {
+            log::error!("failed to send QueueDuplicates event: {e:?}");
+        }
+    }
+
+    fn handle_pending_commands(&mut self) {
+        for cmd in self.commands.drain() {
+            self.handle_command(cmd);
+        }
     }
 
     fn handle_command(&mut self, cmd: PlayerCommand) {
+        if matches!(
+            cmd,
+            PlayerCommand::Pause | PlayerCommand::Resume | PlayerCommand::PauseOrResume
+        ) {
+            // The user took over, do not resume on their behalf.
+            self.paused_by_interruption = false;
+        }
+        if self.playing_test_signal && self.end_test_signal(&cmd) {
+            return;
+        }
         match cmd {
             PlayerCommand::LoadQueue { items, position } => self.load_queue(items, position),
             PlayerCommand::LoadAndPlay { item } => self.load_and_play(item),
@@ -123,9 +306,29 @@ impl Player {
             PlayerCommand::Stop => self.stop(),
             PlayerCommand::Seek { position } => self.seek(position),
             PlayerCommand::Configure { config } => self.configure(config),
-            PlayerCommand::SetQueueBehavior { behavior } => self.queue.set_behaviour(behavior),
-            PlayerCommand::AddToQueue { item } => self.queue.add(item),
+            PlayerCommand::SetShuffle { shuffle } => self.queue.set_shuffle(shuffle),
+            PlayerCommand::SetRepeat { repeat } => self.queue.set_repeat(repeat),
+            PlayerCommand::AddToQueue { item } => {
+                let skipped = !self.queue.add(item);
+                self.report_duplicates(usize::from(skipped), false);
+            }
+            PlayerCommand::AddToQueueNext { items } => {
+                let skipped = self.queue.add_next(items);
+                self.report_duplicates(skipped, false);
+            }
+            PlayerCommand::MoveQueueItem { from, to } => self.queue.move_up_next(from, to),
+            PlayerCommand::RemoveQueueItem { index } => self.queue.remove_up_next(index),
+            PlayerCommand::ClearUpNext => self.queue.clear_up_next(),
+            PlayerCommand::SetQueueDeduplication { enabled } => self.queue.set_deduplicate(enabled),
+            PlayerCommand::RemoveQueueDuplicates => {
+                let removed = self.queue.remove_duplicates();
+                self.report_duplicates(removed, true);
+            }
+            PlayerCommand::SetTrackAnalysis { item_id, analysis } => {
+                self.set_track_analysis(item_id, analysis)
+            }
             PlayerCommand::SetVolume { volume } => self.set_volume(volume),
+            PlayerCommand::PlayTestSignal { signal } => self.play_test_signal(signal),
         }
     }
 
@@ -137,9 +340,10 @@ impl Player {
             } if item == requested_item => match result {
                 Ok(loaded_item) => {
                     self.consecutive_loading_failures = 0;
-                    self.play_loaded(loaded_item);
+                    self.play_loaded(item, loaded_item);
                 }
                 Err(err) => {
+                    self.recovering = None;
                     self.consecutive_loading_failures += 1;
                     if self.consecutive_loading_failures < STOP_AFTER_CONSECUTIVE_LOADING_FAILURES {
                         log::error!("skipping, error while loading: {err}");
@@ -164,6 +368,9 @@ impl Player {
             } if item == requested_item => match result {
                 Ok(loaded_item) => {
                     log::info!("preloaded audio file");
+                    if self.config.prefetch_at.is_some() {
+                        loaded_item.file.prefetch();
+                    }
                     self.preload = PreloadState::Preloaded {
                         item,
                         loaded_item: Box::new(loaded_item),
@@ -189,20 +396,176 @@ impl Player {
         match &mut self.state {
             PlayerState::Playing { position, .. } | PlayerState::Paused { position, .. } => {
                 *position = new_position;
+                self.watchdog.report_position(new_position);
             }
             _ => {
                 log::warn!("received unexpected position report");
             }
         }
-        const PRELOAD_BEFORE_END_OF_TRACK: Duration = Duration::from_secs(30);
         let time_until_end_of_track = path.duration.checked_sub(new_position).unwrap_or_default();
-        if time_until_end_of_track <= PRELOAD_BEFORE_END_OF_TRACK {
+        let past_prefetch_point = self
+            .config
+            .prefetch_at
+            .is_some_and(|at| new_position >= path.duration.mul_f32(at.clamp(0.0, 1.0)));
+        if time_until_end_of_track <= PRELOAD_BEFORE_END_OF_TRACK || past_prefetch_point {
             if let Some(&item_to_preload) = self.queue.get_following() {
                 self.preload(item_to_preload);
             }
         }
     }
 
+    fn handle_watchdog_tick(&mut self) {
+        self.adapt_quality();
+
+        let PlayerState::Playing { path, position } = self.state else {
+            return;
+        };
+        if self.cdn.bandwidth().is_buffering(path.file_id) {
+            // Waiting for the network is not a stall, the play-head moves on
+            // once the data arrives.
+            self.watchdog.reset(position);
+            return;
+        }
+        if !self.watchdog.is_stalled() {
+            return;
+        }
+        let Some(&item) = self.queue.get_current() else {
+            return;
+        };
+        if !self.watchdog.try_recover() {
+            log::error!("stopping, playback keeps stalling after restarting the worker");
+            self.stop();
+            return;
+        }
+        log::warn!("playback stalled at {position:?}, restarting the playback worker");
+
+        // Drop the stuck worker together with its audio source and load the item
+        // again.  The position is restored in `play_loaded`.
+        self.audio_output_sink.stop();
+        self.playback_mgr =
+            PlaybackManager::new(self.audio_output_sink.clone(), self.sender.clone());
+        self.recovering = Some((path.item_id, position));
+        self.load_and_play(item);
+    }
+
+    /// If the played file ran out of data and the measured bandwidth cannot
+    /// sustain its tier, load the item again in a tier it can.  The current file
+    /// keeps playing until the new one is ready.
+    fn adapt_quality(&mut self) {
+        let PlayerState::Playing { path, .. } = self.state else {
+            return;
+        };
+        if !self.config.adaptive_quality || self.switching_quality.is_some() {
+            return;
+        }
+        let bandwidth = self.cdn.bandwidth();
+        if !bandwidth.take_rebuffer(path.file_id) {
+            return;
+        }
+        let Some(current) = path.quality else {
+            return;
+        };
+        let quality = self.config.quality_for(bandwidth);
+        if quality >= current {
+            return;
+        }
+        let Some(&item) = self.queue.get_current() else {
+            return;
+        };
+        log::info!("rebuffering in {current:?} quality, switching to {quality:?}");
+
+        let config = PlaybackConfig {
+            quality,
+            adaptive_quality: false,
+            ..self.config.clone()
+        };
+        thread::spawn({
+            let sender = self.sender.clone();
+            let session = self.session.clone();
+            let cdn = self.cdn.clone();
+            let cache = self.cache.clone();
+            move || {
+                let result = item.load(&session, cdn, cache, &config);
+                if let Err(e) = sender.send(PlayerEvent::QualityLoaded { item, result }) {
+                    log::error!("failed to send QualityLoaded event: {e:?}");
+                }
+            }
+        });
+        self.switching_quality = Some(item);
+    }
+
+    fn handle_quality_loaded(
+        &mut self,
+        item: PlaybackItem,
+        result: Result<LoadedPlaybackItem, Error>,
+    ) {
+        if self.switching_quality != Some(item) {
+            log::info!("stale quality switch received, ignoring");
+            return;
+        }
+        self.switching_quality = None;
+        let PlayerState::Playing { position, .. } = self.state else {
+            return;
+        };
+        let loaded_item = match result {
+            Ok(loaded_item) => loaded_item,
+            Err(err) => {
+                log::warn!("failed to switch quality, error while loading: {err}");
+                return;
+            }
+        };
+        let path = loaded_item.file.path();
+        let fade = self.fade_points(&path);
+        let tempo = self.tempo(&path);
+        self.audio_output_sink.stop();
+        self.playback_mgr.play(loaded_item, fade, tempo);
+        self.playback_mgr.seek(position);
+        self.watchdog.reset(position);
+        self.state = PlayerState::Playing { path, position };
+        if let Err(e) = self.sender.send(PlayerEvent::QualityChanged { path }) {
+            log::error!("failed to send QualityChanged event: {e:?}");
+        }
+    }
+
+    fn handle_interruption(&mut self, active: bool) {
+        let policy = self.config.interruption_policy;
+        if active {
+            if policy != InterruptionPolicy::Ignore
+                && matches!(self.state, PlayerState::Playing { .. })
+            {
+                log::info!("another application is playing, pausing");
+                self.pause();
+                self.paused_by_interruption = true;
+            }
+        } else if mem::take(&mut self.paused_by_interruption)
+            && policy == InterruptionPolicy::PauseAndResume
+        {
+            log::info!("other applications are quiet, resuming");
+            self.resume();
+        }
+    }
+
+    /// Start the next item on top of the fade-out of the current one.  If the
+    /// next item is not loaded yet, it starts after the current one ends.
+    fn handle_fading_out(&mut self, path: MediaPath) {
+        let PlayerState::Playing { path: playing, .. } = self.state else {
+            return;
+        };
+        if playing.item_id != path.item_id || self.switching_quality.is_some() {
+            return;
+        }
+        let Some(&following) = self.queue.get_following() else {
+            return;
+        };
+        match mem::replace(&mut self.preload, PreloadState::None) {
+            PreloadState::Preloaded { item, loaded_item } if item == following => {
+                self.queue.skip_to_following();
+                self.crossfade_to(item, *loaded_item);
+            }
+            other => self.preload = other,
+        }
+    }
+
     fn handle_end_of_track(&mut self) {
         self.queue.skip_to_following();
         if let Some(&item) = self.queue.get_current() {
@@ -222,6 +585,9 @@ impl Player {
     }
 
     fn load_and_play(&mut self, item: PlaybackItem) {
+        self.paused_by_interruption = false;
+        self.switching_quality = None;
+
         // Make sure to stop the sink, so any current audio source is cleared and the
         // playback stopped.
         self.audio_output_sink.stop();
@@ -233,7 +599,7 @@ impl Player {
                 loaded_item,
             } if preloaded_item == item => {
                 // This item is already loaded in the preloader state.
-                self.play_loaded(*loaded_item);
+                self.play_loaded(item, *loaded_item);
                 return;
             }
 
@@ -300,17 +666,132 @@ impl Player {
         self.audio_output_sink.set_volume(volume as f32);
     }
 
-    fn play_loaded(&mut self, loaded_item: LoadedPlaybackItem) {
+    fn play_loaded(&mut self, item: PlaybackItem, loaded_item: LoadedPlaybackItem) {
         log::info!("starting playback");
         let path = loaded_item.file.path();
-        let position = Duration::default();
-        self.playback_mgr.play(loaded_item);
+        let mut position = Duration::default();
+        let fade = self.fade_points(&path);
+        let tempo = self.tempo(&path);
+        let restored = match self.restoring.take() {
+            Some((item_id, restored_position, resume)) if item_id == item.item_id => {
+                Some((restored_position, resume))
+            }
+            _ => None,
+        };
+        if let Some((restored_position, resume)) = restored {
+            if resume {
+                self.playback_mgr.play(loaded_item, fade, tempo);
+            } else {
+                self.playback_mgr.load(loaded_item, fade, tempo);
+            }
+            self.playback_mgr.seek(restored_position);
+            self.watchdog.reset(restored_position);
+            let event = if resume {
+                self.state = PlayerState::Playing {
+                    path,
+                    position: restored_position,
+                };
+                PlayerEvent::Playing {
+                    path,
+                    position: restored_position,
+                }
+            } else {
+                self.state = PlayerState::Paused {
+                    path,
+                    position: restored_position,
+                };
+                PlayerEvent::Pausing {
+                    path,
+                    position: restored_position,
+                }
+            };
+            if let Err(e) = self.sender.send(event) {
+                log::error!("failed to send playback event: {e:?}");
+            }
+            return;
+        }
+        self.playback_mgr.play(loaded_item, fade, tempo);
+        let recovered = match self.recovering.take() {
+            Some((item_id, recovered_position)) if item_id == path.item_id => {
+                position = recovered_position;
+                self.playback_mgr.seek(position);
+                true
+            }
+            _ => false,
+        };
+        if !recovered && !item.skip_intro.is_zero() && item.skip_intro < path.duration {
+            position = item.skip_intro;
+            self.playback_mgr.seek(position);
+        }
+        self.watchdog.reset(position);
+        self.state = PlayerState::Playing { path, position };
+        if let Err(e) = self.sender.send(PlayerEvent::Playing { path, position }) {
+            log::error!("failed to send Playing event: {e:?}");
+        }
+        if recovered {
+            if let Err(e) = self.sender.send(PlayerEvent::Recovered { path, position }) {
+                log::error!("failed to send Recovered event: {e:?}");
+            }
+        }
+    }
+
+    fn crossfade_to(&mut self, item: PlaybackItem, loaded_item: LoadedPlaybackItem) {
+        log::info!("crossfading to the next track");
+        self.paused_by_interruption = false;
+        let path = loaded_item.file.path();
+        let fade = self.fade_points(&path);
+        let tempo = self.tempo(&path);
+        self.playback_mgr.crossfade(loaded_item, fade, tempo);
+        let mut position = Duration::default();
+        if !item.skip_intro.is_zero() && item.skip_intro < path.duration {
+            position = item.skip_intro;
+            self.playback_mgr.seek(position);
+        }
+        self.watchdog.reset(position);
         self.state = PlayerState::Playing { path, position };
         if let Err(e) = self.sender.send(PlayerEvent::Playing { path, position }) {
             log::error!("failed to send Playing event: {e:?}");
         }
     }
 
+    /// Fades for the played `path`, placed by its analysis if we have one.
+    fn fade_points(&self, path: &MediaPath) -> Option<FadePoints> {
+        let fade = self.config.crossfade?;
+        // Analyses are keyed by the requested item, which might differ from the
+        // played one if an alternative track was picked.
+        let item_id = self
+            .queue
+            .get_current()
+            .map_or(path.item_id, |item| item.item_id);
+        let analysis = self.analyses.iter().find(|(id, _)| *id == item_id);
+        let points = match analysis {
+            Some((_, analysis)) => FadePoints::from_analysis(analysis, path.duration, fade),
+            None => FadePoints::fixed(path.duration, fade),
+        };
+        Some(points)
+    }
+
+    fn tempo(&self, path: &MediaPath) -> Tempo {
+        if path.item_id.id_type == ItemIdType::Podcast {
+            self.config.podcast_tempo
+        } else {
+            Tempo::NORMAL
+        }
+    }
+
+    fn set_track_analysis(&mut self, item_id: ItemId, analysis: TrackAnalysis) {
+        self.analyses.retain(|(id, _)| *id != item_id);
+        if self.analyses.len() == MAX_TRACK_ANALYSES {
+            self.analyses.pop_front();
+        }
+        self.analyses.push_back((item_id, analysis));
+        if let PlayerState::Playing { path, .. } | PlayerState::Paused { path, .. } = self.state {
+            if self.queue.get_current().map(|item| item.item_id) == Some(item_id) {
+                self.playback_mgr.update_fade(self.fade_points(&path));
+            }
+        }
+    }
+
     fn pause(&mut self) {
         match mem::replace(&mut self.state, PlayerState::Invalid) {
             PlayerState::Playing { path, position } | PlayerState::Paused { path, position } => {
@@ -332,6 +813,7 @@ impl Player {
             PlayerState::Playing { path, position } | PlayerState::Paused { path, position } => {
                 log::info!("resuming playback");
                 self.audio_output_sink.resume();
+                self.watchdog.reset(position);
                 if let Err(e) = self.sender.send(PlayerEvent::Resuming { path, position }) {
                     log::error!("failed to send Resuming event: {e:?}");
                 }
@@ -354,7 +836,17 @@ impl Player {
     }
 
     fn previous(&mut self) {
-        if self.is_near_playback_start() {
+        let Some(double_press) = self.previous_presses.press(Instant::now()) else {
+            return;
+        };
+        if double_press && self.config.double_press_previous_restarts {
+            self.queue.skip_to_start();
+            if let Some(&item) = self.queue.get_current() {
+                self.load_and_play(item);
+            } else {
+                self.stop();
+            }
+        } else if self.is_near_playback_start() {
             self.queue.skip_to_previous();
             if let Some(&item) = self.queue.get_current() {
                 self.load_and_play(item);
@@ -362,7 +854,7 @@ impl Player {
                 self.stop();
             }
         } else {
-            self.seek(Duration::default());
+            self.seek(self.playback_start());
         }
     }
 
@@ -383,21 +875,96 @@ impl Player {
         self.state = PlayerState::Stopped;
         self.queue.clear();
         self.consecutive_loading_failures = 0;
+        self.recovering = None;
+        self.paused_by_interruption = false;
+        self.switching_quality = None;
     }
 
     fn seek(&mut self, position: Duration) {
         self.playback_mgr.seek(position);
     }
 
+    /// Pause the current item and play `signal` instead.  The item is loaded
+    /// again once the signal stops, see `end_test_signal`.
+    fn play_test_signal(&mut self, signal: TestSignal) {
+        if !self.playing_test_signal {
+            self.paused_for_test_signal = match self.state {
+                PlayerState::Playing { position, .. } | PlayerState::Paused { position, .. } => {
+                    self.pause();
+                    self.queue.get_current().map(|&item| (item, position))
+                }
+                PlayerState::Loading { item, .. } => {
+                    // Ignore the item once it has loaded, it starts from the
+                    // beginning after the signal.
+                    self.state = PlayerState::Stopped;
+                    Some((item, Duration::ZERO))
+                }
+                PlayerState::Stopped | PlayerState::Invalid => None,
+            };
+        }
+        self.playing_test_signal = true;
+        self.playback_mgr.play_test_signal(signal, &self.config.dsp);
+    }
+
+    /// Stop the test signal before `cmd` is handled.  Transport commands apply
+    /// to the item that was paused for the signal, which is loaded again.
+    /// Returns `true` if that took care of `cmd`.
+    fn end_test_signal(&mut self, cmd: &PlayerCommand) -> bool {
+        let (position, resume) = match cmd {
+            PlayerCommand::Stop | PlayerCommand::Pause => (None, false),
+            PlayerCommand::Resume | PlayerCommand::PauseOrResume => (None, true),
+            PlayerCommand::Seek { position } => (Some(*position), false),
+            PlayerCommand::LoadQueue { .. }
+            | PlayerCommand::LoadAndPlay { .. }
+            | PlayerCommand::Previous
+            | PlayerCommand::Next => {
+                // These load an item of their own.
+                self.playing_test_signal = false;
+                self.paused_for_test_signal = None;
+                self.audio_output_sink.stop();
+                return false;
+            }
+            _ => return false,
+        };
+        self.playing_test_signal = false;
+        self.audio_output_sink.stop();
+        if let Some((item, paused_at)) = self.paused_for_test_signal.take() {
+            self.restoring = Some((item.item_id, position.unwrap_or(paused_at), resume));
+            self.load_and_play(item);
+        }
+        true
+    }
+
     fn configure(&mut self, config: PlaybackConfig) {
-        self.playback_mgr.update_equalizer(config.equalizer.clone());
+        self.interruption.set_policy(config.interruption_policy);
+        if self.config.dsp != config.dsp {
+            self.playback_mgr.update_dsp(config.dsp.clone());
+        }
+        let crossfade_changed = self.config.crossfade != config.crossfade;
+        let tempo_changed = self.config.podcast_tempo != config.podcast_tempo;
         self.config = config;
+        if let PlayerState::Playing { path, .. } | PlayerState::Paused { path, .. } = self.state {
+            if crossfade_changed {
+                self.playback_mgr.update_fade(self.fade_points(&path));
+            }
+            if tempo_changed {
+                self.playback_mgr.update_tempo(self.tempo(&path));
+            }
+        }
+    }
+
+    /// Position playback of the current item starts from, after its skipped
+    /// intro.
+    fn playback_start(&self) -> Duration {
+        self.queue
+            .get_current()
+            .map_or(Duration::ZERO, |item| item.skip_intro)
     }
 
     fn is_near_playback_start(&self) -> bool {
         match self.state {
             PlayerState::Playing { position, .. } | PlayerState::Paused { position, .. } => {
-                position < PREVIOUS_TRACK_THRESHOLD
+                position < self.playback_start() + PREVIOUS_TRACK_THRESHOLD
             }
             _ => false,
         }
@@ -435,20 +1002,75 @@ pub enum PlayerCommand {
     Configure {
         config: PlaybackConfig,
     },
-    SetQueueBehavior {
-        behavior: QueueBehavior,
+    SetShuffle {
+        shuffle: Shuffle,
+    },
+    SetRepeat {
+        repeat: Repeat,
     },
     AddToQueue {
         item: PlaybackItem,
     },
+    /// Queue `items` right after the current item, instead of after the
+    /// previously queued ones.
+    AddToQueueNext {
+        items: Vec<PlaybackItem>,
+    },
+    /// Move an item of the up-next list, indices are the ones reported in
+    /// `PlayerEvent::QueueChanged`.
+    MoveQueueItem {
+        from: usize,
+        to: usize,
+    },
+    /// Remove an item of the up-next list.
+    RemoveQueueItem {
+        index: usize,
+    },
+    /// Remove everything that would be played after the current item.
+    ClearUpNext,
+    /// Skip items that are already playing or up next when adding to the
+    /// queue, see `PlayerEvent::QueueDuplicates`.
+    SetQueueDeduplication {
+        enabled: bool,
+    },
+    /// Remove the up-next items that repeat an earlier one, or the current
+    /// item.
+    RemoveQueueDuplicates,
+    /// Place the fades of `item_id` according to its analysis instead of using
+    /// fixed-length fades.
+    SetTrackAnalysis {
+        item_id: ItemId,
+        analysis: TrackAnalysis,
+    },
     /// Change playback volume to a value in 0.0..=1.0 range.
     SetVolume {
         volume: f64,
     },
+    /// Stop playing and play a generated test signal instead, until `Stop`.
+    PlayTestSignal {
+        signal: TestSignal,
+    },
+}
+
+impl PlayerCommand {
+    /// Commands that move the playback somewhere else, and are superseded by a
+    /// later command doing the same.
+    fn is_navigation(&self) -> bool {
+        matches!(
+            self,
+            Self::LoadQueue { .. }
+                | Self::LoadAndPlay { .. }
+                | Self::Preload { .. }
+                | Self::Previous
+                | Self::Next
+                | Self::Seek { .. }
+        )
+    }
 }
 
 pub enum PlayerEvent {
-    Command(PlayerCommand),
+    /// Commands are waiting in the command queue, see `Player::command_sender`.
+    CommandsPending,
     /// Track has started loading.  `Loaded` follows.
     Loading {
         item: PlaybackItem,
@@ -465,6 +1087,12 @@ pub enum PlayerEvent {
         item: PlaybackItem,
         result: Result<LoadedPlaybackItem, Error>,
     },
+    /// The current item has been loaded again in a lower quality tier.
+    /// `QualityChanged` follows in case of success.
+    QualityLoaded {
+        item: PlaybackItem,
+        result: Result<LoadedPlaybackItem, Error>,
+    },
     /// Player has started playing new track.  `Position` events will follow.
     Playing {
         path: MediaPath,
@@ -490,11 +1118,29 @@ pub enum PlayerEvent {
         path: MediaPath,
         position: Duration,
     },
+    /// The fade-out of a track has started.  `Playing` follows if the next
+    /// track starts on top of it, otherwise `EndOfTrack` follows as usual.
+    FadingOut { path: MediaPath },
     /// Player has finished playing a track.  `Loading` or `Playing` might
     /// follow if the queue is not empty, `Stopped` will follow if it is.
     EndOfTrack,
     /// The queue is empty.
     Stopped,
+    /// Periodic wake-up used by the watchdog to check on the playback.
+    WatchdogTick,
+    /// Playback stalled and the playback worker was restarted.  Playing
+    /// continues from `position`.
+    Recovered { path: MediaPath, position: Duration },
+    /// Playback of the current item continues from a file of another quality
+    /// tier, see `PlaybackConfig::adaptive_quality`.
+    QualityChanged { path: MediaPath },
+    /// The items to be played after the current one have changed.
+    QueueChanged { up_next: Vec<PlaybackItem> },
+    /// `count` items were skipped as duplicates when adding to the queue, or
+    /// `removed` from it by `PlayerCommand::RemoveQueueDuplicates`.
+    QueueDuplicates { count: usize, removed: bool },
+    /// Another application started or stopped playing audio.
+    Interruption { active: bool },
 }
 
 enum PlayerState {
@@ -1,14 +1,48 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    mem,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
 use rand::prelude::SliceRandom;
 
-use super::PlaybackItem;
+use crate::{error::Error, item_id::ItemId};
 
-#[derive(Debug, Default)]
-pub enum QueueBehavior {
+use super::{shuffle::smart_shuffle, PlaybackItem, PlayerCommand, PlayerEvent};
+
+/// Maximum number of commands waiting for the player thread.  Bursts of
+/// navigation commands are coalesced, so this is only reached if the player
+/// thread stops draining the queue.
+pub const COMMAND_QUEUE_CAPACITY: usize = 256;
+
+/// Two presses of Previous closer than this are a double press.
+pub const DOUBLE_PRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Presses closer than this are taken for one, like a bouncing key or a key
+/// delivered both by the window and the media controls.
+pub const PRESS_DEBOUNCE: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shuffle {
     #[default]
-    Sequential,
+    Off,
     Random,
-    LoopTrack,
-    LoopAll,
+    /// Shuffle that keeps tracks by the same artist, or from the same album,
+    /// apart.
+    Smart,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Repeat {
+    #[default]
+    Off,
+    /// Start over once the last item played.
+    All,
+    /// Play the current item again when it ends.  Skipping still moves on.
+    One,
 }
 
 pub struct Queue {
@@ -17,7 +51,10 @@ pub struct Queue {
     position: usize,
     user_items_position: usize,
     positions: Vec<usize>,
-    behavior: QueueBehavior,
+    shuffle: Shuffle,
+    repeat: Repeat,
+    /// Skip items that are already playing or up next when adding.
+    deduplicate: bool,
 }
 
 impl Queue {
@@ -28,7 +65,9 @@ impl Queue {
             position: 0,
             user_items_position: 0,
             positions: Vec::new(),
-            behavior: QueueBehavior::default(),
+            shuffle: Shuffle::default(),
+            repeat: Repeat::default(),
+            deduplicate: false,
         }
     }
 
@@ -45,8 +84,61 @@ impl Queue {
         self.compute_positions();
     }
 
-    pub fn add(&mut self, item: PlaybackItem) {
+    /// Queue `item` after anything added earlier.  Returns `false` if it was
+    /// skipped as a duplicate.
+    pub fn add(&mut self, item: PlaybackItem) -> bool {
+        if self.deduplicate && self.queued_ids().contains(&item.item_id) {
+            return false;
+        }
         self.user_items.push(item);
+        true
+    }
+
+    /// Queue `items` to be played right after the current item, before
+    /// anything added earlier.  Returns the number of items skipped as
+    /// duplicates.
+    pub fn add_next(&mut self, mut items: Vec<PlaybackItem>) -> usize {
+        let count = items.len();
+        if self.deduplicate {
+            let mut queued = self.queued_ids();
+            items.retain(|item| queued.insert(item.item_id));
+        }
+        let skipped = count - items.len();
+        let position = self.user_items_position;
+        self.user_items.splice(position..position, items);
+        skipped
+    }
+
+    /// Skip items that are already playing or up next when adding.  Items
+    /// that were played before are queued again.
+    pub fn set_deduplicate(&mut self, deduplicate: bool) {
+        self.deduplicate = deduplicate;
+    }
+
+    /// Remove the up-next items that repeat an earlier one, or the current
+    /// item.  Returns the number of items removed.
+    pub fn remove_duplicates(&mut self) -> usize {
+        let mut removed = 0;
+        let mut seen: HashSet<_> = self
+            .get_current()
+            .map(|item| item.item_id)
+            .into_iter()
+            .collect();
+        self.edit_up_next(|up_next| {
+            let count = up_next.len();
+            up_next.retain(|item| seen.insert(item.item_id));
+            removed = count - up_next.len();
+        });
+        removed
+    }
+
+    /// IDs of the current item and the up-next ones.
+    fn queued_ids(&self) -> HashSet<ItemId> {
+        self.get_current()
+            .into_iter()
+            .chain(&self.up_next())
+            .map(|item| item.item_id)
+            .collect()
     }
 
     fn handle_added_queue(&mut self) {
@@ -61,11 +153,72 @@ impl Queue {
         }
     }
 
-    pub fn set_behaviour(&mut self, behavior: QueueBehavior) {
-        self.behavior = behavior;
+    /// Items that will be played after the current one, in the order they will
+    /// be played: items added by the user first, then the rest of the context.
+    pub fn up_next(&self) -> Vec<PlaybackItem> {
+        let split = (self.position + 1).min(self.positions.len());
+        self.user_items[self.user_items_position..]
+            .iter()
+            .chain(self.positions[split..].iter().map(|&p| &self.items[p]))
+            .copied()
+            .collect()
+    }
+
+    /// Move the up-next item at index `from` to index `to`.
+    pub fn move_up_next(&mut self, from: usize, to: usize) {
+        self.edit_up_next(|up_next| {
+            if from < up_next.len() {
+                let item = up_next.remove(from);
+                up_next.insert(to.min(up_next.len()), item);
+            }
+        });
+    }
+
+    /// Remove the up-next item at `index`.
+    pub fn remove_up_next(&mut self, index: usize) {
+        self.edit_up_next(|up_next| {
+            if index < up_next.len() {
+                up_next.remove(index);
+            }
+        });
+    }
+
+    /// Remove everything that would be played after the current item.
+    pub fn clear_up_next(&mut self) {
+        self.edit_up_next(|up_next| up_next.clear());
+    }
+
+    fn edit_up_next(&mut self, edit: impl FnOnce(&mut Vec<PlaybackItem>)) {
+        let mut up_next = self.up_next();
+        edit(&mut up_next);
+
+        self.user_items.truncate(self.user_items_position);
+        if self.positions.is_empty() {
+            // Nothing is playing, so there is only the user queue to keep.
+            self.user_items.extend(up_next);
+            return;
+        }
+        // Lay the items out in the order they are played in, so the edited order
+        // is kept even in shuffle mode.
+        let split = (self.position + 1).min(self.positions.len());
+        let mut items: Vec<_> = self.positions[..split]
+            .iter()
+            .map(|&p| self.items[p])
+            .collect();
+        items.extend(up_next);
+        self.positions = (0..items.len()).collect();
+        self.items = items;
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: Shuffle) {
+        self.shuffle = shuffle;
         self.compute_positions();
     }
 
+    pub fn set_repeat(&mut self, repeat: Repeat) {
+        self.repeat = repeat;
+    }
+
     fn compute_positions(&mut self) {
         // In the case of switching away from shuffle, the position should be set back to
         // where it appears in the actual playlist order.
@@ -77,13 +230,18 @@ impl Queue {
         // Start with an ordered 1:1 mapping.
         self.positions = (0..self.items.len()).collect();
 
-        if let QueueBehavior::Random = self.behavior {
+        if self.shuffle != Shuffle::Off {
             // Swap the current position with the first item, so we will start from the
             // beginning, with the full queue ahead of us.  Then shuffle the rest of the
             // items and set the position to 0.
             if self.positions.len() > 1 {
                 self.positions.swap(0, self.position);
-                self.positions[1..].shuffle(&mut rand::rng());
+                let rest = &mut self.positions[1..];
+                if self.shuffle == Shuffle::Smart {
+                    smart_shuffle(&self.items, rest, &mut rand::rng());
+                } else {
+                    rest.shuffle(&mut rand::rng());
+                }
             }
             self.position = 0;
         } else {
@@ -95,6 +253,11 @@ impl Queue {
         self.position = self.previous_position();
     }
 
+    /// Go back to the first item, in the order the items are played.
+    pub fn skip_to_start(&mut self) {
+        self.position = 0;
+    }
+
     pub fn skip_to_next(&mut self) {
         self.handle_added_queue();
         self.position = self.next_position();
@@ -122,20 +285,13 @@ impl Queue {
     }
 
     fn previous_position(&self) -> usize {
-        match self.behavior {
-            QueueBehavior::Sequential
-            | QueueBehavior::Random
-            | QueueBehavior::LoopTrack
-            | QueueBehavior::LoopAll => self.position.saturating_sub(1),
-        }
+        self.position.saturating_sub(1)
     }
 
     fn next_position(&self) -> usize {
-        match self.behavior {
-            QueueBehavior::Sequential | QueueBehavior::Random | QueueBehavior::LoopTrack => {
-                self.position + 1
-            }
-            QueueBehavior::LoopAll => {
+        match self.repeat {
+            Repeat::Off | Repeat::One => self.position + 1,
+            Repeat::All => {
                 if self.items.is_empty() {
                     0
                 } else {
@@ -146,16 +302,172 @@ impl Queue {
     }
 
     fn following_position(&self) -> usize {
-        match self.behavior {
-            QueueBehavior::Sequential | QueueBehavior::Random => self.position + 1,
-            QueueBehavior::LoopTrack => self.position,
-            QueueBehavior::LoopAll => {
-                if self.items.is_empty() {
-                    0
-                } else {
-                    (self.position + 1) % self.items.len()
-                }
+        match self.repeat {
+            Repeat::One => self.position,
+            Repeat::Off | Repeat::All => self.next_position(),
+        }
+    }
+}
+
+/// Tells double presses of Previous from single ones.  This is independent
+/// of the position in the track, which decides what a single press does.
+#[derive(Debug, Default)]
+pub struct PreviousPresses {
+    last: Option<Instant>,
+}
+
+impl PreviousPresses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a press at `now`.  Returns `None` for a bounce of the previous
+    /// press, which should be ignored, and `Some(true)` for the second press of
+    /// a double press.
+    pub fn press(&mut self, now: Instant) -> Option<bool> {
+        let since_last = self.last.map(|last| now.saturating_duration_since(last));
+        match since_last {
+            Some(interval) if interval < PRESS_DEBOUNCE => None,
+            Some(interval) if interval < DOUBLE_PRESS_INTERVAL => {
+                // A third quick press starts a new pair.
+                self.last = None;
+                Some(true)
+            }
+            _ => {
+                self.last = Some(now);
+                Some(false)
             }
         }
     }
 }
+
+/// Cloneable handle used to send commands to the player.
+///
+/// Commands are put into a shared `CommandQueue` and the player thread is woken
+/// up with `PlayerEvent::CommandsPending`, so sending never blocks and a full
+/// queue never disconnects the sender.
+#[derive(Clone)]
+pub struct CommandSender {
+    queue: Arc<CommandQueue>,
+    wake: Sender<PlayerEvent>,
+}
+
+impl CommandSender {
+    pub(crate) fn new(queue: Arc<CommandQueue>, wake: Sender<PlayerEvent>) -> Self {
+        Self { queue, wake }
+    }
+
+    /// Enqueue a command for the player thread.  If the queue is full, the
+    /// command is dropped with a warning.  Fails only if the player is gone.
+    pub fn send(&self, cmd: PlayerCommand) -> Result<(), Error> {
+        match self.queue.push(cmd) {
+            Ok(true) => {
+                self.wake.send(PlayerEvent::CommandsPending)?;
+            }
+            Ok(false) => {
+                // The player has already been woken up and will pick the
+                // command up with the rest of the batch.
+            }
+            Err(_) => {
+                log::warn!("player command queue is full, dropping command");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bounded queue of commands waiting for the player thread.
+///
+/// Commands are handled in the order they were sent.  Rapid bursts of `Next`,
+/// `Previous` and `Seek` are coalesced so that only the latest intent is kept,
+/// which keeps the player responsive even when the controls are spammed.
+/// Coalescing only looks at the commands pushed since the last one of another
+/// kind, so a `Pause` sent after `LoadQueue` still runs after it.
+pub struct CommandQueue {
+    state: Mutex<CommandQueueState>,
+    capacity: usize,
+}
+
+#[derive(Default)]
+struct CommandQueueState {
+    commands: VecDeque<PlayerCommand>,
+    wake_pending: bool,
+}
+
+impl CommandQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(CommandQueueState::default()),
+            capacity,
+        }
+    }
+
+    /// Push a command, coalescing it with the pending ones.  Returns `true` if
+    /// the consumer needs to be woken up, or gives the command back if the
+    /// queue is full.
+    pub fn push(&self, cmd: PlayerCommand) -> Result<bool, PlayerCommand> {
+        let mut state = self.state.lock();
+        state.coalesce(&cmd);
+        if state.commands.len() >= self.capacity {
+            return Err(cmd);
+        }
+        state.commands.push_back(cmd);
+        Ok(!mem::replace(&mut state.wake_pending, true))
+    }
+
+    /// Take all pending commands in the order they were sent.
+    pub fn drain(&self) -> Vec<PlayerCommand> {
+        let mut state = self.state.lock();
+        state.wake_pending = false;
+        state.commands.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CommandQueueState {
+    /// Remove the trailing pending commands that are superseded by `cmd`.
+    fn coalesce(&mut self, cmd: &PlayerCommand) {
+        let superseded: fn(&PlayerCommand) -> bool = match cmd {
+            // Stopping or replacing the queue makes any pending navigation
+            // pointless.
+            PlayerCommand::Stop => |c| c.is_navigation() || matches!(c, PlayerCommand::Stop),
+            PlayerCommand::LoadQueue { .. } | PlayerCommand::LoadAndPlay { .. } => {
+                |c| c.is_navigation()
+            }
+            PlayerCommand::Next | PlayerCommand::Previous => |c| {
+                matches!(
+                    c,
+                    PlayerCommand::Next | PlayerCommand::Previous | PlayerCommand::Seek { .. }
+                )
+            },
+            PlayerCommand::Seek { .. } => |c| matches!(c, PlayerCommand::Seek { .. }),
+            PlayerCommand::Preload { .. } => |c| matches!(c, PlayerCommand::Preload { .. }),
+            PlayerCommand::Configure { .. } => |c| matches!(c, PlayerCommand::Configure { .. }),
+            PlayerCommand::SetVolume { .. } => |c| matches!(c, PlayerCommand::SetVolume { .. }),
+            PlayerCommand::Pause
+            | PlayerCommand::Resume
+            | PlayerCommand::PauseOrResume
+            | PlayerCommand::SetShuffle { .. }
+            | PlayerCommand::SetRepeat { .. }
+            | PlayerCommand::AddToQueue { .. }
+            | PlayerCommand::AddToQueueNext { .. }
+            | PlayerCommand::MoveQueueItem { .. }
+            | PlayerCommand::RemoveQueueItem { .. }
+            | PlayerCommand::ClearUpNext
+            | PlayerCommand::SetQueueDeduplication { .. }
+            | PlayerCommand::RemoveQueueDuplicates
+            | PlayerCommand::SetTrackAnalysis { .. }
+            | PlayerCommand::PlayTestSignal { .. } => return,
+        };
+        while self.commands.back().is_some_and(superseded) {
+            self.commands.pop_back();
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::item_id::ItemId;
+
+use super::item::PlaybackItem;
+
+/// How far items may drift from their evenly spaced slots, as a fraction of the
+/// spacing.  Keeps the order from looking mechanical while still making sure
+/// two items of a group are never next to each other if it can be avoided.
+const JITTER: f64 = 0.2;
+
+#[derive(Hash, Eq, PartialEq)]
+enum ArtistKey {
+    Artist(ItemId),
+    /// Items with an unknown artist are each kept on their own.
+    Unknown(usize),
+}
+
+/// Reorder `indices` (pointing into `items`) so that items by the same artist
+/// are spread evenly over the whole order, and items of one artist's albums are
+/// interleaved with each other.
+pub fn smart_shuffle(items: &[PlaybackItem], indices: &mut [usize], rng: &mut impl Rng) {
+    let mut artists: HashMap<ArtistKey, HashMap<Option<ItemId>, Vec<usize>>> = HashMap::new();
+    for &index in indices.iter() {
+        let group = items[index].group;
+        let artist = match group.artist {
+            Some(id) => ArtistKey::Artist(id),
+            None => ArtistKey::Unknown(index),
+        };
+        artists
+            .entry(artist)
+            .or_default()
+            .entry(group.album)
+            .or_default()
+            .push(index);
+    }
+
+    let artists = artists
+        .into_values()
+        .map(|albums| {
+            let albums = albums
+                .into_values()
+                .map(|mut album| {
+                    album.shuffle(rng);
+                    album
+                })
+                .collect();
+            spread(albums, rng)
+        })
+        .collect();
+
+    for (slot, index) in indices.iter_mut().zip(spread(artists, rng)) {
+        *slot = index;
+    }
+}
+
+/// Merge the groups into one order, giving the items of every group evenly
+/// spaced slots over `[0, 1)` that start at a random offset and are moved by a
+/// small random jitter.  The order inside each group is kept.
+fn spread(groups: Vec<Vec<usize>>, rng: &mut impl Rng) -> Vec<usize> {
+    let mut slots = Vec::new();
+    for group in groups {
+        let spacing = 1.0 / group.len() as f64;
+        let offset = rng.random::<f64>() * spacing;
+        for (k, index) in group.into_iter().enumerate() {
+            let jitter = (rng.random::<f64>() - 0.5) * JITTER * spacing;
+            slots.push((offset + k as f64 * spacing + jitter, index));
+        }
+    }
+    slots.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    slots.into_iter().map(|(_, index)| index).collect()
+}
@@ -13,8 +13,18 @@ use rangemap::RangeSet;
 use tempfile::NamedTempFile;
 
 pub enum StreamRequest {
-    Preload { offset: u64, length: u64 },
-    Blocked { offset: u64 },
+    Preload {
+        offset: u64,
+        length: u64,
+    },
+    Blocked {
+        offset: u64,
+    },
+    /// A reader seeked to `offset`, which is not downloaded yet.  Unlike
+    /// `Blocked`, this is expected and not a sign of a slow connection.
+    Seek {
+        offset: u64,
+    },
 }
 
 pub struct StreamStorage {
@@ -28,6 +38,8 @@ pub struct StreamReader {
     reader: File,
     data_map: Arc<StreamDataMap>,
     req_sender: Sender<StreamRequest>,
+    /// Set by a seek to data that is not downloaded, until it is read.
+    seeking: bool,
 }
 
 pub struct StreamWriter {
@@ -91,6 +103,7 @@ impl StreamStorage {
             reader: self.file.reopen()?, // Re-opened files have a starting seek position.
             data_map: self.data_map.clone(),
             req_sender: self.req_sender.clone(),
+            seeking: false,
         })
     }
 
@@ -101,6 +114,18 @@ impl StreamStorage {
         })
     }
 
+    /// Request all the data no reader has asked for yet, to have the file
+    /// downloaded completely.
+    pub fn request_remaining(&self) {
+        let total_size = self.data_map.total_size;
+        for (offset, length) in self.data_map.not_yet_requested(0, total_size) {
+            self.data_map.mark_as_requested(offset, length);
+            self.req_sender
+                .send(StreamRequest::Preload { offset, length })
+                .expect("Data request channel was closed");
+        }
+    }
+
     pub fn receiver(&self) -> &Receiver<StreamRequest> {
         &self.req_receiver
     }
@@ -163,6 +188,23 @@ impl Seek for StreamWriter {
 const MINIMUM_READ_LENGTH: u64 = 1024 * 64;
 const PREFETCH_READ_LENGTH: u64 = 1024 * 256;
 
+impl StreamReader {
+    /// Request the data in `position..position + length` that was not
+    /// requested yet, in ranges of at least `MINIMUM_READ_LENGTH` bytes.
+    fn request_ahead(&self, position: u64, length: u64) {
+        for (pos, len) in self.data_map.not_yet_requested(position, length) {
+            let req_len = len.max(MINIMUM_READ_LENGTH);
+            self.data_map.mark_as_requested(pos, req_len);
+            self.req_sender
+                .send(StreamRequest::Preload {
+                    offset: pos,
+                    length: req_len,
+                })
+                .expect("Data request channel was closed");
+        }
+    }
+}
+
 impl Read for StreamReader {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let position = self.reader.stream_position()?;
@@ -175,26 +217,24 @@ impl Read for StreamReader {
         // Make sure that at least `PREFETCH_READ_LENGTH` bytes in front of the reading
         // head is requested.
         let prefetch_len = needed_len.max(PREFETCH_READ_LENGTH).min(remaining_len);
-        for (pos, len) in self.data_map.not_yet_requested(position, prefetch_len) {
-            let req_len = len.max(MINIMUM_READ_LENGTH);
-            self.data_map.mark_as_requested(pos, req_len);
-            self.req_sender
-                .send(StreamRequest::Preload {
-                    offset: pos,
-                    length: req_len,
-                })
-                .expect("Data request channel was closed");
-        }
+        self.request_ahead(position, prefetch_len);
 
         // Block and wait until at least a part of the range is available, and read it.
+        let seeking = self.seeking;
         let ready_to_read_len = self.data_map.wait_for(position, |offset| {
             // Notify the servicing thread we are blocked, so it can possibly prioritize the
-            // blocked offset.
+            // blocked offset.  Waiting for the data of a seek is not a rebuffer.
+            let request = if seeking {
+                StreamRequest::Seek { offset }
+            } else {
+                StreamRequest::Blocked { offset }
+            };
             self.req_sender
-                .send(StreamRequest::Blocked { offset })
+                .send(request)
                 .expect("Data request channel was closed");
         });
         assert!(ready_to_read_len > 0);
+        self.seeking = false;
         self.reader
             .read(&mut buf[..ready_to_read_len.min(needed_len) as usize])
     }
@@ -202,7 +242,21 @@ impl Read for StreamReader {
 
 impl Seek for StreamReader {
     fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
-        self.reader.seek(pos)
+        let previous = self.reader.stream_position()?;
+        let position = self.reader.seek(pos)?;
+        let remaining_len = self.data_map.remaining(position);
+        // Ranges downloaded before, e.g. on an earlier seek to the same place,
+        // stay in the data map and are read without waiting.
+        if position != previous && remaining_len > 0 && !self.data_map.is_downloaded(position) {
+            // Request the data at the target right away, ahead of the
+            // sequential prefetch, instead of on the next read.
+            self.request_ahead(position, PREFETCH_READ_LENGTH.min(remaining_len));
+            self.req_sender
+                .send(StreamRequest::Seek { offset: position })
+                .expect("Data request channel was closed");
+            self.seeking = true;
+        }
+        Ok(position)
     }
 }
 
@@ -252,6 +306,11 @@ impl StreamDataMap {
         self.condvar.notify_all();
     }
 
+    /// Returns true if the byte at `offset` is downloaded.
+    fn is_downloaded(&self, offset: u64) -> bool {
+        self.downloaded.lock().contains(&offset)
+    }
+
     /// Block, waiting until at least some data at given offset is downloaded.
     /// Returns length that is available.  See `self.mark_as_downloaded`.
     fn wait_for(&self, offset: u64, blocking_callback: impl Fn(u64)) -> u64 {
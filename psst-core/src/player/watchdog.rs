@@ -0,0 +1,74 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Sender;
+
+use super::PlayerEvent;
+
+/// How often the player is woken up to check on the playback.
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Playback is considered stalled if the play-head did not move for this long.
+const STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Give up after this many recoveries without any progress in between.
+const MAX_CONSECUTIVE_RECOVERIES: usize = 3;
+
+/// Keeps track of the play-head, so the player can notice that the playback
+/// worker or the audio callback stopped advancing it.
+pub struct Watchdog {
+    last_position: Duration,
+    last_progress: Instant,
+    recoveries: usize,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Self {
+            last_position: Duration::default(),
+            last_progress: Instant::now(),
+            recoveries: 0,
+        }
+    }
+
+    /// Periodically send `PlayerEvent::WatchdogTick` into the player channel.
+    /// The thread ends once the player, and with it the receiving side of the
+    /// channel, is dropped.
+    pub fn spawn_ticker(sender: Sender<PlayerEvent>) {
+        thread::spawn(move || loop {
+            thread::sleep(CHECK_INTERVAL);
+            if sender.send(PlayerEvent::WatchdogTick).is_err() {
+                break;
+            }
+        });
+    }
+
+    /// Playback has (re)started at `position`, start measuring from now.
+    pub fn reset(&mut self, position: Duration) {
+        self.last_position = position;
+        self.last_progress = Instant::now();
+    }
+
+    pub fn report_position(&mut self, position: Duration) {
+        if position != self.last_position {
+            self.reset(position);
+            self.recoveries = 0;
+        }
+    }
+
+    pub fn is_stalled(&self) -> bool {
+        self.last_progress.elapsed() >= STALL_TIMEOUT
+    }
+
+    /// Returns `false` if we already tried to recover too many times in a row.
+    pub fn try_recover(&mut self) -> bool {
+        if self.recoveries >= MAX_CONSECUTIVE_RECOVERIES {
+            false
+        } else {
+            self.recoveries += 1;
+            true
+        }
+    }
+}
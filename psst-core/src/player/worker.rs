@@ -1,7 +1,7 @@
 use std::{
     ops::Range,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
     time::Duration,
@@ -18,15 +18,22 @@ use crate::{
     actor::{Act, Actor, ActorHandle},
     audio::{
         decode::AudioDecoder,
-        equalizer::{Equalizer, EqualizerConfig},
-        output::{AudioSink, DefaultAudioSink},
+        generator::{SignalGenerator, TestSignal},
+        output::{latency_to_frames, AudioSink, DefaultAudioSink},
+        processor::{AudioProcessor, DspChain, DspConfig},
         resample::ResamplingQuality,
-        source::{AudioSource, ResampledSource, StereoMappedSource},
+        source::{
+            AudioSource, CrossfadeSource, ProcessedSource, ResampledSource, StereoMappedSource,
+            TempoSource, TempoUpdate,
+        },
+        spectrum::{Spectrum, SpectrumSource},
+        stretch::Tempo,
     },
     error::Error,
 };
 
 use super::{
+    crossfade::{FadeEnvelope, FadePoints},
     file::{MediaFile, MediaPath},
     LoadedPlaybackItem, PlayerEvent,
 };
@@ -34,7 +41,22 @@ use super::{
 pub struct PlaybackManager {
     sink: DefaultAudioSink,
     event_send: Sender<PlayerEvent>,
-    current: Option<(MediaPath, Sender<Msg>, Sender<EqualizerConfig>)>,
+    current: Option<CurrentPlayback>,
+    /// Sources to start on top of the one that is playing, see `crossfade`.
+    crossfade: Option<Sender<Box<dyn AudioSource>>>,
+    /// DSP updates for the test signal that is playing, if any.
+    test_signal: Option<Sender<DspConfig>>,
+}
+
+/// Channels to the source of the item that is playing.
+struct CurrentPlayback {
+    path: MediaPath,
+    worker: Sender<Msg>,
+    dsp: Sender<DspConfig>,
+    fade: Sender<Option<FadePoints>>,
+    tempo: Sender<TempoUpdate>,
+    /// Set once the next item has started on top of this one.
+    superseded: Arc<AtomicBool>,
 }
 
 impl PlaybackManager {
@@ -43,43 +65,140 @@ impl PlaybackManager {
             sink,
             event_send,
             current: None,
+            crossfade: None,
+            test_signal: None,
+        }
+    }
+
+    pub fn play(&mut self, loaded: LoadedPlaybackItem, fade: Option<FadePoints>, tempo: Tempo) {
+        self.load(loaded, fade, tempo);
+        self.sink.resume();
+    }
+
+    /// Like `play`, but leaves the sink paused.
+    pub fn load(&mut self, loaded: LoadedPlaybackItem, fade: Option<FadePoints>, tempo: Tempo) {
+        let quality = loaded.resampling_quality;
+        let source = self.start(loaded, fade, tempo);
+        // Prefer switching the output to the rate of the source over resampling it.
+        let sink_rate = self.sink.negotiate_sample_rate(source.sample_rate());
+        let source = self.fit_to_sink(source, sink_rate, quality);
+        let (crossfade_sender, crossfade_receiver) = crossbeam_channel::unbounded();
+        self.sink
+            .play(CrossfadeSource::new(source, crossfade_receiver));
+        self.crossfade = Some(crossfade_sender);
+    }
+
+    /// Start playing `loaded` on top of the item that is playing, which goes on
+    /// until the end of its fade-out.
+    pub fn crossfade(
+        &mut self,
+        loaded: LoadedPlaybackItem,
+        fade: Option<FadePoints>,
+        tempo: Tempo,
+    ) {
+        let Some(crossfade) = self.crossfade.clone() else {
+            self.play(loaded, fade, tempo);
+            return;
+        };
+        if let Some(current) = &self.current {
+            current.superseded.store(true, Ordering::Relaxed);
+        }
+        let quality = loaded.resampling_quality;
+        let source = self.start(loaded, fade, tempo);
+        // The outgoing item is still playing, so the output keeps its rate.
+        let source = self.fit_to_sink(source, self.sink.sample_rate(), quality);
+        if crossfade.send(source).is_err() {
+            log::debug!("failed to send the next source to the crossfade");
         }
     }
 
-    pub fn play(&mut self, loaded: LoadedPlaybackItem) {
+    /// Start decoding `loaded` and make it the current item.
+    fn start(
+        &mut self,
+        loaded: LoadedPlaybackItem,
+        fade: Option<FadePoints>,
+        tempo: Tempo,
+    ) -> impl AudioSource {
         let path = loaded.file.path();
-        let (source, worker_sender, eq_sender) = DecoderSource::new(
+        let (source, senders) = DecoderSource::new(
             loaded.file,
             loaded.source,
             loaded.norm_factor,
-            loaded.equalizer_config,
+            &loaded.dsp,
+            fade,
+            self.sink.latency().unwrap_or_default(),
             self.event_send.clone(),
         );
-        self.current = Some((path, worker_sender, eq_sender));
-        if source.sample_rate() == self.sink.sample_rate()
-            && source.channel_count() == self.sink.channel_count()
+        // The tempo is changed after the position reporting in `DecoderSource`, so
+        // the reported positions stay in the time of the original item.
+        self.test_signal = None;
+        let (tempo_sender, tempo_receiver) = crossbeam_channel::unbounded();
+        let source = TempoSource::new(source, tempo, tempo_receiver);
+        // Analysed after the tempo change, so that the visualizer follows what is heard.
+        let source = SpectrumSource::new(source, Spectrum::global());
+        let DecoderSenders {
+            worker,
+            dsp,
+            fade,
+            superseded,
+        } = senders;
+        self.current = Some(CurrentPlayback {
+            path,
+            worker,
+            dsp,
+            fade,
+            tempo: tempo_sender,
+            superseded,
+        });
+        source
+    }
+
+    fn fit_to_sink(
+        &self,
+        source: impl AudioSource,
+        sink_rate: u32,
+        quality: ResamplingQuality,
+    ) -> Box<dyn AudioSource> {
+        if source.sample_rate() == sink_rate && source.channel_count() == self.sink.channel_count()
         {
             // We can start playing the source right away.
-            self.sink.play(source);
+            Box::new(source)
         } else {
-            // Some output streams have different sample rate than the source, so we need to
+            // The output device could not switch to the rate of the source, so we need to
             // resample before pushing to the sink.
-            let source = ResampledSource::new(
-                source,
-                self.sink.sample_rate(),
-                ResamplingQuality::SincMediumQuality,
-            );
+            let source = ResampledSource::new(source, sink_rate, quality);
             // Source output streams also have a different channel count. Map the stereo
             // channels and silence the others.
-            let source = StereoMappedSource::new(source, self.sink.channel_count());
-            self.sink.play(source);
+            Box::new(StereoMappedSource::new(source, self.sink.channel_count()))
         }
+    }
+
+    /// Play `signal` at the rate and channel count of the output, so that
+    /// every channel can be checked.  Runs until something else is played or
+    /// the sink is stopped.
+    pub fn play_test_signal(&mut self, signal: TestSignal, dsp: &DspConfig) {
+        self.current = None;
+        self.crossfade = None;
+        let (dsp_sender, dsp_receiver) = crossbeam_channel::unbounded();
+        let source =
+            SignalGenerator::new(signal, self.sink.sample_rate(), self.sink.channel_count())
+                .with_spoken_channels();
+        self.sink
+            .play(ProcessedSource::new(source, dsp, dsp_receiver));
         self.sink.resume();
+        self.test_signal = Some(dsp_sender);
     }
 
     pub fn seek(&self, position: Duration) {
-        if let Some((path, worker, _)) = &self.current {
+        if let Some(CurrentPlayback {
+            path,
+            worker,
+            tempo,
+            ..
+        }) = &self.current
+        {
             let _ = worker.send(Msg::Seek(position));
+            let _ = tempo.send(TempoUpdate::Seek);
 
             // Because the position events are sent in the `DecoderSource`, doing this here
             // is slightly hacky. The alternative would be propagating `event_send` into the
@@ -91,10 +210,29 @@ impl PlaybackManager {
         }
     }
 
-    pub fn update_equalizer(&self, config: EqualizerConfig) {
-        if let Some((_, _, eq_sender)) = &self.current {
-            if let Err(err) = eq_sender.send(config) {
-                log::debug!("failed to send equalizer update to playback worker: {err}");
+    pub fn update_dsp(&self, config: DspConfig) {
+        if let Some(test_signal) = &self.test_signal {
+            let _ = test_signal.send(config.clone());
+        }
+        if let Some(current) = &self.current {
+            if let Err(err) = current.dsp.send(config) {
+                log::debug!("failed to send DSP update to playback worker: {err}");
+            }
+        }
+    }
+
+    pub fn update_fade(&self, fade: Option<FadePoints>) {
+        if let Some(current) = &self.current {
+            if let Err(err) = current.fade.send(fade) {
+                log::debug!("failed to send fade update to playback worker: {err}");
+            }
+        }
+    }
+
+    pub fn update_tempo(&self, tempo: Tempo) {
+        if let Some(current) = &self.current {
+            if let Err(err) = current.tempo.send(TempoUpdate::Set(tempo)) {
+                log::debug!("failed to send tempo update to playback worker: {err}");
             }
         }
     }
@@ -110,11 +248,27 @@ pub struct DecoderSource {
     precision: u64,
     reported: u64,
     end_of_track: bool,
-    norm_factor: f32,
-    equalizer: Equalizer,
-    equalizer_updates: Receiver<EqualizerConfig>,
+    dsp: DspChain,
+    dsp_updates: Receiver<DspConfig>,
+    fade: Option<FadeEnvelope>,
+    fade_updates: Receiver<Option<FadePoints>>,
+    /// `PlayerEvent::FadingOut` was sent for the current fade-out.
+    fading_out: bool,
+    /// The next item plays on top of this one, which only plays out its
+    /// fade-out without reporting anything more.
+    superseded: Arc<AtomicBool>,
     signal_spec: SignalSpec,
     time_base: TimeBase,
+    /// Samples the output buffer holds before they are heard.
+    output_latency: u64,
+}
+
+/// Channels for updating a playing `DecoderSource`.
+struct DecoderSenders {
+    worker: Sender<Msg>,
+    dsp: Sender<DspConfig>,
+    fade: Sender<Option<FadePoints>>,
+    superseded: Arc<AtomicBool>,
 }
 
 impl DecoderSource {
@@ -122,9 +276,11 @@ impl DecoderSource {
         file: MediaFile,
         decoder: AudioDecoder,
         norm_factor: f32,
-        equalizer_config: crate::audio::equalizer::EqualizerConfig,
+        dsp_config: &DspConfig,
+        fade: Option<FadePoints>,
+        output_latency: Duration,
         event_send: Sender<PlayerEvent>,
-    ) -> (Self, Sender<Msg>, Sender<EqualizerConfig>) {
+    ) -> (Self, DecoderSenders) {
         const REPORT_PRECISION: Duration = Duration::from_millis(900);
 
         // Gather the source signal parameters and compute how often we should report
@@ -137,6 +293,8 @@ impl DecoderSource {
         let precision = (signal_spec.rate as f64
             * signal_spec.channels.count() as f64
             * REPORT_PRECISION.as_secs_f64()) as u64;
+        let output_latency = latency_to_frames(output_latency, signal_spec.rate) as u64
+            * signal_spec.channels.count() as u64;
 
         // Create a ring-buffer for the decoded samples.  Worker thread is producing,
         // we are consuming in the `AudioSource` impl.
@@ -162,12 +320,21 @@ impl DecoderSource {
         });
         let _ = actor.send(Msg::Read);
 
-        // Create the equalizer for this audio stream
-        let equalizer = Equalizer::new(equalizer_config, signal_spec.rate);
+        // Create the processors for this audio stream.
+        let dsp = DspChain::new(
+            dsp_config,
+            norm_factor,
+            signal_spec.rate,
+            signal_spec.channels.count(),
+        );
 
-        let (eq_send, eq_recv) = crossbeam_channel::unbounded();
+        let (dsp_send, dsp_recv) = crossbeam_channel::unbounded();
+        let (fade_send, fade_recv) = crossbeam_channel::unbounded();
+        let fade =
+            fade.map(|points| points.envelope(signal_spec.rate, signal_spec.channels.count()));
 
         let actor_sender = actor.sender();
+        let superseded = Arc::new(AtomicBool::new(false));
 
         (
             Self {
@@ -175,19 +342,27 @@ impl DecoderSource {
                 actor,
                 consumer,
                 event_send,
-                norm_factor,
-                equalizer,
-                equalizer_updates: eq_recv,
+                dsp,
+                dsp_updates: dsp_recv,
+                fade,
+                fade_updates: fade_recv,
+                fading_out: false,
+                superseded: Arc::clone(&superseded),
                 signal_spec,
                 time_base,
+                output_latency,
                 total_samples,
                 end_of_track: false,
                 position,
                 precision,
                 reported: u64::MAX, // Something sufficiently distinct from any position.
             },
-            actor_sender,
-            eq_send,
+            DecoderSenders {
+                worker: actor_sender,
+                dsp: dsp_send,
+                fade: fade_send,
+                superseded,
+            },
         )
     }
 
@@ -199,6 +374,14 @@ impl DecoderSource {
         self.reported > pos || pos - self.reported >= self.precision
     }
 
+    /// Position of the samples that are coming out of the speakers, which lag
+    /// behind the ones read from the buffer by the DSP chain and the output
+    /// buffer.
+    fn audible_position(&self, position: u64) -> u64 {
+        let latency = self.dsp.latency() * self.signal_spec.channels.count();
+        position.saturating_sub(latency as u64 + self.output_latency)
+    }
+
     fn samples_to_duration(&self, samples: u64) -> Duration {
         let frames = samples / self.signal_spec.channels.count() as u64;
         let time = self.time_base.calc_time(frames);
@@ -212,20 +395,57 @@ impl AudioSource for DecoderSource {
             return 0;
         }
 
-        while let Ok(config) = self.equalizer_updates.try_recv() {
-            self.equalizer.update_config(config);
+        while let Ok(config) = self.dsp_updates.try_recv() {
+            self.dsp.update_config(&config);
+        }
+        while let Ok(fade) = self.fade_updates.try_recv() {
+            self.fade = fade.map(|points| {
+                points.envelope(self.signal_spec.rate, self.signal_spec.channels.count())
+            });
         }
         let written = self.consumer.read(output).unwrap_or(0);
 
-        // Apply the normalization factor.
-        output[..written]
-            .iter_mut()
-            .for_each(|s| *s *= self.norm_factor);
+        // Run the processors in the configured order.
+        self.dsp.process(&mut output[..written]);
 
-        // Apply equalizer if enabled
-        self.equalizer.process(&mut output[..written]);
+        // Apply the fade-in and fade-out of the track.
+        if let Some(fade) = &self.fade {
+            let start = self.position.load(Ordering::Relaxed);
+            output[..written]
+                .iter_mut()
+                .zip(start..)
+                .for_each(|(s, position)| *s *= fade.gain(position));
+        }
 
         let position = self.written_samples(written as u64);
+        let mut total_samples = self.total_samples.load(Ordering::Relaxed);
+        if let Some(fade) = &self.fade {
+            // Playback ends with the fade-out, even if the file goes on.
+            total_samples = total_samples.min(fade.end());
+        }
+        if self.superseded.load(Ordering::Relaxed) {
+            // The player has moved on to the next item, so this one ends quietly.
+            self.end_of_track = position >= total_samples;
+            return written;
+        }
+
+        if let Some(fade) = &self.fade {
+            if position < fade.fade_out_start() {
+                // Seeking back before the fade-out starts it over.
+                self.fading_out = false;
+            } else if !self.fading_out
+                && position < total_samples
+                && self
+                    .event_send
+                    .try_send(PlayerEvent::FadingOut {
+                        path: self.file.path(),
+                    })
+                    .is_ok()
+            {
+                self.fading_out = true;
+            }
+        }
+
         if self.should_report(position) {
             // Send a position report, so the upper layers can visualize the playback
             // progress and preload the next track.  We cannot block here, so if the channel
@@ -234,7 +454,7 @@ impl AudioSource for DecoderSource {
                 .event_send
                 .try_send(PlayerEvent::Position {
                     path: self.file.path(),
-                    position: self.samples_to_duration(position),
+                    position: self.samples_to_duration(self.audible_position(position)),
                 })
                 .is_ok()
             {
@@ -242,7 +462,6 @@ impl AudioSource for DecoderSource {
             }
         }
 
-        let total_samples = self.total_samples.load(Ordering::Relaxed);
         if position >= total_samples {
             // After reading the total number of samples, we stop. Signal to the upper layer
             // this track is over and short-circuit all further reads from this source.
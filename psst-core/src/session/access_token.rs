@@ -7,7 +7,7 @@ use std::time::{Duration, Instant};
 use parking_lot::Mutex;
 use serde::Deserialize;
 
-use crate::error::Error;
+use crate::{error::Error, oauth::DEFAULT_TOKEN_LIFETIME};
 
 use super::SessionService;
 
@@ -82,8 +82,11 @@ impl TokenProvider {
         if let Some(tok) = session.oauth_bearer() {
             return Ok(AccessToken {
                 token: tok,
-                // Give the bearer a reasonable lifetime; it will be replaced when refreshed.
-                expires: Instant::now() + Duration::from_secs(3600),
+                // Without a known expiry, give the bearer a reasonable lifetime; it will be
+                // replaced when refreshed.
+                expires: session
+                    .oauth_expires_at()
+                    .unwrap_or_else(|| Instant::now() + DEFAULT_TOKEN_LIFETIME),
             });
         }
 
@@ -1,6 +1,8 @@
 pub mod access_token;
 pub mod audio_key;
 pub mod mercury;
+pub mod reconnect;
+pub mod token_refresh;
 
 use std::{
     io,
@@ -10,6 +12,7 @@ use std::{
         Arc,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
@@ -40,17 +43,42 @@ pub struct SessionConfig {
     pub proxy_url: Option<String>,
 }
 
+/// State of the connection to the access point, reported to the subscribers
+/// of `SessionService`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConnectionState {
+    #[default]
+    Disconnected,
+    Connected,
+    /// The connection dropped, attempt number `attempt` to open it again is
+    /// made in `retry_in`.
+    Reconnecting {
+        attempt: u32,
+        retry_in: Duration,
+    },
+}
+
 /// Cheap to clone, shareable service handle that holds the active session
 /// worker.  Session connection is lazily opened in  `connected()`, using config
 /// values set in `update_config()`.  In case the session dies or is explicitly
 /// shut down, worker is disposed of, and a new session is opened on the next
-/// request.
+/// request.  A connection that drops on its own is opened again in the
+/// background, see `reconnect`.
 #[derive(Clone)]
 pub struct SessionService {
     connected: Arc<Mutex<Option<SessionWorker>>>,
     config: Arc<Mutex<Option<SessionConfig>>>,
     oauth_bearer: Arc<Mutex<Option<String>>>,
     oauth_refresh_token: Arc<Mutex<Option<String>>>,
+    oauth_expires_at: Arc<Mutex<Option<Instant>>>,
+    state: Arc<Mutex<ConnectionState>>,
+    subscribers: Arc<Mutex<Vec<Sender<ConnectionState>>>>,
+    /// Set while a connection is wanted, i.e. since the last successful
+    /// connection and until an explicit shutdown.
+    wanted: Arc<AtomicBool>,
+    supervised: Arc<AtomicBool>,
+    /// Set for a service that never connects, see `detach`.
+    detached: Arc<AtomicBool>,
 }
 
 impl SessionService {
@@ -62,6 +90,12 @@ impl SessionService {
             config: Arc::default(),
             oauth_bearer: Arc::default(),
             oauth_refresh_token: Arc::default(),
+            oauth_expires_at: Arc::default(),
+            state: Arc::default(),
+            subscribers: Arc::default(),
+            wanted: Arc::default(),
+            supervised: Arc::default(),
+            detached: Arc::default(),
         }
     }
 
@@ -72,16 +106,33 @@ impl SessionService {
             config: Arc::new(Mutex::new(Some(config))),
             oauth_bearer: Arc::default(),
             oauth_refresh_token: Arc::default(),
+            oauth_expires_at: Arc::default(),
+            state: Arc::default(),
+            subscribers: Arc::default(),
+            wanted: Arc::default(),
+            supervised: Arc::default(),
+            detached: Arc::default(),
         }
     }
 
     /// Replace the active session config.  If a session is already connected,
     /// shut it down and wait until it's terminated.
     pub fn update_config(&self, config: SessionConfig) {
+        if self.is_detached() {
+            return;
+        }
         self.config.lock().replace(config);
         self.shutdown();
     }
 
+    /// Proxy URL of the active session config.
+    pub fn proxy_url(&self) -> Option<String> {
+        self.config
+            .lock()
+            .as_ref()
+            .and_then(|config| config.proxy_url.clone())
+    }
+
     /// Returns true if a session worker is actively servicing the connected
     /// session.  We return false here after any case of I/O errors or an
     /// explicit session shutdown.
@@ -95,6 +146,9 @@ impl SessionService {
     /// `SessionConnection::open` has an internal timeout, and should give up in
     /// a timely manner.
     pub fn connected(&self) -> Result<SessionHandle, Error> {
+        if self.is_detached() {
+            return Err(Error::SessionDisconnected);
+        }
         let mut connected = self.connected.lock();
         let is_connected_and_not_terminated =
             matches!(connected.as_ref(), Some(worker) if !worker.has_terminated());
@@ -108,6 +162,11 @@ impl SessionService {
             )?;
             let worker = SessionWorker::run(connection.transport);
             connected.replace(worker);
+            self.wanted.store(true, Ordering::SeqCst);
+            self.set_state(ConnectionState::Connected);
+            if !self.supervised.swap(true, Ordering::SeqCst) {
+                reconnect::spawn_supervisor(self.clone());
+            }
         }
         connected
             .as_ref()
@@ -115,17 +174,72 @@ impl SessionService {
             .ok_or(Error::SessionDisconnected)
     }
 
+    /// Stand in for a session that never reaches Spotify, e.g. while the UI is
+    /// tested against fixtures.  Any connected session is shut down, configs
+    /// set later are ignored, and every request fails as disconnected.
+    pub fn detach(&self) {
+        self.detached.store(true, Ordering::SeqCst);
+        self.shutdown();
+    }
+
+    fn is_detached(&self) -> bool {
+        self.detached.load(Ordering::SeqCst)
+    }
+
     /// Signal a shutdown to the active worker and wait until it terminates.
     pub fn shutdown(&self) {
+        self.wanted.store(false, Ordering::SeqCst);
         if let Some(worker) = self.connected.lock().take() {
             worker.handle().request_shutdown();
             worker.join();
         }
+        self.set_state(ConnectionState::Disconnected);
     }
 
-    /// Set or clear OAuth bearer used by dependent services.
+    /// Receive the connection state whenever it changes.
+    pub fn subscribe(&self) -> Receiver<ConnectionState> {
+        let (sender, receiver) = unbounded();
+        self.subscribers.lock().push(sender);
+        receiver
+    }
+
+    /// The last reported connection state.
+    pub fn state(&self) -> ConnectionState {
+        self.state.lock().clone()
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        let mut current = self.state.lock();
+        if *current != state {
+            *current = state.clone();
+            self.subscribers
+                .lock()
+                .retain(|subscriber| subscriber.send(state.clone()).is_ok());
+        }
+    }
+
+    fn is_wanted(&self) -> bool {
+        self.wanted.load(Ordering::SeqCst)
+    }
+
+    /// Set or clear OAuth bearer used by dependent services.  Setting a
+    /// different bearer forgets the expiry of the previous one.
     pub fn set_oauth_bearer(&self, token: Option<String>) {
-        *self.oauth_bearer.lock() = token;
+        let mut bearer = self.oauth_bearer.lock();
+        if *bearer != token {
+            *self.oauth_expires_at.lock() = None;
+        }
+        *bearer = token;
+    }
+
+    /// Record that the current OAuth bearer expires in `expires_in`.
+    pub fn set_oauth_expiry(&self, expires_in: Duration) {
+        *self.oauth_expires_at.lock() = Some(Instant::now() + expires_in);
+    }
+
+    /// When the current OAuth bearer expires, `None` if unknown.
+    pub fn oauth_expires_at(&self) -> Option<Instant> {
+        *self.oauth_expires_at.lock()
     }
 
     /// Get the currently configured OAuth bearer, if any.
@@ -0,0 +1,89 @@
+use std::{thread, time::Duration};
+
+use rand::Rng;
+
+use crate::error::Error;
+
+use super::{ConnectionState, SessionHandle, SessionService};
+
+/// Delay before the first reconnection attempt, doubled with every attempt.
+const BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between two attempts.
+const MAX_DELAY: Duration = Duration::from_secs(64);
+
+/// How often the supervisor checks the connection.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many times a request is tried before its error is returned.
+const REQUEST_ATTEMPTS: u32 = 4;
+
+/// Delay before reconnection `attempt`, counted from 1.  `jitter` is in
+/// `0.0..1.0` and spreads the delay over its upper half, so clients dropped
+/// at the same moment don't all come back at once.
+pub fn reconnect_delay(attempt: u32, jitter: f64) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16);
+    let delay = BASE_DELAY.saturating_mul(1 << exponent).min(MAX_DELAY);
+    delay.mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0)
+}
+
+fn random_delay(attempt: u32) -> Duration {
+    reconnect_delay(attempt, rand::rng().random())
+}
+
+/// True for errors after which the request may succeed on a new connection.
+fn is_connection_lost(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::SessionDisconnected | Error::ConnectionFailed | Error::IoError(_)
+    )
+}
+
+/// Runs `request` on the connected session.  If the connection drops, it is
+/// opened again and the request is repeated, with a growing delay between
+/// the attempts.
+pub fn with_retry<T>(
+    session: &SessionService,
+    request: impl Fn(&SessionHandle) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let mut attempt = 1;
+    loop {
+        match session.connected().and_then(|handle| request(&handle)) {
+            Err(err) if is_connection_lost(&err) && attempt < REQUEST_ATTEMPTS => {
+                log::warn!("session request failed, retrying: {err}");
+                thread::sleep(random_delay(attempt));
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Watches the connection of `session` from a background thread, and opens
+/// it again with exponential backoff whenever it drops while still wanted.
+pub fn spawn_supervisor(session: SessionService) {
+    thread::spawn(move || loop {
+        thread::sleep(CHECK_INTERVAL);
+        if !session.is_wanted() || session.is_connected() {
+            continue;
+        }
+        log::warn!("session connection dropped, reconnecting");
+        let mut attempt = 1;
+        while session.is_wanted() && !session.is_connected() {
+            let retry_in = random_delay(attempt);
+            session.set_state(ConnectionState::Reconnecting { attempt, retry_in });
+            thread::sleep(retry_in);
+            if !session.is_wanted() {
+                break;
+            }
+            match session.connected() {
+                Ok(_) => log::info!("session reconnected after {attempt} attempts"),
+                Err(err) => log::warn!("reconnection attempt {attempt} failed: {err}"),
+            }
+            attempt = attempt.saturating_add(1);
+        }
+        if !session.is_connected() {
+            session.set_state(ConnectionState::Disconnected);
+        }
+    });
+}
@@ -0,0 +1,95 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::oauth::{refresh_tokens, OAuthTokens};
+
+use super::SessionService;
+
+/// Refresh the OAuth bearer this long before it expires.
+pub const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// How often the expiry of the bearer is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Wait before trying again after a failed refresh.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Refreshes the OAuth bearer of a session from a background thread, a few
+/// minutes before it expires, so long sessions don't run into a burst of
+/// unauthorized responses.  A bearer of unknown expiry is refreshed right
+/// away.
+pub struct TokenRefresher {
+    stopped: Arc<AtomicBool>,
+}
+
+impl TokenRefresher {
+    /// `on_refresh` is called with the new tokens after they are installed
+    /// in the session, to pass them on to other services and persist them.
+    /// The thread ends once the refresher is dropped.
+    pub fn spawn<F>(session: SessionService, on_refresh: F) -> Self
+    where
+        F: Fn(&OAuthTokens) + Send + 'static,
+    {
+        let stopped = Arc::new(AtomicBool::new(false));
+        thread::spawn({
+            let stopped = stopped.clone();
+            move || {
+                let mut retry_at = None;
+                while !stopped.load(Ordering::Relaxed) {
+                    thread::sleep(CHECK_INTERVAL);
+                    if retry_at.is_some_and(|at| Instant::now() < at) {
+                        continue;
+                    }
+                    if session.oauth_bearer().is_none()
+                        || !is_refresh_due(session.oauth_expires_at(), Instant::now())
+                    {
+                        continue;
+                    }
+                    let Some(refresh_token) = session.oauth_refresh_token() else {
+                        continue;
+                    };
+                    match refresh_tokens(&refresh_token) {
+                        Ok(tokens) => {
+                            log::info!(
+                                "refreshed OAuth bearer, valid for {}s",
+                                tokens.expires_in.as_secs()
+                            );
+                            retry_at = None;
+                            session.set_oauth_bearer(Some(tokens.access_token.clone()));
+                            session.set_oauth_expiry(tokens.expires_in);
+                            if let Some(rotated) = &tokens.refresh_token {
+                                session.set_oauth_refresh_token(Some(rotated.clone()));
+                            }
+                            on_refresh(&tokens);
+                        }
+                        Err(err) => {
+                            log::warn!("failed to refresh OAuth bearer: {err}");
+                            retry_at = Some(Instant::now() + RETRY_INTERVAL);
+                        }
+                    }
+                }
+            }
+        });
+        Self { stopped }
+    }
+}
+
+impl Drop for TokenRefresher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// True if a bearer expiring at `expires_at` should be refreshed at `now`.
+pub fn is_refresh_due(expires_at: Option<Instant>, now: Instant) -> bool {
+    match expires_at {
+        Some(expires_at) => expires_at.saturating_duration_since(now) <= REFRESH_MARGIN,
+        None => true,
+    }
+}
@@ -1,7 +1,12 @@
-use psst_core::cache::Cache;
-use psst_core::item_id::{ItemId, ItemIdType};
+use psst_core::audio::decrypt::{AudioDecrypt, AudioKey};
+use psst_core::cache::{
+    Cache, CacheCategory, CacheScanUpdate, CacheVerification, EpisodeProgress, PartialDownload,
+};
+use psst_core::item_id::{FileId, ItemId, ItemIdType};
 use psst_core::protocol::metadata::Track;
 use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::time::{Duration, SystemTime};
 use tempfile::TempDir;
 
 fn create_test_cache() -> (TempDir, std::sync::Arc<Cache>) {
@@ -21,6 +26,8 @@ fn cache_new_creates_directory_structure() {
     assert!(cache_path.join("episode").exists());
     assert!(cache_path.join("audio").exists());
     assert!(cache_path.join("key").exists());
+    assert!(cache_path.join("progress").exists());
+    assert!(cache_path.join("speed").exists());
 }
 
 #[test]
@@ -232,3 +239,239 @@ fn cache_handles_empty_track_data() {
     // Either way is acceptable, just testing it doesn't crash
     let _ = retrieved;
 }
+
+#[test]
+fn cache_save_and_get_episode_progress() {
+    let (_temp_dir, cache) = create_test_cache();
+
+    let item_id = ItemId::new(42, ItemIdType::Podcast);
+    assert!(cache.get_episode_progress(item_id).is_none());
+
+    let progress = EpisodeProgress::new(Duration::from_secs(754), false);
+    cache.save_episode_progress(item_id, &progress).unwrap();
+    let retrieved = cache.get_episode_progress(item_id).unwrap();
+    assert_eq!(retrieved, progress);
+    assert_eq!(retrieved.position(), Duration::from_secs(754));
+
+    let played = EpisodeProgress::new(Duration::ZERO, true);
+    cache.save_episode_progress(item_id, &played).unwrap();
+    assert_eq!(cache.get_episode_progress(item_id), Some(played));
+}
+
+#[test]
+fn cache_save_and_get_episode_speed() {
+    let (_temp_dir, cache) = create_test_cache();
+
+    let item_id = ItemId::new(42, ItemIdType::Podcast);
+    let other_id = ItemId::new(43, ItemIdType::Podcast);
+    assert!(cache.get_episode_speed(item_id).is_none());
+
+    cache.save_episode_speed(item_id, 1.5).unwrap();
+    assert_eq!(cache.get_episode_speed(item_id), Some(1.5));
+    assert!(cache.get_episode_speed(other_id).is_none());
+}
+
+#[test]
+fn cache_usage_report_by_category() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let cache_path = temp_dir.path().to_path_buf();
+    let cache = Cache::new(cache_path.clone()).expect("failed to create cache");
+
+    fs::write(cache_path.join("audio").join("a"), [0; 1000]).unwrap();
+    fs::write(cache_path.join("track").join("t"), [0; 100]).unwrap();
+    fs::create_dir_all(cache_path.join("images")).unwrap();
+    fs::write(cache_path.join("images").join("i"), [0; 10]).unwrap();
+    fs::write(cache_path.join("country_code"), b"SE").unwrap();
+
+    let usage = cache.usage_report().unwrap();
+    assert_eq!(usage.get(CacheCategory::Audio), 1000);
+    assert_eq!(usage.get(CacheCategory::Tracks), 100);
+    assert_eq!(usage.get(CacheCategory::Images), 10);
+    assert_eq!(usage.get(CacheCategory::Episodes), 0);
+    assert_eq!(usage.other, 2);
+    assert_eq!(usage.total(), 1112);
+}
+
+#[test]
+fn cache_clear_category_keeps_other_categories() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let cache_path = temp_dir.path().to_path_buf();
+    let cache = Cache::new(cache_path.clone()).expect("failed to create cache");
+
+    fs::write(cache_path.join("audio").join("a"), [0; 1000]).unwrap();
+    fs::write(cache_path.join("track").join("t"), [0; 100]).unwrap();
+
+    cache.clear_category(CacheCategory::Audio).unwrap();
+
+    assert!(cache_path.join("audio").exists());
+    assert!(!cache_path.join("audio").join("a").exists());
+    assert!(cache_path.join("track").join("t").exists());
+
+    // Categories that were never written to can be cleared too.
+    cache.clear_category(CacheCategory::Images).unwrap();
+}
+
+#[test]
+fn cache_scan_usage_reports_the_same_as_usage_report() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let cache_path = temp_dir.path().to_path_buf();
+    let cache = Cache::new(cache_path.clone()).expect("failed to create cache");
+
+    fs::create_dir_all(cache_path.join("images").join("nested")).unwrap();
+    fs::write(cache_path.join("images").join("nested").join("i"), [0; 10]).unwrap();
+    fs::write(cache_path.join("key").join("k"), [0; 16]).unwrap();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let _scan = cache.scan_usage(move |update| {
+        if let CacheScanUpdate::Done(result) = update {
+            sender.send(result.unwrap()).unwrap();
+        }
+    });
+    let usage = receiver
+        .recv_timeout(Duration::from_secs(10))
+        .expect("scan did not finish");
+    assert_eq!(usage, cache.usage_report().unwrap());
+    assert_eq!(usage.get(CacheCategory::Images), 10);
+    assert_eq!(usage.get(CacheCategory::Keys), 16);
+}
+
+/// An Ogg page with a single segment.
+fn ogg_page(header_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut page = b"OggS".to_vec();
+    page.extend([0, header_type]);
+    page.extend([0; 20]);
+    page.extend([1, body.len() as u8]);
+    page.extend(body);
+    page
+}
+
+fn encrypt(key: AudioKey, data: Vec<u8>) -> Vec<u8> {
+    let mut encrypted = Vec::new();
+    AudioDecrypt::new(key, Cursor::new(data))
+        .read_to_end(&mut encrypted)
+        .unwrap();
+    encrypted
+}
+
+#[test]
+fn cache_verify_removes_truncated_audio_files() {
+    let (_temp_dir, cache) = create_test_cache();
+    let item_id = ItemId::from_base62("4uLU6hMCjMI75M1A2tKUQC", ItemIdType::Track).unwrap();
+    let key = AudioKey([7; 16]);
+
+    // Spotify header, then a stream of two pages, the last one marked as such.
+    let mut audio = vec![0; 167];
+    audio.extend(ogg_page(0x02, &[1; 30]));
+    audio.extend(ogg_page(0x04, &[2; 50]));
+    let audio = encrypt(key, audio);
+
+    let intact = FileId([1; 20]);
+    let truncated = FileId([2; 20]);
+    let without_key = FileId([3; 20]);
+    cache.save_audio_key(item_id, intact, &key).unwrap();
+    cache.save_audio_key(item_id, truncated, &key).unwrap();
+    fs::write(cache.audio_file_path(intact), &audio).unwrap();
+    fs::write(cache.audio_file_path(truncated), &audio[..audio.len() - 20]).unwrap();
+    fs::write(cache.audio_file_path(without_key), &audio).unwrap();
+
+    let report = cache.verify().unwrap();
+    assert_eq!(
+        report,
+        CacheVerification {
+            checked: 3,
+            removed: 1,
+            removed_bytes: audio.len() as u64 - 20,
+            unverified: 1,
+        }
+    );
+    assert!(cache.audio_file_path(intact).exists());
+    assert!(!cache.audio_file_path(truncated).exists());
+    assert!(cache.audio_file_path(without_key).exists());
+}
+
+#[test]
+fn cache_download_continues_where_it_stopped() {
+    let (_temp_dir, cache) = create_test_cache();
+    let key = AudioKey([7; 16]);
+    let file_id = FileId([4; 20]);
+
+    let mut audio = vec![0; 167];
+    audio.extend(ogg_page(0x04, &[2; 50]));
+    let audio = encrypt(key, audio);
+    let total_length = audio.len() as u64;
+
+    assert_eq!(cache.partial_download(file_id), None);
+    let mut file = cache.continue_download(file_id, total_length).unwrap();
+    file.write_all(&audio[..100]).unwrap();
+    drop(file);
+    assert_eq!(
+        cache.partial_download(file_id),
+        Some(PartialDownload {
+            downloaded: 100,
+            total_length,
+        })
+    );
+
+    let mut file = cache.continue_download(file_id, total_length).unwrap();
+    file.write_all(&audio[100..]).unwrap();
+    drop(file);
+    cache.finish_download(file_id, key).unwrap();
+    assert_eq!(fs::read(cache.audio_file_path(file_id)).unwrap(), audio);
+    assert_eq!(cache.partial_download(file_id), None);
+}
+
+#[test]
+fn cache_download_starts_over_when_the_length_changes() {
+    let (_temp_dir, cache) = create_test_cache();
+    let file_id = FileId([5; 20]);
+
+    let mut file = cache.continue_download(file_id, 1000).unwrap();
+    file.write_all(&[1; 100]).unwrap();
+    drop(file);
+    cache.continue_download(file_id, 2000).unwrap();
+    assert_eq!(
+        cache.partial_download(file_id),
+        Some(PartialDownload {
+            downloaded: 0,
+            total_length: 2000,
+        })
+    );
+}
+
+#[test]
+fn cache_broken_download_is_removed() {
+    let (_temp_dir, cache) = create_test_cache();
+    let file_id = FileId([6; 20]);
+
+    let mut file = cache.continue_download(file_id, 200).unwrap();
+    file.write_all(&[1; 200]).unwrap();
+    drop(file);
+    assert!(cache.finish_download(file_id, AudioKey([7; 16])).is_err());
+    assert_eq!(cache.partial_download(file_id), None);
+    assert!(!cache.audio_file_path(file_id).exists());
+}
+
+#[test]
+fn cache_removes_stale_downloads() {
+    let (_temp_dir, cache) = create_test_cache();
+    let stale = FileId([7; 20]);
+    let recent = FileId([8; 20]);
+
+    let mut file = cache.continue_download(stale, 1000).unwrap();
+    file.write_all(&[1; 100]).unwrap();
+    file.set_modified(SystemTime::now() - Duration::from_secs(3600))
+        .unwrap();
+    drop(file);
+    let mut file = cache.continue_download(recent, 1000).unwrap();
+    file.write_all(&[1; 10]).unwrap();
+    drop(file);
+
+    let removed = cache
+        .remove_stale_downloads(Duration::from_secs(60))
+        .unwrap();
+    assert_eq!(removed, 100);
+    assert_eq!(cache.partial_download(stale), None);
+    assert!(cache.partial_download(recent).is_some());
+    // Partial downloads are not counted as cached files.
+    assert_eq!(cache.verify().unwrap().checked, 0);
+}
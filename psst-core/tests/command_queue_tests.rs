@@ -0,0 +1,114 @@
+use psst_core::player::{queue::CommandQueue, PlayerCommand};
+use std::time::Duration;
+
+fn seek(secs: u64) -> PlayerCommand {
+    PlayerCommand::Seek {
+        position: Duration::from_secs(secs),
+    }
+}
+
+#[test]
+fn command_queue_wakes_consumer_once_per_batch() {
+    let queue = CommandQueue::new(16);
+
+    assert!(matches!(queue.push(PlayerCommand::Pause), Ok(true)));
+    assert!(matches!(queue.push(PlayerCommand::Resume), Ok(false)));
+    assert_eq!(queue.drain().len(), 2);
+    assert!(matches!(queue.push(PlayerCommand::Pause), Ok(true)));
+}
+
+#[test]
+fn command_queue_coalesces_skip_bursts() {
+    let queue = CommandQueue::new(16);
+
+    for _ in 0..10 {
+        queue.push(PlayerCommand::Next).ok();
+    }
+    queue.push(PlayerCommand::Previous).ok();
+
+    let commands = queue.drain();
+    assert_eq!(commands.len(), 1);
+    assert!(matches!(commands[0], PlayerCommand::Previous));
+}
+
+#[test]
+fn command_queue_keeps_latest_seek() {
+    let queue = CommandQueue::new(16);
+
+    queue.push(PlayerCommand::Next).ok();
+    queue.push(seek(10)).ok();
+    queue.push(seek(20)).ok();
+
+    let commands = queue.drain();
+    assert_eq!(commands.len(), 2);
+    assert!(matches!(commands[0], PlayerCommand::Next));
+    assert!(
+        matches!(commands[1], PlayerCommand::Seek { position } if position == Duration::from_secs(20))
+    );
+}
+
+#[test]
+fn command_queue_skip_discards_pending_seek() {
+    let queue = CommandQueue::new(16);
+
+    queue.push(seek(10)).ok();
+    queue.push(PlayerCommand::Next).ok();
+
+    let commands = queue.drain();
+    assert_eq!(commands.len(), 1);
+    assert!(matches!(commands[0], PlayerCommand::Next));
+}
+
+#[test]
+fn command_queue_stop_clears_navigation() {
+    let queue = CommandQueue::new(16);
+
+    queue.push(PlayerCommand::Next).ok();
+    queue.push(seek(5)).ok();
+    queue.push(PlayerCommand::Stop).ok();
+
+    let commands = queue.drain();
+    assert_eq!(commands.len(), 1);
+    assert!(matches!(commands[0], PlayerCommand::Stop));
+}
+
+#[test]
+fn command_queue_keeps_the_order_of_transport_commands() {
+    let queue = CommandQueue::new(16);
+
+    queue.push(PlayerCommand::Next).ok();
+    queue.push(PlayerCommand::Pause).ok();
+    queue.push(PlayerCommand::Next).ok();
+
+    let commands = queue.drain();
+    assert_eq!(commands.len(), 3);
+    assert!(matches!(commands[0], PlayerCommand::Next));
+    assert!(matches!(commands[1], PlayerCommand::Pause));
+    assert!(matches!(commands[2], PlayerCommand::Next));
+}
+
+#[test]
+fn command_queue_rejects_commands_when_full() {
+    let queue = CommandQueue::new(2);
+
+    assert!(queue.push(PlayerCommand::Pause).is_ok());
+    assert!(queue.push(PlayerCommand::Resume).is_ok());
+    assert!(queue.push(PlayerCommand::PauseOrResume).is_err());
+    assert_eq!(queue.len(), 2);
+
+    queue.drain();
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn command_queue_coalescing_makes_room_when_full() {
+    let queue = CommandQueue::new(2);
+
+    queue.push(PlayerCommand::Pause).ok();
+    queue.push(PlayerCommand::Next).ok();
+    assert!(queue.push(PlayerCommand::Previous).is_ok());
+
+    let commands = queue.drain();
+    assert_eq!(commands.len(), 2);
+    assert!(matches!(commands[1], PlayerCommand::Previous));
+}
@@ -0,0 +1,73 @@
+use psst_core::player::crossfade::{FadePoints, LoudnessSegment, TrackAnalysis};
+use std::time::Duration;
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+fn segment(start: u64, loudness_max: f64) -> LoudnessSegment {
+    LoudnessSegment {
+        start: secs(start),
+        duration: secs(1),
+        loudness_max,
+    }
+}
+
+#[test]
+fn crossfade_fixed_fades_cover_both_ends() {
+    let points = FadePoints::fixed(secs(200), secs(6));
+
+    assert_eq!(points.fade_in_end, secs(6));
+    assert_eq!(points.fade_out_start, secs(194));
+    assert_eq!(points.end, secs(200));
+}
+
+#[test]
+fn crossfade_fixed_fades_do_not_overlap_on_short_tracks() {
+    let points = FadePoints::fixed(secs(8), secs(10));
+
+    assert_eq!(points.fade_in_end, secs(4));
+    assert_eq!(points.fade_out_start, secs(4));
+}
+
+#[test]
+fn crossfade_analysis_keeps_fades_inside_quiet_parts() {
+    // The intro is quiet for a second and the outro only for the last two.
+    let analysis = TrackAnalysis::new(secs(1), secs(198), (0..200).map(|s| segment(s, -10.0)));
+    let points = FadePoints::from_analysis(&analysis, secs(200), secs(6));
+
+    assert_eq!(points.fade_in_end, secs(1));
+    assert_eq!(points.fade_out_start, secs(198));
+    assert_eq!(points.end, secs(200));
+}
+
+#[test]
+fn crossfade_analysis_limits_long_natural_fades() {
+    let analysis = TrackAnalysis::new(secs(20), secs(170), (0..200).map(|s| segment(s, -10.0)));
+    let points = FadePoints::from_analysis(&analysis, secs(200), secs(6));
+
+    assert_eq!(points.fade_in_end, secs(6));
+    assert_eq!(points.fade_out_start, secs(194));
+}
+
+#[test]
+fn crossfade_analysis_cuts_trailing_silence() {
+    let segments = (0..200).map(|s| segment(s, if s < 190 { -10.0 } else { -60.0 }));
+    let analysis = TrackAnalysis::new(secs(0), secs(185), segments);
+    let points = FadePoints::from_analysis(&analysis, secs(200), secs(3));
+
+    assert_eq!(points.end, secs(190));
+    assert_eq!(points.fade_out_start, secs(187));
+}
+
+#[test]
+fn crossfade_envelope_ramps_gain() {
+    let envelope = FadePoints::fixed(secs(10), secs(2)).envelope(10, 1);
+
+    assert_eq!(envelope.gain(0), 0.0);
+    assert_eq!(envelope.gain(10), 0.5);
+    assert_eq!(envelope.gain(50), 1.0);
+    assert_eq!(envelope.gain(90), 0.5);
+    assert_eq!(envelope.gain(100), 0.0);
+    assert_eq!(envelope.end(), 100);
+}
@@ -0,0 +1,59 @@
+use psst_core::player::download::{DownloadQueue, CHUNK_LENGTH};
+
+fn drain(queue: &mut DownloadQueue) -> Vec<(u64, u64)> {
+    std::iter::from_fn(|| queue.pop()).collect()
+}
+
+#[test]
+fn download_queue_splits_ranges_into_chunks() {
+    let mut queue = DownloadQueue::new();
+    queue.push(100, CHUNK_LENGTH * 2 + 10);
+
+    assert_eq!(
+        drain(&mut queue),
+        vec![
+            (100, CHUNK_LENGTH),
+            (100 + CHUNK_LENGTH, CHUNK_LENGTH),
+            (100 + CHUNK_LENGTH * 2, 10),
+        ]
+    );
+}
+
+#[test]
+fn download_queue_serves_blocked_offset_first() {
+    let mut queue = DownloadQueue::new();
+    queue.push(0, CHUNK_LENGTH * 3);
+    queue.push(CHUNK_LENGTH * 10, CHUNK_LENGTH);
+
+    // A seek to the middle of the second chunk.
+    queue.prioritize(CHUNK_LENGTH + 1);
+
+    assert_eq!(
+        drain(&mut queue),
+        vec![
+            (CHUNK_LENGTH, CHUNK_LENGTH),
+            (CHUNK_LENGTH * 2, CHUNK_LENGTH),
+            (CHUNK_LENGTH * 10, CHUNK_LENGTH),
+            (0, CHUNK_LENGTH),
+        ]
+    );
+}
+
+#[test]
+fn download_queue_serves_seek_target_before_sequential_prefetch() {
+    let mut queue = DownloadQueue::new();
+    queue.push(0, CHUNK_LENGTH * 2);
+    // A seek far ahead requests its range after the sequential prefetch.
+    queue.push(CHUNK_LENGTH * 20, CHUNK_LENGTH);
+
+    queue.prioritize(CHUNK_LENGTH * 20);
+
+    assert_eq!(
+        drain(&mut queue),
+        vec![
+            (CHUNK_LENGTH * 20, CHUNK_LENGTH),
+            (0, CHUNK_LENGTH),
+            (CHUNK_LENGTH, CHUNK_LENGTH),
+        ]
+    );
+}
@@ -0,0 +1,178 @@
+use psst_core::audio::{
+    bass_mono::BassMono,
+    limiter::Limiter,
+    processor::{AudioProcessor, DspChain, DspConfig, DspStage},
+    resample::{LinearResampler, ResamplingQuality, ResamplingSpec},
+};
+use std::f32::consts::PI;
+
+const SAMPLE_RATE: u32 = 44100;
+
+#[test]
+fn dsp_order_is_completed_and_deduplicated() {
+    let order = DspStage::complete_order(&[DspStage::Limiter, DspStage::Limiter]);
+
+    assert_eq!(
+        order,
+        vec![
+            DspStage::Limiter,
+            DspStage::Normalization,
+            DspStage::BassMono,
+            DspStage::Equalizer
+        ]
+    );
+}
+
+#[test]
+fn limiter_keeps_peaks_below_full_scale() {
+    let mut limiter = Limiter::new(SAMPLE_RATE, 2);
+    let mut samples = vec![1.5, -2.0, 0.5, 0.5, 0.1, -0.1];
+
+    limiter.process(&mut samples);
+
+    assert!(samples.iter().all(|s| s.abs() < 1.0));
+    // Both channels of a frame get the same gain.
+    assert!((samples[0] / samples[1] - 1.5 / -2.0).abs() < 1e-6);
+}
+
+#[test]
+fn limiter_leaves_quiet_signals_alone() {
+    let mut limiter = Limiter::new(SAMPLE_RATE, 2);
+    let mut samples = vec![0.5, -0.5, 0.25, 0.75];
+
+    limiter.process(&mut samples);
+
+    assert_eq!(samples, vec![0.5, -0.5, 0.25, 0.75]);
+}
+
+#[test]
+fn dsp_chain_runs_stages_in_order() {
+    let loud = || vec![0.8_f32; 8];
+    let config = |order: &[DspStage]| DspConfig {
+        order: order.to_vec(),
+        ..DspConfig::default()
+    };
+
+    // Normalizing first lets the limiter catch the boosted signal.
+    let mut limited_last = DspChain::new(
+        &config(&[DspStage::Normalization, DspStage::Limiter]),
+        2.0,
+        SAMPLE_RATE,
+        2,
+    );
+    let mut samples = loud();
+    limited_last.process(&mut samples);
+    assert!(samples.iter().all(|s| *s < 1.0));
+
+    // Limiting first passes the quiet signal, then the normalization boosts it.
+    let mut limited_first = DspChain::new(
+        &config(&[DspStage::Limiter, DspStage::Normalization]),
+        2.0,
+        SAMPLE_RATE,
+        2,
+    );
+    let mut samples = loud();
+    limited_first.process(&mut samples);
+    assert!(samples.iter().all(|s| (*s - 1.6).abs() < 1e-6));
+}
+
+#[test]
+fn dsp_chain_limits_streams_without_normalization() {
+    let mut chain = DspChain::new(&DspConfig::default(), 1.0, SAMPLE_RATE, 2);
+    let mut samples = vec![1.2_f32; 8];
+    chain.process(&mut samples);
+    assert!(samples.iter().all(|s| *s < 1.0));
+}
+
+/// Interleaved stereo signal with a bass tone on the left and a treble tone
+/// on the right channel.
+fn split_stereo(frames: usize) -> Vec<f32> {
+    let tone = |freq: f32, n: usize| (2.0 * PI * freq * n as f32 / SAMPLE_RATE as f32).sin() * 0.5;
+    (0..frames)
+        .flat_map(|n| [tone(50.0, n), tone(5000.0, n)])
+        .collect()
+}
+
+fn bass_mono(enabled: bool) -> BassMono {
+    let mut bass_mono = BassMono::new(SAMPLE_RATE, 2);
+    bass_mono.update_config(&DspConfig {
+        bass_mono: enabled,
+        ..DspConfig::default()
+    });
+    bass_mono
+}
+
+#[test]
+fn bass_mono_folds_low_frequencies_only() {
+    let original = split_stereo(SAMPLE_RATE as usize);
+    let mut samples = original.clone();
+    bass_mono(true).process(&mut samples);
+
+    // Skip the settling of the filter.  Half the energy of the difference
+    // between the channels is the 50 Hz tone, which is folded into mono,
+    // the 5 kHz half is left.
+    let difference = |samples: &[f32]| -> f32 {
+        samples[samples.len() / 2..]
+            .chunks_exact(2)
+            .map(|frame| (frame[0] - frame[1]).powi(2))
+            .sum()
+    };
+    let ratio = difference(&samples) / difference(&original);
+    assert!((ratio - 0.5).abs() < 0.05, "difference kept {ratio}");
+}
+
+#[test]
+fn bass_mono_keeps_mono_signals_mono() {
+    let mut samples: Vec<f32> = (0..1000)
+        .map(|n| (n as f32 * 0.01).sin())
+        .flat_map(|s| [s, s])
+        .collect();
+    bass_mono(true).process(&mut samples);
+    assert!(samples.chunks_exact(2).all(|frame| frame[0] == frame[1]));
+}
+
+#[test]
+fn bass_mono_passes_signal_when_disabled() {
+    let stereo = split_stereo(1000);
+    let mut samples = stereo.clone();
+    bass_mono(false).process(&mut samples);
+    assert_eq!(samples, stereo);
+}
+
+#[test]
+fn dsp_chain_reports_latency_and_picks_up_config() {
+    let mut chain = DspChain::new(&DspConfig::default(), 1.0, SAMPLE_RATE, 2);
+    assert_eq!(chain.latency(), 0);
+
+    chain.update_config(&DspConfig {
+        order: vec![DspStage::Limiter],
+        ..DspConfig::default()
+    });
+    assert_eq!(chain.order()[0], DspStage::Limiter);
+    assert_eq!(chain.order().len(), DspStage::ALL.len());
+}
+
+#[test]
+fn linear_resampler_interpolates_across_calls() {
+    let spec = ResamplingSpec {
+        input_rate: 22050,
+        output_rate: 44100,
+        channels: 2,
+    };
+    let mut resampler = LinearResampler::new(ResamplingQuality::Linear, spec).unwrap();
+    let input: Vec<f32> = (0..8).flat_map(|i| [i as f32, -(i as f32)]).collect();
+    let mut output = vec![0.0; 64];
+
+    let (consumed, written) = resampler.process(&input[..8], &mut output).unwrap();
+    assert_eq!(consumed, 8);
+    assert_eq!(
+        &output[..written],
+        &[0.0, -0.0, 0.5, -0.5, 1.0, -1.0, 1.5, -1.5, 2.0, -2.0, 2.5, -2.5]
+    );
+
+    // The next call picks up between the last frame and the new ones.
+    let (consumed, written) = resampler.process(&input[8..], &mut output).unwrap();
+    assert_eq!(consumed, 8);
+    assert_eq!(&output[..4], &[3.0, -3.0, 3.5, -3.5]);
+    assert_eq!(written, 16);
+}
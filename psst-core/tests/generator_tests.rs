@@ -0,0 +1,120 @@
+use psst_core::audio::{
+    generator::{Chime, ChimeGenerator, SignalGenerator, TestSignal},
+    source::AudioSource,
+    speech::{channel_names, parse_wave, Speech},
+};
+
+const SAMPLE_RATE: u32 = 48000;
+
+fn generate(signal: TestSignal, channels: usize, seconds: f32) -> Vec<f32> {
+    let mut generator = SignalGenerator::new(signal, SAMPLE_RATE, channels);
+    let mut samples = vec![0.0; (seconds * SAMPLE_RATE as f32) as usize * channels];
+    assert_eq!(generator.write(&mut samples), samples.len());
+    samples
+}
+
+#[test]
+fn sine_has_the_requested_frequency() {
+    let samples = generate(TestSignal::Sine { frequency: 1000.0 }, 2, 1.0);
+
+    let left: Vec<f32> = samples.iter().step_by(2).copied().collect();
+    let rising_crossings = left
+        .windows(2)
+        .filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+        .count();
+    assert!((999..=1001).contains(&rising_crossings));
+    // Both channels carry the same tone.
+    assert!(samples.chunks_exact(2).all(|frame| frame[0] == frame[1]));
+}
+
+#[test]
+fn noise_stays_within_range() {
+    for signal in [TestSignal::WhiteNoise, TestSignal::PinkNoise] {
+        let samples = generate(signal, 1, 1.0);
+        assert!(samples.iter().all(|s| s.abs() <= 1.0));
+        let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+        assert!(rms > 0.01, "{signal:?} is too quiet: {rms}");
+    }
+}
+
+#[test]
+fn channel_identification_plays_one_channel_at_a_time() {
+    let channels = 3;
+    let samples = generate(TestSignal::ChannelIdentification, channels, 6.0);
+
+    // Every channel gets its own two-second slot, in order.
+    let slot = 2 * SAMPLE_RATE as usize * channels;
+    for (index, chunk) in samples.chunks(slot).enumerate() {
+        for frame in chunk.chunks_exact(channels) {
+            for (channel, sample) in frame.iter().enumerate() {
+                if channel != index {
+                    assert_eq!(*sample, 0.0);
+                }
+            }
+        }
+        assert!(chunk.iter().any(|s| *s != 0.0));
+    }
+}
+
+#[test]
+fn chimes_are_short_and_quiet() {
+    for chime in [Chime::Ping, Chime::Rising, Chime::Falling] {
+        let mut generator = ChimeGenerator::new(chime, SAMPLE_RATE, 2);
+        let mut samples = vec![0.0; 2 * SAMPLE_RATE as usize * 2];
+        let written = generator.write(&mut samples);
+
+        // The chime ends on its own, and stays ended.
+        assert_eq!(
+            written,
+            (chime.duration() * SAMPLE_RATE as f32).ceil() as usize * 2
+        );
+        assert_eq!(generator.write(&mut samples), 0);
+        let samples = &samples[..written];
+        assert!(
+            samples.iter().all(|s| s.abs() <= 0.2),
+            "{chime:?} is too loud"
+        );
+        assert!(
+            samples.iter().any(|s| s.abs() > 0.01),
+            "{chime:?} is silent"
+        );
+        // Starts without a click, and has faded out by the end.
+        assert!(samples[0].abs() < 0.001);
+        assert!(samples[written - 1].abs() < 0.001);
+    }
+}
+
+#[test]
+fn spoken_words_are_read_from_wave_files() {
+    // Stereo 16-bit PCM at 22050 Hz, with a padded chunk before the data.
+    let mut wave = Vec::new();
+    wave.extend(b"RIFF\0\0\0\0WAVE");
+    wave.extend(b"fmt \x10\0\0\0");
+    wave.extend([1, 0, 2, 0]);
+    wave.extend(22050_u32.to_le_bytes());
+    wave.extend((22050_u32 * 4).to_le_bytes());
+    wave.extend([4, 0, 16, 0]);
+    wave.extend(b"LIST\x03\0\0\0abc\0");
+    wave.extend(b"data\x08\0\0\0");
+    for sample in [16384_i16, 0, -32768, -32768] {
+        wave.extend(sample.to_le_bytes());
+    }
+
+    let speech = parse_wave(&wave).unwrap();
+    assert_eq!(
+        speech,
+        Speech {
+            samples: vec![0.25, -1.0],
+            sample_rate: 22050,
+        }
+    );
+    assert_eq!(speech.resampled(44100).len(), 4);
+    assert!(parse_wave(b"RIFF\0\0\0\0AVI ").is_err());
+}
+
+#[test]
+fn channels_are_named_in_wave_order() {
+    assert_eq!(channel_names(2), ["Left", "Right"]);
+    assert_eq!(channel_names(6)[3], "Subwoofer");
+    assert_eq!(channel_names(10)[9], "Channel 10");
+}
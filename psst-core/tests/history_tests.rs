@@ -0,0 +1,271 @@
+use psst_core::history::{
+    ExportFormat, PlayHistory, PlayLog, PlayLogFormat, PlayRecord, SkipRecord,
+};
+use std::{fs, path::Path, time::Duration};
+use tempfile::TempDir;
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn play(track_id: &str, played_at: u64) -> PlayRecord {
+    PlayRecord {
+        track_id: track_id.to_string(),
+        name: format!("Track {track_id}"),
+        artist: "Artist".to_string(),
+        album: "Album".to_string(),
+        played_at,
+    }
+}
+
+fn skip(track_id: &str, playlist_id: &str, skipped_at: u64) -> SkipRecord {
+    SkipRecord {
+        track_id: track_id.to_string(),
+        name: format!("Track {track_id}"),
+        artist: "Artist".to_string(),
+        playlist_id: Some(playlist_id.to_string()),
+        skipped_at,
+    }
+}
+
+#[test]
+fn history_persists_records_across_reopen() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let path = temp_dir.path().join("history.jsonl");
+
+    let history = PlayHistory::open(path.clone()).expect("failed to open history");
+    history.record(play("a", 10)).unwrap();
+    history.record(play("b", 20)).unwrap();
+
+    let reopened = PlayHistory::open(path).expect("failed to reopen history");
+    assert_eq!(reopened.records(), vec![play("a", 10), play("b", 20)]);
+}
+
+#[test]
+fn history_clear_removes_plays_and_skips() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let path = temp_dir.path().join("history.jsonl");
+
+    let history = PlayHistory::open(path.clone()).expect("failed to open history");
+    history.record(play("a", 10)).unwrap();
+    history.record_skip(skip("a", "p", 20)).unwrap();
+    history.clear().unwrap();
+    assert_eq!(history.play_count(), 0);
+    assert!(!path.exists());
+
+    history.record(play("b", 30)).unwrap();
+    let reopened = PlayHistory::open(path).expect("failed to reopen history");
+    assert_eq!(reopened.records(), vec![play("b", 30)]);
+    assert!(reopened.skipped_tracks("p", 0, 1).is_empty());
+}
+
+#[test]
+fn history_skips_invalid_lines() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let path = temp_dir.path().join("history.jsonl");
+    let valid = serde_json::to_string(&play("a", 10)).unwrap();
+    std::fs::write(&path, format!("not json\n{valid}\n\n")).unwrap();
+
+    let history = PlayHistory::open(path).expect("failed to open history");
+    assert_eq!(history.records(), vec![play("a", 10)]);
+}
+
+#[test]
+fn top_tracks_counts_plays_since_cutoff() {
+    let history = PlayHistory::in_memory();
+    for record in [
+        play("old", 1),
+        play("old", 2),
+        play("old", 3),
+        play("a", 100),
+        play("b", 101),
+        play("b", 102),
+        play("c", 103),
+    ] {
+        history.record(record).unwrap();
+    }
+
+    let top = history.top_tracks(100, 2);
+    let ids: Vec<_> = top.iter().map(|t| t.track_id.as_str()).collect();
+    assert_eq!(ids, vec!["b", "c"]);
+    assert_eq!(top[0].play_count, 2);
+    assert_eq!(top[0].last_played, 102);
+}
+
+#[test]
+fn rediscover_lists_tracks_not_played_recently() {
+    let now = 1000 * DAY;
+    let history = PlayHistory::in_memory();
+    for record in [
+        play("forgotten", now - 500 * DAY),
+        play("forgotten", now - 400 * DAY),
+        play("recent", now - 700 * DAY),
+        play("recent", now - DAY),
+        play("once", now - 600 * DAY),
+    ] {
+        history.record(record).unwrap();
+    }
+
+    let tracks = history.rediscover(now, Duration::from_secs(365 * DAY), 10);
+    let ids: Vec<_> = tracks.iter().map(|t| t.track_id.as_str()).collect();
+    assert_eq!(ids, vec!["forgotten", "once"]);
+}
+
+#[test]
+fn play_counts_follow_the_local_day() {
+    const HOUR: u64 = 60 * 60;
+    let now = 100 * DAY + 12 * HOUR;
+    let history = PlayHistory::in_memory();
+    for record in [
+        play("a", now),
+        play("b", now - HOUR),
+        play("c", 100 * DAY - HOUR),
+        play("d", 98 * DAY + 3 * HOUR),
+        play("e", 90 * DAY),
+    ] {
+        history.record(record).unwrap();
+    }
+
+    assert_eq!(history.daily_play_counts(now, 3, 0), vec![1, 1, 2]);
+    // Two hours ahead of UTC, the play an hour before midnight is on the
+    // next day.
+    assert_eq!(
+        history.daily_play_counts(now, 3, 2 * HOUR as i64),
+        vec![1, 0, 3]
+    );
+
+    let hours = history.hourly_play_counts(98 * DAY, 0);
+    assert_eq!((hours[12], hours[11], hours[23], hours[3]), (1, 1, 1, 1));
+    assert_eq!(hours.iter().sum::<usize>(), 4);
+    assert_eq!(history.hourly_play_counts(0, -(HOUR as i64))[23], 1);
+}
+
+#[test]
+fn history_exports_csv_with_quoted_fields() {
+    let history = PlayHistory::in_memory();
+    history.record(play("a", 10)).unwrap();
+    let mut record = play("b", 20);
+    record.name = "Say \"Hi\", Again".to_string();
+    history.record(record).unwrap();
+
+    let mut output = Vec::new();
+    history.export(ExportFormat::Csv, &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "track_id,name,artist,album,played_at\r\n\
+         a,Track a,Artist,Album,10\r\n\
+         b,\"Say \"\"Hi\"\", Again\",Artist,Album,20\r\n"
+    );
+}
+
+#[test]
+fn history_export_schema_matches_json_fields() {
+    let history = PlayHistory::in_memory();
+    history.record(play("a", 10)).unwrap();
+
+    let mut output = Vec::new();
+    history.export(ExportFormat::Json, &mut output).unwrap();
+    let exported: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let mut keys: Vec<_> = exported[0]
+        .as_object()
+        .expect("plays are exported as objects")
+        .keys()
+        .cloned()
+        .collect();
+    keys.sort();
+
+    let mut documented: Vec<_> = PlayRecord::SCHEMA
+        .iter()
+        .map(|field| field.name.to_string())
+        .collect();
+    documented.sort();
+    assert_eq!(keys, documented);
+}
+
+#[test]
+fn play_log_format_follows_the_extension() {
+    assert_eq!(
+        PlayLogFormat::from_path(Path::new("plays.CSV")),
+        PlayLogFormat::Csv
+    );
+    assert_eq!(
+        PlayLogFormat::from_path(Path::new("plays.jsonl")),
+        PlayLogFormat::JsonLines
+    );
+    assert_eq!(
+        PlayLogFormat::from_path(Path::new("plays")),
+        PlayLogFormat::JsonLines
+    );
+}
+
+#[test]
+fn play_log_appends_csv_rows_after_one_header() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let path = temp_dir.path().join("plays.csv");
+
+    let log = PlayLog::new(path.clone());
+    log.append(&play("a", 10)).unwrap();
+    PlayLog::new(path.clone()).append(&play("b", 20)).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(path).unwrap(),
+        "track_id,name,artist,album,played_at\r\n\
+         a,Track a,Artist,Album,10\r\n\
+         b,Track b,Artist,Album,20\r\n"
+    );
+}
+
+#[test]
+fn play_log_appends_json_lines() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let path = temp_dir.path().join("plays.jsonl");
+
+    let log = PlayLog::new(path.clone());
+    log.append(&play("a", 10)).unwrap();
+    log.append(&play("b", 20)).unwrap();
+
+    let logged: Vec<PlayRecord> = fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(logged, vec![play("a", 10), play("b", 20)]);
+}
+
+#[test]
+fn history_persists_skips_across_reopen() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let path = temp_dir.path().join("history.jsonl");
+
+    let history = PlayHistory::open(path.clone()).expect("failed to open history");
+    history.record_skip(skip("a", "p", 10)).unwrap();
+    history.record_skip(skip("a", "p", 20)).unwrap();
+
+    let reopened = PlayHistory::open(path).expect("failed to reopen history");
+    assert_eq!(reopened.skipped_tracks("p", 0, 2).len(), 1);
+    assert!(reopened.records().is_empty());
+}
+
+#[test]
+fn skipped_tracks_are_skipped_repeatedly_and_not_played_since() {
+    let history = PlayHistory::in_memory();
+    for record in [
+        skip("always", "p", 10),
+        skip("always", "p", 20),
+        skip("always", "p", 30),
+        skip("once", "p", 10),
+        skip("played", "p", 10),
+        skip("played", "p", 20),
+        skip("elsewhere", "other", 10),
+        skip("elsewhere", "other", 20),
+        skip("before", "p", 1),
+        skip("before", "p", 9),
+    ] {
+        history.record_skip(record).unwrap();
+    }
+    history.record(play("played", 25)).unwrap();
+    history.record(play("always", 5)).unwrap();
+
+    let skipped = history.skipped_tracks("p", 10, 2);
+    let ids: Vec<_> = skipped.iter().map(|t| t.track_id.as_str()).collect();
+    assert_eq!(ids, vec!["always"]);
+    assert_eq!(skipped[0].skip_count, 3);
+}
@@ -0,0 +1,89 @@
+use psst_core::lastfm::{
+    api_signature, parse_friends, parse_recent_track, parse_user_loved, FriendTrack,
+};
+
+#[test]
+fn friends_are_parsed_from_a_list_or_a_single_object() {
+    let many = r#"{"friends":{"user":[{"name":"alice"},{"name":"bob"}],"@attr":{"total":"2"}}}"#;
+    assert_eq!(parse_friends(many).unwrap(), vec!["alice", "bob"]);
+
+    let one = r#"{"friends":{"user":{"name":"alice"},"@attr":{"total":"1"}}}"#;
+    assert_eq!(parse_friends(one).unwrap(), vec!["alice"]);
+
+    let none = r#"{"friends":{"@attr":{"total":"0"}}}"#;
+    assert!(parse_friends(none).unwrap().is_empty());
+}
+
+#[test]
+fn friends_request_fails_on_api_error() {
+    let error = r#"{"error":6,"message":"User not found"}"#;
+    assert!(parse_friends(error).is_err());
+}
+
+#[test]
+fn recent_track_is_the_first_one_listed() {
+    let body = r##"{"recenttracks":{"track":[
+        {"name":"Song","artist":{"#text":"Band"},"album":{"#text":"Record"},
+         "@attr":{"nowplaying":"true"}},
+        {"name":"Older","artist":{"#text":"Band"},"album":{"#text":""},
+         "date":{"uts":"1700000000","#text":"14 Nov 2023, 22:13"}}
+    ]}}"##;
+    assert_eq!(
+        parse_recent_track("alice", body).unwrap(),
+        Some(FriendTrack {
+            friend: "alice".to_string(),
+            artist: "Band".to_string(),
+            title: "Song".to_string(),
+            album: Some("Record".to_string()),
+            now_playing: true,
+            played_at: None,
+        })
+    );
+}
+
+#[test]
+fn recent_track_without_album_or_scrobbles() {
+    let body = r##"{"recenttracks":{"track":
+        {"name":"Older","artist":{"#text":"Band"},"album":{"#text":""},
+         "date":{"uts":"1700000000"}}
+    }}"##;
+    let track = parse_recent_track("bob", body).unwrap().unwrap();
+    assert_eq!(track.album, None);
+    assert!(!track.now_playing);
+    assert_eq!(track.played_at, Some(1_700_000_000));
+
+    let empty = r#"{"recenttracks":{"track":[],"@attr":{"total":"0"}}}"#;
+    assert_eq!(parse_recent_track("bob", empty).unwrap(), None);
+}
+
+#[test]
+fn user_loved_is_read_from_track_info() {
+    let loved = r#"{"track":{"name":"Song","userplaycount":"3","userloved":"1"}}"#;
+    assert!(parse_user_loved(loved).unwrap());
+
+    let not_loved = r#"{"track":{"name":"Song","userplaycount":"0","userloved":"0"}}"#;
+    assert!(!parse_user_loved(not_loved).unwrap());
+
+    // Without a username, Last.fm leaves the field out.
+    let anonymous = r#"{"track":{"name":"Song"}}"#;
+    assert!(!parse_user_loved(anonymous).unwrap());
+
+    let error = r#"{"error":6,"message":"Track not found"}"#;
+    assert!(parse_user_loved(error).is_err());
+}
+
+#[test]
+fn api_signature_sorts_parameters_and_appends_the_secret() {
+    let params = [
+        ("track", "Song"),
+        ("method", "track.love"),
+        ("artist", "Band"),
+        ("api_key", "key"),
+        ("sk", "session"),
+    ];
+    // MD5 of "api_keykeyartistBandmethodtrack.lovesksessiontrackSongsecret".
+    assert_eq!(
+        api_signature(&params, "secret"),
+        "5bb5c9e2655e91530021f1ac300de8f8"
+    );
+}
@@ -0,0 +1,25 @@
+use psst_core::local::{is_audio_file, scan};
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn audio_files_are_recognized_by_extension() {
+    assert!(is_audio_file(Path::new("music/song.mp3")));
+    assert!(is_audio_file(Path::new("music/song.FLAC")));
+    assert!(is_audio_file(Path::new("music/song.ogg")));
+    assert!(!is_audio_file(Path::new("music/cover.jpg")));
+    assert!(!is_audio_file(Path::new("music/mp3")));
+}
+
+#[test]
+fn scan_skips_unreadable_and_unrelated_files() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let nested = temp_dir.path().join("artist").join("album");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(nested.join("notes.txt"), "not audio").unwrap();
+    std::fs::write(nested.join("broken.mp3"), "not really an mp3").unwrap();
+
+    let missing = temp_dir.path().join("missing");
+    let tracks = scan(&[temp_dir.path().to_path_buf(), missing]);
+    assert!(tracks.is_empty());
+}
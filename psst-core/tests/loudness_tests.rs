@@ -0,0 +1,35 @@
+use std::f32::consts::PI;
+
+use psst_core::audio::loudness::LoudnessMeter;
+
+const SAMPLE_RATE: u32 = 48000;
+
+fn stereo_sine(amplitude: f32, seconds: usize) -> Vec<f32> {
+    (0..SAMPLE_RATE as usize * seconds)
+        .map(|n| amplitude * (2.0 * PI * 997.0 * n as f32 / SAMPLE_RATE as f32).sin())
+        .flat_map(|s| [s, s])
+        .collect()
+}
+
+#[test]
+fn full_scale_sine_measures_zero_lufs() {
+    // The calibration signal of BS.1770: a 997 Hz sine at full scale on both
+    // channels is 0 LUFS.
+    let mut meter = LoudnessMeter::new(SAMPLE_RATE, 2);
+    meter.process(&stereo_sine(1.0, 3));
+    let loudness = meter.integrated().unwrap();
+    assert!(loudness.abs() < 0.1, "measured {loudness} LUFS");
+}
+
+#[test]
+fn silence_is_gated_out() {
+    let mut meter = LoudnessMeter::new(SAMPLE_RATE, 2);
+    meter.process(&vec![0.0; SAMPLE_RATE as usize * 2 * 2]);
+    assert_eq!(meter.integrated(), None);
+
+    // A silent intro does not pull the loudness of the music down, only the
+    // blocks overlapping the start of the tone lower it a little.
+    meter.process(&stereo_sine(0.5, 3));
+    let loudness = meter.integrated().unwrap();
+    assert!((loudness + 6.02).abs() < 0.3, "measured {loudness} LUFS");
+}
@@ -0,0 +1,80 @@
+use std::fs;
+
+use psst_core::{
+    audio::decrypt::AudioKey,
+    cache::Cache,
+    item_id::{FileId, ItemId, ItemIdType},
+    offline::SyncStatus,
+    player::{
+        file::QualityTier,
+        item::{cached_items, cached_media_path},
+    },
+    protocol::metadata::{mod_AudioFile::Format, AudioFile, Track},
+};
+use tempfile::TempDir;
+
+#[test]
+fn running_download_is_syncing() {
+    let status = SyncStatus::of(Some("old"), "new", Some((3, 12)));
+    assert_eq!(status, SyncStatus::Syncing { done: 3, total: 12 });
+    assert_eq!(status.percent(), Some(25));
+    assert_eq!(SyncStatus::of(None, "new", Some((0, 0))).percent(), Some(0));
+}
+
+#[test]
+fn playlist_is_synced_at_downloaded_snapshot() {
+    assert_eq!(SyncStatus::of(Some("abc"), "abc", None), SyncStatus::Synced);
+    assert_eq!(SyncStatus::of(Some("abc"), "", None), SyncStatus::Synced);
+    assert_eq!(SyncStatus::Synced.percent(), None);
+}
+
+#[test]
+fn changed_or_unfinished_playlist_is_out_of_date() {
+    assert_eq!(
+        SyncStatus::of(Some("abc"), "def", None),
+        SyncStatus::OutOfDate
+    );
+    assert_eq!(SyncStatus::of(None, "abc", None), SyncStatus::OutOfDate);
+    assert_eq!(SyncStatus::of(None, "", None), SyncStatus::OutOfDate);
+}
+
+#[test]
+fn items_play_offline_with_any_cached_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = Cache::new(temp_dir.path().to_path_buf()).unwrap();
+    let track = |gid: u8| Track {
+        gid: Some(vec![gid; 16]),
+        duration: Some(180_000),
+        file: [(Format::OGG_VORBIS_160, 1), (Format::OGG_VORBIS_320, 2)]
+            .into_iter()
+            .map(|(format, id)| AudioFile {
+                file_id: Some(vec![gid + id; 20]),
+                format: Some(format),
+            })
+            .collect(),
+        ..Track::default()
+    };
+    let downloaded = ItemId::from_raw(&[10; 16], ItemIdType::Track).unwrap();
+    let streamed = ItemId::from_raw(&[20; 16], ItemIdType::Track).unwrap();
+    cache.save_track(downloaded, &track(10)).unwrap();
+    cache.save_track(streamed, &track(20)).unwrap();
+
+    // Only the normal quality file was downloaded.
+    let file_id = FileId([11; 20]);
+    cache
+        .save_audio_key(downloaded, file_id, &AudioKey([7; 16]))
+        .unwrap();
+    fs::write(cache.audio_file_path(file_id), [0; 64]).unwrap();
+    // A key alone is not enough.
+    cache
+        .save_audio_key(streamed, FileId([21; 20]), &AudioKey([7; 16]))
+        .unwrap();
+
+    let path = cached_media_path(downloaded, &cache, QualityTier::High).unwrap();
+    assert_eq!(path.file_id, file_id);
+    assert!(cached_media_path(streamed, &cache, QualityTier::High).is_none());
+    assert_eq!(
+        cached_items(&cache, QualityTier::High).unwrap(),
+        [downloaded].into_iter().collect()
+    );
+}
@@ -0,0 +1,227 @@
+use std::time::{Duration, Instant};
+
+use psst_core::{
+    audio::normalize::NormalizationLevel,
+    item_id::{ItemId, ItemIdType},
+    player::{
+        item::{ItemGroup, PlaybackItem},
+        queue::{PreviousPresses, Queue, Repeat, Shuffle, DOUBLE_PRESS_INTERVAL, PRESS_DEBOUNCE},
+    },
+};
+
+fn item(id: u128) -> PlaybackItem {
+    PlaybackItem {
+        item_id: ItemId::new(id, ItemIdType::Track),
+        norm_level: NormalizationLevel::None,
+        group: ItemGroup::default(),
+        skip_intro: Duration::ZERO,
+    }
+}
+
+fn items(ids: &[u128]) -> Vec<PlaybackItem> {
+    ids.iter().copied().map(item).collect()
+}
+
+#[test]
+fn play_queue_up_next_lists_user_items_first() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1, 2]), 0);
+    queue.add(item(10));
+
+    assert_eq!(queue.up_next(), items(&[10, 1, 2]));
+}
+
+#[test]
+fn play_queue_move_changes_play_order() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1, 2, 3]), 0);
+    queue.add(item(10));
+
+    queue.move_up_next(0, 2);
+    assert_eq!(queue.up_next(), items(&[1, 2, 10, 3]));
+
+    queue.skip_to_next();
+    assert_eq!(queue.get_current(), Some(&item(1)));
+    assert_eq!(queue.up_next(), items(&[2, 10, 3]));
+}
+
+#[test]
+fn play_queue_remove_and_clear_keep_current_item() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1, 2, 3]), 1);
+
+    queue.remove_up_next(0);
+    assert_eq!(queue.get_current(), Some(&item(1)));
+    assert_eq!(queue.up_next(), items(&[3]));
+
+    queue.clear_up_next();
+    assert_eq!(queue.get_current(), Some(&item(1)));
+    assert!(queue.up_next().is_empty());
+}
+
+#[test]
+fn play_queue_edits_keep_shuffled_order() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1, 2, 3, 4]), 2);
+    queue.set_shuffle(Shuffle::Random);
+
+    let mut expected = queue.up_next();
+    let moved = expected.remove(3);
+    expected.insert(0, moved);
+    queue.move_up_next(3, 0);
+
+    assert_eq!(queue.get_current(), Some(&item(2)));
+    assert_eq!(queue.up_next(), expected);
+}
+
+#[test]
+fn play_queue_edits_user_items_without_playback() {
+    let mut queue = Queue::new();
+    queue.add(item(7));
+    queue.add(item(8));
+
+    queue.remove_up_next(0);
+
+    assert_eq!(queue.get_current(), None);
+    assert_eq!(queue.up_next(), items(&[8]));
+}
+
+#[test]
+fn play_queue_ignores_out_of_range_indices() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1, 2]), 0);
+
+    queue.remove_up_next(5);
+    queue.move_up_next(7, 0);
+
+    assert_eq!(queue.up_next(), items(&[1, 2]));
+}
+
+#[test]
+fn play_queue_add_next_goes_before_earlier_additions() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1]), 0);
+    queue.add(item(10));
+    queue.add_next(items(&[20, 21]));
+
+    assert_eq!(queue.up_next(), items(&[20, 21, 10, 1]));
+
+    queue.skip_to_next();
+    assert_eq!(queue.get_current(), Some(&item(20)));
+    queue.add_next(items(&[30]));
+    assert_eq!(queue.up_next(), items(&[30, 21, 10, 1]));
+}
+
+#[test]
+fn play_queue_smart_shuffle_starts_with_current_item() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1, 2, 3, 4]), 2);
+    queue.set_shuffle(Shuffle::Smart);
+
+    assert_eq!(queue.get_current(), Some(&item(2)));
+    let mut up_next: Vec<_> = queue.up_next().iter().map(|i| i.item_id.id).collect();
+    up_next.sort_unstable();
+    assert_eq!(up_next, vec![0, 1, 3, 4]);
+}
+
+#[test]
+fn play_queue_repeat_keeps_shuffled_order() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1, 2, 3]), 0);
+    queue.set_shuffle(Shuffle::Random);
+    let order: Vec<_> = queue
+        .get_current()
+        .into_iter()
+        .copied()
+        .chain(queue.up_next())
+        .collect();
+
+    queue.set_repeat(Repeat::All);
+    assert_eq!(queue.up_next(), order[1..]);
+    for _ in 0..order.len() {
+        queue.skip_to_following();
+    }
+    assert_eq!(queue.get_current(), Some(&order[0]));
+
+    queue.set_repeat(Repeat::One);
+    queue.skip_to_following();
+    assert_eq!(queue.get_current(), Some(&order[0]));
+    queue.skip_to_next();
+    assert_eq!(queue.get_current(), Some(&order[1]));
+}
+
+#[test]
+fn play_queue_skip_to_start_goes_to_first_item() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1, 2]), 2);
+
+    queue.skip_to_start();
+    assert_eq!(queue.get_current(), Some(&item(0)));
+    assert_eq!(queue.up_next(), items(&[1, 2]));
+}
+
+#[test]
+fn play_queue_deduplication_skips_user_queued_items() {
+    let mut queue = Queue::new();
+    queue.set_deduplicate(true);
+    queue.fill(items(&[0, 1]), 0);
+
+    assert!(queue.add(item(10)));
+    assert!(!queue.add(item(10)));
+    assert_eq!(queue.add_next(items(&[10, 20, 20])), 2);
+    assert_eq!(queue.up_next(), items(&[20, 10, 1]));
+}
+
+#[test]
+fn play_queue_deduplication_skips_upcoming_context_items() {
+    let mut queue = Queue::new();
+    queue.set_deduplicate(true);
+    queue.fill(items(&[0, 1, 2]), 1);
+
+    // Playing and coming up in the context.
+    assert!(!queue.add(item(1)));
+    assert!(!queue.add(item(2)));
+    // Already played, so it is queued again.
+    assert!(queue.add(item(0)));
+    assert_eq!(queue.up_next(), items(&[0, 2]));
+}
+
+#[test]
+fn play_queue_keeps_duplicates_unless_enabled() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1]), 0);
+
+    assert!(queue.add(item(1)));
+    assert_eq!(queue.add_next(items(&[1])), 0);
+    assert_eq!(queue.up_next(), items(&[1, 1, 1]));
+}
+
+#[test]
+fn play_queue_remove_duplicates_keeps_first_occurrence() {
+    let mut queue = Queue::new();
+    queue.fill(items(&[0, 1, 2, 1]), 0);
+    queue.add(item(2));
+    queue.add(item(0));
+
+    // The user queue comes first, so its copy of 2 is kept over the
+    // context's.
+    assert_eq!(queue.remove_duplicates(), 3);
+    assert_eq!(queue.get_current(), Some(&item(0)));
+    assert_eq!(queue.up_next(), items(&[2, 1]));
+    assert_eq!(queue.remove_duplicates(), 0);
+}
+
+#[test]
+fn previous_presses_detect_double_presses() {
+    let mut presses = PreviousPresses::new();
+    let start = Instant::now();
+    let ms = |millis| start + Duration::from_millis(millis);
+
+    assert_eq!(presses.press(start), Some(false));
+    // A bounce of the same press.
+    assert_eq!(presses.press(start + PRESS_DEBOUNCE / 2), None);
+    assert_eq!(presses.press(ms(200)), Some(true));
+    // The pair is complete, the next press starts a new one.
+    assert_eq!(presses.press(ms(400)), Some(false));
+    assert_eq!(presses.press(ms(400) + DOUBLE_PRESS_INTERVAL), Some(false));
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use psst_core::{
+    item_id::FileId,
+    metadata::ToMediaPath,
+    player::{
+        bandwidth::{adaptive_tier, BandwidthMeter},
+        file::QualityTier,
+    },
+    protocol::metadata::{mod_AudioFile::Format, AudioFile, Track},
+};
+
+fn track_with_formats(formats: &[Format]) -> Track {
+    Track {
+        gid: Some(vec![1; 16]),
+        duration: Some(180_000),
+        file: formats
+            .iter()
+            .enumerate()
+            .map(|(index, &format)| AudioFile {
+                file_id: Some(vec![index as u8; 20]),
+                format: Some(format),
+            })
+            .collect(),
+        ..Track::default()
+    }
+}
+
+#[test]
+fn fallback_prefers_better_lossy_tiers_before_worse_ones() {
+    let order: Vec<_> = QualityTier::Normal.fallback_order().collect();
+    assert_eq!(
+        order,
+        [QualityTier::Normal, QualityTier::High, QualityTier::Low]
+    );
+
+    let order: Vec<_> = QualityTier::Lossless.fallback_order().collect();
+    assert_eq!(
+        order,
+        [
+            QualityTier::Lossless,
+            QualityTier::High,
+            QualityTier::Normal,
+            QualityTier::Low
+        ]
+    );
+}
+
+#[test]
+fn lossless_is_picked_only_when_preferred() {
+    let track = track_with_formats(&[Format::OGG_VORBIS_320, Format::FLAC_FLAC]);
+
+    let path = track.to_media_path(QualityTier::Lossless).unwrap();
+    assert_eq!(path.quality, Some(QualityTier::Lossless));
+    assert_eq!(path.file_id.0, [1; 20]);
+
+    let path = track.to_media_path(QualityTier::High).unwrap();
+    assert_eq!(path.quality, Some(QualityTier::High));
+    assert_eq!(path.file_id.0, [0; 20]);
+}
+
+#[test]
+fn media_path_carries_the_bitrate_of_the_picked_file() {
+    let track = track_with_formats(&[Format::MP3_256, Format::OGG_VORBIS_160]);
+    let path = track.to_media_path(QualityTier::High).unwrap();
+    assert_eq!(path.quality, Some(QualityTier::High));
+    assert_eq!(path.bitrate, Some(256));
+
+    let track = track_with_formats(&[Format::FLAC_FLAC]);
+    let path = track.to_media_path(QualityTier::Lossless).unwrap();
+    assert_eq!(path.bitrate, None);
+}
+
+#[test]
+fn drm_protected_formats_are_never_picked() {
+    let track = track_with_formats(&[Format::AAC_320, Format::XHE_AAC_24, Format::MP3_96]);
+
+    let path = track.to_media_path(QualityTier::High).unwrap();
+    assert_eq!(path.quality, Some(QualityTier::Low));
+
+    let track = track_with_formats(&[Format::AAC_320]);
+    assert!(track.to_media_path(QualityTier::High).is_none());
+}
+
+#[test]
+fn adaptive_tier_keeps_headroom_over_the_bitrate() {
+    // Nothing measured yet.
+    assert_eq!(adaptive_tier(None, QualityTier::High), QualityTier::Normal);
+    assert_eq!(adaptive_tier(None, QualityTier::Low), QualityTier::Low);
+
+    assert_eq!(
+        adaptive_tier(Some(1_000_000), QualityTier::High),
+        QualityTier::High
+    );
+    assert_eq!(
+        adaptive_tier(Some(500_000), QualityTier::High),
+        QualityTier::Normal
+    );
+    assert_eq!(
+        adaptive_tier(Some(100_000), QualityTier::High),
+        QualityTier::Low
+    );
+    // Lossless is never picked by bandwidth.
+    assert_eq!(
+        adaptive_tier(Some(100_000_000), QualityTier::Lossless),
+        QualityTier::High
+    );
+}
+
+#[test]
+fn bandwidth_meter_smooths_measurements() {
+    let meter = BandwidthMeter::new();
+    assert_eq!(meter.estimate(), None);
+
+    // 64 KiB in half a second is 1 Mbit/s.
+    meter.record(64 * 1024, Duration::from_millis(500));
+    assert_eq!(meter.estimate(), Some(1_048_576));
+
+    // A slower chunk moves the estimate only partially.
+    meter.record(64 * 1024, Duration::from_secs(1));
+    let estimate = meter.estimate().unwrap();
+    assert!(estimate > 524_288 && estimate < 1_048_576);
+
+    // Tiny downloads are mostly latency, and are ignored.
+    meter.record(1024, Duration::from_secs(1));
+    assert_eq!(meter.estimate(), Some(estimate));
+}
+
+#[test]
+fn rebuffers_are_reported_once_per_file() {
+    let meter = BandwidthMeter::new();
+    let file = FileId([1; 20]);
+    meter.record_rebuffer(file);
+    assert!(!meter.take_rebuffer(FileId([2; 20])));
+    assert!(meter.take_rebuffer(file));
+    assert!(!meter.take_rebuffer(file));
+}
+
+#[test]
+fn buffering_lasts_until_the_next_chunk() {
+    let meter = BandwidthMeter::new();
+    let file = FileId([1; 20]);
+    meter.record_seek(file);
+    assert!(meter.is_buffering(file));
+    assert!(!meter.take_rebuffer(file));
+
+    meter.record_chunk(FileId([2; 20]));
+    assert!(meter.is_buffering(file));
+    meter.record_chunk(file);
+    assert!(!meter.is_buffering(file));
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use psst_core::{
+    connection::Credentials,
+    error::Error,
+    session::{reconnect::reconnect_delay, SessionConfig, SessionService},
+};
+
+#[test]
+fn reconnect_delay_doubles_up_to_a_limit() {
+    let delays: Vec<_> = (1..=9)
+        .map(|attempt| reconnect_delay(attempt, 1.0))
+        .collect();
+    assert_eq!(delays[0], Duration::from_secs(1));
+    assert_eq!(delays[1], Duration::from_secs(2));
+    assert_eq!(delays[5], Duration::from_secs(32));
+    assert_eq!(delays[6], Duration::from_secs(64));
+    assert_eq!(delays[8], Duration::from_secs(64));
+    assert_eq!(reconnect_delay(u32::MAX, 1.0), Duration::from_secs(64));
+}
+
+#[test]
+fn jitter_spreads_over_the_upper_half() {
+    assert_eq!(reconnect_delay(4, 0.0), Duration::from_secs(4));
+    assert_eq!(reconnect_delay(4, 0.5), Duration::from_secs(6));
+    assert!(reconnect_delay(4, 0.99) < Duration::from_secs(8));
+}
+
+#[test]
+fn detached_session_never_connects() {
+    let session = SessionService::empty();
+    session.detach();
+    session.update_config(SessionConfig {
+        login_creds: Credentials::from_username_and_password("user".into(), "pass".into()),
+        proxy_url: Some("socks5://localhost:1080".into()),
+    });
+    assert_eq!(session.proxy_url(), None);
+    assert!(matches!(
+        session.connected(),
+        Err(Error::SessionDisconnected)
+    ));
+    assert!(!session.is_connected());
+}
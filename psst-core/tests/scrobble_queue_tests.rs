@@ -0,0 +1,144 @@
+use psst_core::{
+    error::Error,
+    lastfm::{retry_backoff, PendingScrobble, ScrobbleQueue},
+};
+use rustfm_scrobble::ScrobblerError;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const NOW: u64 = 1_700_000_000;
+const DAY: u64 = 24 * 60 * 60;
+
+fn scrobble(title: &str, timestamp: u64) -> PendingScrobble {
+    PendingScrobble {
+        artist: "Artist".to_string(),
+        title: title.to_string(),
+        album: Some("Album".to_string()),
+        timestamp,
+    }
+}
+
+fn offline(_: &PendingScrobble) -> Result<(), Error> {
+    Err(Error::ConfigError("offline".to_string()))
+}
+
+#[test]
+fn scrobble_queue_sends_in_play_order() {
+    let queue = ScrobbleQueue::in_memory();
+    queue.push(scrobble("a", NOW - 20)).unwrap();
+    queue.push(scrobble("b", NOW - 10)).unwrap();
+
+    let mut sent = Vec::new();
+    let count = queue
+        .flush(NOW, |scrobble| {
+            sent.push(scrobble.title.clone());
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(count, 2);
+    assert_eq!(sent, vec!["a", "b"]);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn scrobble_queue_keeps_scrobbles_and_backs_off_after_failure() {
+    let queue = ScrobbleQueue::in_memory();
+    queue.push(scrobble("a", NOW)).unwrap();
+
+    assert!(queue.flush(NOW, offline).is_err());
+    assert_eq!(queue.len(), 1);
+
+    // Still backing off, nothing is sent.
+    let mut attempts = 0;
+    let count = queue
+        .flush(NOW, |_| {
+            attempts += 1;
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!((count, attempts), (0, 0));
+
+    // The connection came back.
+    queue.retry_now();
+    assert_eq!(queue.flush(NOW, |_| Ok(())).unwrap(), 1);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn scrobble_queue_drops_scrobbles_rejected_by_last_fm() {
+    let queue = ScrobbleQueue::in_memory();
+    queue.push(scrobble("invalid", NOW - 20)).unwrap();
+    queue.push(scrobble("valid", NOW - 10)).unwrap();
+
+    let mut sent = Vec::new();
+    let count = queue
+        .flush(NOW, |scrobble| {
+            if scrobble.title == "invalid" {
+                return Err(Error::ScrobblerError(Box::new(ScrobblerError::new(
+                    r#"{"error":6,"message":"Invalid parameters"}"#.to_string(),
+                ))));
+            }
+            sent.push(scrobble.title.clone());
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(count, 1);
+    assert_eq!(sent, vec!["valid"]);
+    assert!(queue.is_empty());
+    assert_eq!(queue.retry_in(), None);
+}
+
+#[test]
+fn scrobble_queue_tells_when_to_retry() {
+    let queue = ScrobbleQueue::in_memory();
+    queue.push(scrobble("a", NOW)).unwrap();
+    assert_eq!(queue.retry_in(), None);
+
+    assert!(queue.flush(NOW, offline).is_err());
+    let retry_in = queue.retry_in().expect("a retry is due");
+    assert!(retry_in <= retry_backoff(1));
+}
+
+#[test]
+fn scrobble_queue_drops_scrobbles_too_old_for_last_fm() {
+    let queue = ScrobbleQueue::in_memory();
+    queue.push(scrobble("old", NOW - 15 * DAY)).unwrap();
+    queue.push(scrobble("new", NOW - DAY)).unwrap();
+
+    let mut sent = Vec::new();
+    queue
+        .flush(NOW, |scrobble| {
+            sent.push(scrobble.title.clone());
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(sent, vec!["new"]);
+}
+
+#[test]
+fn scrobble_queue_persists_across_reopen() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let path = temp_dir.path().join("scrobbles.jsonl");
+
+    let queue = ScrobbleQueue::open(path.clone()).unwrap();
+    queue.push(scrobble("a", NOW - 20)).unwrap();
+    queue.push(scrobble("b", NOW - 10)).unwrap();
+    let _ = queue.flush(NOW, offline);
+
+    let reopened = ScrobbleQueue::open(path).unwrap();
+    assert_eq!(
+        reopened.pending(),
+        vec![scrobble("a", NOW - 20), scrobble("b", NOW - 10)]
+    );
+}
+
+#[test]
+fn retry_backoff_doubles_up_to_an_hour() {
+    assert_eq!(retry_backoff(1), Duration::from_secs(30));
+    assert_eq!(retry_backoff(2), Duration::from_secs(60));
+    assert_eq!(retry_backoff(3), Duration::from_secs(120));
+    assert_eq!(retry_backoff(20), Duration::from_secs(60 * 60));
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use psst_core::{
+    audio::normalize::NormalizationLevel,
+    item_id::{ItemId, ItemIdType},
+    player::{
+        item::{ItemGroup, PlaybackItem},
+        shuffle::smart_shuffle,
+    },
+};
+
+fn item(id: u128, artist: u128, album: u128) -> PlaybackItem {
+    PlaybackItem {
+        item_id: ItemId::new(id, ItemIdType::Track),
+        norm_level: NormalizationLevel::None,
+        group: ItemGroup {
+            artist: Some(ItemId::new(artist, ItemIdType::Unknown)),
+            album: Some(ItemId::new(album, ItemIdType::Unknown)),
+        },
+        skip_intro: Duration::ZERO,
+    }
+}
+
+fn longest_run(order: &[usize], key: impl Fn(usize) -> u128) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+    for &index in order {
+        let current = key(index);
+        run = if previous == Some(current) {
+            run + 1
+        } else {
+            1
+        };
+        longest = longest.max(run);
+        previous = Some(current);
+    }
+    longest
+}
+
+#[test]
+fn smart_shuffle_keeps_every_item() {
+    let items: Vec<_> = (0..20).map(|id| item(id, id % 3, id % 5)).collect();
+    let mut order: Vec<_> = (0..items.len()).collect();
+
+    smart_shuffle(&items, &mut order, &mut rand::rng());
+
+    order.sort_unstable();
+    assert_eq!(order, (0..items.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn smart_shuffle_spreads_artists_apart() {
+    // Two artists with ten tracks each never play more than twice in a row.
+    let items: Vec<_> = (0..20).map(|id| item(id, id % 2, id % 2)).collect();
+    for _ in 0..100 {
+        let mut order: Vec<_> = (0..items.len()).collect();
+        smart_shuffle(&items, &mut order, &mut rand::rng());
+        assert!(longest_run(&order, |index| index as u128 % 2) <= 2);
+    }
+}
+
+#[test]
+fn smart_shuffle_interleaves_albums_of_one_artist() {
+    // One artist, two albums of six tracks each.
+    let items: Vec<_> = (0..12).map(|id| item(id, 0, id % 2)).collect();
+    for _ in 0..100 {
+        let mut order: Vec<_> = (0..items.len()).collect();
+        smart_shuffle(&items, &mut order, &mut rand::rng());
+        assert!(longest_run(&order, |index| index as u128 % 2) <= 2);
+    }
+}
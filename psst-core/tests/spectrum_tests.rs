@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use psst_core::audio::{
+    generator::{SignalGenerator, TestSignal},
+    source::AudioSource,
+    spectrum::{band_frequency_range, Spectrum, SpectrumSource, FFT_SIZE, SPECTRUM_BANDS},
+};
+
+const SAMPLE_RATE: u32 = 44100;
+
+struct Silence;
+
+impl AudioSource for Silence {
+    fn write(&mut self, output: &mut [f32]) -> usize {
+        output.fill(0.0);
+        output.len()
+    }
+
+    fn channel_count(&self) -> usize {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+}
+
+fn play<S: AudioSource>(source: &mut S, frames: usize) -> Vec<f32> {
+    let mut samples = vec![0.0; frames * source.channel_count()];
+    assert_eq!(source.write(&mut samples), samples.len());
+    samples
+}
+
+fn loudest_band(bands: &[f32; SPECTRUM_BANDS]) -> usize {
+    (0..SPECTRUM_BANDS)
+        .max_by(|a, b| bands[*a].total_cmp(&bands[*b]))
+        .unwrap()
+}
+
+#[test]
+fn sine_peaks_in_its_band() {
+    let spectrum = Arc::new(Spectrum::new());
+    spectrum.set_enabled(true);
+    let generator = SignalGenerator::new(TestSignal::Sine { frequency: 1000.0 }, SAMPLE_RATE, 2);
+    let mut source = SpectrumSource::new(generator, spectrum.clone());
+
+    play(&mut source, FFT_SIZE * 2);
+
+    let frame = spectrum.frame();
+    assert_eq!(frame.sequence, 2);
+    let (low, high) = band_frequency_range(loudest_band(&frame.bands));
+    assert!(low <= 1000.0 && 1000.0 < high);
+    // The generator plays at -12 dBFS.
+    let level = frame.bands[loudest_band(&frame.bands)];
+    assert!((0.75..0.9).contains(&level), "level {level}");
+}
+
+#[test]
+fn silence_has_no_levels() {
+    let spectrum = Arc::new(Spectrum::new());
+    spectrum.set_enabled(true);
+    let mut source = SpectrumSource::new(Silence, spectrum.clone());
+
+    play(&mut source, FFT_SIZE);
+
+    let frame = spectrum.frame();
+    assert_eq!(frame.sequence, 1);
+    assert!(frame.bands.iter().all(|level| *level == 0.0));
+}
+
+#[test]
+fn samples_pass_through_and_analysis_waits_to_be_enabled() {
+    let spectrum = Arc::new(Spectrum::new());
+    let signal = TestSignal::Sine { frequency: 440.0 };
+    let mut source = SpectrumSource::new(
+        SignalGenerator::new(signal, SAMPLE_RATE, 2),
+        spectrum.clone(),
+    );
+    let mut plain = SignalGenerator::new(signal, SAMPLE_RATE, 2);
+
+    assert_eq!(play(&mut source, FFT_SIZE), play(&mut plain, FFT_SIZE));
+    assert_eq!(spectrum.frame().sequence, 0);
+
+    spectrum.set_enabled(true);
+    play(&mut source, FFT_SIZE);
+    assert_eq!(spectrum.frame().sequence, 1);
+}
+
+#[test]
+fn bands_cover_the_audible_range_in_order() {
+    let (lowest, _) = band_frequency_range(0);
+    let (_, highest) = band_frequency_range(SPECTRUM_BANDS - 1);
+    assert!(lowest < 50.0 && highest > 15_000.0);
+    for band in 1..SPECTRUM_BANDS {
+        assert_eq!(
+            band_frequency_range(band - 1).1,
+            band_frequency_range(band).0
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use psst_core::audio::stretch::{SilenceTrimmer, Tempo, TimeStretch};
+use std::f32::consts::PI;
+
+const SAMPLE_RATE: u32 = 8000;
+
+fn sine(frames: usize) -> Vec<f32> {
+    (0..frames)
+        .map(|n| (2.0 * PI * 220.0 * n as f32 / SAMPLE_RATE as f32).sin() * 0.5)
+        .collect()
+}
+
+fn noise(frames: usize) -> Vec<f32> {
+    let mut state = 1_u32;
+    (0..frames)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 24) as f32 - 0.5
+        })
+        .collect()
+}
+
+fn stretch(speed: f32, input: &[f32]) -> Vec<f32> {
+    let mut stretch = TimeStretch::new(1, SAMPLE_RATE, speed);
+    stretch.push(input);
+    let mut output = vec![0.0; input.len() * 3];
+    let written = stretch.read(&mut output);
+    output.truncate(written);
+    output
+}
+
+#[test]
+fn tempo_speed_is_clamped() {
+    assert_eq!(Tempo::new(10.0, false).speed, Tempo::MAX_SPEED);
+    assert_eq!(Tempo::new(0.1, false).speed, Tempo::MIN_SPEED);
+    assert!(Tempo::new(1.0, false).is_normal());
+    assert!(!Tempo::new(1.0, true).is_normal());
+}
+
+#[test]
+fn stretch_changes_length_by_speed() {
+    let input = sine(SAMPLE_RATE as usize * 4);
+
+    let fast = stretch(2.0, &input).len() as f32 / input.len() as f32;
+    let slow = stretch(0.5, &input).len() as f32 / input.len() as f32;
+
+    assert!((fast - 0.5).abs() < 0.05, "2x speed gave ratio {fast}");
+    assert!((slow - 2.0).abs() < 0.1, "0.5x speed gave ratio {slow}");
+}
+
+#[test]
+fn stretch_at_normal_speed_reconstructs_the_input() {
+    let input = noise(SAMPLE_RATE as usize);
+    let output = stretch(1.0, &input);
+
+    // The first half segment fades in, after that the output matches.
+    let hop = (0.04 * SAMPLE_RATE as f32) as usize / 2;
+    assert!(output.len() > hop * 2);
+    for (out, inp) in output.iter().zip(&input).skip(hop) {
+        assert!((out - inp).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn silence_trimmer_shortens_only_long_pauses() {
+    let mut trimmer = SilenceTrimmer::new(1, SAMPLE_RATE);
+    let short_pause = SAMPLE_RATE as usize / 10;
+    let long_pause = SAMPLE_RATE as usize * 2;
+
+    let tone = vec![0.5; 100];
+    let mut samples = tone.clone();
+    samples.extend(vec![0.0; short_pause]);
+    samples.extend(&tone);
+    samples.extend(vec![0.0; long_pause]);
+    samples.extend(&tone);
+
+    let kept = trimmer.trim(&mut samples);
+    let max_silence = (0.3 * SAMPLE_RATE as f32) as usize;
+    assert_eq!(kept, 300 + short_pause + max_silence);
+    assert_eq!(&samples[kept - 100..kept], &tone[..]);
+}
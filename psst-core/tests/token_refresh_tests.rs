@@ -0,0 +1,33 @@
+use std::time::{Duration, Instant};
+
+use psst_core::session::{
+    token_refresh::{is_refresh_due, REFRESH_MARGIN},
+    SessionService,
+};
+
+#[test]
+fn refresh_is_due_shortly_before_expiry() {
+    let now = Instant::now();
+    assert!(is_refresh_due(None, now));
+    assert!(is_refresh_due(Some(now), now));
+    assert!(is_refresh_due(Some(now + REFRESH_MARGIN), now));
+    assert!(!is_refresh_due(
+        Some(now + REFRESH_MARGIN + Duration::from_secs(1)),
+        now
+    ));
+}
+
+#[test]
+fn new_bearer_forgets_the_previous_expiry() {
+    let session = SessionService::empty();
+    session.set_oauth_bearer(Some("first".into()));
+    session.set_oauth_expiry(Duration::from_secs(3600));
+    assert!(session.oauth_expires_at().is_some());
+
+    // Installing the same bearer again keeps its expiry.
+    session.set_oauth_bearer(Some("first".into()));
+    assert!(session.oauth_expires_at().is_some());
+
+    session.set_oauth_bearer(Some("second".into()));
+    assert!(session.oauth_expires_at().is_none());
+}
@@ -1,12 +1,23 @@
-use crate::data::{AlbumLink, PlaylistLink, Track};
+use crate::data::{AlbumLink, Playlist, PlaylistLink, Track, TrackId};
 use druid::{Selector, WidgetId};
-use psst_core::{audio::equalizer::EqualizerConfig, item_id::ItemId, player::item::PlaybackItem};
+use psst_core::{
+    audio::{equalizer::EqualizerConfig, generator::TestSignal},
+    item_id::ItemId,
+    player::item::PlaybackItem,
+    session::ConnectionState,
+};
 use std::sync::Arc;
 use std::time::Duration;
 
 use crate::{
-    data::{Nav, PlaybackPayload, QueueBehavior, QueueEntry},
+    data::{
+        KeybindAction, Nav, PlaybackPayload, QueueEntry, QueuePlacement, RecommendationsRequest,
+        Repeat, Shuffle, TrackColumn,
+    },
+    discord::DiscordState,
+    instance::InstanceCommand,
     ui::find::Find,
+    webapi::{ApiFeature, ApiStatus},
 };
 
 // Widget IDs
@@ -18,36 +29,74 @@ pub const SHOW_ACCOUNT_SETUP: Selector = Selector::new("app.show-initial");
 pub const CLOSE_ALL_WINDOWS: Selector = Selector::new("app.close-all-windows");
 pub const QUIT_APP_WITH_SAVE: Selector = Selector::new("app.quit-with-save");
 pub const SET_FOCUS: Selector = Selector::new("app.set-focus");
+/// Run the action of a keybind, from its keys or a menu entry.
+pub const RUN_KEYBIND: Selector<KeybindAction> = Selector::new("app.run-keybind");
 pub const COPY: Selector<String> = Selector::new("app.copy-to-clipboard");
 pub const GO_TO_URL: Selector<String> = Selector::new("app.go-to-url");
 pub const OAUTH_TOKENS_REFRESHED: Selector<(String, Option<String>)> =
     Selector::new("app.oauth-tokens-refreshed");
+/// An optional feature of the Web API became usable or unusable, with
+/// whether to tell the user about it.
+pub const API_STATUS_CHANGED: Selector<(ApiFeature, ApiStatus, bool)> =
+    Selector::new("app.api-status-changed");
 pub const BEGIN_THEME_IMPORT: Selector = Selector::new("app.begin-theme-import");
+pub const BEGIN_LOCAL_FOLDER_IMPORT: Selector = Selector::new("app.begin-local-folder-import");
 pub const BEGIN_THEME_EXPORT: Selector = Selector::new("app.begin-theme-export");
+pub const BEGIN_HISTORY_EXPORT: Selector = Selector::new("app.begin-history-export");
+pub const CLEAR_HISTORY: Selector = Selector::new("app.clear-history");
 
 // Find
 pub const TOGGLE_FINDER: Selector = Selector::new("app.show-finder");
-pub const FIND_IN_PLAYLIST: Selector<Find> = Selector::new("find-in-playlist");
-pub const FIND_IN_SAVED_TRACKS: Selector<Find> = Selector::new("find-in-saved-tracks");
+pub const TOGGLE_PALETTE: Selector = Selector::new("app.toggle-palette");
+pub const FIND_IN_PAGE: Selector<Find> = Selector::new("find-in-page");
 
 // Session
 pub const SESSION_CONNECT: Selector = Selector::new("app.session-connect");
+pub const SESSION_STATE_CHANGED: Selector<ConnectionState> =
+    Selector::new("app.session-state-changed");
+pub const SESSION_CONNECT_FAILED: Selector = Selector::new("app.session-connect-failed");
 pub const LOG_OUT: Selector = Selector::new("app.log-out");
+pub const DISCORD_STATE_CHANGED: Selector<DiscordState> =
+    Selector::new("app.discord-state-changed");
+pub const APPLY_PROXY: Selector = Selector::new("app.apply-proxy");
 
 // Navigation
 pub const NAVIGATE: Selector<Nav> = Selector::new("app.navigates");
 pub const NAVIGATE_BACK: Selector<usize> = Selector::new("app.navigate-back");
 pub const NAVIGATE_REFRESH: Selector = Selector::new("app.navigate-refresh");
 pub const TOGGLE_LYRICS: Selector = Selector::new("app.toggle-lyrics");
+pub const TOGGLE_QUEUE: Selector = Selector::new("app.toggle-queue");
 
 // Playback state
 pub const PLAYBACK_LOADING: Selector<ItemId> = Selector::new("app.playback-loading");
-pub const PLAYBACK_PLAYING: Selector<(ItemId, Duration)> = Selector::new("app.playback-playing");
+/// Item, position, and the format it is played in.
+pub const PLAYBACK_PLAYING: Selector<(ItemId, Duration, Option<Arc<str>>)> =
+    Selector::new("app.playback-playing");
 pub const PLAYBACK_PROGRESS: Selector<Duration> = Selector::new("app.playback-progress");
 pub const PLAYBACK_PAUSING: Selector = Selector::new("app.playback-pausing");
 pub const PLAYBACK_RESUMING: Selector = Selector::new("app.playback-resuming");
 pub const PLAYBACK_BLOCKED: Selector = Selector::new("app.playback-blocked");
 pub const PLAYBACK_STOPPED: Selector = Selector::new("app.playback-stopped");
+pub const PLAYBACK_RECOVERED: Selector<Duration> = Selector::new("app.playback-recovered");
+/// Number of scrobbles still waiting to be sent to Last.fm.
+pub const PENDING_SCROBBLES_CHANGED: Selector<usize> =
+    Selector::new("app.lastfm.pending-scrobbles-changed");
+/// The audio output was opened on the device with the given ID.
+pub const OUTPUT_DEVICE_OPENED: Selector<Arc<str>> = Selector::new("app.output-device-opened");
+/// Latency the audio output reported when playback started.
+pub const OUTPUT_LATENCY_CHANGED: Selector<Option<Duration>> =
+    Selector::new("app.output-latency-changed");
+pub const PLAYBACK_QUALITY_CHANGED: Selector<Option<Arc<str>>> =
+    Selector::new("app.playback-quality-changed");
+pub const PLAYBACK_QUEUE_CHANGED: Selector<Vec<ItemId>> =
+    Selector::new("app.playback-queue-changed");
+/// Number of queue items skipped as duplicates, or removed with
+/// `REMOVE_QUEUE_DUPLICATES` if the flag is set.
+pub const PLAYBACK_QUEUE_DUPLICATES: Selector<(usize, bool)> =
+    Selector::new("app.playback-queue-duplicates");
+/// Recommended tracks to keep playing with once the queue runs out.
+pub const PLAYBACK_AUTOPLAY_LOADED: Selector<Vec<QueueEntry>> =
+    Selector::new("app.playback-autoplay-loaded");
 
 // Equalizer
 pub const EQUALIZER_CONFIG_CHANGED: Selector<EqualizerConfig> =
@@ -61,29 +110,86 @@ pub const PLAY_PREVIOUS: Selector = Selector::new("app.play-previous");
 pub const PLAY_PAUSE: Selector = Selector::new("app.play-pause");
 pub const PLAY_RESUME: Selector = Selector::new("app.play-resume");
 pub const PLAY_NEXT: Selector = Selector::new("app.play-next");
+pub const PLAY_PREVIOUS_CHAPTER: Selector = Selector::new("app.play-previous-chapter");
+pub const PLAY_NEXT_CHAPTER: Selector = Selector::new("app.play-next-chapter");
 pub const PLAY_STOP: Selector = Selector::new("app.play-stop");
+pub const PLAY_TEST_SIGNAL: Selector<TestSignal> = Selector::new("app.play-test-signal");
 pub const ADD_TO_QUEUE: Selector<(QueueEntry, PlaybackItem)> = Selector::new("app.add-to-queue");
-pub const PLAY_QUEUE_BEHAVIOR: Selector<QueueBehavior> = Selector::new("app.play-queue-behavior");
+pub const QUEUE_ITEMS: Selector<(QueuePlacement, Vec<QueueEntry>)> =
+    Selector::new("app.queue-items");
+/// Add an item to the end of the queue without interrupting playback, counting
+/// consecutively queued items in a single alert.
+pub const QUEUE_ITEM_QUIETLY: Selector<QueueEntry> = Selector::new("app.queue-item-quietly");
+pub const QUEUE_PLAYLIST: Selector<(PlaylistLink, QueuePlacement)> =
+    Selector::new("app.queue-playlist");
+pub const QUEUE_ALBUM: Selector<(AlbumLink, QueuePlacement)> = Selector::new("app.queue-album");
+/// Show recommendations seeded by several tracks of the playlist.
+pub const SHOW_PLAYLIST_RADIO: Selector<PlaylistLink> = Selector::new("app.show-playlist-radio");
+/// Play the recommendations of the request, as a radio.
+pub const START_RADIO: Selector<Arc<RecommendationsRequest>> = Selector::new("app.start-radio");
+/// Move an up-next item from one index to another.
+pub const MOVE_QUEUE_ITEM: Selector<(usize, usize)> = Selector::new("app.move-queue-item");
+pub const REMOVE_QUEUE_ITEM: Selector<usize> = Selector::new("app.remove-queue-item");
+pub const CLEAR_UP_NEXT: Selector = Selector::new("app.clear-up-next");
+pub const REMOVE_QUEUE_DUPLICATES: Selector = Selector::new("app.remove-queue-duplicates");
+pub const PLAY_SHUFFLE: Selector<Shuffle> = Selector::new("app.play-shuffle");
+pub const PLAY_REPEAT: Selector<Repeat> = Selector::new("app.play-repeat");
 pub const PLAY_SEEK: Selector<f64> = Selector::new("app.play-seek");
+/// Playback rate of the playing podcast episode.
+pub const PLAY_PODCAST_SPEED: Selector<f64> = Selector::new("app.play-podcast-speed");
+pub const PLAY_SKIP_SILENCE: Selector<bool> = Selector::new("app.play-skip-silence");
 pub const SKIP_TO_POSITION: Selector<u64> = Selector::new("app.skip-to-position");
+/// Love or unlove a track on Last.fm.
+pub const LASTFM_SET_LOVED: Selector<(Arc<Track>, bool)> = Selector::new("app.lastfm.set-loved");
+pub const LASTFM_LOVED_CHANGED: Selector<(TrackId, bool)> =
+    Selector::new("app.lastfm.loved-changed");
+/// Sent by `psst-gui --command` from another process.
+pub const INSTANCE_COMMAND: Selector<InstanceCommand> = Selector::new("app.instance-command");
+/// Seconds to skip at the start of every track played from a playlist.
+pub const SET_PLAYLIST_INTRO_SKIP: Selector<(PlaylistLink, u64)> =
+    Selector::new("app.set-playlist-intro-skip");
+/// Mark a playlist for offline listening and download it, or unmark it.
+pub const SET_PLAYLIST_OFFLINE: Selector<(Playlist, bool)> =
+    Selector::new("app.set-playlist-offline");
 
-// Sorting control
-pub const SORT_BY_DATE_ADDED: Selector = Selector::new("app.sort-by-date-added");
-pub const SORT_BY_TITLE: Selector = Selector::new("app.sort-by-title");
-pub const SORT_BY_ARTIST: Selector = Selector::new("app.sort-by-artist");
-pub const SORT_BY_ALBUM: Selector = Selector::new("app.sort-by-album");
-pub const SORT_BY_DURATION: Selector = Selector::new("app.sort-by-duration");
-
-// Sort direction control
-pub const TOGGLE_SORT_ORDER: Selector = Selector::new("app.toggle-sort-order");
+// Track tables
+/// Sort the track table of the page by the column, or reverse it if it is
+/// already sorted by it.
+pub const SORT_TRACKS: Selector<TrackColumn> = Selector::new("app.sort-tracks");
 
 // Track credits
 pub const SHOW_CREDITS_WINDOW: Selector<Arc<Track>> = Selector::new("app.credits-show-window");
 pub const LOAD_TRACK_CREDITS: Selector<Arc<Track>> = Selector::new("app.credits-load");
+pub const LOAD_AUDIO_FEATURES: Selector<TrackId> = Selector::new("app.audio-features-load");
+
+// Unplayable tracks
+/// Search for a playable release of an unplayable track, and show it.
+pub const FIND_PLAYABLE_VERSION: Selector<Arc<Track>> = Selector::new("app.find-playable-version");
+
+// Selecting and dragging tracks onto playlists
+pub const TOGGLE_TRACK_SELECTION: Selector<Arc<Track>> =
+    Selector::new("app.toggle-track-selection");
+pub const CLEAR_TRACK_SELECTION: Selector = Selector::new("app.clear-track-selection");
+pub const START_TRACK_DRAG: Selector<Arc<Track>> = Selector::new("app.start-track-drag");
+pub const END_TRACK_DRAG: Selector = Selector::new("app.end-track-drag");
+
+// Local files
+pub const EXPORT_REPLAY_GAIN: Selector<Arc<Track>> = Selector::new("app.export-replay-gain");
 
 // Artwork
 pub const SHOW_ARTWORK: Selector = Selector::new("app.show-artwork");
 
+// Now playing view
+pub const TOGGLE_NOW_PLAYING: Selector = Selector::new("app.toggle-now-playing");
+pub const TOGGLE_MINI_PLAYER: Selector = Selector::new("app.toggle-mini-player");
+
+// Library
+pub const SYNC_LIBRARY: Selector = Selector::new("app.sync-library");
+pub const TOGGLE_OFFLINE_MODE: Selector = Selector::new("app.toggle-offline-mode");
+
+// Generated playlists
+pub const SYNC_GENERATED_PLAYLISTS: Selector = Selector::new("app.sync-generated-playlists");
+
 // Updates
 pub const CHECK_FOR_UPDATES: Selector = Selector::new("app.check-for-updates");
 pub const INSTALL_UPDATE: Selector<crate::data::UpdateInfo> = Selector::new("app.install-update");
@@ -21,6 +21,7 @@ impl<W: Widget<AppState>> Controller<AppState, W> for AlertCleanupController {
             }
             Event::Timer(_) => {
                 data.cleanup_alerts();
+                data.release_held_notifications();
                 ctx.request_timer(CLEANUP_INTERVAL);
             }
             _ => {}
@@ -0,0 +1,59 @@
+use druid::{widget::Controller, Cursor, Data, Env, Event, EventCtx, Point, Widget};
+
+/// Moves the dragged row by the number of rows the mouse has travelled when the
+/// button is released.  All rows have the same height, so the distance tells
+/// the target index without needing the positions of the other rows.
+pub struct DragToReorder<F> {
+    start: Option<Point>,
+    handler: F,
+}
+
+impl<F> DragToReorder<F> {
+    /// `handler` is called with the number of rows the row was dragged by,
+    /// negative for upwards.
+    pub fn new<T>(handler: F) -> Self
+    where
+        F: Fn(&mut EventCtx, &mut T, isize),
+    {
+        Self {
+            start: None,
+            handler,
+        }
+    }
+}
+
+impl<T, F, W> Controller<T, W> for DragToReorder<F>
+where
+    T: Data,
+    F: Fn(&mut EventCtx, &mut T, isize),
+    W: Widget<T>,
+{
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                self.start = Some(mouse.window_pos);
+                ctx.set_active(true);
+            }
+            Event::MouseMove(mouse) if ctx.is_active() => {
+                if let Some(start) = self.start {
+                    if (mouse.window_pos.y - start.y).abs() > ctx.size().height / 2.0 {
+                        ctx.set_cursor(&Cursor::ResizeUpDown);
+                    }
+                }
+            }
+            Event::MouseUp(mouse) if mouse.button.is_left() => {
+                if let Some(start) = self.start.take() {
+                    ctx.set_active(false);
+                    ctx.clear_cursor();
+                    let rows =
+                        ((mouse.window_pos.y - start.y) / ctx.size().height).round() as isize;
+                    if rows != 0 {
+                        (self.handler)(ctx, data, rows);
+                    }
+                }
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use druid::{widget::Controller, Cursor, Data, Env, Event, EventCtx, Point, Widget};
+
+use crate::{
+    cmd,
+    data::{Track, WithCtx},
+};
+
+/// How far the mouse has to travel with the button held before a click turns
+/// into a drag.
+const DRAG_THRESHOLD: f64 = 6.0;
+
+/// Lets a track row be dragged.  The dragged track is kept in the common
+/// context until the button is released, so drop targets anywhere in the
+/// window can see it.
+pub struct DragTrack<F> {
+    start: Option<Point>,
+    dragging: bool,
+    track: F,
+}
+
+impl<F> DragTrack<F> {
+    /// `track` returns the track to drag, or `None` if the row can't be
+    /// dragged.
+    pub fn new<T>(track: F) -> Self
+    where
+        F: Fn(&T) -> Option<Arc<Track>>,
+    {
+        Self {
+            start: None,
+            dragging: false,
+            track,
+        }
+    }
+}
+
+impl<T, F, W> Controller<T, W> for DragTrack<F>
+where
+    T: Data,
+    F: Fn(&T) -> Option<Arc<Track>>,
+    W: Widget<T>,
+{
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                self.start = Some(mouse.window_pos);
+                ctx.set_active(true);
+            }
+            Event::MouseMove(mouse) if ctx.is_active() && !self.dragging => {
+                let moved = self
+                    .start
+                    .is_some_and(|start| start.distance(mouse.window_pos) > DRAG_THRESHOLD);
+                if moved {
+                    if let Some(track) = (self.track)(data) {
+                        self.dragging = true;
+                        ctx.set_cursor(&Cursor::Pointer);
+                        ctx.submit_command(cmd::START_TRACK_DRAG.with(track));
+                    }
+                }
+            }
+            Event::MouseUp(mouse) if mouse.button.is_left() => {
+                if self.start.take().is_some() {
+                    ctx.set_active(false);
+                }
+                if self.dragging {
+                    self.dragging = false;
+                    ctx.clear_cursor();
+                    ctx.submit_command(cmd::END_TRACK_DRAG);
+                    // Not a click on the row, but not handled either, the
+                    // drop targets after this row see the release as well.
+                    return;
+                }
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
+/// Data of a drop target, which sees the dragged track in the common context.
+pub trait DragContext {
+    fn dragged_track(&self) -> Option<&Arc<Track>>;
+}
+
+impl<T> DragContext for WithCtx<T> {
+    fn dragged_track(&self) -> Option<&Arc<Track>> {
+        self.ctx.dragged_track.as_ref()
+    }
+}
+
+/// Accepts a track dragged with `DragTrack` when it is released over the
+/// widget.  The release is passed on, the row it was dragged from has to see
+/// it to end the drag.
+pub struct DropTrack<F> {
+    handler: F,
+}
+
+impl<F> DropTrack<F> {
+    pub fn new<T>(handler: F) -> Self
+    where
+        F: Fn(&mut EventCtx, &mut T, Arc<Track>),
+    {
+        Self { handler }
+    }
+}
+
+impl<T, F, W> Controller<T, W> for DropTrack<F>
+where
+    T: Data + DragContext,
+    F: Fn(&mut EventCtx, &mut T, Arc<Track>),
+    W: Widget<T>,
+{
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::MouseUp(mouse) = event {
+            if mouse.button.is_left() && ctx.is_hot() {
+                if let Some(track) = data.dragged_track().cloned() {
+                    (self.handler)(ctx, data, track);
+                }
+            }
+        }
+        child.event(ctx, event, data, env);
+    }
+}
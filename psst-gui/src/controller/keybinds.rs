@@ -0,0 +1,71 @@
+use druid::{
+    widget::{prelude::*, Controller},
+    Command,
+};
+
+use crate::{
+    cmd,
+    data::{AppState, KeybindAction, Nav},
+};
+
+/// Runs the keybind actions, for the keys the focused widget leaves alone and
+/// for the menu entries.  The playback actions are passed on to the
+/// `PlaybackController`.
+pub struct KeybindsController;
+
+impl KeybindsController {
+    fn command(action: KeybindAction) -> Option<Command> {
+        let command = match action {
+            KeybindAction::Refresh => cmd::NAVIGATE_REFRESH.into(),
+            KeybindAction::GoHome => cmd::NAVIGATE.with(Nav::Home),
+            KeybindAction::GoSavedTracks => cmd::NAVIGATE.with(Nav::SavedTracks),
+            KeybindAction::GoSavedAlbums => cmd::NAVIGATE.with(Nav::SavedAlbums),
+            KeybindAction::GoShows => cmd::NAVIGATE.with(Nav::Shows),
+            KeybindAction::FocusSearch => cmd::SET_FOCUS.to(cmd::WIDGET_SEARCH_INPUT),
+            KeybindAction::ToggleFinder => cmd::TOGGLE_FINDER.into(),
+            KeybindAction::ToggleNowPlaying => cmd::TOGGLE_NOW_PLAYING.into(),
+            KeybindAction::ToggleMiniPlayer => cmd::TOGGLE_MINI_PLAYER.into(),
+            KeybindAction::CommandPalette => cmd::TOGGLE_PALETTE.into(),
+            KeybindAction::Quit => cmd::QUIT_APP_WITH_SAVE.into(),
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+impl<W> Controller<AppState, W> for KeybindsController
+where
+    W: Widget<AppState>,
+{
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(cmd::RUN_KEYBIND) => {
+                match Self::command(*cmd.get_unchecked(cmd::RUN_KEYBIND)) {
+                    Some(command) => {
+                        ctx.submit_command(command);
+                        ctx.set_handled();
+                    }
+                    None => child.event(ctx, event, data, env),
+                }
+            }
+            Event::KeyDown(key) => {
+                // Text boxes and sliders take their keys first.
+                child.event(ctx, event, data, env);
+                if !ctx.is_handled() {
+                    if let Some(action) = data.config.keybinds.action_for(key) {
+                        ctx.submit_command(cmd::RUN_KEYBIND.with(action));
+                        ctx.set_handled();
+                    }
+                }
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
@@ -1,31 +1,45 @@
 mod after_delay;
 mod alert_cleanup;
 mod double_click;
+mod drag_to_reorder;
+mod drag_track;
 mod ex_click;
 mod ex_cursor;
 mod ex_scroll;
 mod input;
+mod keybinds;
 mod nav;
+mod network;
+mod offline;
 mod on_command;
 mod on_command_async;
 mod on_debounce;
 mod on_update;
 mod playback;
+mod scroll_restore;
 mod session;
 mod sort;
+mod tray;
 
 pub use after_delay::AfterDelay;
 pub use alert_cleanup::AlertCleanupController;
 pub use double_click::DoubleClick;
+pub use drag_to_reorder::DragToReorder;
+pub use drag_track::{DragContext, DragTrack, DropTrack};
 pub use ex_click::ExClick;
 pub use ex_cursor::ExCursor;
 pub use ex_scroll::ExScroll;
 pub use input::InputController;
+pub use keybinds::KeybindsController;
 pub use nav::NavController;
+pub use network::NetworkController;
+pub use offline::OfflineController;
 pub use on_command::OnCommand;
 pub use on_command_async::OnCommandAsync;
 pub use on_debounce::OnDebounce;
 pub use on_update::OnUpdate;
 pub use playback::PlaybackController;
+pub use scroll_restore::ScrollRestore;
 pub use session::SessionController;
 pub use sort::SortController;
+pub use tray::TrayController;
@@ -1,21 +1,60 @@
 use crate::{
     cmd,
-    data::{AppState, Nav, SpotifyUrl},
-    ui::{album, artist, library, lyrics, playlist, recommend, search, show},
+    data::{AppState, ListeningActivity, Nav, PaletteEntry, SavedTracks, SpotifyUrl},
+    ui::{
+        album, artist, browse, history, home, library, local, lyrics, playlist, recommend, search,
+        show, stats,
+    },
 };
 use druid::widget::{prelude::*, Controller};
-use druid::Code;
+use psst_core::history::PlayHistory;
 
 pub struct NavController;
 
 impl NavController {
     fn load_route_data(&self, ctx: &mut EventCtx, data: &mut AppState) {
         match &data.nav {
-            Nav::Home => {}
+            Nav::Home => {
+                if data
+                    .home_detail
+                    .made_for_you
+                    .resolved()
+                    .is_some_and(|made_for_you| made_for_you.is_stale())
+                {
+                    ctx.submit_command(home::REFRESH_MADE_FOR_YOU);
+                }
+                // Friends' listening changes all the time, the Last.fm client
+                // caches it for a few minutes.
+                if let Some(user) = data.config.lastfm_friends_user() {
+                    ctx.submit_command(home::LOAD_LASTFM_FRIENDS.with(user));
+                }
+            }
+            Nav::Browse => {
+                if !data.browse.featured.is_resolved() {
+                    ctx.submit_command(browse::LOAD_FEATURED.with(0));
+                }
+                if !data.browse.categories.is_resolved() {
+                    ctx.submit_command(browse::LOAD_CATEGORIES.with(0));
+                }
+            }
+            Nav::NewReleases => {
+                if !data.browse.new_releases.is_resolved() {
+                    ctx.submit_command(browse::LOAD_NEW_RELEASES.with(0));
+                }
+            }
+            Nav::BrowseCategory(link) => {
+                if !data.browse.category.contains(link) {
+                    ctx.submit_command(browse::LOAD_CATEGORY.with((link.to_owned(), 0)));
+                }
+            }
             Nav::Lyrics => {}
             Nav::SavedTracks => {
                 if !data.library.saved_tracks.is_resolved() {
-                    ctx.submit_command(library::LOAD_TRACKS);
+                    let all = !SavedTracks::is_paged(
+                        data.config.saved_tracks_sort,
+                        &data.common_ctx.track_filters,
+                    );
+                    ctx.submit_command(library::LOAD_TRACKS.with(all));
                 }
             }
             Nav::SavedAlbums => {
@@ -28,11 +67,44 @@ impl NavController {
                     ctx.submit_command(library::LOAD_SHOWS);
                 }
             }
+            Nav::SavedEpisodes => {
+                if !data.library.saved_episodes.is_resolved() {
+                    ctx.submit_command(library::LOAD_EPISODES);
+                }
+            }
+            Nav::LocalFiles => {
+                if !data.local_files.contains(&data.config.local_folders) {
+                    ctx.submit_command(
+                        local::LOAD_LOCAL_FILES.with(data.config.local_folders.clone()),
+                    );
+                }
+            }
+            Nav::History => {
+                // Reload when plays were recorded since the page was loaded.
+                let count = PlayHistory::global().play_count();
+                if !data.listening_history.contains(&count) {
+                    ctx.submit_command(history::LOAD_HISTORY.with(count));
+                }
+            }
+            Nav::Stats => {
+                let range = data.stats.range;
+                if !data.stats.top_artists.contains(&range) {
+                    ctx.submit_command(stats::LOAD_TOP_ARTISTS.with(range));
+                }
+                if !data.stats.top_tracks.contains(&range) {
+                    ctx.submit_command(stats::LOAD_TOP_TRACKS.with(range));
+                }
+                data.stats.activity = ListeningActivity::load();
+            }
             Nav::SearchResults(query) => {
                 if let Some(link) = SpotifyUrl::parse(query) {
                     ctx.submit_command(search::OPEN_LINK.with(link));
-                } else if !data.search.results.contains(query) {
-                    ctx.submit_command(search::LOAD_RESULTS.with(query.to_owned()));
+                } else {
+                    data.config.add_search(query);
+                    data.config.save();
+                    if !data.search.results.contains(query) {
+                        ctx.submit_command(search::LOAD_RESULTS.with(query.to_owned()));
+                    }
                 }
             }
             Nav::AlbumDetail(link, _) => {
@@ -81,6 +153,7 @@ where
         match event {
             Event::Command(cmd) if cmd.is(cmd::NAVIGATE) => {
                 let nav = cmd.get_unchecked(cmd::NAVIGATE);
+                data.search.close_dropdown();
                 data.navigate(nav);
                 ctx.set_handled();
                 self.load_route_data(ctx, data);
@@ -104,6 +177,7 @@ where
                     data.lyrics_visible = false;
                 } else {
                     data.lyrics_visible = true;
+                    data.queue_visible = false;
                     if let Some(np) = data.playback.now_playing.as_ref() {
                         ctx.submit_command(lyrics::SHOW_LYRICS.with(np.clone()));
                     }
@@ -111,13 +185,29 @@ where
                 ctx.set_handled();
                 self.load_route_data(ctx, data);
             }
-            Event::MouseDown(cmd) if cmd.button.is_x1() => {
-                data.navigate_back();
+            Event::WindowConnected => {
+                data.palette.register(PaletteEntry::new(
+                    "View",
+                    "Show or hide the lyrics",
+                    cmd::TOGGLE_LYRICS.into(),
+                ));
+                data.palette.register(PaletteEntry::new(
+                    "View",
+                    "Show or hide the queue",
+                    cmd::TOGGLE_QUEUE.into(),
+                ));
+                child.event(ctx, event, data, env);
+            }
+            Event::Command(cmd) if cmd.is(cmd::TOGGLE_QUEUE) => {
+                // The queue shares the right-hand panel with the lyrics.
+                data.queue_visible = !data.queue_visible;
+                if data.queue_visible {
+                    data.lyrics_visible = false;
+                }
                 ctx.set_handled();
-                self.load_route_data(ctx, data);
             }
-            Event::KeyDown(key) if key.mods.meta() && key.code == Code::KeyR => {
-                data.refresh();
+            Event::MouseDown(cmd) if cmd.button.is_x1() => {
+                data.navigate_back();
                 ctx.set_handled();
                 self.load_route_data(ctx, data);
             }
@@ -0,0 +1,107 @@
+use std::{thread, time::Duration};
+
+use druid::{
+    widget::{prelude::*, Controller},
+    Selector, Target, TimerToken,
+};
+
+use crate::{
+    data::{AppState, MeteredBehavior},
+    network,
+};
+
+/// Result of a metered connection check, `None` if the system does not tell.
+const METERED_CHECKED: Selector<Option<bool>> = Selector::new("app.network.metered-checked");
+
+const CHECK_NOW: Selector = Selector::new("app.network.check-now");
+
+/// How often the connection is checked.  Windows has to start PowerShell for
+/// every check, so it is asked less often there.
+#[cfg(not(windows))]
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+#[cfg(windows)]
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Periodically checks if the connection is metered, unless the user does
+/// not care about it.
+pub struct NetworkController {
+    timer: TimerToken,
+}
+
+impl NetworkController {
+    pub fn new() -> Self {
+        Self {
+            timer: TimerToken::INVALID,
+        }
+    }
+
+    fn check(&mut self, ctx: &mut EventCtx, data: &AppState) {
+        self.timer = ctx.request_timer(CHECK_INTERVAL);
+        if data.config.metered_behavior == MeteredBehavior::Ignore {
+            return;
+        }
+        let sink = ctx.get_external_handle();
+        let widget_id = ctx.widget_id();
+        thread::spawn(move || {
+            let metered = network::is_metered();
+            let _ = sink.submit_command(METERED_CHECKED, metered, Target::Widget(widget_id));
+        });
+    }
+}
+
+impl<W> Controller<AppState, W> for NetworkController
+where
+    W: Widget<AppState>,
+{
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::WindowConnected => {
+                self.check(ctx, data);
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.check(ctx, data);
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.is(CHECK_NOW) => {
+                self.check(ctx, data);
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.is(METERED_CHECKED) => {
+                let metered = *cmd.get_unchecked(METERED_CHECKED);
+                if data.network_metered != metered {
+                    log::info!("metered connection: {metered:?}");
+                    data.network_metered = metered;
+                    // Ask again the next time we get on a metered connection.
+                    data.metered_confirmed = false;
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env)
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &AppState,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if old_data.config.metered_behavior != data.config.metered_behavior {
+            ctx.submit_command(CHECK_NOW.to(ctx.widget_id()));
+        }
+        child.update(ctx, old_data, data, env)
+    }
+}
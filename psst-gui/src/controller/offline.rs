@@ -0,0 +1,355 @@
+use std::{collections::HashSet, sync::Arc, thread};
+
+use druid::{
+    widget::{prelude::*, Controller},
+    Selector, Target,
+};
+use psst_core::{
+    cdn::Cdn,
+    item_id::ItemId,
+    offline::{DownloadManager, SyncEvent, SyncJob, SyncStatus},
+    player::item,
+};
+
+use super::SessionController;
+use crate::{
+    cmd,
+    data::{AppState, NotificationEvent, OfflinePlaylist, PaletteEntry, Playlist},
+    ui::playlist,
+    webapi::WebApi,
+};
+
+/// Progress of the downloads, sent from the download thread.
+const SYNC_EVENT: Selector<SyncEvent> = Selector::new("app.offline.sync-event");
+
+/// Download the marked playlists that changed since their last download.
+const SYNC_OUT_OF_DATE: Selector = Selector::new("app.offline.sync-out-of-date");
+
+/// The app went offline or back online, see `AppState::is_offline`.
+const OFFLINE_CHANGED: Selector = Selector::new("app.offline.offline-changed");
+
+/// Downloads were held back or may continue, see `AppState::can_download`.
+const CAN_DOWNLOAD_CHANGED: Selector = Selector::new("app.offline.can-download-changed");
+
+/// Items that play from the cache, scanned on a background thread.
+const OFFLINE_ITEMS: Selector<Arc<HashSet<ItemId>>> = Selector::new("app.offline.offline-items");
+
+/// Keeps the playlists marked for offline listening downloaded, and tracks
+/// the progress of their downloads for the sync badges.  Also switches the
+/// app offline and back, see `AppState::is_offline`.
+pub struct OfflineController {
+    /// Started with the first download.
+    manager: Option<DownloadManager>,
+}
+
+impl OfflineController {
+    pub fn new() -> Self {
+        Self { manager: None }
+    }
+
+    fn manager(&mut self, ctx: &mut EventCtx, data: &AppState) -> Option<DownloadManager> {
+        if self.manager.is_none() {
+            let cache = data.preferences.cache.clone()?;
+            let cdn = Cdn::new(data.session.clone())
+                .map_err(|err| log::error!("failed to create the CDN client: {err}"))
+                .ok()?;
+            let sink = ctx.get_external_handle();
+            let widget_id = ctx.widget_id();
+            self.manager = Some(DownloadManager::new(
+                data.session.clone(),
+                cdn,
+                cache,
+                data.config.playback().quality,
+                move |event| {
+                    let _ = sink.submit_command(SYNC_EVENT, event, Target::Widget(widget_id));
+                },
+            ));
+        }
+        self.manager.clone()
+    }
+
+    fn sync(&mut self, ctx: &mut EventCtx, data: &mut AppState, playlist: &Playlist) {
+        if !data.can_download() {
+            // Picked up once online and off a metered connection.
+            return;
+        }
+        let Some(manager) = self.manager(ctx, data) else {
+            return;
+        };
+        let total = playlist.track_count.unwrap_or(0);
+        data.with_offline_playlists_mut(|playlists| {
+            if let Some(offline) = playlists.get_mut(&playlist.id) {
+                offline.progress = Some((0, total));
+            }
+        });
+
+        let sink = ctx.get_external_handle();
+        let widget_id = ctx.widget_id();
+        let playlist_id = playlist.id.to_string();
+        let snapshot_id = playlist.snapshot_id.to_string();
+        thread::spawn(
+            move || match WebApi::global().get_playlist_tracks(&playlist_id) {
+                Ok(tracks) => manager.sync(SyncJob {
+                    playlist_id,
+                    snapshot_id,
+                    items: tracks.iter().map(|track| track.id.0).collect(),
+                }),
+                Err(err) => {
+                    // The playlist stays out of date, and is tried again with
+                    // the next change of the library.
+                    log::warn!("failed to load the tracks of playlist {playlist_id}: {err}");
+                    let event = SyncEvent::Cancelled { playlist_id };
+                    let _ = sink.submit_command(SYNC_EVENT, event, Target::Widget(widget_id));
+                }
+            },
+        );
+    }
+
+    fn sync_out_of_date(&mut self, ctx: &mut EventCtx, data: &mut AppState) {
+        let Some(playlists) = data.library.playlists.resolved() else {
+            return;
+        };
+        let out_of_date: Vec<Playlist> = playlists
+            .iter()
+            .filter(|playlist| {
+                data.config
+                    .offline_playlists
+                    .get(&playlist.id)
+                    .is_some_and(|offline| offline.status(playlist) == SyncStatus::OutOfDate)
+            })
+            .cloned()
+            .collect();
+        for playlist in &out_of_date {
+            self.sync(ctx, data, playlist);
+        }
+    }
+
+    /// Stop the running downloads, they continue where they stopped with the
+    /// next sync.
+    fn cancel_downloads(&self, data: &AppState) {
+        if let Some(manager) = &self.manager {
+            for (playlist_id, playlist) in data.config.offline_playlists.iter() {
+                if playlist.progress.is_some() {
+                    manager.cancel(playlist_id);
+                }
+            }
+        }
+    }
+
+    fn can_download_changed(&mut self, ctx: &mut EventCtx, data: &mut AppState) {
+        if data.can_download() {
+            self.sync_out_of_date(ctx, data);
+        } else if !data.is_offline() {
+            log::info!("holding back offline downloads on a metered connection");
+            self.cancel_downloads(data);
+        }
+    }
+
+    fn offline_changed(&mut self, ctx: &mut EventCtx, data: &mut AppState) {
+        let offline = data.is_offline();
+        WebApi::global().set_offline(offline);
+        if offline {
+            // The running downloads would only fail, they continue once
+            // online.
+            self.cancel_downloads(data);
+            if data.config.offline_mode {
+                // Closed for good, instead of reconnecting in the background.
+                let session = data.session.clone();
+                thread::spawn(move || session.shutdown());
+            }
+            Self::scan_offline_items(ctx, data);
+        } else {
+            data.common_ctx_mut().offline_items = None;
+            // The session opens again with the first request.
+            ctx.submit_command(playlist::LOAD_LIST);
+            ctx.submit_command(cmd::SYNC_LIBRARY);
+        }
+    }
+
+    fn scan_offline_items(ctx: &mut EventCtx, data: &AppState) {
+        let Some(cache) = data.preferences.cache.clone() else {
+            return;
+        };
+        let quality = data.config.playback().quality;
+        let sink = ctx.get_external_handle();
+        let widget_id = ctx.widget_id();
+        thread::spawn(move || match item::cached_items(&cache, quality) {
+            Ok(items) => {
+                let items = Arc::new(items);
+                let _ = sink.submit_command(OFFLINE_ITEMS, items, Target::Widget(widget_id));
+            }
+            Err(err) => log::error!("failed to scan the cache for offline items: {err}"),
+        });
+    }
+
+    fn handle_event(data: &mut AppState, event: &SyncEvent) {
+        match event {
+            SyncEvent::Progress {
+                playlist_id,
+                done,
+                total,
+            } => data.with_offline_playlists_mut(|playlists| {
+                if let Some(offline) = playlists.get_mut(playlist_id.as_str()) {
+                    offline.progress = Some((*done, *total));
+                }
+            }),
+            SyncEvent::Finished {
+                playlist_id,
+                snapshot_id,
+                failed,
+            } => {
+                data.with_offline_playlists_mut(|playlists| {
+                    if let Some(offline) = playlists.get_mut(playlist_id.as_str()) {
+                        offline.progress = None;
+                        if *failed == 0 {
+                            offline.synced_snapshot = Some(snapshot_id.as_str().into());
+                        }
+                    }
+                });
+                if *failed > 0 {
+                    data.notify(
+                        NotificationEvent::Error,
+                        format!("{failed} tracks could not be downloaded for offline listening."),
+                    );
+                } else {
+                    let name = data
+                        .library
+                        .playlists
+                        .resolved()
+                        .and_then(|playlists| {
+                            playlists
+                                .iter()
+                                .find(|p| p.id.as_ref() == playlist_id.as_str())
+                        })
+                        .map(|playlist| playlist.name.clone());
+                    if let Some(name) = name {
+                        data.notify(
+                            NotificationEvent::DownloadComplete,
+                            format!("{name} is downloaded for offline listening."),
+                        );
+                    }
+                }
+            }
+            SyncEvent::Cancelled { playlist_id } => {
+                data.with_offline_playlists_mut(|playlists| {
+                    if let Some(offline) = playlists.get_mut(playlist_id.as_str()) {
+                        offline.progress = None;
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl<W> Controller<AppState, W> for OfflineController
+where
+    W: Widget<AppState>,
+{
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(cmd::SET_PLAYLIST_OFFLINE) => {
+                let (playlist, offline) = cmd.get_unchecked(cmd::SET_PLAYLIST_OFFLINE);
+                if *offline {
+                    data.with_offline_playlists_mut(|playlists| {
+                        playlists.insert(playlist.id.clone(), OfflinePlaylist::default());
+                    });
+                    if !data.can_download() && !data.is_offline() {
+                        data.info_alert("The download starts once off the metered connection.");
+                    }
+                    self.sync(ctx, data, playlist);
+                } else {
+                    data.with_offline_playlists_mut(|playlists| {
+                        playlists.remove(&playlist.id);
+                    });
+                    if let Some(manager) = &self.manager {
+                        manager.cancel(&playlist.id);
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(SYNC_EVENT) => {
+                Self::handle_event(data, cmd.get_unchecked(SYNC_EVENT));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(SYNC_OUT_OF_DATE) => {
+                self.sync_out_of_date(ctx, data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::TOGGLE_OFFLINE_MODE) => {
+                if data.lost_connection && !data.config.offline_mode {
+                    // Offline only because the connection was lost, so going
+                    // online means trying to connect again.
+                    data.lost_connection = false;
+                    SessionController::connect_in_background(ctx, data);
+                    data.info_alert("Connecting again.");
+                } else {
+                    data.config.offline_mode = !data.config.offline_mode;
+                    data.config.save();
+                    // Going online is worth a try, even after losing the
+                    // connection.
+                    data.lost_connection = false;
+                    data.info_alert(if data.config.offline_mode {
+                        "Offline mode is on, only downloaded music plays."
+                    } else {
+                        "Offline mode is off."
+                    });
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(OFFLINE_CHANGED) => {
+                self.offline_changed(ctx, data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CAN_DOWNLOAD_CHANGED) => {
+                self.can_download_changed(ctx, data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(OFFLINE_ITEMS) => {
+                if data.is_offline() {
+                    let items = cmd.get_unchecked(OFFLINE_ITEMS).clone();
+                    data.common_ctx_mut().offline_items = Some(items);
+                }
+                ctx.set_handled();
+            }
+            Event::WindowConnected => {
+                data.palette.register(PaletteEntry::new(
+                    "Library",
+                    "Turn offline mode on or off",
+                    cmd::TOGGLE_OFFLINE_MODE.into(),
+                ));
+                if data.is_offline() {
+                    Self::scan_offline_items(ctx, data);
+                }
+                child.event(ctx, event, data, env);
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &AppState,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if !old_data.library.playlists.same(&data.library.playlists) {
+            ctx.submit_command(SYNC_OUT_OF_DATE.to(ctx.widget_id()));
+        }
+        if old_data.is_offline() != data.is_offline() {
+            ctx.submit_command(OFFLINE_CHANGED.to(ctx.widget_id()));
+        }
+        if old_data.can_download() != data.can_download() {
+            ctx.submit_command(CAN_DOWNLOAD_CHANGED.to(ctx.widget_id()));
+        }
+        child.update(ctx, old_data, data, env)
+    }
+}
@@ -1,25 +1,32 @@
 use std::{
+    mem::{self, Discriminant},
+    sync::Arc,
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use crossbeam_channel::Sender;
-use discord_rich_presence::{
-    activity::{Activity, Assets, Timestamps},
-    DiscordIpc, DiscordIpcClient,
-};
+use crossbeam_channel::{Receiver, Sender};
 use druid::{
     im::Vector,
     widget::{prelude::*, Controller},
-    Code, ExtEventSink, InternalLifeCycle, KbKey, WindowHandle,
+    ExtEventSink, InternalLifeCycle, Selector, Target, TimerToken, WindowHandle,
 };
+use parking_lot::Mutex;
 use psst_core::{
-    audio::{normalize::NormalizationLevel, output::DefaultAudioOutput},
-    cache::Cache,
+    audio::{
+        normalize::NormalizationLevel,
+        output::{AudioOutput, DefaultAudioOutput},
+    },
+    cache::{Cache, CacheHandle, EpisodeProgress},
     cdn::Cdn,
-    lastfm::LastFmClient,
-    player::{item::PlaybackItem, PlaybackConfig, Player, PlayerCommand, PlayerEvent},
-    session::SessionService,
+    history::{PlayHistory, PlayLog, PlayRecord, SkipRecord},
+    item_id::ItemId,
+    lastfm::{LastFmApi, LastFmClient, PendingScrobble, ScrobbleQueue},
+    player::{
+        file::MediaPath, item::PlaybackItem, queue::CommandSender, PlaybackConfig, Player,
+        PlayerCommand, PlayerEvent,
+    },
+    session::{ConnectionState, SessionService},
 };
 use rustfm_scrobble::Scrobbler;
 use souvlaki::{
@@ -27,28 +34,138 @@ use souvlaki::{
 };
 use std::time::{SystemTime, UNIX_EPOCH};
 
+#[cfg(target_os = "linux")]
+use crate::portal::{self, Shortcut};
+
 use crate::{
     cmd,
-    data::Nav,
     data::{
-        AppState, Config, NowPlaying, Playable, Playback, PlaybackOrigin, PlaybackState,
-        QueueBehavior, QueueEntry,
+        AppState, Config, KeybindAction, NotificationEvent, NowPlaying, PaletteEntry, Playable,
+        Playback, PlaybackClock, PlaybackOrigin, PlaybackPayload, PlaybackState, PlaylistLink,
+        PresenceIdle, QueueEntry, QueuePlacement, RecommendationsRequest, Repeat, Shuffle,
+        SkipReview, SkippedTrack, Track, TrackId,
     },
-    ui::lyrics,
+    discord::{DiscordPresence, Presence},
+    instance::InstanceCommand,
+    sleep_inhibitor::SleepInhibitor,
+    ui::{lyrics, playlist},
+    webapi::WebApi,
 };
 
+#[cfg(target_os = "linux")]
+const MEDIA_SHORTCUTS: &[Shortcut] = &[
+    Shortcut {
+        id: "play-pause",
+        description: "Play or pause",
+        preferred_trigger: "XF86AudioPlay",
+    },
+    Shortcut {
+        id: "next",
+        description: "Next track",
+        preferred_trigger: "XF86AudioNext",
+    },
+    Shortcut {
+        id: "previous",
+        description: "Previous track",
+        preferred_trigger: "XF86AudioPrev",
+    },
+];
+
+/// A media key pressed on Wayland can reach us twice, through MPRIS when the
+/// desktop forwards it and through the GlobalShortcuts portal.  The second
+/// delivery of the same key within this window is dropped.
+const MEDIA_KEY_DUPLICATE_WINDOW: Duration = Duration::from_millis(500);
+
+/// Last media control event and whether it came from the portal, shared by
+/// the MPRIS and portal handlers.
+#[derive(Clone, Default)]
+struct MediaKeys {
+    last: Arc<Mutex<Option<(Discriminant<MediaControlEvent>, bool, Instant)>>>,
+}
+
+impl MediaKeys {
+    fn is_duplicate(&self, event: &MediaControlEvent, from_portal: bool) -> bool {
+        let kind = mem::discriminant(event);
+        let now = Instant::now();
+        let mut last = self.last.lock();
+        let duplicate = matches!(
+            *last,
+            Some((last_kind, last_from_portal, at))
+                if last_kind == kind
+                    && last_from_portal != from_portal
+                    && now.duration_since(at) < MEDIA_KEY_DUPLICATE_WINDOW
+        );
+        // A duplicate is not matched again, a third press is a new one.
+        *last = if duplicate {
+            None
+        } else {
+            Some((kind, from_portal, now))
+        };
+        duplicate
+    }
+}
+
 pub struct PlaybackController {
-    sender: Option<Sender<PlayerEvent>>,
+    sender: Option<CommandSender>,
     thread: Option<JoinHandle<()>>,
     output: Option<DefaultAudioOutput>,
     media_controls: Option<MediaControls>,
     has_scrobbled: bool,
+    has_recorded_play: bool,
     scrobbler: Option<Scrobbler>,
-    discord_client: Option<DiscordIpcClient>,
+    discord: Option<DiscordPresence>,
+    /// Fires when playback has been paused long enough for the presence to
+    /// go idle.
+    presence_idle_timer: TimerToken,
+    /// Sends the queued scrobbles again once the backoff has passed.
+    scrobble_retry_timer: TimerToken,
+    presence_idle: bool,
     startup: bool,
     sender_disconnected: bool,
     dynamic_cover_warning_logged: bool,
+    /// Position to seek to once the next item starts playing.
+    pending_seek: Option<Duration>,
+    /// Track the last autoplay recommendations were requested for.
+    autoplay_seed: Option<TrackId>,
+    /// Alert counting the items queued in a row with `QUEUE_ITEM_QUIETLY`,
+    /// and their count.
+    quiet_queue_alert: Option<(usize, usize)>,
+    /// Alert of the last `ADD_TO_QUEUE` or `QUEUE_ITEMS`, with the placement
+    /// and count of the items, corrected if the player skips duplicates.
+    queue_alert: Option<(usize, QueuePlacement, usize)>,
+    sleep_inhibitor: SleepInhibitor,
+    cache: Option<CacheHandle>,
+    /// Position of the playing episode when its progress was last saved.
+    episode_progress_saved: Duration,
+    /// Position last applied to the app state, see `PlaybackClock`.
+    progress_applied: Option<Duration>,
+    /// Tracks whose audio analysis should be fetched, see `load_track_analysis`.
+    track_analyses: Option<Sender<ItemId>>,
 }
+
+/// Send the queued scrobbles, without waiting for the backoff of the last
+/// failure.
+const FLUSH_SCROBBLES: Selector = Selector::new("app.lastfm.flush-scrobbles");
+
+/// Number of recommended tracks added when the queue runs out.
+const AUTOPLAY_LENGTH: usize = 25;
+
+/// How often position reports are applied to the app state, unless the lyrics
+/// are shown and need every one of them.
+const PROGRESS_APPLY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the progress of a playing episode is saved.
+const EPISODE_PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+/// Episodes stopped this close to their end count as played, the rest is
+/// usually credits or ads.
+const EPISODE_END_MARGIN: Duration = Duration::from_secs(30);
+
+/// Skips a track needs before it is offered for removal from a playlist.
+const MIN_SKIPS: usize = 3;
+/// Fewest tracks offered for removal at once.
+const MIN_SKIPPED_TRACKS: usize = 3;
+/// Least time between two offers for the same playlist.
+const SKIP_SUGGESTION_INTERVAL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 fn init_scrobbler_instance(data: &AppState) -> Option<Scrobbler> {
     if data.config.lastfm_enable {
         if let (Some(api_key), Some(api_secret), Some(session_key)) = (
@@ -75,7 +192,8 @@ fn init_scrobbler_instance(data: &AppState) -> Option<Scrobbler> {
     None
 }
 
-fn init_discord_client(config: &Config) -> Option<DiscordIpcClient> {
+/// Application ID to connect to Discord with, if the presence is enabled.
+fn discord_app_id(config: &Config) -> Option<String> {
     if !config.enable_discord_presence {
         log::info!("Discord Rich Presence is disabled");
         return None;
@@ -86,23 +204,7 @@ fn init_discord_client(config: &Config) -> Option<DiscordIpcClient> {
         log::warn!("Discord Rich Presence enabled but no Application ID configured");
         return None;
     }
-
-    match DiscordIpcClient::new(app_id) {
-        Ok(mut client) => match client.connect() {
-            Ok(()) => {
-                log::info!("Discord Rich Presence connected successfully");
-                Some(client)
-            }
-            Err(e) => {
-                log::warn!("Failed to connect to Discord Rich Presence: {}", e);
-                None
-            }
-        },
-        Err(e) => {
-            log::warn!("Failed to create Discord IPC client: {}", e);
-            None
-        }
-    }
+    Some(app_id.to_string())
 }
 
 impl PlaybackController {
@@ -113,11 +215,24 @@ impl PlaybackController {
             output: None,
             media_controls: None,
             has_scrobbled: false,
+            has_recorded_play: false,
             scrobbler: None,
-            discord_client: None,
+            discord: None,
+            presence_idle_timer: TimerToken::INVALID,
+            scrobble_retry_timer: TimerToken::INVALID,
+            presence_idle: false,
             startup: true,
             sender_disconnected: false,
             dynamic_cover_warning_logged: false,
+            pending_seek: None,
+            autoplay_seed: None,
+            quiet_queue_alert: None,
+            queue_alert: None,
+            sleep_inhibitor: SleepInhibitor::new(),
+            cache: None,
+            episode_progress_saved: Duration::ZERO,
+            progress_applied: None,
+            track_analyses: None,
         }
     }
 
@@ -125,32 +240,89 @@ impl PlaybackController {
         &mut self,
         session: SessionService,
         config: PlaybackConfig,
+        output_device: Option<&str>,
+        output_latency: Option<Duration>,
         event_sink: ExtEventSink,
         widget_id: WidgetId,
         #[allow(unused_variables)] window: &WindowHandle,
     ) {
-        let output = DefaultAudioOutput::open().unwrap();
+        let output = DefaultAudioOutput::open_device(output_device, output_latency).unwrap();
+        Self::forward_device_changes(&output, event_sink.clone(), widget_id);
         let cache_dir = Config::cache_dir().unwrap();
-        let proxy_url = Config::proxy();
+        let cache = Cache::new(cache_dir).unwrap();
+        self.cache = Some(cache.clone());
         let player = Player::new(
             session.clone(),
-            Cdn::new(session, proxy_url.as_deref()).unwrap(),
-            Cache::new(cache_dir).unwrap(),
+            Cdn::new(session).unwrap(),
+            cache,
             config,
             &output,
         );
 
-        self.media_controls = Self::create_media_controls(player.sender(), window)
-            .map_err(|err| log::error!("failed to connect to media control interface: {err:?}"))
-            .ok();
+        let media_keys = MediaKeys::default();
+        self.media_controls =
+            Self::create_media_controls(player.command_sender(), media_keys.clone(), window)
+                .map_err(|err| log::error!("failed to connect to media control interface: {err:?}"))
+                .ok();
+
+        // Wayland does not let applications grab the media keys, they have to
+        // be bound through the portal.
+        #[cfg(target_os = "linux")]
+        if portal::is_wayland_session() {
+            Self::bind_media_shortcuts(player.command_sender(), media_keys);
+        }
 
-        self.sender = Some(player.sender());
+        self.sender = Some(player.command_sender());
         self.thread = Some(thread::spawn(move || {
             Self::service_events(player, event_sink, widget_id);
         }));
         self.output.replace(output);
     }
 
+    /// Tell the UI about the output moving to another device on its own, for
+    /// example when the played one gets unplugged, so that the volume of the
+    /// new device is applied.
+    fn forward_device_changes(
+        output: &DefaultAudioOutput,
+        event_sink: ExtEventSink,
+        widget_id: WidgetId,
+    ) {
+        let changes = output.device_changes();
+        let spawned = thread::Builder::new()
+            .name("output_device".to_string())
+            .spawn(move || {
+                for device in changes.into_iter().flatten() {
+                    let _ = event_sink.submit_command(
+                        cmd::OUTPUT_DEVICE_OPENED,
+                        Arc::from(device),
+                        widget_id,
+                    );
+                }
+            });
+        if let Err(err) = spawned {
+            log::error!("failed to spawn the output device thread: {err}");
+        }
+    }
+
+    /// Move playback to the output device chosen in the preferences, applying
+    /// the volume last used on it.
+    fn switch_output_device(&mut self, ctx: &mut UpdateCtx, device: Option<&str>) {
+        let Some(output) = &self.output else {
+            return;
+        };
+        match output.switch_device(device) {
+            Ok(Some(device)) => {
+                ctx.submit_command(
+                    cmd::OUTPUT_DEVICE_OPENED
+                        .with(device.into())
+                        .to(ctx.widget_id()),
+                );
+            }
+            Ok(None) => {}
+            Err(err) => log::error!("failed to switch the output device: {err}"),
+        }
+    }
+
     fn service_events(mut player: Player, event_sink: ExtEventSink, widget_id: WidgetId) {
         for event in player.receiver() {
             // Forward events that affect the UI state to the UI thread.
@@ -166,11 +338,18 @@ impl PlaybackController {
                     let progress = position.to_owned();
                     if let Err(e) = event_sink.submit_command(
                         cmd::PLAYBACK_PLAYING,
-                        (path.item_id, progress),
+                        (path.item_id, progress, quality_label(path)),
                         widget_id,
                     ) {
                         log::error!("failed to submit PLAYBACK_PLAYING command: {e:?}");
                     }
+                    if let Err(e) = event_sink.submit_command(
+                        cmd::OUTPUT_LATENCY_CHANGED,
+                        player.output_latency(),
+                        widget_id,
+                    ) {
+                        log::error!("failed to submit OUTPUT_LATENCY_CHANGED command: {e:?}");
+                    }
                 }
                 PlayerEvent::Pausing { .. } => {
                     if let Err(e) = event_sink.submit_command(cmd::PLAYBACK_PAUSING, (), widget_id)
@@ -204,6 +383,39 @@ impl PlaybackController {
                         log::error!("failed to submit PLAYBACK_STOPPED command: {e:?}");
                     }
                 }
+                PlayerEvent::Recovered { position, .. } => {
+                    if let Err(e) =
+                        event_sink.submit_command(cmd::PLAYBACK_RECOVERED, *position, widget_id)
+                    {
+                        log::error!("failed to submit PLAYBACK_RECOVERED command: {e:?}");
+                    }
+                }
+                PlayerEvent::QualityChanged { path } => {
+                    if let Err(e) = event_sink.submit_command(
+                        cmd::PLAYBACK_QUALITY_CHANGED,
+                        quality_label(path),
+                        widget_id,
+                    ) {
+                        log::error!("failed to submit PLAYBACK_QUALITY_CHANGED command: {e:?}");
+                    }
+                }
+                PlayerEvent::QueueChanged { up_next } => {
+                    let item_ids = up_next.iter().map(|item| item.item_id).collect();
+                    if let Err(e) =
+                        event_sink.submit_command(cmd::PLAYBACK_QUEUE_CHANGED, item_ids, widget_id)
+                    {
+                        log::error!("failed to submit PLAYBACK_QUEUE_CHANGED command: {e:?}");
+                    }
+                }
+                PlayerEvent::QueueDuplicates { count, removed } => {
+                    if let Err(e) = event_sink.submit_command(
+                        cmd::PLAYBACK_QUEUE_DUPLICATES,
+                        (*count, *removed),
+                        widget_id,
+                    ) {
+                        log::error!("failed to submit PLAYBACK_QUEUE_DUPLICATES command: {e:?}");
+                    }
+                }
                 _ => {}
             }
 
@@ -214,7 +426,8 @@ impl PlaybackController {
     }
 
     fn create_media_controls(
-        sender: Sender<PlayerEvent>,
+        sender: CommandSender,
+        media_keys: MediaKeys,
         #[allow(unused_variables)] window: &WindowHandle,
     ) -> Result<MediaControls, souvlaki::Error> {
         let hwnd = {
@@ -238,21 +451,38 @@ impl PlaybackController {
         })?;
 
         media_controls.attach(move |event| {
-            Self::handle_media_control_event(event, &sender);
+            if !media_keys.is_duplicate(&event, false) {
+                Self::handle_media_control_event(event, &sender);
+            }
         })?;
 
         Ok(media_controls)
     }
 
-    fn handle_media_control_event(event: MediaControlEvent, sender: &Sender<PlayerEvent>) {
+    #[cfg(target_os = "linux")]
+    fn bind_media_shortcuts(sender: CommandSender, media_keys: MediaKeys) {
+        portal::bind_global_shortcuts(MEDIA_SHORTCUTS, move |id| {
+            let event = match id {
+                "play-pause" => MediaControlEvent::Toggle,
+                "next" => MediaControlEvent::Next,
+                "previous" => MediaControlEvent::Previous,
+                _ => return,
+            };
+            if !media_keys.is_duplicate(&event, true) {
+                Self::handle_media_control_event(event, &sender);
+            }
+        });
+    }
+
+    fn handle_media_control_event(event: MediaControlEvent, sender: &CommandSender) {
         let cmd = match event {
-            MediaControlEvent::Play => PlayerEvent::Command(PlayerCommand::Resume),
-            MediaControlEvent::Pause => PlayerEvent::Command(PlayerCommand::Pause),
-            MediaControlEvent::Toggle => PlayerEvent::Command(PlayerCommand::PauseOrResume),
-            MediaControlEvent::Next => PlayerEvent::Command(PlayerCommand::Next),
-            MediaControlEvent::Previous => PlayerEvent::Command(PlayerCommand::Previous),
+            MediaControlEvent::Play => PlayerCommand::Resume,
+            MediaControlEvent::Pause => PlayerCommand::Pause,
+            MediaControlEvent::Toggle => PlayerCommand::PauseOrResume,
+            MediaControlEvent::Next => PlayerCommand::Next,
+            MediaControlEvent::Previous => PlayerCommand::Previous,
             MediaControlEvent::SetPosition(MediaPosition(duration)) => {
-                PlayerEvent::Command(PlayerCommand::Seek { position: duration })
+                PlayerCommand::Seek { position: duration }
             }
             _ => {
                 return;
@@ -268,7 +498,7 @@ impl PlaybackController {
             let progress = playback
                 .now_playing
                 .as_ref()
-                .map(|now_playing| MediaPosition(now_playing.progress));
+                .map(|_| MediaPosition(PlaybackClock::global().position()));
             media_controls
                 .set_playback(match playback.state {
                     PlaybackState::Loading | PlaybackState::Stopped => MediaPlayback::Stopped,
@@ -321,22 +551,22 @@ impl PlaybackController {
         }
     }
 
-    fn send(&mut self, event: PlayerEvent) {
+    fn send(&mut self, cmd: PlayerCommand) {
         if let Some(s) = &self.sender {
-            if let Err(e) = s.send(event) {
+            if let Err(e) = s.send(cmd) {
                 if !self.sender_disconnected {
                     log::error!("player thread has disconnected, commands will be ignored: {e:?}");
-                    log::error!(
-                        "this may be caused by rapid playback control inputs or an internal error"
-                    );
                     self.sender_disconnected = true;
                 }
             }
         }
     }
 
-    fn report_now_playing(&mut self, playback: &Playback) {
-        if let Some(now_playing) = playback.now_playing.as_ref() {
+    fn report_now_playing(&mut self, ctx: &mut EventCtx, data: &AppState) {
+        if !data.config.scrobbling.now_playing {
+            return;
+        }
+        if let Some(now_playing) = data.playback.now_playing.as_ref() {
             if let Playable::Track(track) = &now_playing.item {
                 if let Some(scrobbler) = &self.scrobbler {
                     let artist = track.artist_name();
@@ -352,6 +582,12 @@ impl PlaybackController {
                         log::warn!("failed to report 'Now Playing' to Last.fm: {e}");
                     } else {
                         log::info!("reported 'Now Playing' to Last.fm: {artist} - {title}");
+                        // Last.fm is reachable, send what was queued while it
+                        // was not.
+                        if !ScrobbleQueue::global().is_empty() {
+                            ScrobbleQueue::global().retry_now();
+                            Self::flush_scrobbles(ctx, &data.config);
+                        }
                     }
                 } else {
                     log::debug!("Last.fm not configured, skipping now_playing report.");
@@ -360,26 +596,30 @@ impl PlaybackController {
         }
     }
 
-    fn report_scrobble(&mut self, playback: &Playback) {
-        if let Some(now_playing) = playback.now_playing.as_ref() {
+    /// Queue a scrobble once enough of the track was played, see
+    /// `ScrobbleSettings`, and send the queue.  Scrobbles that cannot be sent
+    /// stay queued, see `ScrobbleQueue`.
+    fn report_scrobble(&mut self, ctx: &mut EventCtx, data: &AppState) {
+        if let Some(now_playing) = data.playback.now_playing.as_ref() {
             if let Playable::Track(track) = &now_playing.item {
-                if now_playing.progress >= track.duration / 2 && !self.has_scrobbled {
-                    if let Some(scrobbler) = &self.scrobbler {
-                        let artist = track.artist_name();
-                        let title = track.name.clone();
-                        let album = track.album.clone();
-
-                        if let Err(e) = LastFmClient::scrobble_song(
-                            scrobbler,
-                            artist.as_ref(),
-                            title.as_ref(),
-                            album.as_ref().map(|a| a.name.as_ref()),
-                        ) {
-                            log::warn!("failed to scrobble track to Last.fm: {e}");
-                        } else {
-                            log::info!("scrobbled track to Last.fm: {artist} - {title}");
-                            self.has_scrobbled = true;
+                if now_playing.position() >= data.config.scrobbling.threshold(track.duration)
+                    && !self.has_scrobbled
+                {
+                    if self.scrobbler.is_some() {
+                        let scrobble = PendingScrobble {
+                            artist: track.artist_name().to_string(),
+                            title: track.name.to_string(),
+                            album: track.album.as_ref().map(|a| a.name.to_string()),
+                            timestamp: SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .map(|d| d.as_secs())
+                                .unwrap_or(0),
+                        };
+                        if let Err(e) = ScrobbleQueue::global().push(scrobble) {
+                            log::warn!("failed to queue scrobble: {e}");
                         }
+                        self.has_scrobbled = true;
+                        Self::flush_scrobbles(ctx, &data.config);
                     } else {
                         log::debug!("Last.fm not configured, skipping scrobble.");
                     }
@@ -388,102 +628,419 @@ impl PlaybackController {
         }
     }
 
-    fn update_discord_presence(&mut self, playback: &Playback, config: &Config) {
-        let Some(mut client) = self.discord_client.take() else {
+    /// Send the queued scrobbles on a background thread, and report how many
+    /// are left.
+    fn flush_scrobbles(ctx: &mut EventCtx, config: &Config) {
+        if !config.lastfm_enable {
+            return;
+        }
+        let api_key = config.lastfm_api_key.clone();
+        let api_secret = config.lastfm_api_secret.clone();
+        let session_key = config.lastfm_session_key.clone();
+        let event_sink = ctx.get_external_handle();
+        let widget_id = ctx.widget_id();
+        thread::spawn(move || {
+            let queue = ScrobbleQueue::global();
+            match LastFmClient::create_scrobbler(
+                api_key.as_deref(),
+                api_secret.as_deref(),
+                session_key.as_deref(),
+            ) {
+                Ok(scrobbler) => {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    match queue.flush(now, |scrobble| {
+                        LastFmClient::scrobble_pending(&scrobbler, scrobble)
+                    }) {
+                        Ok(0) => {}
+                        Ok(sent) => log::info!("sent {sent} scrobbles to Last.fm"),
+                        Err(e) => log::warn!(
+                            "failed to scrobble to Last.fm, {} scrobbles queued: {e}",
+                            queue.len()
+                        ),
+                    }
+                }
+                Err(e) => log::warn!("cannot send scrobbles: {e}"),
+            }
+            if let Err(e) =
+                event_sink.submit_command(cmd::PENDING_SCROBBLES_CHANGED, queue.len(), widget_id)
+            {
+                log::error!("failed to submit PENDING_SCROBBLES_CHANGED command: {e:?}");
+            }
+        });
+    }
+
+    /// Look up whether the playing track is loved on Last.fm, unless it is
+    /// already known.
+    fn fetch_lastfm_loved(ctx: &mut EventCtx, data: &AppState) {
+        let Some(track) = data
+            .playback
+            .now_playing
+            .as_ref()
+            .and_then(|np| np.item.track())
+        else {
+            return;
+        };
+        let known = data
+            .library
+            .lastfm_loved
+            .as_ref()
+            .map(|loved| loved.contains_key(&track.id));
+        let (Some(false), Some(api_key), Some(username)) = (
+            known,
+            data.config.lastfm_api_key.clone(),
+            data.config.lastfm_username.clone(),
+        ) else {
             return;
         };
+        let track_id = track.id;
+        let artist = track.artist_name();
+        let title = track.name.clone();
+        let event_sink = ctx.get_external_handle();
+        let widget_id = ctx.widget_id();
+        thread::spawn(move || {
+            match LastFmApi::global().is_loved(&api_key, &username, &artist, &title) {
+                Ok(loved) => {
+                    event_sink
+                        .submit_command(cmd::LASTFM_LOVED_CHANGED, (track_id, loved), widget_id)
+                        .ok();
+                }
+                Err(e) => log::warn!("failed to get Last.fm loved state of {title}: {e}"),
+            }
+        });
+    }
 
-        let (result, action) = match playback.state {
-            PlaybackState::Playing => {
-                if let Some(now_playing) = &playback.now_playing {
-                    let mut activity = Activity::new();
-                    activity = activity.details(now_playing.item.name().as_ref());
-
-                    let mut state_parts = Vec::new();
-                    match &now_playing.item {
-                        Playable::Track(track) => {
-                            if config.presence_show_artist {
-                                state_parts.push(track.artist_name().to_string());
-                            }
-                            if config.presence_show_album {
-                                if let Some(album) = &track.album {
-                                    state_parts.push(album.name.to_string());
-                                }
-                            }
+    /// Love or unlove `track` on a background thread.  The new state is shown
+    /// right away, and reverted if Last.fm refuses it.
+    fn set_lastfm_loved(ctx: &mut EventCtx, data: &mut AppState, track: &Track, loved: bool) {
+        let (Some(api_key), Some(api_secret), Some(session_key)) = (
+            data.config.lastfm_api_key.clone(),
+            data.config.lastfm_api_secret.clone(),
+            data.config.lastfm_session_key.clone(),
+        ) else {
+            return;
+        };
+        data.with_library_mut(|library| library.set_lastfm_loved(track.id, loved));
+
+        let track_id = track.id;
+        let artist = track.artist_name();
+        let title = track.name.clone();
+        let event_sink = ctx.get_external_handle();
+        let widget_id = ctx.widget_id();
+        thread::spawn(move || {
+            let result = LastFmApi::global().set_loved(
+                &api_key,
+                &api_secret,
+                &session_key,
+                &artist,
+                &title,
+                loved,
+            );
+            if let Err(e) = result {
+                log::warn!("failed to update Last.fm loved state of {title}: {e}");
+                event_sink
+                    .submit_command(cmd::LASTFM_LOVED_CHANGED, (track_id, !loved), widget_id)
+                    .ok();
+            }
+        });
+    }
+
+    /// Record the play in the history, and in the play log if it is enabled,
+    /// once half of the track was played.
+    fn record_play(&mut self, playback: &Playback, config: &Config) {
+        if let Some(now_playing) = playback.now_playing.as_ref() {
+            if let Playable::Track(track) = &now_playing.item {
+                if now_playing.position() >= track.duration / 2
+                    && !self.has_recorded_play
+                    && !track.is_local
+                {
+                    let record = PlayRecord {
+                        track_id: track.id.0.to_base62(),
+                        name: track.name.to_string(),
+                        artist: track.artist_name().to_string(),
+                        album: track.album_name().to_string(),
+                        played_at: SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map(|d| d.as_secs())
+                            .unwrap_or(0),
+                    };
+                    let log_path = config.play_log_path.trim();
+                    if config.play_log_enable && !log_path.is_empty() {
+                        if let Err(err) = PlayLog::new(log_path.into()).append(&record) {
+                            log::warn!("failed to append play to {log_path}: {err}");
                         }
-                        Playable::Episode(episode) => {
-                            if config.presence_show_artist {
-                                state_parts.push(episode.show.name.to_string());
-                            }
+                    }
+                    if config.history_enable {
+                        if let Err(err) = PlayHistory::global().record(record) {
+                            log::warn!("failed to record play in history: {err}");
                         }
                     }
+                    self.has_recorded_play = true;
+                }
+            }
+        }
+    }
 
-                    let state_string = if state_parts.is_empty() {
-                        None
-                    } else {
-                        Some(state_parts.join(" • "))
-                    };
+    /// Record the playing track as skipped if it is left within
+    /// `SkipRecord::WINDOW`, and offer to remove the tracks of its playlist
+    /// that keep being skipped.
+    fn record_skip(&mut self, data: &mut AppState) {
+        if !data.config.history_enable {
+            return;
+        }
+        let Some(now_playing) = &data.playback.now_playing else {
+            return;
+        };
+        let Playable::Track(track) = &now_playing.item else {
+            return;
+        };
+        if track.is_local || PlaybackClock::global().position() >= SkipRecord::WINDOW {
+            return;
+        }
+        let playlist = match &now_playing.origin {
+            PlaybackOrigin::Playlist(link) => Some(link.clone()),
+            _ => None,
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let record = SkipRecord {
+            track_id: track.id.0.to_base62(),
+            name: track.name.to_string(),
+            artist: track.artist_name().to_string(),
+            playlist_id: playlist.as_ref().map(|link| link.id.to_string()),
+            skipped_at: now,
+        };
+        if let Err(err) = PlayHistory::global().record_skip(record) {
+            log::warn!("failed to record skip in history: {err}");
+        }
+        if let Some(link) = playlist {
+            Self::suggest_skip_removals(data, link, now);
+        }
+    }
 
-                    if let Some(state) = state_string.as_deref() {
-                        activity = activity.state(state);
-                    }
+    fn suggest_skip_removals(data: &mut AppState, link: PlaylistLink, now: u64) {
+        let writable = data
+            .library
+            .playlists
+            .resolved()
+            .and_then(|playlists| playlists.iter().find(|p| p.id == link.id))
+            .is_some_and(|playlist| data.library.is_playlist_writable(playlist));
+        let since = data
+            .config
+            .skip_suggested_at
+            .get(&link.id)
+            .copied()
+            .unwrap_or(0);
+        if !data.config.suggest_skip_removals
+            || !writable
+            || now < since + SKIP_SUGGESTION_INTERVAL.as_secs()
+            // Offered after the quiet hours, the skips are kept until then.
+            || data.is_quiet_hours()
+        {
+            return;
+        }
+        let tracks: Vector<SkippedTrack> = PlayHistory::global()
+            .skipped_tracks(&link.id, since, MIN_SKIPS)
+            .into_iter()
+            .map(|track| SkippedTrack {
+                uri: format!("spotify:track:{}", track.track_id).into(),
+                name: track.name.into(),
+                artist: track.artist.into(),
+                skip_count: track.skip_count,
+                remove: true,
+            })
+            .collect();
+        if tracks.len() < MIN_SKIPPED_TRACKS {
+            return;
+        }
+        data.config.skip_suggested_at.insert(link.id.clone(), now);
+        data.config.save();
+        data.info_alert_with_action(
+            format!(
+                "You always skip these {} songs in {}. Remove them from the playlist?",
+                tracks.len(),
+                link.name
+            ),
+            "Review",
+            playlist::SHOW_SKIP_REVIEW.with(SkipReview { link, tracks }),
+        );
+    }
 
-                    if config.presence_show_track_duration {
-                        if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
-                            let elapsed = now_playing.progress.as_secs() as i64;
-                            let duration = now_playing.item.duration().as_secs() as i64;
-                            let start_time = now.as_secs() as i64 - elapsed;
-                            let end_time = start_time + duration;
-                            activity = activity
-                                .timestamps(Timestamps::new().start(start_time).end(end_time));
-                        }
-                    }
+    /// Remember how far the playing episode got, both in the cache and in the
+    /// loaded show page.
+    fn save_episode_progress(&mut self, data: &mut AppState) {
+        let Some(now_playing) = &data.playback.now_playing else {
+            return;
+        };
+        let Playable::Episode(episode) = &now_playing.item else {
+            return;
+        };
+        let position = now_playing.position();
+        let remaining = episode.duration.saturating_sub(position);
+        let progress = if remaining <= EPISODE_END_MARGIN {
+            EpisodeProgress::new(Duration::ZERO, true)
+        } else {
+            EpisodeProgress::new(position, false)
+        };
+        self.episode_progress_saved = position;
 
-                    let image_choice = self.prepare_discord_large_image(now_playing, config);
-                    let mut owned_image: Option<String> = None;
-                    let image_ref = match image_choice {
-                        DiscordImageKey::Borrowed(key) => key,
-                        DiscordImageKey::Owned(key) => {
-                            owned_image = Some(key);
-                            owned_image.as_deref().unwrap()
-                        }
-                    };
+        let id = episode.id;
+        if let Some(cache) = &self.cache {
+            if let Err(err) = cache.save_episode_progress(id.0, &progress) {
+                log::warn!("failed to save episode progress: {err}");
+            }
+        }
+        if let Some(show) = data.show_detail.episodes.resolved_mut() {
+            for episode in show.episodes.iter_mut().filter(|e| e.id == id) {
+                Arc::make_mut(episode).resume_point = Some(progress.into());
+            }
+        }
+    }
+
+    /// Remember the speed of the playing episode, it plays at it next time.
+    fn save_episode_speed(&self, data: &AppState) {
+        let Some(Playable::Episode(episode)) =
+            data.playback.now_playing.as_ref().map(|np| &np.item)
+        else {
+            return;
+        };
+        if let Some(cache) = &self.cache {
+            if let Err(err) = cache.save_episode_speed(episode.id.0, data.playback.podcast_speed) {
+                log::warn!("failed to save episode speed: {err}");
+            }
+        }
+    }
+
+    /// Switch to the speed `item` was last played at, or to the last chosen
+    /// one.  The player picks it up with the next `Configure`.
+    fn apply_episode_speed(&self, data: &mut AppState, item: &Playable) {
+        let Playable::Episode(episode) = item else {
+            return;
+        };
+        data.playback.podcast_speed = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get_episode_speed(episode.id.0))
+            .unwrap_or(data.config.podcast_speed);
+    }
 
-                    let assets = Assets::new()
-                        .large_text("Psst - Fast Spotify Client")
-                        .large_image(image_ref);
+    /// Position the episode was last left at, from the cache or from Spotify.
+    fn episode_resume_position(&self, item: &Playable) -> Option<Duration> {
+        let Playable::Episode(episode) = item else {
+            return None;
+        };
+        let saved = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get_episode_progress(episode.id.0));
+        match saved {
+            Some(progress) if progress.fully_played => None,
+            Some(progress) => Some(progress.position()),
+            None => episode.resume_position(),
+        }
+    }
+
+    fn update_discord_presence(&mut self, playback: &Playback, config: &Config) {
+        if self.discord.is_none() {
+            return;
+        }
 
-                    activity = activity.assets(assets);
+        let paused = playback.state == PlaybackState::Paused;
+        let presence = match playback.state {
+            PlaybackState::Paused if self.presence_idle => match config.presence_idle {
+                PresenceIdle::Clear => None,
+                PresenceIdle::Idle => Some(Presence {
+                    details: "Idle".to_string(),
+                    state: None,
+                    timestamps: None,
+                    large_image: "psst_logo".to_string(),
+                    large_text: None,
+                }),
+            },
+            PlaybackState::Playing | PlaybackState::Paused => {
+                let Some(now_playing) = &playback.now_playing else {
+                    return;
+                };
+                let mut state_parts = Vec::new();
+                match &now_playing.item {
+                    Playable::Track(track) => {
+                        if config.presence_show_artist {
+                            state_parts.push(track.artist_name().to_string());
+                        }
+                        if config.presence_show_album {
+                            if let Some(album) = &track.album {
+                                state_parts.push(album.name.to_string());
+                            }
+                        }
+                    }
+                    Playable::Episode(episode) => {
+                        if config.presence_show_artist {
+                            state_parts.push(episode.show.name.to_string());
+                        }
+                    }
+                }
 
-                    let result = client.set_activity(activity);
-                    drop(owned_image);
+                if paused {
+                    state_parts.insert(0, "Paused".to_string());
+                }
 
-                    (result, "update Discord Rich Presence")
+                let state = if state_parts.is_empty() {
+                    None
                 } else {
-                    (Ok(()), "update Discord Rich Presence")
+                    Some(state_parts.join(" • "))
+                };
+
+                let mut timestamps = None;
+                if config.presence_show_track_duration && !paused {
+                    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+                        let elapsed = now_playing.position().as_secs() as i64;
+                        let duration = now_playing.item.duration().as_secs() as i64;
+                        let start_time = now.as_secs() as i64 - elapsed;
+                        timestamps = Some((start_time, start_time + duration));
+                    }
                 }
-            }
-            PlaybackState::Paused | PlaybackState::Stopped => {
-                (client.clear_activity(), "clear Discord Rich Presence")
-            }
-            _ => (Ok(()), "update Discord Rich Presence"),
-        };
 
-        let mut reconnect_needed = false;
+                let large_image = match self.prepare_discord_large_image(now_playing, config) {
+                    DiscordImageKey::Borrowed(key) => key.to_string(),
+                    DiscordImageKey::Owned(key) => key,
+                };
 
-        if let Err(err) = result {
-            log::warn!("Failed to {}: {}", action, err);
-            if err.to_string().contains("Broken pipe") {
-                reconnect_needed = true;
+                Some(Presence {
+                    details: now_playing.item.name().to_string(),
+                    state,
+                    timestamps,
+                    large_image,
+                    large_text: Some("Psst - Fast Spotify Client".to_string()),
+                })
             }
+            PlaybackState::Stopped => None,
+            _ => return,
+        };
+
+        if let Some(discord) = &self.discord {
+            discord.publish(presence);
         }
+    }
 
-        if reconnect_needed {
-            let _ = client.close();
-            self.discord_client = init_discord_client(config);
+    /// Let the presence of the paused track go idle after the configured
+    /// time.
+    fn start_presence_idle_timer(&mut self, ctx: &mut EventCtx, config: &Config) {
+        let minutes = config.presence_idle_minutes as u64;
+        self.presence_idle = minutes == 0;
+        self.presence_idle_timer = if self.presence_idle {
+            TimerToken::INVALID
         } else {
-            self.discord_client = Some(client);
-        }
+            ctx.request_timer(Duration::from_secs(minutes * 60))
+        };
+    }
+
+    fn cancel_presence_idle_timer(&mut self) {
+        self.presence_idle = false;
+        self.presence_idle_timer = TimerToken::INVALID;
     }
 
     fn prepare_discord_large_image<'a>(
@@ -513,15 +1070,56 @@ impl PlaybackController {
         DiscordImageKey::Borrowed("psst_logo")
     }
 
-    fn play(&mut self, items: &Vector<QueueEntry>, position: usize) {
-        let playback_items = items.iter().map(|queued| PlaybackItem {
+    fn playback_item(queued: &QueueEntry, config: &Config) -> PlaybackItem {
+        PlaybackItem {
             item_id: queued.item.id(),
             norm_level: match queued.origin {
                 PlaybackOrigin::Album(_) => NormalizationLevel::Album,
                 _ => NormalizationLevel::Track,
             },
-        });
-        let playback_items_vec: Vec<PlaybackItem> = playback_items.collect();
+            group: queued.item.item_group(),
+            skip_intro: Self::skip_intro(&queued.origin, config),
+        }
+    }
+
+    /// Queue the items of `payload` and return the position to play from.
+    /// Offline, only the available items are queued, and playback starts at
+    /// the first of them at or after the position.  `None` if none is.
+    fn queue_payload(data: &mut AppState, payload: &PlaybackPayload) -> Option<usize> {
+        let mut position = 0;
+        let mut queue = Vector::new();
+        for (index, item) in payload.items.iter().enumerate() {
+            if !data.common_ctx.is_available(item.id()) {
+                continue;
+            }
+            if index < payload.position {
+                position += 1;
+            }
+            queue.push_back(QueueEntry {
+                origin: payload.origin.to_owned(),
+                item: item.to_owned(),
+            });
+        }
+        if position >= queue.len() {
+            data.info_alert("Nothing here is available offline.");
+            return None;
+        }
+        data.playback.queue = queue;
+        Some(position)
+    }
+
+    fn skip_intro(origin: &PlaybackOrigin, config: &Config) -> Duration {
+        match origin {
+            PlaybackOrigin::Playlist(link) => config.playlist_intro_skip(&link.id),
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn play(&mut self, items: &Vector<QueueEntry>, position: usize, config: &Config) {
+        let playback_items_vec: Vec<PlaybackItem> = items
+            .iter()
+            .map(|entry| Self::playback_item(entry, config))
+            .collect();
 
         // Make sure position is within bounds
         let position = if position >= playback_items_vec.len() {
@@ -530,38 +1128,73 @@ impl PlaybackController {
             position
         };
 
-        self.send(PlayerEvent::Command(PlayerCommand::LoadQueue {
+        self.send(PlayerCommand::LoadQueue {
             items: playback_items_vec,
             position,
-        }));
+        });
     }
 
     fn pause(&mut self) {
-        self.send(PlayerEvent::Command(PlayerCommand::Pause));
+        self.send(PlayerCommand::Pause);
     }
 
     fn resume(&mut self) {
-        self.send(PlayerEvent::Command(PlayerCommand::Resume));
+        self.send(PlayerCommand::Resume);
     }
 
     fn pause_or_resume(&mut self) {
-        self.send(PlayerEvent::Command(PlayerCommand::PauseOrResume));
+        self.send(PlayerCommand::PauseOrResume);
     }
 
     fn previous(&mut self) {
-        self.send(PlayerEvent::Command(PlayerCommand::Previous));
+        self.send(PlayerCommand::Previous);
     }
 
-    fn next(&mut self) {
-        self.send(PlayerEvent::Command(PlayerCommand::Next));
+    fn next(&mut self, data: &mut AppState) {
+        self.record_skip(data);
+        self.send(PlayerCommand::Next);
     }
 
     fn stop(&mut self) {
-        self.send(PlayerEvent::Command(PlayerCommand::Stop));
+        self.send(PlayerCommand::Stop);
     }
 
     fn seek(&mut self, position: Duration) {
-        self.send(PlayerEvent::Command(PlayerCommand::Seek { position }));
+        self.send(PlayerCommand::Seek { position });
+    }
+
+    /// Run a playback shortcut, returns false for the other actions.
+    fn handle_keybind(&mut self, data: &mut AppState, action: KeybindAction) -> bool {
+        match action {
+            KeybindAction::PlayPause => self.pause_or_resume(),
+            KeybindAction::SeekForward => self.seek_relative(data, true),
+            KeybindAction::SeekBackward => self.seek_relative(data, false),
+            KeybindAction::NextTrack => self.next(data),
+            KeybindAction::PreviousTrack => self.previous(),
+            KeybindAction::NextChapter => self.skip_chapter(data, true),
+            KeybindAction::PreviousChapter => self.skip_chapter(data, false),
+            KeybindAction::VolumeUp => {
+                data.playback.volume = (data.playback.volume + 0.1).min(1.0);
+            }
+            KeybindAction::VolumeDown => {
+                data.playback.volume = (data.playback.volume - 0.1).max(0.0);
+            }
+            // Run by the focused row of a track list.
+            KeybindAction::QueueFocusedRow => return false,
+            // Run by the `KeybindsController`.
+            KeybindAction::Refresh
+            | KeybindAction::GoHome
+            | KeybindAction::GoSavedTracks
+            | KeybindAction::GoSavedAlbums
+            | KeybindAction::GoShows
+            | KeybindAction::FocusSearch
+            | KeybindAction::ToggleFinder
+            | KeybindAction::ToggleNowPlaying
+            | KeybindAction::ToggleMiniPlayer
+            | KeybindAction::CommandPalette
+            | KeybindAction::Quit => return false,
+        }
+        true
     }
 
     fn seek_relative(&mut self, data: &AppState, forward: bool) {
@@ -569,10 +1202,11 @@ impl PlaybackController {
             let seek_duration = Duration::from_secs(data.config.seek_duration as u64);
 
             // Calculate new position, ensuring it does not exceed duration for forward seeks.
+            let progress = PlaybackClock::global().position();
             let seek_position = if forward {
-                now_playing.progress + seek_duration
+                progress + seek_duration
             } else {
-                now_playing.progress.saturating_sub(seek_duration)
+                progress.saturating_sub(seek_duration)
             }
             .min(now_playing.item.duration());
 
@@ -580,30 +1214,173 @@ impl PlaybackController {
         }
     }
 
+    fn skip_chapter(&mut self, data: &AppState, forward: bool) {
+        if let Some(now_playing) = &data.playback.now_playing {
+            let start = if forward {
+                now_playing.next_chapter_start()
+            } else {
+                now_playing.previous_chapter_start()
+            };
+            if let Some(start) = start {
+                self.seek(start);
+            }
+        }
+    }
+
     fn set_volume(&mut self, volume: f64) {
-        self.send(PlayerEvent::Command(PlayerCommand::SetVolume { volume }));
+        self.send(PlayerCommand::SetVolume { volume });
     }
 
     fn add_to_queue(&mut self, item: &PlaybackItem) {
-        self.send(PlayerEvent::Command(PlayerCommand::AddToQueue {
-            item: *item,
-        }));
+        self.send(PlayerCommand::AddToQueue { item: *item });
+    }
+
+    fn show_queue_alert(&mut self, data: &mut AppState, placement: QueuePlacement, count: usize) {
+        data.info_alert(queue_message(placement, count, 0));
+        self.queue_alert = data.alerts.back().map(|alert| (alert.id, placement, count));
+    }
+
+    fn queue_items(&mut self, placement: QueuePlacement, entries: &[QueueEntry], config: &Config) {
+        match placement {
+            QueuePlacement::Next => self.send(PlayerCommand::AddToQueueNext {
+                items: entries
+                    .iter()
+                    .map(|entry| Self::playback_item(entry, config))
+                    .collect(),
+            }),
+            QueuePlacement::Last => {
+                for entry in entries {
+                    self.add_to_queue(&Self::playback_item(entry, config));
+                }
+            }
+        }
     }
 
-    fn set_queue_behavior(&mut self, behavior: QueueBehavior) {
-        self.send(PlayerEvent::Command(PlayerCommand::SetQueueBehavior {
-            behavior: match behavior {
-                QueueBehavior::Sequential => psst_core::player::queue::QueueBehavior::Sequential,
-                QueueBehavior::Random => psst_core::player::queue::QueueBehavior::Random,
-                QueueBehavior::LoopTrack => psst_core::player::queue::QueueBehavior::LoopTrack,
-                QueueBehavior::LoopAll => psst_core::player::queue::QueueBehavior::LoopAll,
+    fn set_shuffle(&mut self, shuffle: Shuffle) {
+        self.send(PlayerCommand::SetShuffle {
+            shuffle: match shuffle {
+                Shuffle::Off => psst_core::player::queue::Shuffle::Off,
+                Shuffle::Random => psst_core::player::queue::Shuffle::Random,
+                Shuffle::Smart => psst_core::player::queue::Shuffle::Smart,
             },
-        }));
+        });
+    }
+
+    fn set_repeat(&mut self, repeat: Repeat) {
+        self.send(PlayerCommand::SetRepeat {
+            repeat: match repeat {
+                Repeat::Off => psst_core::player::queue::Repeat::Off,
+                Repeat::All => psst_core::player::queue::Repeat::All,
+                Repeat::One => psst_core::player::queue::Repeat::One,
+            },
+        });
+    }
+
+    /// Fetch the audio analysis of `item` in the background and hand it to the
+    /// player, which uses it to place the fades.  Without it, fixed-length fades
+    /// are used.  Analyses are fetched one after the other on a thread started
+    /// on first use.
+    fn load_track_analysis(&mut self, item: &Playable) {
+        let (Some(track), Some(sender)) = (item.track(), &self.sender) else {
+            return;
+        };
+        // Local files have no analysis in the Web API.
+        if track.is_local {
+            return;
+        }
+        let analyses = self.track_analyses.get_or_insert_with(|| {
+            let (analyses, receiver) = crossbeam_channel::unbounded();
+            let sender = sender.clone();
+            let spawned = thread::Builder::new()
+                .name("track_analysis".to_string())
+                .spawn(move || Self::fetch_track_analyses(receiver, sender));
+            if let Err(err) = spawned {
+                log::error!("failed to spawn the track analysis thread: {err}");
+            }
+            analyses
+        });
+        let _ = analyses.send(track.id.0);
+    }
+
+    fn fetch_track_analyses(items: Receiver<ItemId>, sender: CommandSender) {
+        for item_id in items {
+            match WebApi::global().get_audio_analysis(&item_id.to_base62()) {
+                Ok(analysis) => {
+                    if let Some(analysis) = analysis.track_analysis() {
+                        sender
+                            .send(PlayerCommand::SetTrackAnalysis { item_id, analysis })
+                            .ok();
+                    }
+                }
+                Err(err) => {
+                    log::info!("no audio analysis, using fixed-length fades: {err}");
+                }
+            }
+        }
+    }
+
+    /// Once nothing is left up next, fetch recommendations seeded by the
+    /// current track in the background, so they can be queued before the
+    /// current track ends.
+    fn prepare_autoplay(&mut self, ctx: &mut EventCtx, data: &AppState) {
+        if !data.config.autoplay
+            || data.playback.shuffle != Shuffle::Off
+            || data.playback.repeat != Repeat::Off
+            || !data.playback.up_next.is_empty()
+        {
+            return;
+        }
+        let Some(track) = data
+            .playback
+            .now_playing
+            .as_ref()
+            .and_then(|now_playing| now_playing.item.track())
+        else {
+            return;
+        };
+        if track.is_local || self.autoplay_seed == Some(track.id) {
+            return;
+        }
+        self.autoplay_seed = Some(track.id);
+
+        let seed = track.id;
+        let request = Arc::new(RecommendationsRequest::for_track(seed));
+        let event_sink = ctx.get_external_handle();
+        let widget_id = ctx.widget_id();
+        thread::spawn(
+            move || match WebApi::global().get_recommendations(request.clone()) {
+                Ok(recommendations) => {
+                    let entries: Vec<QueueEntry> = recommendations
+                        .tracks
+                        .iter()
+                        .filter(|track| track.id != seed && track.is_playable != Some(false))
+                        .take(AUTOPLAY_LENGTH)
+                        .map(|track| QueueEntry {
+                            item: Playable::Track(track.clone()),
+                            origin: PlaybackOrigin::Recommendations(request.clone()),
+                        })
+                        .collect();
+                    if let Err(e) =
+                        event_sink.submit_command(cmd::PLAYBACK_AUTOPLAY_LOADED, entries, widget_id)
+                    {
+                        log::error!("failed to submit PLAYBACK_AUTOPLAY_LOADED command: {e:?}");
+                    }
+                }
+                Err(err) => {
+                    log::warn!("failed to load autoplay recommendations: {err}");
+                }
+            },
+        );
     }
 
     fn update_lyrics(&mut self, ctx: &mut EventCtx, data: &AppState, now_playing: &NowPlaying) {
-        if matches!(data.nav, Nav::Lyrics) || data.lyrics_visible {
-            ctx.submit_command(lyrics::SHOW_LYRICS.with(now_playing.clone()));
+        if data.lyrics_shown() {
+            // The lyrics can be in the now-playing window as well.
+            ctx.submit_command(
+                lyrics::SHOW_LYRICS
+                    .with(now_playing.clone())
+                    .to(Target::Global),
+            );
         }
     }
 }
@@ -625,18 +1402,20 @@ where
                 ctx.request_focus();
             }
             Event::Command(cmd) if cmd.is(cmd::EQUALIZER_CONFIG_CHANGED) => {
-                self.send(PlayerEvent::Command(PlayerCommand::Configure {
-                    config: data.config.playback(),
-                }));
+                self.send(PlayerCommand::Configure {
+                    config: data.playback_config(),
+                });
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAYBACK_LOADING) => {
                 let item = cmd.get_unchecked(cmd::PLAYBACK_LOADING);
 
                 if let Some(queued) = data.queued_entry(*item) {
+                    self.apply_episode_speed(data, &queued.item);
                     data.loading_playback(queued.item, queued.origin);
                     self.update_media_control_playback(&data.playback);
                     self.update_media_control_metadata(&data.playback, &data.config);
+                    self.cancel_presence_idle_timer();
                     self.update_discord_presence(&data.playback, &data.config);
                 } else {
                     log::warn!("loaded item not found in playback queue");
@@ -644,20 +1423,51 @@ where
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAYBACK_PLAYING) => {
-                let (item, progress) = cmd.get_unchecked(cmd::PLAYBACK_PLAYING);
+                let (item, progress, quality) = cmd.get_unchecked(cmd::PLAYBACK_PLAYING);
 
                 // Song has changed, so we reset the has_scrobbled value
                 self.has_scrobbled = false;
-                self.report_now_playing(&data.playback);
+                self.has_recorded_play = false;
+                self.report_now_playing(ctx, data);
+
+                // Save the episode we are leaving before it is replaced.
+                self.save_episode_progress(data);
+                let pending_seek = self.pending_seek.take();
 
                 if let Some(queued) = data.queued_entry(*item) {
-                    data.start_playback(queued.item, queued.origin, progress.to_owned());
+                    // Crossfaded items start playing without loading first.
+                    if data.config.crossfade.as_duration().is_some() {
+                        self.load_track_analysis(&queued.item);
+                    }
+                    self.apply_episode_speed(data, &queued.item);
+                    if let Some(position) =
+                        pending_seek.or_else(|| self.episode_resume_position(&queued.item))
+                    {
+                        self.seek(position);
+                    }
+                    self.episode_progress_saved = Duration::ZERO;
+                    self.progress_applied = Some(progress.to_owned());
+
+                    // Playing locally takes over from any remote device we were controlling.
+                    data.playback.remote = None;
+                    data.start_playback(
+                        queued.item,
+                        queued.origin,
+                        progress.to_owned(),
+                        quality.clone(),
+                    );
                     self.update_media_control_playback(&data.playback);
                     self.update_media_control_metadata(&data.playback, &data.config);
+                    self.cancel_presence_idle_timer();
                     self.update_discord_presence(&data.playback, &data.config);
+                    Self::fetch_lastfm_loved(ctx, data);
                     if let Some(now_playing) = &data.playback.now_playing {
                         self.update_lyrics(ctx, data, now_playing);
                     }
+                    data.notification_sound(NotificationEvent::TrackChange);
+                    // The queue is not reported as changed if nothing was up
+                    // next before this item either.
+                    self.prepare_autoplay(ctx, data);
                 } else {
                     log::warn!("played item not found in playback queue");
                 }
@@ -665,21 +1475,47 @@ where
             }
             Event::Command(cmd) if cmd.is(cmd::PLAYBACK_PROGRESS) => {
                 let progress = cmd.get_unchecked(cmd::PLAYBACK_PROGRESS);
-                data.progress_playback(progress.to_owned());
+                let apply = data.lyrics_shown()
+                    || !self.progress_applied.is_some_and(|applied| {
+                        progress.abs_diff(applied) < PROGRESS_APPLY_INTERVAL
+                    });
+                if apply {
+                    data.progress_playback(progress.to_owned());
+                    self.progress_applied = Some(progress.to_owned());
+                } else {
+                    PlaybackClock::global().set(
+                        progress.to_owned(),
+                        data.playback.state == PlaybackState::Playing,
+                    );
+                }
+                PlaybackClock::global().count_report(apply);
 
-                self.report_scrobble(&data.playback);
+                if progress.abs_diff(self.episode_progress_saved) >= EPISODE_PROGRESS_INTERVAL {
+                    self.save_episode_progress(data);
+                }
+                self.report_scrobble(ctx, data);
+                self.record_play(&data.playback, &data.config);
                 self.update_media_control_playback(&data.playback);
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAYBACK_PAUSING) => {
+                self.save_episode_progress(data);
                 data.pause_playback();
                 self.update_media_control_playback(&data.playback);
+                self.start_presence_idle_timer(ctx, &data.config);
                 self.update_discord_presence(&data.playback, &data.config);
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAYBACK_RESUMING) => {
                 data.resume_playback();
                 self.update_media_control_playback(&data.playback);
+                self.cancel_presence_idle_timer();
+                self.update_discord_presence(&data.playback, &data.config);
+                ctx.set_handled();
+            }
+            Event::Timer(token) if *token == self.presence_idle_timer => {
+                self.presence_idle_timer = TimerToken::INVALID;
+                self.presence_idle = true;
                 self.update_discord_presence(&data.playback, &data.config);
                 ctx.set_handled();
             }
@@ -687,24 +1523,86 @@ where
                 data.block_playback();
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(cmd::PLAYBACK_RECOVERED) => {
+                let progress = cmd.get_unchecked(cmd::PLAYBACK_RECOVERED);
+                data.progress_playback(progress.to_owned());
+                data.info_alert("Playback stalled and was restarted.");
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(FLUSH_SCROBBLES) => {
+                ScrobbleQueue::global().retry_now();
+                Self::flush_scrobbles(ctx, &data.config);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PENDING_SCROBBLES_CHANGED) => {
+                data.pending_scrobbles = *cmd.get_unchecked(cmd::PENDING_SCROBBLES_CHANGED);
+                // Nothing else sends the queue again if no track is played.
+                self.scrobble_retry_timer = match ScrobbleQueue::global().retry_in() {
+                    Some(delay) => ctx.request_timer(delay),
+                    None => TimerToken::INVALID,
+                };
+                ctx.set_handled();
+            }
+            Event::Timer(token) if *token == self.scrobble_retry_timer => {
+                self.scrobble_retry_timer = TimerToken::INVALID;
+                Self::flush_scrobbles(ctx, &data.config);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::LASTFM_SET_LOVED) => {
+                let (track, loved) = cmd.get_unchecked(cmd::LASTFM_SET_LOVED);
+                Self::set_lastfm_loved(ctx, data, track, *loved);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::LASTFM_LOVED_CHANGED) => {
+                let &(track_id, loved) = cmd.get_unchecked(cmd::LASTFM_LOVED_CHANGED);
+                data.with_library_mut(|library| library.set_lastfm_loved(track_id, loved));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::OUTPUT_DEVICE_OPENED) => {
+                let device = cmd.get_unchecked(cmd::OUTPUT_DEVICE_OPENED);
+                if let Some(&volume) = data.config.device_volumes.get(device.as_ref()) {
+                    log::info!("restoring volume {volume} of {device:?}");
+                    data.playback.volume = volume;
+                }
+                data.playback.output_device = Some(device.clone());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::OUTPUT_LATENCY_CHANGED) => {
+                data.playback.output_latency = *cmd.get_unchecked(cmd::OUTPUT_LATENCY_CHANGED);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAYBACK_QUALITY_CHANGED) => {
+                let quality = cmd.get_unchecked(cmd::PLAYBACK_QUALITY_CHANGED);
+                if let Some(now_playing) = data.playback.now_playing.as_mut() {
+                    now_playing.quality = quality.clone();
+                }
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(cmd::PLAYBACK_STOPPED) => {
+                self.save_episode_progress(data);
                 data.stop_playback();
+                data.playback.autoplay_from = None;
+                self.autoplay_seed = None;
                 self.update_media_control_playback(&data.playback);
+                self.cancel_presence_idle_timer();
                 self.update_discord_presence(&data.playback, &data.config);
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAY_TRACKS) => {
+                if !data.confirm_metered_playback() {
+                    ctx.set_handled();
+                    return;
+                }
                 let payload = cmd.get_unchecked(cmd::PLAY_TRACKS);
-                data.playback.queue = payload
-                    .items
-                    .iter()
-                    .map(|item| QueueEntry {
-                        origin: payload.origin.to_owned(),
-                        item: item.to_owned(),
-                    })
-                    .collect();
+                let Some(position) = Self::queue_payload(data, payload) else {
+                    ctx.set_handled();
+                    return;
+                };
 
-                self.play(&data.playback.queue, payload.position);
+                self.pending_seek = payload.seek;
+                self.autoplay_seed = None;
+                data.playback.autoplay_from = None;
+                self.play(&data.playback.queue, position, &data.config);
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAY_PAUSE) => {
@@ -720,25 +1618,178 @@ where
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAY_NEXT) => {
-                self.next();
+                self.next(data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::INSTANCE_COMMAND) => {
+                match cmd.get_unchecked(cmd::INSTANCE_COMMAND) {
+                    InstanceCommand::PlayPause => self.pause_or_resume(),
+                    InstanceCommand::Next => self.next(data),
+                    InstanceCommand::Previous => self.previous(),
+                    InstanceCommand::Volume(change) => {
+                        data.playback.volume = change.apply(data.playback.volume);
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAY_PREVIOUS_CHAPTER) => {
+                self.skip_chapter(data, false);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAY_NEXT_CHAPTER) => {
+                self.skip_chapter(data, true);
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAY_STOP) => {
                 self.stop();
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(cmd::PLAY_TEST_SIGNAL) => {
+                let signal = *cmd.get_unchecked(cmd::PLAY_TEST_SIGNAL);
+                self.send(PlayerCommand::PlayTestSignal { signal });
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(cmd::ADD_TO_QUEUE) => {
                 log::info!("adding to queue");
                 let (entry, item) = cmd.get_unchecked(cmd::ADD_TO_QUEUE);
+                let item = PlaybackItem {
+                    skip_intro: Self::skip_intro(&entry.origin, &data.config),
+                    ..*item
+                };
 
-                self.add_to_queue(item);
+                self.add_to_queue(&item);
                 data.add_queued_entry(entry.clone());
+                self.show_queue_alert(data, QueuePlacement::Last, 1);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::QUEUE_ITEMS) => {
+                let (placement, entries) = cmd.get_unchecked(cmd::QUEUE_ITEMS);
+                self.queue_items(*placement, entries, &data.config);
+                data.add_queued_entries(*placement, entries);
+                self.show_queue_alert(data, *placement, entries.len());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::QUEUE_ITEM_QUIETLY) => {
+                let entry = cmd.get_unchecked(cmd::QUEUE_ITEM_QUIETLY);
+                self.queue_items(
+                    QueuePlacement::Last,
+                    std::slice::from_ref(entry),
+                    &data.config,
+                );
+                data.add_queued_entry(entry.clone());
+                self.queue_alert = None;
+
+                let message = |count| match count {
+                    1 => "1 track queued".to_string(),
+                    n => format!("{n} tracks queued"),
+                };
+                self.quiet_queue_alert = match self.quiet_queue_alert {
+                    Some((id, count)) if data.refresh_alert(id, message(count + 1)) => {
+                        Some((id, count + 1))
+                    }
+                    _ => {
+                        data.info_alert(message(1));
+                        data.alerts.back().map(|alert| (alert.id, 1))
+                    }
+                };
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAYBACK_QUEUE_CHANGED) => {
+                let item_ids = cmd.get_unchecked(cmd::PLAYBACK_QUEUE_CHANGED);
+                data.playback.up_next = item_ids
+                    .iter()
+                    .filter_map(|&item_id| data.queued_entry(item_id))
+                    .collect();
+                self.prepare_autoplay(ctx, data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAYBACK_AUTOPLAY_LOADED) => {
+                let entries = cmd.get_unchecked(cmd::PLAYBACK_AUTOPLAY_LOADED);
+                // Skip the recommendations if something got queued in the
+                // meantime, or playback has already ended.
+                if data.playback.up_next.is_empty() && data.playback.state != PlaybackState::Stopped
+                {
+                    if let Some(first) = entries.first() {
+                        data.playback.autoplay_from = Some(first.item.id());
+                    }
+                    self.queue_items(QueuePlacement::Last, entries, &data.config);
+                    for entry in entries {
+                        data.add_queued_entry(entry.clone());
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::MOVE_QUEUE_ITEM) => {
+                let &(from, to) = cmd.get_unchecked(cmd::MOVE_QUEUE_ITEM);
+                self.send(PlayerCommand::MoveQueueItem { from, to });
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::REMOVE_QUEUE_ITEM) => {
+                let &index = cmd.get_unchecked(cmd::REMOVE_QUEUE_ITEM);
+                self.send(PlayerCommand::RemoveQueueItem { index });
                 ctx.set_handled();
             }
-            Event::Command(cmd) if cmd.is(cmd::PLAY_QUEUE_BEHAVIOR) => {
-                let behavior = cmd.get_unchecked(cmd::PLAY_QUEUE_BEHAVIOR);
-                data.set_queue_behavior(behavior.to_owned());
-                self.set_queue_behavior(behavior.to_owned());
+            Event::Command(cmd) if cmd.is(cmd::CLEAR_UP_NEXT) => {
+                self.send(PlayerCommand::ClearUpNext);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::REMOVE_QUEUE_DUPLICATES) => {
+                self.send(PlayerCommand::RemoveQueueDuplicates);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAYBACK_QUEUE_DUPLICATES) => {
+                let duplicates = *cmd.get_unchecked(cmd::PLAYBACK_QUEUE_DUPLICATES);
+                // Correct the alert of the items just queued, instead of
+                // contradicting it with a second one.
+                if let ((skipped, false), Some((id, placement, count))) =
+                    (duplicates, self.queue_alert.take())
+                {
+                    let added = count.saturating_sub(skipped);
+                    if data.refresh_alert(id, queue_message(placement, added, skipped)) {
+                        ctx.set_handled();
+                        return;
+                    }
+                }
+                let message = match duplicates {
+                    (0, true) => "There are no duplicates in the queue.".to_string(),
+                    (1, true) => "Removed 1 duplicate from the queue.".to_string(),
+                    (n, true) => format!("Removed {n} duplicates from the queue."),
+                    (1, false) => "1 track was already in the queue and was skipped.".to_string(),
+                    (n, false) => {
+                        format!("{n} tracks were already in the queue and were skipped.")
+                    }
+                };
+                data.info_alert(message);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAY_SHUFFLE) => {
+                let shuffle = *cmd.get_unchecked(cmd::PLAY_SHUFFLE);
+                data.set_shuffle(shuffle);
+                self.set_shuffle(shuffle);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAY_REPEAT) => {
+                let repeat = *cmd.get_unchecked(cmd::PLAY_REPEAT);
+                data.set_repeat(repeat);
+                self.set_repeat(repeat);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAY_PODCAST_SPEED) => {
+                let speed = cmd.get_unchecked(cmd::PLAY_PODCAST_SPEED);
+                data.set_podcast_tempo(*speed, data.playback.skip_silence);
+                self.save_episode_speed(data);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::SET_PLAYLIST_INTRO_SKIP) => {
+                let (link, seconds) = cmd.get_unchecked(cmd::SET_PLAYLIST_INTRO_SKIP);
+                data.config
+                    .set_playlist_intro_skip(link.id.clone(), *seconds);
+                data.config.save();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAY_SKIP_SILENCE) => {
+                let skip_silence = cmd.get_unchecked(cmd::PLAY_SKIP_SILENCE);
+                data.set_podcast_tempo(data.playback.podcast_speed, *skip_silence);
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAY_SEEK) => {
@@ -756,34 +1807,19 @@ where
                 self.seek(Duration::from_millis(*location));
                 ctx.set_handled();
             }
-            // Keyboard shortcuts.
-            Event::KeyDown(key) if key.code == Code::Space => {
-                self.pause_or_resume();
-                ctx.set_handled();
-            }
-            Event::KeyDown(key) if key.code == Code::ArrowRight => {
-                if key.mods.shift() {
-                    self.next();
-                } else {
-                    self.seek_relative(data, true);
-                }
-                ctx.set_handled();
+            Event::WindowConnected => {
+                data.palette.register(PaletteEntry::new(
+                    "Playback",
+                    "Remove duplicates from the queue",
+                    cmd::REMOVE_QUEUE_DUPLICATES.into(),
+                ));
+                child.event(ctx, event, data, env);
             }
-            Event::KeyDown(key) if key.code == Code::ArrowLeft => {
-                if key.mods.shift() {
-                    self.previous();
-                } else {
-                    self.seek_relative(data, false);
+            Event::Command(cmd) if cmd.is(cmd::RUN_KEYBIND) => {
+                let action = *cmd.get_unchecked(cmd::RUN_KEYBIND);
+                if self.handle_keybind(data, action) {
+                    ctx.set_handled();
                 }
-                ctx.set_handled();
-            }
-            Event::KeyDown(key) if key.key == KbKey::Character("+".to_string()) => {
-                data.playback.volume = (data.playback.volume + 0.1).min(1.0);
-                ctx.set_handled();
-            }
-            Event::KeyDown(key) if key.key == KbKey::Character("-".to_string()) => {
-                data.playback.volume = (data.playback.volume - 0.1).max(0.0);
-                ctx.set_handled();
             }
             _ => child.event(ctx, event, data, env),
         }
@@ -801,7 +1837,9 @@ where
             LifeCycle::WidgetAdded => {
                 self.open_audio_output_and_start_threads(
                     data.session.clone(),
-                    data.config.playback(),
+                    data.playback_config(),
+                    data.config.output_device.as_deref(),
+                    data.config.output_latency.as_duration(),
                     ctx.get_external_handle(),
                     ctx.widget_id(),
                     ctx.window(),
@@ -809,7 +1847,20 @@ where
 
                 // Initialize values loaded from the config.
                 self.set_volume(data.playback.volume);
-                self.set_queue_behavior(data.playback.queue_behavior);
+                self.set_shuffle(data.playback.shuffle);
+                self.set_repeat(data.playback.repeat);
+                self.send(PlayerCommand::SetQueueDeduplication {
+                    enabled: data.config.deduplicate_queue,
+                });
+
+                // The volume last used on the device replaces the global one.
+                if let Some(device) = self.output.as_ref().and_then(AudioOutput::device_id) {
+                    ctx.submit_command(
+                        cmd::OUTPUT_DEVICE_OPENED
+                            .with(device.into())
+                            .to(ctx.widget_id()),
+                    );
+                }
 
                 // Request focus so we can receive keyboard events.
                 ctx.submit_command(cmd::SET_FOCUS.to(ctx.widget_id()));
@@ -824,7 +1875,12 @@ where
         if self.startup {
             self.startup = false;
             self.scrobbler = init_scrobbler_instance(data);
-            self.discord_client = init_discord_client(&data.config);
+            let sink = ctx.get_external_handle();
+            let discord = DiscordPresence::new(move |state| {
+                let _ = sink.submit_command(cmd::DISCORD_STATE_CHANGED, state, Target::Global);
+            });
+            discord.configure(discord_app_id(&data.config));
+            self.discord = Some(discord);
         }
         child.lifecycle(ctx, event, data, env);
     }
@@ -841,6 +1897,50 @@ where
             self.set_volume(data.playback.volume);
         }
 
+        if old_data.config.output_device != data.config.output_device {
+            self.switch_output_device(ctx, data.config.output_device.as_deref());
+        }
+
+        if old_data.playback.state != data.playback.state
+            || old_data.config.prevent_sleep != data.config.prevent_sleep
+        {
+            // Loading is counted as playing, so that the inhibitor is kept
+            // across track changes.
+            let playing = matches!(
+                data.playback.state,
+                PlaybackState::Playing | PlaybackState::Loading
+            );
+            self.sleep_inhibitor
+                .set_active(data.config.prevent_sleep && playing);
+        }
+
+        let playback_config_changed = old_data.config.audio_quality != data.config.audio_quality
+            || old_data.config.resampling_quality != data.config.resampling_quality
+            || old_data.config.crossfade != data.config.crossfade
+            || old_data.config.dsp_order != data.config.dsp_order
+            || old_data.config.bass_mono != data.config.bass_mono
+            || old_data.playback.podcast_speed != data.playback.podcast_speed
+            || old_data.playback.skip_silence != data.playback.skip_silence
+            || old_data.config.metered_behavior != data.config.metered_behavior
+            || old_data.config.interruption_behavior != data.config.interruption_behavior
+            || old_data.config.download_parallelism != data.config.download_parallelism
+            || old_data.config.double_press_previous != data.config.double_press_previous
+            || old_data.config.prefetch_percent != data.config.prefetch_percent
+            || old_data.network_metered != data.network_metered
+            || old_data.is_offline() != data.is_offline();
+
+        if playback_config_changed {
+            self.send(PlayerCommand::Configure {
+                config: data.playback_config(),
+            });
+        }
+
+        if old_data.config.deduplicate_queue != data.config.deduplicate_queue {
+            self.send(PlayerCommand::SetQueueDeduplication {
+                enabled: data.config.deduplicate_queue,
+            });
+        }
+
         let lastfm_changed = old_data.config.lastfm_api_key != data.config.lastfm_api_key
             || old_data.config.lastfm_api_secret != data.config.lastfm_api_secret
             || old_data.config.lastfm_session_key != data.config.lastfm_session_key
@@ -850,18 +1950,23 @@ where
             self.scrobbler = init_scrobbler_instance(data);
         }
 
+        // Send the scrobbles queued while offline, or with other credentials.
+        let reconnected = old_data.connection_state != ConnectionState::Connected
+            && data.connection_state == ConnectionState::Connected;
+        if reconnected || lastfm_changed {
+            ctx.submit_command(FLUSH_SCROBBLES.to(ctx.widget_id()));
+        }
+
         // Reinitialize Discord client if presence settings changed
         let discord_changed = old_data.config.enable_discord_presence
             != data.config.enable_discord_presence
             || old_data.config.discord_app_id != data.config.discord_app_id;
 
         if discord_changed {
-            // Disconnect existing client if any
-            if let Some(mut client) = self.discord_client.take() {
-                let _ = client.close();
+            if let Some(discord) = &self.discord {
+                discord.configure(discord_app_id(&data.config));
             }
-            // Initialize new client if enabled
-            self.discord_client = init_discord_client(&data.config);
+            self.update_discord_presence(&data.playback, &data.config);
         }
 
         // Update presence if privacy settings changed
@@ -870,7 +1975,8 @@ where
             || old_data.config.presence_show_album != data.config.presence_show_album
             || old_data.config.presence_show_track_duration
                 != data.config.presence_show_track_duration
-            || old_data.config.presence_dynamic_cover != data.config.presence_dynamic_cover;
+            || old_data.config.presence_dynamic_cover != data.config.presence_dynamic_cover
+            || old_data.config.presence_idle != data.config.presence_idle;
 
         if privacy_changed {
             self.dynamic_cover_warning_logged = false;
@@ -914,3 +2020,33 @@ fn clean_discord_image_source(source: &str) -> Option<String> {
     }
     Some(trimmed.to_string())
 }
+
+/// Describes the file being played, `None` for local files.
+fn quality_label(path: &MediaPath) -> Option<Arc<str>> {
+    path.quality?;
+    let label = match path.bitrate {
+        Some(bitrate) => format!("{} {bitrate} kbit/s", path.file_format),
+        None => format!("{} lossless", path.file_format),
+    };
+    Some(label.into())
+}
+
+/// Alert for `added` items queued at `placement`, and `skipped` ones that
+/// were already in the queue.
+fn queue_message(placement: QueuePlacement, added: usize, skipped: usize) -> String {
+    let queued = match (placement, added) {
+        (_, 0) => String::new(),
+        (QueuePlacement::Next, 1) => "Track will play next.".to_string(),
+        (QueuePlacement::Next, n) => format!("{n} tracks will play next."),
+        (QueuePlacement::Last, 1) => "Track added to queue.".to_string(),
+        (QueuePlacement::Last, n) => format!("{n} tracks added to queue."),
+    };
+    let skipped = match (added, skipped) {
+        (_, 0) => String::new(),
+        (0, 1) => "The track is already in the queue.".to_string(),
+        (0, n) => format!("All {n} tracks are already in the queue."),
+        (_, 1) => " 1 track already in the queue was skipped.".to_string(),
+        (_, n) => format!(" {n} tracks already in the queue were skipped."),
+    };
+    queued + &skipped
+}
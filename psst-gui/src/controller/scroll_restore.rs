@@ -0,0 +1,102 @@
+use druid::{
+    widget::{prelude::*, Axis, Controller, Scroll},
+    Data,
+};
+
+use crate::data::AppState;
+
+/// Give up restoring an offset after this many frames, if the page content
+/// is still too short to scroll that far.
+const MAX_RESTORE_FRAMES: usize = 60;
+
+/// Keeps the scroll offset of the page in `AppState::scroll_offset`, and
+/// scrolls to it when the navigation changes it, e.g. when going back to a
+/// page.
+pub struct ScrollRestore {
+    pending: Option<f64>,
+    frames: usize,
+}
+
+impl ScrollRestore {
+    pub fn new() -> Self {
+        Self {
+            pending: None,
+            frames: 0,
+        }
+    }
+
+    fn restore_later(&mut self, offset: f64) {
+        self.pending = Some(offset);
+        self.frames = 0;
+    }
+}
+
+impl<W: Widget<AppState>> Controller<AppState, Scroll<AppState, W>> for ScrollRestore {
+    fn event(
+        &mut self,
+        child: &mut Scroll<AppState, W>,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::AnimFrame(_) => {
+                if let Some(offset) = self.pending {
+                    // Wait for the content to be laid out before scrolling, it
+                    // might also still be loading.
+                    child.scroll_to_on_axis(ctx, Axis::Vertical, offset);
+                    self.frames += 1;
+                    if child.offset().y >= offset || self.frames >= MAX_RESTORE_FRAMES {
+                        self.pending = None;
+                    } else {
+                        ctx.request_anim_frame();
+                    }
+                }
+            }
+            Event::Wheel(_) | Event::MouseDown(_) => {
+                // The user scrolls on their own.
+                self.pending = None;
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+        if self.pending.is_none() && !child.offset().y.same(&data.scroll_offset) {
+            data.scroll_offset = child.offset().y;
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut Scroll<AppState, W>,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            if data.scroll_offset > 0.0 {
+                self.restore_later(data.scroll_offset);
+                ctx.request_anim_frame();
+            }
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(
+        &mut self,
+        child: &mut Scroll<AppState, W>,
+        ctx: &mut UpdateCtx,
+        old_data: &AppState,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if !old_data.scroll_offset.same(&data.scroll_offset)
+            && !child.offset().y.same(&data.scroll_offset)
+        {
+            self.restore_later(data.scroll_offset);
+            ctx.request_anim_frame();
+        }
+        child.update(ctx, old_data, data, env);
+    }
+}
@@ -1,17 +1,30 @@
-use druid::widget::{prelude::*, Controller};
+use std::thread;
+
+use druid::{
+    widget::{prelude::*, Controller},
+    Target,
+};
 
 use crate::{
     cmd,
     data::AppState,
-    ui::{home, playlist, user},
+    test_mode::TestMode,
+    ui::{home, library, playlist, user},
 };
 
 pub struct SessionController;
 
 impl SessionController {
     fn connect(&self, ctx: &mut EventCtx, data: &mut AppState) {
-        // Update the session configuration, any active session will get shut down.
-        data.session.update_config(data.config.session());
+        // Update the session configuration, any active session will get shut
+        // down.  The detached session of the test mode would only fail to
+        // connect, and show the app as offline.
+        if TestMode::global().is_none() {
+            data.session.update_config(data.config.session());
+            if !data.config.offline_mode {
+                Self::connect_in_background(ctx, data);
+            }
+        }
 
         // Re-apply persisted OAuth bearer to both core session and Web API, if present.
         if let Some(tok) = data.config.oauth_bearer.clone() {
@@ -21,9 +34,28 @@ impl SessionController {
 
         // Reload the global, usually visible data.
         ctx.submit_command(playlist::LOAD_LIST);
+        ctx.submit_command(cmd::SYNC_LIBRARY);
+        // For the save entries of the episode menus.
+        ctx.submit_command(library::LOAD_EPISODES);
         ctx.submit_command(home::LOAD_MADE_FOR_YOU);
+        if let Some(user) = data.config.lastfm_friends_user() {
+            ctx.submit_command(home::LOAD_LASTFM_FRIENDS.with(user));
+        }
         ctx.submit_command(user::LOAD_PROFILE);
     }
+
+    /// Open the session on a background thread, so that a failed connection
+    /// shows up as offline right away, and not only with the first playback.
+    pub fn connect_in_background(ctx: &mut EventCtx, data: &AppState) {
+        let session = data.session.clone();
+        let sink = ctx.get_external_handle();
+        thread::spawn(move || {
+            if let Err(err) = session.connected() {
+                log::warn!("failed to connect: {err}");
+                let _ = sink.submit_command(cmd::SESSION_CONNECT_FAILED, (), Target::Global);
+            }
+        });
+    }
 }
 
 impl<W> Controller<AppState, W> for SessionController
@@ -40,7 +72,7 @@ where
     ) {
         match event {
             Event::Command(cmd) if cmd.is(cmd::SESSION_CONNECT) => {
-                if data.config.has_credentials() {
+                if data.config.has_credentials() || TestMode::global().is_some() {
                     self.connect(ctx, data);
                 }
                 ctx.set_handled();
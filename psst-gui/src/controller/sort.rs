@@ -2,9 +2,11 @@ use druid::widget::{prelude::*, Controller};
 use druid::{Event, EventCtx, Widget};
 
 use crate::cmd;
-use crate::data::config::SortCriteria;
-use crate::data::{config::SortOrder, AppState};
+use crate::data::{AppState, Nav, SavedTracks};
+use crate::ui::library;
 
+/// Sorts the track table of the page by the clicked column, and loads the
+/// saved tracks whole once they are filtered.
 pub struct SortController;
 
 impl<W> Controller<AppState, W> for SortController
@@ -20,54 +22,75 @@ where
         env: &Env,
     ) {
         match event {
-            Event::Command(cmd) if cmd.is(cmd::TOGGLE_SORT_ORDER) => {
-                if data.config.sort_order == SortOrder::Ascending {
-                    data.config.sort_order = SortOrder::Descending;
-                } else {
-                    data.config.sort_order = SortOrder::Ascending;
+            Event::Command(cmd) if cmd.is(cmd::SORT_TRACKS) => {
+                let column = *cmd.get_unchecked(cmd::SORT_TRACKS);
+                match data.nav {
+                    Nav::PlaylistDetail(_) => {
+                        data.config.playlist_sort = data.config.playlist_sort.clicked(column);
+                        data.config.save();
+                        // Sorted again when loaded.
+                        ctx.submit_command(cmd::NAVIGATE_REFRESH);
+                    }
+                    Nav::SavedTracks => {
+                        data.config.saved_tracks_sort =
+                            data.config.saved_tracks_sort.clicked(column);
+                        data.config.save();
+                        saved_tracks_sort_changed(ctx, data);
+                    }
+                    _ => return,
                 }
-
-                ctx.submit_command(cmd::NAVIGATE_REFRESH);
                 ctx.set_handled();
             }
-            Event::Command(cmd) if cmd.is(cmd::SORT_BY_TITLE) => {
-                if data.config.sort_criteria != SortCriteria::Title {
-                    data.config.sort_criteria = SortCriteria::Title;
-                    ctx.submit_command(cmd::NAVIGATE_REFRESH);
-                    ctx.set_handled();
-                }
-            }
-            Event::Command(cmd) if cmd.is(cmd::SORT_BY_ALBUM) => {
-                if data.config.sort_criteria != SortCriteria::Album {
-                    data.config.sort_criteria = SortCriteria::Album;
-                    ctx.submit_command(cmd::NAVIGATE_REFRESH);
-                    ctx.set_handled();
-                }
-            }
-            Event::Command(cmd) if cmd.is(cmd::SORT_BY_DATE_ADDED) => {
-                if data.config.sort_criteria != SortCriteria::DateAdded {
-                    data.config.sort_criteria = SortCriteria::DateAdded;
-                    ctx.submit_command(cmd::NAVIGATE_REFRESH);
-                    ctx.set_handled();
-                }
-            }
-            Event::Command(cmd) if cmd.is(cmd::SORT_BY_ARTIST) => {
-                if data.config.sort_criteria != SortCriteria::Artist {
-                    data.config.sort_criteria = SortCriteria::Artist;
-                    ctx.submit_command(cmd::NAVIGATE_REFRESH);
-                    ctx.set_handled();
-                }
-            }
-            Event::Command(cmd) if cmd.is(cmd::SORT_BY_DURATION) => {
-                if data.config.sort_criteria != SortCriteria::Duration {
-                    data.config.sort_criteria = SortCriteria::Duration;
-                    ctx.submit_command(cmd::NAVIGATE_REFRESH);
-                    ctx.set_handled();
-                }
-            }
             _ => {
                 child.event(ctx, event, data, env);
             }
         }
     }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &AppState,
+        data: &AppState,
+        env: &Env,
+    ) {
+        let filters = &data.common_ctx.track_filters;
+        if old_data.common_ctx.track_filters.is_empty()
+            && !filters.is_empty()
+            && data.nav == Nav::SavedTracks
+        {
+            let partial = data
+                .library
+                .saved_tracks
+                .resolved()
+                .is_some_and(|saved| !saved.window.is_complete());
+            if partial {
+                ctx.submit_command(library::LOAD_TRACKS.with(true));
+            }
+        }
+        child.update(ctx, old_data, data, env);
+    }
+}
+
+/// Saved tracks are kept in the library and sorted in place, apart from the
+/// playlists.  Saved tracks loaded only in part are loaded whole first, unless
+/// sorted in the order of the API.
+fn saved_tracks_sort_changed(ctx: &mut EventCtx, data: &mut AppState) {
+    let sort = data.config.saved_tracks_sort;
+    let filters = data.common_ctx.track_filters.clone();
+    let mut load_all = false;
+    data.with_library_mut(|library| {
+        if let Some(saved) = library.saved_tracks.resolved_mut() {
+            if saved.window.is_complete() {
+                saved.sort(sort);
+            } else {
+                load_all = !SavedTracks::is_paged(sort, &filters);
+            }
+        }
+    });
+    if load_all {
+        ctx.submit_command(library::LOAD_TRACKS.with(true));
+    }
+    ctx.submit_command(cmd::NAVIGATE_REFRESH);
 }
@@ -0,0 +1,143 @@
+use druid::{
+    commands,
+    widget::{prelude::*, Controller},
+    Selector, Target, WindowState,
+};
+
+use crate::{
+    cmd,
+    data::{AppState, PlaybackState},
+    instance::InstanceCommand,
+    tray::{Tray, TrayAction},
+};
+
+/// Tray menu entries handled by the main window, the playback ones go
+/// straight to the player.
+const TRAY_ACTION: Selector<TrayAction> = Selector::new("app.tray.action");
+
+/// Shows the tray icon for the main window, and hides the window instead of
+/// closing it if `Config::play_in_background` is set.  The player lives in
+/// the window, so it keeps playing until the app quits.
+pub struct TrayController {
+    /// `None` if the platform has no tray.
+    tray: Option<Tray>,
+    window_hidden: bool,
+}
+
+impl TrayController {
+    pub fn new() -> Self {
+        Self {
+            tray: None,
+            window_hidden: false,
+        }
+    }
+
+    fn show_tray(&mut self, ctx: &mut EventCtx, data: &AppState) {
+        let sink = ctx.get_external_handle();
+        let widget_id = ctx.widget_id();
+        let tray = Tray::new(move |action| {
+            let command = match action {
+                TrayAction::PlayPause => InstanceCommand::PlayPause,
+                TrayAction::Next => InstanceCommand::Next,
+                TrayAction::Previous => InstanceCommand::Previous,
+                TrayAction::ToggleWindow | TrayAction::Quit => {
+                    let _ = sink.submit_command(TRAY_ACTION, action, Target::Widget(widget_id));
+                    return;
+                }
+            };
+            let _ = sink.submit_command(cmd::INSTANCE_COMMAND, command, Target::Global);
+        });
+        match tray {
+            Ok(tray) => {
+                self.tray = Some(tray);
+                self.update_tray(data);
+            }
+            Err(err) => log::warn!("failed to show the tray icon: {err}"),
+        }
+    }
+
+    fn update_tray(&self, data: &AppState) {
+        if let Some(tray) = &self.tray {
+            let title = data.playback.now_playing.as_ref().map(|np| np.title());
+            let playing = data.playback.state == PlaybackState::Playing;
+            tray.set_now_playing(title.as_deref(), playing);
+        }
+    }
+
+    /// Hide the window to the tray, or without one, to the dock on macOS and
+    /// the taskbar elsewhere, where it can be brought back from.
+    fn hide_window(&mut self, ctx: &mut EventCtx) {
+        if self.tray.is_some() {
+            self.set_window_hidden(ctx, true);
+        } else if cfg!(target_os = "macos") {
+            #[cfg_attr(not(target_os = "macos"), allow(deprecated))]
+            ctx.submit_command(commands::HIDE_APPLICATION);
+        } else {
+            ctx.window().set_window_state(WindowState::Minimized);
+        }
+    }
+
+    fn set_window_hidden(&mut self, ctx: &mut EventCtx, hidden: bool) {
+        if hidden {
+            ctx.window().hide();
+        } else {
+            ctx.window().show();
+            ctx.window().bring_to_front_and_focus();
+        }
+        self.window_hidden = hidden;
+    }
+}
+
+impl<W> Controller<AppState, W> for TrayController
+where
+    W: Widget<AppState>,
+{
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::WindowConnected => {
+                self.show_tray(ctx, data);
+            }
+            Event::WindowCloseRequested if data.config.play_in_background => {
+                // Keep the window, and with it the player, alive.
+                data.save_volume();
+                data.config.save();
+                self.hide_window(ctx);
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.is(TRAY_ACTION) => {
+                match cmd.get_unchecked(TRAY_ACTION) {
+                    TrayAction::ToggleWindow => self.set_window_hidden(ctx, !self.window_hidden),
+                    TrayAction::Quit => ctx.submit_command(cmd::QUIT_APP_WITH_SAVE),
+                    _ => {}
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env)
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &AppState,
+        data: &AppState,
+        env: &Env,
+    ) {
+        let item = |data: &AppState| data.playback.now_playing.as_ref().map(|np| np.item.id());
+        if old_data.playback.state != data.playback.state || item(old_data) != item(data) {
+            self.update_tray(data);
+        }
+        child.update(ctx, old_data, data, env)
+    }
+}
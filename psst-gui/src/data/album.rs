@@ -2,9 +2,15 @@ use std::sync::Arc;
 
 use druid::{im::Vector, Data, Lens};
 use serde::{Deserialize, Serialize};
-use time::{formatting::Formattable, macros::format_description, Date};
+use time::Date;
 
-use crate::data::{ArtistLink, Cached, Image, Promise, Track};
+use crate::{
+    data::{
+        ArtistLink, Cached, FindQuery, Image, MatchFindQuery, Promise, RestrictionReason,
+        Restrictions, Track,
+    },
+    format,
+};
 
 #[derive(Clone, Data, Lens)]
 pub struct AlbumDetail {
@@ -25,40 +31,43 @@ pub struct Album {
     #[serde(default = "super::utils::default_str")]
     #[serde(deserialize_with = "super::utils::deserialize_null_arc_str")]
     pub label: Arc<str>,
+    /// Only the first page of the tracks is part of the album object, see
+    /// `total_tracks`.
     #[serde(default)]
     #[serde(deserialize_with = "super::utils::deserialize_first_page")]
     pub tracks: Vector<Arc<Track>>,
+    #[serde(default)]
+    pub total_tracks: usize,
     #[serde(deserialize_with = "super::utils::deserialize_date_option")]
     #[data(same_fn = "PartialEq::eq")]
     pub release_date: Option<Date>,
     #[data(same_fn = "PartialEq::eq")]
     pub release_date_precision: Option<DatePrecision>,
+    /// Present when the album cannot be played.
+    #[serde(default)]
+    pub restrictions: Option<Restrictions>,
+    /// When the album was saved to the library, for saved albums.
+    #[serde(skip)]
+    #[data(same_fn = "PartialEq::eq")]
+    pub saved_at: Option<Date>,
 }
 
 impl Album {
-    pub fn release(&self) -> String {
-        self.release_with_format(match self.release_date_precision {
-            Some(DatePrecision::Year) | None => format_description!("[year]"),
-            Some(DatePrecision::Month) => format_description!("[month repr:long] [year]"),
-            Some(DatePrecision::Day) => format_description!("[month repr:long] [day], [year]"),
-        })
+    pub fn restriction(&self) -> Option<RestrictionReason> {
+        self.restrictions.as_ref().map(Restrictions::reason)
     }
 
-    pub fn release_year(&self) -> String {
-        self.release_with_format(format_description!("[year]"))
-    }
-
-    pub fn release_year_int(&self) -> usize {
-        self.release_year().parse::<usize>().unwrap_or_else(|err| {
-            log::error!("error parsing release year for {}: {}", self.name, err);
-            usize::MAX
-        })
+    pub fn release(&self) -> String {
+        match (self.release_date, self.release_date_precision) {
+            (Some(date), Some(DatePrecision::Month)) => format::month(date),
+            (Some(date), Some(DatePrecision::Day)) => format::date(date),
+            _ => self.release_year(),
+        }
     }
 
-    fn release_with_format(&self, format: &(impl Formattable + ?Sized)) -> String {
+    pub fn release_year(&self) -> String {
         self.release_date
-            .as_ref()
-            .map(|date| date.format(format).expect("invalid format"))
+            .map(|date| date.year().to_string())
             .unwrap_or_default()
     }
 
@@ -78,6 +87,35 @@ impl Album {
         }
     }
 
+    /// Key shared by market-specific releases of the same album, used to spot
+    /// duplicates in the library.
+    pub fn duplicate_key(&self) -> String {
+        let artists = self
+            .artists
+            .iter()
+            .map(|artist| artist.name.trim().to_lowercase())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "{}|{}|{:?}",
+            self.name.trim().to_lowercase(),
+            artists,
+            self.album_type
+        )
+    }
+
+    /// Title of the disc starting at the track at `position`, for albums of
+    /// more than one disc.
+    pub fn disc_header(&self, position: usize) -> Option<Arc<str>> {
+        let disc = self.tracks.get(position)?.disc_number;
+        let first_of_disc = match position.checked_sub(1) {
+            Some(previous) => self.tracks.get(previous)?.disc_number != disc,
+            None => true,
+        };
+        let multi_disc = self.tracks.iter().any(|track| track.disc_number != disc);
+        (first_of_disc && multi_disc).then(|| format!("Disc {disc}").into())
+    }
+
     pub fn has_explicit(&self) -> bool {
         self.tracks.iter().any(|t| t.explicit)
     }
@@ -95,6 +133,17 @@ impl Album {
     }
 }
 
+impl MatchFindQuery for Album {
+    fn matches_query(&self, query: &FindQuery) -> bool {
+        query.matches_str(&self.name)
+            || query.matches_str(&self.label)
+            || self
+                .artists
+                .iter()
+                .any(|artist| query.matches_str(&artist.name))
+    }
+}
+
 #[derive(Clone, Debug, Data, Lens, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub struct AlbumLink {
     pub id: Arc<str>,
@@ -134,6 +183,22 @@ pub struct Copyright {
     pub kind: CopyrightType,
 }
 
+impl Copyright {
+    /// The notice with its symbol, which Spotify leaves out of some of them.
+    pub fn notice(&self) -> String {
+        let symbol = match self.kind {
+            CopyrightType::Copyright => '©',
+            CopyrightType::Performance => '℗',
+        };
+        let text = self.text.trim();
+        if text.starts_with(['©', '℗', '(']) {
+            text.to_string()
+        } else {
+            format!("{symbol} {text}")
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Deserialize)]
 pub enum CopyrightType {
     #[serde(rename = "C")]
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use druid::{im::Vector, Data, Lens};
 use serde::{Deserialize, Serialize};
 
-use crate::data::{Album, Cached, Image, Promise, Track};
+use crate::data::{Album, AlbumType, Cached, FindQuery, Image, MatchFindQuery, Promise, Track};
 
 #[derive(Clone, Data, Lens)]
 pub struct ArtistDetail {
@@ -12,6 +12,7 @@ pub struct ArtistDetail {
     pub top_tracks: Promise<ArtistTracks, ArtistLink>,
     pub related_artists: Promise<Cached<Vector<Artist>>, ArtistLink>,
     pub artist_info: Promise<ArtistInfo, ArtistLink>,
+    pub following: Promise<bool, ArtistLink>,
 }
 
 #[derive(Clone, Data, Lens, Deserialize)]
@@ -34,13 +35,56 @@ impl Artist {
     }
 }
 
+impl MatchFindQuery for Artist {
+    fn matches_query(&self, query: &FindQuery) -> bool {
+        query.matches_str(&self.name)
+    }
+}
+
 #[derive(Clone, Data, Lens)]
 pub struct ArtistAlbums {
+    pub albums: ArtistReleases,
+    pub singles: ArtistReleases,
+    pub compilations: ArtistReleases,
+    pub appears_on: ArtistReleases,
+}
+
+impl ArtistAlbums {
+    pub fn group_mut(&mut self, group: &AlbumType) -> &mut ArtistReleases {
+        match group {
+            AlbumType::Album => &mut self.albums,
+            AlbumType::Single => &mut self.singles,
+            AlbumType::Compilation => &mut self.compilations,
+            AlbumType::AppearsOn => &mut self.appears_on,
+        }
+    }
+}
+
+/// Releases of an artist in one group, only the first ones until all of them
+/// are asked for.
+#[derive(Clone, Data, Lens)]
+pub struct ArtistReleases {
+    pub group: AlbumType,
     pub albums: Vector<Arc<Album>>,
-    pub singles: Vector<Arc<Album>>,
-    pub compilations: Vector<Arc<Album>>,
-    pub appears_on: Vector<Arc<Album>>,
+    pub total: usize,
+    pub loading: bool,
+}
+
+impl ArtistReleases {
+    pub fn title(&self) -> &'static str {
+        match self.group {
+            AlbumType::Album => "Albums",
+            AlbumType::Single => "Singles & EPs",
+            AlbumType::Compilation => "Compilations",
+            AlbumType::AppearsOn => "Appears On",
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.albums.len() >= self.total
+    }
 }
+
 #[derive(Clone, Data, Lens)]
 pub struct ArtistInfo {
     pub main_image: Arc<str>,
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use druid::{im::Vector, Data, Lens};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{Album, Image, Playlist, Promise};
+
+#[derive(Clone, Data, Lens)]
+pub struct Browse {
+    pub featured: Promise<Feed<Playlist>>,
+    pub new_releases: Promise<Feed<Arc<Album>>>,
+    pub categories: Promise<Feed<Category>>,
+    pub category: Promise<Feed<Playlist>, CategoryLink>,
+}
+
+/// Items of a browse feed, loaded a page at a time as they are scrolled to.
+#[derive(Clone, Data, Lens)]
+pub struct Feed<T: Data> {
+    pub items: Vector<T>,
+    pub total: usize,
+    /// The next page is being loaded.
+    pub loading: bool,
+}
+
+impl<T: Data> Feed<T> {
+    pub fn new((items, total): (Vector<T>, usize)) -> Self {
+        Self {
+            items,
+            total,
+            loading: false,
+        }
+    }
+
+    /// Offset of the next page, `None` when all items are loaded, or the
+    /// next page is being loaded.
+    pub fn next_offset(&self) -> Option<usize> {
+        (!self.loading && self.items.len() < self.total).then_some(self.items.len())
+    }
+
+    pub fn append(&mut self, (items, total): (Vector<T>, usize)) {
+        self.items.append(items);
+        self.total = total;
+        self.loading = false;
+    }
+}
+
+#[derive(Clone, Data, Lens, Deserialize)]
+pub struct Category {
+    pub id: Arc<str>,
+    pub name: Arc<str>,
+    #[serde(default)]
+    pub icons: Vector<Image>,
+}
+
+impl Category {
+    pub fn image(&self, width: f64, height: f64) -> Option<&Image> {
+        Image::at_least_of_size(&self.icons, width, height)
+    }
+
+    pub fn link(&self) -> CategoryLink {
+        CategoryLink {
+            id: self.id.clone(),
+            name: self.name.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Data, Lens, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct CategoryLink {
+    pub id: Arc<str>,
+    pub name: Arc<str>,
+}
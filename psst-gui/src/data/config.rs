@@ -1,51 +1,95 @@
 use std::{
+    collections::HashMap,
     env::{self, VarError},
+    fmt,
     fs::{self, File, OpenOptions},
     io::{BufReader, BufWriter},
     path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
 };
 
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::OpenOptionsExt;
 
-use druid::{Data, Lens, Size};
+use druid::{im::Vector, Data, Lens, Point, Size};
 use platform_dirs::AppDirs;
 use psst_core::{
-    audio::equalizer::{EqualizerConfig, EqualizerPreset},
-    cache::{mkdir_if_not_exists, CacheHandle},
+    audio::{
+        equalizer::{EqualizerConfig, EqualizerPreset},
+        generator::Chime,
+        processor::{DspConfig, DspStage},
+        resample::ResamplingQuality,
+        stretch::Tempo,
+    },
+    cache::{mkdir_if_not_exists, CacheHandle, CacheUsage, CacheVerification},
     connection::Credentials,
-    player::PlaybackConfig,
+    player::{
+        file::QualityTier, interruption::InterruptionPolicy, PlaybackConfig,
+        DEFAULT_DOWNLOAD_PARALLELISM, DEFAULT_PREFETCH_AT,
+    },
     session::{SessionConfig, SessionConnection},
 };
 use serde::{Deserialize, Serialize};
+use url::Url;
 
-use super::{Nav, Promise, QueueBehavior, SliderScrollScale, UpdateInfo, UpdatePreferences};
-use crate::ui::theme;
+use super::{
+    GeneratedPlaylists, KeybindAction, Keybinds, LastFmUser, Nav, OfflinePlaylists, Promise,
+    Repeat, Shuffle, SliderScrollScale, SortOrder, TableSort, TrackColumn, UpdateInfo,
+    UpdatePreferences,
+};
+use crate::{
+    keychain::{self, Secrets},
+    test_mode::TestMode,
+    ui::theme,
+};
 
 #[derive(Clone, Debug, Data, Lens)]
 pub struct Preferences {
     pub active: PreferencesTab,
     #[data(ignore)]
     pub cache: Option<CacheHandle>,
-    pub cache_size: Promise<u64, (), ()>,
+    pub cache_usage: Promise<Arc<CacheUsage>, (), ()>,
+    /// Partial count while the cache is being measured.
+    pub cache_usage_progress: Option<Arc<CacheUsage>>,
+    pub cache_verification: Promise<Arc<CacheVerification>, (), String>,
+    /// Access point reached through the tested proxy URL.
+    pub proxy_test: Promise<Arc<str>, Option<String>, String>,
     pub auth: Authentication,
     pub lastfm_auth_result: Option<String>,
     pub available_update: Option<UpdateInfo>,
     pub checking_update: bool,
     pub installing_update: bool,
     pub update_install_status: Option<String>,
+    pub syncing_playlists: bool,
+    /// Action waiting for its new key combination in the Keybinds tab.
+    pub capturing_keybind: Option<KeybindAction>,
 }
 
 impl Preferences {
+    /// Final or partial usage of the cache, whichever is known.
+    pub fn cache_usage_so_far(&self) -> Option<&CacheUsage> {
+        match &self.cache_usage {
+            Promise::Resolved { val, .. } => Some(val),
+            Promise::Deferred { .. } => self.cache_usage_progress.as_deref(),
+            _ => None,
+        }
+    }
+
     pub fn reset(&mut self) {
-        self.cache_size.clear();
+        self.cache_usage.clear();
+        self.cache_usage_progress = None;
+        self.cache_verification.clear();
+        self.proxy_test.clear();
         self.auth.result.clear();
         self.auth.lastfm_api_key_input.clear();
         self.auth.lastfm_api_secret_input.clear();
-    }
-
-    pub fn measure_cache_usage() -> Option<u64> {
-        Config::cache_dir().and_then(|path| get_dir_size(&path))
+        self.capturing_keybind = None;
     }
 }
 
@@ -56,7 +100,10 @@ pub enum PreferencesTab {
     Equalizer,
     Account,
     DiscordPresence,
+    Keybinds,
+    Network,
     Cache,
+    Diagnostics,
     Updates,
     About,
 }
@@ -85,7 +132,7 @@ impl Authentication {
         }
     }
 
-    pub fn session_config(&self) -> SessionConfig {
+    pub fn session_config(&self, proxy_url: Option<String>) -> SessionConfig {
         SessionConfig {
             login_creds: if !self.access_token.is_empty() {
                 Credentials::from_access_token(self.access_token.clone())
@@ -95,7 +142,7 @@ impl Authentication {
                     self.password.clone(),
                 )
             },
-            proxy_url: Config::proxy(),
+            proxy_url,
         }
     }
 
@@ -113,11 +160,39 @@ impl Authentication {
 const APP_NAME: &str = "Psst";
 const CONFIG_FILENAME: &str = "config.json";
 const PROXY_ENV_VAR: &str = "SOCKS_PROXY";
+/// Number of the recent searches kept.
+const SEARCH_HISTORY_LEN: usize = 20;
+
+/// Saves started so far, a background save is skipped if a newer one started
+/// while it waited.
+static SAVES: AtomicU64 = AtomicU64::new(0);
+/// Held while the config file is written.
+static SAVING: Mutex<()> = Mutex::new(());
 
 fn default_sidebar_visible() -> bool {
     true
 }
 
+fn default_history_enable() -> bool {
+    true
+}
+
+fn default_suggest_skip_removals() -> bool {
+    true
+}
+
+fn default_presence_idle_minutes() -> usize {
+    10
+}
+
+fn default_download_parallelism() -> usize {
+    DEFAULT_DOWNLOAD_PARALLELISM
+}
+
+fn default_prefetch_percent() -> u32 {
+    (DEFAULT_PREFETCH_AT * 100.0) as u32
+}
+
 #[derive(Clone, Debug, Data, Lens, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
@@ -126,27 +201,131 @@ pub struct Config {
     #[serde(alias = "oauth_token_override")]
     pub oauth_bearer: Option<String>,
     pub oauth_refresh_token: Option<String>,
+    /// The secrets above are kept in the OS keychain and left out of the
+    /// file.  Falls back to the file when the keychain is not available.
+    #[data(ignore)]
+    secrets_in_keychain: bool,
+    /// Loading from the keychain failed, leave the secrets there untouched
+    /// until new ones are stored.
+    #[serde(skip)]
+    #[data(ignore)]
+    keychain_unreadable: bool,
     pub audio_quality: AudioQuality,
+    #[data(same_fn = "PartialEq::eq")]
+    pub resampling_quality: ResamplingQuality,
+    #[serde(default)]
+    pub output_latency: OutputLatency,
+    /// ID of the output device to play to, `None` for the default device of
+    /// the system.
+    #[serde(default)]
+    pub output_device: Option<String>,
+    #[serde(default)]
+    pub crossfade: Crossfade,
     pub theme: Theme,
     #[serde(default)]
     pub custom_theme: CustomTheme,
     pub volume: f64,
+    /// Last volume used on each output device, by device ID.
+    #[data(same_fn = "PartialEq::eq")]
+    #[serde(default)]
+    pub device_volumes: HashMap<String, f64>,
     pub last_route: Option<Nav>,
-    pub queue_behavior: QueueBehavior,
+    #[serde(default)]
+    pub shuffle: Shuffle,
+    #[serde(default)]
+    pub repeat: Repeat,
+    /// Shuffle and repeat in one setting, as written by older versions.
+    /// Moved over to `shuffle` and `repeat` on load.
+    #[data(ignore)]
+    #[serde(skip_serializing)]
+    queue_behavior: Option<LegacyQueueBehavior>,
+    /// Keep playing recommended tracks when the queue runs out.
+    #[serde(default)]
+    pub autoplay: bool,
+    /// Skip tracks that are already playing or up next when queueing.
+    #[serde(default)]
+    pub deduplicate_queue: bool,
+    /// Pressing Previous twice quickly restarts the album or playlist.
+    #[serde(default)]
+    pub double_press_previous: bool,
+    /// Keep the system from going to sleep while music is playing.
+    pub prevent_sleep: bool,
+    /// Playback rate of podcast episodes without a speed of their own, the
+    /// one chosen last.
+    pub podcast_speed: f64,
+    /// Shorten long pauses in podcast episodes.
+    pub skip_silence: bool,
+    /// What to do when streaming over a metered connection.
+    pub metered_behavior: MeteredBehavior,
+    /// What to do when another application starts playing audio.
+    pub interruption_behavior: InterruptionBehavior,
     pub show_track_cover: bool,
     pub show_playlist_images: bool,
+    /// Layout of the playback bar, it is compact in narrow windows anyway.
+    #[serde(default)]
+    pub playback_bar_layout: PlaybackBarLayout,
     pub window_size: Size,
+    /// Where the mini player was last moved to on the screen.
+    #[serde(default)]
+    pub mini_player_position: Option<Point>,
     pub slider_scroll_scale: SliderScrollScale,
-    pub sort_order: SortOrder,
-    pub sort_criteria: SortCriteria,
-    pub paginated_limit: usize,
+    /// Sort of the track table of playlists.
+    pub playlist_sort: TableSort,
+    /// Sort of the track table of the saved tracks.
+    pub saved_tracks_sort: TableSort,
+    /// Sort of all track lists, as written by older versions.  Moved over to
+    /// `playlist_sort` on load.
+    #[data(ignore)]
+    #[serde(skip_serializing)]
+    sort_criteria: Option<TrackColumn>,
+    #[data(ignore)]
+    #[serde(skip_serializing)]
+    sort_order: Option<SortOrder>,
     pub seek_duration: usize,
     pub lastfm_session_key: Option<String>,
     pub lastfm_api_key: Option<String>,
     pub lastfm_api_secret: Option<String>,
     pub lastfm_enable: bool,
+    /// Name of the connected Last.fm account.  Missing for accounts connected
+    /// before it was stored, reconnecting fills it in.
+    #[serde(default)]
+    pub lastfm_username: Option<String>,
+    #[serde(default)]
+    pub lastfm_show_friends: bool,
+    #[serde(default)]
+    pub scrobbling: ScrobbleSettings,
+    /// Record plays and skips in the local listening history, see
+    /// `PlayHistory`.
+    #[serde(default = "default_history_enable")]
+    pub history_enable: bool,
+    /// Append every play to `play_log_path`, see `PlayLog`.
+    #[serde(default)]
+    pub play_log_enable: bool,
+    #[serde(default)]
+    pub play_log_path: String,
+    /// Offer to remove the tracks that keep being skipped from playlists.
+    #[serde(default = "default_suggest_skip_removals")]
+    pub suggest_skip_removals: bool,
+    /// When the removal of skipped tracks was last offered, by playlist ID.
+    /// Only the skips since then count for the next offer.
+    #[data(same_fn = "PartialEq::eq")]
+    #[serde(default)]
+    pub skip_suggested_at: HashMap<Arc<str>, u64>,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
     #[serde(default = "default_sidebar_visible")]
     pub sidebar_visible: bool,
+    /// Show the synced lyrics in the now-playing view.
+    #[serde(default)]
+    pub now_playing_lyrics: bool,
+    /// Show the spectrum of the audio in the now-playing view.
+    #[serde(default)]
+    pub now_playing_visualizer: bool,
+    /// Keep the player running when the main window is closed, hiding the
+    /// window to the tray, or to the dock or taskbar without one.  The app
+    /// then quits only from the menu or the tray.
+    #[serde(default)]
+    pub play_in_background: bool,
     #[serde(default)]
     pub enable_discord_presence: bool,
     #[serde(default)]
@@ -159,14 +338,62 @@ pub struct Config {
     pub presence_show_track_duration: bool,
     #[serde(default)]
     pub presence_dynamic_cover: bool,
+    /// What the presence shows once playback has been paused for
+    /// `presence_idle_minutes`.
+    #[serde(default)]
+    pub presence_idle: PresenceIdle,
+    #[serde(default = "default_presence_idle_minutes")]
+    pub presence_idle_minutes: usize,
     #[data(ignore)]
     #[serde(default)]
     pub equalizer: EqualizerConfig,
     #[data(ignore)]
     #[serde(default)]
     pub custom_equalizer_presets: Vec<EqualizerPreset>,
+    /// Order of the processing stages.
+    #[data(same_fn = "PartialEq::eq")]
+    pub dsp_order: Vec<DspStage>,
+    /// Fold the bass of both channels into mono.
+    pub bass_mono: bool,
     #[serde(default)]
     pub update_preferences: UpdatePreferences,
+    #[serde(default)]
+    pub generated_playlists: GeneratedPlaylists,
+    /// Folders scanned for the Local Files section.
+    #[serde(default)]
+    pub local_folders: Vector<String>,
+    /// Episodes of each show counted when it was last opened, by show ID, for
+    /// the new-episode badges of the followed shows.
+    #[data(same_fn = "PartialEq::eq")]
+    #[serde(default)]
+    pub seen_episodes: HashMap<Arc<str>, usize>,
+    /// Recent search queries, the most recent first.
+    #[serde(default)]
+    pub search_history: Vector<Arc<str>>,
+    /// Seconds to skip at the start of every track, by playlist ID.
+    #[data(same_fn = "PartialEq::eq")]
+    #[serde(default)]
+    pub playlist_intro_skip: HashMap<Arc<str>, u64>,
+    /// Playlists kept downloaded for offline listening.
+    #[data(same_fn = "PartialEq::eq")]
+    #[serde(default)]
+    pub offline_playlists: OfflinePlaylists,
+    #[data(same_fn = "PartialEq::eq")]
+    #[serde(default)]
+    pub keybinds: Keybinds,
+    #[serde(default)]
+    pub proxy: ProxySettings,
+    /// Stay offline, showing the indexed library and playing only what is
+    /// in the cache.
+    #[serde(default)]
+    pub offline_mode: bool,
+    /// Chunks of a streamed file downloaded at once.
+    #[serde(default = "default_download_parallelism")]
+    pub download_parallelism: usize,
+    /// Percentage of the current track after which the next one is downloaded,
+    /// 0 to download it only as it plays.
+    #[serde(default = "default_prefetch_percent")]
+    pub prefetch_percent: u32,
 }
 
 impl Default for Config {
@@ -175,34 +402,81 @@ impl Default for Config {
             credentials: Default::default(),
             oauth_bearer: None,
             oauth_refresh_token: None,
+            secrets_in_keychain: false,
+            keychain_unreadable: false,
             audio_quality: Default::default(),
+            resampling_quality: Default::default(),
+            output_latency: Default::default(),
+            output_device: None,
+            crossfade: Default::default(),
             theme: Default::default(),
             custom_theme: Default::default(),
             volume: 1.0,
+            device_volumes: HashMap::new(),
             last_route: Default::default(),
-            queue_behavior: Default::default(),
+            shuffle: Default::default(),
+            repeat: Default::default(),
+            queue_behavior: None,
+            autoplay: false,
+            deduplicate_queue: false,
+            double_press_previous: false,
+            prevent_sleep: true,
+            podcast_speed: 1.0,
+            skip_silence: false,
+            metered_behavior: Default::default(),
+            interruption_behavior: Default::default(),
             show_track_cover: Default::default(),
             show_playlist_images: true,
+            playback_bar_layout: PlaybackBarLayout::default(),
             window_size: Size::new(theme::grid(80.0), theme::grid(100.0)),
+            mini_player_position: None,
             slider_scroll_scale: Default::default(),
-            sort_order: Default::default(),
-            sort_criteria: Default::default(),
-            paginated_limit: 500,
+            playlist_sort: TableSort::PLAYLIST_ORDER,
+            saved_tracks_sort: TableSort::RECENTLY_SAVED,
+            sort_criteria: None,
+            sort_order: None,
             seek_duration: 10,
             lastfm_session_key: None,
             lastfm_api_key: None,
             lastfm_api_secret: None,
             lastfm_enable: false,
+            lastfm_username: None,
+            lastfm_show_friends: false,
+            scrobbling: Default::default(),
+            history_enable: true,
+            play_log_enable: false,
+            play_log_path: String::new(),
+            suggest_skip_removals: true,
+            skip_suggested_at: HashMap::new(),
+            notifications: Default::default(),
             sidebar_visible: true,
+            now_playing_lyrics: false,
+            now_playing_visualizer: false,
+            play_in_background: false,
             enable_discord_presence: false,
             discord_app_id: String::new(),
             presence_show_artist: true,
             presence_show_album: true,
             presence_show_track_duration: true,
             presence_dynamic_cover: false,
+            presence_idle: PresenceIdle::default(),
+            presence_idle_minutes: default_presence_idle_minutes(),
             equalizer: Default::default(),
             custom_equalizer_presets: Vec::new(),
+            dsp_order: DspStage::default_order(),
+            bass_mono: false,
             update_preferences: Default::default(),
+            generated_playlists: Default::default(),
+            local_folders: Vector::new(),
+            seen_episodes: HashMap::new(),
+            search_history: Vector::new(),
+            playlist_intro_skip: HashMap::new(),
+            offline_playlists: HashMap::new(),
+            keybinds: Keybinds::default(),
+            proxy: Default::default(),
+            offline_mode: false,
+            download_parallelism: DEFAULT_DOWNLOAD_PARALLELISM,
+            prefetch_percent: default_prefetch_percent(),
         }
     }
 }
@@ -222,10 +496,16 @@ impl Config {
     }
 
     pub fn cache_dir() -> Option<PathBuf> {
+        if let Some(test_mode) = TestMode::global() {
+            return Some(test_mode.cache_dir());
+        }
         Self::app_dirs().map(|dirs| dirs.cache_dir)
     }
 
     pub fn config_dir() -> Option<PathBuf> {
+        if let Some(test_mode) = TestMode::global() {
+            return Some(test_mode.config_dir());
+        }
         Self::app_dirs().map(|dirs| dirs.config_dir)
     }
 
@@ -238,13 +518,79 @@ impl Config {
         if let Ok(file) = File::open(&path) {
             log::info!("loading config: {:?}", &path);
             let reader = BufReader::new(file);
-            Some(serde_json::from_reader(reader).expect("Failed to read config"))
+            let mut config: Config =
+                serde_json::from_reader(reader).expect("Failed to read config");
+            config.migrate();
+            if config.secrets_in_keychain {
+                match keychain::load() {
+                    Ok(secrets) => config.set_secrets(secrets),
+                    Err(err) => {
+                        log::error!("failed to load secrets from the keychain: {err}");
+                        config.keychain_unreadable = true;
+                    }
+                }
+            } else if !config.secrets().is_empty() {
+                // Written before the keychain was used, or while it was not
+                // available.  Saving moves the secrets over.
+                config.save();
+            }
+            Some(config)
         } else {
             None
         }
     }
 
+    /// Move over the settings written by older versions.
+    fn migrate(&mut self) {
+        if let Some(behavior) = self.queue_behavior.take() {
+            (self.shuffle, self.repeat) = behavior.split();
+        }
+        // Older versions always wrote a sort, and did not sort the saved
+        // tracks by it, so those keep the most recently saved first.
+        if self.sort_criteria.is_some() || self.sort_order.is_some() {
+            self.playlist_sort = TableSort::new(
+                self.sort_criteria.take().unwrap_or(TrackColumn::DateAdded),
+                self.sort_order.take().unwrap_or_default(),
+            );
+        }
+    }
+
+    fn secrets(&self) -> Secrets {
+        Secrets {
+            credentials: self.credentials.clone(),
+            oauth_bearer: self.oauth_bearer.clone(),
+            oauth_refresh_token: self.oauth_refresh_token.clone(),
+            proxy_password: Some(self.proxy.password.clone()).filter(|p| !p.is_empty()),
+        }
+    }
+
+    fn set_secrets(&mut self, secrets: Secrets) {
+        self.credentials = secrets.credentials;
+        self.oauth_bearer = secrets.oauth_bearer;
+        self.oauth_refresh_token = secrets.oauth_refresh_token;
+        self.proxy.password = secrets.proxy_password.unwrap_or_default();
+    }
+
     pub fn save(&self) {
+        SAVES.fetch_add(1, Ordering::SeqCst);
+        let _saving = SAVING.lock();
+        self.write();
+    }
+
+    /// Save on a background thread, for changes coming in while downloading
+    /// that should not hold up the UI.
+    pub fn save_in_background(&self) {
+        let save = SAVES.fetch_add(1, Ordering::SeqCst) + 1;
+        let config = self.clone();
+        thread::spawn(move || {
+            let _saving = SAVING.lock();
+            if SAVES.load(Ordering::SeqCst) == save {
+                config.write();
+            }
+        });
+    }
+
+    fn write(&self) {
         let dir = Self::config_dir().expect("Failed to get config dir");
         let path = Self::config_path().expect("Failed to get config path");
         mkdir_if_not_exists(&dir).expect("Failed to create config dir");
@@ -254,10 +600,35 @@ impl Config {
         #[cfg(target_family = "unix")]
         options.mode(0o600);
 
+        let mut stored = self.clone();
+        let secrets = self.secrets();
+        let in_keychain = if self.keychain_unreadable && secrets.is_empty() {
+            true
+        } else if !keychain::is_available() {
+            // Failed before, keep the secrets in the config without waiting
+            // for the keychain again.
+            false
+        } else {
+            match keychain::store(&secrets) {
+                Ok(()) => true,
+                Err(err) => {
+                    log::warn!(
+                        "failed to store secrets in the keychain, keeping them in the config: \
+                         {err}"
+                    );
+                    false
+                }
+            }
+        };
+        if in_keychain {
+            stored.set_secrets(Secrets::default());
+        }
+        stored.secrets_in_keychain = in_keychain;
+
         let file = options.open(&path).expect("Failed to create config");
         let writer = BufWriter::new(file);
 
-        serde_json::to_writer_pretty(writer, self).expect("Failed to write config");
+        serde_json::to_writer_pretty(writer, &stored).expect("Failed to write config");
         log::info!("saved config: {:?}", &path);
     }
 
@@ -279,22 +650,77 @@ impl Config {
             .and_then(|c| c.username.as_deref())
     }
 
+    /// Last.fm account to show the friends of on the home page, if enabled.
+    pub fn lastfm_friends_user(&self) -> Option<LastFmUser> {
+        if !self.lastfm_show_friends {
+            return None;
+        }
+        Some(LastFmUser {
+            api_key: self.lastfm_api_key.as_deref()?.into(),
+            username: self.lastfm_username.as_deref()?.into(),
+        })
+    }
+
     pub fn session(&self) -> SessionConfig {
         SessionConfig {
             login_creds: self.credentials.clone().expect("Missing credentials"),
-            proxy_url: Config::proxy(),
+            proxy_url: self.proxy_url(),
         }
     }
 
     pub fn playback(&self) -> PlaybackConfig {
         PlaybackConfig {
-            bitrate: self.audio_quality.as_bitrate(),
-            equalizer: self.equalizer.clone(),
+            quality: self.audio_quality.as_tier(),
+            adaptive_quality: self.audio_quality == AudioQuality::Auto,
+            dsp: DspConfig {
+                order: DspStage::complete_order(&self.dsp_order),
+                equalizer: self.equalizer.clone(),
+                bass_mono: self.bass_mono,
+            },
+            resampling_quality: self.resampling_quality,
+            crossfade: self.crossfade.as_duration(),
+            podcast_tempo: Tempo::new(self.podcast_speed as f32, self.skip_silence),
+            interruption_policy: self.interruption_behavior.as_policy(),
+            download_parallelism: self.download_parallelism,
+            double_press_previous_restarts: self.double_press_previous,
+            prefetch_at: (self.prefetch_percent > 0).then(|| self.prefetch_percent as f32 / 100.0),
             ..PlaybackConfig::default()
         }
     }
 
-    pub fn proxy() -> Option<String> {
+    /// Length of the intro skipped in tracks played from a playlist.
+    pub fn playlist_intro_skip(&self, playlist_id: &str) -> Duration {
+        let seconds = self.playlist_intro_skip.get(playlist_id).copied();
+        Duration::from_secs(seconds.unwrap_or(0))
+    }
+
+    pub fn set_playlist_intro_skip(&mut self, playlist_id: Arc<str>, seconds: u64) {
+        if seconds == 0 {
+            self.playlist_intro_skip.remove(&playlist_id);
+        } else {
+            self.playlist_intro_skip.insert(playlist_id, seconds);
+        }
+    }
+
+    /// Put `query` at the front of the recent searches.
+    pub fn add_search(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.search_history
+            .retain(|recent| !recent.eq_ignore_ascii_case(query));
+        self.search_history.push_front(query.into());
+        self.search_history.truncate(SEARCH_HISTORY_LEN);
+    }
+
+    /// URL of the proxy used for all connections.  The `SOCKS_PROXY`
+    /// environment variable takes precedence over the preferences.
+    pub fn proxy_url(&self) -> Option<String> {
+        Self::proxy_from_env().or_else(|| self.proxy.url())
+    }
+
+    pub fn proxy_from_env() -> Option<String> {
         env::var(PROXY_ENV_VAR).map_or_else(
             |err| match err {
                 VarError::NotPresent => None,
@@ -310,18 +736,334 @@ impl Config {
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
 pub enum AudioQuality {
+    /// Steps between the lossy tiers by the measured bandwidth.
+    Auto,
     Low,
     Normal,
     #[default]
     High,
+    Lossless,
 }
 
 impl AudioQuality {
-    fn as_bitrate(self) -> usize {
+    /// Preferred tier, or the highest one for `Auto`.
+    pub fn as_tier(self) -> QualityTier {
+        match self {
+            AudioQuality::Low => QualityTier::Low,
+            AudioQuality::Normal => QualityTier::Normal,
+            AudioQuality::Auto | AudioQuality::High => QualityTier::High,
+            AudioQuality::Lossless => QualityTier::Lossless,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum Crossfade {
+    #[default]
+    Off,
+    Short,
+    Medium,
+    Long,
+}
+
+impl Crossfade {
+    /// Longest fade in and out of a track.
+    pub fn as_duration(self) -> Option<Duration> {
+        match self {
+            Crossfade::Off => None,
+            Crossfade::Short => Some(Duration::from_secs(3)),
+            Crossfade::Medium => Some(Duration::from_secs(6)),
+            Crossfade::Long => Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum MeteredBehavior {
+    /// Stream as usual.
+    #[default]
+    Ignore,
+    /// Ask for a confirmation before starting playback.
+    Warn,
+    /// Stream in low quality.
+    DataSaver,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum ProxyKind {
+    #[default]
+    None,
+    Http,
+    Socks5,
+}
+
+#[derive(Clone, Debug, Default, Data, Lens, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// Kept in the keychain with the other secrets when it is available.
+    pub password: String,
+}
+
+impl ProxySettings {
+    /// URL of the proxy, or `None` when connecting directly or when the
+    /// host or port are missing.
+    pub fn url(&self) -> Option<String> {
+        let scheme = match self.kind {
+            ProxyKind::None => return None,
+            ProxyKind::Http => "http",
+            ProxyKind::Socks5 => "socks5",
+        };
+        let host = self.host.trim();
+        if host.is_empty() || self.port == 0 {
+            return None;
+        }
+        let mut url = Url::parse(&format!("{scheme}://{host}:{}", self.port)).ok()?;
+        if !self.username.is_empty() {
+            // Percent-encodes the credentials.
+            url.set_username(&self.username).ok()?;
+            url.set_password(Some(&self.password)).ok()?;
+        }
+        Some(url.as_str().trim_end_matches('/').to_string())
+    }
+}
+
+/// Single setting that shuffle and repeat were chosen with, before they
+/// became separate settings.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+enum LegacyQueueBehavior {
+    Sequential,
+    Random,
+    SmartShuffle,
+    LoopTrack,
+    LoopAll,
+}
+
+impl LegacyQueueBehavior {
+    fn split(self) -> (Shuffle, Repeat) {
+        match self {
+            Self::Sequential => (Shuffle::Off, Repeat::Off),
+            Self::Random => (Shuffle::Random, Repeat::Off),
+            Self::SmartShuffle => (Shuffle::Smart, Repeat::Off),
+            Self::LoopTrack => (Shuffle::Off, Repeat::One),
+            Self::LoopAll => (Shuffle::Off, Repeat::All),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum PlaybackBarLayout {
+    /// Artwork, the item on up to three lines, and all the playback controls.
+    #[default]
+    Expanded,
+    /// A single row with the item and the basic controls.
+    Compact,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum PresenceIdle {
+    /// Remove the presence.
+    #[default]
+    Clear,
+    /// Replace the paused track with an "Idle" status.
+    Idle,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum ScrobbleAfter {
+    #[default]
+    Percent,
+    Seconds,
+}
+
+#[derive(Clone, Debug, Data, Lens, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrobbleSettings {
+    pub after: ScrobbleAfter,
+    pub percent: usize,
+    pub seconds: usize,
+    /// Report the playing track to Last.fm as "now playing".
+    pub now_playing: bool,
+}
+
+impl Default for ScrobbleSettings {
+    fn default() -> Self {
+        Self {
+            after: ScrobbleAfter::Percent,
+            percent: 50,
+            seconds: 240,
+            now_playing: true,
+        }
+    }
+}
+
+impl ScrobbleSettings {
+    /// Position at which a track of `duration` is scrobbled.  A fixed time
+    /// longer than half of the track is cut to the half, as Last.fm does.
+    pub fn threshold(&self, duration: Duration) -> Duration {
+        match self.after {
+            ScrobbleAfter::Percent => duration * self.percent.clamp(1, 100) as u32 / 100,
+            ScrobbleAfter::Seconds => Duration::from_secs(self.seconds as u64).min(duration / 2),
+        }
+    }
+}
+
+/// Something the app lets the user know about, with an optional sound.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data)]
+pub enum NotificationEvent {
+    TrackChange,
+    DownloadComplete,
+    Error,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum NotificationSound {
+    #[default]
+    None,
+    Ping,
+    Rising,
+    Falling,
+}
+
+impl NotificationSound {
+    pub fn chime(self) -> Option<Chime> {
         match self {
-            AudioQuality::Low => 96,
-            AudioQuality::Normal => 160,
-            AudioQuality::High => 320,
+            NotificationSound::None => None,
+            NotificationSound::Ping => Some(Chime::Ping),
+            NotificationSound::Rising => Some(Chime::Rising),
+            NotificationSound::Falling => Some(Chime::Falling),
+        }
+    }
+}
+
+/// Time of the day, in minutes after midnight, written like "22:30".
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Data, Serialize, Deserialize)]
+pub struct TimeOfDay(pub u16);
+
+impl TimeOfDay {
+    pub const fn new(hour: u16, minute: u16) -> Self {
+        Self(hour * 60 + minute)
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = InvalidTimeOfDay;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (hour, minute) = s.trim().split_once(':').ok_or(InvalidTimeOfDay)?;
+        let hour: u16 = hour.parse().map_err(|_| InvalidTimeOfDay)?;
+        let minute: u16 = minute.parse().map_err(|_| InvalidTimeOfDay)?;
+        if hour > 23 || minute > 59 {
+            return Err(InvalidTimeOfDay);
+        }
+        Ok(Self::new(hour, minute))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidTimeOfDay;
+
+impl fmt::Display for InvalidTimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("expected a time like 22:30")
+    }
+}
+
+impl std::error::Error for InvalidTimeOfDay {}
+
+#[derive(Clone, Debug, Data, Lens, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub track_change_sound: NotificationSound,
+    pub download_complete_sound: NotificationSound,
+    pub error_sound: NotificationSound,
+    /// Keep quiet from `quiet_from` until `quiet_until`, with no sounds and
+    /// no alerts about what happened in the background.
+    pub quiet_hours: bool,
+    pub quiet_from: TimeOfDay,
+    pub quiet_until: TimeOfDay,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            track_change_sound: NotificationSound::None,
+            download_complete_sound: NotificationSound::None,
+            error_sound: NotificationSound::None,
+            quiet_hours: false,
+            quiet_from: TimeOfDay::new(22, 0),
+            quiet_until: TimeOfDay::new(7, 0),
+        }
+    }
+}
+
+impl NotificationSettings {
+    pub fn sound(&self, event: NotificationEvent) -> NotificationSound {
+        match event {
+            NotificationEvent::TrackChange => self.track_change_sound,
+            NotificationEvent::DownloadComplete => self.download_complete_sound,
+            NotificationEvent::Error => self.error_sound,
+        }
+    }
+
+    /// Are the quiet hours on at `time`.  They can run past midnight.
+    pub fn is_quiet_at(&self, time: TimeOfDay) -> bool {
+        if !self.quiet_hours {
+            return false;
+        }
+        if self.quiet_from <= self.quiet_until {
+            self.quiet_from <= time && time < self.quiet_until
+        } else {
+            self.quiet_from <= time || time < self.quiet_until
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum InterruptionBehavior {
+    #[default]
+    KeepPlaying,
+    Pause,
+    /// Pause, and resume once the other application is quiet.
+    PauseAndResume,
+}
+
+impl InterruptionBehavior {
+    pub fn as_policy(self) -> InterruptionPolicy {
+        match self {
+            InterruptionBehavior::KeepPlaying => InterruptionPolicy::Ignore,
+            InterruptionBehavior::Pause => InterruptionPolicy::Pause,
+            InterruptionBehavior::PauseAndResume => InterruptionPolicy::PauseAndResume,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum OutputLatency {
+    #[default]
+    Default,
+    Low,
+    Medium,
+    High,
+}
+
+impl OutputLatency {
+    /// Requested size of the output buffer, `None` leaves it to the audio backend.
+    pub fn as_duration(self) -> Option<Duration> {
+        match self {
+            OutputLatency::Default => None,
+            OutputLatency::Low => Some(Duration::from_millis(20)),
+            OutputLatency::Medium => Some(Duration::from_millis(100)),
+            OutputLatency::High => Some(Duration::from_millis(500)),
         }
     }
 }
@@ -434,35 +1176,6 @@ pub enum Theme {
     Custom,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
-pub enum SortOrder {
-    #[default]
-    Ascending,
-    Descending,
-}
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
-pub enum SortCriteria {
-    Title,
-    Artist,
-    Album,
-    Duration,
-    #[default]
-    DateAdded,
-}
-
-fn get_dir_size(path: &Path) -> Option<u64> {
-    fs::read_dir(path).ok()?.try_fold(0, |acc, entry| {
-        let entry = entry.ok()?;
-        let size = if entry.file_type().ok()?.is_dir() {
-            get_dir_size(&entry.path())?
-        } else {
-            entry.metadata().ok()?.len()
-        };
-        Some(acc + size)
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -545,4 +1258,141 @@ mod tests {
         assert_eq!(theme.font_family, "System UI");
         assert_eq!(theme.font_size, "13.0");
     }
+
+    #[test]
+    fn test_scrobble_threshold() {
+        let track = Duration::from_secs(600);
+        let mut settings = ScrobbleSettings::default();
+        assert_eq!(settings.threshold(track), Duration::from_secs(300));
+
+        settings.percent = 90;
+        assert_eq!(settings.threshold(track), Duration::from_secs(540));
+
+        settings.after = ScrobbleAfter::Seconds;
+        settings.seconds = 30;
+        assert_eq!(settings.threshold(track), Duration::from_secs(30));
+
+        // Never later than half of a short track.
+        assert_eq!(
+            settings.threshold(Duration::from_secs(40)),
+            Duration::from_secs(20)
+        );
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let mut settings = NotificationSettings {
+            quiet_hours: true,
+            ..Default::default()
+        };
+        // From 22:00 until 07:00, past midnight.
+        assert!(settings.is_quiet_at(TimeOfDay::new(23, 30)));
+        assert!(settings.is_quiet_at(TimeOfDay::new(0, 0)));
+        assert!(settings.is_quiet_at(TimeOfDay::new(6, 59)));
+        assert!(!settings.is_quiet_at(TimeOfDay::new(7, 0)));
+        assert!(!settings.is_quiet_at(TimeOfDay::new(12, 0)));
+
+        settings.quiet_from = TimeOfDay::new(13, 0);
+        settings.quiet_until = TimeOfDay::new(14, 0);
+        assert!(settings.is_quiet_at(TimeOfDay::new(13, 30)));
+        assert!(!settings.is_quiet_at(TimeOfDay::new(14, 30)));
+
+        settings.quiet_hours = false;
+        assert!(!settings.is_quiet_at(TimeOfDay::new(13, 30)));
+    }
+
+    #[test]
+    fn test_time_of_day_parsing() {
+        assert_eq!("22:30".parse(), Ok(TimeOfDay::new(22, 30)));
+        assert_eq!(" 7:05 ".parse(), Ok(TimeOfDay::new(7, 5)));
+        assert_eq!(TimeOfDay::new(7, 5).to_string(), "07:05");
+        assert!("24:00".parse::<TimeOfDay>().is_err());
+        assert!("12".parse::<TimeOfDay>().is_err());
+    }
+
+    #[test]
+    fn test_sort_migration() {
+        let mut config: Config =
+            serde_json::from_str(r#"{"sort_order": "Descending", "sort_criteria": "Title"}"#)
+                .unwrap();
+        config.migrate();
+        assert_eq!(
+            config.playlist_sort,
+            TableSort::new(TrackColumn::Title, SortOrder::Descending)
+        );
+        assert_eq!(config.saved_tracks_sort, TableSort::RECENTLY_SAVED);
+
+        // As written by default by older versions.
+        let mut config: Config = serde_json::from_str(
+            r#"{
+                "credentials": null,
+                "oauth_bearer": null,
+                "oauth_refresh_token": null,
+                "audio_quality": "High",
+                "theme": "Custom",
+                "volume": 1.0,
+                "last_route": null,
+                "queue_behavior": "Sequential",
+                "show_track_cover": false,
+                "show_playlist_images": true,
+                "window_size": {"width": 640.0, "height": 800.0},
+                "sort_order": "Ascending",
+                "sort_criteria": "DateAdded",
+                "paginated_limit": 500,
+                "seek_duration": 10,
+                "lastfm_session_key": null,
+                "lastfm_api_key": null,
+                "lastfm_api_secret": null,
+                "lastfm_enable": false,
+                "sidebar_visible": true
+            }"#,
+        )
+        .unwrap();
+        config.migrate();
+        assert_eq!(config.playlist_sort, TableSort::PLAYLIST_ORDER);
+        assert_eq!(config.saved_tracks_sort, TableSort::RECENTLY_SAVED);
+
+        let mut config: Config = serde_json::from_str("{}").unwrap();
+        config.migrate();
+        assert_eq!(config.playlist_sort, TableSort::PLAYLIST_ORDER);
+        assert_eq!(config.saved_tracks_sort, TableSort::RECENTLY_SAVED);
+    }
+
+    #[test]
+    fn test_queue_behavior_migration() {
+        let mut config: Config =
+            serde_json::from_str(r#"{"queue_behavior": "SmartShuffle"}"#).unwrap();
+        config.migrate();
+        assert_eq!(
+            (config.shuffle, config.repeat),
+            (Shuffle::Smart, Repeat::Off)
+        );
+
+        let mut config: Config = serde_json::from_str(r#"{"queue_behavior": "LoopAll"}"#).unwrap();
+        config.migrate();
+        assert_eq!((config.shuffle, config.repeat), (Shuffle::Off, Repeat::All));
+
+        let saved = serde_json::to_value(&config).unwrap();
+        assert!(saved.get("queue_behavior").is_none());
+        assert_eq!(saved["repeat"], "All");
+    }
+
+    #[test]
+    fn test_search_history() {
+        let mut config = Config::default();
+        config.add_search("daft punk");
+        config.add_search("  ");
+        config.add_search("Radiohead ");
+        config.add_search("Daft Punk");
+        assert_eq!(
+            config.search_history,
+            Vector::from(vec![Arc::from("Daft Punk"), Arc::from("Radiohead")])
+        );
+
+        for index in 0..SEARCH_HISTORY_LEN + 5 {
+            config.add_search(&index.to_string());
+        }
+        assert_eq!(config.search_history.len(), SEARCH_HISTORY_LEN);
+        assert_eq!(&*config.search_history[0], "24");
+    }
 }
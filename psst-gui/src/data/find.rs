@@ -1,12 +1,19 @@
+use std::sync::Arc;
+
 use druid::{Data, Lens};
 use regex::{Regex, RegexBuilder};
 
+use crate::data::Ctx;
+
 #[derive(Clone, Default, Debug, Data, Lens)]
 pub struct Finder {
     pub focused_result: usize,
     pub results: usize,
     pub show: bool,
     pub query: String,
+    /// Hide the items that do not match, instead of only highlighting the
+    /// ones that do.
+    pub filter: bool,
 }
 
 impl Finder {
@@ -38,7 +45,7 @@ impl Finder {
     }
 
     pub fn focus_next(&mut self) {
-        self.focused_result = if self.focused_result < self.results - 1 {
+        self.focused_result = if self.focused_result + 1 < self.results {
             self.focused_result + 1
         } else {
             0
@@ -77,3 +84,15 @@ impl FindQuery {
 pub trait MatchFindQuery {
     fn matches_query(&self, query: &FindQuery) -> bool;
 }
+
+impl<T: MatchFindQuery> MatchFindQuery for Arc<T> {
+    fn matches_query(&self, query: &FindQuery) -> bool {
+        self.as_ref().matches_query(query)
+    }
+}
+
+impl<C, T: MatchFindQuery> MatchFindQuery for Ctx<C, T> {
+    fn matches_query(&self, query: &FindQuery) -> bool {
+        self.data.matches_query(query)
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use druid::{im::Vector, Data, Lens};
+use psst_core::history::{PlayHistory, TrackStats};
+use serde::{Deserialize, Serialize};
+
+use crate::{data::Track, error::Error, webapi::WebApi};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(7 * 24 * 60 * 60); // 1 week
+const MONTH: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const YEAR: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+const PLAYLIST_LENGTH: usize = 50;
+
+/// Playlists built from the locally recorded listening history.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data)]
+pub enum PlaylistGenerator {
+    TopThisMonth,
+    Rediscover,
+}
+
+impl PlaylistGenerator {
+    pub const ALL: [Self; 2] = [Self::TopThisMonth, Self::Rediscover];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TopThisMonth => "Your Top 50 This Month",
+            Self::Rediscover => "Rediscover",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::TopThisMonth => "Your most played tracks of the last 30 days.",
+            Self::Rediscover => "Tracks you used to play, but haven't heard in a year.",
+        }
+    }
+
+    pub fn tracks(self, history: &PlayHistory, now: u64) -> Vec<TrackStats> {
+        match self {
+            Self::TopThisMonth => {
+                history.top_tracks(now.saturating_sub(MONTH.as_secs()), PLAYLIST_LENGTH)
+            }
+            Self::Rediscover => history.rediscover(now, YEAR, PLAYLIST_LENGTH),
+        }
+    }
+}
+
+/// Tracks of a generated playlist, as shown on the history page.
+#[derive(Clone, Data, Lens)]
+pub struct GeneratedPlaylist {
+    pub generator: PlaylistGenerator,
+    pub tracks: Vector<Arc<Track>>,
+}
+
+#[derive(Clone, Debug, Default, Data, Lens, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratedPlaylists {
+    /// Whether to push the generated playlists to the Spotify account, weekly
+    /// and on demand.  They are only shown locally otherwise.
+    pub sync_to_spotify: bool,
+    /// Timestamp of the last sync (seconds since UNIX epoch)
+    pub last_sync_timestamp: u64,
+    /// Spotify playlists created for the generators, reused on every sync.
+    pub top_this_month_id: Option<String>,
+    pub rediscover_id: Option<String>,
+}
+
+impl GeneratedPlaylists {
+    /// Check if the playlists are due to be pushed again.
+    pub fn should_sync(&self) -> bool {
+        self.sync_to_spotify
+            && unix_now().saturating_sub(self.last_sync_timestamp) >= SYNC_INTERVAL.as_secs()
+    }
+
+    fn playlist_id(&mut self, generator: PlaylistGenerator) -> &mut Option<String> {
+        match generator {
+            PlaylistGenerator::TopThisMonth => &mut self.top_this_month_id,
+            PlaylistGenerator::Rediscover => &mut self.rediscover_id,
+        }
+    }
+
+    /// Regenerate all playlists and replace their tracks in the Spotify
+    /// account, creating the playlists that don't exist yet.  Returns the
+    /// updated state, to be stored in the config.  Only used if
+    /// `sync_to_spotify` is on.
+    pub fn sync(&self) -> Result<Self, Error> {
+        let webapi = WebApi::global();
+        let history = PlayHistory::global();
+        let now = unix_now();
+        let mut synced = self.clone();
+        let mut user_id = None;
+
+        for generator in PlaylistGenerator::ALL {
+            let uris: Vec<String> = generator
+                .tracks(&history, now)
+                .into_iter()
+                .map(|track| format!("spotify:track:{}", track.track_id))
+                .collect();
+            if uris.is_empty() {
+                continue;
+            }
+
+            let existing = synced.playlist_id(generator).clone();
+            let replaced = existing.is_some_and(|id| {
+                webapi
+                    .replace_playlist_tracks(&id, &uris)
+                    .inspect_err(|err| log::warn!("failed to update generated playlist: {err}"))
+                    .is_ok()
+            });
+            if !replaced {
+                // The playlist was never created, or the user has removed it.
+                if user_id.is_none() {
+                    user_id = Some(webapi.get_user_profile()?.id);
+                }
+                let playlist = webapi.create_playlist(
+                    user_id.as_deref().unwrap(),
+                    generator.name(),
+                    generator.description(),
+                )?;
+                webapi.replace_playlist_tracks(&playlist.id, &uris)?;
+                *synced.playlist_id(generator) = Some(playlist.id.to_string());
+            }
+        }
+
+        synced.last_sync_timestamp = now;
+        Ok(synced)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0))
+        .as_secs()
+}
@@ -0,0 +1,448 @@
+use std::{borrow::Cow, collections::HashMap, fmt};
+
+use druid::{Data, HotKey, KbKey, KeyEvent, RawMods};
+use serde::{de::IntoDeserializer, Deserialize, Deserializer, Serialize};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Data)]
+pub enum KeybindCategory {
+    Playback,
+    Navigation,
+    Application,
+}
+
+impl KeybindCategory {
+    pub const ALL: [Self; 3] = [Self::Playback, Self::Navigation, Self::Application];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Playback => "Playback",
+            Self::Navigation => "Navigation",
+            Self::Application => "Application",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Data, Serialize, Deserialize)]
+pub enum KeybindAction {
+    PlayPause,
+    SeekForward,
+    SeekBackward,
+    NextTrack,
+    PreviousTrack,
+    NextChapter,
+    PreviousChapter,
+    VolumeUp,
+    VolumeDown,
+    QueueFocusedRow,
+    Refresh,
+    GoHome,
+    GoSavedTracks,
+    GoSavedAlbums,
+    GoShows,
+    FocusSearch,
+    ToggleFinder,
+    ToggleNowPlaying,
+    ToggleMiniPlayer,
+    CommandPalette,
+    Quit,
+}
+
+impl KeybindAction {
+    pub const ALL: [Self; 21] = [
+        Self::PlayPause,
+        Self::SeekForward,
+        Self::SeekBackward,
+        Self::NextTrack,
+        Self::PreviousTrack,
+        Self::NextChapter,
+        Self::PreviousChapter,
+        Self::VolumeUp,
+        Self::VolumeDown,
+        Self::QueueFocusedRow,
+        Self::Refresh,
+        Self::GoHome,
+        Self::GoSavedTracks,
+        Self::GoSavedAlbums,
+        Self::GoShows,
+        Self::FocusSearch,
+        Self::ToggleFinder,
+        Self::ToggleNowPlaying,
+        Self::ToggleMiniPlayer,
+        Self::CommandPalette,
+        Self::Quit,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::PlayPause => "Play or pause",
+            Self::SeekForward => "Seek forward",
+            Self::SeekBackward => "Seek backward",
+            Self::NextTrack => "Next track",
+            Self::PreviousTrack => "Previous track",
+            Self::NextChapter => "Next chapter",
+            Self::PreviousChapter => "Previous chapter",
+            Self::VolumeUp => "Volume up",
+            Self::VolumeDown => "Volume down",
+            Self::QueueFocusedRow => "Queue the focused track",
+            Self::Refresh => "Reload the page",
+            Self::GoHome => "Home",
+            Self::GoSavedTracks => "Saved tracks",
+            Self::GoSavedAlbums => "Saved albums",
+            Self::GoShows => "Saved shows",
+            Self::FocusSearch => "Search",
+            Self::ToggleFinder => "Find in page",
+            Self::ToggleNowPlaying => "Now playing view",
+            Self::ToggleMiniPlayer => "Mini player",
+            Self::CommandPalette => "Command palette",
+            Self::Quit => "Quit Psst",
+        }
+    }
+
+    pub fn category(self) -> KeybindCategory {
+        match self {
+            Self::PlayPause
+            | Self::SeekForward
+            | Self::SeekBackward
+            | Self::NextTrack
+            | Self::PreviousTrack
+            | Self::NextChapter
+            | Self::PreviousChapter
+            | Self::VolumeUp
+            | Self::VolumeDown
+            | Self::QueueFocusedRow => KeybindCategory::Playback,
+            Self::Refresh
+            | Self::GoHome
+            | Self::GoSavedTracks
+            | Self::GoSavedAlbums
+            | Self::GoShows
+            | Self::FocusSearch
+            | Self::ToggleFinder
+            | Self::ToggleNowPlaying
+            | Self::ToggleMiniPlayer
+            | Self::CommandPalette => KeybindCategory::Navigation,
+            Self::Quit => KeybindCategory::Application,
+        }
+    }
+
+    pub fn default_combination(self) -> Option<KeyCombination> {
+        // The menu shortcuts use Cmd on macOS and Ctrl elsewhere.
+        let cmd = if cfg!(target_os = "macos") {
+            "Meta"
+        } else {
+            "Ctrl"
+        };
+        let combination: Cow<str> = match self {
+            Self::PlayPause => "Space".into(),
+            Self::SeekForward => "ArrowRight".into(),
+            Self::SeekBackward => "ArrowLeft".into(),
+            Self::NextTrack => "Shift+ArrowRight".into(),
+            Self::PreviousTrack => "Shift+ArrowLeft".into(),
+            Self::NextChapter => "Alt+ArrowRight".into(),
+            Self::PreviousChapter => "Alt+ArrowLeft".into(),
+            Self::VolumeUp => "+".into(),
+            Self::VolumeDown => "-".into(),
+            Self::QueueFocusedRow => "q".into(),
+            Self::Refresh => "Meta+r".into(),
+            Self::GoHome => format!("{cmd}+1").into(),
+            Self::GoSavedTracks => format!("{cmd}+2").into(),
+            Self::GoSavedAlbums => format!("{cmd}+3").into(),
+            Self::GoShows => format!("{cmd}+4").into(),
+            Self::FocusSearch => format!("{cmd}+l").into(),
+            Self::ToggleFinder => format!("{cmd}+f").into(),
+            Self::ToggleNowPlaying => "F11".into(),
+            Self::ToggleMiniPlayer => return None,
+            Self::CommandPalette => format!("{cmd}+k").into(),
+            Self::Quit => format!("{cmd}+q").into(),
+        };
+        combination.parse().ok()
+    }
+}
+
+/// Keys pressed together, like "Ctrl+Shift+ArrowRight".  Shift is not
+/// recorded for characters, as it already changes the character itself.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Data, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyCombination {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+    /// Name of the key, like "ArrowRight", or the typed character.
+    pub key: String,
+}
+
+impl KeyCombination {
+    /// Combination of a key press.  `None` for presses of only modifier keys,
+    /// and of keys that cannot be told apart.
+    pub fn from_key_event(event: &KeyEvent) -> Option<Self> {
+        let key = match &event.key {
+            KbKey::Character(c) if c == " " => "Space".to_string(),
+            KbKey::Character(c) => c.clone(),
+            KbKey::Shift
+            | KbKey::Control
+            | KbKey::Alt
+            | KbKey::AltGraph
+            | KbKey::Meta
+            | KbKey::Super
+            | KbKey::CapsLock
+            | KbKey::Unidentified
+            | KbKey::Dead => return None,
+            key => key.to_string(),
+        };
+        let shift = event.mods.shift() && !matches!(event.key, KbKey::Character(_));
+        Some(Self {
+            ctrl: event.mods.ctrl(),
+            alt: event.mods.alt(),
+            shift,
+            meta: event.mods.meta(),
+            key,
+        })
+    }
+
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        Self::from_key_event(event).as_ref() == Some(self)
+    }
+
+    /// The combination as a menu shortcut.
+    pub fn hotkey(&self) -> HotKey {
+        let mods = match (self.ctrl, self.alt, self.shift, self.meta) {
+            (false, false, false, false) => RawMods::None,
+            (true, false, false, false) => RawMods::Ctrl,
+            (false, true, false, false) => RawMods::Alt,
+            (false, false, true, false) => RawMods::Shift,
+            (false, false, false, true) => RawMods::Meta,
+            (true, true, false, false) => RawMods::AltCtrl,
+            (true, false, true, false) => RawMods::CtrlShift,
+            (true, false, false, true) => RawMods::CtrlMeta,
+            (false, true, true, false) => RawMods::AltShift,
+            (false, true, false, true) => RawMods::AltMeta,
+            (false, false, true, true) => RawMods::MetaShift,
+            (true, true, true, false) => RawMods::AltCtrlShift,
+            (true, true, false, true) => RawMods::AltCtrlMeta,
+            (true, false, true, true) => RawMods::CtrlMetaShift,
+            (false, true, true, true) => RawMods::AltMetaShift,
+            (true, true, true, true) => RawMods::AltCtrlMetaShift,
+        };
+        let key = match self.key.as_str() {
+            "Space" => KbKey::Character(" ".to_string()),
+            key => key
+                .parse()
+                .unwrap_or_else(|_| KbKey::Character(key.to_string())),
+        };
+        HotKey::new(mods, key)
+    }
+}
+
+impl fmt::Display for KeyCombination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "Ctrl"),
+            (self.alt, "Alt"),
+            (self.shift, "Shift"),
+            (self.meta, "Meta"),
+        ] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        f.write_str(&self.key)
+    }
+}
+
+impl std::str::FromStr for KeyCombination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut combination = Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            meta: false,
+            key: String::new(),
+        };
+        let mut rest = s;
+        loop {
+            let held = if let Some(r) = rest.strip_prefix("Ctrl+") {
+                rest = r;
+                &mut combination.ctrl
+            } else if let Some(r) = rest.strip_prefix("Alt+") {
+                rest = r;
+                &mut combination.alt
+            } else if let Some(r) = rest.strip_prefix("Shift+") {
+                rest = r;
+                &mut combination.shift
+            } else if let Some(r) = rest.strip_prefix("Meta+") {
+                rest = r;
+                &mut combination.meta
+            } else {
+                break;
+            };
+            *held = true;
+        }
+        if rest.is_empty() {
+            return Err(format!("missing key in {s:?}"));
+        }
+        combination.key = rest.to_string();
+        Ok(combination)
+    }
+}
+
+impl TryFrom<String> for KeyCombination {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<KeyCombination> for String {
+    fn from(combination: KeyCombination) -> Self {
+        combination.to_string()
+    }
+}
+
+/// Key combinations of the actions, as changes to their defaults.  `None`
+/// leaves an action without a combination.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Keybinds {
+    changed: HashMap<KeybindAction, Option<KeyCombination>>,
+}
+
+/// Changes of actions that were renamed or removed, or of combinations that
+/// cannot be read, are dropped instead of failing the whole config, and the
+/// actions get their defaults back.
+impl<'de> Deserialize<'de> for Keybinds {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = HashMap::<String, Option<String>>::deserialize(deserializer)?;
+        let mut changed = HashMap::new();
+        for (name, combination) in stored {
+            let action = KeybindAction::deserialize(name.as_str().into_deserializer())
+                .map_err(|err: serde::de::value::Error| err.to_string());
+            let combination = combination.map(|c| c.parse::<KeyCombination>()).transpose();
+            match (action, combination) {
+                (Ok(action), Ok(combination)) => {
+                    changed.insert(action, combination);
+                }
+                (Err(err), _) | (_, Err(err)) => {
+                    log::warn!("dropping the keybind of {name}: {err}");
+                }
+            }
+        }
+        Ok(Self { changed })
+    }
+}
+
+impl Keybinds {
+    pub fn get(&self, action: KeybindAction) -> Option<KeyCombination> {
+        match self.changed.get(&action) {
+            Some(combination) => combination.clone(),
+            None => action.default_combination(),
+        }
+    }
+
+    pub fn set(&mut self, action: KeybindAction, combination: Option<KeyCombination>) {
+        if combination == action.default_combination() {
+            self.changed.remove(&action);
+        } else {
+            self.changed.insert(action, combination);
+        }
+    }
+
+    pub fn is_changed(&self, action: KeybindAction) -> bool {
+        self.changed.contains_key(&action)
+    }
+
+    pub fn reset(&mut self, action: KeybindAction) {
+        self.changed.remove(&action);
+    }
+
+    pub fn reset_all(&mut self) {
+        self.changed.clear();
+    }
+
+    /// Another action bound to the same combination as `action`.
+    pub fn conflicting_action(&self, action: KeybindAction) -> Option<KeybindAction> {
+        let combination = self.get(action)?;
+        KeybindAction::ALL
+            .into_iter()
+            .find(|&other| other != action && self.get(other).as_ref() == Some(&combination))
+    }
+
+    /// Action bound to the pressed keys.
+    pub fn action_for(&self, event: &KeyEvent) -> Option<KeybindAction> {
+        let combination = KeyCombination::from_key_event(event)?;
+        KeybindAction::ALL
+            .into_iter()
+            .find(|&action| self.get(action).as_ref() == Some(&combination))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid::Modifiers;
+
+    use super::*;
+
+    #[test]
+    fn combinations_round_trip() {
+        for text in ["Space", "Ctrl+Shift+ArrowRight", "Meta+r", "+", "Ctrl++"] {
+            let combination: KeyCombination = text.parse().unwrap();
+            assert_eq!(combination.to_string(), text);
+        }
+        assert!("Ctrl+".parse::<KeyCombination>().is_err());
+    }
+
+    #[test]
+    fn shift_is_part_of_characters() {
+        let plus = KeyEvent::for_test(Modifiers::SHIFT, "+");
+        assert_eq!(
+            KeyCombination::from_key_event(&plus),
+            Some("+".parse().unwrap())
+        );
+        let right = KeyEvent::for_test(Modifiers::SHIFT, KbKey::ArrowRight);
+        assert_eq!(
+            Keybinds::default().action_for(&right),
+            Some(KeybindAction::NextTrack)
+        );
+        let shift = KeyEvent::for_test(Modifiers::SHIFT, KbKey::Shift);
+        assert_eq!(KeyCombination::from_key_event(&shift), None);
+    }
+
+    #[test]
+    fn changes_are_kept_apart_from_defaults() {
+        let mut keybinds = Keybinds::default();
+        keybinds.set(KeybindAction::VolumeUp, Some("ArrowRight".parse().unwrap()));
+        assert!(keybinds.is_changed(KeybindAction::VolumeUp));
+        assert_eq!(
+            keybinds.conflicting_action(KeybindAction::VolumeUp),
+            Some(KeybindAction::SeekForward)
+        );
+
+        keybinds.set(KeybindAction::VolumeUp, "+".parse().ok());
+        assert!(!keybinds.is_changed(KeybindAction::VolumeUp));
+        assert_eq!(keybinds.conflicting_action(KeybindAction::VolumeUp), None);
+
+        keybinds.set(KeybindAction::PlayPause, None);
+        assert_eq!(keybinds.get(KeybindAction::PlayPause), None);
+        keybinds.reset_all();
+        assert_eq!(keybinds, Keybinds::default());
+    }
+
+    #[test]
+    fn unreadable_changes_are_dropped() {
+        let keybinds: Keybinds = serde_json::from_str(
+            r#"{"VolumeUp": "Ctrl+ArrowUp", "PlayPause": null, "Shuffle": "s", "Refresh": "Ctrl+"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            keybinds.get(KeybindAction::VolumeUp),
+            "Ctrl+ArrowUp".parse().ok()
+        );
+        assert_eq!(keybinds.get(KeybindAction::PlayPause), None);
+        assert!(!keybinds.is_changed(KeybindAction::Refresh));
+
+        let saved = serde_json::to_string(&keybinds).unwrap();
+        assert_eq!(serde_json::from_str::<Keybinds>(&saved).unwrap(), keybinds);
+    }
+}
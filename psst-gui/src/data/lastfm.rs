@@ -0,0 +1,57 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use druid::{Data, Lens};
+use psst_core::lastfm;
+
+/// Last.fm user whose friends are shown on the home page, with the API key to
+/// ask for them.
+#[derive(Clone, Debug, Data)]
+pub struct LastFmUser {
+    pub api_key: Arc<str>,
+    pub username: Arc<str>,
+}
+
+/// The latest track of a Last.fm friend.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct FriendTrack {
+    pub friend: Arc<str>,
+    pub artist: Arc<str>,
+    pub title: Arc<str>,
+    pub album: Option<Arc<str>>,
+    pub now_playing: bool,
+    pub played_at: Option<u64>,
+}
+
+impl FriendTrack {
+    /// Query finding the same track on Spotify.
+    pub fn search_query(&self) -> Arc<str> {
+        format!("{} {}", self.title, self.artist).into()
+    }
+
+    /// Time since the track was scrobbled, `None` while it is playing.
+    pub fn played_ago(&self) -> Option<Duration> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.played_at
+            .filter(|_| !self.now_playing)
+            .map(|played_at| Duration::from_secs(now.saturating_sub(played_at)))
+    }
+}
+
+impl From<lastfm::FriendTrack> for FriendTrack {
+    fn from(track: lastfm::FriendTrack) -> Self {
+        Self {
+            friend: track.friend.into(),
+            artist: track.artist.into(),
+            title: track.title.into(),
+            album: track.album.map(Into::into),
+            now_playing: track.now_playing,
+            played_at: track.played_at,
+        }
+    }
+}
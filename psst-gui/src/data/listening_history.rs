@@ -0,0 +1,166 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use druid::{im::Vector, Data, Lens};
+use psst_core::{
+    history::PlayHistory,
+    item_id::{ItemId, ItemIdType},
+};
+use time::{Date, OffsetDateTime};
+
+use crate::{
+    data::{AlbumLink, ArtistLink, GeneratedPlaylist, PlaylistGenerator, Track, TrackId},
+    error::Error,
+    format, notifications,
+    webapi::WebApi,
+};
+
+/// Number of the most recent plays shown on the history page.
+const PLAYS_SHOWN: usize = 500;
+
+/// Recent plays of the local listening history, the most recent first, and
+/// the playlists generated from it.
+#[derive(Clone, Default, Data, Lens)]
+pub struct ListeningHistory {
+    /// Generated playlists that have any tracks, in the order of
+    /// `PlaylistGenerator::ALL`.
+    pub generated: Vector<GeneratedPlaylist>,
+    pub plays: Vector<HistoryPlay>,
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct HistoryPlay {
+    pub track: Arc<Track>,
+    /// Local date of the play.
+    #[data(same_fn = "PartialEq::eq")]
+    pub date: Date,
+}
+
+impl ListeningHistory {
+    /// Load the tracks of the most recent plays and of the generated
+    /// playlists.  Tracks that cannot be loaded, e.g. while offline, are shown
+    /// with the names stored in the history.
+    pub fn load() -> Result<Self, Error> {
+        let history = PlayHistory::global();
+        let records: Vec<_> = history
+            .records()
+            .into_iter()
+            .rev()
+            .take(PLAYS_SHOWN)
+            .collect();
+        let now = OffsetDateTime::now_utc().unix_timestamp() as u64;
+        let generated: Vec<_> = PlaylistGenerator::ALL
+            .into_iter()
+            .map(|generator| (generator, generator.tracks(&history, now)))
+            .filter(|(_, tracks)| !tracks.is_empty())
+            .collect();
+        let mut ids: Vec<&str> = records
+            .iter()
+            .map(|record| record.track_id.as_str())
+            .chain(
+                generated
+                    .iter()
+                    .flat_map(|(_, tracks)| tracks.iter().map(|track| track.track_id.as_str())),
+            )
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let tracks: HashMap<String, Arc<Track>> = WebApi::global()
+            .get_tracks(&ids)
+            .unwrap_or_else(|err| {
+                log::warn!("failed to load the tracks of the history: {err}");
+                Vector::new()
+            })
+            .into_iter()
+            .map(|track| (track.id.0.to_base62(), track))
+            .collect();
+        let plays = records
+            .iter()
+            .filter_map(|record| {
+                let track = match tracks.get(&record.track_id) {
+                    Some(track) => track.clone(),
+                    None => stored_track(
+                        &record.track_id,
+                        &record.name,
+                        &record.artist,
+                        &record.album,
+                    )?,
+                };
+                Some(HistoryPlay {
+                    track,
+                    date: notifications::local_date(record.played_at),
+                })
+            })
+            .collect();
+        let generated = generated
+            .into_iter()
+            .map(|(generator, stats)| GeneratedPlaylist {
+                generator,
+                tracks: stats
+                    .iter()
+                    .filter_map(|stats| match tracks.get(&stats.track_id) {
+                        Some(track) => Some(track.clone()),
+                        None => stored_track(&stats.track_id, &stats.name, &stats.artist, ""),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(Self { generated, plays })
+    }
+
+    /// Title of the day of the play at `position`, if it is the latest play
+    /// of that day.
+    pub fn day_header(&self, position: usize) -> Option<Arc<str>> {
+        let date = self.plays.get(position)?.date;
+        let previous = position
+            .checked_sub(1)
+            .and_then(|previous| self.plays.get(previous));
+        if previous.is_some_and(|previous| previous.date == date) {
+            return None;
+        }
+        let today = notifications::local_date(OffsetDateTime::now_utc().unix_timestamp() as u64);
+        let title = if date == today {
+            "Today".to_string()
+        } else if date.next_day() == Some(today) {
+            "Yesterday".to_string()
+        } else {
+            format::date(date)
+        };
+        Some(title.into())
+    }
+}
+
+/// Track with only the names stored in the history, which have no Spotify
+/// IDs to link to.
+fn stored_track(track_id: &str, name: &str, artist: &str, album: &str) -> Option<Arc<Track>> {
+    let id = ItemId::from_base62(track_id, ItemIdType::Track)?;
+    Some(Arc::new(Track {
+        id: TrackId(id),
+        name: name.into(),
+        album: (!album.is_empty()).then(|| AlbumLink {
+            id: "null".into(),
+            name: album.into(),
+            images: Vector::new(),
+        }),
+        artists: (!artist.is_empty())
+            .then(|| ArtistLink {
+                id: "null".into(),
+                name: artist.into(),
+            })
+            .into_iter()
+            .collect(),
+        duration: Duration::ZERO,
+        disc_number: 1,
+        track_number: 0,
+        explicit: false,
+        is_local: false,
+        local_path: None,
+        is_playable: None,
+        restrictions: None,
+        external_ids: Default::default(),
+        popularity: None,
+        track_pos: 0,
+        lyrics: None,
+        added_at: None,
+        added_by: None,
+    }))
+}
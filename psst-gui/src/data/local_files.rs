@@ -0,0 +1,61 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use druid::{im::Vector, Data, Lens};
+use psst_core::local::{self, LocalTrack};
+
+use crate::data::{AlbumLink, ArtistLink, Track, TrackId};
+
+/// Music found in the folders picked by the user.
+#[derive(Clone, Default, Data, Lens)]
+pub struct LocalFiles {
+    pub tracks: Vector<Arc<Track>>,
+}
+
+impl LocalFiles {
+    /// Scan `folders` for audio files.  This reads every file, so it should
+    /// not be called on the UI thread.
+    pub fn scan(folders: &Vector<String>) -> Self {
+        let folders: Vec<PathBuf> = folders.iter().map(PathBuf::from).collect();
+        let tracks = local::scan(&folders)
+            .into_iter()
+            .enumerate()
+            .map(|(position, track)| Arc::new(local_track(track, position)))
+            .collect();
+        Self { tracks }
+    }
+}
+
+fn local_track(track: LocalTrack, position: usize) -> Track {
+    Track {
+        id: TrackId(track.item_id()),
+        name: track.title.into(),
+        // Local artists and albums have no Spotify ID to link to.
+        album: track.album.map(|name| AlbumLink {
+            id: "null".into(),
+            name: name.into(),
+            images: Vector::new(),
+        }),
+        artists: track
+            .artist
+            .into_iter()
+            .map(|name| ArtistLink {
+                id: "null".into(),
+                name: name.into(),
+            })
+            .collect(),
+        duration: track.duration.unwrap_or(Duration::ZERO),
+        disc_number: 1,
+        track_number: track.track_number.unwrap_or(0) as usize,
+        explicit: false,
+        is_local: true,
+        local_path: Some(track.path.to_string_lossy().into()),
+        is_playable: Some(true),
+        restrictions: None,
+        external_ids: Default::default(),
+        popularity: None,
+        track_pos: position,
+        lyrics: None,
+        added_at: None,
+        added_by: None,
+    }
+}
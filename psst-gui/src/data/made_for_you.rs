@@ -0,0 +1,184 @@
+use std::time::{Duration, SystemTime};
+
+use druid::{im::Vector, Data, Lens};
+
+use crate::data::Playlist;
+
+/// Daily Mixes change daily and the other mixes weekly, the section is loaded
+/// again once it is older than this.
+pub const MADE_FOR_YOU_REFRESH: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Kinds of the personalized playlists Spotify makes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MixKind {
+    DailyMix,
+    DiscoverWeekly,
+    ReleaseRadar,
+    Blend,
+    Other,
+}
+
+impl MixKind {
+    pub const ALL: [Self; 5] = [
+        Self::DailyMix,
+        Self::DiscoverWeekly,
+        Self::ReleaseRadar,
+        Self::Blend,
+        Self::Other,
+    ];
+
+    /// Kind of `playlist`, told by its name and description, as the Made For
+    /// You section does not say.  Only the English names are known.
+    pub fn of(playlist: &Playlist) -> Self {
+        let name = playlist.name.trim();
+        if name.starts_with("Daily Mix") {
+            Self::DailyMix
+        } else if name == "Discover Weekly" {
+            Self::DiscoverWeekly
+        } else if name == "Release Radar" {
+            Self::ReleaseRadar
+        } else if name.contains(" + ") || playlist.description.to_lowercase().contains("blend") {
+            Self::Blend
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::DailyMix => "Daily Mixes",
+            Self::DiscoverWeekly => "Discover Weekly",
+            Self::ReleaseRadar => "Release Radar",
+            Self::Blend => "Blends",
+            Self::Other => "More Made For You",
+        }
+    }
+}
+
+/// Playlists of the Made For You section, in a row for each kind.
+#[derive(Clone, Data, Lens)]
+pub struct MadeForYou {
+    pub daily_mixes: Vector<Playlist>,
+    pub discover_weekly: Vector<Playlist>,
+    pub release_radar: Vector<Playlist>,
+    pub blends: Vector<Playlist>,
+    pub other: Vector<Playlist>,
+    #[data(ignore)]
+    pub loaded_at: SystemTime,
+}
+
+impl MadeForYou {
+    /// Sort `playlists` into their rows.  With the time the section was
+    /// `cached_at`, it counts as loaded then, and not now.
+    pub fn new(
+        playlists: impl IntoIterator<Item = Playlist>,
+        cached_at: Option<SystemTime>,
+    ) -> Self {
+        let mut made_for_you = Self {
+            daily_mixes: Vector::new(),
+            discover_weekly: Vector::new(),
+            release_radar: Vector::new(),
+            blends: Vector::new(),
+            other: Vector::new(),
+            loaded_at: cached_at.unwrap_or_else(SystemTime::now),
+        };
+        for playlist in playlists {
+            made_for_you
+                .row_mut(MixKind::of(&playlist))
+                .push_back(playlist);
+        }
+        made_for_you
+    }
+
+    pub fn row(&self, kind: MixKind) -> &Vector<Playlist> {
+        match kind {
+            MixKind::DailyMix => &self.daily_mixes,
+            MixKind::DiscoverWeekly => &self.discover_weekly,
+            MixKind::ReleaseRadar => &self.release_radar,
+            MixKind::Blend => &self.blends,
+            MixKind::Other => &self.other,
+        }
+    }
+
+    fn row_mut(&mut self, kind: MixKind) -> &mut Vector<Playlist> {
+        match kind {
+            MixKind::DailyMix => &mut self.daily_mixes,
+            MixKind::DiscoverWeekly => &mut self.discover_weekly,
+            MixKind::ReleaseRadar => &mut self.release_radar,
+            MixKind::Blend => &mut self.blends,
+            MixKind::Other => &mut self.other,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        MixKind::ALL
+            .into_iter()
+            .all(|kind| self.row(kind).is_empty())
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.loaded_at.elapsed().unwrap_or_default() >= MADE_FOR_YOU_REFRESH
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::data::PublicUser;
+
+    fn playlist(name: &str, description: &str) -> Playlist {
+        Playlist {
+            id: Arc::from(name),
+            name: Arc::from(name),
+            images: None,
+            description: Arc::from(description),
+            track_count: None,
+            owner: PublicUser {
+                display_name: "Spotify".into(),
+                id: "spotify".into(),
+            },
+            collaborative: false,
+            public: None,
+            snapshot_id: "".into(),
+        }
+    }
+
+    #[test]
+    fn playlists_are_sorted_into_rows_by_kind() {
+        let made_for_you = MadeForYou::new(
+            [
+                playlist("Daily Mix 2", "Radiohead, Portishead and more"),
+                playlist("Discover Weekly", "Your weekly mixtape of fresh music."),
+                playlist("Daily Mix 1", "Daft Punk, Justice and more"),
+                playlist("Alice + Bob", ""),
+                playlist("Weekend", "A blend of music for you and Carol."),
+                playlist("Release Radar", "Catch all the latest music."),
+                playlist("On Repeat", "Songs you can't get enough of."),
+            ],
+            None,
+        );
+        let names = |kind| -> Vec<String> {
+            made_for_you
+                .row(kind)
+                .iter()
+                .map(|playlist| playlist.name.to_string())
+                .collect()
+        };
+        assert_eq!(names(MixKind::DailyMix), ["Daily Mix 2", "Daily Mix 1"]);
+        assert_eq!(names(MixKind::DiscoverWeekly), ["Discover Weekly"]);
+        assert_eq!(names(MixKind::ReleaseRadar), ["Release Radar"]);
+        assert_eq!(names(MixKind::Blend), ["Alice + Bob", "Weekend"]);
+        assert_eq!(names(MixKind::Other), ["On Repeat"]);
+        assert!(!made_for_you.is_stale());
+        assert!(MadeForYou::new([], None).is_empty());
+    }
+
+    #[test]
+    fn cached_sections_age_from_the_cache() {
+        let cached_at = |age| Some(SystemTime::now() - age);
+        assert!(!MadeForYou::new([], cached_at(Duration::from_secs(60))).is_stale());
+        assert!(MadeForYou::new([], cached_at(MADE_FOR_YOU_REFRESH)).is_stale());
+    }
+}
@@ -1,10 +1,19 @@
 mod album;
 mod artist;
+mod browse;
 pub mod config;
 mod ctx;
 mod find;
+mod generated_playlists;
 mod id;
+mod keybinds;
+mod lastfm;
+mod listening_history;
+mod local_files;
+mod made_for_you;
 mod nav;
+mod paging;
+mod palette;
 mod playback;
 mod playlist;
 mod promise;
@@ -12,12 +21,15 @@ mod recommend;
 mod search;
 mod show;
 mod slider_scroll_scale;
+mod stats;
+mod table;
 mod track;
 mod update_checker;
 mod user;
 pub mod utils;
 
 use std::{
+    collections::HashSet as StdHashSet,
     fmt::Display,
     mem,
     sync::{
@@ -28,46 +40,76 @@ use std::{
 };
 
 use druid::{
-    im::{HashSet, Vector},
-    Data, Lens,
+    im::{HashMap, HashSet, Vector},
+    Command, Data, Lens,
 };
-use psst_core::{item_id::ItemId, session::SessionService};
+use psst_core::{
+    audio::stretch::Tempo,
+    item_id::{ItemId, ItemIdType},
+    player::PlaybackConfig,
+    session::{ConnectionState, SessionService},
+};
+use time::{Date, OffsetDateTime};
 
 pub use crate::data::{
-    album::{Album, AlbumDetail, AlbumLink, AlbumType},
+    album::{Album, AlbumDetail, AlbumLink, AlbumType, Copyright},
     artist::{
-        Artist, ArtistAlbums, ArtistDetail, ArtistInfo, ArtistLink, ArtistStats, ArtistTracks,
+        Artist, ArtistAlbums, ArtistDetail, ArtistInfo, ArtistLink, ArtistReleases, ArtistStats,
+        ArtistTracks,
     },
+    browse::{Browse, Category, CategoryLink, Feed},
     config::{
-        AudioQuality, Authentication, Config, CustomTheme, Preferences, PreferencesTab, Theme,
+        AudioQuality, Authentication, Config, Crossfade, CustomTheme, InterruptionBehavior,
+        MeteredBehavior, NotificationEvent, NotificationSettings, NotificationSound, OutputLatency,
+        PlaybackBarLayout, Preferences, PreferencesTab, PresenceIdle, ProxyKind, ProxySettings,
+        ScrobbleAfter, ScrobbleSettings, Theme, TimeOfDay,
     },
     ctx::Ctx,
     find::{FindQuery, Finder, MatchFindQuery},
+    generated_playlists::{GeneratedPlaylist, GeneratedPlaylists, PlaylistGenerator},
+    keybinds::{KeyCombination, KeybindAction, KeybindCategory, Keybinds},
+    lastfm::{FriendTrack, LastFmUser},
+    listening_history::{HistoryPlay, ListeningHistory},
+    local_files::LocalFiles,
+    made_for_you::{MadeForYou, MixKind, MADE_FOR_YOU_REFRESH},
     nav::{Nav, Route, SpotifyUrl},
+    paging::{PageInsert, PageWindow, PAGE_SIZE},
+    palette::{CommandPalette, PaletteEntry},
     playback::{
-        NowPlaying, Playable, PlayableMatcher, Playback, PlaybackOrigin, PlaybackPayload,
-        PlaybackState, QueueBehavior, QueueEntry,
+        ClockStats, ConnectDevice, NowPlaying, Playable, PlayableMatcher, Playback, PlaybackClock,
+        PlaybackOrigin, PlaybackPayload, PlaybackState, QueueEntry, QueuePlacement, RemoteCommand,
+        RemotePlayback, Repeat, Shuffle,
     },
     playlist::{
-        Playlist, PlaylistAddTrack, PlaylistDetail, PlaylistLink, PlaylistRemoveTrack,
-        PlaylistTracks,
+        NewPlaylist, OfflinePlaylist, OfflinePlaylists, Playlist, PlaylistAddTracks,
+        PlaylistDetail, PlaylistEdit, PlaylistLink, PlaylistMoveTrack, PlaylistRemoveTrack,
+        PlaylistTracks, PlaylistUndoAdd, PlaylistVisibility, SkipReview, SkippedTrack,
     },
     promise::{Promise, PromiseState},
     recommend::{
         Range, Recommend, Recommendations, RecommendationsKnobs, RecommendationsParams,
         RecommendationsRequest, Toggled,
     },
-    search::{Search, SearchResults, SearchTopic},
-    show::{Episode, EpisodeId, EpisodeLink, Show, ShowDetail, ShowEpisodes, ShowLink},
+    search::{Search, SearchEntry, SearchResults, SearchTopic},
+    show::{Chapter, Episode, EpisodeId, EpisodeLink, Show, ShowDetail, ShowEpisodes, ShowLink},
     slider_scroll_scale::SliderScrollScale,
-    track::{AudioAnalysis, Track, TrackId, TrackLines},
+    stats::{ListeningActivity, Stats, TimeRange},
+    table::{SortOrder, TableSort, TrackColumn, TrackFilters},
+    track::{
+        AudioAnalysis, AudioFeatures, RestrictionReason, Restrictions, Track, TrackId, TrackLines,
+    },
     update_checker::{
         UpdateInfo, UpdateInstallEvent, UpdateInstallPhase, UpdateInstaller, UpdatePreferences,
     },
-    user::{PublicUser, UserProfile},
+    user::{PublicUser, PublicUserProfile, UserProfile},
     utils::{Cached, Float64, Image, Page},
 };
-use crate::ui::credits::TrackCredits;
+use crate::{
+    discord::DiscordState,
+    format, notifications,
+    ui::credits::TrackCredits,
+    webapi::{ApiFeature, ApiStatus},
+};
 
 pub const ALERT_DURATION: Duration = Duration::from_secs(5);
 
@@ -75,8 +117,19 @@ pub const ALERT_DURATION: Duration = Duration::from_secs(5);
 pub struct AppState {
     #[data(ignore)]
     pub session: SessionService,
+    #[data(same_fn = "PartialEq::eq")]
+    pub connection_state: ConnectionState,
+    /// Went offline when the connection was lost, until it is back.  See
+    /// `Config::offline_mode` for staying offline.
+    pub lost_connection: bool,
+    #[data(same_fn = "PartialEq::eq")]
+    pub discord_state: DiscordState,
     pub nav: Nav,
     pub history: Vector<Nav>,
+    /// Scroll offsets of the pages in `history`, at the same indices.
+    pub history_scroll_offsets: Vector<f64>,
+    /// Vertical scroll offset of the current page.
+    pub scroll_offset: f64,
     pub config: Config,
     pub preferences: Preferences,
     pub playback: Playback,
@@ -85,16 +138,40 @@ pub struct AppState {
     pub album_detail: AlbumDetail,
     pub artist_detail: ArtistDetail,
     pub playlist_detail: PlaylistDetail,
+    /// Skipped tracks shown in the review window, see `SkipReview`.
+    pub skip_review: Option<SkipReview>,
     pub show_detail: ShowDetail,
     pub library: Arc<Library>,
     pub common_ctx: Arc<CommonCtx>,
     pub home_detail: HomeDetail,
+    pub browse: Browse,
+    pub stats: Stats,
     pub alerts: Vector<Alert>,
+    /// Background alerts held back during the quiet hours.
+    pub held_notifications: Vector<(NotificationEvent, Arc<str>)>,
     pub finder: Finder,
+    pub palette: CommandPalette,
     pub added_queue: Vector<QueueEntry>,
+    /// Tracks in the local folders, and the folders they were scanned from.
+    pub local_files: Promise<LocalFiles, Vector<String>>,
+    /// Recent plays, and the number of plays recorded when they were loaded.
+    pub listening_history: Promise<ListeningHistory, usize>,
     pub lyrics: Promise<Vector<TrackLines>>,
     pub lyrics_visible: bool,
+    pub queue_visible: bool,
+    /// Is the now-playing view open, in its own window.
+    pub now_playing_visible: bool,
     pub credits: Option<TrackCredits>,
+    /// Audio features of the track shown in the song info window.
+    pub audio_features: Promise<AudioFeatures, TrackId>,
+    /// Is the connection metered, `None` if the system does not tell.
+    pub network_metered: Option<bool>,
+    /// The user chose to stream over the current metered connection anyway.
+    pub metered_confirmed: bool,
+    /// Scrobbles waiting to be sent to Last.fm.
+    pub pending_scrobbles: usize,
+    /// Last known status of the optional Web API features used so far.
+    pub api_status: HashMap<ApiFeature, ApiStatus>,
 }
 
 impl AppState {
@@ -104,7 +181,14 @@ impl AppState {
             saved_albums: Promise::Empty,
             saved_tracks: Promise::Empty,
             saved_shows: Promise::Empty,
+            saved_episodes: Promise::Empty,
+            seen_episodes: config
+                .seen_episodes
+                .iter()
+                .map(|(id, count)| (id.clone(), *count))
+                .collect(),
             playlists: Promise::Empty,
+            lastfm_loved: config.lastfm_session_key.is_some().then(HashMap::new),
         });
         let common_ctx = Arc::new(CommonCtx {
             now_playing: None,
@@ -113,40 +197,81 @@ impl AppState {
             show_playlist_images: config.show_playlist_images,
             nav: Nav::Home,
             playback_progress: None,
+            dragged_track: None,
+            dragged_tracks: Vector::new(),
+            selected_tracks: Vector::new(),
+            offline_playlists: config.offline_playlists.clone(),
+            offline_items: None,
+            track_filters: TrackFilters::default(),
         });
         let playback = Playback {
             state: PlaybackState::Stopped,
             now_playing: None,
-            queue_behavior: config.queue_behavior,
+            shuffle: config.shuffle,
+            repeat: config.repeat,
             queue: Vector::new(),
+            up_next: Vector::new(),
+            autoplay_from: None,
             volume: config.volume,
+            podcast_speed: config.podcast_speed,
+            skip_silence: config.skip_silence,
+            remote: None,
+            output_device: None,
+            output_latency: None,
         };
         Self {
             session: SessionService::empty(),
+            connection_state: ConnectionState::Disconnected,
+            lost_connection: false,
+            discord_state: DiscordState::Disabled,
             nav: Nav::Home,
             history: Vector::new(),
+            history_scroll_offsets: Vector::new(),
+            scroll_offset: 0.0,
             config,
             preferences: Preferences {
                 active: PreferencesTab::General,
                 cache: None,
-                cache_size: Promise::Empty,
+                cache_usage: Promise::Empty,
+                cache_usage_progress: None,
+                cache_verification: Promise::Empty,
+                proxy_test: Promise::Empty,
                 auth: Authentication::new(),
                 lastfm_auth_result: None,
                 available_update: None,
                 checking_update: false,
                 installing_update: false,
                 update_install_status: None,
+                syncing_playlists: false,
+                capturing_keybind: None,
             },
             playback,
             added_queue: Vector::new(),
+            local_files: Promise::Empty,
+            listening_history: Promise::Empty,
             search: Search {
                 input: "".into(),
                 results: Promise::Empty,
+                suggestions: Promise::Empty,
+                dropdown: false,
+                selected: None,
             },
             recommend: Recommend {
                 knobs: Default::default(),
                 results: Promise::Empty,
             },
+            browse: Browse {
+                featured: Promise::Empty,
+                new_releases: Promise::Empty,
+                categories: Promise::Empty,
+                category: Promise::Empty,
+            },
+            stats: Stats {
+                range: TimeRange::ShortTerm,
+                top_tracks: Promise::Empty,
+                top_artists: Promise::Empty,
+                activity: ListeningActivity::default(),
+            },
             home_detail: HomeDetail {
                 made_for_you: Promise::Empty,
                 user_top_mixes: Promise::Empty,
@@ -158,6 +283,7 @@ impl AppState {
                 jump_back_in: Promise::Empty,
                 user_top_tracks: Promise::Empty,
                 user_top_artists: Promise::Empty,
+                lastfm_friends: Promise::Empty,
             },
             album_detail: AlbumDetail {
                 album: Promise::Empty,
@@ -168,22 +294,35 @@ impl AppState {
                 top_tracks: Promise::Empty,
                 related_artists: Promise::Empty,
                 artist_info: Promise::Empty,
+                following: Promise::Empty,
             },
             playlist_detail: PlaylistDetail {
                 playlist: Promise::Empty,
                 tracks: Promise::Empty,
+                edit: None,
             },
+            skip_review: None,
             show_detail: ShowDetail {
                 show: Promise::Empty,
                 episodes: Promise::Empty,
+                following: Promise::Empty,
             },
             library,
             common_ctx,
             alerts: Vector::new(),
+            held_notifications: Vector::new(),
             finder: Finder::new(),
+            palette: CommandPalette::default(),
             lyrics: Promise::Empty,
             lyrics_visible: false,
+            queue_visible: false,
+            now_playing_visible: false,
             credits: None,
+            audio_features: Promise::Empty,
+            network_metered: None,
+            metered_confirmed: false,
+            pending_scrobbles: 0,
+            api_status: HashMap::new(),
         }
     }
 }
@@ -192,19 +331,29 @@ impl AppState {
     pub fn navigate(&mut self, nav: &Nav) {
         if &self.nav != nav {
             let previous = mem::replace(&mut self.nav, nav.to_owned());
+            self.finder = Finder::new();
             self.history.push_back(previous);
+            self.history_scroll_offsets
+                .push_back(mem::take(&mut self.scroll_offset));
             self.config.last_route.replace(nav.to_owned());
-            Arc::make_mut(&mut self.common_ctx).nav = nav.to_owned();
+            let common_ctx = Arc::make_mut(&mut self.common_ctx);
+            common_ctx.nav = nav.to_owned();
+            common_ctx.track_filters = TrackFilters::default();
+            common_ctx.selected_tracks.clear();
         }
     }
 
     pub fn navigate_back(&mut self) {
         if let Some(mut nav) = self.history.pop_back() {
+            let mut scroll_offset = self.history_scroll_offsets.pop_back();
             if let Nav::SearchResults(query) = &nav {
                 if SpotifyUrl::parse(query).is_some() {
                     nav = self.history.pop_back().unwrap_or(Nav::Home);
+                    scroll_offset = self.history_scroll_offsets.pop_back();
                 }
             }
+            self.scroll_offset = scroll_offset.unwrap_or_default();
+            self.finder = Finder::new();
 
             if let Nav::AlbumDetail(album, _) = nav {
                 nav = Nav::AlbumDetail(album, None);
@@ -212,7 +361,10 @@ impl AppState {
 
             self.nav = nav;
             self.config.last_route.replace(self.nav.to_owned());
-            Arc::make_mut(&mut self.common_ctx).nav = self.nav.clone();
+            let common_ctx = Arc::make_mut(&mut self.common_ctx);
+            common_ctx.nav = self.nav.clone();
+            common_ctx.track_filters = TrackFilters::default();
+            common_ctx.selected_tracks.clear();
         }
     }
 
@@ -220,6 +372,14 @@ impl AppState {
         let current: Nav = mem::replace(&mut self.nav, Nav::Home);
         self.nav = current;
     }
+
+    /// Are the lyrics of the playing track on screen, on the lyrics page, in
+    /// the side panel or in the now-playing view.
+    pub fn lyrics_shown(&self) -> bool {
+        matches!(self.nav, Nav::Lyrics)
+            || self.lyrics_visible
+            || (self.now_playing_visible && self.config.now_playing_lyrics)
+    }
 }
 
 impl AppState {
@@ -241,29 +401,54 @@ impl AppState {
         self.added_queue.push_back(queue_entry);
     }
 
+    /// Mirror `entries` queued at `placement`, in the order the player plays
+    /// them.  Items to play next go in front of the ones queued earlier.
+    pub fn add_queued_entries(&mut self, placement: QueuePlacement, entries: &[QueueEntry]) {
+        match placement {
+            QueuePlacement::Next => {
+                for (index, entry) in entries.iter().enumerate() {
+                    self.added_queue.insert(index, entry.clone());
+                }
+            }
+            QueuePlacement::Last => self.added_queue.extend(entries.iter().cloned()),
+        }
+    }
+
     pub fn loading_playback(&mut self, item: Playable, origin: PlaybackOrigin) {
         let ctx = self.common_ctx_mut();
         ctx.now_playing.take();
         ctx.playback_progress = Some(0);
+        PlaybackClock::global().set(Duration::ZERO, false);
         self.playback.state = PlaybackState::Loading;
         self.playback.now_playing.replace(NowPlaying {
+            chapters: item.chapters(),
             item,
             origin,
             progress: Duration::default(),
+            quality: None,
             library: Arc::clone(&self.library),
         });
     }
 
-    pub fn start_playback(&mut self, item: Playable, origin: PlaybackOrigin, progress: Duration) {
+    pub fn start_playback(
+        &mut self,
+        item: Playable,
+        origin: PlaybackOrigin,
+        progress: Duration,
+        quality: Option<Arc<str>>,
+    ) {
         let progress_ms = progress.as_millis() as u64;
         let ctx = self.common_ctx_mut();
         ctx.now_playing.replace(item.clone());
         ctx.playback_progress = Some(progress_ms);
+        PlaybackClock::global().set(progress, true);
         self.playback.state = PlaybackState::Playing;
         self.playback.now_playing.replace(NowPlaying {
+            chapters: item.chapters(),
             item,
             origin,
             progress,
+            quality,
             library: Arc::clone(&self.library),
         });
     }
@@ -273,31 +458,114 @@ impl AppState {
             now_playing.progress = progress;
         }
         self.common_ctx_mut().playback_progress = Some(progress.as_millis() as u64);
+        PlaybackClock::global().set(progress, self.playback.state == PlaybackState::Playing);
     }
 
     pub fn pause_playback(&mut self) {
         self.playback.state = PlaybackState::Paused;
+        PlaybackClock::global().set_running(false);
     }
 
     pub fn resume_playback(&mut self) {
         self.playback.state = PlaybackState::Playing;
+        PlaybackClock::global().set_running(true);
     }
 
     pub fn block_playback(&mut self) {
         // TODO: Figure out how to signal blocked playback properly.
     }
 
+    /// Store the volume in the config, also as the last one used on the
+    /// current output device.
+    pub fn save_volume(&mut self) {
+        self.config.volume = self.playback.volume;
+        if let Some(device) = &self.playback.output_device {
+            self.config
+                .device_volumes
+                .insert(device.to_string(), self.playback.volume);
+        }
+    }
+
     pub fn stop_playback(&mut self) {
         self.playback.state = PlaybackState::Stopped;
         self.playback.now_playing.take();
         let ctx = self.common_ctx_mut();
         ctx.now_playing.take();
         ctx.playback_progress = None;
+        PlaybackClock::global().set(Duration::ZERO, false);
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: Shuffle) {
+        self.playback.shuffle = shuffle;
+        self.config.shuffle = shuffle;
+        self.config.save();
+    }
+
+    pub fn set_repeat(&mut self, repeat: Repeat) {
+        self.playback.repeat = repeat;
+        self.config.repeat = repeat;
+        self.config.save();
+    }
+
+    /// Nothing is loaded from Spotify, because of the offline mode or a lost
+    /// connection.
+    pub fn is_offline(&self) -> bool {
+        self.config.offline_mode || self.lost_connection
     }
 
-    pub fn set_queue_behavior(&mut self, queue_behavior: QueueBehavior) {
-        self.playback.queue_behavior = queue_behavior;
-        self.config.queue_behavior = queue_behavior;
+    /// Playback configuration, with the data saver applied on metered
+    /// connections.
+    pub fn playback_config(&self) -> PlaybackConfig {
+        let mut config = self.config.playback();
+        config.offline = self.is_offline();
+        // The speed of the playing episode, which might be its own.
+        config.podcast_tempo = Tempo::new(
+            self.playback.podcast_speed as f32,
+            self.playback.skip_silence,
+        );
+        if self.network_metered == Some(true)
+            && self.config.metered_behavior == MeteredBehavior::DataSaver
+        {
+            config.quality = config.quality.min(AudioQuality::Low.as_tier());
+            // Tracks that end up skipped would be downloaded for nothing.
+            config.prefetch_at = None;
+        }
+        config
+    }
+
+    /// Whether the playlists marked for offline listening are downloaded now.
+    /// Unless metered connections are ignored, downloads wait for a
+    /// connection that is not metered.
+    pub fn can_download(&self) -> bool {
+        !self.is_offline()
+            && (self.network_metered != Some(true)
+                || self.config.metered_behavior == MeteredBehavior::Ignore)
+    }
+
+    /// In `MeteredBehavior::Warn`, the first request to play over a metered
+    /// connection only shows a warning.  Returns true if playback can start.
+    pub fn confirm_metered_playback(&mut self) -> bool {
+        if self.is_offline()
+            || self.network_metered != Some(true)
+            || self.config.metered_behavior != MeteredBehavior::Warn
+            || self.metered_confirmed
+        {
+            return true;
+        }
+        self.metered_confirmed = true;
+        self.info_alert(
+            "You are on a metered connection, streaming might use up your data plan. \
+             Play again to stream anyway.",
+        );
+        false
+    }
+
+    /// Also makes `speed` the one of episodes that have no speed of their own.
+    pub fn set_podcast_tempo(&mut self, speed: f64, skip_silence: bool) {
+        self.playback.podcast_speed = speed;
+        self.playback.skip_silence = skip_silence;
+        self.config.podcast_speed = speed;
+        self.config.skip_silence = skip_silence;
         self.config.save();
     }
 }
@@ -307,6 +575,95 @@ impl AppState {
         Arc::make_mut(&mut self.common_ctx)
     }
 
+    /// Change the playlists marked for offline listening, and save them
+    /// unless only the download progress changed.
+    pub fn with_offline_playlists_mut(&mut self, func: impl FnOnce(&mut OfflinePlaylists)) {
+        let old = self.config.offline_playlists.clone();
+        func(&mut self.config.offline_playlists);
+        let saved_changed = old.len() != self.config.offline_playlists.len()
+            || old.iter().any(|(id, playlist)| {
+                self.config
+                    .offline_playlists
+                    .get(id)
+                    .map(|p| &p.synced_snapshot)
+                    != Some(&playlist.synced_snapshot)
+            });
+        if saved_changed {
+            self.config.save_in_background();
+        }
+        self.common_ctx_mut().offline_playlists = self.config.offline_playlists.clone();
+    }
+
+    /// Count the episodes of the shows as seen, the new-episode badges of the
+    /// followed shows count from here.  With `keep_known`, shows counted
+    /// before are left as they are.
+    pub fn mark_episodes_seen<'a>(
+        &mut self,
+        shows: impl IntoIterator<Item = &'a Show>,
+        keep_known: bool,
+    ) {
+        let mut changed = false;
+        for show in shows {
+            let Some(total) = show.total_episodes else {
+                continue;
+            };
+            let known = self.config.seen_episodes.get(&show.id).copied();
+            if known == Some(total) || keep_known && known.is_some() {
+                continue;
+            }
+            self.config.seen_episodes.insert(show.id.clone(), total);
+            changed = true;
+        }
+        if changed {
+            self.config.save();
+            let seen = self
+                .config
+                .seen_episodes
+                .iter()
+                .map(|(id, count)| (id.clone(), *count))
+                .collect();
+            self.with_library_mut(|library| library.seen_episodes = seen);
+        }
+    }
+
+    /// Show the edited name and description of a playlist everywhere it is
+    /// listed, before Spotify confirmed the change.
+    pub fn apply_playlist_edit(&mut self, edit: &PlaylistEdit) {
+        let link = edit.link();
+        let description: Arc<str> = edit.description.trim().into();
+        if let Some(playlist) = self.playlist_detail.playlist.resolved() {
+            if playlist.id == link.id {
+                let mut playlist = playlist.clone();
+                playlist.name = link.name.clone();
+                playlist.description = description.clone();
+                self.playlist_detail
+                    .playlist
+                    .resolve(link.clone(), playlist);
+            }
+        }
+        if let Nav::PlaylistDetail(current) = &mut self.nav {
+            if current.id == link.id {
+                *current = link.clone();
+            }
+        }
+        self.with_library_mut(|l| l.change_playlist_details(link, description));
+    }
+
+    /// Show the changed visibility of a playlist, before Spotify confirmed
+    /// the change.
+    pub fn apply_playlist_visibility(&mut self, id: &Arc<str>, visibility: PlaylistVisibility) {
+        if let Some(playlist) = self.playlist_detail.playlist.resolved() {
+            if &playlist.id == id {
+                let mut playlist = playlist.clone();
+                playlist.set_visibility(visibility);
+                self.playlist_detail
+                    .playlist
+                    .resolve(playlist.link(), playlist);
+            }
+        }
+        self.with_library_mut(|l| l.change_playlist_visibility(id, visibility));
+    }
+
     pub fn with_library_mut(&mut self, func: impl FnOnce(&mut Library)) {
         func(Arc::make_mut(&mut self.library));
         self.library_updated();
@@ -327,6 +684,7 @@ impl AppState {
             style,
             id: Alert::fresh_id(),
             created_at: Instant::now(),
+            action: None,
         };
         self.alerts.push_back(alert);
     }
@@ -335,8 +693,94 @@ impl AppState {
         self.add_alert(message, AlertStyle::Info);
     }
 
+    /// Show an info alert with a button, like "Undo", that submits `command`
+    /// and dismisses the alert.
+    pub fn info_alert_with_action(
+        &mut self,
+        message: impl Display,
+        label: impl Display,
+        command: impl Into<Command>,
+    ) {
+        let alert = Alert {
+            message: message.to_string().into(),
+            style: AlertStyle::Info,
+            id: Alert::fresh_id(),
+            created_at: Instant::now(),
+            action: Some(AlertAction {
+                label: label.to_string().into(),
+                command: command.into(),
+            }),
+        };
+        self.alerts.push_back(alert);
+    }
+
     pub fn error_alert(&mut self, message: impl Display) {
         self.add_alert(message, AlertStyle::Error);
+        self.notification_sound(NotificationEvent::Error);
+    }
+
+    /// Are the quiet hours on, see `NotificationSettings`.
+    pub fn is_quiet_hours(&self) -> bool {
+        self.config
+            .notifications
+            .is_quiet_at(notifications::time_of_day())
+    }
+
+    /// Play the sound chosen for `event`, if any, unless it is quiet hours.
+    pub fn notification_sound(&self, event: NotificationEvent) {
+        if let Some(chime) = self.config.notifications.sound(event).chime() {
+            if !self.is_quiet_hours() {
+                notifications::play_sound(chime, self.config.output_device.clone());
+            }
+        }
+    }
+
+    /// Tell about something that happened in the background, with an alert
+    /// and the sound of `event`.  During the quiet hours, the alert is held
+    /// back until they are over, see `release_held_notifications`.
+    pub fn notify(&mut self, event: NotificationEvent, message: impl Display) {
+        if self.is_quiet_hours() {
+            self.held_notifications
+                .push_back((event, message.to_string().into()));
+            return;
+        }
+        self.add_alert(message, Self::notification_style(event));
+        self.notification_sound(event);
+    }
+
+    /// Show the alerts held back during the quiet hours once they are over,
+    /// with a single sound for all of them.
+    pub fn release_held_notifications(&mut self) {
+        if self.held_notifications.is_empty() || self.is_quiet_hours() {
+            return;
+        }
+        let held = std::mem::take(&mut self.held_notifications);
+        for (event, message) in &held {
+            self.add_alert(message, Self::notification_style(*event));
+        }
+        if let Some((event, _)) = held.last() {
+            self.notification_sound(*event);
+        }
+    }
+
+    fn notification_style(event: NotificationEvent) -> AlertStyle {
+        match event {
+            NotificationEvent::Error => AlertStyle::Error,
+            _ => AlertStyle::Info,
+        }
+    }
+
+    /// Replace the message of a still visible alert and show it for another
+    /// full duration.  Returns false if the alert is gone already.
+    pub fn refresh_alert(&mut self, id: usize, message: impl Display) -> bool {
+        match self.alerts.iter_mut().find(|a| a.id == id) {
+            Some(alert) => {
+                alert.message = message.to_string().into();
+                alert.created_at = Instant::now();
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn dismiss_alert(&mut self, id: usize) {
@@ -357,20 +801,33 @@ pub struct Library {
     pub saved_albums: Promise<SavedAlbums>,
     pub saved_tracks: Promise<SavedTracks>,
     pub saved_shows: Promise<Shows>,
+    pub saved_episodes: Promise<SavedEpisodes>,
+    /// Copy of `Config::seen_episodes`, for the new-episode badges.
+    pub seen_episodes: HashMap<Arc<str>, usize>,
+    /// Tracks known to be loved or not on Last.fm, `None` without a Last.fm
+    /// session.
+    pub lastfm_loved: Option<HashMap<TrackId, bool>>,
 }
 
 impl Library {
-    pub fn add_track(&mut self, track: Arc<Track>) {
+    pub fn add_track(&mut self, mut track: Arc<Track>) {
         if let Some(saved) = self.saved_tracks.resolved_mut() {
+            Arc::make_mut(&mut track).added_at = Some(today());
             saved.set.insert(track.id);
-            saved.tracks.push_front(track);
+            if saved.window.offset == 0 {
+                saved.tracks.push_front(track);
+            }
+            saved.window.add_first();
+            saved.sort(saved.sort);
         }
     }
 
     pub fn remove_track(&mut self, track_id: &TrackId) {
         if let Some(saved) = self.saved_tracks.resolved_mut() {
             saved.set.remove(track_id);
+            let len = saved.tracks.len();
             saved.tracks.retain(|t| &t.id != track_id);
+            saved.window.remove(saved.tracks.len() < len);
         }
     }
 
@@ -382,8 +839,23 @@ impl Library {
         }
     }
 
-    pub fn add_album(&mut self, album: Arc<Album>) {
+    pub fn is_lastfm_loved(&self, track_id: &TrackId) -> bool {
+        self.lastfm_loved
+            .as_ref()
+            .and_then(|loved| loved.get(track_id))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    pub fn set_lastfm_loved(&mut self, track_id: TrackId, loved: bool) {
+        if let Some(loved_tracks) = &mut self.lastfm_loved {
+            loved_tracks.insert(track_id, loved);
+        }
+    }
+
+    pub fn add_album(&mut self, mut album: Arc<Album>) {
         if let Some(saved) = self.saved_albums.resolved_mut() {
+            Arc::make_mut(&mut album).saved_at = Some(today());
             saved.set.insert(album.id.clone());
             saved.albums.push_front(album);
         }
@@ -426,42 +898,98 @@ impl Library {
         }
     }
 
+    /// Episodes released since a followed show was last opened.
+    pub fn new_episodes(&self, show: &Show) -> usize {
+        match (show.total_episodes, self.seen_episodes.get(&show.id)) {
+            (Some(total), Some(seen)) if self.contains_show(show) => total.saturating_sub(*seen),
+            _ => 0,
+        }
+    }
+
+    pub fn add_episode(&mut self, episode: Arc<Episode>) {
+        if let Some(saved) = self.saved_episodes.resolved_mut() {
+            saved.set.insert(episode.id);
+            saved.episodes.push_front(episode);
+        }
+    }
+
+    pub fn remove_episode(&mut self, episode_id: &EpisodeId) {
+        if let Some(saved) = self.saved_episodes.resolved_mut() {
+            saved.set.remove(episode_id);
+            saved.episodes.retain(|e| &e.id != episode_id);
+        }
+    }
+
+    pub fn contains_episode(&self, episode: &Episode) -> bool {
+        if let Some(saved) = self.saved_episodes.resolved() {
+            saved.set.contains(&episode.id)
+        } else {
+            false
+        }
+    }
+
     pub fn writable_playlists(&self) -> Vec<&Playlist> {
         if let Some(saved) = self.playlists.resolved() {
             saved
                 .iter()
-                .filter(|playlist| {
-                    self.user_profile
-                        .resolved()
-                        .map(|user| playlist.owner.id == user.id)
-                        .unwrap_or(false)
-                        || playlist.collaborative
-                })
+                .filter(|playlist| self.is_playlist_writable(playlist))
                 .collect()
         } else {
             Vec::new()
         }
     }
 
+    pub fn is_playlist_writable(&self, playlist: &Playlist) -> bool {
+        self.user_profile
+            .resolved()
+            .map(|user| playlist.owner.id == user.id)
+            .unwrap_or(false)
+            || playlist.collaborative
+    }
+
+    pub fn playlist_track_count(&self, link: &PlaylistLink) -> Option<usize> {
+        self.playlists
+            .resolved()?
+            .iter()
+            .find(|playlist| playlist.id == link.id)?
+            .track_count
+    }
+
     pub fn add_playlist(&mut self, playlist: Playlist) {
         if let Some(playlists) = self.playlists.resolved_mut() {
             playlists.push_back(playlist);
         }
     }
 
+    /// Replace the playlist with the id, e.g. the placeholder of a created
+    /// playlist, see `NewPlaylist`.
+    pub fn replace_playlist(&mut self, id: &str, playlist: Playlist) {
+        if let Some(playlists) = self.playlists.resolved_mut() {
+            if let Some(existing) = playlists.iter_mut().find(|p| p.id.as_ref() == id) {
+                *existing = playlist;
+            }
+        }
+    }
+
     pub fn remove_from_playlist(&mut self, id: &str) {
         if let Some(playlists) = self.playlists.resolved_mut() {
             playlists.retain(|p| p.id.as_ref() != id);
         }
     }
 
-    pub fn rename_playlist(&mut self, link: PlaylistLink) {
+    pub fn change_playlist_details(&mut self, link: PlaylistLink, description: Arc<str>) {
         if let Some(saved) = self.playlists.resolved_mut() {
-            for playlist in saved.iter_mut() {
-                if playlist.id == link.id {
-                    playlist.name = link.name;
-                    break;
-                }
+            if let Some(playlist) = saved.iter_mut().find(|p| p.id == link.id) {
+                playlist.name = link.name;
+                playlist.description = description;
+            }
+        }
+    }
+
+    pub fn change_playlist_visibility(&mut self, id: &Arc<str>, visibility: PlaylistVisibility) {
+        if let Some(saved) = self.playlists.resolved_mut() {
+            if let Some(playlist) = saved.iter_mut().find(|p| &p.id == id) {
+                playlist.set_visibility(visibility);
             }
         }
     }
@@ -482,18 +1010,18 @@ impl Library {
         }
     }
 
-    pub fn increment_playlist_track_count(&mut self, link: &PlaylistLink) {
+    pub fn increment_playlist_track_count(&mut self, link: &PlaylistLink, by: usize) {
         if let Some(saved) = self.playlists.resolved_mut() {
             if let Some(playlist) = saved.iter_mut().find(|p| p.id == link.id) {
-                playlist.track_count = playlist.track_count.map(|count| count + 1);
+                playlist.track_count = playlist.track_count.map(|count| count + by);
             }
         }
     }
 
-    pub fn decrement_playlist_track_count(&mut self, link: &PlaylistLink) {
+    pub fn decrement_playlist_track_count(&mut self, link: &PlaylistLink, by: usize) {
         if let Some(saved) = self.playlists.resolved_mut() {
             if let Some(playlist) = saved.iter_mut().find(|p| p.id == link.id) {
-                playlist.track_count = playlist.track_count.map(|count| count.saturating_sub(1));
+                playlist.track_count = playlist.track_count.map(|count| count.saturating_sub(by));
             }
         }
     }
@@ -507,36 +1035,166 @@ impl Default for Library {
             saved_albums: Promise::Empty,
             saved_tracks: Promise::Empty,
             saved_shows: Promise::Empty,
+            saved_episodes: Promise::Empty,
+            seen_episodes: HashMap::new(),
+            lastfm_loved: None,
         }
     }
 }
 
+/// Saved tracks, loaded in pages as they are scrolled to in the order of the
+/// API, or all at once for the other orders.
 #[derive(Clone, Default, Data, Lens)]
 pub struct SavedTracks {
+    /// The loaded tracks, see `window`.
     pub tracks: Vector<Arc<Track>>,
+    pub window: PageWindow,
+    /// Every saved track loaded so far, also those no longer in `tracks`.
     pub set: HashSet<TrackId>,
+    pub sort: TableSort,
 }
 
 impl SavedTracks {
+    /// `tracks` are expected in the order of the API, the most recently saved
+    /// first.
     pub fn new(tracks: Vector<Arc<Track>>) -> Self {
         let set = tracks.iter().map(|t| t.id).collect();
-        Self { tracks, set }
+        Self {
+            window: PageWindow::complete(tracks.len()),
+            tracks,
+            set,
+            sort: TableSort::RECENTLY_SAVED,
+        }
+    }
+
+    /// First page of the tracks, out of `total` saved ones.
+    pub fn first_page(tracks: Vector<Arc<Track>>, total: usize) -> Self {
+        let mut saved = Self::new(tracks);
+        saved.window.total = total;
+        saved
+    }
+
+    /// Tracks can be loaded page by page only in the order of the API, and
+    /// when they are not filtered.
+    pub fn is_paged(sort: TableSort, filters: &TrackFilters) -> bool {
+        sort == TableSort::RECENTLY_SAVED && filters.is_empty()
+    }
+
+    /// Take in the `tracks` loaded from `offset`, out of `total`.
+    pub fn insert_page(&mut self, offset: usize, tracks: Vector<Arc<Track>>, total: usize) {
+        self.set.extend(tracks.iter().map(|t| t.id));
+        match self.window.insert(offset, tracks.len(), total) {
+            PageInsert::Append { drop_front } => {
+                self.tracks.append(tracks);
+                self.tracks.slice(..drop_front);
+            }
+            PageInsert::Prepend { drop_back } => {
+                let kept = self.tracks.len() - drop_back;
+                self.tracks.truncate(kept);
+                let mut tracks = tracks;
+                tracks.append(mem::take(&mut self.tracks));
+                self.tracks = tracks;
+            }
+            PageInsert::Replace => {
+                self.tracks = tracks;
+            }
+        }
+    }
+
+    pub fn sorted(mut self, sort: TableSort) -> Self {
+        self.sort(sort);
+        self
     }
+
+    pub fn sort(&mut self, sort: TableSort) {
+        let mut tracks: Vec<_> = self.tracks.iter().cloned().collect();
+        tracks.sort_by(|a, b| sort.compare(a, b));
+        self.tracks = tracks.into();
+        self.sort = sort;
+    }
+
+    /// Title of the month the track at `position` was saved in, if the tracks
+    /// are sorted by that date and it starts a new month.
+    pub fn month_header(&self, position: usize) -> Option<Arc<str>> {
+        if self.sort.column != TrackColumn::DateAdded {
+            return None;
+        }
+        let date = self.tracks.get(position)?.added_at?;
+        let previous = position
+            .checked_sub(1)
+            .and_then(|previous| self.tracks.get(previous)?.added_at);
+        if previous.is_some_and(|previous| {
+            (previous.year(), previous.month()) == (date.year(), date.month())
+        }) {
+            return None;
+        }
+        Some(format::month(date).into())
+    }
+}
+
+fn today() -> Date {
+    OffsetDateTime::now_utc().date()
 }
 
 #[derive(Clone, Default, Data, Lens)]
 pub struct SavedAlbums {
     pub albums: Vector<Arc<Album>>,
     pub set: HashSet<Arc<str>>,
+    pub duplicates: Option<Vector<AlbumDuplicates>>,
 }
 
 impl SavedAlbums {
     pub fn new(albums: Vector<Arc<Album>>) -> Self {
         let set = albums.iter().map(|a| a.id.clone()).collect();
-        Self { albums, set }
+        Self {
+            albums,
+            set,
+            duplicates: None,
+        }
+    }
+
+    /// Group saved albums that are the same release under different market ids.
+    /// In each group, the variant with the most tracks is kept, preferring the
+    /// most recently saved one on ties.
+    pub fn find_duplicates(&self) -> Vector<AlbumDuplicates> {
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut groups: Vec<Vec<Arc<Album>>> = Vec::new();
+        for album in &self.albums {
+            let key = album.duplicate_key();
+            match index.get(&key) {
+                Some(&i) => groups[i].push(album.clone()),
+                None => {
+                    index.insert(key, groups.len());
+                    groups.push(vec![album.clone()]);
+                }
+            }
+        }
+        groups
+            .into_iter()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                let mut keep_index = 0;
+                for (i, album) in group.iter().enumerate() {
+                    if album.tracks.len() > group[keep_index].tracks.len() {
+                        keep_index = i;
+                    }
+                }
+                let keep = group.remove(keep_index);
+                AlbumDuplicates {
+                    keep,
+                    remove: group.into_iter().collect(),
+                }
+            })
+            .collect()
     }
 }
 
+#[derive(Clone, Data, Lens)]
+pub struct AlbumDuplicates {
+    pub keep: Arc<Album>,
+    pub remove: Vector<Arc<Album>>,
+}
+
 #[derive(Clone, Default, Data, Lens)]
 pub struct Shows {
     pub shows: Vector<Arc<Show>>,
@@ -550,6 +1208,19 @@ impl Shows {
     }
 }
 
+#[derive(Clone, Default, Data, Lens)]
+pub struct SavedEpisodes {
+    pub episodes: Vector<Arc<Episode>>,
+    pub set: HashSet<EpisodeId>,
+}
+
+impl SavedEpisodes {
+    pub fn new(episodes: Vector<Arc<Episode>>) -> Self {
+        let set = episodes.iter().map(|e| e.id).collect();
+        Self { episodes, set }
+    }
+}
+
 #[derive(Clone, Data)]
 pub struct CommonCtx {
     pub now_playing: Option<Playable>,
@@ -558,19 +1229,46 @@ pub struct CommonCtx {
     pub show_track_cover: bool,
     pub show_playlist_images: bool,
     pub nav: Nav,
+    /// Track being dragged from a track list onto a playlist in the sidebar.
+    pub dragged_track: Option<Arc<Track>>,
+    /// Tracks that go along with the drag, the selection if the dragged
+    /// track is selected.
+    pub dragged_tracks: Vector<Arc<Track>>,
+    /// Track rows selected with Ctrl or Cmd held, in the order they were
+    /// selected.
+    pub selected_tracks: Vector<Arc<Track>>,
+    /// Copy of `Config::offline_playlists`, for the sync badges.
+    #[data(same_fn = "PartialEq::eq")]
+    pub offline_playlists: OfflinePlaylists,
+    /// Items that play from the cache, known while offline.
+    pub offline_items: Option<Arc<StdHashSet<ItemId>>>,
+    /// Filters of the track table of the page, see `PlayableIter::is_table`.
+    pub track_filters: TrackFilters,
 }
 
 impl CommonCtx {
     pub fn is_playing(&self, item: &Playable) -> bool {
         matches!(&self.now_playing, Some(i) if i.same(item))
     }
+
+    pub fn is_track_selected(&self, track: &Track) -> bool {
+        self.selected_tracks.iter().any(|t| t.id == track.id)
+    }
+
+    /// False for the items that cannot play while offline.
+    pub fn is_available(&self, item_id: ItemId) -> bool {
+        match &self.offline_items {
+            Some(items) => item_id.id_type == ItemIdType::LocalFile || items.contains(&item_id),
+            None => true,
+        }
+    }
 }
 
 pub type WithCtx<T> = Ctx<Arc<CommonCtx>, T>;
 
 #[derive(Clone, Data, Lens)]
 pub struct HomeDetail {
-    pub made_for_you: Promise<MixedView>,
+    pub made_for_you: Promise<MadeForYou>,
     pub user_top_mixes: Promise<MixedView>,
     pub best_of_artists: Promise<MixedView>,
     pub recommended_stations: Promise<MixedView>,
@@ -580,6 +1278,8 @@ pub struct HomeDetail {
     pub jump_back_in: Promise<MixedView>,
     pub user_top_tracks: Promise<Vector<Arc<Track>>>,
     pub user_top_artists: Promise<Vector<Artist>>,
+    /// Latest tracks of the Last.fm friends of the user.
+    pub lastfm_friends: Promise<Vector<FriendTrack>, LastFmUser>,
 }
 
 #[derive(Clone, Data, Lens)]
@@ -599,6 +1299,7 @@ pub struct Alert {
     pub message: Arc<str>,
     pub style: AlertStyle,
     pub created_at: Instant,
+    pub action: Option<AlertAction>,
 }
 
 impl Alert {
@@ -607,6 +1308,13 @@ impl Alert {
     }
 }
 
+#[derive(Clone, Data)]
+pub struct AlertAction {
+    pub label: Arc<str>,
+    #[data(ignore)]
+    pub command: Command,
+}
+
 #[derive(Clone, Data, Eq, PartialEq)]
 pub enum AlertStyle {
     Error,
@@ -5,17 +5,24 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::data::track::TrackId;
-use crate::data::{AlbumLink, ArtistLink, PlaylistLink, ShowLink};
+use crate::data::{AlbumLink, ArtistLink, CategoryLink, PlaylistLink, ShowLink};
 
 use super::RecommendationsRequest;
 
 #[derive(Copy, Clone, Debug, Data, PartialEq, Eq, Hash)]
 pub enum Route {
     Home,
+    Browse,
+    NewReleases,
+    BrowseCategory,
     Lyrics,
     SavedTracks,
     SavedAlbums,
     Shows,
+    SavedEpisodes,
+    LocalFiles,
+    History,
+    Stats,
     SearchResults,
     ArtistDetail,
     AlbumDetail,
@@ -28,10 +35,17 @@ pub enum Route {
 pub enum Nav {
     #[default]
     Home,
+    Browse,
+    NewReleases,
+    BrowseCategory(CategoryLink),
     Lyrics,
     SavedTracks,
     SavedAlbums,
     Shows,
+    SavedEpisodes,
+    LocalFiles,
+    History,
+    Stats,
     SearchResults(Arc<str>),
     AlbumDetail(AlbumLink, Option<TrackId>),
     ArtistDetail(ArtistLink),
@@ -44,10 +58,17 @@ impl Nav {
     pub fn route(&self) -> Route {
         match self {
             Nav::Home => Route::Home,
+            Nav::Browse => Route::Browse,
+            Nav::NewReleases => Route::NewReleases,
+            Nav::BrowseCategory(_) => Route::BrowseCategory,
             Nav::Lyrics => Route::Lyrics,
             Nav::SavedTracks => Route::SavedTracks,
             Nav::SavedAlbums => Route::SavedAlbums,
             Nav::Shows => Route::Shows,
+            Nav::SavedEpisodes => Route::SavedEpisodes,
+            Nav::LocalFiles => Route::LocalFiles,
+            Nav::History => Route::History,
+            Nav::Stats => Route::Stats,
             Nav::SearchResults(_) => Route::SearchResults,
             Nav::AlbumDetail(_, _) => Route::AlbumDetail,
             Nav::ArtistDetail(_) => Route::ArtistDetail,
@@ -60,32 +81,54 @@ impl Nav {
     pub fn title(&self) -> String {
         match self {
             Nav::Home => "Home".to_string(),
+            Nav::Browse => "Browse".to_string(),
+            Nav::NewReleases => "New Releases".to_string(),
+            Nav::BrowseCategory(link) => link.name.to_string(),
             Nav::Lyrics => "Lyrics".to_string(),
             Nav::SavedTracks => "Your Favourites".to_string(),
             Nav::SavedAlbums => "Saved Albums".to_string(),
             Nav::Shows => "Podcasts".to_string(),
+            Nav::SavedEpisodes => "Your Episodes".to_string(),
+            Nav::LocalFiles => "Local Files".to_string(),
+            Nav::History => "History".to_string(),
+            Nav::Stats => "Stats".to_string(),
             Nav::SearchResults(query) => query.to_string(),
             Nav::AlbumDetail(link, _) => link.name.to_string(),
             Nav::ArtistDetail(link) => link.name.to_string(),
             Nav::PlaylistDetail(link) => link.name.to_string(),
             Nav::ShowDetail(link) => link.name.to_string(),
-            Nav::Recommendations(_) => "Recommended".to_string(),
+            Nav::Recommendations(request) => request
+                .title
+                .as_deref()
+                .unwrap_or("Recommended")
+                .to_string(),
         }
     }
 
     pub fn full_title(&self) -> String {
         match self {
             Nav::Home => "Home".to_string(),
+            Nav::Browse => "Browse".to_string(),
+            Nav::NewReleases => "New Releases".to_string(),
+            Nav::BrowseCategory(link) => format!("Genre \"{}\"", link.name),
             Nav::Lyrics => "Lyrics".to_string(),
             Nav::SavedTracks => "Your Favourites".to_string(),
             Nav::SavedAlbums => "Saved Albums".to_string(),
             Nav::Shows => "Saved Shows".to_string(),
+            Nav::SavedEpisodes => "Your Episodes".to_string(),
+            Nav::LocalFiles => "Local Files".to_string(),
+            Nav::History => "Listening History".to_string(),
+            Nav::Stats => "Your Stats".to_string(),
             Nav::SearchResults(query) => format!("Search \"{query}\""),
             Nav::AlbumDetail(link, _) => format!("Album \"{}\"", link.name),
             Nav::ArtistDetail(link) => format!("Artist \"{}\"", link.name),
             Nav::PlaylistDetail(link) => format!("Playlist \"{}\"", link.name),
             Nav::ShowDetail(link) => format!("Show \"{}\"", link.name),
-            Nav::Recommendations(_) => "Recommended".to_string(),
+            Nav::Recommendations(request) => request
+                .title
+                .as_deref()
+                .unwrap_or("Recommended")
+                .to_string(),
         }
     }
 }
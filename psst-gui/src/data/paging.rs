@@ -0,0 +1,197 @@
+use druid::Data;
+
+/// Items requested from the API at a time.
+pub const PAGE_SIZE: usize = 50;
+
+/// Most items kept loaded, the pages furthest from the visible ones are
+/// dropped past it.
+const MAX_LOADED: usize = 20 * PAGE_SIZE;
+
+/// Items loaded ahead of, and behind, the visible ones.
+const PREFETCH: usize = 2 * PAGE_SIZE;
+
+/// Part of a long list that is loaded, the `len` items from `offset` out of
+/// the `total` ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Data)]
+pub struct PageWindow {
+    pub offset: usize,
+    pub len: usize,
+    pub total: usize,
+    /// A page is being loaded, no other is asked for until it arrives.
+    pub loading: bool,
+}
+
+/// Where a loaded page goes, see `PageWindow::insert`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PageInsert {
+    /// After the loaded items, dropping that many items from the front.
+    Append { drop_front: usize },
+    /// Before the loaded items, dropping that many items from the back.
+    Prepend { drop_back: usize },
+    /// In place of the loaded items.
+    Replace,
+}
+
+impl PageWindow {
+    /// Window of a list that is loaded whole.
+    pub fn complete(len: usize) -> Self {
+        Self {
+            offset: 0,
+            len,
+            total: len,
+            loading: false,
+        }
+    }
+
+    pub fn end(&self) -> usize {
+        self.offset + self.len
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.offset == 0 && self.len >= self.total
+    }
+
+    /// Offset and limit of the page to load for the `visible` items to be
+    /// loaded, with `PREFETCH` items around them.  Scrolling far past the
+    /// loaded items starts over at the visible ones.
+    pub fn page_to_load(&self, visible: (usize, usize)) -> Option<(usize, usize)> {
+        if self.loading || self.is_complete() {
+            return None;
+        }
+        let (first, last) = visible;
+        let wanted_start = first.saturating_sub(PREFETCH);
+        let wanted_end = (last + PREFETCH).min(self.total);
+        if wanted_end <= self.offset || wanted_start >= self.end() {
+            let start = first.min(self.total.saturating_sub(1)) / PAGE_SIZE * PAGE_SIZE;
+            Some((start, PAGE_SIZE))
+        } else if wanted_end > self.end() {
+            Some((self.end(), PAGE_SIZE))
+        } else if wanted_start < self.offset {
+            let start = self.offset.saturating_sub(PAGE_SIZE);
+            Some((start, self.offset - start))
+        } else {
+            None
+        }
+    }
+
+    /// Take in the `len` items loaded from `offset`, out of `total`, and
+    /// return where they go.
+    pub fn insert(&mut self, offset: usize, len: usize, total: usize) -> PageInsert {
+        self.loading = false;
+        self.total = total;
+        if offset == self.end() && self.len > 0 {
+            let drop_front = (self.len + len).saturating_sub(MAX_LOADED);
+            self.offset += drop_front;
+            self.len += len - drop_front;
+            PageInsert::Append { drop_front }
+        } else if offset + len == self.offset && self.len > 0 {
+            let drop_back = (self.len + len).saturating_sub(MAX_LOADED);
+            self.offset = offset;
+            self.len += len - drop_back;
+            PageInsert::Prepend { drop_back }
+        } else {
+            self.offset = offset;
+            self.len = len;
+            PageInsert::Replace
+        }
+    }
+
+    /// An item was added at the start of the list.
+    pub fn add_first(&mut self) {
+        self.total += 1;
+        if self.offset == 0 {
+            self.len += 1;
+        } else {
+            self.offset += 1;
+        }
+    }
+
+    /// An item was removed from the list, `loaded` if it was one of the
+    /// loaded ones.
+    pub fn remove(&mut self, loaded: bool) {
+        self.total = self.total.saturating_sub(1);
+        if loaded {
+            self.len = self.len.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(offset: usize, len: usize, total: usize) -> PageWindow {
+        PageWindow {
+            offset,
+            len,
+            total,
+            loading: false,
+        }
+    }
+
+    #[test]
+    fn loads_pages_ahead_of_the_visible_items() {
+        let first_page = window(0, 50, 10_000);
+        assert_eq!(first_page.page_to_load((0, 10)), Some((50, 50)));
+
+        let loaded = window(0, 300, 10_000);
+        assert_eq!(loaded.page_to_load((100, 120)), None);
+        assert_eq!(loaded.page_to_load((190, 210)), Some((300, 50)));
+
+        let scrolled = window(1000, 1000, 10_000);
+        assert_eq!(scrolled.page_to_load((1050, 1070)), Some((950, 50)));
+        assert_eq!(
+            scrolled.page_to_load((5025, 5040)),
+            Some((5000, 50)),
+            "a jump loads the page of the visible items"
+        );
+
+        let loading = PageWindow {
+            loading: true,
+            ..first_page
+        };
+        assert_eq!(loading.page_to_load((0, 10)), None);
+        assert_eq!(PageWindow::complete(70).page_to_load((60, 70)), None);
+        assert_eq!(window(0, 50, 60).page_to_load((40, 60)), Some((50, 50)));
+    }
+
+    #[test]
+    fn keeps_a_bounded_window() {
+        let mut loaded = window(0, 50, 10_000);
+        assert_eq!(
+            loaded.insert(50, 50, 10_000),
+            PageInsert::Append { drop_front: 0 }
+        );
+        assert_eq!(loaded, window(0, 100, 10_000));
+
+        let mut full = window(0, MAX_LOADED, 10_000);
+        assert_eq!(
+            full.insert(MAX_LOADED, 50, 10_000),
+            PageInsert::Append { drop_front: 50 }
+        );
+        assert_eq!(full, window(50, MAX_LOADED, 10_000));
+        assert_eq!(
+            full.insert(0, 50, 10_000),
+            PageInsert::Prepend { drop_back: 50 }
+        );
+        assert_eq!(full, window(0, MAX_LOADED, 10_000));
+
+        assert_eq!(full.insert(5000, 50, 10_001), PageInsert::Replace);
+        assert_eq!(full, window(5000, 50, 10_001));
+    }
+
+    #[test]
+    fn follows_added_and_removed_items() {
+        let mut first = window(0, 50, 100);
+        first.add_first();
+        assert_eq!(first, window(0, 51, 101));
+        first.remove(true);
+        assert_eq!(first, window(0, 50, 100));
+
+        let mut scrolled = window(100, 50, 500);
+        scrolled.add_first();
+        assert_eq!(scrolled, window(101, 50, 501));
+        scrolled.remove(false);
+        assert_eq!(scrolled, window(101, 50, 500));
+    }
+}
@@ -0,0 +1,160 @@
+use std::{cmp::Reverse, sync::Arc};
+
+use druid::{im::Vector, Command, Data, Lens};
+
+/// Most entries listed for a query.
+const MAX_RESULTS: usize = 50;
+
+/// Entry of the command palette, running `command` when chosen.
+#[derive(Clone, Data)]
+pub struct PaletteEntry {
+    pub title: Arc<str>,
+    /// Shown after the title, like the keys of a keybind.
+    pub detail: Arc<str>,
+    pub category: &'static str,
+    #[data(ignore)]
+    pub command: Command,
+}
+
+impl PaletteEntry {
+    pub fn new(category: &'static str, title: impl Into<Arc<str>>, command: Command) -> Self {
+        Self {
+            title: title.into(),
+            detail: "".into(),
+            category,
+            command,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<Arc<str>>) -> Self {
+        self.detail = detail.into();
+        self
+    }
+}
+
+#[derive(Clone, Default, Data, Lens)]
+pub struct CommandPalette {
+    pub show: bool,
+    pub query: String,
+    pub results: Vector<PaletteEntry>,
+    pub selected: usize,
+    /// Entries added by the controllers, next to the keybinds, pages,
+    /// playlists and searches the palette lists itself.
+    pub registered: Vector<PaletteEntry>,
+}
+
+impl CommandPalette {
+    /// Add an entry to the palette, replacing the one with the same category
+    /// and title.
+    pub fn register(&mut self, entry: PaletteEntry) {
+        self.registered
+            .retain(|e| e.category != entry.category || e.title != entry.title);
+        self.registered.push_back(entry);
+    }
+
+    pub fn open(&mut self) {
+        self.show = true;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.show = false;
+        self.results.clear();
+    }
+
+    /// List the `entries` matching the query, best first.
+    pub fn update_results(&mut self, entries: impl IntoIterator<Item = PaletteEntry>) {
+        self.results = rank(&self.query, entries, |entry| &entry.title)
+            .into_iter()
+            .take(MAX_RESULTS)
+            .collect();
+        self.selected = 0;
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = match self.selected {
+            0 => self.results.len().saturating_sub(1),
+            selected => selected - 1,
+        };
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = if self.selected + 1 < self.results.len() {
+            self.selected + 1
+        } else {
+            0
+        };
+    }
+
+    pub fn selected_entry(&self) -> Option<&PaletteEntry> {
+        self.results.get(self.selected)
+    }
+}
+
+/// Order the `items` whose text matches `query` by their score, keeping the
+/// order of the items for equal scores.
+fn rank<T>(query: &str, items: impl IntoIterator<Item = T>, text: impl Fn(&T) -> &str) -> Vec<T> {
+    let mut scored: Vec<(u32, T)> = items
+        .into_iter()
+        .filter_map(|item| fuzzy_score(query, text(&item)).map(|score| (score, item)))
+        .collect();
+    scored.sort_by_key(|(score, _)| Reverse(*score));
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Score of `text` for the fuzzy `query`, `None` unless all characters of the
+/// query appear in the text in order, ignoring case.  Characters starting a
+/// word or following the previous match score higher, so "st" ranks "Saved
+/// tracks" above "Last song".
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if previous_match.is_some_and(|previous| previous + 1 == found) {
+            score += 2;
+        }
+        previous_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_characters_in_order() {
+        assert!(fuzzy_score("svtr", "Saved tracks").is_some());
+        assert!(fuzzy_score("SAVED", "saved tracks").is_some());
+        assert!(fuzzy_score("go home", "Go to home").is_some());
+        assert_eq!(fuzzy_score("trs", "Saved tracks"), Some(8));
+        assert_eq!(fuzzy_score("rt", "Saved tracks"), None);
+        assert_eq!(fuzzy_score("", "Anything"), Some(0));
+    }
+
+    #[test]
+    fn ranks_word_starts_and_runs_first() {
+        assert_eq!(
+            rank(
+                "st",
+                ["Last song", "Saved tracks", "Stop", "Play"],
+                |item| item
+            ),
+            ["Saved tracks", "Stop", "Last song"]
+        );
+        assert_eq!(
+            rank("", ["b", "a"], |item| item),
+            ["b", "a"],
+            "without a query, the order is kept"
+        );
+    }
+}
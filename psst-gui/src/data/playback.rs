@@ -1,22 +1,54 @@
-use std::{fmt, sync::Arc, time::Duration};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use druid::{im::Vector, Data, Lens};
+use druid::{im::Vector, Data, Lens, LensExt};
 use druid_enums::Matcher;
-use psst_core::item_id::ItemId;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use psst_core::{
+    item_id::{ItemId, ItemIdType},
+    player::item::ItemGroup,
+};
 use serde::{Deserialize, Serialize};
 
 use super::{
-    AlbumLink, ArtistLink, Episode, Library, Nav, PlaylistLink, RecommendationsRequest, ShowLink,
-    Track,
+    AlbumLink, ArtistLink, Chapter, Episode, Library, Nav, PlaylistLink, RecommendationsRequest,
+    ShowLink, Track,
 };
 
+/// Going to the previous chapter in the first seconds of a chapter skips
+/// the current one.
+const PREVIOUS_CHAPTER_THRESHOLD: Duration = Duration::from_secs(3);
+
 #[derive(Clone, Data, Lens)]
 pub struct Playback {
     pub state: PlaybackState,
     pub now_playing: Option<NowPlaying>,
-    pub queue_behavior: QueueBehavior,
+    pub shuffle: Shuffle,
+    pub repeat: Repeat,
     pub queue: Vector<QueueEntry>,
+    /// Items the player will play after the current one, in play order.
+    pub up_next: Vector<QueueEntry>,
+    /// First item that was queued by autoplay, once the queue has run out.
+    #[data(same_fn = "PartialEq::eq")]
+    pub autoplay_from: Option<ItemId>,
     pub volume: f64,
+    /// Playback rate of the playing podcast episode, remembered for each
+    /// episode, see `Config::podcast_speed`.
+    pub podcast_speed: f64,
+    pub skip_silence: bool,
+    /// Playback on another Connect device we are acting as a remote for.
+    pub remote: Option<RemotePlayback>,
+    /// ID of the local audio device we play to, if the output knows it.
+    pub output_device: Option<Arc<str>>,
+    /// Latency the audio output reported, once playback has started.
+    pub output_latency: Option<Duration>,
 }
 
 #[derive(Clone, Debug, Data, Lens)]
@@ -25,6 +57,15 @@ pub struct QueueEntry {
     pub origin: PlaybackOrigin,
 }
 
+/// Where queued items go in the play queue.
+#[derive(Copy, Clone, Debug, Data, Eq, PartialEq)]
+pub enum QueuePlacement {
+    /// Right after the current item.
+    Next,
+    /// After the previously queued items.
+    Last,
+}
+
 #[derive(Clone, Debug, Matcher)]
 pub enum Playable {
     Track(Arc<Track>),
@@ -47,6 +88,22 @@ impl Playable {
         }
     }
 
+    /// Artist and album of the item, for smart shuffle.  Episodes are grouped
+    /// by their show.
+    pub fn item_group(&self) -> ItemGroup {
+        let parse = |id: &str| ItemId::from_base62(id, ItemIdType::Unknown);
+        match self {
+            Playable::Track(track) => ItemGroup {
+                artist: track.artists.front().and_then(|artist| parse(&artist.id)),
+                album: track.album.as_ref().and_then(|album| parse(&album.id)),
+            },
+            Playable::Episode(episode) => ItemGroup {
+                artist: parse(&episode.show.id),
+                album: None,
+            },
+        }
+    }
+
     pub fn name(&self) -> &Arc<str> {
         match self {
             Playable::Track(track) => &track.name,
@@ -61,6 +118,13 @@ impl Playable {
         }
     }
 
+    pub fn chapters(&self) -> Vector<Chapter> {
+        match self {
+            Playable::Track(_) => Vector::new(),
+            Playable::Episode(episode) => episode.chapters(),
+        }
+    }
+
     pub fn same(&self, other: &Self) -> bool {
         self.id() == other.id()
     }
@@ -73,12 +137,30 @@ impl Data for Playable {
 }
 
 #[derive(Default, Copy, Clone, Debug, Data, Eq, PartialEq, Serialize, Deserialize)]
-pub enum QueueBehavior {
+pub enum Shuffle {
     #[default]
-    Sequential,
+    Off,
     Random,
-    LoopTrack,
-    LoopAll,
+    Smart,
+}
+
+#[derive(Default, Copy, Clone, Debug, Data, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Repeat {
+    #[default]
+    Off,
+    All,
+    One,
+}
+
+impl Repeat {
+    /// Mode after this one, for the repeat button.
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::Off => Self::All,
+            Self::All => Self::One,
+            Self::One => Self::Off,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Data, Eq, PartialEq)]
@@ -94,6 +176,9 @@ pub struct NowPlaying {
     pub item: Playable,
     pub origin: PlaybackOrigin,
     pub progress: Duration,
+    pub chapters: Vector<Chapter>,
+    /// Format and quality tier of the played file, like "Ogg Vorbis 320 kbit/s".
+    pub quality: Option<Arc<str>>,
 
     // Although keeping a ref to the `Library` here is a bit of a hack, it dramatically
     // simplifies displaying the track context menu in the playback bar.
@@ -101,6 +186,54 @@ pub struct NowPlaying {
 }
 
 impl NowPlaying {
+    /// Short description of the item, like "Artist - Title".
+    pub fn title(&self) -> String {
+        match &self.item {
+            Playable::Track(track) => format!("{} - {}", track.artist_name(), track.name),
+            Playable::Episode(episode) => episode.name.to_string(),
+        }
+    }
+
+    /// Position in the item.  Unlike `progress`, which the app state only gets
+    /// every few seconds, this is up to date, see `PlaybackClock`.
+    pub fn position(&self) -> Duration {
+        PlaybackClock::global().position()
+    }
+
+    pub fn current_chapter(&self) -> Option<&Chapter> {
+        let position = self.position();
+        self.chapters
+            .iter()
+            .rev()
+            .find(|chapter| chapter.start <= position)
+    }
+
+    /// Start of the chapter after the current one, `None` in the last one.
+    pub fn next_chapter_start(&self) -> Option<Duration> {
+        let position = self.position();
+        self.chapters
+            .iter()
+            .map(|chapter| chapter.start)
+            .find(|&start| start > position)
+    }
+
+    /// Start of the current chapter, or of the previous one if the current
+    /// one has just started.
+    pub fn previous_chapter_start(&self) -> Option<Duration> {
+        let position = self.position();
+        let current = self
+            .chapters
+            .iter()
+            .rposition(|chapter| chapter.start <= position)?;
+        let just_started = position - self.chapters[current].start < PREVIOUS_CHAPTER_THRESHOLD;
+        let index = if just_started {
+            current.saturating_sub(1)
+        } else {
+            current
+        };
+        Some(self.chapters[index].start)
+    }
+
     pub fn cover_image_url(&self, width: f64, height: f64) -> Option<&str> {
         match &self.item {
             Playable::Track(track) => {
@@ -114,10 +247,33 @@ impl NowPlaying {
         }
     }
 
+    /// URL and size of the largest image of the item.  The Web API lists
+    /// images largest first, but doesn't promise to.  Of images without a
+    /// size, the first one is picked.
     pub fn cover_image_metadata(&self) -> Option<(&str, (u32, u32))> {
         match &self.item {
             Playable::Track(track) => track.album.as_ref().and_then(|album| {
-                album.images.get(0).map(|img| {
+                album
+                    .images
+                    .iter()
+                    .rev()
+                    .max_by_key(|img| img.width)
+                    .map(|img| {
+                        (
+                            &*img.url,
+                            (
+                                img.width.unwrap_or(0) as u32,
+                                img.height.unwrap_or(0) as u32,
+                            ),
+                        )
+                    })
+            }),
+            Playable::Episode(episode) => episode
+                .images
+                .iter()
+                .rev()
+                .max_by_key(|img| img.width)
+                .map(|img| {
                     (
                         &*img.url,
                         (
@@ -125,17 +281,7 @@ impl NowPlaying {
                             img.height.unwrap_or(0) as u32,
                         ),
                     )
-                })
-            }),
-            Playable::Episode(episode) => episode.images.get(0).map(|img| {
-                (
-                    &*img.url,
-                    (
-                        img.width.unwrap_or(0) as u32,
-                        img.height.unwrap_or(0) as u32,
-                    ),
-                )
-            }),
+                }),
         }
     }
 }
@@ -144,12 +290,17 @@ impl NowPlaying {
 pub enum PlaybackOrigin {
     Home,
     Library,
+    LocalFiles,
+    History,
+    SavedEpisodes,
     Album(AlbumLink),
     Artist(ArtistLink),
     Playlist(PlaylistLink),
     Show(ShowLink),
     Search(Arc<str>),
     Recommendations(Arc<RecommendationsRequest>),
+    /// Radio started from a track, an artist, or an album.
+    Radio(Arc<RecommendationsRequest>),
 }
 
 impl PlaybackOrigin {
@@ -157,12 +308,17 @@ impl PlaybackOrigin {
         match &self {
             PlaybackOrigin::Home => Nav::Home,
             PlaybackOrigin::Library => Nav::SavedTracks,
+            PlaybackOrigin::LocalFiles => Nav::LocalFiles,
+            PlaybackOrigin::History => Nav::History,
+            PlaybackOrigin::SavedEpisodes => Nav::SavedEpisodes,
             PlaybackOrigin::Album(link) => Nav::AlbumDetail(link.clone(), None),
             PlaybackOrigin::Artist(link) => Nav::ArtistDetail(link.clone()),
             PlaybackOrigin::Playlist(link) => Nav::PlaylistDetail(link.clone()),
             PlaybackOrigin::Show(link) => Nav::ShowDetail(link.clone()),
             PlaybackOrigin::Search(query) => Nav::SearchResults(query.clone()),
-            PlaybackOrigin::Recommendations(request) => Nav::Recommendations(request.clone()),
+            PlaybackOrigin::Recommendations(request) | PlaybackOrigin::Radio(request) => {
+                Nav::Recommendations(request.clone())
+            }
         }
     }
 }
@@ -172,12 +328,18 @@ impl fmt::Display for PlaybackOrigin {
         match &self {
             PlaybackOrigin::Home => f.write_str("Home"),
             PlaybackOrigin::Library => f.write_str("Saved Tracks"),
+            PlaybackOrigin::LocalFiles => f.write_str("Local Files"),
+            PlaybackOrigin::History => f.write_str("History"),
+            PlaybackOrigin::SavedEpisodes => f.write_str("Your Episodes"),
             PlaybackOrigin::Album(link) => link.name.fmt(f),
             PlaybackOrigin::Artist(link) => link.name.fmt(f),
             PlaybackOrigin::Playlist(link) => link.name.fmt(f),
             PlaybackOrigin::Show(link) => link.name.fmt(f),
             PlaybackOrigin::Search(query) => query.fmt(f),
             PlaybackOrigin::Recommendations(_) => f.write_str("Recommended"),
+            PlaybackOrigin::Radio(request) => {
+                f.write_str(request.title.as_deref().unwrap_or("Radio"))
+            }
         }
     }
 }
@@ -187,4 +349,149 @@ pub struct PlaybackPayload {
     pub origin: PlaybackOrigin,
     pub items: Vector<Playable>,
     pub position: usize,
+    /// Seek to this position once playback starts.
+    pub seek: Option<Duration>,
+}
+
+/// A Spotify Connect device, as listed by the Web API.
+#[derive(Clone, Debug, Data, Lens, Deserialize)]
+pub struct ConnectDevice {
+    pub id: Option<Arc<str>>,
+    pub name: Arc<str>,
+    #[serde(rename = "type")]
+    pub kind: Arc<str>,
+    pub is_active: bool,
+    pub volume_percent: Option<u32>,
+}
+
+/// Playback state of the currently active Connect device.
+#[derive(Clone, Debug, Data, Lens, Deserialize)]
+pub struct RemotePlayback {
+    pub device: ConnectDevice,
+    pub context: Option<RemoteContext>,
+    pub item: Option<Arc<Track>>,
+    #[serde(default)]
+    pub progress_ms: u64,
+    pub is_playing: bool,
+}
+
+#[derive(Clone, Debug, Data, Deserialize)]
+pub struct RemoteContext {
+    pub uri: Arc<str>,
+}
+
+impl RemotePlayback {
+    pub fn volume() -> impl Lens<Self, f64> {
+        Self::device.then(ConnectDevice::volume_percent).map(
+            |percent| percent.unwrap_or(0) as f64 / 100.0,
+            |percent, volume| *percent = Some((volume * 100.0).round() as u32),
+        )
+    }
+}
+
+/// Transport and volume commands sent to a remote Connect device.
+#[derive(Clone, Copy, Debug, Data)]
+pub enum RemoteCommand {
+    Resume,
+    Pause,
+    Previous,
+    Next,
+    SetVolume(u32),
+}
+
+/// Position of the playing item, kept outside of the app state.  Position
+/// reports of the player reach the app state only every few seconds, because
+/// every change of it is diffed through the whole widget tree.  The seek bar
+/// and the time label read the position from here on their own timers.
+pub struct PlaybackClock {
+    state: Mutex<ClockState>,
+    reports: AtomicU64,
+    applied: AtomicU64,
+    repaints: AtomicU64,
+}
+
+struct ClockState {
+    position: Duration,
+    reported_at: Instant,
+    running: bool,
+}
+
+/// Counters of the position updates, shown in the diagnostics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClockStats {
+    /// Position reports received from the player.
+    pub reports: u64,
+    /// Reports that were applied to the app state.
+    pub applied: u64,
+    /// Repaints of the seek bar and the time label.
+    pub repaints: u64,
+}
+
+/// Extrapolate the position at most this far past the last report, in case
+/// the player stalls.
+const MAX_EXTRAPOLATION: Duration = Duration::from_secs(2);
+
+static CLOCK: Lazy<PlaybackClock> = Lazy::new(|| PlaybackClock {
+    state: Mutex::new(ClockState {
+        position: Duration::ZERO,
+        reported_at: Instant::now(),
+        running: false,
+    }),
+    reports: AtomicU64::new(0),
+    applied: AtomicU64::new(0),
+    repaints: AtomicU64::new(0),
+});
+
+impl PlaybackClock {
+    pub fn global() -> &'static Self {
+        &CLOCK
+    }
+
+    pub fn set(&self, position: Duration, running: bool) {
+        *self.state.lock() = ClockState {
+            position,
+            reported_at: Instant::now(),
+            running,
+        };
+    }
+
+    /// Stop or start advancing from the current position.
+    pub fn set_running(&self, running: bool) {
+        let position = self.position();
+        self.set(position, running);
+    }
+
+    /// The last reported position, advanced by the time since the report
+    /// while playing.
+    pub fn position(&self) -> Duration {
+        let state = self.state.lock();
+        if state.running {
+            state.position + state.reported_at.elapsed().min(MAX_EXTRAPOLATION)
+        } else {
+            state.position
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.lock().running
+    }
+
+    pub fn count_report(&self, applied: bool) {
+        self.reports.fetch_add(1, Ordering::Relaxed);
+        if applied {
+            self.applied.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn count_repaint(&self) {
+        self.repaints.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> ClockStats {
+        ClockStats {
+            reports: self.reports.load(Ordering::Relaxed),
+            applied: self.applied.load(Ordering::Relaxed),
+            repaints: self.repaints.load(Ordering::Relaxed),
+        }
+    }
 }
@@ -1,21 +1,118 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use druid::{im::Vector, Data, Lens};
+use psst_core::offline::SyncStatus;
 use serde::{Deserialize, Deserializer, Serialize};
+use time::Date;
 
 use crate::data::utils::sanitize_html_string;
-use crate::data::{user::PublicUser, Image, Promise, Track, TrackId};
+use crate::data::{
+    user::{PublicUser, PublicUserProfile},
+    FindQuery, Image, MatchFindQuery, Promise, Track, TrackId,
+};
 
 #[derive(Clone, Debug, Data, Lens)]
 pub struct PlaylistDetail {
     pub playlist: Promise<Playlist, PlaylistLink>,
     pub tracks: Promise<PlaylistTracks, PlaylistLink>,
+    pub edit: Option<PlaylistEdit>,
 }
 
-#[derive(Clone, Debug, Data, Lens, Deserialize)]
-pub struct PlaylistAddTrack {
+/// Name and description of an owned playlist, while being edited in the
+/// playlist header.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct PlaylistEdit {
+    pub id: Arc<str>,
+    pub name: String,
+    pub description: String,
+}
+
+impl PlaylistEdit {
+    pub fn new(playlist: &Playlist) -> Self {
+        Self {
+            id: playlist.id.clone(),
+            name: playlist.name.to_string(),
+            description: playlist.description.to_string(),
+        }
+    }
+
+    pub fn link(&self) -> PlaylistLink {
+        PlaylistLink {
+            id: self.id.clone(),
+            name: self.name.trim().into(),
+        }
+    }
+}
+
+/// Playlist to create for the user.
+#[derive(Clone, Debug, Data)]
+pub struct NewPlaylist {
+    pub owner: PublicUser,
+    pub name: Arc<str>,
+}
+
+impl NewPlaylist {
+    /// Id of the placeholder, see `placeholder`.
+    pub fn placeholder_id(&self) -> Arc<str> {
+        format!("new:{}", self.name).into()
+    }
+
+    /// Stand-in listed in the library until Spotify returns the created
+    /// playlist.
+    pub fn placeholder(&self) -> Playlist {
+        Playlist {
+            id: self.placeholder_id(),
+            name: self.name.clone(),
+            images: None,
+            description: "".into(),
+            track_count: Some(0),
+            owner: self.owner.clone(),
+            collaborative: false,
+            public: Some(false),
+            snapshot_id: "".into(),
+        }
+    }
+}
+
+/// Playlist marked for offline listening.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OfflinePlaylist {
+    /// Snapshot of the playlist when it was last downloaded completely.
+    pub synced_snapshot: Option<Arc<str>>,
+    /// Items downloaded and total of the running download.
+    #[serde(skip)]
+    pub progress: Option<(usize, usize)>,
+}
+
+/// Playlists marked for offline listening, by playlist ID.
+pub type OfflinePlaylists = HashMap<Arc<str>, OfflinePlaylist>;
+
+impl OfflinePlaylist {
+    pub fn status(&self, playlist: &Playlist) -> SyncStatus {
+        SyncStatus::of(
+            self.synced_snapshot.as_deref(),
+            &playlist.snapshot_id,
+            self.progress,
+        )
+    }
+}
+
+#[derive(Clone, Debug, Data, Lens)]
+pub struct PlaylistAddTracks {
     pub link: PlaylistLink,
-    pub track_id: TrackId,
+    pub track_ids: Vector<TrackId>,
+}
+
+/// Tracks appended to a playlist, to take out again with undo.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct PlaylistUndoAdd {
+    pub link: PlaylistLink,
+    /// Version of the playlist the tracks were appended in, the positions
+    /// refer to it whatever changed since.
+    pub snapshot_id: Arc<str>,
+    pub uris: Vector<Arc<str>>,
+    /// Position of the first appended track.
+    pub position: usize,
 }
 
 #[derive(Clone, Debug, Data, Lens, Deserialize)]
@@ -24,6 +121,57 @@ pub struct PlaylistRemoveTrack {
     pub track_pos: usize,
 }
 
+/// Tracks of a playlist the user keeps skipping, offered for removal.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct SkipReview {
+    pub link: PlaylistLink,
+    pub tracks: Vector<SkippedTrack>,
+}
+
+impl SkipReview {
+    /// The tracks still ticked for removal.
+    pub fn removed(&self) -> Vector<SkippedTrack> {
+        self.tracks.iter().filter(|t| t.remove).cloned().collect()
+    }
+}
+
+#[derive(Clone, Debug, Data, Lens)]
+pub struct SkippedTrack {
+    pub uri: Arc<str>,
+    pub name: Arc<str>,
+    pub artist: Arc<str>,
+    pub skip_count: usize,
+    pub remove: bool,
+}
+
+/// Move of the track at `from` so that it ends up at `to`.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct PlaylistMoveTrack {
+    pub link: PlaylistLink,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl PlaylistMoveTrack {
+    /// Position the track is inserted before, in the playlist with the track
+    /// still at `from`, as the Web API expects it.
+    pub fn insert_before(&self) -> usize {
+        if self.to > self.from {
+            self.to + 1
+        } else {
+            self.to
+        }
+    }
+
+    pub fn undo(&self) -> Self {
+        Self {
+            link: self.link.clone(),
+            from: self.to,
+            to: self.from,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Data, Lens, Deserialize)]
 pub struct Playlist {
     pub id: Arc<str>,
@@ -39,6 +187,16 @@ pub struct Playlist {
     pub collaborative: bool,
     #[serde(rename = "public")]
     pub public: Option<bool>,
+    /// Version of the playlist, changes with every edit.  Empty when not
+    /// known.
+    #[serde(default = "super::utils::default_str")]
+    pub snapshot_id: Arc<str>,
+}
+
+impl MatchFindQuery for Playlist {
+    fn matches_query(&self, query: &FindQuery) -> bool {
+        query.matches_str(&self.name) || query.matches_str(&self.owner.display_name)
+    }
 }
 
 impl Playlist {
@@ -58,6 +216,47 @@ impl Playlist {
     pub fn url(&self) -> String {
         format!("https://open.spotify.com/playlist/{id}", id = self.id)
     }
+
+    pub fn visibility(&self) -> PlaylistVisibility {
+        if self.collaborative {
+            PlaylistVisibility::Collaborative
+        } else if self.public == Some(true) {
+            PlaylistVisibility::Public
+        } else {
+            PlaylistVisibility::Private
+        }
+    }
+
+    pub fn set_visibility(&mut self, visibility: PlaylistVisibility) {
+        self.public = Some(visibility.is_public());
+        self.collaborative = visibility == PlaylistVisibility::Collaborative;
+    }
+}
+
+/// Who can see and edit a playlist owned by the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Data)]
+pub enum PlaylistVisibility {
+    Private,
+    Public,
+    /// Editable by everyone the user shares it with.  Collaborative
+    /// playlists cannot be public.
+    Collaborative,
+}
+
+impl PlaylistVisibility {
+    pub const ALL: [Self; 3] = [Self::Private, Self::Public, Self::Collaborative];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Private => "Private",
+            Self::Public => "Public",
+            Self::Collaborative => "Collaborative",
+        }
+    }
+
+    pub fn is_public(self) -> bool {
+        self == Self::Public
+    }
 }
 
 #[derive(Clone, Debug, Data, Lens)]
@@ -74,6 +273,45 @@ impl PlaylistTracks {
             name: self.name.clone(),
         }
     }
+
+    /// Move a track, see `PlaylistMoveTrack`.  The tracks have to be in the
+    /// order of the playlist.
+    pub fn move_track(&mut self, from: usize, to: usize) {
+        if from >= self.tracks.len() || to >= self.tracks.len() {
+            return;
+        }
+        let track = self.tracks.remove(from);
+        self.tracks.insert(to, track);
+        for (pos, track) in self.tracks.iter_mut().enumerate() {
+            if track.track_pos != pos {
+                Arc::make_mut(track).track_pos = pos;
+            }
+        }
+    }
+
+    /// Users who added the tracks, in the order of their first track, with
+    /// the count of tracks each added.  Empty unless the playlist has more
+    /// than one contributor.
+    pub fn members(&self) -> Vector<(Arc<PublicUserProfile>, usize)> {
+        let mut members: Vector<(Arc<PublicUserProfile>, usize)> = Vector::new();
+        for user in self
+            .tracks
+            .iter()
+            .filter_map(|track| track.added_by.as_ref())
+        {
+            match members.iter_mut().find(|(member, _)| member.id == user.id) {
+                Some((_, count)) => *count += 1,
+                None => members.push_back((user.clone(), 1)),
+            }
+        }
+        members
+    }
+
+    /// Date the most recent track was added, the closest to a modification
+    /// date the API offers.
+    pub fn last_updated(&self) -> Option<Date> {
+        self.tracks.iter().filter_map(|track| track.added_at).max()
+    }
 }
 
 #[derive(Clone, Debug, Data, Lens, Eq, PartialEq, Hash, Deserialize, Serialize)]
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use druid::{
     im::{vector, Vector},
@@ -6,7 +6,7 @@ use druid::{
 };
 use serde::{Deserialize, Serialize};
 
-use super::{ArtistLink, Float64, Promise, Track, TrackId};
+use super::{Album, ArtistLink, Float64, Promise, Track, TrackId};
 
 #[derive(Clone, Data, Lens)]
 pub struct Recommend {
@@ -14,12 +14,23 @@ pub struct Recommend {
     pub results: Promise<Recommendations, Arc<RecommendationsRequest>>,
 }
 
+/// Most seeds the recommendations endpoint takes in one request, artists and
+/// tracks together.
+pub const MAX_SEEDS_PER_REQUEST: usize = 5;
+
+/// Tracks a playlist radio is seeded with.  They are sent in several requests,
+/// which makes for a longer and more varied list.
+pub const PLAYLIST_RADIO_SEEDS: usize = 15;
+
 #[derive(Clone, Debug, Default, Data, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct RecommendationsRequest {
     pub seed_artists: Vector<ArtistLink>,
     pub seed_tracks: Vector<TrackId>,
     #[serde(skip)]
     pub params: RecommendationsParams,
+    /// Title of the page, like the name of the playlist of a radio.
+    #[serde(default)]
+    pub title: Option<Arc<str>>,
 }
 
 impl RecommendationsRequest {
@@ -30,6 +41,91 @@ impl RecommendationsRequest {
         }
     }
 
+    /// Radio of a playlist, seeded with tracks picked by `radio_seeds`.
+    pub fn for_playlist(name: Arc<str>, tracks: &[Arc<Track>]) -> Self {
+        Self {
+            seed_tracks: radio_seeds(tracks, PLAYLIST_RADIO_SEEDS).into(),
+            title: Some(format!("{name} Radio").into()),
+            ..Self::default()
+        }
+    }
+
+    /// Radio of a track, seeded with the track.
+    pub fn track_radio(track: &Track) -> Self {
+        Self {
+            seed_tracks: vector![track.id],
+            title: Some(format!("{} Radio", track.name).into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn artist_radio(artist: ArtistLink) -> Self {
+        Self {
+            title: Some(format!("{} Radio", artist.name).into()),
+            seed_artists: vector![artist],
+            ..Self::default()
+        }
+    }
+
+    /// Radio of an album, seeded with tracks picked by `radio_seeds`, or with
+    /// its artists when the tracks are not loaded.
+    pub fn album_radio(album: &Album) -> Self {
+        let tracks: Vec<_> = album.tracks.iter().cloned().collect();
+        let seed_tracks: Vector<_> = radio_seeds(&tracks, MAX_SEEDS_PER_REQUEST).into();
+        let seed_artists = if seed_tracks.is_empty() {
+            album
+                .artists
+                .iter()
+                .take(MAX_SEEDS_PER_REQUEST)
+                .cloned()
+                .collect()
+        } else {
+            Vector::new()
+        };
+        Self {
+            seed_artists,
+            seed_tracks,
+            title: Some(format!("{} Radio", album.name).into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn seed_count(&self) -> usize {
+        self.seed_artists.len() + self.seed_tracks.len()
+    }
+
+    /// Split into requests with at most `MAX_SEEDS_PER_REQUEST` seeds, and the
+    /// same params.
+    pub fn split_seeds(&self) -> Vec<Self> {
+        if self.seed_count() <= MAX_SEEDS_PER_REQUEST {
+            return vec![self.clone()];
+        }
+        let mut artists = self.seed_artists.iter().cloned();
+        let mut tracks = self.seed_tracks.iter().copied();
+        let mut requests = Vec::new();
+        loop {
+            let mut request = Self {
+                params: self.params.clone(),
+                title: self.title.clone(),
+                ..Self::default()
+            };
+            while request.seed_count() < MAX_SEEDS_PER_REQUEST {
+                if let Some(artist) = artists.next() {
+                    request.seed_artists.push_back(artist);
+                } else if let Some(track) = tracks.next() {
+                    request.seed_tracks.push_back(track);
+                } else {
+                    break;
+                }
+            }
+            if request.seed_count() == 0 {
+                break;
+            }
+            requests.push(request);
+        }
+        requests
+    }
+
     pub fn with_params(mut self, params: RecommendationsParams) -> Self {
         self.params = params;
         self
@@ -163,3 +259,170 @@ pub enum RecommendationsSeedType {
     Track,
     Genre,
 }
+
+impl Recommendations {
+    /// Combine the results of the requests `request` was split into.  Tracks are
+    /// taken from each result in turn, so that every seed is represented
+    /// throughout the list.  Duplicates and the seeds themselves are left out.
+    pub fn merge(request: Arc<RecommendationsRequest>, parts: Vec<Self>) -> Self {
+        let mut seen: HashSet<TrackId> = request.seed_tracks.iter().copied().collect();
+        let mut seeds = Vector::new();
+        let mut lists = Vec::new();
+        for part in parts {
+            seeds.extend(part.seeds);
+            lists.push(part.tracks.into_iter());
+        }
+        let mut tracks = Vector::new();
+        loop {
+            let mut exhausted = true;
+            for list in &mut lists {
+                if let Some(track) = list.next() {
+                    exhausted = false;
+                    if seen.insert(track.id) {
+                        tracks.push_back(track);
+                    }
+                }
+            }
+            if exhausted {
+                break;
+            }
+        }
+        Self {
+            request,
+            seeds,
+            tracks,
+        }
+    }
+}
+
+/// Pick up to `count` tracks that represent `tracks`: one from each evenly
+/// sized section of the list, preferring artists not picked yet.  Local and
+/// unplayable tracks cannot seed recommendations.
+pub fn radio_seeds(tracks: &[Arc<Track>], count: usize) -> Vec<TrackId> {
+    let candidates: Vec<_> = tracks
+        .iter()
+        .filter(|track| !track.is_local && track.is_playable != Some(false))
+        .collect();
+    let count = count.min(candidates.len());
+    let mut artists = HashSet::new();
+    let mut seeds = Vec::with_capacity(count);
+    for section in 0..count {
+        let start = section * candidates.len() / count;
+        let end = (section + 1) * candidates.len() / count;
+        let section = &candidates[start..end];
+        let track = section
+            .iter()
+            .find(|track| {
+                !track
+                    .artists
+                    .front()
+                    .is_some_and(|artist| artists.contains(&artist.id))
+            })
+            .unwrap_or(&section[0]);
+        if let Some(artist) = track.artists.front() {
+            artists.insert(artist.id.clone());
+        }
+        seeds.push(track.id);
+    }
+    seeds
+}
+
+#[cfg(test)]
+mod tests {
+    use psst_core::item_id::{ItemId, ItemIdType};
+
+    use super::*;
+
+    fn track(id: u128, artist: &str) -> Arc<Track> {
+        let json = serde_json::json!({
+            "id": ItemId::new(id, ItemIdType::Track).to_base62(),
+            "name": format!("Track {id}"),
+            "artists": [{ "id": artist, "name": artist }],
+            "duration_ms": 180_000,
+            "disc_number": 1,
+            "track_number": 1,
+            "explicit": false,
+            "is_local": false,
+        });
+        Arc::new(serde_json::from_value(json).unwrap())
+    }
+
+    fn track_id(id: u128) -> TrackId {
+        TrackId(ItemId::new(id, ItemIdType::Track))
+    }
+
+    #[test]
+    fn radio_seeds_spread_over_playlist_and_artists() {
+        let tracks: Vec<_> = (0..12)
+            .map(|id| track(id, if id % 3 == 0 { "a" } else { "b" }))
+            .collect();
+
+        // One seed from each third of the playlist, the second and third not by
+        // an artist picked before.
+        let seeds = radio_seeds(&tracks, 3);
+        assert_eq!(seeds, vec![track_id(0), track_id(4), track_id(8)]);
+
+        assert_eq!(radio_seeds(&tracks[..2], 5).len(), 2);
+    }
+
+    #[test]
+    fn album_radio_falls_back_to_artists() {
+        let json = serde_json::json!({
+            "id": "album",
+            "name": "Album",
+            "album_type": "album",
+            "artists": [{ "id": "a", "name": "A" }, { "id": "b", "name": "B" }],
+            "release_date": null,
+        });
+        let mut album: Album = serde_json::from_value(json).unwrap();
+        let request = RecommendationsRequest::album_radio(&album);
+        assert_eq!(request.seed_artists.len(), 2);
+        assert!(request.seed_tracks.is_empty());
+        assert_eq!(request.title.as_deref(), Some("Album Radio"));
+
+        album.tracks = (0..8).map(|id| track(id, "a")).collect();
+        let request = RecommendationsRequest::album_radio(&album);
+        assert!(request.seed_artists.is_empty());
+        assert_eq!(request.seed_tracks.len(), MAX_SEEDS_PER_REQUEST);
+    }
+
+    #[test]
+    fn requests_are_split_by_seed_limit() {
+        let request = RecommendationsRequest {
+            seed_tracks: (0..12).map(track_id).collect(),
+            ..RecommendationsRequest::default()
+        };
+        let split = request.split_seeds();
+        let sizes: Vec<_> = split.iter().map(|r| r.seed_count()).collect();
+        assert_eq!(sizes, vec![5, 5, 2]);
+        assert_eq!(split[2].seed_tracks, vector![track_id(10), track_id(11)]);
+    }
+
+    #[test]
+    fn merged_results_alternate_and_skip_duplicates() {
+        let request = Arc::new(RecommendationsRequest {
+            seed_tracks: vector![track_id(1)],
+            ..RecommendationsRequest::default()
+        });
+        let part = |ids: &[u128]| Recommendations {
+            request: request.clone(),
+            seeds: Vector::new(),
+            tracks: ids.iter().map(|&id| track(id, "a")).collect(),
+        };
+        let merged = Recommendations::merge(
+            request.clone(),
+            vec![part(&[10, 11, 12]), part(&[20, 10, 1, 21])],
+        );
+        let ids: Vec<_> = merged.tracks.iter().map(|track| track.id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                track_id(10),
+                track_id(20),
+                track_id(11),
+                track_id(12),
+                track_id(21)
+            ]
+        );
+    }
+}
@@ -4,10 +4,95 @@ use druid::{im::Vector, Data, Lens};
 
 use crate::data::{Album, Artist, Playlist, Promise, Show, Track};
 
+/// Recent searches shown in the dropdown of the search box.
+const DROPDOWN_RECENT: usize = 5;
+/// Suggestions shown in the dropdown of the search box.
+const DROPDOWN_SUGGESTIONS: usize = 8;
+
 #[derive(Clone, Data, Lens)]
 pub struct Search {
     pub input: String,
     pub results: Promise<SearchResults, Arc<str>>,
+    /// Names matching the input, loaded while typing.
+    pub suggestions: Promise<Vector<Arc<str>>, Arc<str>>,
+    /// The dropdown under the search box is open.
+    pub dropdown: bool,
+    /// Entry of the dropdown selected with the arrow keys.
+    pub selected: Option<usize>,
+}
+
+/// Entry of the dropdown under the search box.
+#[derive(Clone, Data, Lens)]
+pub struct SearchEntry {
+    pub query: Arc<str>,
+    /// The query was searched before, rather than suggested.
+    pub recent: bool,
+}
+
+impl Search {
+    /// Trimmed input, the query the suggestions are loaded for.
+    pub fn query(&self) -> Arc<str> {
+        self.input.trim().into()
+    }
+
+    /// Recent searches matching the input, followed by the suggestions for
+    /// it.
+    pub fn entries(&self, history: &Vector<Arc<str>>) -> Vector<SearchEntry> {
+        let query = self.query();
+        let needle = query.to_lowercase();
+        let recent = history
+            .iter()
+            .filter(|recent| recent.to_lowercase().contains(&needle))
+            .take(DROPDOWN_RECENT)
+            .map(|recent| SearchEntry {
+                query: recent.clone(),
+                recent: true,
+            });
+        let mut entries: Vector<SearchEntry> = recent.collect();
+        let suggestions = self
+            .suggestions
+            .resolved()
+            .filter(|_| self.suggestions.contains(&query));
+        if let Some(suggestions) = suggestions {
+            entries.extend(
+                suggestions
+                    .iter()
+                    .filter(|suggestion| {
+                        !history
+                            .iter()
+                            .any(|recent| recent.eq_ignore_ascii_case(suggestion))
+                    })
+                    .map(|suggestion| SearchEntry {
+                        query: suggestion.clone(),
+                        recent: false,
+                    }),
+            );
+        }
+        entries
+    }
+
+    pub fn select_next(&mut self, count: usize) {
+        self.selected = match self.selected {
+            _ if count == 0 => None,
+            Some(index) if index + 1 < count => Some(index + 1),
+            Some(_) => Some(count - 1),
+            None => Some(0),
+        };
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.and_then(|index| index.checked_sub(1));
+    }
+
+    pub fn open_dropdown(&mut self) {
+        self.dropdown = true;
+        self.selected = None;
+    }
+
+    pub fn close_dropdown(&mut self) {
+        self.dropdown = false;
+        self.selected = None;
+    }
 }
 
 #[derive(Copy, Clone, Data, Eq, PartialEq)]
@@ -50,3 +135,27 @@ pub struct SearchResults {
     pub playlists: Vector<Playlist>,
     pub shows: Vector<Arc<Show>>,
 }
+
+impl SearchResults {
+    /// Names of the artists, tracks and albums found, for the dropdown of the
+    /// search box.
+    pub fn suggestions(&self) -> Vector<Arc<str>> {
+        let mut suggestions: Vector<Arc<str>> = Vector::new();
+        let names = self
+            .artists
+            .iter()
+            .map(|artist| &artist.name)
+            .chain(self.tracks.iter().map(|track| &track.name))
+            .chain(self.albums.iter().map(|album| &album.name));
+        for name in names {
+            if !suggestions
+                .iter()
+                .any(|suggestion| suggestion.eq_ignore_ascii_case(name))
+            {
+                suggestions.push_back(name.clone());
+            }
+        }
+        suggestions.truncate(DROPDOWN_SUGGESTIONS);
+        suggestions
+    }
+}
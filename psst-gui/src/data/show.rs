@@ -1,11 +1,19 @@
 use std::{convert::TryFrom, sync::Arc, time::Duration};
 
 use druid::{im::Vector, Data, Lens};
-use psst_core::item_id::{ItemId, ItemIdType};
+use once_cell::sync::Lazy;
+use psst_core::{
+    cache::EpisodeProgress,
+    item_id::{ItemId, ItemIdType},
+};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use time::{macros::format_description, Date};
+use time::Date;
 
-use crate::data::{Image, Promise};
+use crate::{
+    data::{FindQuery, Image, MatchFindQuery, Promise},
+    format,
+};
 
 use super::album::DatePrecision;
 
@@ -13,6 +21,7 @@ use super::album::DatePrecision;
 pub struct ShowDetail {
     pub show: Promise<Arc<Show>, ShowLink>,
     pub episodes: Promise<ShowEpisodes, ShowLink>,
+    pub following: Promise<bool, ShowLink>,
 }
 
 #[derive(Clone, Data, Lens, Deserialize)]
@@ -38,6 +47,12 @@ impl Show {
     }
 }
 
+impl MatchFindQuery for Show {
+    fn matches_query(&self, query: &FindQuery) -> bool {
+        query.matches_str(&self.name) || query.matches_str(&self.publisher)
+    }
+}
+
 #[derive(Clone, Data, Lens)]
 pub struct ShowEpisodes {
     pub show: ShowLink,
@@ -88,12 +103,94 @@ impl Episode {
     }
 
     pub fn release(&self) -> String {
-        let format = format_description!("[month repr:short] [day], [year]");
         self.release_date
-            .as_ref()
-            .map(|date| date.format(format).expect("Invalid format"))
+            .map(format::date)
             .unwrap_or_else(|| '-'.to_string())
     }
+
+    /// Position to continue listening from, `None` if the episode was not
+    /// started or was already played through.
+    pub fn resume_position(&self) -> Option<Duration> {
+        self.resume_point
+            .as_ref()
+            .filter(|point| !point.fully_played && !point.resume_position.is_zero())
+            .map(|point| point.resume_position)
+    }
+
+    /// Chapters listed in the description, like `12:34 Interview`.
+    pub fn chapters(&self) -> Vector<Chapter> {
+        Chapter::parse(&self.description, self.duration)
+    }
+}
+
+/// Longest chapter title kept, descriptions without line breaks would
+/// otherwise run into the last title.
+const MAX_CHAPTER_TITLE: usize = 60;
+
+/// `[h:]mm:ss`, optionally in parentheses.
+static CHAPTER_TIMESTAMP: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\(?\b(?:(\d{1,2}):)?(\d{1,2}):([0-5]\d)\b\)?").unwrap());
+
+#[derive(Clone, Debug, Data, Lens, Eq, PartialEq)]
+pub struct Chapter {
+    pub start: Duration,
+    pub title: Arc<str>,
+}
+
+impl Chapter {
+    /// Parse a chapter list out of free text.  The list starts at the first
+    /// `0:00` timestamp, if there is one, and ends at the first timestamp that
+    /// is not after the previous one.  A single timestamp is not a list.
+    fn parse(text: &str, duration: Duration) -> Vector<Self> {
+        let stamps: Vec<_> = CHAPTER_TIMESTAMP.captures_iter(text).collect();
+        let start_of = |index: usize| {
+            let field = |n| {
+                stamps[index]
+                    .get(n)
+                    .map_or(0, |m| m.as_str().parse::<u64>().unwrap_or(0))
+            };
+            Duration::from_secs(field(1) * 3600 + field(2) * 60 + field(3))
+        };
+        let first = (0..stamps.len())
+            .find(|&index| start_of(index).is_zero())
+            .unwrap_or(0);
+
+        let mut chapters = Vector::new();
+        for index in first..stamps.len() {
+            let start = start_of(index);
+            let past_end = !duration.is_zero() && start >= duration;
+            if past_end
+                || chapters
+                    .last()
+                    .is_some_and(|last: &Chapter| last.start >= start)
+            {
+                break;
+            }
+            let title_start = stamps[index].get(0).unwrap().end();
+            let title_end = stamps
+                .get(index + 1)
+                .map_or(text.len(), |next| next.get(0).unwrap().start());
+            let title = text[title_start..title_end]
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim_matches(|c: char| c.is_whitespace() || "-–—|:,;".contains(c));
+            let title: Arc<str> = if title.is_empty() {
+                format!("Chapter {}", chapters.len() + 1).into()
+            } else if title.chars().count() > MAX_CHAPTER_TITLE {
+                let cut: String = title.chars().take(MAX_CHAPTER_TITLE).collect();
+                format!("{}…", cut.trim_end()).into()
+            } else {
+                title.into()
+            };
+            chapters.push_back(Chapter { start, title });
+        }
+        if chapters.len() < 2 {
+            Vector::new()
+        } else {
+            chapters
+        }
+    }
 }
 
 #[derive(Clone, Debug, Data, Lens, Deserialize)]
@@ -110,6 +207,15 @@ pub struct ResumePoint {
     pub resume_position: Duration,
 }
 
+impl From<EpisodeProgress> for ResumePoint {
+    fn from(progress: EpisodeProgress) -> Self {
+        Self {
+            fully_played: progress.fully_played,
+            resume_position: progress.position(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Hash, Deserialize, Serialize)]
 #[serde(try_from = "String")]
 #[serde(into = "String")]
@@ -136,3 +242,36 @@ impl From<EpisodeId> for String {
         id.0.to_base62()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn chapters_are_parsed_from_timestamps() {
+        let text = "Our guest tells all.\n\n(00:00) Intro\n05:30 - The interview\n1:02:03 Outro";
+        let chapters = Chapter::parse(text, 2 * HOUR);
+
+        let parsed: Vec<_> = chapters
+            .iter()
+            .map(|c| (c.start.as_secs(), c.title.as_ref()))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![(0, "Intro"), (330, "The interview"), (3723, "Outro")]
+        );
+    }
+
+    #[test]
+    fn chapters_need_an_ascending_list() {
+        // A lone mention of a time is not a chapter list.
+        assert!(Chapter::parse("We start at 10:00 sharp.", HOUR).is_empty());
+        // The list starts at zero and stops at the first step back.
+        let text = "At 45:00 we digress. 0:00 Start 12:00 Middle 03:00 Aside";
+        let chapters = Chapter::parse(text, HOUR);
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[1].title.as_ref(), "Middle");
+    }
+}
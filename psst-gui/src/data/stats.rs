@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use druid::{im::Vector, Data, Lens};
+use psst_core::history::PlayHistory;
+use time::OffsetDateTime;
+
+use crate::{
+    data::{Artist, Promise, Track},
+    notifications,
+};
+
+/// Days shown in the chart of the daily plays.
+const ACTIVITY_DAYS: usize = 30;
+
+#[derive(Clone, Data, Lens)]
+pub struct Stats {
+    pub range: TimeRange,
+    pub top_tracks: Promise<Vector<Arc<Track>>, TimeRange>,
+    pub top_artists: Promise<Vector<Artist>, TimeRange>,
+    pub activity: ListeningActivity,
+}
+
+/// Period the top tracks and artists are computed over by Spotify.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Data)]
+pub enum TimeRange {
+    /// About the last four weeks.
+    ShortTerm,
+    /// About the last six months.
+    MediumTerm,
+    /// About the last year.
+    LongTerm,
+}
+
+impl TimeRange {
+    pub const ALL: [Self; 3] = [Self::ShortTerm, Self::MediumTerm, Self::LongTerm];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ShortTerm => "Last 4 Weeks",
+            Self::MediumTerm => "Last 6 Months",
+            Self::LongTerm => "Last Year",
+        }
+    }
+
+    /// Value of the `time_range` parameter of the Web API.
+    pub fn api_value(self) -> &'static str {
+        match self {
+            Self::ShortTerm => "short_term",
+            Self::MediumTerm => "medium_term",
+            Self::LongTerm => "long_term",
+        }
+    }
+}
+
+/// Plays of the local history, counted for the charts of the stats page.
+#[derive(Clone, Default, Data, Lens)]
+pub struct ListeningActivity {
+    /// Plays of each of the last days, the oldest first.
+    pub daily: Vector<usize>,
+    /// Plays of the same days by hour of the day, from midnight.
+    pub hourly: Vector<usize>,
+}
+
+impl ListeningActivity {
+    pub fn load() -> Self {
+        let history = PlayHistory::global();
+        let now = OffsetDateTime::now_utc().unix_timestamp() as u64;
+        let offset = notifications::local_offset().whole_seconds().into();
+        let since = now.saturating_sub(ACTIVITY_DAYS as u64 * 24 * 60 * 60);
+        Self {
+            daily: history
+                .daily_play_counts(now, ACTIVITY_DAYS, offset)
+                .into_iter()
+                .collect(),
+            hourly: history
+                .hourly_play_counts(since, offset)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.daily.iter().sum()
+    }
+}
@@ -0,0 +1,297 @@
+//! Track lists shown as a table, sorted by one of the columns and filtered by
+//! any of them.
+
+use std::{cmp::Ordering, time::Duration};
+
+use druid::Data;
+use serde::{Deserialize, Serialize};
+
+use crate::data::Track;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Data, Serialize, Deserialize)]
+pub enum TrackColumn {
+    Title,
+    Artist,
+    Album,
+    Duration,
+    DateAdded,
+    Popularity,
+}
+
+impl TrackColumn {
+    pub const ALL: [Self; 6] = [
+        Self::Title,
+        Self::Artist,
+        Self::Album,
+        Self::Duration,
+        Self::DateAdded,
+        Self::Popularity,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Title => "Title",
+            Self::Artist => "Artist",
+            Self::Album => "Album",
+            Self::Duration => "Duration",
+            Self::DateAdded => "Date Added",
+            Self::Popularity => "Popularity",
+        }
+    }
+
+    /// Example filter, shown in the empty filter of the column.
+    pub fn filter_hint(self) -> &'static str {
+        match self {
+            Self::Title | Self::Artist | Self::Album => "Filter",
+            Self::Duration => "<3:30",
+            Self::DateAdded => ">2024-06",
+            Self::Popularity => ">50",
+        }
+    }
+
+    pub fn compare(self, a: &Track, b: &Track) -> Ordering {
+        match self {
+            Self::Title => a.name.cmp(&b.name),
+            Self::Artist => a.artist_name().cmp(&b.artist_name()),
+            Self::Album => a.album_name().cmp(&b.album_name()),
+            Self::Duration => a.duration.cmp(&b.duration),
+            Self::DateAdded => a.added_at.cmp(&b.added_at),
+            Self::Popularity => a.popularity.cmp(&b.popularity),
+        }
+    }
+
+    /// Value of the column that text filters are matched against, with the
+    /// duration like "3:30" and the date like "2024-06-30".
+    fn text(self, track: &Track) -> Option<String> {
+        match self {
+            Self::Title => Some(track.name.to_string()),
+            Self::Artist => Some(track.artist_names()),
+            Self::Album => Some(track.album.as_ref()?.name.to_string()),
+            Self::Duration => {
+                let secs = track.duration.as_secs();
+                Some(format!("{}:{:02}", secs / 60, secs % 60))
+            }
+            Self::DateAdded => Some(track.added_at?.to_string()),
+            Self::Popularity => Some(track.popularity?.to_string()),
+        }
+    }
+
+    /// Does `track` pass `filter`.  Filters match anywhere in the value,
+    /// ignoring case.  The duration, the date and the popularity can also be
+    /// compared with a `<` or a `>` in front, like "<3:30", ">2024-06" or
+    /// ">50".
+    fn matches(self, track: &Track, filter: &str) -> bool {
+        let filter = filter.trim();
+        if filter.is_empty() {
+            return true;
+        }
+        let comparison = match filter.chars().next() {
+            Some('<') => Some(Ordering::Less),
+            Some('>') => Some(Ordering::Greater),
+            _ => None,
+        };
+        let value = if comparison.is_some() {
+            filter[1..].trim()
+        } else {
+            filter
+        };
+        match (self, comparison) {
+            (Self::Duration, Some(wanted)) => parse_duration(value)
+                .is_some_and(|duration| track.duration.cmp(&duration) == wanted),
+            (Self::DateAdded, Some(wanted)) => track
+                .added_at
+                .is_some_and(|date| date.to_string().as_str().cmp(value) == wanted),
+            (Self::Popularity, Some(wanted)) => value
+                .parse::<u32>()
+                .ok()
+                .zip(track.popularity)
+                .is_some_and(|(value, popularity)| popularity.cmp(&value) == wanted),
+            _ => self
+                .text(track)
+                .is_some_and(|text| text.to_lowercase().contains(&filter.to_lowercase())),
+        }
+    }
+}
+
+/// Minutes, or minutes and seconds like "3:30".
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (minutes, seconds) = value.split_once([':', '∶']).unwrap_or((value, "0"));
+    let minutes: u64 = minutes.trim().parse().ok()?;
+    let seconds: u64 = seconds.trim().parse().ok()?;
+    Some(Duration::from_secs(minutes * 60 + seconds))
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize)]
+pub struct TableSort {
+    pub column: TrackColumn,
+    pub order: SortOrder,
+}
+
+impl TableSort {
+    /// Tracks of a playlist in the order of the playlist.
+    pub const PLAYLIST_ORDER: Self = Self::new(TrackColumn::DateAdded, SortOrder::Ascending);
+
+    /// Saved tracks in the order of the API, the most recently saved first.
+    pub const RECENTLY_SAVED: Self = Self::new(TrackColumn::DateAdded, SortOrder::Descending);
+
+    pub const fn new(column: TrackColumn, order: SortOrder) -> Self {
+        Self { column, order }
+    }
+
+    /// Sort after the header of `column` was clicked.  The sorted column is
+    /// reversed, the others are sorted ascending, except for the date added,
+    /// which starts with the newest.
+    pub fn clicked(self, column: TrackColumn) -> Self {
+        let order = if column == self.column {
+            match self.order {
+                SortOrder::Ascending => SortOrder::Descending,
+                SortOrder::Descending => SortOrder::Ascending,
+            }
+        } else if column == TrackColumn::DateAdded {
+            SortOrder::Descending
+        } else {
+            SortOrder::Ascending
+        };
+        Self::new(column, order)
+    }
+
+    pub fn compare(self, a: &Track, b: &Track) -> Ordering {
+        let ordering = self.column.compare(a, b);
+        match self.order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+}
+
+/// Filters typed in the filter row of a table, empty for the columns that
+/// are not filtered.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Data)]
+pub struct TrackFilters {
+    title: String,
+    artist: String,
+    album: String,
+    duration: String,
+    date_added: String,
+    popularity: String,
+}
+
+impl TrackFilters {
+    pub fn get(&self, column: TrackColumn) -> &str {
+        match column {
+            TrackColumn::Title => &self.title,
+            TrackColumn::Artist => &self.artist,
+            TrackColumn::Album => &self.album,
+            TrackColumn::Duration => &self.duration,
+            TrackColumn::DateAdded => &self.date_added,
+            TrackColumn::Popularity => &self.popularity,
+        }
+    }
+
+    pub fn set(&mut self, column: TrackColumn, filter: String) {
+        let field = match column {
+            TrackColumn::Title => &mut self.title,
+            TrackColumn::Artist => &mut self.artist,
+            TrackColumn::Album => &mut self.album,
+            TrackColumn::Duration => &mut self.duration,
+            TrackColumn::DateAdded => &mut self.date_added,
+            TrackColumn::Popularity => &mut self.popularity,
+        };
+        *field = filter;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        TrackColumn::ALL
+            .into_iter()
+            .all(|column| self.get(column).trim().is_empty())
+    }
+
+    pub fn matches(&self, track: &Track) -> bool {
+        TrackColumn::ALL
+            .into_iter()
+            .all(|column| column.matches(track, self.get(column)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use time::macros::date;
+
+    use super::*;
+
+    fn track(name: &str, artist: &str, secs: u64, popularity: u32) -> Track {
+        serde_json::from_value(json!({
+            "name": name,
+            "album": null,
+            "artists": [{ "id": "artist", "name": artist }],
+            "duration_ms": secs * 1000,
+            "disc_number": 1,
+            "track_number": 1,
+            "explicit": false,
+            "is_local": false,
+            "is_playable": true,
+            "popularity": popularity,
+            "lyrics": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn clicking_a_header_sorts_by_its_column() {
+        let sort = TableSort::PLAYLIST_ORDER.clicked(TrackColumn::Title);
+        assert_eq!(
+            sort,
+            TableSort::new(TrackColumn::Title, SortOrder::Ascending)
+        );
+        let sort = sort.clicked(TrackColumn::Title);
+        assert_eq!(
+            sort,
+            TableSort::new(TrackColumn::Title, SortOrder::Descending)
+        );
+        assert_eq!(
+            sort.clicked(TrackColumn::DateAdded),
+            TableSort::RECENTLY_SAVED
+        );
+
+        let short = track("b", "x", 120, 10);
+        let long = track("a", "x", 300, 90);
+        let by_duration = TableSort::new(TrackColumn::Duration, SortOrder::Descending);
+        assert_eq!(by_duration.compare(&long, &short), Ordering::Less);
+        assert_eq!(sort.compare(&long, &short), Ordering::Greater);
+    }
+
+    #[test]
+    fn filters_match_every_column() {
+        let mut added = track("Blue Monday", "New Order", 449, 70);
+        added.added_at = Some(date!(2024 - 06 - 15));
+
+        let mut filters = TrackFilters::default();
+        assert!(filters.is_empty());
+        assert!(filters.matches(&added));
+
+        filters.set(TrackColumn::Title, "monday".into());
+        filters.set(TrackColumn::Artist, " order ".into());
+        assert!(filters.matches(&added));
+        filters.set(TrackColumn::Artist, "joy".into());
+        assert!(!filters.matches(&added));
+
+        let matches = |column: TrackColumn, filter: &str| column.matches(&added, filter);
+        assert!(matches(TrackColumn::Duration, "7:29"));
+        assert!(matches(TrackColumn::Duration, ">7"));
+        assert!(!matches(TrackColumn::Duration, "<3:30"));
+        assert!(matches(TrackColumn::DateAdded, ">2024-06"));
+        assert!(!matches(TrackColumn::DateAdded, "<2024"));
+        assert!(matches(TrackColumn::Popularity, ">50"));
+        assert!(!matches(TrackColumn::Popularity, "<50"));
+        assert!(!matches(TrackColumn::Popularity, ">lots"));
+        assert!(!matches(TrackColumn::Album, "any"));
+    }
+}
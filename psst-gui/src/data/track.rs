@@ -2,10 +2,14 @@ use std::{convert::TryFrom, sync::Arc, time::Duration};
 
 use druid::{im::Vector, lens::Map, Data, Lens};
 use itertools::Itertools;
-use psst_core::item_id::{ItemId, ItemIdType};
+use psst_core::{
+    item_id::{ItemId, ItemIdType},
+    player::crossfade::{LoudnessSegment, TrackAnalysis},
+};
 use serde::{Deserialize, Serialize};
+use time::Date;
 
-use crate::data::{AlbumLink, ArtistLink};
+use crate::data::{AlbumLink, ArtistLink, PublicUserProfile};
 
 #[derive(Clone, Debug, Data, Lens, Deserialize)]
 pub struct Track {
@@ -24,10 +28,23 @@ pub struct Track {
     #[serde(skip_deserializing)]
     pub local_path: Option<Arc<str>>,
     pub is_playable: Option<bool>,
+    #[serde(default)]
+    pub restrictions: Option<Restrictions>,
+    #[serde(default)]
+    pub external_ids: ExternalIds,
     pub popularity: Option<u32>,
     #[serde(skip)]
     pub track_pos: usize,
     pub lyrics: Option<Arc<[TrackLines]>>,
+    /// When the track was added to the playlist it was loaded from, or saved
+    /// to the library.
+    #[serde(skip)]
+    #[data(same_fn = "PartialEq::eq")]
+    pub added_at: Option<Date>,
+    /// Who added the track to the collaborative playlist it was loaded from,
+    /// `None` for playlists with a single contributor.
+    #[serde(skip)]
+    pub added_by: Option<Arc<PublicUserProfile>>,
 }
 
 impl Track {
@@ -73,6 +90,65 @@ impl Track {
     pub fn url(&self) -> String {
         format!("https://open.spotify.com/track/{}", self.id.0.to_base62())
     }
+
+    /// Why the track cannot be played, `None` if it can.  Only known for
+    /// tracks loaded with the market of the user.
+    pub fn restriction(&self) -> Option<RestrictionReason> {
+        if self.is_local || self.is_playable != Some(false) {
+            return None;
+        }
+        Some(
+            self.restrictions
+                .as_ref()
+                .map_or(RestrictionReason::Other, Restrictions::reason),
+        )
+    }
+}
+
+#[derive(Clone, Debug, Data, Deserialize)]
+pub struct Restrictions {
+    pub reason: Arc<str>,
+}
+
+impl Restrictions {
+    pub fn reason(&self) -> RestrictionReason {
+        match self.reason.as_ref() {
+            "market" => RestrictionReason::Market,
+            "product" => RestrictionReason::Product,
+            "explicit" => RestrictionReason::Explicit,
+            _ => RestrictionReason::Other,
+        }
+    }
+}
+
+/// Why Spotify does not let an item play.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RestrictionReason {
+    /// Not licensed in the country of the user.
+    Market,
+    /// Not included in the subscription of the user.
+    Product,
+    /// Filtered out by the explicit content setting of the account.
+    Explicit,
+    Other,
+}
+
+impl RestrictionReason {
+    pub fn explanation(self) -> &'static str {
+        match self {
+            Self::Market => "Not available in your country",
+            Self::Product => "Not available with your subscription",
+            Self::Explicit => "Hidden by your explicit content setting",
+            Self::Other => "Not available",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Data, Deserialize)]
+pub struct ExternalIds {
+    /// International Standard Recording Code, shared by all releases of a
+    /// recording.
+    pub isrc: Option<Arc<str>>,
 }
 
 #[derive(Clone, Debug, Data, Lens, Deserialize, Serialize, PartialEq)]
@@ -113,9 +189,64 @@ impl From<TrackId> for String {
 #[allow(dead_code)]
 #[derive(Clone, Data, Debug, Deserialize)]
 pub struct AudioAnalysis {
+    #[serde(default)]
+    pub track: Option<AudioAnalysisTrack>,
     pub segments: Vector<AudioSegment>,
 }
 
+impl AudioAnalysis {
+    /// Summary used by the player to place the fades of this track.
+    pub fn track_analysis(&self) -> Option<TrackAnalysis> {
+        let track = self.track.as_ref()?;
+        Some(TrackAnalysis::new(
+            track.end_of_fade_in,
+            track.start_of_fade_out,
+            self.segments.iter().map(|segment| LoudnessSegment {
+                start: segment.interval.start,
+                duration: segment.interval.duration,
+                loudness_max: segment.loudness_max,
+            }),
+        ))
+    }
+}
+
+/// Summary of the sound of a track, computed by Spotify.
+#[derive(Clone, Data, Debug, Deserialize)]
+pub struct AudioFeatures {
+    /// Beats per minute.
+    pub tempo: f64,
+    /// Pitch class of the key, from C at 0, or -1 if none was detected.
+    pub key: i64,
+    /// 1 for major, 0 for minor.
+    pub mode: i64,
+    /// Intensity, from 0.0 to 1.0.
+    pub energy: f64,
+    /// How suitable for dancing, from 0.0 to 1.0.
+    pub danceability: f64,
+    /// Average loudness, in decibels.
+    pub loudness: f64,
+}
+
+impl AudioFeatures {
+    /// Name of the key, like "F♯ minor".
+    pub fn key_name(&self) -> Option<String> {
+        const PITCHES: [&str; 12] = [
+            "C", "C♯", "D", "D♯", "E", "F", "F♯", "G", "G♯", "A", "A♯", "B",
+        ];
+        let pitch = PITCHES.get(usize::try_from(self.key).ok()?)?;
+        let mode = if self.mode == 1 { "major" } else { "minor" };
+        Some(format!("{pitch} {mode}"))
+    }
+}
+
+#[derive(Clone, Data, Debug, Deserialize)]
+pub struct AudioAnalysisTrack {
+    #[serde(deserialize_with = "super::utils::deserialize_secs")]
+    pub end_of_fade_in: Duration,
+    #[serde(deserialize_with = "super::utils::deserialize_secs")]
+    pub start_of_fade_out: Duration,
+}
+
 #[allow(dead_code)]
 #[derive(Clone, Data, Debug, Deserialize)]
 pub struct AudioSegment {
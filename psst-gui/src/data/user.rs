@@ -1,8 +1,10 @@
 use std::sync::Arc;
 
-use druid::{Data, Lens};
+use druid::{im::Vector, Data, Lens};
 use serde::Deserialize;
 
+use crate::data::Image;
+
 #[derive(Clone, Data, Lens, Deserialize)]
 pub struct UserProfile {
     pub display_name: Arc<str>,
@@ -15,3 +17,24 @@ pub struct PublicUser {
     pub display_name: Arc<str>,
     pub id: Arc<str>,
 }
+
+/// Profile of another user, like the members of a collaborative playlist.
+/// Only the ID is known until the profile is loaded.
+#[derive(Clone, Data, Lens, Deserialize, Debug)]
+pub struct PublicUserProfile {
+    pub id: Arc<str>,
+    #[serde(default)]
+    pub display_name: Option<Arc<str>>,
+    #[serde(default)]
+    pub images: Vector<Image>,
+}
+
+impl PublicUserProfile {
+    pub fn name(&self) -> Arc<str> {
+        self.display_name.clone().unwrap_or_else(|| self.id.clone())
+    }
+
+    pub fn image(&self, width: f64, height: f64) -> Option<&Image> {
+        Image::at_least_of_size(&self.images, width, height)
+    }
+}
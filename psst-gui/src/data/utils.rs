@@ -138,6 +138,8 @@ where
     D: Deserializer<'de>,
 {
     let date = String::deserialize(deserializer)?;
+    // Timestamps like `2024-03-04T12:00:00Z` are cut to their date.
+    let date = date.split('T').next().unwrap_or_default();
     let mut parts = date.splitn(3, '-');
     let year = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
     let month: u8 = parts.next().and_then(|p| p.parse().ok()).unwrap_or(1);
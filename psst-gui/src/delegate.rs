@@ -1,32 +1,58 @@
+use std::{io, path::PathBuf, sync::Arc};
+
 use druid::{
-    commands, AppDelegate, Application, Command, DelegateCtx, Env, Event, Handled, Target,
-    WindowDesc, WindowId,
+    commands, im::Vector, image, AppDelegate, Application, ClipboardFormat, Command, DelegateCtx,
+    Env, Event, Handled, Target, WindowDesc, WindowId,
 };
 use threadpool::ThreadPool;
 
 use crate::ui::playlist::{
-    RENAME_PLAYLIST, RENAME_PLAYLIST_CONFIRM, UNFOLLOW_PLAYLIST, UNFOLLOW_PLAYLIST_CONFIRM,
+    CREATE_PLAYLIST, CREATE_PLAYLIST_CONFIRM, EDIT_PLAYLIST, EDIT_PLAYLIST_CONFIRM,
+    REMOVE_SKIPPED_TRACKS, REMOVE_SKIPPED_TRACKS_CONFIRM, UNFOLLOW_PLAYLIST,
+    UNFOLLOW_PLAYLIST_CONFIRM,
 };
 use crate::ui::theme;
+use crate::ui::{COPY_ARTWORK, DOWNLOAD_ARTWORK};
 use crate::{
     cmd,
-    data::{AppState, Config, UpdateInfo, UpdateInstallEvent, UpdateInstallPhase, UpdateInstaller},
+    data::{
+        AppState, Config, GeneratedPlaylists, ListeningHistory, NewPlaylist, PublicUser,
+        SkipReview, UpdateInfo, UpdateInstallEvent, UpdateInstallPhase, UpdateInstaller,
+    },
+    error::Error,
     token_utils::TokenUtils,
     ui,
     webapi::WebApi,
     widget::remote_image,
 };
 use druid::Selector;
+use psst_core::{
+    audio::{
+        generator::{SignalGenerator, TestSignal},
+        output::{AudioOutput, AudioSink, DefaultAudioOutput},
+        source::ProcessedSource,
+        spectrum::Spectrum,
+    },
+    history::{ExportFormat, PlayHistory},
+    session::ConnectionState,
+};
 
 const UPDATE_CHECK_RESULT: Selector<Option<UpdateInfo>> = Selector::new("app.update-check-result");
+const GENERATED_PLAYLISTS_SYNCED: Selector<Result<GeneratedPlaylists, Error>> =
+    Selector::new("app.generated-playlists-synced");
+const ARTWORK_SAVED: Selector<Result<PathBuf, Error>> = Selector::new("app.artwork-saved");
+const ARTWORK_COPIED: Selector<Result<Vec<u8>, Error>> = Selector::new("app.artwork-copied");
 const UPDATE_INSTALL_STATUS_CMD: Selector<UpdateInstallEvent> = cmd::UPDATE_INSTALL_STATUS;
 
 enum OpenDialogKind {
     ThemeImport,
+    LocalFolder,
 }
 
 enum SaveDialogKind {
     ThemeExport,
+    HistoryExport,
+    Artwork(String),
 }
 
 pub struct Delegate {
@@ -34,10 +60,15 @@ pub struct Delegate {
     preferences_window: Option<WindowId>,
     credits_window: Option<WindowId>,
     artwork_window: Option<WindowId>,
+    now_playing_window: Option<WindowId>,
+    mini_player_window: Option<WindowId>,
     image_pool: ThreadPool,
     size_updated: bool,
     pending_open_dialog: Option<OpenDialogKind>,
     pending_save_dialog: Option<SaveDialogKind>,
+    /// Output of the test signals played before there is a main window, and
+    /// with it a player.
+    test_output: Option<DefaultAudioOutput>,
 }
 
 impl Delegate {
@@ -49,10 +80,13 @@ impl Delegate {
             preferences_window: None,
             credits_window: None,
             artwork_window: None,
+            now_playing_window: None,
+            mini_player_window: None,
             image_pool: ThreadPool::with_name("image_loading".into(), MAX_IMAGE_THREADS),
             size_updated: false,
             pending_open_dialog: None,
             pending_save_dialog: None,
+            test_output: None,
         }
     }
 
@@ -85,6 +119,9 @@ impl Delegate {
     }
 
     fn show_main(&mut self, config: &Config, ctx: &mut DelegateCtx) {
+        // The player of the main window takes over the test signals.
+        self.stop_test_signal();
+        self.test_output = None;
         let config_clone = config.clone();
         Self::show_or_create_window(
             &mut self.main_window,
@@ -106,6 +143,8 @@ impl Delegate {
         self.main_window = None;
         self.preferences_window = None;
         self.credits_window = None;
+        self.now_playing_window = None;
+        self.mini_player_window = None;
     }
 
     fn close_preferences(&mut self, ctx: &mut DelegateCtx) {
@@ -128,7 +167,7 @@ impl Delegate {
             }
             None => {
                 let window = WindowDesc::new(ui::credits::credits_widget())
-                    .title("Track Credits")
+                    .title("Song Info")
                     .window_size((theme::grid(50.0), theme::grid(55.0)))
                     .resizable(false);
                 let window_id = window.id;
@@ -142,6 +181,79 @@ impl Delegate {
     fn show_artwork(&mut self, ctx: &mut DelegateCtx) {
         Self::show_or_create_window(&mut self.artwork_window, ui::artwork_window, ctx);
     }
+
+    fn toggle_now_playing(&mut self, ctx: &mut DelegateCtx, data: &mut AppState) {
+        if let Some(id) = self.now_playing_window {
+            ctx.submit_command(commands::CLOSE_WINDOW.to(id));
+            return;
+        }
+        Self::show_or_create_window(&mut self.now_playing_window, ui::now_playing_window, ctx);
+        data.now_playing_visible = true;
+        if let Some(np) = data.playback.now_playing.as_ref() {
+            if data.lyrics_shown() {
+                ctx.submit_command(ui::lyrics::SHOW_LYRICS.with(np.clone()));
+            }
+        }
+    }
+
+    fn toggle_mini_player(&mut self, config: &Config, ctx: &mut DelegateCtx) {
+        if let Some(id) = self.mini_player_window {
+            ctx.submit_command(commands::CLOSE_WINDOW.to(id));
+            return;
+        }
+        let config_clone = config.clone();
+        Self::show_or_create_window(
+            &mut self.mini_player_window,
+            || ui::mini_player_window(&config_clone),
+            ctx,
+        );
+    }
+
+    /// Play `signal` on an output of our own, for the account setup, where
+    /// there is no player to play it.
+    fn play_test_signal(&mut self, signal: TestSignal, config: &Config) {
+        if self.test_output.is_none() {
+            match DefaultAudioOutput::open() {
+                Ok(output) => self.test_output = Some(output),
+                Err(err) => {
+                    log::error!("failed to open the output for the test signal: {err}");
+                    return;
+                }
+            }
+        }
+        if let Some(output) = &self.test_output {
+            let sink = output.sink();
+            let generator = SignalGenerator::new(signal, sink.sample_rate(), sink.channel_count())
+                .with_spoken_channels();
+            // The equalizer cannot be changed from the account setup.
+            let (_, dsp_updates) = crossbeam_channel::unbounded();
+            sink.play(ProcessedSource::new(
+                generator,
+                &config.playback().dsp,
+                dsp_updates,
+            ));
+            sink.resume();
+        }
+    }
+
+    fn stop_test_signal(&mut self) {
+        if let Some(output) = &self.test_output {
+            output.sink().stop();
+        }
+    }
+
+    /// Commands of the now-playing view and the mini player that only the
+    /// main window runs, like the playback controls and keybinds.
+    fn is_main_window_command(cmd: &Command) -> bool {
+        cmd.is(cmd::RUN_KEYBIND)
+            || cmd.is(cmd::PLAY_PREVIOUS)
+            || cmd.is(cmd::PLAY_PAUSE)
+            || cmd.is(cmd::PLAY_RESUME)
+            || cmd.is(cmd::PLAY_STOP)
+            || cmd.is(cmd::PLAY_NEXT)
+            || cmd.is(cmd::PLAY_SEEK)
+            || cmd.is(cmd::SKIP_TO_POSITION)
+    }
 }
 
 impl AppDelegate<AppState> for Delegate {
@@ -162,11 +274,21 @@ impl AppDelegate<AppState> for Delegate {
                         .with(track.clone())
                         .to(Target::Global),
                 );
+                ctx.submit_command(cmd::LOAD_AUDIO_FEATURES.with(track.id).to(Target::Global));
             }
             Handled::Yes
         } else if cmd.is(cmd::SHOW_MAIN) {
             self.show_main(&data.config, ctx);
             Handled::Yes
+        } else if let Some(signal) = cmd
+            .get(cmd::PLAY_TEST_SIGNAL)
+            .filter(|_| self.main_window.is_none())
+        {
+            self.play_test_signal(*signal, &data.config);
+            Handled::Yes
+        } else if cmd.is(cmd::PLAY_STOP) && self.main_window.is_none() {
+            self.stop_test_signal();
+            Handled::Yes
         } else if cmd.is(cmd::SHOW_ACCOUNT_SETUP) {
             self.show_account_setup(ctx);
             Handled::Yes
@@ -179,9 +301,47 @@ impl AppDelegate<AppState> for Delegate {
         } else if cmd.is(cmd::BEGIN_THEME_IMPORT) {
             self.pending_open_dialog = Some(OpenDialogKind::ThemeImport);
             Handled::Yes
+        } else if cmd.is(cmd::BEGIN_LOCAL_FOLDER_IMPORT) {
+            self.pending_open_dialog = Some(OpenDialogKind::LocalFolder);
+            Handled::Yes
         } else if cmd.is(cmd::BEGIN_THEME_EXPORT) {
             self.pending_save_dialog = Some(SaveDialogKind::ThemeExport);
             Handled::Yes
+        } else if cmd.is(cmd::BEGIN_HISTORY_EXPORT) {
+            self.pending_save_dialog = Some(SaveDialogKind::HistoryExport);
+            Handled::Yes
+        } else if let Some((url, _)) = cmd.get(DOWNLOAD_ARTWORK) {
+            self.pending_save_dialog = Some(SaveDialogKind::Artwork(url.clone()));
+            Handled::Yes
+        } else if let Some(url) = cmd.get(COPY_ARTWORK) {
+            let event_sink = ctx.get_external_handle();
+            let url: Arc<str> = url.as_str().into();
+            std::thread::spawn(move || {
+                let result = WebApi::global()
+                    .get_image_data(&url)
+                    .and_then(|data| encode_png(&data));
+                event_sink
+                    .submit_command(ARTWORK_COPIED, result, Target::Global)
+                    .ok();
+            });
+            Handled::Yes
+        } else if let Some(result) = cmd.get(ARTWORK_COPIED) {
+            match result {
+                Ok(png) => {
+                    Application::global()
+                        .clipboard()
+                        .put_formats(&[ClipboardFormat::new(PNG_CLIPBOARD_FORMAT, png.clone())]);
+                    data.info_alert("Artwork copied to clipboard.");
+                }
+                Err(err) => data.error_alert(format!("Failed to copy artwork: {err}")),
+            }
+            Handled::Yes
+        } else if let Some(result) = cmd.get(ARTWORK_SAVED) {
+            match result {
+                Ok(path) => data.info_alert(format!("Artwork saved to {}", path.display())),
+                Err(err) => data.error_alert(format!("Failed to save artwork: {err}")),
+            }
+            Handled::Yes
         } else if cmd.is(commands::CLOSE_WINDOW) {
             if let Some(window_id) = self.preferences_window {
                 if target == Target::Window(window_id) {
@@ -201,15 +361,115 @@ impl AppDelegate<AppState> for Delegate {
         } else if let Some(text) = cmd.get(cmd::GO_TO_URL) {
             let _ = open::that(text);
             Handled::Yes
+        } else if cmd.is(cmd::APPLY_PROXY) {
+            let proxy_url = data.config.proxy_url();
+            WebApi::global().set_proxy(proxy_url.as_deref());
+            if data.config.has_credentials() {
+                // Reconnects through the new proxy on the next request, the
+                // CDN follows the session config.
+                data.session.update_config(data.config.session());
+            }
+            data.info_alert("Proxy settings applied.");
+            Handled::Yes
+        } else if let Some(state) = cmd.get(cmd::SESSION_STATE_CHANGED) {
+            data.connection_state = state.clone();
+            // Reconnections only run after a connection was lost, not while
+            // the offline mode keeps the session closed.
+            match state {
+                ConnectionState::Reconnecting { .. } if !data.lost_connection => {
+                    data.lost_connection = true;
+                    if !data.config.offline_mode {
+                        data.info_alert("Connection lost, showing what is available offline.");
+                    }
+                }
+                ConnectionState::Connected if data.lost_connection => {
+                    data.lost_connection = false;
+                    if !data.config.offline_mode {
+                        data.info_alert("Back online.");
+                    }
+                }
+                _ => {}
+            }
+            Handled::Yes
+        } else if cmd.is(cmd::SESSION_CONNECT_FAILED) {
+            // No reconnections follow a failed first connection, going online
+            // tries again.
+            if !data.lost_connection && !data.config.offline_mode {
+                data.lost_connection = true;
+                data.info_alert("Could not connect, showing what is available offline.");
+            }
+            Handled::Yes
+        } else if let Some(state) = cmd.get(cmd::DISCORD_STATE_CHANGED) {
+            data.discord_state = state.clone();
+            Handled::Yes
+        } else if let Some(track) = cmd.get(cmd::TOGGLE_TRACK_SELECTION) {
+            let selected = &mut data.common_ctx_mut().selected_tracks;
+            match selected.iter().position(|t| t.id == track.id) {
+                Some(index) => {
+                    selected.remove(index);
+                }
+                None => selected.push_back(track.clone()),
+            }
+            Handled::Yes
+        } else if cmd.is(cmd::CLEAR_TRACK_SELECTION) {
+            data.common_ctx_mut().selected_tracks.clear();
+            Handled::Yes
+        } else if let Some(track) = cmd.get(cmd::START_TRACK_DRAG) {
+            let common_ctx = data.common_ctx_mut();
+            // A selected row takes the whole selection along.
+            common_ctx.dragged_tracks = if common_ctx.is_track_selected(track) {
+                common_ctx.selected_tracks.clone()
+            } else {
+                Vector::unit(track.clone())
+            };
+            common_ctx.dragged_track = Some(track.clone());
+            Handled::Yes
+        } else if cmd.is(cmd::END_TRACK_DRAG) {
+            let common_ctx = data.common_ctx_mut();
+            common_ctx.dragged_track = None;
+            common_ctx.dragged_tracks.clear();
+            Handled::Yes
         } else if let Handled::Yes = self.command_image(ctx, target, cmd, data) {
             Handled::Yes
         } else if let Some(link) = cmd.get(UNFOLLOW_PLAYLIST_CONFIRM) {
             ctx.submit_command(UNFOLLOW_PLAYLIST.with(link.clone()));
             Handled::Yes
-        } else if let Some(link) = cmd.get(RENAME_PLAYLIST_CONFIRM) {
-            ctx.submit_command(RENAME_PLAYLIST.with(link.clone()));
+        } else if let Some(edit) = cmd.get(EDIT_PLAYLIST_CONFIRM) {
+            if edit.name.trim().is_empty() {
+                data.error_alert("A playlist needs a name.");
+            } else {
+                ctx.submit_command(EDIT_PLAYLIST.with(edit.clone()));
+            }
+            Handled::Yes
+        } else if cmd.is(REMOVE_SKIPPED_TRACKS_CONFIRM) {
+            if let Some(review) = data.skip_review.take() {
+                let removed = review.removed();
+                if !removed.is_empty() {
+                    ctx.submit_command(REMOVE_SKIPPED_TRACKS.with(SkipReview {
+                        tracks: removed,
+                        ..review
+                    }));
+                }
+            }
+            Handled::Yes
+        } else if let Some(name) = cmd.get(CREATE_PLAYLIST_CONFIRM) {
+            if name.is_empty() {
+                data.error_alert("A playlist needs a name.");
+            } else if let Some(profile) = data.library.user_profile.resolved() {
+                ctx.submit_command(CREATE_PLAYLIST.with(NewPlaylist {
+                    owner: PublicUser {
+                        id: profile.id.clone(),
+                        display_name: profile.display_name.clone(),
+                    },
+                    name: name.clone(),
+                }));
+            } else {
+                data.error_alert("Playlists can be created once your profile is loaded.");
+            }
             Handled::Yes
         } else if cmd.is(cmd::QUIT_APP_WITH_SAVE) {
+            data.save_volume();
+            data.config.save();
             ctx.submit_command(commands::QUIT_APP);
             Handled::Yes
         } else if cmd.is(commands::QUIT_APP) {
@@ -217,6 +477,22 @@ impl AppDelegate<AppState> for Delegate {
         } else if cmd.is(crate::cmd::SHOW_ARTWORK) {
             self.show_artwork(ctx);
             Handled::Yes
+        } else if cmd.is(cmd::TOGGLE_NOW_PLAYING) {
+            self.toggle_now_playing(ctx, data);
+            Handled::Yes
+        } else if cmd.is(cmd::TOGGLE_MINI_PLAYER) {
+            self.toggle_mini_player(&data.config, ctx);
+            Handled::Yes
+        } else if Self::is_main_window_command(cmd)
+            && [self.now_playing_window, self.mini_player_window]
+                .into_iter()
+                .flatten()
+                .any(|id| target == Target::Window(id))
+        {
+            if let Some(main) = self.main_window {
+                ctx.submit_command(cmd.clone().to(main));
+            }
+            Handled::Yes
         } else if let Some((access, refresh)) = cmd.get(cmd::OAUTH_TOKENS_REFRESHED) {
             TokenUtils::apply_refresh_result(
                 &data.session,
@@ -226,6 +502,16 @@ impl AppDelegate<AppState> for Delegate {
                 true,
             );
             Handled::Yes
+        } else if let Some((feature, status, alert)) = cmd.get(cmd::API_STATUS_CHANGED) {
+            if *alert {
+                data.info_alert(format!(
+                    "{} unavailable: {}",
+                    feature.label(),
+                    status.describe()
+                ));
+            }
+            data.api_status.insert(*feature, status.clone());
+            Handled::Yes
         } else if let Some(file_info) = cmd.get(commands::OPEN_FILE) {
             let context = self
                 .pending_open_dialog
@@ -246,6 +532,13 @@ impl AppDelegate<AppState> for Delegate {
                         }
                     }
                 }
+                OpenDialogKind::LocalFolder => {
+                    let folder = file_info.path().to_string_lossy().into_owned();
+                    if !data.config.local_folders.contains(&folder) {
+                        data.config.local_folders.push_back(folder);
+                        data.config.save();
+                    }
+                }
             }
             Handled::Yes
         } else if let Some(file_info) = cmd.get(commands::SAVE_FILE_AS) {
@@ -255,6 +548,20 @@ impl AppDelegate<AppState> for Delegate {
                 .unwrap_or(SaveDialogKind::ThemeExport);
 
             match context {
+                SaveDialogKind::Artwork(url) => {
+                    let event_sink = ctx.get_external_handle();
+                    let path = file_info.path().to_path_buf();
+                    let url: Arc<str> = url.into();
+                    std::thread::spawn(move || {
+                        let result = WebApi::global()
+                            .get_image_data(&url)
+                            .and_then(|data| std::fs::write(&path, data).map_err(Error::from))
+                            .map(|_| path);
+                        event_sink
+                            .submit_command(ARTWORK_SAVED, result, Target::Global)
+                            .ok();
+                    });
+                }
                 SaveDialogKind::ThemeExport => {
                     match data.config.custom_theme.export_to_file(file_info.path()) {
                         Ok(()) => {
@@ -268,6 +575,18 @@ impl AppDelegate<AppState> for Delegate {
                         }
                     }
                 }
+                SaveDialogKind::HistoryExport => {
+                    let path = file_info.path();
+                    let format = ExportFormat::from_path(path).unwrap_or(ExportFormat::Csv);
+                    match PlayHistory::global().export_to_file(format, path) {
+                        Ok(()) => {
+                            data.info_alert(format!("History exported to {}", path.display()));
+                        }
+                        Err(err) => {
+                            data.error_alert(format!("Failed to export history: {err}"));
+                        }
+                    }
+                }
             }
 
             Handled::Yes
@@ -288,6 +607,51 @@ impl AppDelegate<AppState> for Delegate {
                 }
             });
             Handled::Yes
+        } else if cmd.is(cmd::CLEAR_HISTORY) {
+            match PlayHistory::global().clear() {
+                Ok(()) => {
+                    data.listening_history
+                        .resolve(0, ListeningHistory::default());
+                    data.info_alert("Listening history cleared.");
+                }
+                Err(err) => {
+                    data.error_alert(format!("Failed to clear history: {err}"));
+                }
+            }
+            Handled::Yes
+        } else if cmd.is(cmd::SYNC_GENERATED_PLAYLISTS) {
+            // The playlists stay local unless saving them to Spotify is on.
+            if data.config.generated_playlists.sync_to_spotify
+                && !data.preferences.syncing_playlists
+            {
+                data.preferences.syncing_playlists = true;
+                let event_sink = ctx.get_external_handle();
+                let playlists = data.config.generated_playlists.clone();
+                std::thread::spawn(move || {
+                    event_sink
+                        .submit_command(
+                            GENERATED_PLAYLISTS_SYNCED,
+                            playlists.sync(),
+                            Target::Global,
+                        )
+                        .ok();
+                });
+            }
+            Handled::Yes
+        } else if let Some(result) = cmd.get(GENERATED_PLAYLISTS_SYNCED) {
+            data.preferences.syncing_playlists = false;
+            match result {
+                Ok(playlists) => {
+                    data.config.generated_playlists = playlists.clone();
+                    data.config.save();
+                    data.info_alert("Generated playlists updated.");
+                }
+                Err(err) => {
+                    log::error!("failed to sync generated playlists: {err}");
+                    data.error_alert(format!("Failed to update generated playlists: {err}"));
+                }
+            }
+            Handled::Yes
         } else if let Some(info) = cmd.get(cmd::INSTALL_UPDATE) {
             data.preferences.installing_update = true;
             data.preferences.update_install_status =
@@ -402,6 +766,7 @@ impl AppDelegate<AppState> for Delegate {
         if self.credits_window == Some(id) {
             self.credits_window = None;
             data.credits = None;
+            data.audio_features.clear();
         }
         if self.preferences_window == Some(id) {
             self.preferences_window.take();
@@ -409,7 +774,7 @@ impl AppDelegate<AppState> for Delegate {
             data.preferences.auth.clear();
         }
         if self.main_window == Some(id) {
-            data.config.volume = data.playback.volume;
+            data.save_volume();
             data.config.save();
             ctx.submit_command(commands::CLOSE_ALL_WINDOWS);
             ctx.submit_command(commands::QUIT_APP);
@@ -417,6 +782,14 @@ impl AppDelegate<AppState> for Delegate {
         if self.artwork_window == Some(id) {
             self.artwork_window = None;
         }
+        if self.now_playing_window == Some(id) {
+            self.now_playing_window = None;
+            data.now_playing_visible = false;
+            Spectrum::global().set_enabled(false);
+        }
+        if self.mini_player_window == Some(id) {
+            self.mini_player_window = None;
+        }
     }
 
     fn event(
@@ -428,17 +801,19 @@ impl AppDelegate<AppState> for Delegate {
         _env: &Env,
     ) -> Option<Event> {
         if self.main_window == Some(window_id) {
-            if let Event::WindowSize(size) = event {
+            if let Event::WindowSize(size) = &event {
                 if !self.size_updated {
                     self.size_updated = true;
                 } else {
-                    data.config.window_size = size;
+                    data.config.window_size = *size;
                 }
             }
         } else if [
             self.preferences_window,
             self.artwork_window,
             self.credits_window,
+            self.now_playing_window,
+            self.mini_player_window,
         ]
         .contains(&Some(window_id))
         {
@@ -461,9 +836,10 @@ impl Delegate {
         cmd: &Command,
         _data: &mut AppState,
     ) -> Handled {
-        if let Some(location) = cmd.get(remote_image::REQUEST_DATA).cloned() {
+        if let Some(request) = cmd.get(remote_image::REQUEST_DATA).cloned() {
             let sink = ctx.get_external_handle();
-            if let Some(image_buf) = WebApi::global().get_cached_image(&location) {
+            let remote_image::ImageRequest { location, size } = request;
+            if let Some(image_buf) = WebApi::global().get_cached_image(&location, size) {
                 let payload = remote_image::ImagePayload {
                     location,
                     image_buf,
@@ -472,7 +848,7 @@ impl Delegate {
                     .unwrap();
             } else {
                 self.image_pool.execute(move || {
-                    let result = WebApi::global().get_image(location.clone());
+                    let result = WebApi::global().get_image(location.clone(), size);
                     match result {
                         Ok(image_buf) => {
                             let payload = remote_image::ImagePayload {
@@ -494,3 +870,20 @@ impl Delegate {
         }
     }
 }
+
+/// Clipboard format identifier for PNG image data on the current platform.
+const PNG_CLIPBOARD_FORMAT: &str = if cfg!(target_os = "macos") {
+    "public.png"
+} else if cfg!(target_os = "windows") {
+    "PNG"
+} else {
+    "image/png"
+};
+
+/// Re-encode an image as PNG, the format most widely accepted when pasting.
+fn encode_png(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let image = image::load_from_memory(data)?;
+    let mut png = Vec::new();
+    image.write_to(&mut io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
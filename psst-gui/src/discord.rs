@@ -0,0 +1,207 @@
+//! Discord Rich Presence.  The IPC connection to the Discord client is kept
+//! on a background thread, which connects again with backoff whenever
+//! Discord restarts or the socket drops, and then publishes the current
+//! presence again.
+
+use std::{
+    error::Error,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{unbounded, RecvTimeoutError, Sender};
+use discord_rich_presence::{
+    activity::{Activity, Assets, Timestamps},
+    DiscordIpc, DiscordIpcClient,
+};
+use psst_core::session::reconnect::reconnect_delay;
+
+/// State of the connection to the Discord client.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DiscordState {
+    /// The presence is turned off, or has no application ID.
+    #[default]
+    Disabled,
+    Connected,
+    /// Discord is not running or the connection dropped, attempt number
+    /// `attempt` to connect again is made in `retry_in`.
+    Reconnecting {
+        attempt: u32,
+        retry_in: Duration,
+    },
+}
+
+/// Activity shown in Discord.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Presence {
+    pub details: String,
+    pub state: Option<String>,
+    /// Start and end of the playing item, in Unix seconds.
+    pub timestamps: Option<(i64, i64)>,
+    pub large_image: String,
+    pub large_text: Option<String>,
+}
+
+impl Presence {
+    fn activity(&self) -> Activity<'_> {
+        let mut assets = Assets::new().large_image(&self.large_image);
+        if let Some(text) = &self.large_text {
+            assets = assets.large_text(text);
+        }
+        let mut activity = Activity::new().details(&self.details).assets(assets);
+        if let Some(state) = &self.state {
+            activity = activity.state(state);
+        }
+        if let Some((start, end)) = self.timestamps {
+            activity = activity.timestamps(Timestamps::new().start(start).end(end));
+        }
+        activity
+    }
+}
+
+enum Message {
+    /// Connect with the application ID, or disconnect with `None`.
+    Configure(Option<String>),
+    /// Show the presence, or clear it with `None`.
+    Publish(Option<Presence>),
+}
+
+/// Handle of the presence thread, which exits when the handle is dropped.
+pub struct DiscordPresence {
+    sender: Sender<Message>,
+}
+
+impl DiscordPresence {
+    /// Start the presence thread, calling `on_state` whenever the state of
+    /// the connection changes.
+    pub fn new(on_state: impl Fn(DiscordState) + Send + 'static) -> Self {
+        let (sender, receiver) = unbounded();
+        let spawned = thread::Builder::new()
+            .name("discord_presence".to_string())
+            .spawn(move || {
+                let mut worker = Worker {
+                    app_id: None,
+                    client: None,
+                    presence: None,
+                    attempt: 0,
+                    retry_at: None,
+                    on_state: Box::new(on_state),
+                };
+                loop {
+                    let message = match worker.retry_at {
+                        Some(retry_at) => receiver.recv_deadline(retry_at),
+                        None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                    };
+                    match message {
+                        Ok(Message::Configure(app_id)) => worker.configure(app_id),
+                        Ok(Message::Publish(presence)) => {
+                            worker.presence = presence;
+                            worker.publish();
+                        }
+                        Err(RecvTimeoutError::Timeout) => worker.connect(),
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                worker.disconnect();
+            });
+        if let Err(err) = spawned {
+            log::error!("failed to spawn the Discord presence thread: {err}");
+        }
+        Self { sender }
+    }
+
+    pub fn configure(&self, app_id: Option<String>) {
+        let _ = self.sender.send(Message::Configure(app_id));
+    }
+
+    pub fn publish(&self, presence: Option<Presence>) {
+        let _ = self.sender.send(Message::Publish(presence));
+    }
+}
+
+struct Worker {
+    app_id: Option<String>,
+    client: Option<DiscordIpcClient>,
+    /// Published again after every reconnect.
+    presence: Option<Presence>,
+    /// Failed connection attempts in a row.
+    attempt: u32,
+    retry_at: Option<Instant>,
+    on_state: Box<dyn Fn(DiscordState) + Send>,
+}
+
+impl Worker {
+    fn configure(&mut self, app_id: Option<String>) {
+        if app_id == self.app_id && (self.client.is_some() || self.retry_at.is_some()) {
+            return;
+        }
+        self.disconnect();
+        self.app_id = app_id;
+        self.attempt = 0;
+        if self.app_id.is_some() {
+            self.connect();
+        } else {
+            (self.on_state)(DiscordState::Disabled);
+        }
+    }
+
+    fn connect(&mut self) {
+        self.retry_at = None;
+        let Some(app_id) = &self.app_id else {
+            return;
+        };
+        match open_client(app_id) {
+            Ok(client) => {
+                log::info!("Discord Rich Presence connected");
+                self.client = Some(client);
+                self.attempt = 0;
+                (self.on_state)(DiscordState::Connected);
+                self.publish();
+            }
+            Err(err) => {
+                log::warn!("failed to connect to Discord: {err}");
+                self.retry();
+            }
+        }
+    }
+
+    fn publish(&mut self) {
+        let Some(client) = &mut self.client else {
+            return;
+        };
+        let result = match &self.presence {
+            Some(presence) => client.set_activity(presence.activity()),
+            None => client.clear_activity(),
+        };
+        if let Err(err) = result {
+            // Most likely Discord quit, the presence is published again once
+            // it is back.
+            log::warn!("failed to update Discord Rich Presence: {err}");
+            self.disconnect();
+            self.retry();
+        }
+    }
+
+    fn retry(&mut self) {
+        self.attempt = self.attempt.saturating_add(1);
+        let retry_in = reconnect_delay(self.attempt, rand::random());
+        self.retry_at = Some(Instant::now() + retry_in);
+        (self.on_state)(DiscordState::Reconnecting {
+            attempt: self.attempt,
+            retry_in,
+        });
+    }
+
+    fn disconnect(&mut self) {
+        self.retry_at = None;
+        if let Some(mut client) = self.client.take() {
+            let _ = client.close();
+        }
+    }
+}
+
+fn open_client(app_id: &str) -> Result<DiscordIpcClient, Box<dyn Error>> {
+    let mut client = DiscordIpcClient::new(app_id)?;
+    client.connect()?;
+    Ok(client)
+}
@@ -0,0 +1,378 @@
+//! Formatting of the numbers, dates, durations and sizes shown in the UI, in
+//! the conventions of the locale the app runs in.
+
+use std::time::Duration;
+
+use druid::Application;
+use once_cell::sync::Lazy;
+use time::Date;
+
+static LOCALE: Lazy<Locale> = Lazy::new(|| {
+    let tag = Application::get_locale();
+    log::info!("formatting for locale {tag:?}");
+    Locale::from_tag(&tag)
+});
+
+/// Length of a track, like "3∶05".
+pub fn duration(dur: Duration) -> String {
+    let minutes = dur.as_secs() / 60;
+    let seconds = dur.as_secs() % 60;
+    format!("{minutes}∶{seconds:02}")
+}
+
+/// Length of a whole track list, like "1 hr 5 min" or "48 min 12 sec".
+pub fn total_duration(dur: Duration) -> String {
+    LOCALE.total_duration(dur)
+}
+
+pub fn number(n: i64) -> String {
+    LOCALE.number(n)
+}
+
+pub fn date(date: Date) -> String {
+    LOCALE.date(date)
+}
+
+pub fn month(date: Date) -> String {
+    LOCALE.month(date)
+}
+
+pub fn size(bytes: u64) -> String {
+    LOCALE.size(bytes)
+}
+
+#[derive(Clone, Copy)]
+enum DateStyle {
+    /// "March 4, 2024".
+    MonthDayYear,
+    /// "4 March 2024", with the text put after the day and before the year.
+    DayMonthYear {
+        after_day: &'static str,
+        before_year: &'static str,
+    },
+}
+
+type MonthNames = [&'static str; 12];
+
+/// Abbreviations of hours, minutes and seconds.
+type DurationUnits = [&'static str; 3];
+
+const ENGLISH_UNITS: DurationUnits = ["hr", "min", "sec"];
+const GERMAN_UNITS: DurationUnits = ["Std.", "Min.", "Sek."];
+const SWEDISH_UNITS: DurationUnits = ["tim", "min", "s"];
+const DUTCH_UNITS: DurationUnits = ["u", "min", "s"];
+const ROMANCE_UNITS: DurationUnits = ["h", "min", "s"];
+
+const ENGLISH_MONTHS: MonthNames = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const GERMAN_MONTHS: MonthNames = [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+const FRENCH_MONTHS: MonthNames = [
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+];
+const SPANISH_MONTHS: MonthNames = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+const ITALIAN_MONTHS: MonthNames = [
+    "gennaio",
+    "febbraio",
+    "marzo",
+    "aprile",
+    "maggio",
+    "giugno",
+    "luglio",
+    "agosto",
+    "settembre",
+    "ottobre",
+    "novembre",
+    "dicembre",
+];
+const DUTCH_MONTHS: MonthNames = [
+    "januari",
+    "februari",
+    "maart",
+    "april",
+    "mei",
+    "juni",
+    "juli",
+    "augustus",
+    "september",
+    "oktober",
+    "november",
+    "december",
+];
+const PORTUGUESE_MONTHS: MonthNames = [
+    "janeiro",
+    "fevereiro",
+    "março",
+    "abril",
+    "maio",
+    "junho",
+    "julho",
+    "agosto",
+    "setembro",
+    "outubro",
+    "novembro",
+    "dezembro",
+];
+const SWEDISH_MONTHS: MonthNames = [
+    "januari",
+    "februari",
+    "mars",
+    "april",
+    "maj",
+    "juni",
+    "juli",
+    "augusti",
+    "september",
+    "oktober",
+    "november",
+    "december",
+];
+
+/// Conventions of a locale.  Languages without their own conventions here
+/// are formatted like US English.
+pub struct Locale {
+    decimal: char,
+    group: &'static str,
+    months: &'static MonthNames,
+    units: &'static DurationUnits,
+    date_style: DateStyle,
+}
+
+impl Locale {
+    /// Parse a BCP 47 tag like "de-AT", or a POSIX locale like "de_AT.UTF-8".
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.split(['.', '@']).next().unwrap_or_default();
+        let mut subtags = tag.split(['-', '_']);
+        let language = subtags.next().unwrap_or_default().to_ascii_lowercase();
+        let region = subtags.next().unwrap_or_default().to_ascii_uppercase();
+
+        let day_first = |after_day, before_year| DateStyle::DayMonthYear {
+            after_day,
+            before_year,
+        };
+        let (decimal, group, months, date_style) = match language.as_str() {
+            "de" => (',', ".", &GERMAN_MONTHS, day_first(". ", " ")),
+            "fr" => (',', "\u{202f}", &FRENCH_MONTHS, day_first(" ", " ")),
+            "es" => (',', ".", &SPANISH_MONTHS, day_first(" de ", " de ")),
+            "it" => (',', ".", &ITALIAN_MONTHS, day_first(" ", " ")),
+            "nl" => (',', ".", &DUTCH_MONTHS, day_first(" ", " ")),
+            "pt" => (',', ".", &PORTUGUESE_MONTHS, day_first(" de ", " de ")),
+            "sv" => (',', "\u{a0}", &SWEDISH_MONTHS, day_first(" ", " ")),
+            "en" if !matches!(region.as_str(), "" | "US" | "CA" | "PH") => {
+                ('.', ",", &ENGLISH_MONTHS, day_first(" ", " "))
+            }
+            _ => ('.', ",", &ENGLISH_MONTHS, DateStyle::MonthDayYear),
+        };
+        let units = match language.as_str() {
+            "de" => &GERMAN_UNITS,
+            "fr" | "es" | "it" | "pt" => &ROMANCE_UNITS,
+            "nl" => &DUTCH_UNITS,
+            "sv" => &SWEDISH_UNITS,
+            _ => &ENGLISH_UNITS,
+        };
+        Self {
+            decimal,
+            group,
+            months,
+            units,
+            date_style,
+        }
+    }
+
+    /// Whole number with grouped thousands, like "1,234,567".
+    pub fn number(&self, n: i64) -> String {
+        let sign = if n < 0 { "-" } else { "" };
+        format!("{sign}{}", self.group_digits(&n.unsigned_abs().to_string()))
+    }
+
+    /// Number with `digits` decimals, like "1,234.56".
+    pub fn decimal(&self, value: f64, digits: usize) -> String {
+        let formatted = format!("{:.digits$}", value.abs());
+        let (whole, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+        let sign = if value < 0.0 { "-" } else { "" };
+        let mut text = format!("{sign}{}", self.group_digits(whole));
+        if !fraction.is_empty() {
+            text.push(self.decimal);
+            text.push_str(fraction);
+        }
+        text
+    }
+
+    /// Day, like "March 4, 2024".
+    pub fn date(&self, date: Date) -> String {
+        let month = self.month_name(date);
+        match self.date_style {
+            DateStyle::MonthDayYear => format!("{month} {}, {}", date.day(), date.year()),
+            DateStyle::DayMonthYear {
+                after_day,
+                before_year,
+            } => format!(
+                "{}{after_day}{month}{before_year}{}",
+                date.day(),
+                date.year()
+            ),
+        }
+    }
+
+    /// Month of `date`, like "March 2024".
+    pub fn month(&self, date: Date) -> String {
+        let before_year = match self.date_style {
+            DateStyle::MonthDayYear => " ",
+            DateStyle::DayMonthYear { before_year, .. } => before_year,
+        };
+        format!("{}{before_year}{}", self.month_name(date), date.year())
+    }
+
+    /// Length of a whole track list, like "1 hr 5 min" or "48 min 12 sec".
+    pub fn total_duration(&self, dur: Duration) -> String {
+        let [hr, min, sec] = self.units;
+        let hours = dur.as_secs() / 3600;
+        let minutes = dur.as_secs() / 60 % 60;
+        let seconds = dur.as_secs() % 60;
+        if hours > 0 {
+            format!("{hours} {hr} {minutes} {min}")
+        } else {
+            format!("{minutes} {min} {seconds} {sec}")
+        }
+    }
+
+    /// Size of data, like "12.34 MB".
+    pub fn size(&self, bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+        let mut value = bytes as f64 / 1e3;
+        let mut unit = 0;
+        while value >= 1e3 && unit < UNITS.len() - 1 {
+            value /= 1e3;
+            unit += 1;
+        }
+        format!("{} {}", self.decimal(value, 2), UNITS[unit])
+    }
+
+    fn month_name(&self, date: Date) -> &'static str {
+        self.months[usize::from(u8::from(date.month())) - 1]
+    }
+
+    fn group_digits(&self, digits: &str) -> String {
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push_str(self.group);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Month;
+
+    use super::*;
+
+    fn date() -> Date {
+        Date::from_calendar_date(2024, Month::March, 4).unwrap()
+    }
+
+    #[test]
+    fn formats_numbers() {
+        let us = Locale::from_tag("en-US");
+        assert_eq!(us.number(0), "0");
+        assert_eq!(us.number(999), "999");
+        assert_eq!(us.number(1_234_567), "1,234,567");
+        assert_eq!(us.number(-1_000), "-1,000");
+        assert_eq!(us.decimal(1234.567, 2), "1,234.57");
+
+        let german = Locale::from_tag("de_DE.UTF-8");
+        assert_eq!(german.number(1_234_567), "1.234.567");
+        assert_eq!(german.decimal(1234.5, 1), "1.234,5");
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(Locale::from_tag("en-US").date(date()), "March 4, 2024");
+        assert_eq!(Locale::from_tag("en_GB").date(date()), "4 March 2024");
+        assert_eq!(Locale::from_tag("de-AT").date(date()), "4. März 2024");
+        assert_eq!(Locale::from_tag("es").date(date()), "4 de marzo de 2024");
+        assert_eq!(Locale::from_tag("es").month(date()), "marzo de 2024");
+        assert_eq!(Locale::from_tag("xx").month(date()), "March 2024");
+    }
+
+    #[test]
+    fn formats_sizes() {
+        let us = Locale::from_tag("en-US");
+        assert_eq!(us.size(0), "0.00 kB");
+        assert_eq!(us.size(12_340_000), "12.34 MB");
+        assert_eq!(us.size(2_500_000_000), "2.50 GB");
+        assert_eq!(Locale::from_tag("fr-FR").size(12_340_000), "12,34 MB");
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(duration(Duration::from_secs(185)), "3∶05");
+        let us = Locale::from_tag("en-US");
+        assert_eq!(
+            us.total_duration(Duration::from_secs(2892)),
+            "48 min 12 sec"
+        );
+        assert_eq!(us.total_duration(Duration::from_secs(3900)), "1 hr 5 min");
+        let german = Locale::from_tag("de");
+        assert_eq!(
+            german.total_duration(Duration::from_secs(3900)),
+            "1 Std. 5 Min."
+        );
+        let french = Locale::from_tag("fr-FR");
+        assert_eq!(
+            french.total_duration(Duration::from_secs(2892)),
+            "48 min 12 s"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! Lets `psst-gui --command ...` control an already running instance, e.g.
+//! from window manager key bindings.  The running instance listens on a
+//! loopback port, written to a file in the config directory, and reads one
+//! command per connection.
+
+use std::{
+    fmt, fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+const PORT_FILENAME: &str = "instance.port";
+
+/// Prefix of every request, so that a stale port file reused by an unrelated
+/// program is not mistaken for a running instance.
+const REQUEST_PREFIX: &str = "psst ";
+const REPLY_OK: &str = "ok";
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_REQUEST_LENGTH: u64 = 256;
+
+pub const USAGE: &str = "usage: psst-gui --command play-pause|next|prev|volume [+|-]PERCENT";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstanceCommand {
+    PlayPause,
+    Next,
+    Previous,
+    Volume(VolumeChange),
+}
+
+/// Volume change in percent points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VolumeChange {
+    By(i32),
+    To(u32),
+}
+
+impl InstanceCommand {
+    /// Parse the words following `--command`, e.g. `["volume", "+5"]`.
+    pub fn parse<S: AsRef<str>>(words: &[S]) -> Result<Self, String> {
+        let words: Vec<&str> = words.iter().map(AsRef::as_ref).collect();
+        match words.as_slice() {
+            ["play-pause"] => Ok(Self::PlayPause),
+            ["next"] => Ok(Self::Next),
+            ["prev" | "previous"] => Ok(Self::Previous),
+            ["volume", amount] => VolumeChange::parse(amount).map(Self::Volume),
+            _ => Err(format!("unknown command {:?}", words.join(" "))),
+        }
+    }
+
+    /// Look for `--command` in the process arguments.  Returns `None` if the
+    /// GUI should start as usual.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Result<Self, String>> {
+        let mut args = args.into_iter().skip_while(|arg| arg != "--command");
+        args.next()?;
+        let words: Vec<String> = args.collect();
+        Some(Self::parse(&words))
+    }
+}
+
+impl fmt::Display for InstanceCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PlayPause => f.write_str("play-pause"),
+            Self::Next => f.write_str("next"),
+            Self::Previous => f.write_str("prev"),
+            Self::Volume(VolumeChange::By(delta)) => write!(f, "volume {delta:+}"),
+            Self::Volume(VolumeChange::To(percent)) => write!(f, "volume {percent}"),
+        }
+    }
+}
+
+impl VolumeChange {
+    fn parse(amount: &str) -> Result<Self, String> {
+        let invalid = |_| format!("invalid volume {amount:?}");
+        if amount.starts_with(['+', '-']) {
+            amount.parse().map(Self::By).map_err(invalid)
+        } else {
+            amount.parse().map(Self::To).map_err(invalid)
+        }
+    }
+
+    /// Apply the change to `volume`, in the range of 0.0 to 1.0.
+    pub fn apply(self, volume: f64) -> f64 {
+        let volume = match self {
+            Self::By(delta) => volume + f64::from(delta) / 100.0,
+            Self::To(percent) => f64::from(percent) / 100.0,
+        };
+        volume.clamp(0.0, 1.0)
+    }
+}
+
+/// Send `command` to the instance running with the config in `dir`.
+pub fn forward(dir: &Path, command: InstanceCommand) -> Result<(), String> {
+    let not_running = |_| "psst is not running".to_string();
+    let port: u16 = fs::read_to_string(dir.join(PORT_FILENAME))
+        .map_err(not_running)?
+        .trim()
+        .parse()
+        .map_err(|_| "psst is not running".to_string())?;
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(not_running)?;
+    stream.set_read_timeout(Some(TIMEOUT)).ok();
+    writeln!(stream, "{REQUEST_PREFIX}{command}").map_err(|err| err.to_string())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream.take(MAX_REQUEST_LENGTH))
+        .read_line(&mut reply)
+        .map_err(not_running)?;
+    match reply.trim_end() {
+        REPLY_OK => Ok(()),
+        "" => Err("psst is not running".to_string()),
+        err => Err(err.to_string()),
+    }
+}
+
+/// Accept commands from other processes on a background thread, calling
+/// `on_command` for each of them.  The port is written to the config in
+/// `dir` for `forward` to find.
+pub fn listen(dir: &Path, on_command: impl Fn(InstanceCommand) + Send + 'static) -> io::Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let port = listener.local_addr()?.port();
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(PORT_FILENAME), port.to_string())?;
+
+    thread::Builder::new()
+        .name("instance_commands".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        if let Err(err) = serve(stream, &on_command) {
+                            log::warn!("failed to read an instance command: {err}");
+                        }
+                    }
+                    Err(err) => log::warn!("failed to accept an instance command: {err}"),
+                }
+            }
+        })?;
+    Ok(())
+}
+
+fn serve(mut stream: TcpStream, on_command: &impl Fn(InstanceCommand)) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST_LENGTH)).read_line(&mut request)?;
+    let Some(request) = request.trim_end().strip_prefix(REQUEST_PREFIX) else {
+        return Ok(());
+    };
+    let words: Vec<&str> = request.split_whitespace().collect();
+    match InstanceCommand::parse(&words) {
+        Ok(command) => {
+            log::info!("received instance command: {command}");
+            on_command(command);
+            writeln!(stream, "{REPLY_OK}")
+        }
+        Err(err) => writeln!(stream, "{err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(
+            InstanceCommand::parse(&["play-pause"]),
+            Ok(InstanceCommand::PlayPause)
+        );
+        assert_eq!(
+            InstanceCommand::parse(&["prev"]),
+            Ok(InstanceCommand::Previous)
+        );
+        assert_eq!(
+            InstanceCommand::parse(&["volume", "+5"]),
+            Ok(InstanceCommand::Volume(VolumeChange::By(5)))
+        );
+        assert_eq!(
+            InstanceCommand::parse(&["volume", "-10"]),
+            Ok(InstanceCommand::Volume(VolumeChange::By(-10)))
+        );
+        assert_eq!(
+            InstanceCommand::parse(&["volume", "40"]),
+            Ok(InstanceCommand::Volume(VolumeChange::To(40)))
+        );
+        assert!(InstanceCommand::parse(&["volume", "loud"]).is_err());
+        assert!(InstanceCommand::parse(&["stop"]).is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for command in [
+            InstanceCommand::PlayPause,
+            InstanceCommand::Next,
+            InstanceCommand::Previous,
+            InstanceCommand::Volume(VolumeChange::By(5)),
+            InstanceCommand::Volume(VolumeChange::By(-5)),
+            InstanceCommand::Volume(VolumeChange::To(80)),
+        ] {
+            let line = command.to_string();
+            let words: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(InstanceCommand::parse(&words), Ok(command));
+        }
+    }
+
+    #[test]
+    fn finds_command_in_args() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(InstanceCommand::from_args(args(&["psst-gui"])), None);
+        assert_eq!(
+            InstanceCommand::from_args(args(&["psst-gui", "--command", "volume", "+5"])),
+            Some(Ok(InstanceCommand::Volume(VolumeChange::By(5))))
+        );
+    }
+
+    #[test]
+    fn volume_is_clamped() {
+        assert_eq!(VolumeChange::By(10).apply(0.95), 1.0);
+        assert_eq!(VolumeChange::By(-10).apply(0.05), 0.0);
+        assert_eq!(VolumeChange::To(50).apply(0.9), 0.5);
+    }
+
+    #[test]
+    fn forwards_to_listener() {
+        let dir = std::env::temp_dir().join(format!("psst-instance-{}", std::process::id()));
+        let (sender, receiver) = std::sync::mpsc::channel();
+        listen(&dir, move |command| sender.send(command).unwrap()).unwrap();
+
+        forward(&dir, InstanceCommand::Next).unwrap();
+        assert_eq!(receiver.recv().unwrap(), InstanceCommand::Next);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,117 @@
+//! Keeps the Spotify credentials, OAuth tokens and the proxy password in the
+//! keychain of the OS: the macOS Keychain, the Windows Credential Manager, or
+//! the Secret Service on Linux.
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use keyring::Entry;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use psst_core::connection::Credentials;
+
+const SERVICE: &str = "Psst";
+const CREDENTIALS: &str = "credentials";
+const OAUTH_BEARER: &str = "oauth-bearer";
+const OAUTH_REFRESH_TOKEN: &str = "oauth-refresh-token";
+const PROXY_PASSWORD: &str = "proxy-password";
+
+/// Secrets last read or written, the config is saved often and most saves do
+/// not touch them.
+static KNOWN: Lazy<Mutex<HashMap<&'static str, Option<String>>>> = Lazy::new(Default::default);
+
+/// Set once the keychain failed, so that the following saves of the config
+/// do not try, and wait for, it again until the next start.
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Default)]
+pub struct Secrets {
+    pub credentials: Option<Credentials>,
+    pub oauth_bearer: Option<String>,
+    pub oauth_refresh_token: Option<String>,
+    pub proxy_password: Option<String>,
+}
+
+impl Secrets {
+    pub fn is_empty(&self) -> bool {
+        self.credentials.is_none()
+            && self.oauth_bearer.is_none()
+            && self.oauth_refresh_token.is_none()
+            && self.proxy_password.is_none()
+    }
+}
+
+/// `false` if the keychain failed before in this session.
+pub fn is_available() -> bool {
+    !FAILED.load(Ordering::Relaxed)
+}
+
+pub fn load() -> keyring::Result<Secrets> {
+    remember_failure(load_secrets())
+}
+
+/// Writes the secrets that changed, and deletes the ones that are `None`.
+pub fn store(secrets: &Secrets) -> keyring::Result<()> {
+    remember_failure(store_secrets(secrets))
+}
+
+fn remember_failure<T>(result: keyring::Result<T>) -> keyring::Result<T> {
+    if result.is_err() {
+        FAILED.store(true, Ordering::Relaxed);
+    }
+    result
+}
+
+fn load_secrets() -> keyring::Result<Secrets> {
+    let credentials = read(CREDENTIALS)?.and_then(|json| {
+        serde_json::from_str(&json)
+            .map_err(|err| log::error!("failed to parse credentials from the keychain: {err}"))
+            .ok()
+    });
+    Ok(Secrets {
+        credentials,
+        oauth_bearer: read(OAUTH_BEARER)?,
+        oauth_refresh_token: read(OAUTH_REFRESH_TOKEN)?,
+        proxy_password: read(PROXY_PASSWORD)?,
+    })
+}
+
+fn store_secrets(secrets: &Secrets) -> keyring::Result<()> {
+    let credentials = secrets
+        .credentials
+        .as_ref()
+        .map(|credentials| serde_json::to_string(credentials).expect("serializable credentials"));
+    write(CREDENTIALS, credentials)?;
+    write(OAUTH_BEARER, secrets.oauth_bearer.clone())?;
+    write(OAUTH_REFRESH_TOKEN, secrets.oauth_refresh_token.clone())?;
+    write(PROXY_PASSWORD, secrets.proxy_password.clone())
+}
+
+fn read(name: &'static str) -> keyring::Result<Option<String>> {
+    let secret = match Entry::new(SERVICE, name)?.get_password() {
+        Ok(secret) => Some(secret),
+        Err(keyring::Error::NoEntry) => None,
+        Err(err) => return Err(err),
+    };
+    KNOWN.lock().insert(name, secret.clone());
+    Ok(secret)
+}
+
+fn write(name: &'static str, secret: Option<String>) -> keyring::Result<()> {
+    let mut known = KNOWN.lock();
+    if known.get(name) == Some(&secret) {
+        return Ok(());
+    }
+    let entry = Entry::new(SERVICE, name)?;
+    match &secret {
+        Some(secret) => entry.set_password(secret)?,
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(err) => return Err(err),
+        },
+    }
+    known.insert(name, secret);
+    Ok(())
+}
@@ -5,8 +5,19 @@ mod cmd;
 mod controller;
 mod data;
 mod delegate;
+mod discord;
 mod error;
+mod format;
+mod instance;
+mod keychain;
+mod network;
+mod notifications;
+#[cfg(target_os = "linux")]
+mod portal;
+mod sleep_inhibitor;
+mod test_mode;
 mod token_utils;
+mod tray;
 mod ui;
 mod webapi;
 mod widget;
@@ -16,11 +27,19 @@ use env_logger::{Builder, Env};
 use token_utils::TokenUtils;
 use webapi::WebApi;
 
-use psst_core::{cache::Cache, oauth::refresh_access_token};
+use psst_core::{
+    cache::Cache,
+    history::PlayHistory,
+    lastfm::{LastFmApi, ScrobbleQueue},
+    oauth::refresh_tokens,
+    session::token_refresh::TokenRefresher,
+};
 
 use crate::{
     data::{AppState, Config},
     delegate::Delegate,
+    instance::InstanceCommand,
+    test_mode::TestMode,
 };
 
 const ENV_LOG: &str = "PSST_LOG";
@@ -34,12 +53,43 @@ fn main() {
             .write_style(ENV_LOG_STYLE),
     )
     .init();
+    notifications::init_local_offset();
+
+    // Forward `--command` to the running instance instead of starting.
+    if let Some(command) = InstanceCommand::from_args(std::env::args()) {
+        let command = command.unwrap_or_else(|err| {
+            eprintln!("{err}\n{}", instance::USAGE);
+            std::process::exit(2);
+        });
+        let result = Config::config_dir()
+            .ok_or_else(|| "missing config directory".to_string())
+            .and_then(|dir| instance::forward(&dir, command));
+        if let Err(err) = result {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Start with fixtures instead of Spotify, and a script to run.
+    let test_mode = TestMode::install_from_env().unwrap_or_else(|err| {
+        eprintln!("failed to enter the test mode: {err}");
+        std::process::exit(1);
+    });
+    let test_script = std::env::var_os(test_mode::TEST_SCRIPT_ENV).map(|path| {
+        test_mode::load_script(path.as_ref()).unwrap_or_else(|err| {
+            eprintln!("{err}\n{}", test_mode::SCRIPT_USAGE);
+            std::process::exit(2);
+        })
+    });
 
     // Load configuration
     let config = Config::load().unwrap_or_default();
 
-    let paginated_limit = config.paginated_limit;
     let mut state = AppState::default_with_config(config.clone());
+    if test_mode.is_some() {
+        state.session.detach();
+    }
 
     if let Some(cache_dir) = Config::cache_dir() {
         match Cache::new(cache_dir) {
@@ -52,23 +102,44 @@ fn main() {
         }
     }
 
-    WebApi::new(
-        Config::proxy().as_deref(),
-        Config::cache_dir(),
-        paginated_limit,
-    )
-    .install_as_global();
+    WebApi::new(config.proxy_url().as_deref(), Config::cache_dir()).install_as_global();
+    WebApi::global().set_offline(config.offline_mode);
 
-    if let Some(refresh_token) = state.config.oauth_refresh_token.clone() {
-        match refresh_access_token(&refresh_token) {
-            Ok((access_token, maybe_refresh_token)) => {
+    // Load the local listening history, used to generate playlists.
+    let history = match Config::config_dir() {
+        Some(dir) => PlayHistory::open(dir.join("history.jsonl")).unwrap_or_else(|err| {
+            log::error!("Failed to load play history: {err}");
+            PlayHistory::in_memory()
+        }),
+        None => PlayHistory::in_memory(),
+    };
+    history.install_as_global();
+
+    // Scrobbles that could not be sent yet, flushed once Last.fm is reachable.
+    let scrobble_queue = match Config::config_dir() {
+        Some(dir) => ScrobbleQueue::open(dir.join("scrobbles.jsonl")).unwrap_or_else(|err| {
+            log::error!("Failed to load pending scrobbles: {err}");
+            ScrobbleQueue::in_memory()
+        }),
+        None => ScrobbleQueue::in_memory(),
+    };
+    state.pending_scrobbles = scrobble_queue.len();
+    scrobble_queue.install_as_global();
+    LastFmApi::new(config.proxy_url().as_deref()).install_as_global();
+
+    // The fixtures of the test mode need no fresh tokens.
+    let refresh_token = state.config.oauth_refresh_token.clone();
+    if let Some(refresh_token) = refresh_token.filter(|_| test_mode.is_none()) {
+        match refresh_tokens(&refresh_token) {
+            Ok(tokens) => {
                 TokenUtils::apply_refresh_result(
                     &state.session,
                     &mut state.config,
-                    access_token,
-                    maybe_refresh_token,
+                    tokens.access_token,
+                    tokens.refresh_token,
                     true,
                 );
+                state.session.set_oauth_expiry(tokens.expires_in);
             }
             Err(e) => {
                 log::warn!(
@@ -85,14 +156,16 @@ fn main() {
 
     let delegate;
     let launcher;
-    if state.config.has_credentials() {
+    if state.config.has_credentials() || test_mode.is_some() {
         // Credentials are configured, open the main window.
         let window = ui::main_window(&state.config);
         delegate = Delegate::with_main(window.id);
         launcher = AppLauncher::with_window(window).configure_env(ui::theme::setup);
 
-        // Load user's local tracks for the WebApi.
-        WebApi::global().load_local_tracks(state.config.username().unwrap());
+        // Load user's local tracks for the WebApi, the test mode has none.
+        if let (None, Some(username)) = (test_mode, state.config.username()) {
+            WebApi::global().load_local_tracks(username);
+        }
     } else {
         // No configured credentials, open the account setup.
         let window = ui::account_setup_window();
@@ -102,8 +175,58 @@ fn main() {
 
     WebApi::global().set_event_sink(launcher.get_external_handle());
 
+    // Accept `--command` from other processes.
+    if let Some(dir) = Config::config_dir() {
+        let event_sink = launcher.get_external_handle();
+        let result = instance::listen(&dir, move |command| {
+            event_sink
+                .submit_command(cmd::INSTANCE_COMMAND, command, druid::Target::Global)
+                .ok();
+        });
+        if let Err(err) = result {
+            log::warn!("failed to accept commands from other processes: {err}");
+        }
+    }
+
+    // Show the state of the session connection, including the reconnections.
+    let session_states = state.session.subscribe();
+    let event_sink = launcher.get_external_handle();
+    std::thread::spawn(move || {
+        for session_state in session_states {
+            if event_sink
+                .submit_command(
+                    cmd::SESSION_STATE_CHANGED,
+                    session_state,
+                    druid::Target::Global,
+                )
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    // Refresh the OAuth bearer before it expires, the delegate passes the new
+    // tokens on to the Web API and the config.
+    let _token_refresher = TokenRefresher::spawn(state.session.clone(), {
+        let event_sink = launcher.get_external_handle();
+        move |tokens| {
+            event_sink
+                .submit_command(
+                    cmd::OAUTH_TOKENS_REFRESHED,
+                    (tokens.access_token.clone(), tokens.refresh_token.clone()),
+                    druid::Target::Global,
+                )
+                .ok();
+        }
+    });
+
+    if let Some(commands) = test_script {
+        test_mode::run_script(commands, launcher.get_external_handle());
+    }
+
     // Check for updates on startup if enabled
-    if state.config.update_preferences.should_check_for_updates() {
+    if test_mode.is_none() && state.config.update_preferences.should_check_for_updates() {
         log::info!("Checking for updates on startup");
         launcher
             .get_external_handle()
@@ -111,8 +234,23 @@ fn main() {
             .ok();
     }
 
+    // Refresh the generated playlists in the Spotify account once a week
+    if test_mode.is_none()
+        && state.config.has_credentials()
+        && state.config.generated_playlists.should_sync()
+    {
+        launcher
+            .get_external_handle()
+            .submit_command(cmd::SYNC_GENERATED_PLAYLISTS, (), druid::Target::Global)
+            .ok();
+    }
+
     launcher
         .delegate(delegate)
         .launch(state)
         .expect("Application launch");
+
+    if let Some(test_mode) = test_mode {
+        test_mode.remove_scratch();
+    }
 }
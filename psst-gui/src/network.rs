@@ -0,0 +1,84 @@
+//! Detects whether the system is on a metered connection, like a mobile
+//! hotspot or a cellular modem, where streaming might cost money.
+
+#[cfg(windows)]
+use std::process::{Command, Stdio};
+
+/// `Some(true)` if the current connection is metered, `None` if the system
+/// does not tell.  Blocks on D-Bus or an external command, so this should not
+/// be called on the UI thread.
+pub fn is_metered() -> Option<bool> {
+    platform::is_metered()
+}
+
+#[cfg(windows)]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+    {
+        use std::os::windows::process::CommandExt;
+
+        // Do not flash a console window.
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use zbus::blocking::{Connection, Proxy};
+
+    /// Asks NetworkManager over D-Bus.  Its `Metered` property is one of
+    /// unknown (0), yes (1), no (2), guessed yes (3) and guessed no (4).
+    pub fn is_metered() -> Option<bool> {
+        let connection = Connection::system().ok()?;
+        let proxy = Proxy::new(
+            &connection,
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+        )
+        .ok()?;
+        match proxy.get_property::<u32>("Metered").ok()? {
+            1 | 3 => Some(true),
+            2 | 4 => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    /// Asks for the cost of the internet connection profile.  Any cost other
+    /// than unrestricted (1) or unknown (0) means the data is metered.
+    pub fn is_metered() -> Option<bool> {
+        const SCRIPT: &str = "[Windows.Networking.Connectivity.NetworkInformation, \
+            Windows.Networking.Connectivity, ContentType = WindowsRuntime]::\
+            GetInternetConnectionProfile().GetConnectionCost().NetworkCostType.value__";
+
+        let output = super::command_output(
+            "powershell",
+            &["-NoProfile", "-NonInteractive", "-Command", SCRIPT],
+        )?;
+        match output.trim() {
+            "0" | "" => None,
+            "1" => Some(false),
+            _ => Some(true),
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    pub fn is_metered() -> Option<bool> {
+        None
+    }
+}
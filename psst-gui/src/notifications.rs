@@ -0,0 +1,92 @@
+//! Sounds of the notifications, and the local time for the quiet hours and
+//! the listening history.
+//! The sounds play on an output of their own, on the device chosen for the
+//! music, so that they mix with it instead of interrupting it.
+
+use std::thread;
+
+use crossbeam_channel::{Receiver, Sender};
+use once_cell::sync::OnceCell;
+use psst_core::audio::{
+    generator::{Chime, ChimeGenerator},
+    output::{AudioOutput, AudioSink, DefaultAudioOutput},
+};
+use time::{Date, OffsetDateTime, UtcOffset};
+
+use crate::data::TimeOfDay;
+
+static LOCAL_OFFSET: OnceCell<UtcOffset> = OnceCell::new();
+static SOUNDS: OnceCell<Sender<(Chime, Option<String>)>> = OnceCell::new();
+
+/// Remember the offset of the local time zone.  Has to be called before any
+/// other thread is started, the offset cannot be read safely afterwards on
+/// some platforms.
+pub fn init_local_offset() {
+    let offset = UtcOffset::current_local_offset().unwrap_or_else(|err| {
+        log::warn!("failed to read the local time zone, using UTC: {err}");
+        UtcOffset::UTC
+    });
+    let _ = LOCAL_OFFSET.set(offset);
+}
+
+/// Local time of the day.  Follows changes of the time zone, like daylight
+/// saving time, only after a restart.
+pub fn time_of_day() -> TimeOfDay {
+    let now = OffsetDateTime::now_utc().to_offset(local_offset());
+    TimeOfDay::new(now.hour().into(), now.minute().into())
+}
+
+/// Local date of the Unix `timestamp`, in seconds.
+pub fn local_date(timestamp: u64) -> Date {
+    OffsetDateTime::from_unix_timestamp(timestamp as i64)
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+        .to_offset(local_offset())
+        .date()
+}
+
+/// Offset of the local time zone, read at the start.
+pub fn local_offset() -> UtcOffset {
+    LOCAL_OFFSET.get().copied().unwrap_or(UtcOffset::UTC)
+}
+
+/// Play `chime` in the background on `device`, the default output if `None`.
+/// The output is opened on first use, and again when the device changes.
+pub fn play_sound(chime: Chime, device: Option<String>) {
+    let sounds = SOUNDS.get_or_init(|| {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let spawned = thread::Builder::new()
+            .name("notification_sounds".to_string())
+            .spawn(move || play_sounds(receiver));
+        if let Err(err) = spawned {
+            log::error!("failed to spawn the notification sounds thread: {err}");
+        }
+        sender
+    });
+    let _ = sounds.send((chime, device));
+}
+
+fn play_sounds(chimes: Receiver<(Chime, Option<String>)>) {
+    let mut output: Option<(Option<String>, DefaultAudioOutput)> = None;
+    for (chime, device) in chimes {
+        if !matches!(&output, Some((opened, _)) if *opened == device) {
+            // Close the previous output before opening the new one.
+            output = None;
+            match DefaultAudioOutput::open_device(device.as_deref(), None) {
+                Ok(opened) => output = Some((device, opened)),
+                Err(err) => {
+                    log::error!("failed to open the output for notification sounds: {err}");
+                    continue;
+                }
+            }
+        }
+        if let Some((_, output)) = &output {
+            let sink = output.sink();
+            sink.play(ChimeGenerator::new(
+                chime,
+                sink.sample_rate(),
+                sink.channel_count(),
+            ));
+            sink.resume();
+        }
+    }
+}
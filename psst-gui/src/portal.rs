@@ -0,0 +1,208 @@
+//! Clients of the xdg-desktop-portal interfaces used on Wayland, where an
+//! application cannot grab keys or talk to the power manager directly.
+
+use std::{collections::HashMap, env, thread};
+
+use zbus::{
+    blocking::{Connection, Proxy},
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+};
+
+const DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// Inhibit flag of the Inhibit portal blocking the session from going idle.
+const INHIBIT_IDLE: u32 = 8;
+
+/// Returns true if the desktop session runs on Wayland, and the portals should
+/// be used instead of the X11 and logind paths.
+pub fn is_wayland_session() -> bool {
+    match env::var("XDG_SESSION_TYPE") {
+        Ok(session_type) => session_type.eq_ignore_ascii_case("wayland"),
+        Err(_) => env::var_os("WAYLAND_DISPLAY").is_some(),
+    }
+}
+
+/// A sleep inhibition taken through the Inhibit portal, released when
+/// dropped.
+pub struct InhibitRequest {
+    connection: Connection,
+    handle: OwnedObjectPath,
+}
+
+impl InhibitRequest {
+    pub fn acquire(reason: &str) -> zbus::Result<Self> {
+        let connection = Connection::session()?;
+        let options = HashMap::from([("reason", Value::from(reason))]);
+        let reply = connection.call_method(
+            Some(DESTINATION),
+            PATH,
+            Some("org.freedesktop.portal.Inhibit"),
+            "Inhibit",
+            &("", INHIBIT_IDLE, options),
+        )?;
+        let handle = reply.body::<OwnedObjectPath>()?;
+        Ok(Self { connection, handle })
+    }
+}
+
+impl Drop for InhibitRequest {
+    fn drop(&mut self) {
+        let result = self.connection.call_method(
+            Some(DESTINATION),
+            self.handle.as_ref(),
+            Some("org.freedesktop.portal.Request"),
+            "Close",
+            &(),
+        );
+        if let Err(err) = result {
+            log::warn!("failed to release the portal inhibitor: {err}");
+        }
+    }
+}
+
+/// A shortcut registered with the GlobalShortcuts portal.
+pub struct Shortcut {
+    pub id: &'static str,
+    pub description: &'static str,
+    /// Key the portal suggests to the user, in the XDG shortcuts format.
+    pub preferred_trigger: &'static str,
+}
+
+/// Bind `shortcuts` with the GlobalShortcuts portal on a background thread,
+/// and call `on_activated` with the id of every activated shortcut.  The
+/// desktop may ask the user to confirm the bindings first.  The session lives
+/// as long as the D-Bus connection, which is for the rest of the process.
+pub fn bind_global_shortcuts(
+    shortcuts: &'static [Shortcut],
+    on_activated: impl Fn(&str) + Send + 'static,
+) {
+    let result = thread::Builder::new()
+        .name("global_shortcuts".to_string())
+        .spawn(move || {
+            if let Err(err) = listen_global_shortcuts(shortcuts, on_activated) {
+                log::warn!("global shortcuts portal unavailable: {err}");
+            }
+        });
+    if let Err(err) = result {
+        log::error!("failed to spawn the global shortcuts thread: {err}");
+    }
+}
+
+fn listen_global_shortcuts(
+    shortcuts: &[Shortcut],
+    on_activated: impl Fn(&str),
+) -> zbus::Result<()> {
+    let connection = Connection::session()?;
+
+    let token = request_token();
+    let options = HashMap::from([
+        ("handle_token", Value::from(token.as_str())),
+        ("session_handle_token", Value::from(token.as_str())),
+    ]);
+    let results = request(&connection, &token, "CreateSession", &(options,))?;
+    let session = results
+        .get("session_handle")
+        .cloned()
+        .and_then(session_handle)
+        .ok_or_else(|| zbus::Error::Failure("portal returned no session".to_string()))?;
+
+    // Subscribe before binding, shortcuts can fire as soon as they are bound.
+    let proxy = Proxy::new(
+        &connection,
+        DESTINATION,
+        PATH,
+        "org.freedesktop.portal.GlobalShortcuts",
+    )?;
+    let activations = proxy.receive_signal("Activated")?;
+
+    let token = request_token();
+    let shortcuts: Vec<_> = shortcuts
+        .iter()
+        .map(|shortcut| {
+            let properties = HashMap::from([
+                ("description", Value::from(shortcut.description)),
+                ("preferred_trigger", Value::from(shortcut.preferred_trigger)),
+            ]);
+            (shortcut.id, properties)
+        })
+        .collect();
+    let options = HashMap::from([("handle_token", Value::from(token.as_str()))]);
+    request(
+        &connection,
+        &token,
+        "BindShortcuts",
+        &(session.as_ref(), shortcuts, "", options),
+    )?;
+    log::info!("bound global shortcuts through the portal");
+
+    for message in activations {
+        match message.body::<(OwnedObjectPath, String, u64, HashMap<String, OwnedValue>)>() {
+            Ok((activated_session, id, _, _)) if activated_session == session => on_activated(&id),
+            Ok(_) => {}
+            Err(err) => log::warn!("invalid shortcut activation: {err}"),
+        }
+    }
+    Ok(())
+}
+
+/// The session handle is specified as a string, but some portal backends
+/// send it as an object path.
+fn session_handle(value: OwnedValue) -> Option<OwnedObjectPath> {
+    match OwnedObjectPath::try_from(value.clone()) {
+        Ok(path) => Some(path),
+        Err(_) => String::try_from(value)
+            .ok()
+            .and_then(|path| OwnedObjectPath::try_from(path).ok()),
+    }
+}
+
+fn request_token() -> String {
+    format!("psst_{}", rand::random::<u32>())
+}
+
+/// Call a GlobalShortcuts method that answers through a request object, and
+/// wait for its response.  The subscription is made before the call on the
+/// path the portal derives from `token`, so that the response cannot be missed.
+fn request<B>(
+    connection: &Connection,
+    token: &str,
+    method: &str,
+    body: &B,
+) -> zbus::Result<HashMap<String, OwnedValue>>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    let sender = connection
+        .unique_name()
+        .ok_or_else(|| zbus::Error::Failure("connection has no name".to_string()))?
+        .as_str()
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let path = format!("{PATH}/request/{sender}/{token}");
+    let proxy = Proxy::new(
+        connection,
+        DESTINATION,
+        ObjectPath::try_from(path.as_str())?,
+        "org.freedesktop.portal.Request",
+    )?;
+    let mut responses = proxy.receive_signal("Response")?;
+
+    connection.call_method(
+        Some(DESTINATION),
+        PATH,
+        Some("org.freedesktop.portal.GlobalShortcuts"),
+        method,
+        body,
+    )?;
+
+    let response = responses
+        .next()
+        .ok_or_else(|| zbus::Error::Failure(format!("no response to {method}")))?;
+    let (code, results) = response.body::<(u32, HashMap<String, OwnedValue>)>()?;
+    match code {
+        0 => Ok(results),
+        1 => Err(zbus::Error::Failure(format!("{method} was cancelled"))),
+        _ => Err(zbus::Error::Failure(format!("{method} failed"))),
+    }
+}
@@ -0,0 +1,224 @@
+//! Keeps the system awake while music is playing.  Only idle system sleep is
+//! blocked, the display is still allowed to turn off.
+
+/// Holds a platform sleep inhibitor while active.  The inhibitor is released
+/// on `release` or when dropped.
+pub struct SleepInhibitor {
+    inner: Option<platform::Inhibitor>,
+}
+
+impl SleepInhibitor {
+    pub fn new() -> Self {
+        Self { inner: None }
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        if active {
+            self.inhibit();
+        } else {
+            self.release();
+        }
+    }
+
+    pub fn inhibit(&mut self) {
+        if self.inner.is_none() {
+            match platform::Inhibitor::acquire() {
+                Ok(inhibitor) => {
+                    log::info!("preventing system sleep while playing");
+                    self.inner = Some(inhibitor);
+                }
+                Err(err) => {
+                    log::warn!("failed to prevent system sleep: {err}");
+                }
+            }
+        }
+    }
+
+    pub fn release(&mut self) {
+        if self.inner.take().is_some() {
+            log::info!("allowing system sleep");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::{Child, Command, Stdio};
+
+    use crate::portal::{self, InhibitRequest};
+
+    /// On Wayland the Inhibit portal is asked first, as sandboxed sessions
+    /// cannot reach logind.  Elsewhere, or if the portal is missing,
+    /// `systemd-inhibit` is used.
+    pub enum Inhibitor {
+        Portal(InhibitRequest),
+        Systemd(SystemdInhibit),
+    }
+
+    impl Inhibitor {
+        pub fn acquire() -> Result<Self, String> {
+            if portal::is_wayland_session() {
+                match InhibitRequest::acquire("Playing audio") {
+                    Ok(request) => return Ok(Self::Portal(request)),
+                    Err(err) => log::info!("inhibit portal unavailable: {err}"),
+                }
+            }
+            SystemdInhibit::acquire().map(Self::Systemd)
+        }
+    }
+
+    /// Runs `systemd-inhibit` for as long as the inhibitor lives.  The lock
+    /// taken by logind is released as soon as the process exits.
+    pub struct SystemdInhibit {
+        child: Child,
+    }
+
+    impl SystemdInhibit {
+        fn acquire() -> Result<Self, String> {
+            let child = Command::new("systemd-inhibit")
+                .args([
+                    "--what=idle",
+                    "--who=Psst",
+                    "--why=Playing audio",
+                    "--mode=block",
+                    "sleep",
+                    "infinity",
+                ])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .map_err(|err| format!("cannot run systemd-inhibit: {err}"))?;
+            Ok(Self { child })
+        }
+    }
+
+    impl Drop for SystemdInhibit {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{ffi::c_void, os::raw::c_char};
+
+    type CFStringRef = *const c_void;
+    type IOPMAssertionID = u32;
+    type IOReturn = i32;
+
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const K_IOPM_ASSERTION_LEVEL_ON: u32 = 255;
+    const K_IO_RETURN_SUCCESS: IOReturn = 0;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            alloc: *const c_void,
+            c_str: *const c_char,
+            encoding: u32,
+        ) -> CFStringRef;
+        fn CFRelease(cf: *const c_void);
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPMAssertionCreateWithName(
+            assertion_type: CFStringRef,
+            assertion_level: u32,
+            assertion_name: CFStringRef,
+            assertion_id: *mut IOPMAssertionID,
+        ) -> IOReturn;
+        fn IOPMAssertionRelease(assertion_id: IOPMAssertionID) -> IOReturn;
+    }
+
+    /// An IOKit power assertion that keeps the system, but not the display,
+    /// from idle sleeping.
+    pub struct Inhibitor {
+        assertion_id: IOPMAssertionID,
+    }
+
+    impl Inhibitor {
+        pub fn acquire() -> Result<Self, String> {
+            let mut assertion_id = 0;
+            let result = unsafe {
+                let assertion_type = cf_string(c"PreventUserIdleSystemSleep".as_ptr());
+                let name = cf_string(c"Psst is playing audio".as_ptr());
+                let result = IOPMAssertionCreateWithName(
+                    assertion_type,
+                    K_IOPM_ASSERTION_LEVEL_ON,
+                    name,
+                    &mut assertion_id,
+                );
+                CFRelease(assertion_type);
+                CFRelease(name);
+                result
+            };
+            if result == K_IO_RETURN_SUCCESS {
+                Ok(Self { assertion_id })
+            } else {
+                Err(format!("IOPMAssertionCreateWithName failed: {result:#x}"))
+            }
+        }
+    }
+
+    impl Drop for Inhibitor {
+        fn drop(&mut self) {
+            unsafe {
+                IOPMAssertionRelease(self.assertion_id);
+            }
+        }
+    }
+
+    unsafe fn cf_string(s: *const c_char) -> CFStringRef {
+        CFStringCreateWithCString(std::ptr::null(), s, K_CF_STRING_ENCODING_UTF8)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    const ES_CONTINUOUS: u32 = 0x8000_0000;
+    const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+
+    /// Sets the execution state of the calling thread.  The state belongs to
+    /// the thread, so the inhibitor has to be dropped on the thread that
+    /// acquired it (the UI thread).
+    pub struct Inhibitor;
+
+    impl Inhibitor {
+        pub fn acquire() -> Result<Self, String> {
+            let previous = unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+            if previous == 0 {
+                Err("SetThreadExecutionState failed".to_string())
+            } else {
+                Ok(Self)
+            }
+        }
+    }
+
+    impl Drop for Inhibitor {
+        fn drop(&mut self) {
+            unsafe {
+                SetThreadExecutionState(ES_CONTINUOUS);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    pub struct Inhibitor;
+
+    impl Inhibitor {
+        pub fn acquire() -> Result<Self, String> {
+            Err("not supported on this platform".to_string())
+        }
+    }
+}
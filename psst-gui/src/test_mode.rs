@@ -0,0 +1,293 @@
+//! Test mode of the GUI, the counterpart of `PSST_CLI_TEST_MODE`.  With
+//! `PSST_GUI_TEST_MODE` set to a directory of fixtures, the GUI starts
+//! without reaching Spotify: the Web API answers from the fixtures, the
+//! session is detached and never connects, and the config, cache and history
+//! are kept in a scratch directory of their own, removed again on quit.  `PSST_GUI_TEST_SCRIPT` names a file of
+//! commands run once the app is up, for reproducible bug reports and UI
+//! tests.
+//!
+//! Fixtures are laid out like the requested URLs, the response to
+//! `https://api.spotify.com/v1/me/playlists` is read from
+//! `api.spotify.com/v1/me/playlists.json`, or from the file without the
+//! extension, e.g. for images.  Paged responses list all their items in one
+//! fixture.  A `config.json` in the directory is used as the config.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use druid::{ExtEventSink, Target};
+use once_cell::sync::OnceCell;
+use serde_json::Value;
+
+use crate::{cmd, data::Nav, instance::InstanceCommand};
+
+pub const TEST_MODE_ENV: &str = "PSST_GUI_TEST_MODE";
+pub const TEST_SCRIPT_ENV: &str = "PSST_GUI_TEST_SCRIPT";
+
+const CONFIG_FILENAME: &str = "config.json";
+
+pub const SCRIPT_USAGE: &str = "script commands: wait MILLISECONDS | go home|browse|new-releases|saved-tracks|saved-albums|shows|episodes|local-files|history|stats | search QUERY | open SPOTIFY_URL | back | play-pause | next | prev | volume [+|-]PERCENT | quit";
+
+pub struct TestMode {
+    fixtures: PathBuf,
+    scratch: PathBuf,
+}
+
+static GLOBAL_TEST_MODE: OnceCell<TestMode> = OnceCell::new();
+
+impl TestMode {
+    /// Enter the test mode if `PSST_GUI_TEST_MODE` is set.  Has to be called
+    /// before the config is loaded.
+    pub fn install_from_env() -> io::Result<Option<&'static Self>> {
+        let Some(fixtures) = env::var_os(TEST_MODE_ENV) else {
+            return Ok(None);
+        };
+        let fixtures = PathBuf::from(fixtures);
+        let scratch = env::temp_dir().join(format!("psst-gui-test-{}", process::id()));
+        if scratch.exists() {
+            fs::remove_dir_all(&scratch)?;
+        }
+        let test_mode = Self { fixtures, scratch };
+        fs::create_dir_all(test_mode.config_dir())?;
+        fs::create_dir_all(test_mode.cache_dir())?;
+        let config = test_mode.fixtures.join(CONFIG_FILENAME);
+        if config.is_file() {
+            fs::copy(&config, test_mode.config_dir().join(CONFIG_FILENAME))?;
+        }
+        log::info!(
+            "test mode with fixtures in {:?}, scratch files in {:?}",
+            test_mode.fixtures,
+            test_mode.scratch
+        );
+        Ok(Some(GLOBAL_TEST_MODE.get_or_init(|| test_mode)))
+    }
+
+    pub fn global() -> Option<&'static Self> {
+        GLOBAL_TEST_MODE.get()
+    }
+
+    pub fn config_dir(&self) -> PathBuf {
+        self.scratch.join("config")
+    }
+
+    pub fn cache_dir(&self) -> PathBuf {
+        self.scratch.join("cache")
+    }
+
+    /// Remove the scratch directory, once the app has quit.
+    pub fn remove_scratch(&self) {
+        if let Err(err) = fs::remove_dir_all(&self.scratch) {
+            log::warn!("failed to remove {:?}: {err}", self.scratch);
+        }
+    }
+
+    /// Body of the fixture for a request to `host` and `path`.  With an
+    /// `offset`, the page of `limit` items starting there is cut out of a
+    /// paged fixture.
+    pub fn fixture(
+        &self,
+        host: &str,
+        path: &str,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> io::Result<Vec<u8>> {
+        let base = self.fixtures.join(host).join(path.trim_start_matches('/'));
+        let json = base.with_file_name(format!(
+            "{}.json",
+            base.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        let file = if json.is_file() { json } else { base };
+        let body = fs::read(&file).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("missing fixture {}: {err}", file.display()),
+            )
+        })?;
+        Ok(match offset {
+            Some(offset) => page(body, offset, limit.unwrap_or(usize::MAX)),
+            None => body,
+        })
+    }
+}
+
+/// Cut the items from `offset` out of a paged response, and set its paging
+/// fields to match.  Other responses are returned as they are.
+fn page(body: Vec<u8>, offset: usize, limit: usize) -> Vec<u8> {
+    let Ok(mut page) = serde_json::from_slice::<Value>(&body) else {
+        return body;
+    };
+    let Some(items) = page.get_mut("items").and_then(Value::as_array_mut) else {
+        return body;
+    };
+    let total = items.len();
+    *items = items.drain(..).skip(offset).take(limit).collect();
+    page["offset"] = offset.into();
+    page["limit"] = limit.min(total).into();
+    page["total"] = total.into();
+    serde_json::to_vec(&page).unwrap_or(body)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptCommand {
+    Wait(Duration),
+    Navigate(Nav),
+    Back,
+    Instance(InstanceCommand),
+    Quit,
+}
+
+impl ScriptCommand {
+    /// Parse one line of a script, `None` for empty lines and comments
+    /// starting with `#`.
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let command = match (word, rest) {
+            ("wait", millis) => millis
+                .parse()
+                .map(|millis| Self::Wait(Duration::from_millis(millis)))
+                .map_err(|_| format!("invalid wait {millis:?}"))?,
+            ("go", page) => Self::Navigate(match page {
+                "home" => Nav::Home,
+                "browse" => Nav::Browse,
+                "new-releases" => Nav::NewReleases,
+                "saved-tracks" => Nav::SavedTracks,
+                "saved-albums" => Nav::SavedAlbums,
+                "shows" => Nav::Shows,
+                "episodes" => Nav::SavedEpisodes,
+                "local-files" => Nav::LocalFiles,
+                "history" => Nav::History,
+                "stats" => Nav::Stats,
+                _ => return Err(format!("unknown page {page:?}")),
+            }),
+            // Links typed into the search open the linked page.
+            ("search" | "open", query) if !query.is_empty() => {
+                Self::Navigate(Nav::SearchResults(Arc::from(query)))
+            }
+            ("back", "") => Self::Back,
+            ("quit", "") => Self::Quit,
+            _ => {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                Self::Instance(InstanceCommand::parse(&words)?)
+            }
+        };
+        Ok(Some(command))
+    }
+}
+
+/// Read the script at `path`, failing on the first line that cannot be
+/// parsed.
+pub fn load_script(path: &Path) -> Result<Vec<ScriptCommand>, String> {
+    let script = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+    parse_script(&script)
+}
+
+fn parse_script(script: &str) -> Result<Vec<ScriptCommand>, String> {
+    let mut commands = Vec::new();
+    for (number, line) in script.lines().enumerate() {
+        let command =
+            ScriptCommand::parse(line).map_err(|err| format!("line {}: {err}", number + 1))?;
+        commands.extend(command);
+    }
+    Ok(commands)
+}
+
+/// Submit the commands of a script in order on a background thread.
+pub fn run_script(commands: Vec<ScriptCommand>, sink: ExtEventSink) {
+    let spawned = thread::Builder::new()
+        .name("test_script".to_string())
+        .spawn(move || {
+            for command in commands {
+                log::info!("test script: {command:?}");
+                let submitted = match command {
+                    ScriptCommand::Wait(duration) => {
+                        thread::sleep(duration);
+                        Ok(())
+                    }
+                    ScriptCommand::Navigate(nav) => {
+                        sink.submit_command(cmd::NAVIGATE, nav, Target::Global)
+                    }
+                    ScriptCommand::Back => {
+                        sink.submit_command(cmd::NAVIGATE_BACK, 1, Target::Global)
+                    }
+                    ScriptCommand::Instance(command) => {
+                        sink.submit_command(cmd::INSTANCE_COMMAND, command, Target::Global)
+                    }
+                    ScriptCommand::Quit => {
+                        sink.submit_command(cmd::QUIT_APP_WITH_SAVE, (), Target::Global)
+                    }
+                };
+                if submitted.is_err() {
+                    // The app has quit.
+                    break;
+                }
+            }
+        });
+    if let Err(err) = spawned {
+        log::error!("failed to spawn the test script thread: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scripts() {
+        let script = "# open a playlist\n\
+                      wait 500\n\
+                      \n\
+                      go saved-tracks\n\
+                      open https://open.spotify.com/playlist/abc\n\
+                      volume -5\n\
+                      back\n\
+                      quit\n";
+        assert_eq!(
+            parse_script(script),
+            Ok(vec![
+                ScriptCommand::Wait(Duration::from_millis(500)),
+                ScriptCommand::Navigate(Nav::SavedTracks),
+                ScriptCommand::Navigate(Nav::SearchResults(
+                    "https://open.spotify.com/playlist/abc".into()
+                )),
+                ScriptCommand::Instance(InstanceCommand::Volume(
+                    crate::instance::VolumeChange::By(-5)
+                )),
+                ScriptCommand::Back,
+                ScriptCommand::Quit,
+            ])
+        );
+        assert_eq!(
+            parse_script("go home\ngo nowhere"),
+            Err("line 2: unknown page \"nowhere\"".to_string())
+        );
+        assert!(parse_script("wait soon").is_err());
+        assert!(parse_script("search").is_err());
+    }
+
+    #[test]
+    fn pages_are_cut_out_of_fixtures() {
+        let fixture = br#"{"items": [1, 2, 3], "limit": 50, "offset": 0, "total": 3}"#.to_vec();
+        let cut = |offset, limit| {
+            serde_json::from_slice::<Value>(&page(fixture.clone(), offset, limit)).unwrap()
+        };
+        assert_eq!(cut(0, 2)["items"], serde_json::json!([1, 2]));
+        assert_eq!(cut(2, 2)["items"], serde_json::json!([3]));
+        assert_eq!(cut(2, 2)["total"], 3);
+        assert_eq!(cut(3, 50)["items"], serde_json::json!([]));
+
+        let other = br#"{"id": "abc"}"#.to_vec();
+        assert_eq!(page(other.clone(), 10, 10), other);
+    }
+}
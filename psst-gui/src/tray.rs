@@ -0,0 +1,111 @@
+//! Icon in the system tray on Windows, or the menu bar on macOS, with mini
+//! playback controls.  Lets Psst keep playing while the main window is
+//! hidden.  Linux has no tray, see the dependencies of the crate.
+
+use std::error::Error;
+
+#[cfg(any(windows, target_os = "macos"))]
+use druid::image;
+#[cfg(any(windows, target_os = "macos"))]
+use tray_icon::{
+    menu::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem},
+    Icon, TrayIcon, TrayIconBuilder,
+};
+
+#[cfg(any(windows, target_os = "macos"))]
+const ICON: &[u8] = include_bytes!("../assets/logo_32.png");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(any(windows, target_os = "macos")), allow(dead_code))]
+pub enum TrayAction {
+    PlayPause,
+    Next,
+    Previous,
+    ToggleWindow,
+    Quit,
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+pub struct Tray {
+    icon: TrayIcon,
+    play_pause: MenuItem,
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+impl Tray {
+    /// Show the icon, calling `on_action` for every picked menu entry.  Has
+    /// to be called on the main thread, after the platform event loop was
+    /// initialized.
+    pub fn new(
+        on_action: impl Fn(TrayAction) + Send + Sync + 'static,
+    ) -> Result<Self, Box<dyn Error>> {
+        let play_pause = MenuItem::new("Play", true, None);
+        let next = MenuItem::new("Next", true, None);
+        let previous = MenuItem::new("Previous", true, None);
+        let toggle_window = MenuItem::new("Show/Hide Window", true, None);
+        let quit = MenuItem::new("Quit Psst", true, None);
+        let menu = Menu::with_items(&[
+            &play_pause,
+            &next,
+            &previous,
+            &PredefinedMenuItem::separator(),
+            &toggle_window,
+            &quit,
+        ])?;
+
+        let actions: Vec<(MenuId, TrayAction)> = vec![
+            (play_pause.id().clone(), TrayAction::PlayPause),
+            (next.id().clone(), TrayAction::Next),
+            (previous.id().clone(), TrayAction::Previous),
+            (toggle_window.id().clone(), TrayAction::ToggleWindow),
+            (quit.id().clone(), TrayAction::Quit),
+        ];
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            if let Some((_, action)) = actions.iter().find(|(id, _)| *id == event.id) {
+                on_action(*action);
+            }
+        }));
+
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("Psst")
+            .with_icon(load_icon()?)
+            .build()?;
+        Ok(Self { icon, play_pause })
+    }
+
+    /// Show the playing item in the tooltip, and the action of the play/pause
+    /// entry.
+    pub fn set_now_playing(&self, title: Option<&str>, playing: bool) {
+        self.play_pause
+            .set_text(if playing { "Pause" } else { "Play" });
+        let tooltip = match title {
+            Some(title) => format!("Psst\n{title}"),
+            None => "Psst".to_string(),
+        };
+        if let Err(err) = self.icon.set_tooltip(Some(tooltip)) {
+            log::warn!("failed to set the tray tooltip: {err}");
+        }
+    }
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn load_icon() -> Result<Icon, Box<dyn Error>> {
+    let image = image::load_from_memory(ICON)?.into_rgba8();
+    let (width, height) = image.dimensions();
+    Ok(Icon::from_rgba(image.into_raw(), width, height)?)
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub struct Tray;
+
+#[cfg(not(any(windows, target_os = "macos")))]
+impl Tray {
+    pub fn new(
+        _on_action: impl Fn(TrayAction) + Send + Sync + 'static,
+    ) -> Result<Self, Box<dyn Error>> {
+        Err("no tray on this platform".into())
+    }
+
+    pub fn set_now_playing(&self, _title: Option<&str>, _playing: bool) {}
+}
@@ -8,9 +8,10 @@ use druid::{
 use crate::{
     cmd,
     data::{
-        Album, AlbumDetail, AlbumLink, AppState, ArtistLink, Cached, CommonCtx, Ctx, Library, Nav,
-        Playable, PlaybackOrigin, WithCtx,
+        Album, AlbumDetail, AlbumLink, AppState, ArtistLink, Cached, CommonCtx, Copyright, Ctx,
+        Library, Nav, Playable, PlaybackOrigin, QueuePlacement, RecommendationsRequest, WithCtx,
     },
+    format,
     ui::playable::PlayableIter,
     webapi::WebApi,
     widget::{icons, Async, MyWidgetExt, RemoteImage},
@@ -51,11 +52,20 @@ fn loaded_detail_widget() -> impl Widget<WithCtx<Cached<Arc<Album>>>> {
     let album_date = Label::dynamic(|album: &Arc<Album>, _| album.release())
         .with_text_size(theme::TEXT_SIZE_SMALL);
 
-    let album_label = Label::raw()
-        .with_line_break_mode(LineBreaking::WordWrap)
-        .with_text_size(theme::TEXT_SIZE_SMALL)
-        .with_text_color(theme::PLACEHOLDER_COLOR)
-        .lens(Album::label.in_arc());
+    let album_summary =
+        Label::dynamic(|album: &Arc<Album>, _| utils::track_list_summary(&album.tracks))
+            .with_text_size(theme::TEXT_SIZE_SMALL);
+
+    let album_label = Label::dynamic(|album: &Arc<Album>, _| {
+        if album.label.is_empty() {
+            String::new()
+        } else {
+            format!("Label: {}", album.label)
+        }
+    })
+    .with_line_break_mode(LineBreaking::WordWrap)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR);
 
     let album_info = Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
@@ -63,6 +73,8 @@ fn loaded_detail_widget() -> impl Widget<WithCtx<Cached<Arc<Album>>>> {
         .with_default_spacer()
         .with_child(album_date)
         .with_default_spacer()
+        .with_child(album_summary)
+        .with_default_spacer()
         .with_child(album_label)
         .padding(theme::grid(1.0));
 
@@ -81,12 +93,26 @@ fn loaded_detail_widget() -> impl Widget<WithCtx<Cached<Arc<Album>>>> {
         },
     });
 
+    let album_copyrights = Label::dynamic(|album: &Arc<Album>, _| {
+        album
+            .copyrights
+            .iter()
+            .map(Copyright::notice)
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+    .with_line_break_mode(LineBreaking::WordWrap)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR)
+    .padding(theme::grid(1.0));
+
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_spacer(theme::grid(1.0))
         .with_child(album_top)
         .with_spacer(theme::grid(1.0))
         .with_child(album_tracks)
+        .with_child(album_copyrights.lens(Ctx::data()))
         .lens(Ctx::map(Cached::data))
 }
 
@@ -94,6 +120,7 @@ fn cover_widget(size: f64) -> impl Widget<Arc<Album>> {
     RemoteImage::new(utils::placeholder_widget(), move |album: &Arc<Album>, _| {
         album.image(size, size).map(|image| image.url.clone())
     })
+    .with_size((size, size))
     .fix_size(size, size)
 }
 
@@ -135,10 +162,19 @@ pub fn album_widget(horizontal: bool) -> impl Widget<WithCtx<Arc<Album>>> {
     .with_spacing(theme::grid(1.0))
     .lens(Album::artists.in_arc());
 
-    let album_date = Label::<Arc<Album>>::dynamic(|album, _| album.release_year())
-        .with_line_break_mode(LineBreaking::WordWrap)
-        .with_text_size(theme::TEXT_SIZE_SMALL)
-        .with_text_color(theme::PLACEHOLDER_COLOR);
+    let album_date = Label::<Arc<Album>>::dynamic(|album, _| {
+        let mut text = album.release_year();
+        if let Some(saved_at) = album.saved_at {
+            text = format!("{text} • Saved {}", format::date(saved_at));
+        }
+        match album.restriction() {
+            Some(reason) => format!("{text} • {}", reason.explanation()),
+            None => text,
+        }
+    })
+    .with_line_break_mode(LineBreaking::WordWrap)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR);
 
     let album_layout = if horizontal {
         Flex::column()
@@ -221,6 +257,25 @@ fn album_menu(album: &Arc<Album>, library: &Arc<Library>) -> Menu<AppState> {
 
     menu = menu.separator();
 
+    menu = menu.entry(
+        MenuItem::new(LocalizedString::new("menu-item-play-next").with_placeholder("Play Next"))
+            .command(cmd::QUEUE_ALBUM.with((album.link(), QueuePlacement::Next))),
+    );
+    menu = menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-add-to-queue").with_placeholder("Add Album to Queue"),
+        )
+        .command(cmd::QUEUE_ALBUM.with((album.link(), QueuePlacement::Last))),
+    );
+    menu = menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-start-radio").with_placeholder("Start Radio"),
+        )
+        .command(cmd::START_RADIO.with(Arc::new(RecommendationsRequest::album_radio(album)))),
+    );
+
+    menu = menu.separator();
+
     if library.contains_album(album) {
         menu = menu.entry(
             MenuItem::new(
@@ -247,6 +302,10 @@ impl PlayableIter for Arc<Album> {
         PlaybackOrigin::Album(self.link())
     }
 
+    fn group_header(&self, position: usize) -> Option<Arc<str>> {
+        self.disc_header(position)
+    }
+
     fn for_each(&self, mut cb: impl FnMut(Playable, usize)) {
         for (position, track) in self.clone().into_tracks_with_context().iter().enumerate() {
             cb(Playable::Track(track.clone()), position);
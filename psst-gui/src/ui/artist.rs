@@ -1,32 +1,50 @@
+use std::sync::Arc;
+
 use druid::{
     im::Vector,
     kurbo::Circle,
     widget::{CrossAxisAlignment, Either, Flex, Label, LabelText, LineBreaking, List, Scroll},
-    Data, Insets, LensExt, LocalizedString, Menu, MenuItem, Selector, Size, UnitPoint, Widget,
+    Data, Env, Insets, LensExt, LocalizedString, Menu, MenuItem, Selector, Size, UnitPoint, Widget,
     WidgetExt,
 };
 
 use crate::{
     cmd,
     data::{
-        AppState, Artist, ArtistAlbums, ArtistDetail, ArtistInfo, ArtistLink, ArtistTracks, Cached,
-        Ctx, Nav, WithCtx,
+        AlbumType, AppState, Artist, ArtistAlbums, ArtistDetail, ArtistInfo, ArtistLink,
+        ArtistReleases, ArtistTracks, Cached, Ctx, Nav, RecommendationsRequest, WithCtx,
     },
+    format,
     ui::utils::{stat_row, InfoLayout},
     webapi::WebApi,
     widget::{Async, Empty, MyWidgetExt, RemoteImage},
 };
 
 use super::{
-    album, playable, theme, track,
+    album,
+    find::Findable,
+    playable, theme, track,
     utils::{self},
 };
 
 pub const LOAD_DETAIL: Selector<ArtistLink> = Selector::new("app.artist.load-detail");
 
+pub const FOLLOW_ARTIST: Selector<ArtistLink> = Selector::new("app.artist.follow");
+pub const UNFOLLOW_ARTIST: Selector<ArtistLink> = Selector::new("app.artist.unfollow");
+
+/// Load all releases of the group on the shown artist page.
+const SHOW_ALL_RELEASES: Selector<AlbumType> = Selector::new("app.artist.show-all-releases");
+const LOAD_ALL_RELEASES: Selector<(ArtistLink, AlbumType)> =
+    Selector::new("app.artist.load-all-releases");
+
 pub fn detail_widget() -> impl Widget<AppState> {
     Flex::column()
         .with_child(async_artist_info().padding((theme::grid(1.0), 0.0)))
+        .with_child(
+            follow_widget()
+                .align_left()
+                .padding((theme::grid(1.0), 0.0)),
+        )
         .with_child(async_top_tracks_widget())
         .with_child(async_albums_widget().padding((theme::grid(1.0), 0.0)))
         .with_child(async_related_widget().padding((theme::grid(1.0), 0.0)))
@@ -75,6 +93,102 @@ fn async_albums_widget() -> impl Widget<AppState> {
             |_, data, d| data.artist_detail.albums.defer(d),
             |_, data, r| data.artist_detail.albums.update(r),
         )
+        .on_command(SHOW_ALL_RELEASES, |ctx, group, data| {
+            if let Some(link) = data.artist_detail.albums.deferred() {
+                ctx.submit_command(LOAD_ALL_RELEASES.with((link.clone(), group.clone())));
+            }
+        })
+        .on_command_async(
+            LOAD_ALL_RELEASES,
+            |(link, group)| WebApi::global().get_artist_releases(&link.id, group),
+            |_, data, (_, group)| {
+                if let Some(albums) = data.artist_detail.albums.resolved_mut() {
+                    albums.group_mut(&group).loading = true;
+                }
+            },
+            |_, data, ((link, group), result)| {
+                // Dropped if another artist is shown by the time it loads.
+                if !data.artist_detail.albums.contains(&link) {
+                    return;
+                }
+                let Some(albums) = data.artist_detail.albums.resolved_mut() else {
+                    return;
+                };
+                match result {
+                    Ok(releases) => *albums.group_mut(&group) = releases,
+                    Err(err) => {
+                        albums.group_mut(&group).loading = false;
+                        data.error_alert(err);
+                    }
+                }
+            },
+        )
+}
+
+fn follow_widget() -> impl Widget<AppState> {
+    let is_following = |data: &AppState| data.artist_detail.following.resolved() == Some(&true);
+
+    let button = Label::dynamic(move |data: &AppState, _| {
+        if is_following(data) {
+            "Following".to_string()
+        } else {
+            "Follow".to_string()
+        }
+    })
+    .with_font(theme::UI_FONT_MEDIUM)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .padding((theme::grid(1.5), theme::grid(0.5)))
+    .link()
+    .rounded(theme::BUTTON_BORDER_RADIUS)
+    .active(move |data: &AppState, _| is_following(data))
+    .on_left_click(move |ctx, _, data: &mut AppState, _| {
+        if let Some(link) = data.artist_detail.following.deferred() {
+            let command = if is_following(data) {
+                UNFOLLOW_ARTIST
+            } else {
+                FOLLOW_ARTIST
+            };
+            ctx.submit_command(command.with(link.clone()));
+        }
+    });
+
+    Either::new(
+        |data: &AppState, _| data.artist_detail.following.is_resolved(),
+        button,
+        Empty,
+    )
+    .on_command_async(
+        LOAD_DETAIL,
+        |d| WebApi::global().is_following_artist(&d.id),
+        |_, data, d| data.artist_detail.following.defer(d),
+        |_, data, r| data.artist_detail.following.update(r),
+    )
+    .on_command_async(
+        FOLLOW_ARTIST,
+        |link| WebApi::global().follow_artist(&link.id),
+        |_, data, link| data.artist_detail.following.resolve(link, true),
+        |_, data, (link, r)| {
+            if let Err(err) = r {
+                data.artist_detail.following.resolve(link, false);
+                data.error_alert(err);
+            } else {
+                data.info_alert(format!("Following {}.", link.name));
+            }
+        },
+    )
+    .on_command_async(
+        UNFOLLOW_ARTIST,
+        |link| WebApi::global().unfollow_artist(&link.id),
+        |_, data, link| data.artist_detail.following.resolve(link, false),
+        |_, data, (link, r)| {
+            if let Err(err) = r {
+                data.artist_detail.following.resolve(link, true);
+                data.error_alert(err);
+            } else {
+                data.info_alert(format!("Stopped following {}.", link.name));
+            }
+        },
+    )
 }
 
 fn async_artist_info() -> impl Widget<AppState> {
@@ -163,6 +277,7 @@ pub fn cover_widget(size: f64) -> impl Widget<Artist> {
     RemoteImage::new(utils::placeholder_widget(), move |artist: &Artist, _| {
         artist.image(size, size).map(|image| image.url.clone())
     })
+    .with_size((size, size))
     .fix_size(size, size)
     .clip(Circle::new((radius, radius), radius))
 }
@@ -174,6 +289,7 @@ fn artist_info_widget() -> impl Widget<WithCtx<ArtistInfo>> {
         utils::placeholder_widget(),
         move |artist: &ArtistInfo, _| Some(artist.main_image.clone()),
     )
+    .with_size((size, size))
     .fix_size(size, size)
     .clip(Size::new(size, size).to_rounded_rect(4.0))
     .lens(Ctx::data());
@@ -188,20 +304,17 @@ fn artist_info_widget() -> impl Widget<WithCtx<ArtistInfo>> {
 
     let artist_stats = Flex::column()
         .with_child(stat_row("Followers:", |info: &ArtistInfo| {
-            utils::format_number_with_commas(info.stats.followers)
+            format::number(info.stats.followers)
         }))
         .with_default_spacer()
         .with_child(stat_row("Monthly Listeners:", |info: &ArtistInfo| {
-            utils::format_number_with_commas(info.stats.monthly_listeners)
+            format::number(info.stats.monthly_listeners)
         }))
         .with_default_spacer()
         .with_child(Either::new(
             |ctx: &WithCtx<ArtistInfo>, _| ctx.data.stats.world_rank > 0,
             stat_row("Ranking:", |info: &ArtistInfo| {
-                format!(
-                    "#{} in the world",
-                    utils::format_number_with_commas(info.stats.world_rank)
-                )
+                format!("#{} in the world", format::number(info.stats.world_rank))
             }),
             Empty,
         ));
@@ -232,25 +345,58 @@ fn top_tracks_widget() -> impl Widget<WithCtx<ArtistTracks>> {
 fn albums_widget() -> impl Widget<WithCtx<ArtistAlbums>> {
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
-        .with_child(header_widget("Albums"))
-        .with_child(List::new(|| album::album_widget(false)).lens(Ctx::map(ArtistAlbums::albums)))
-        .with_child(header_widget("Singles"))
-        .with_child(List::new(|| album::album_widget(false)).lens(Ctx::map(ArtistAlbums::singles)))
-        .with_child(header_widget("Compilations"))
-        .with_child(
-            List::new(|| album::album_widget(false)).lens(Ctx::map(ArtistAlbums::compilations)),
-        )
-        .with_child(header_widget("Appears On"))
+        .with_child(releases_widget().lens(Ctx::map(ArtistAlbums::albums)))
+        .with_child(releases_widget().lens(Ctx::map(ArtistAlbums::singles)))
+        .with_child(releases_widget().lens(Ctx::map(ArtistAlbums::compilations)))
+        .with_child(releases_widget().lens(Ctx::map(ArtistAlbums::appears_on)))
+}
+
+fn releases_widget() -> impl Widget<WithCtx<ArtistReleases>> {
+    let show_all = Label::dynamic(|releases: &WithCtx<ArtistReleases>, _| {
+        if releases.data.loading {
+            "Loading…".to_string()
+        } else {
+            format!("Show all {}", releases.data.total)
+        }
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR)
+    .padding(theme::grid(1.0))
+    .link()
+    .rounded(theme::BUTTON_BORDER_RADIUS)
+    .on_left_click(|ctx, _, releases: &mut WithCtx<ArtistReleases>, _| {
+        if !releases.data.loading {
+            ctx.submit_command(SHOW_ALL_RELEASES.with(releases.data.group.clone()));
+        }
+    });
+
+    let releases = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(header_widget(
+            |releases: &WithCtx<ArtistReleases>, _: &Env| releases.data.title().to_string(),
+        ))
         .with_child(
-            List::new(|| album::album_widget(false)).lens(Ctx::map(ArtistAlbums::appears_on)),
+            List::new(|| Findable::new(album::album_widget(false)))
+                .lens(Ctx::map(ArtistReleases::albums)),
         )
+        .with_child(Either::new(
+            |releases: &WithCtx<ArtistReleases>, _| releases.data.is_complete(),
+            Empty,
+            show_all,
+        ));
+
+    Either::new(
+        |releases: &WithCtx<ArtistReleases>, _| releases.data.albums.is_empty(),
+        Empty,
+        releases,
+    )
 }
 
 fn related_widget() -> impl Widget<Cached<Vector<Artist>>> {
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_child(header_widget("Related Artists"))
-        .with_child(List::new(|| artist_widget(false)))
+        .with_child(List::new(|| Findable::new(artist_widget(false))))
         .lens(Cached::data)
 }
 
@@ -305,5 +451,15 @@ fn artist_menu(artist: &ArtistLink) -> Menu<AppState> {
         .command(cmd::COPY.with(artist.url())),
     );
 
+    menu =
+        menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-start-radio").with_placeholder("Start Radio"),
+            )
+            .command(cmd::START_RADIO.with(Arc::new(
+                RecommendationsRequest::artist_radio(artist.clone()),
+            ))),
+        );
+
     menu
 }
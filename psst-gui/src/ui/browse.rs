@@ -0,0 +1,238 @@
+use druid::{
+    im::Vector,
+    widget::{CrossAxisAlignment, Flex, Label, LineBreaking, List, Scroll},
+    Data, LensExt, Selector, Size, Widget, WidgetExt,
+};
+
+use crate::{
+    cmd,
+    data::{AppState, Browse, Category, CategoryLink, Ctx, Feed, Nav, Promise},
+    error::Error,
+    webapi::WebApi,
+    widget::{Async, Grid, LoadMore, MyWidgetExt, RemoteImage},
+};
+
+use super::{album, playlist, theme, utils};
+
+/// Load the page of a feed at the offset, the first one replaces the feed.
+pub const LOAD_FEATURED: Selector<usize> = Selector::new("app.browse.load-featured");
+pub const LOAD_NEW_RELEASES: Selector<usize> = Selector::new("app.browse.load-new-releases");
+pub const LOAD_CATEGORIES: Selector<usize> = Selector::new("app.browse.load-categories");
+pub const LOAD_CATEGORY: Selector<(CategoryLink, usize)> =
+    Selector::new("app.browse.load-category");
+
+pub fn browse_widget() -> impl Widget<AppState> {
+    let featured = Async::new(
+        utils::spinner_widget,
+        || {
+            Scroll::new(List::new(|| playlist::playlist_widget(true)).horizontal())
+                .horizontal()
+                .align_left()
+                .lens(Ctx::map(Feed::items))
+        },
+        utils::error_widget,
+    )
+    .lens(
+        Ctx::make(
+            AppState::common_ctx,
+            AppState::browse.then(Browse::featured),
+        )
+        .then(Ctx::in_promise()),
+    )
+    .on_command_async(
+        LOAD_FEATURED,
+        |offset| WebApi::global().get_featured_playlists(offset),
+        |_, data, offset| defer_page(&mut data.browse.featured, (), offset),
+        |_, data, (offset, result)| {
+            if let Some(err) = update_feed(&mut data.browse.featured, (), offset, result) {
+                data.error_alert(err);
+            }
+        },
+    );
+
+    let categories = Async::new(
+        utils::spinner_widget,
+        || {
+            Grid::new(category_widget)
+                .with_spacing(theme::grid(1.0))
+                .lens(Feed::items)
+        },
+        utils::error_widget,
+    )
+    .lens(AppState::browse.then(Browse::categories))
+    .on_command_async(
+        LOAD_CATEGORIES,
+        |offset| WebApi::global().get_categories(offset),
+        |_, data, offset| defer_page(&mut data.browse.categories, (), offset),
+        |_, data, (offset, result)| {
+            if let Some(err) = update_feed(&mut data.browse.categories, (), offset, result) {
+                data.error_alert(err);
+            }
+        },
+    );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(title_widget("Featured Playlists"))
+        .with_child(featured)
+        .with_child(title_widget("Genres & Moods"))
+        .with_child(categories)
+        .with_child(LoadMore::new(
+            |data: &AppState| data.browse.categories.resolved()?.next_offset(),
+            LOAD_CATEGORIES,
+        ))
+}
+
+pub fn new_releases_widget() -> impl Widget<AppState> {
+    let releases = Async::new(
+        utils::spinner_widget,
+        || {
+            Grid::new(|| album::album_widget(true))
+                .with_spacing(theme::grid(1.0))
+                .lens(Ctx::map(Feed::items))
+        },
+        utils::error_widget,
+    )
+    .lens(
+        Ctx::make(
+            AppState::common_ctx,
+            AppState::browse.then(Browse::new_releases),
+        )
+        .then(Ctx::in_promise()),
+    )
+    .on_command_async(
+        LOAD_NEW_RELEASES,
+        |offset| WebApi::global().get_new_releases(offset),
+        |_, data, offset| defer_page(&mut data.browse.new_releases, (), offset),
+        |_, data, (offset, result)| {
+            if let Some(err) = update_feed(&mut data.browse.new_releases, (), offset, result) {
+                data.error_alert(err);
+            }
+        },
+    );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(releases)
+        .with_child(LoadMore::new(
+            |data: &AppState| data.browse.new_releases.resolved()?.next_offset(),
+            LOAD_NEW_RELEASES,
+        ))
+}
+
+pub fn category_detail_widget() -> impl Widget<AppState> {
+    let playlists = Async::new(
+        utils::spinner_widget,
+        || {
+            Grid::new(|| playlist::playlist_widget(true))
+                .with_spacing(theme::grid(1.0))
+                .lens(Ctx::map(Feed::items))
+        },
+        utils::error_widget,
+    )
+    .lens(
+        Ctx::make(
+            AppState::common_ctx,
+            AppState::browse.then(Browse::category),
+        )
+        .then(Ctx::in_promise()),
+    )
+    .on_command_async(
+        LOAD_CATEGORY,
+        |(link, offset)| WebApi::global().get_category_playlists(&link.id, offset),
+        |_, data, (link, offset)| defer_page(&mut data.browse.category, link, offset),
+        |_, data, ((link, offset), result)| {
+            if let Some(err) = update_feed(&mut data.browse.category, link, offset, result) {
+                data.error_alert(err);
+            }
+        },
+    );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(playlists)
+        .with_child(LoadMore::new(
+            |data: &AppState| {
+                let link = data.browse.category.deferred()?;
+                let offset = data.browse.category.resolved()?.next_offset()?;
+                Some((link.clone(), offset))
+            },
+            LOAD_CATEGORY,
+        ))
+}
+
+fn category_widget() -> impl Widget<Category> {
+    let size = theme::grid(16.0);
+    let icon = RemoteImage::new(
+        utils::placeholder_widget(),
+        move |category: &Category, _| category.image(size, size).map(|image| image.url.clone()),
+    )
+    .with_size((size, size))
+    .fix_size(size, size)
+    .clip(Size::new(size, size).to_rounded_rect(4.0));
+
+    let name = Label::raw()
+        .with_font(theme::UI_FONT_MEDIUM)
+        .with_line_break_mode(LineBreaking::Clip)
+        .lens(Category::name)
+        .fix_width(size);
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(icon)
+        .with_default_spacer()
+        .with_child(name)
+        .padding(theme::grid(1.0))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, category: &mut Category, _| {
+            ctx.submit_command(cmd::NAVIGATE.with(Nav::BrowseCategory(category.link())));
+        })
+}
+
+fn title_widget(title: &str) -> impl Widget<AppState> {
+    Label::new(title).with_text_size(theme::grid(2.5)).padding((
+        theme::grid(1.0),
+        theme::grid(2.0),
+        0.0,
+        theme::grid(1.0),
+    ))
+}
+
+/// Start loading the page of `feed` at `offset`.
+fn defer_page<T: Data, D: Data>(feed: &mut Promise<Feed<T>, D>, def: D, offset: usize) {
+    if offset == 0 {
+        feed.defer(def);
+    } else if let Some(feed) = feed.resolved_mut() {
+        feed.loading = true;
+    }
+}
+
+/// Take in the page of `feed` loaded from `offset`, with the total count.
+/// The error of a page after the first one is returned, to be shown, as the
+/// loaded items stay.
+fn update_feed<T: Data, D: Data + PartialEq>(
+    feed: &mut Promise<Feed<T>, D>,
+    def: D,
+    offset: usize,
+    result: Result<(Vector<T>, usize), Error>,
+) -> Option<Error> {
+    if offset == 0 {
+        feed.update((def, result.map(Feed::new)));
+        return None;
+    }
+    if !feed.contains(&def) {
+        return None;
+    }
+    let loaded = feed.resolved_mut()?;
+    match result {
+        Ok(page) => {
+            loaded.append(page);
+            None
+        }
+        Err(err) => {
+            loaded.loading = false;
+            Some(err)
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use std::sync::Arc;
 
-use crate::widget::Empty;
+use crate::widget::{Async, Empty};
 use crate::{
     cmd,
-    data::{AppState, ArtistLink, Nav},
+    data::{AppState, ArtistLink, AudioFeatures, Nav},
+    error::Error,
     ui::theme,
     ui::utils,
 };
@@ -51,43 +52,97 @@ pub struct CreditArtist {
 
 pub fn credits_widget() -> impl Widget<AppState> {
     Scroll::new(
-        Maybe::new(
-            || {
-                Flex::column()
-                    .cross_axis_alignment(CrossAxisAlignment::Start)
-                    .with_child(
-                        Label::new(|data: &TrackCredits, _: &_| data.track_title.clone())
-                            .with_font(theme::UI_FONT_MEDIUM)
-                            .with_text_size(theme::TEXT_SIZE_LARGE)
-                            .padding(theme::grid(2.0))
-                            .expand_width(),
-                    )
-                    .with_child(Either::new(
-                        |data: &TrackCredits, _| data.role_credits.is_empty(),
-                        Empty,
-                        List::new(role_credit_widget).lens(TrackCredits::role_credits),
-                    ))
-                    .with_child(Either::new(
-                        |data: &TrackCredits, _| data.source_names.is_empty(),
-                        Empty,
-                        Label::new(|data: &TrackCredits, _: &_| {
-                            format!("Source: {}", data.source_names.join(", "))
-                        })
-                        .with_text_size(theme::TEXT_SIZE_SMALL)
-                        .with_text_color(theme::PLACEHOLDER_COLOR)
-                        .padding(theme::grid(2.0)),
-                    ))
-                    .padding(theme::grid(2.0))
-            },
-            utils::spinner_widget,
-        )
-        .lens(AppState::credits)
-        .controller(CreditsController),
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(track_credits_widget())
+            .with_child(
+                Async::new(
+                    utils::spinner_widget,
+                    audio_features_widget,
+                    features_error_widget,
+                )
+                .lens(AppState::audio_features),
+            ),
     )
     .vertical()
     .expand()
 }
 
+fn track_credits_widget() -> impl Widget<AppState> {
+    Maybe::new(
+        || {
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    Label::new(|data: &TrackCredits, _: &_| data.track_title.clone())
+                        .with_font(theme::UI_FONT_MEDIUM)
+                        .with_text_size(theme::TEXT_SIZE_LARGE)
+                        .padding(theme::grid(2.0))
+                        .expand_width(),
+                )
+                .with_child(Either::new(
+                    |data: &TrackCredits, _| data.role_credits.is_empty(),
+                    Empty,
+                    List::new(role_credit_widget).lens(TrackCredits::role_credits),
+                ))
+                .with_child(Either::new(
+                    |data: &TrackCredits, _| data.source_names.is_empty(),
+                    Empty,
+                    Label::new(|data: &TrackCredits, _: &_| {
+                        format!("Source: {}", data.source_names.join(", "))
+                    })
+                    .with_text_size(theme::TEXT_SIZE_SMALL)
+                    .with_text_color(theme::PLACEHOLDER_COLOR)
+                    .padding(theme::grid(2.0)),
+                ))
+                .padding(theme::grid(2.0))
+        },
+        utils::spinner_widget,
+    )
+    .lens(AppState::credits)
+    .controller(CreditsController)
+}
+
+fn audio_features_widget() -> impl Widget<AudioFeatures> {
+    let feature = |name: &str, value: fn(&AudioFeatures) -> String| {
+        Flex::row()
+            .with_child(
+                Label::new(name)
+                    .with_text_color(theme::PLACEHOLDER_COLOR)
+                    .fix_width(theme::grid(14.0)),
+            )
+            .with_child(Label::dynamic(move |features: &AudioFeatures, _| {
+                value(features)
+            }))
+            .padding((0.0, theme::grid(0.5)))
+    };
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Label::new("Audio Features")
+                .with_text_size(theme::TEXT_SIZE_NORMAL)
+                .padding((0.0, 0.0, 0.0, theme::grid(1.0))),
+        )
+        .with_child(feature("Tempo", |f| format!("{:.0} BPM", f.tempo)))
+        .with_child(feature("Key", |f| {
+            f.key_name().unwrap_or_else(|| "Unknown".to_string())
+        }))
+        .with_child(feature("Energy", |f| format!("{:.0}%", f.energy * 100.0)))
+        .with_child(feature("Danceability", |f| {
+            format!("{:.0}%", f.danceability * 100.0)
+        }))
+        .with_child(feature("Loudness", |f| format!("{:.1} dB", f.loudness)))
+        .padding(theme::grid(4.0))
+}
+
+fn features_error_widget() -> impl Widget<Error> {
+    Label::new("Audio features are not available for this track.")
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .padding(theme::grid(4.0))
+}
+
 fn role_credit_widget() -> impl Widget<RoleCredit> {
     Either::new(
         |role: &RoleCredit, _| role.artists.is_empty(),
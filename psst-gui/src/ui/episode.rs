@@ -12,6 +12,7 @@ use crate::{
 };
 
 use super::{
+    library,
     playable::{self, PlayRow},
     theme, utils,
 };
@@ -40,7 +41,7 @@ pub fn playable_widget() -> impl Widget<PlayRow<Arc<Episode>>> {
 
     let is_playing = playable::is_playing_marker_widget().lens(PlayRow::is_playing);
 
-    let duration = Label::<Arc<Episode>>::dynamic(|episode, _| utils::as_human(episode.duration))
+    let duration = Label::<Arc<Episode>>::dynamic(|episode, _| episode_status(episode))
         .with_text_size(theme::TEXT_SIZE_SMALL)
         .with_text_color(theme::PLACEHOLDER_COLOR)
         .lens(PlayRow::item);
@@ -76,11 +77,26 @@ pub fn playable_widget() -> impl Widget<PlayRow<Arc<Episode>>> {
         .context_menu(episode_row_menu)
 }
 
+/// Duration of the episode, or how much of it is left once started.
+fn episode_status(episode: &Episode) -> String {
+    match &episode.resume_point {
+        Some(point) if point.fully_played => "Played".to_string(),
+        _ => match episode.resume_position() {
+            Some(position) => format!(
+                "{} left",
+                utils::as_human(episode.duration.saturating_sub(position))
+            ),
+            None => utils::as_human(episode.duration),
+        },
+    }
+}
+
 fn cover_widget(size: f64) -> impl Widget<Arc<Episode>> {
     RemoteImage::new(
         utils::placeholder_widget(),
         move |episode: &Arc<Episode>, _| episode.image(size, size).map(|image| image.url.clone()),
     )
+    .with_size((size, size))
     .fix_size(size, size)
 }
 
@@ -93,7 +109,7 @@ fn episode_row_menu(row: &PlayRow<Arc<Episode>>) -> Menu<AppState> {
     episode_menu(&row.item, &row.ctx.library)
 }
 
-pub fn episode_menu(episode: &Episode, _library: &Arc<Library>) -> Menu<AppState> {
+pub fn episode_menu(episode: &Episode, library: &Arc<Library>) -> Menu<AppState> {
     let mut menu = Menu::empty();
 
     menu = menu.entry(
@@ -108,5 +124,25 @@ pub fn episode_menu(episode: &Episode, _library: &Arc<Library>) -> Menu<AppState
         .command(cmd::COPY.with(episode.url())),
     );
 
+    menu = menu.separator();
+
+    if library.contains_episode(episode) {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-remove-from-library")
+                    .with_placeholder("Remove from Your Episodes"),
+            )
+            .command(library::UNSAVE_EPISODE.with(episode.id)),
+        );
+    } else {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-save-to-library")
+                    .with_placeholder("Save to Your Episodes"),
+            )
+            .command(library::SAVE_EPISODE.with(Arc::new(episode.clone()))),
+        );
+    }
+
     menu
 }
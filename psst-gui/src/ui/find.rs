@@ -15,6 +15,7 @@ use crate::{
 pub struct Find {
     sender: WidgetId,
     query: FindQuery,
+    filter: bool,
 }
 
 #[derive(Clone)]
@@ -26,26 +27,35 @@ const FIND: Selector = Selector::new("find");
 const REPORT_MATCH: Selector<Report> = Selector::new("report-match");
 const FOCUS_MATCH: Selector = Selector::new("focus-match");
 
+/// Item of a list that the finder of the page highlights when it matches the
+/// query, or hides when it does not and the finder filters.
 pub struct Findable<W> {
     inner: W,
-    selector: Selector<Find>,
     is_matching: bool,
+    is_focused: bool,
+    is_hidden: bool,
 }
 
 impl<W> Findable<W> {
-    pub fn new(inner: W, selector: Selector<Find>) -> Self {
+    pub fn new(inner: W) -> Self {
         Self {
             inner,
-            selector,
             is_matching: false,
+            is_focused: false,
+            is_hidden: false,
         }
     }
 
-    fn set_state(&mut self, ctx: &mut EventCtx, matches: bool) {
-        if self.is_matching != matches {
+    fn set_state(&mut self, ctx: &mut EventCtx, matches: bool, hidden: bool) {
+        if self.is_matching != matches || self.is_focused {
             self.is_matching = matches;
+            self.is_focused = false;
             ctx.request_paint();
         }
+        if self.is_hidden != hidden {
+            self.is_hidden = hidden;
+            ctx.request_layout();
+        }
     }
 }
 
@@ -56,16 +66,14 @@ where
 {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
         match event {
-            Event::Command(cmd) if cmd.is(self.selector) => {
-                let Find { sender, query } = cmd.get_unchecked(self.selector);
-                self.set_state(
-                    ctx,
-                    if query.is_empty() {
-                        false
-                    } else {
-                        data.matches_query(query)
-                    },
-                );
+            Event::Command(cmd) if cmd.is(cmd::FIND_IN_PAGE) => {
+                let Find {
+                    sender,
+                    query,
+                    filter,
+                } = cmd.get_unchecked(cmd::FIND_IN_PAGE);
+                let matches = !query.is_empty() && data.matches_query(query);
+                self.set_state(ctx, matches, *filter && !query.is_empty() && !matches);
                 if self.is_matching {
                     let report = Report {
                         sender: ctx.widget_id(),
@@ -74,8 +82,14 @@ where
                 }
             }
             Event::Command(cmd) if cmd.is(FOCUS_MATCH) => {
+                self.is_focused = true;
+                ctx.request_paint();
                 ctx.scroll_to_view();
             }
+            _ if self.is_hidden && !matches!(event, Event::Command(_)) => {
+                // Hidden items take no pointer or keyboard input.
+                return;
+            }
             _ => {}
         }
         self.inner.event(ctx, event, data, env);
@@ -90,10 +104,20 @@ where
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
-        self.inner.layout(ctx, bc, data, env)
+        let size = self.inner.layout(ctx, bc, data, env);
+        if self.is_hidden {
+            // Collapse the item, the inner widget is still laid out so that
+            // it is ready when the filter is cleared.
+            bc.min()
+        } else {
+            size
+        }
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        if self.is_hidden {
+            return;
+        }
         if self.is_matching {
             let bounds = ctx
                 .size()
@@ -101,12 +125,15 @@ where
                 .inset(-2.0)
                 .to_rounded_rect(env.get(theme::BUTTON_BORDER_RADIUS));
             ctx.fill(bounds, &env.get(theme::GREY_500));
+            if self.is_focused {
+                ctx.stroke(bounds, &env.get(theme::BLUE_100), 1.0);
+            }
         }
         self.inner.paint(ctx, data, env);
     }
 }
 
-pub fn finder_widget(selector: Selector<Find>, label: &'static str) -> impl Widget<Finder> {
+pub fn finder_widget(label: &'static str) -> impl Widget<Finder> {
     let input_id = WidgetId::next();
 
     let input = TextBox::new()
@@ -139,6 +166,14 @@ pub fn finder_widget(selector: Selector<Find>, label: &'static str) -> impl Widg
         .rounded(theme::BUTTON_BORDER_RADIUS)
         .on_left_click(|_, _, data: &mut Finder, _| data.focus_next());
 
+    let filter = Label::new("Filter")
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .padding(theme::grid(0.5))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .active(|data: &Finder, _| data.filter)
+        .on_left_click(|_, _, data: &mut Finder, _| data.filter = !data.filter);
+
     let results_with_controls = Either::new(
         |data, _| data.results > 0,
         Flex::row()
@@ -154,15 +189,15 @@ pub fn finder_widget(selector: Selector<Find>, label: &'static str) -> impl Widg
         .with_flex_child(input, 1.0)
         .with_default_spacer()
         .with_child(results_with_controls)
+        .with_default_spacer()
+        .with_child(filter)
         .padding(theme::grid(1.0))
         .background(theme::GREY_600);
 
-    Either::new(|data, _| data.show, finder, Empty)
-        .controller(FinderController { selector, input_id })
+    Either::new(|data, _| data.show, finder, Empty).controller(FinderController { input_id })
 }
 
 struct FinderController {
-    selector: Selector<Find>,
     input_id: WidgetId,
 }
 
@@ -181,9 +216,10 @@ where
         match event {
             Event::Command(cmd) if cmd.is(FIND) => {
                 data.reset_matches();
-                ctx.submit_command(self.selector.with(Find {
+                ctx.submit_command(cmd::FIND_IN_PAGE.with(Find {
                     sender: ctx.widget_id(),
                     query: FindQuery::new(&data.query),
+                    filter: data.filter,
                 }));
                 ctx.set_handled();
             }
@@ -202,8 +238,17 @@ where
                 ctx.set_handled();
             }
             Event::KeyDown(k_e) if k_e.key == KbKey::Escape => {
+                data.reset();
                 data.show = false;
             }
+            Event::KeyDown(k_e) if data.show && k_e.key == KbKey::Enter => {
+                if k_e.mods.shift() {
+                    data.focus_previous();
+                } else {
+                    data.focus_next();
+                }
+                ctx.set_handled();
+            }
             _ => {}
         }
         child.event(ctx, event, data, env);
@@ -217,7 +262,9 @@ where
         data: &Finder,
         env: &Env,
     ) {
-        if !old_data.query.same(&data.query) || !old_data.focused_result.same(&data.focused_result)
+        if !old_data.query.same(&data.query)
+            || !old_data.focused_result.same(&data.focused_result)
+            || !old_data.filter.same(&data.filter)
         {
             ctx.submit_command(FIND.to(ctx.widget_id()));
         }
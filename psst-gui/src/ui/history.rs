@@ -0,0 +1,83 @@
+use druid::{
+    widget::{CrossAxisAlignment, Either, Flex, Label, LabelText, LineBreaking, List},
+    Data, Env, Insets, LensExt, Selector, Widget, WidgetExt,
+};
+
+use crate::{
+    data::{AppState, Ctx, GeneratedPlaylist, ListeningHistory, WithCtx},
+    widget::{Async, MyWidgetExt},
+};
+
+use super::{playable, theme, track, utils};
+
+/// Load the recent plays, with the number of plays recorded so far.
+pub const LOAD_HISTORY: Selector<usize> = Selector::new("app.history.load");
+
+pub fn history_widget() -> impl Widget<AppState> {
+    Async::new(utils::spinner_widget, plays_widget, utils::error_widget)
+        .lens(Ctx::make(AppState::common_ctx, AppState::listening_history).then(Ctx::in_promise()))
+        .on_command_async(
+            LOAD_HISTORY,
+            |_| ListeningHistory::load(),
+            |_, data, count| data.listening_history.defer(count),
+            |_, data, r| data.listening_history.update(r),
+        )
+}
+
+fn plays_widget() -> impl Widget<WithCtx<ListeningHistory>> {
+    Either::new(
+        |history: &WithCtx<ListeningHistory>, _| history.data.plays.is_empty(),
+        Label::new(
+            "Tracks you play are recorded on this computer and show up here. The history can \
+             be turned off and cleared in the preferences.",
+        )
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .with_line_break_mode(LineBreaking::WordWrap)
+        .padding(theme::grid(1.0)),
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(
+                List::new(generated_playlist_widget).lens(Ctx::map(ListeningHistory::generated)),
+            )
+            .with_child(header_widget("Recent Plays"))
+            .with_child(playable::list_widget(display())),
+    )
+}
+
+/// Playlist generated from the history, see `PlaylistGenerator`.  It is only
+/// saved to the Spotify account if that is turned on in the preferences.
+fn generated_playlist_widget() -> impl Widget<WithCtx<GeneratedPlaylist>> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(header_widget(
+            |playlist: &WithCtx<GeneratedPlaylist>, _: &Env| {
+                playlist.data.generator.name().to_string()
+            },
+        ))
+        .with_child(playable::list_widget(display()))
+}
+
+fn header_widget<T: Data>(text: impl Into<LabelText<T>>) -> impl Widget<T> {
+    Label::new(text)
+        .with_font(theme::UI_FONT_MEDIUM)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .padding(Insets::new(
+            theme::grid(1.0),
+            theme::grid(2.0),
+            0.0,
+            theme::grid(1.0),
+        ))
+}
+
+fn display() -> playable::Display {
+    playable::Display {
+        track: track::Display {
+            title: true,
+            artist: true,
+            album: true,
+            cover: true,
+            ..track::Display::empty()
+        },
+    }
+}
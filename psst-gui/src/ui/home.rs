@@ -1,10 +1,16 @@
 use std::sync::Arc;
 
 use druid::im::Vector;
-use druid::widget::{Either, Flex, Label, Scroll};
-use druid::{widget::List, LensExt, Selector, Widget, WidgetExt};
+use druid::widget::{CrossAxisAlignment, Either, Flex, Label, LineBreaking, Scroll};
+use druid::{lens::Map, widget::List, LensExt, Selector, Widget, WidgetExt};
+use psst_core::lastfm::LastFmApi;
 
-use crate::data::{Artist, Ctx, HomeDetail, MixedView, Show, Shows, Track, WithCtx};
+use crate::cmd;
+use crate::data::{
+    Artist, Ctx, FriendTrack, HomeDetail, LastFmUser, MadeForYou, MixKind, MixedView, Nav, Show,
+    Shows, TimeRange, Track, WithCtx,
+};
+use crate::error::Error;
 use crate::ui::library::{LOAD_SHOWS, SAVE_SHOW, UNSAVE_SHOW};
 use crate::widget::Empty;
 use crate::{
@@ -16,14 +22,18 @@ use crate::{
 use super::{album, artist, playable, show, theme, track};
 use super::{
     playlist,
-    utils::{error_widget, spinner_widget},
+    utils::{self, error_widget, spinner_widget},
 };
 
 pub const LOAD_MADE_FOR_YOU: Selector = Selector::new("app.home.load-made-for-your");
+/// Load only the Made For You section again, once it is stale.
+pub const REFRESH_MADE_FOR_YOU: Selector = Selector::new("app.home.refresh-made-for-you");
+pub const LOAD_LASTFM_FRIENDS: Selector<LastFmUser> = Selector::new("app.home.load-lastfm-friends");
 
 pub fn home_widget() -> impl Widget<AppState> {
     Flex::column()
         .with_child(made_for_you())
+        .with_child(lastfm_friends())
         .with_child(jump_back_in())
         .with_child(user_top_mixes())
         .with_child(recommended_stations())
@@ -47,7 +57,7 @@ fn simple_title_label(title: &str) -> impl Widget<AppState> {
 }
 
 fn made_for_you() -> impl Widget<AppState> {
-    Async::new(spinner_widget, loaded_results_widget, || Empty)
+    Async::new(spinner_widget, made_for_you_widget, || Empty)
         .lens(
             Ctx::make(
                 AppState::common_ctx,
@@ -61,6 +71,57 @@ fn made_for_you() -> impl Widget<AppState> {
             |_, data, q| data.home_detail.made_for_you.defer(q),
             |_, data, r| data.home_detail.made_for_you.update(r),
         )
+        .on_command_async(
+            REFRESH_MADE_FOR_YOU,
+            |_| WebApi::global().get_made_for_you(),
+            |_, data, q| data.home_detail.made_for_you.defer(q),
+            |_, data, r| data.home_detail.made_for_you.update(r),
+        )
+}
+
+fn made_for_you_widget() -> impl Widget<WithCtx<MadeForYou>> {
+    let mut rows = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    for kind in MixKind::ALL {
+        rows.add_child(mix_row_widget(kind));
+    }
+    Either::new(
+        |made_for_you: &WithCtx<MadeForYou>, _| made_for_you.data.is_empty(),
+        Empty,
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_default_spacer()
+            .with_child(
+                Label::new("Made For You")
+                    .with_text_size(theme::grid(2.5))
+                    .padding((theme::grid(1.5), theme::grid(0.5))),
+            )
+            .with_child(rows),
+    )
+}
+
+fn mix_row_widget(kind: MixKind) -> impl Widget<WithCtx<MadeForYou>> {
+    Either::new(
+        move |made_for_you: &WithCtx<MadeForYou>, _| made_for_you.data.row(kind).is_empty(),
+        Empty,
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(
+                Label::new(kind.title())
+                    .with_font(theme::UI_FONT_MEDIUM)
+                    .padding((theme::grid(1.5), theme::grid(1.0), 0.0, 0.0)),
+            )
+            .with_child(
+                Scroll::new(List::new(|| playlist::playlist_widget(true)).horizontal())
+                    .horizontal()
+                    .align_left()
+                    .lens(Ctx::map(Map::new(
+                        move |made_for_you: &MadeForYou| made_for_you.row(kind).clone(),
+                        |_, _| {
+                            // Ignore mutation.
+                        },
+                    ))),
+            ),
+    )
 }
 
 fn recommended_stations() -> impl Widget<AppState> {
@@ -174,6 +235,10 @@ pub fn your_shows() -> impl Widget<AppState> {
                 });
             },
             |_, data, r| {
+                if let Ok(saved) = &r.1 {
+                    // Count the new episodes of the followed shows from now on.
+                    data.mark_episodes_seen(saved.shows.iter().map(|show| &**show), true);
+                }
                 data.home_detail.your_shows.update((
                     (),
                     r.1.clone().map(|saved_shows| MixedView {
@@ -193,6 +258,7 @@ pub fn your_shows() -> impl Widget<AppState> {
             SAVE_SHOW,
             |a| WebApi::global().save_show(&a.id),
             |_, data, s| {
+                data.mark_episodes_seen([&*s], true);
                 data.with_library_mut(move |library| {
                     library.add_show(s);
                 });
@@ -354,12 +420,80 @@ fn user_top_artists_widget() -> impl Widget<AppState> {
     .lens(AppState::home_detail.then(HomeDetail::user_top_artists))
     .on_command_async(
         LOAD_MADE_FOR_YOU,
-        |_| WebApi::global().get_user_top_artist(),
+        |_| WebApi::global().get_user_top_artist(TimeRange::MediumTerm),
         |_, data, d| data.home_detail.user_top_artists.defer(d),
         |_, data, r| data.home_detail.user_top_artists.update(r),
     )
 }
 
+fn lastfm_friends() -> impl Widget<AppState> {
+    Either::new(
+        |data: &AppState, _| data.config.lastfm_show_friends,
+        Flex::column()
+            .with_child(simple_title_label("Friends on Last.fm"))
+            .with_child(lastfm_friends_widget()),
+        Empty,
+    )
+}
+
+fn lastfm_friends_widget() -> impl Widget<AppState> {
+    Async::new(
+        spinner_widget,
+        || {
+            Either::new(
+                |tracks: &Vector<FriendTrack>, _| tracks.is_empty(),
+                Label::new("None of your friends listened to anything lately.")
+                    .with_text_size(theme::TEXT_SIZE_SMALL)
+                    .with_text_color(theme::PLACEHOLDER_COLOR)
+                    .padding((theme::grid(1.5), theme::grid(0.5))),
+                List::new(friend_track_widget),
+            )
+        },
+        error_widget,
+    )
+    .lens(AppState::home_detail.then(HomeDetail::lastfm_friends))
+    .on_command_async(
+        LOAD_LASTFM_FRIENDS,
+        |user| {
+            LastFmApi::global()
+                .friends_tracks(&user.api_key, &user.username)
+                .map(|tracks| tracks.into_iter().map(FriendTrack::from).collect())
+                .map_err(|err| Error::WebApiError(err.to_string()))
+        },
+        |_, data, user| data.home_detail.lastfm_friends.defer(user),
+        |_, data, r| data.home_detail.lastfm_friends.update(r),
+    )
+}
+
+fn friend_track_widget() -> impl Widget<FriendTrack> {
+    let title =
+        Label::dynamic(|track: &FriendTrack, _| format!("{} — {}", track.title, track.artist))
+            .with_font(theme::UI_FONT_MEDIUM)
+            .with_line_break_mode(LineBreaking::Clip);
+
+    let detail = Label::dynamic(|track: &FriendTrack, _| match track.played_ago() {
+        Some(ago) => format!("{} · {} ago", track.friend, utils::as_human(ago)),
+        None if track.now_playing => format!("{} · now playing", track.friend),
+        None => track.friend.to_string(),
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR);
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(title)
+        .with_spacer(2.0)
+        .with_child(detail)
+        .padding(theme::grid(1.0))
+        .expand_width()
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, track, _| {
+            ctx.submit_command(cmd::NAVIGATE.with(Nav::SearchResults(track.search_query())));
+        })
+        .padding((theme::grid(0.5), 0.0))
+}
+
 fn top_tracks_widget() -> impl Widget<WithCtx<Vector<Arc<Track>>>> {
     playable::list_widget(playable::Display {
         track: track::Display {
@@ -383,7 +517,7 @@ fn user_top_tracks_widget() -> impl Widget<AppState> {
         )
         .on_command_async(
             LOAD_MADE_FOR_YOU,
-            |_| WebApi::global().get_user_top_tracks(),
+            |_| WebApi::global().get_user_top_tracks(TimeRange::MediumTerm),
             |_, data, d| data.home_detail.user_top_tracks.defer(d),
             |_, data, r| data.home_detail.user_top_tracks.update(r),
         )
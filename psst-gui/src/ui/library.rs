@@ -1,24 +1,29 @@
 use std::sync::Arc;
 
 use druid::{
-    widget::{Flex, List},
+    im::Vector,
+    widget::{Button, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, ViewSwitcher},
     LensExt, Selector, Widget, WidgetExt,
 };
 
 use crate::{
-    cmd,
     data::{
-        Album, AlbumLink, AppState, Ctx, Library, SavedAlbums, SavedTracks, Show, ShowLink, Track,
-        TrackId,
+        Album, AlbumDuplicates, AlbumLink, AppState, Ctx, Episode, EpisodeId, Library, SavedAlbums,
+        SavedEpisodes, SavedTracks, Show, ShowLink, Track, TrackId, WithCtx, PAGE_SIZE,
     },
     ui::home::{shows_that_you_might_like, your_shows},
     webapi::WebApi,
-    widget::{Async, MyWidgetExt},
+    widget::{Async, Empty, MyWidgetExt, Paged},
 };
 
-use super::{album, playable, track, utils};
+use super::{album, find::Findable, playable, theme, track, utils};
 
-pub const LOAD_TRACKS: Selector = Selector::new("app.library.load-tracks");
+/// Load the saved tracks, all of them with `true`, otherwise the first page,
+/// with the others loaded with `LOAD_TRACKS_PAGE` as they are scrolled to.
+pub const LOAD_TRACKS: Selector<bool> = Selector::new("app.library.load-tracks");
+/// Load the page of saved tracks at the offset, with the limit.
+pub const LOAD_TRACKS_PAGE: Selector<(usize, usize)> =
+    Selector::new("app.library.load-tracks-page");
 pub const LOAD_ALBUMS: Selector = Selector::new("app.library.load-albums");
 pub const LOAD_SHOWS: Selector = Selector::new("app.library.load-shows");
 
@@ -28,25 +33,32 @@ pub const UNSAVE_TRACK: Selector<TrackId> = Selector::new("app.library.unsave-tr
 pub const SAVE_ALBUM: Selector<Arc<Album>> = Selector::new("app.library.save-album");
 pub const UNSAVE_ALBUM: Selector<AlbumLink> = Selector::new("app.library.unsave-album");
 
+pub const FIND_DUPLICATE_ALBUMS: Selector = Selector::new("app.library.find-duplicate-albums");
+pub const DISMISS_DUPLICATE_ALBUMS: Selector =
+    Selector::new("app.library.dismiss-duplicate-albums");
+pub const MERGE_DUPLICATE_ALBUMS: Selector<Vector<Arc<str>>> =
+    Selector::new("app.library.merge-duplicate-albums");
+
 pub const SAVE_SHOW: Selector<Arc<Show>> = Selector::new("app.library.save-show");
 pub const UNSAVE_SHOW: Selector<ShowLink> = Selector::new("app.library.unsave-show");
 
+pub const LOAD_EPISODES: Selector = Selector::new("app.library.load-episodes");
+pub const SAVE_EPISODE: Selector<Arc<Episode>> = Selector::new("app.library.save-episode");
+pub const UNSAVE_EPISODE: Selector<EpisodeId> = Selector::new("app.library.unsave-episode");
+
 pub fn saved_tracks_widget() -> impl Widget<AppState> {
-    Async::new(
+    let tracks = Async::new(
         utils::spinner_widget,
         || {
-            playable::list_widget_with_find(
-                playable::Display {
-                    track: track::Display {
-                        title: true,
-                        artist: true,
-                        album: true,
-                        cover: true,
-                        ..track::Display::empty()
-                    },
+            playable::list_widget(playable::Display {
+                track: track::Display {
+                    title: true,
+                    artist: true,
+                    album: true,
+                    table: true,
+                    ..track::Display::empty()
                 },
-                cmd::FIND_IN_SAVED_TRACKS,
-            )
+            })
         },
         utils::error_widget,
     )
@@ -56,21 +68,75 @@ pub fn saved_tracks_widget() -> impl Widget<AppState> {
             AppState::library.then(Library::saved_tracks.in_arc()),
         )
         .then(Ctx::in_promise()),
+    );
+
+    let tracks = Paged::new(
+        tracks,
+        |data: &AppState| {
+            data.library
+                .saved_tracks
+                .resolved()
+                .map(|saved| saved.window)
+        },
+        LOAD_TRACKS_PAGE,
     )
     .on_command_async(
         LOAD_TRACKS,
-        |_| WebApi::global().get_saved_tracks().map(SavedTracks::new),
+        |all| {
+            if all {
+                WebApi::global().get_saved_tracks().map(SavedTracks::new)
+            } else {
+                WebApi::global()
+                    .get_saved_tracks_page(0, PAGE_SIZE)
+                    .map(|(tracks, total)| SavedTracks::first_page(tracks, total))
+            }
+        },
         |_, data, _| {
             data.with_library_mut(|library| {
                 library.saved_tracks.defer_default();
             });
         },
-        |_, data, r| {
+        |_, data, (def, tracks)| {
+            let sort = data.config.saved_tracks_sort;
             data.with_library_mut(|library| {
-                library.saved_tracks.update(r);
+                let tracks = tracks.map(|mut tracks| {
+                    // Know all tracks of the last sync as saved, not only the
+                    // first page.
+                    tracks.set.extend(WebApi::global().synced_saved_track_ids());
+                    tracks.sorted(sort)
+                });
+                library.saved_tracks.update((def, tracks));
             });
         },
     )
+    .on_command_async(
+        LOAD_TRACKS_PAGE,
+        |(offset, limit)| WebApi::global().get_saved_tracks_page(offset, limit),
+        |_, data, _| {
+            data.with_library_mut(|library| {
+                if let Some(saved) = library.saved_tracks.resolved_mut() {
+                    saved.window.loading = true;
+                }
+            });
+        },
+        |_, data, ((offset, _), result)| match result {
+            Ok((tracks, total)) => {
+                data.with_library_mut(|library| {
+                    if let Some(saved) = library.saved_tracks.resolved_mut() {
+                        saved.insert_page(offset, tracks, total);
+                    }
+                });
+            }
+            Err(err) => {
+                data.with_library_mut(|library| {
+                    if let Some(saved) = library.saved_tracks.resolved_mut() {
+                        saved.window.loading = false;
+                    }
+                });
+                data.error_alert(err);
+            }
+        },
+    )
     .on_command_async(
         SAVE_TRACK,
         |t| WebApi::global().save_track(&t.id.0.to_base62()),
@@ -102,13 +168,169 @@ pub fn saved_tracks_widget() -> impl Widget<AppState> {
                 data.info_alert("Track removed from library.")
             }
         },
-    )
+    );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(track::table_header_widget(|config| {
+            config.saved_tracks_sort
+        }))
+        .with_child(tracks)
 }
 
 pub fn saved_albums_widget() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(duplicate_albums_widget())
+        .with_child(saved_albums_list_widget())
+        .on_command(FIND_DUPLICATE_ALBUMS, |_, _, data| {
+            data.with_library_mut(|library| {
+                if let Some(saved) = library.saved_albums.resolved_mut() {
+                    saved.duplicates = Some(saved.find_duplicates());
+                }
+            });
+        })
+        .on_command(DISMISS_DUPLICATE_ALBUMS, |_, _, data| {
+            data.with_library_mut(|library| {
+                if let Some(saved) = library.saved_albums.resolved_mut() {
+                    saved.duplicates = None;
+                }
+            });
+        })
+        .on_command_async(
+            MERGE_DUPLICATE_ALBUMS,
+            |ids| {
+                let ids: Vec<Arc<str>> = ids.into_iter().collect();
+                WebApi::global().unsave_albums(&ids)
+            },
+            |_, _, _| {},
+            |ctx, data, (ids, r)| {
+                if let Err(err) = r {
+                    // Some albums might have been removed before the failure, reload
+                    // the library to see what is left.
+                    data.error_alert(err);
+                    ctx.submit_command(LOAD_ALBUMS);
+                    return;
+                }
+                data.with_library_mut(|library| {
+                    for id in &ids {
+                        library.remove_album(id);
+                    }
+                    if let Some(saved) = library.saved_albums.resolved_mut() {
+                        saved.duplicates = None;
+                    }
+                });
+                data.info_alert(format!("Removed {} duplicate albums.", ids.len()));
+            },
+        )
+}
+
+fn duplicate_albums_widget() -> impl Widget<AppState> {
+    ViewSwitcher::new(
+        |data: &AppState, _| {
+            data.library
+                .saved_albums
+                .resolved()
+                .map(|saved| saved.duplicates.clone())
+        },
+        |duplicates, _, _| match duplicates {
+            // Library not loaded yet.
+            None => Empty.boxed(),
+            Some(None) => Button::new("Find Duplicates")
+                .on_click(|ctx, _, _| ctx.submit_command(FIND_DUPLICATE_ALBUMS))
+                .padding(theme::grid(1.0))
+                .boxed(),
+            Some(Some(groups)) => duplicate_albums_preview(groups).boxed(),
+        },
+    )
+}
+
+fn duplicate_albums_preview(groups: &Vector<AlbumDuplicates>) -> impl Widget<AppState> {
+    let mut col = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+
+    if groups.is_empty() {
+        return col
+            .with_child(Label::new("No duplicate albums found."))
+            .with_spacer(theme::grid(1.0))
+            .with_child(
+                Button::new("Done")
+                    .on_click(|ctx, _, _| ctx.submit_command(DISMISS_DUPLICATE_ALBUMS)),
+            )
+            .padding(theme::grid(1.0));
+    }
+
+    col = col
+        .with_child(Label::new("Duplicate Albums").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(0.5))
+        .with_child(
+            Label::new(
+                "Nothing has been changed yet. The following albums would be removed from \
+                 your library, keeping the variant with the most tracks.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.0));
+
+    for group in groups {
+        let keep = &group.keep;
+        col = col.with_child(Label::new(format!(
+            "Keep: {} — {} ({} tracks, {})",
+            keep.name,
+            artist_names(keep),
+            keep.tracks.len(),
+            keep.id
+        )));
+        for album in &group.remove {
+            col = col.with_child(
+                Label::new(format!(
+                    "Remove: {} ({} tracks, {})",
+                    album.name,
+                    album.tracks.len(),
+                    album.id
+                ))
+                .with_text_color(theme::PLACEHOLDER_COLOR),
+            );
+        }
+        col = col.with_spacer(theme::grid(1.0));
+    }
+
+    let ids: Vector<Arc<str>> = groups
+        .iter()
+        .flat_map(|group| group.remove.iter().map(|album| album.id.clone()))
+        .collect();
+    let count = ids.len();
+
+    col.with_child(
+        Flex::row()
+            .with_child(Button::new(format!("Remove {count} Duplicates")).on_click(
+                move |ctx, _, _| ctx.submit_command(MERGE_DUPLICATE_ALBUMS.with(ids.clone())),
+            ))
+            .with_spacer(theme::grid(1.0))
+            .with_child(
+                Button::new("Cancel")
+                    .on_click(|ctx, _, _| ctx.submit_command(DISMISS_DUPLICATE_ALBUMS)),
+            ),
+    )
+    .padding(theme::grid(1.0))
+}
+
+fn artist_names(album: &Album) -> String {
+    album
+        .artists
+        .iter()
+        .map(|artist| artist.name.as_ref())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn saved_albums_list_widget() -> impl Widget<AppState> {
     Async::new(
         utils::spinner_widget,
-        || List::new(|| album::album_widget(false)).lens(Ctx::map(SavedAlbums::albums)),
+        || {
+            List::new(|| Findable::new(album::album_widget(false)))
+                .lens(Ctx::map(SavedAlbums::albums))
+        },
         utils::error_widget,
     )
     .lens(
@@ -171,3 +393,81 @@ pub fn saved_shows_widget() -> impl Widget<AppState> {
         .with_child(your_shows())
         .with_child(shows_that_you_might_like())
 }
+
+pub fn saved_episodes_widget() -> impl Widget<AppState> {
+    Async::new(utils::spinner_widget, episodes_widget, utils::error_widget).lens(
+        Ctx::make(
+            AppState::common_ctx,
+            AppState::library.then(Library::saved_episodes.in_arc()),
+        )
+        .then(Ctx::in_promise()),
+    )
+}
+
+fn episodes_widget() -> impl Widget<WithCtx<SavedEpisodes>> {
+    Either::new(
+        |saved: &WithCtx<SavedEpisodes>, _| saved.data.episodes.is_empty(),
+        Label::new("Episodes you save from the shows show up here.")
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .padding(theme::grid(1.0)),
+        playable::list_widget(playable::Display {
+            track: track::Display::empty(),
+        }),
+    )
+}
+
+/// Handle loading, saving and removing the saved episodes, for the episode
+/// menus of every page and of the playback bar.
+pub fn saved_episodes_commands(widget: impl Widget<AppState>) -> impl Widget<AppState> {
+    widget
+        .on_command_async(
+            LOAD_EPISODES,
+            |_| {
+                WebApi::global()
+                    .get_saved_episodes()
+                    .map(SavedEpisodes::new)
+            },
+            |_, data, _| {
+                data.with_library_mut(|library| {
+                    library.saved_episodes.defer_default();
+                });
+            },
+            |_, data, r| {
+                data.with_library_mut(|library| {
+                    library.saved_episodes.update(r);
+                });
+            },
+        )
+        .on_command_async(
+            SAVE_EPISODE,
+            |e| WebApi::global().save_episode(&e.id.0.to_base62()),
+            |_, data, e| {
+                data.with_library_mut(move |library| {
+                    library.add_episode(e);
+                });
+            },
+            |_, data, (_, r)| {
+                if let Err(err) = r {
+                    data.error_alert(err);
+                } else {
+                    data.info_alert("Episode added to Your Episodes.");
+                }
+            },
+        )
+        .on_command_async(
+            UNSAVE_EPISODE,
+            |id| WebApi::global().unsave_episode(&id.0.to_base62()),
+            |_, data, id| {
+                data.with_library_mut(|library| {
+                    library.remove_episode(&id);
+                });
+            },
+            |_, data, (_, r)| {
+                if let Err(err) = r {
+                    data.error_alert(err);
+                } else {
+                    data.info_alert("Episode removed from Your Episodes.");
+                }
+            },
+        )
+}
@@ -0,0 +1,137 @@
+use druid::{
+    im::Vector,
+    widget::{Button, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List},
+    LensExt, Selector, Widget, WidgetExt,
+};
+
+use crate::{
+    cmd,
+    data::{AppState, Config, Ctx, LocalFiles},
+    error::Error,
+    widget::{icons, Async, MyWidgetExt},
+};
+
+use super::{playable, theme, track, utils};
+
+pub const LOAD_LOCAL_FILES: Selector<Vector<String>> = Selector::new("app.local.load-files");
+pub const REMOVE_LOCAL_FOLDER: Selector<String> = Selector::new("app.local.remove-folder");
+
+pub fn local_files_widget() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(folders_widget())
+        .with_spacer(theme::grid(2.0))
+        .with_child(tracks_widget())
+        .on_command(REMOVE_LOCAL_FOLDER, |_, folder, data| {
+            data.config.local_folders.retain(|f| f != folder);
+            data.config.save();
+        })
+        .on_update(|ctx, old_data, data, _| {
+            // Rescan as soon as the folders change.
+            if !old_data
+                .config
+                .local_folders
+                .same(&data.config.local_folders)
+            {
+                ctx.submit_command(LOAD_LOCAL_FILES.with(data.config.local_folders.clone()));
+            }
+        })
+}
+
+fn folders_widget() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Label::new("Folders")
+                        .with_font(theme::UI_FONT_MEDIUM)
+                        .expand_width(),
+                    1.0,
+                )
+                .with_child(
+                    Button::new("Rescan").on_click(|ctx, data: &mut AppState, _| {
+                        ctx.submit_command(
+                            LOAD_LOCAL_FILES.with(data.config.local_folders.clone()),
+                        );
+                    }),
+                )
+                .with_default_spacer()
+                .with_child(Button::new("Add Folder…").on_click(|ctx, _, _| add_folder(ctx))),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Either::new(
+                |folders: &Vector<String>, _| folders.is_empty(),
+                Label::new("Add a folder with MP3, FLAC or Ogg files to play them here.")
+                    .with_text_color(theme::PLACEHOLDER_COLOR)
+                    .with_line_break_mode(LineBreaking::WordWrap),
+                List::new(folder_widget),
+            )
+            .lens(AppState::config.then(Config::local_folders)),
+        )
+        .padding(theme::grid(1.0))
+}
+
+fn folder_widget() -> impl Widget<String> {
+    Flex::row()
+        .with_flex_child(
+            Label::dynamic(|folder: &String, _| folder.clone())
+                .with_line_break_mode(LineBreaking::Clip)
+                .expand_width(),
+            1.0,
+        )
+        .with_default_spacer()
+        .with_child(
+            icons::CLOSE
+                .scale(theme::ICON_SIZE_SMALL)
+                .with_color(theme::PLACEHOLDER_COLOR)
+                .padding(theme::grid(0.5))
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|ctx, _, folder: &mut String, _| {
+                    ctx.submit_command(REMOVE_LOCAL_FOLDER.with(folder.clone()));
+                }),
+        )
+        .padding((0.0, theme::grid(0.5)))
+}
+
+fn tracks_widget() -> impl Widget<AppState> {
+    Async::new(
+        utils::spinner_widget,
+        || {
+            playable::list_widget(playable::Display {
+                track: track::Display {
+                    number: true,
+                    title: true,
+                    artist: true,
+                    album: true,
+                    ..track::Display::empty()
+                },
+            })
+        },
+        utils::error_widget,
+    )
+    .lens(Ctx::make(AppState::common_ctx, AppState::local_files).then(Ctx::in_promise()))
+    .on_command_async(
+        LOAD_LOCAL_FILES,
+        |folders| -> Result<LocalFiles, Error> { Ok(LocalFiles::scan(&folders)) },
+        |_, data, folders| data.local_files.defer(folders),
+        |_, data, r| data.local_files.update(r),
+    )
+}
+
+fn add_folder(ctx: &mut druid::EventCtx) {
+    use druid::FileDialogOptions;
+
+    let options = FileDialogOptions::new()
+        .select_directories()
+        .title("Add Local Music Folder");
+
+    ctx.submit_command(cmd::BEGIN_LOCAL_FOLDER_IMPORT);
+    ctx.submit_command(
+        druid::commands::SHOW_OPEN_PANEL
+            .with(options)
+            .to(druid::Target::Auto),
+    );
+}
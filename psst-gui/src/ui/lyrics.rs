@@ -73,7 +73,7 @@ fn track_info_widget() -> impl Widget<AppState> {
         )
 }
 
-fn track_lyrics_widget() -> impl Widget<AppState> {
+pub fn track_lyrics_widget() -> impl Widget<AppState> {
     Async::new(
         utils::spinner_widget,
         || List::new(lyric_line_widget),
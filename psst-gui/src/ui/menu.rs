@@ -1,8 +1,10 @@
-use druid::{commands, platform_menus, Env, LocalizedString, Menu, MenuItem, SysMods, WindowId};
+use druid::{
+    commands, platform_menus, Env, HotKey, LocalizedString, Menu, MenuItem, SysMods, WindowId,
+};
 
 use crate::{
     cmd,
-    data::{AppState, Nav},
+    data::{AppState, KeybindAction, KeybindCategory},
 };
 
 pub fn main_menu(_window: Option<WindowId>, _data: &AppState, _env: &Env) -> Menu<AppState> {
@@ -13,6 +15,7 @@ pub fn main_menu(_window: Option<WindowId>, _data: &AppState, _env: &Env) -> Men
     }
     .entry(edit_menu())
     .entry(view_menu())
+    .entry(playback_menu())
 }
 
 fn mac_app_menu() -> Menu<AppState> {
@@ -27,7 +30,7 @@ fn mac_app_menu() -> Menu<AppState> {
             //  because l10n is a bit stupid now.
             MenuItem::new(LocalizedString::new("macos-menu-quit").with_placeholder("Quit Psst"))
                 .command(cmd::QUIT_APP_WITH_SAVE)
-                .hotkey(SysMods::Cmd, "q"),
+                .dynamic_hotkey(|data: &AppState, _| keybind_hotkey(data, KeybindAction::Quit)),
         )
         .entry(
             MenuItem::new(LocalizedString::new("macos-menu-hide").with_placeholder("Hide Psst"))
@@ -51,41 +54,40 @@ fn edit_menu() -> Menu<AppState> {
 }
 
 fn view_menu() -> Menu<AppState> {
-    Menu::new(LocalizedString::new("menu-view-menu").with_placeholder("View"))
-        .entry(
-            MenuItem::new(LocalizedString::new("menu-item-home").with_placeholder("Home"))
-                .command(cmd::NAVIGATE.with(Nav::Home))
-                .hotkey(SysMods::Cmd, "1"),
-        )
-        .entry(
-            MenuItem::new(
-                LocalizedString::new("menu-item-saved-tracks").with_placeholder("Saved Tracks"),
-            )
-            .command(cmd::NAVIGATE.with(Nav::SavedTracks))
-            .hotkey(SysMods::Cmd, "2"),
-        )
-        .entry(
-            MenuItem::new(
-                LocalizedString::new("menu-item-saved-albums").with_placeholder("Saved Albums"),
-            )
-            .command(cmd::NAVIGATE.with(Nav::SavedAlbums))
-            .hotkey(SysMods::Cmd, "3"),
-        )
-        .entry(
-            MenuItem::new(
-                LocalizedString::new("menu-item-saved-shows").with_placeholder("Saved Shows"),
+    keybind_menu(
+        LocalizedString::new("menu-view-menu").with_placeholder("View"),
+        KeybindCategory::Navigation,
+    )
+}
+
+fn playback_menu() -> Menu<AppState> {
+    keybind_menu(
+        LocalizedString::new("menu-playback-menu").with_placeholder("Playback"),
+        KeybindCategory::Playback,
+    )
+}
+
+/// Menu of the keybind actions in `category`, showing their current keys.
+fn keybind_menu(title: LocalizedString<AppState>, category: KeybindCategory) -> Menu<AppState> {
+    KeybindAction::ALL
+        .into_iter()
+        .filter(|action| action.category() == category)
+        .fold(Menu::new(title), |menu, action| {
+            menu.entry(
+                MenuItem::new(action.label())
+                    .command(cmd::RUN_KEYBIND.with(action))
+                    .dynamic_hotkey(move |data: &AppState, _| keybind_hotkey(data, action)),
             )
-            .command(cmd::NAVIGATE.with(Nav::Shows))
-            .hotkey(SysMods::Cmd, "4"),
-        )
-        .entry(
-            MenuItem::new(LocalizedString::new("menu-item-search").with_placeholder("Search..."))
-                .command(cmd::SET_FOCUS.to(cmd::WIDGET_SEARCH_INPUT))
-                .hotkey(SysMods::Cmd, "l"),
-        )
-        .entry(
-            MenuItem::new(LocalizedString::new("menu-item-find").with_placeholder("Find..."))
-                .command(cmd::TOGGLE_FINDER)
-                .hotkey(SysMods::Cmd, "f"),
-        )
+        })
+}
+
+/// Menu shortcut of the key configured for `action`.  Menus take their keys
+/// before the focused widget, so keys without Ctrl or Cmd are left to the
+/// `KeybindsController`, and typing is not stolen.
+fn keybind_hotkey(data: &AppState, action: KeybindAction) -> Option<HotKey> {
+    data.config
+        .keybinds
+        .get(action)
+        .filter(|combination| combination.ctrl || combination.meta)
+        .map(|combination| combination.hotkey())
 }
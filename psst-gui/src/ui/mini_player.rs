@@ -0,0 +1,135 @@
+use druid::{
+    widget::{Controller, Painter, ViewSwitcher},
+    Env, Event, EventCtx, LocalizedString, Menu, MenuItem, MouseButton, Point, RenderContext,
+    Widget, WidgetExt,
+};
+
+use crate::{
+    cmd,
+    data::{AppState, PlaybackState},
+    widget::{icons, Empty, Overlay, RemoteImage, ShowOnHover},
+};
+
+use super::{theme, utils};
+
+/// Side of the square mini player window.
+pub const MINI_PLAYER_SIZE: f64 = theme::GRID * 12.0;
+
+/// Distance the mouse has to move before a press becomes a drag.
+const DRAG_THRESHOLD: f64 = 3.0;
+
+/// Cover of the playing item, with a play or pause button over it while the
+/// mouse is over the window.  The window has no frame, so it is moved by
+/// dragging the cover anywhere, and closed from its context menu.
+pub fn mini_player_widget() -> impl Widget<AppState> {
+    let cover = RemoteImage::new(utils::placeholder_widget(), |data: &AppState, _| {
+        data.playback
+            .now_playing
+            .as_ref()
+            .and_then(|np| np.cover_image_url(MINI_PLAYER_SIZE, MINI_PLAYER_SIZE))
+            .map(|url| url.into())
+    })
+    .with_size((MINI_PLAYER_SIZE, MINI_PLAYER_SIZE))
+    .fix_size(MINI_PLAYER_SIZE, MINI_PLAYER_SIZE);
+
+    let button = ViewSwitcher::new(
+        |data: &AppState, _| data.playback.state,
+        |state, _, _| match state {
+            PlaybackState::Playing => icons::PAUSE
+                .scale((theme::grid(4.0), theme::grid(4.0)))
+                .boxed(),
+            PlaybackState::Paused => icons::PLAY
+                .scale((theme::grid(4.0), theme::grid(4.0)))
+                .boxed(),
+            PlaybackState::Loading | PlaybackState::Stopped => Empty.boxed(),
+        },
+    )
+    .center()
+    .background(Painter::new(|ctx, _, env| {
+        let bounds = ctx.size().to_rect();
+        ctx.fill(bounds, &env.get(theme::BACKGROUND_DARK).with_alpha(0.6));
+    }))
+    .expand();
+
+    Overlay::bottom(cover, ShowOnHover::new(button))
+        .background(theme::BACKGROUND_DARK)
+        .controller(MiniPlayerController::default())
+}
+
+fn mini_player_menu() -> Menu<AppState> {
+    Menu::empty()
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-show-now-playing")
+                    .with_placeholder("Open Now Playing View"),
+            )
+            .command(cmd::TOGGLE_NOW_PLAYING),
+        )
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-close-mini-player")
+                    .with_placeholder("Close Mini Player"),
+            )
+            .command(cmd::TOGGLE_MINI_PLAYER),
+        )
+}
+
+/// Moves the window while the cover is dragged, and remembers where it was
+/// left.  A click without a drag plays or pauses.
+#[derive(Default)]
+struct MiniPlayerController {
+    /// Position of the press in the window, while the left button is down.
+    pressed_at: Option<Point>,
+    dragged: bool,
+}
+
+impl<W: Widget<AppState>> Controller<AppState, W> for MiniPlayerController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                self.pressed_at = Some(mouse.window_pos);
+                self.dragged = false;
+                ctx.set_active(true);
+                ctx.set_handled();
+            }
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Right => {
+                ctx.show_context_menu(mini_player_menu(), mouse.window_pos);
+                ctx.set_handled();
+            }
+            Event::MouseMove(mouse) if ctx.is_active() => {
+                if let Some(pressed_at) = self.pressed_at {
+                    let delta = mouse.window_pos - pressed_at;
+                    if self.dragged || delta.hypot() > DRAG_THRESHOLD {
+                        self.dragged = true;
+                        let window = ctx.window();
+                        window.set_position(window.get_position() + delta);
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::MouseUp(mouse) if ctx.is_active() && mouse.button == MouseButton::Left => {
+                ctx.set_active(false);
+                self.pressed_at = None;
+                if self.dragged {
+                    data.config.mini_player_position = Some(ctx.window().get_position());
+                    data.config.save();
+                } else {
+                    match data.playback.state {
+                        PlaybackState::Playing => ctx.submit_command(cmd::PLAY_PAUSE),
+                        PlaybackState::Paused => ctx.submit_command(cmd::PLAY_RESUME),
+                        PlaybackState::Loading | PlaybackState::Stopped => {}
+                    }
+                }
+                ctx.set_handled();
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
@@ -1,19 +1,23 @@
-use crate::data::config::SortCriteria;
 use crate::data::Track;
 use crate::error::Error;
 use crate::{
     cmd,
     controller::{
-        AfterDelay, AlertCleanupController, NavController, SessionController, SortController,
+        AfterDelay, AlertCleanupController, KeybindsController, NavController, NetworkController,
+        OfflineController, ScrollRestore, SessionController, SortController, TrayController,
     },
     data::{
-        config::SortOrder, AlbumLink, Alert, AlertStyle, AppState, Config, Nav, Playable, Playback,
-        PlaybackOrigin, PlaybackPayload, PlaylistLink, Route, ALERT_DURATION,
+        AlbumLink, Alert, AlertStyle, AppState, Config, Nav, Playable, Playback, PlaybackOrigin,
+        PlaybackPayload, PlaylistLink, QueueEntry, QueuePlacement, RecommendationsRequest, Route,
+        TrackId, ALERT_DURATION,
     },
     webapi::WebApi,
     widget::{
-        icons, icons::SvgIcon, Border, Empty, MyWidgetExt, Overlay, RemoteImage, ThemeScope,
-        ViewDispatcher,
+        icons,
+        icons::SvgIcon,
+        zoom::{ZoomAction, ZOOM},
+        Border, Empty, MyWidgetExt, Overlay, RemoteImage, SliderKeys, SliderSteps, ThemeScope,
+        ViewDispatcher, Zoom,
     },
 };
 use credits::TrackCredits;
@@ -21,35 +25,48 @@ use druid::widget::Controller;
 use druid::KbKey;
 use druid::{
     im::Vector,
-    widget::{CrossAxisAlignment, Either, Flex, Label, List, Scroll, Slider, Split, ViewSwitcher},
-    Color, Env, Insets, Key, LensExt, Menu, MenuItem, Selector, Widget, WidgetExt, WindowDesc,
+    widget::{
+        Button, CrossAxisAlignment, Either, Flex, Label, List, Scroll, Slider, Split, ViewSwitcher,
+    },
+    Color, Env, Insets, Key, LensExt, Menu, MenuItem, Screen, Selector, Widget, WidgetExt,
+    WindowDesc, WindowState,
 };
 use druid_shell::Cursor;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
 pub mod album;
 pub mod artist;
+pub mod browse;
 pub mod credits;
 pub mod episode;
 pub mod find;
+pub mod history;
 pub mod home;
 pub mod library;
+pub mod local;
 pub mod lyrics;
 pub mod menu;
+pub mod mini_player;
+pub mod now_playing;
+pub mod palette;
 pub mod playable;
 pub mod playback;
 pub mod playlist;
 pub mod preferences;
+pub mod queue;
 pub mod recommend;
 pub mod search;
 pub mod show;
+pub mod stats;
 pub mod theme;
 pub mod track;
 pub mod user;
 pub mod utils;
 
 pub const DOWNLOAD_ARTWORK: Selector<(String, String)> = Selector::new("app.artwork.download");
+pub const COPY_ARTWORK: Selector<String> = Selector::new("app.artwork.copy");
 
 pub fn main_window(config: &Config) -> WindowDesc<AppState> {
     let win = WindowDesc::new(root_widget())
@@ -92,7 +109,7 @@ pub fn preferences_window() -> WindowDesc<AppState> {
 pub fn account_setup_window() -> WindowDesc<AppState> {
     let win = WindowDesc::new(account_setup_widget())
         .title("Login")
-        .window_size((theme::grid(50.0), theme::grid(45.0)))
+        .window_size((theme::grid(70.0), theme::grid(70.0)))
         .resizable(false)
         .show_title(false)
         .transparent_titlebar(true);
@@ -104,7 +121,7 @@ pub fn account_setup_window() -> WindowDesc<AppState> {
 }
 
 pub fn artwork_window() -> WindowDesc<AppState> {
-    let win_size = (theme::grid(50.0), theme::grid(50.0));
+    let win_size = (theme::grid(50.0), theme::grid(56.0));
 
     // On Windows, the window size includes the titlebar, so we need to account for it
     let win_size = if cfg!(target_os = "windows") {
@@ -139,6 +156,49 @@ pub fn artwork_window() -> WindowDesc<AppState> {
     }
 }
 
+/// Full-screen window of the now-playing view: without a title bar, covering
+/// a monitor, the second one if there is one.
+pub fn now_playing_window() -> WindowDesc<AppState> {
+    let win = WindowDesc::new(ThemeScope::new(now_playing::now_playing_widget()))
+        .title("Now Playing")
+        .show_titlebar(false);
+    let monitors = Screen::get_monitors();
+    let monitor = monitors
+        .iter()
+        .find(|monitor| !monitor.is_primary())
+        .or_else(|| monitors.first());
+    let win = match monitor {
+        Some(monitor) => {
+            let rect = monitor.virtual_rect();
+            win.set_position(rect.origin()).window_size(rect.size())
+        }
+        None => win
+            .window_size((theme::grid(160.0), theme::grid(90.0)))
+            .set_window_state(WindowState::Maximized),
+    };
+
+    if cfg!(target_os = "macos") {
+        win.menu(menu::main_menu)
+    } else {
+        win
+    }
+}
+
+pub fn mini_player_window(config: &Config) -> WindowDesc<AppState> {
+    let size = (mini_player::MINI_PLAYER_SIZE, mini_player::MINI_PLAYER_SIZE);
+    let win = WindowDesc::new(ThemeScope::new(mini_player::mini_player_widget()))
+        .title("Psst")
+        .window_size(size)
+        .resizable(false)
+        .show_titlebar(false)
+        .set_always_on_top(true);
+
+    match config.mini_player_position {
+        Some(position) => win.set_position(position),
+        None => win,
+    }
+}
+
 fn preferences_widget() -> impl Widget<AppState> {
     ThemeScope::new(
         preferences::preferences_widget()
@@ -172,54 +232,125 @@ impl<W: Widget<AppState>> Controller<AppState, W> for ArtworkController {
         }
 
         if let druid::Event::KeyDown(key_event) = event {
-            // Handle D key for download
-            if key_event.key == KbKey::Character('d'.into()) {
-                if let Some(np) = &data.playback.now_playing {
-                    if let Some((url, _)) = np.cover_image_metadata() {
-                        let title = match &np.item {
-                            Playable::Track(track) => track
-                                .album
-                                .as_ref()
-                                .map(|a| a.name.as_ref())
-                                .unwrap_or("Unknown Album"),
-                            Playable::Episode(episode) => episode.show.name.as_ref(),
-                        };
-                        ctx.submit_command(
-                            DOWNLOAD_ARTWORK.with((url.to_string(), title.to_string())),
-                        );
-                        ctx.set_handled();
-                    }
+            let window = druid::Target::Window(ctx.window_id());
+            let zoom = move |action| ZOOM.with(action).to(window);
+            match &key_event.key {
+                // Handle D key for download
+                KbKey::Character(c) if c == "d" => {
+                    save_artwork(ctx, data);
+                    ctx.set_handled();
                 }
+                KbKey::Character(c)
+                    if c == "c" && (key_event.mods.ctrl() || key_event.mods.meta()) =>
+                {
+                    copy_artwork(ctx, data);
+                    ctx.set_handled();
+                }
+                KbKey::Character(c) if c == "+" || c == "=" => {
+                    ctx.submit_command(zoom(ZoomAction::In));
+                    ctx.set_handled();
+                }
+                KbKey::Character(c) if c == "-" => {
+                    ctx.submit_command(zoom(ZoomAction::Out));
+                    ctx.set_handled();
+                }
+                KbKey::Character(c) if c == "0" => {
+                    ctx.submit_command(zoom(ZoomAction::Reset));
+                    ctx.set_handled();
+                }
+                _ => {}
             }
         }
         child.event(ctx, event, data, env);
     }
 }
 
-pub fn artwork_widget() -> impl Widget<AppState> {
-    RemoteImage::new(utils::placeholder_widget(), move |data: &AppState, _| {
-        data.playback
-            .now_playing
+/// URL of the largest available cover of the playing item.
+fn full_size_artwork_url(data: &AppState) -> Option<&str> {
+    data.playback
+        .now_playing
+        .as_ref()
+        .and_then(|np| np.cover_image_metadata())
+        .map(|(url, _)| url)
+}
+
+fn save_artwork(ctx: &mut druid::EventCtx, data: &AppState) {
+    let (Some(np), Some(url)) = (&data.playback.now_playing, full_size_artwork_url(data)) else {
+        return;
+    };
+    let title = match &np.item {
+        Playable::Track(track) => track
+            .album
             .as_ref()
-            .and_then(|np| np.cover_image_url(512.0, 512.0))
-            .map(|url| url.into())
-    })
-    .expand()
-    .background(theme::BACKGROUND_DARK)
-    .controller(ArtworkController)
+            .map(|a| a.name.as_ref())
+            .unwrap_or("Unknown Album"),
+        Playable::Episode(episode) => episode.show.name.as_ref(),
+    };
+    ctx.submit_command(DOWNLOAD_ARTWORK.with((url.to_string(), title.to_string())));
+
+    let options = druid::FileDialogOptions::new()
+        .default_name(format!("{title}.jpg"))
+        .allowed_types(vec![druid::FileSpec::new("Image", &["jpg", "jpeg", "png"])]);
+    ctx.submit_command(
+        druid::commands::SHOW_SAVE_PANEL
+            .with(options)
+            .to(druid::Target::Auto),
+    );
+}
+
+fn copy_artwork(ctx: &mut druid::EventCtx, data: &AppState) {
+    if let Some(url) = full_size_artwork_url(data) {
+        ctx.submit_command(COPY_ARTWORK.with(url.to_string()));
+    }
+}
+
+pub fn artwork_widget() -> impl Widget<AppState> {
+    let artwork = Zoom::new(
+        RemoteImage::new(utils::placeholder_widget(), move |data: &AppState, _| {
+            full_size_artwork_url(data).map(|url| url.into())
+        })
+        .expand(),
+    );
+
+    let zoom_button = |label, action| {
+        Button::new(label).on_click(move |ctx, _, _| {
+            ctx.submit_command(ZOOM.with(action).to(druid::Target::Window(ctx.window_id())));
+        })
+    };
+    let toolbar = Flex::row()
+        .with_child(zoom_button("\u{2212}", ZoomAction::Out))
+        .with_spacer(theme::grid(0.5))
+        .with_child(zoom_button("+", ZoomAction::In))
+        .with_spacer(theme::grid(0.5))
+        .with_child(zoom_button("Fit", ZoomAction::Reset))
+        .with_flex_spacer(1.0)
+        .with_child(Button::new("Copy").on_click(|ctx, data, _| copy_artwork(ctx, data)))
+        .with_spacer(theme::grid(0.5))
+        .with_child(Button::new("Save As\u{2026}").on_click(|ctx, data, _| save_artwork(ctx, data)))
+        .padding(theme::grid(1.0));
+
+    Flex::column()
+        .with_flex_child(artwork, 1.0)
+        .with_child(toolbar)
+        .background(theme::BACKGROUND_DARK)
+        .controller(ArtworkController)
 }
 
 fn root_widget() -> impl Widget<AppState> {
-    let content = Either::new(
+    let content = library::saved_episodes_commands(Either::new(
         |state: &AppState, _| state.config.sidebar_visible,
         layout_with_sidebar(),
         main_panel_only_widget(),
-    );
+    ));
 
     ThemeScope::new(content)
         .controller(SessionController)
+        .controller(NetworkController::new())
+        .controller(OfflineController::new())
         .controller(NavController)
+        .controller(KeybindsController)
         .controller(SortController)
+        .controller(TrayController::new())
         .on_command_async(
             cmd::LOAD_TRACK_CREDITS,
             |track: Arc<Track>| {
@@ -239,6 +370,43 @@ fn root_widget() -> impl Widget<AppState> {
                 }
             },
         )
+        .on_command_async(
+            cmd::LOAD_AUDIO_FEATURES,
+            |id: TrackId| WebApi::global().get_audio_features(&id.0.to_base62()),
+            |_, data: &mut AppState, id| data.audio_features.defer(id),
+            |_, data, r| data.audio_features.update(r),
+        )
+        .on_command_async(
+            cmd::EXPORT_REPLAY_GAIN,
+            |track: Arc<Track>| -> Result<(), Arc<str>> {
+                let path = track
+                    .local_path
+                    .as_deref()
+                    .ok_or("Track is not a local file")?;
+                psst_core::audio::normalize::export_replay_gain(Path::new(path))
+                    .map_err(|err| Arc::from(err.to_string()))
+            },
+            |_, _, _| {},
+            |_, data, (track, result): (Arc<Track>, Result<(), Arc<str>>)| match result {
+                Ok(()) => {
+                    data.info_alert(format!("ReplayGain tags written to \"{}\".", track.name))
+                }
+                Err(err) => data.error_alert(format!("Failed to export ReplayGain tags: {err}")),
+            },
+        )
+        .on_command_async(
+            cmd::SYNC_LIBRARY,
+            |_| WebApi::global().sync_library(),
+            |_, _, _| {},
+            |_, data, (_, result): ((), Result<(), Error>)| match result {
+                Ok(()) => data.with_library_mut(|library| {
+                    if let Some(saved) = library.saved_tracks.resolved_mut() {
+                        saved.set.extend(WebApi::global().synced_saved_track_ids());
+                    }
+                }),
+                Err(err) => log::warn!("failed to sync the library: {err}"),
+            },
+        )
         .on_command_async(
             cmd::PLAY_PLAYLIST,
             |link: PlaylistLink| WebApi::global().get_playlist_tracks(&link.id),
@@ -257,6 +425,7 @@ fn root_widget() -> impl Widget<AppState> {
                                 origin: PlaybackOrigin::Playlist(link.clone()),
                                 items,
                                 position: 0,
+                                seek: None,
                             };
                             ctx.submit_command(cmd::PLAY_TRACKS.with(payload));
                         }
@@ -287,6 +456,7 @@ fn root_widget() -> impl Widget<AppState> {
                                 origin: PlaybackOrigin::Album(link.clone()),
                                 items,
                                 position: 0,
+                                seek: None,
                             };
                             ctx.submit_command(cmd::PLAY_TRACKS.with(payload));
                         }
@@ -298,6 +468,114 @@ fn root_widget() -> impl Widget<AppState> {
                 ctx.set_handled();
             },
         )
+        .on_command_async(
+            cmd::QUEUE_PLAYLIST,
+            |(link, _): (PlaylistLink, QueuePlacement)| {
+                WebApi::global().get_playlist_tracks(&link.id)
+            },
+            |_, _, _| {},
+            |ctx,
+             data,
+             ((link, placement), result): (
+                (PlaylistLink, QueuePlacement),
+                Result<Vector<Arc<Track>>, Error>,
+            )| {
+                match result {
+                    Ok(tracks) => {
+                        let entries = tracks
+                            .into_iter()
+                            .map(|track| QueueEntry {
+                                item: Playable::Track(track),
+                                origin: PlaybackOrigin::Playlist(link.clone()),
+                            })
+                            .collect();
+                        ctx.submit_command(cmd::QUEUE_ITEMS.with((placement, entries)));
+                    }
+                    Err(err) => {
+                        data.error_alert(format!("Failed to queue playlist: {err}"));
+                    }
+                }
+                ctx.set_handled();
+            },
+        )
+        .on_command_async(
+            cmd::SHOW_PLAYLIST_RADIO,
+            |link: PlaylistLink| WebApi::global().get_playlist_tracks(&link.id),
+            |_, _, _| {},
+            |ctx, data, (link, result): (PlaylistLink, Result<Vector<Arc<Track>>, Error>)| {
+                match result {
+                    Ok(tracks) => {
+                        let tracks: Vec<_> = tracks.into_iter().collect();
+                        let request = RecommendationsRequest::for_playlist(link.name, &tracks);
+                        if request.seed_count() == 0 {
+                            data.info_alert("Playlist has no tracks to start a radio from");
+                        } else {
+                            ctx.submit_command(
+                                cmd::NAVIGATE.with(Nav::Recommendations(Arc::new(request))),
+                            );
+                        }
+                    }
+                    Err(err) => {
+                        data.error_alert(format!("Failed to start playlist radio: {err}"));
+                    }
+                }
+                ctx.set_handled();
+            },
+        )
+        .on_command_async(
+            cmd::START_RADIO,
+            |request: Arc<RecommendationsRequest>| WebApi::global().get_recommendations(request),
+            |_, _, _| {},
+            |ctx, data, (request, result)| {
+                match result {
+                    Ok(recommendations) if recommendations.tracks.is_empty() => {
+                        data.info_alert("Found nothing to play on the radio");
+                    }
+                    Ok(recommendations) => {
+                        let items = recommendations
+                            .tracks
+                            .into_iter()
+                            .map(Playable::Track)
+                            .collect();
+                        ctx.submit_command(cmd::PLAY_TRACKS.with(PlaybackPayload {
+                            origin: PlaybackOrigin::Radio(request),
+                            items,
+                            position: 0,
+                            seek: None,
+                        }));
+                    }
+                    Err(err) => {
+                        data.error_alert(format!("Failed to start radio: {err}"));
+                    }
+                }
+                ctx.set_handled();
+            },
+        )
+        .on_command_async(
+            cmd::QUEUE_ALBUM,
+            |(link, _): (AlbumLink, QueuePlacement)| WebApi::global().get_album(&link.id),
+            |_, _, _| {},
+            |ctx, data, ((link, placement), result)| {
+                match result {
+                    Ok(album) => {
+                        let entries = album
+                            .data
+                            .into_tracks_with_context()
+                            .into_iter()
+                            .map(|track| QueueEntry {
+                                item: Playable::Track(track),
+                                origin: PlaybackOrigin::Album(link.clone()),
+                            })
+                            .collect();
+                        ctx.submit_command(cmd::QUEUE_ITEMS.with((placement, entries)));
+                    }
+                    Err(err) => {
+                        data.error_alert(format!("Failed to queue album: {err}"));
+                    }
+                }
+                ctx.set_handled();
+            },
+        )
     // .debug_invalidation()
     // .debug_widget_id()
     // .debug_paint_layout()
@@ -313,21 +591,30 @@ fn layout_with_sidebar() -> impl Widget<AppState> {
 }
 
 fn main_panel_widget() -> impl Widget<AppState> {
-    Flex::column()
+    let panel = Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_child(topbar_widget())
-        // The main content area: the central route view and an optional right-hand lyrics panel.
+        // The main content area: the central route view and an optional right-hand panel showing
+        // either the lyrics or the play queue.
         .with_flex_child(
             Either::new(
-                // If visible and a track is playing, show a draggable split between center and lyrics.
-                |data: &AppState, _| data.lyrics_visible && data.playback.now_playing.is_some(),
-                // When visible: Split the central route view and the lyrics panel. The lyrics panel
-                // has a starting fixed width but the user can drag the divider to resize.
+                // If the queue is visible, or the lyrics are and a track is playing, show a
+                // draggable split between center and the panel.
+                |data: &AppState, _| {
+                    data.queue_visible
+                        || (data.lyrics_visible && data.playback.now_playing.is_some())
+                },
+                // When visible: Split the central route view and the side panel. The panel has a
+                // starting fixed width but the user can drag the divider to resize.
                 Split::columns(
                     Overlay::bottom(route_widget(), alert_widget()),
-                    lyrics::lyrics_widget()
-                        .padding(theme::grid(1.0))
-                        .fix_width(420.0),
+                    Either::new(
+                        |data: &AppState, _| data.queue_visible,
+                        queue::queue_widget(),
+                        lyrics::lyrics_widget(),
+                    )
+                    .padding(theme::grid(1.0))
+                    .fix_width(420.0),
                 )
                 .split_point(0.75)
                 .bar_size(6.0)
@@ -339,8 +626,8 @@ fn main_panel_widget() -> impl Widget<AppState> {
             ),
             1.0,
         )
-        .with_child(playback::panel_widget())
-        .background(theme::BACKGROUND_LIGHT)
+        .with_child(playback::panel_widget());
+    Overlay::top(panel, palette::palette_widget()).background(theme::BACKGROUND_LIGHT)
 }
 
 #[cfg(target_os = "macos")]
@@ -358,7 +645,6 @@ fn topbar_widget() -> impl Widget<AppState> {
         .must_fill_main_axis(true)
         .with_child(topbar_back_button_widget())
         .with_child(topbar_title_widget())
-        .with_child(topbar_sort_widget())
         .background(Border::Bottom.with_color(theme::BACKGROUND_DARK))
 }
 
@@ -371,6 +657,7 @@ fn sidebar_widget() -> impl Widget<AppState> {
         .must_fill_main_axis(true)
         .with_child(sidebar_menu_widget())
         .with_default_spacer()
+        .with_child(playlist::new_playlist_widget())
         .with_flex_child(playlists, 1.0)
         .padding(if cfg!(target_os = "macos") {
             // Accommodate the window controls on Mac.
@@ -409,6 +696,24 @@ fn alert_widget() -> impl Widget<AppState> {
             )
             .with_default_spacer()
             .with_flex_child(Label::raw().lens(Alert::message), 1.0)
+            .with_child(Either::new(
+                |alert: &Alert, _| alert.action.is_some(),
+                Button::dynamic(|alert: &Alert, _| {
+                    alert
+                        .action
+                        .as_ref()
+                        .map(|action| action.label.to_string())
+                        .unwrap_or_default()
+                })
+                .on_click(|ctx, alert: &mut Alert, _| {
+                    if let Some(action) = &alert.action {
+                        ctx.submit_command(action.command.clone());
+                    }
+                    ctx.submit_command(DISMISS_ALERT.with(alert.id));
+                })
+                .padding((theme::grid(1.0), 0.0, 0.0, 0.0)),
+                Empty,
+            ))
             .padding(theme::grid(2.0))
             .background(BG)
             .env_scope(|env, alert: &Alert| {
@@ -440,68 +745,97 @@ fn route_widget() -> impl Widget<AppState> {
         |route: &Route, _, _| match route {
             Route::Home => Scroll::new(home::home_widget().padding(theme::grid(1.0)))
                 .vertical()
+                .controller(ScrollRestore::new())
                 .boxed(),
-            Route::Lyrics => Scroll::new(lyrics::lyrics_widget().padding(theme::grid(1.0)))
+            Route::Browse => Scroll::new(browse::browse_widget().padding(theme::grid(1.0)))
                 .vertical()
+                .controller(ScrollRestore::new())
                 .boxed(),
-            Route::SavedTracks => Flex::column()
-                .with_child(
-                    find::finder_widget(cmd::FIND_IN_SAVED_TRACKS, "Find in Saved Tracks...")
-                        .lens(AppState::finder),
-                )
-                .with_flex_child(
-                    Scroll::new(library::saved_tracks_widget().padding(theme::grid(1.0)))
-                        .vertical(),
-                    1.0,
-                )
-                .boxed(),
-            Route::SavedAlbums => {
-                Scroll::new(library::saved_albums_widget().padding(theme::grid(1.0)))
+            Route::NewReleases => {
+                Scroll::new(browse::new_releases_widget().padding(theme::grid(1.0)))
                     .vertical()
+                    .controller(ScrollRestore::new())
                     .boxed()
             }
-            Route::Shows => Scroll::new(library::saved_shows_widget().padding(theme::grid(1.0)))
-                .vertical()
-                .boxed(),
-            Route::SearchResults => Scroll::new(search::results_widget().padding(theme::grid(1.0)))
-                .vertical()
-                .boxed(),
-            Route::AlbumDetail => Scroll::new(album::detail_widget().padding(theme::grid(1.0)))
+            Route::BrowseCategory => {
+                Scroll::new(browse::category_detail_widget().padding(theme::grid(1.0)))
+                    .vertical()
+                    .controller(ScrollRestore::new())
+                    .boxed()
+            }
+            Route::Lyrics => Scroll::new(lyrics::lyrics_widget().padding(theme::grid(1.0)))
                 .vertical()
+                .controller(ScrollRestore::new())
                 .boxed(),
-            Route::ArtistDetail => Scroll::new(artist::detail_widget().padding(theme::grid(1.0)))
+            Route::SavedTracks => {
+                findable_page_widget("Find in Saved Tracks...", library::saved_tracks_widget())
+            }
+            Route::SavedAlbums => {
+                findable_page_widget("Find in Saved Albums...", library::saved_albums_widget())
+            }
+            Route::Shows => Scroll::new(library::saved_shows_widget().padding(theme::grid(1.0)))
                 .vertical()
+                .controller(ScrollRestore::new())
                 .boxed(),
-            Route::PlaylistDetail => Flex::column()
-                .with_child(
-                    find::finder_widget(cmd::FIND_IN_PLAYLIST, "Find in Playlist...")
-                        .lens(AppState::finder),
-                )
-                .with_flex_child(
-                    Scroll::new(playlist::detail_widget().padding(theme::grid(1.0))).vertical(),
-                    1.0,
-                )
-                .boxed(),
-            Route::ShowDetail => Scroll::new(show::detail_widget().padding(theme::grid(1.0)))
+            Route::SavedEpisodes => {
+                findable_page_widget("Find in Your Episodes...", library::saved_episodes_widget())
+            }
+            Route::LocalFiles => {
+                findable_page_widget("Find in Local Files...", local::local_files_widget())
+            }
+            Route::History => findable_page_widget("Find in History...", history::history_widget()),
+            Route::Stats => Scroll::new(stats::stats_widget().padding(theme::grid(1.0)))
                 .vertical()
+                .controller(ScrollRestore::new())
                 .boxed(),
+            Route::SearchResults => {
+                findable_page_widget("Find in Results...", search::results_widget())
+            }
+            Route::AlbumDetail => findable_page_widget("Find in Album...", album::detail_widget()),
+            Route::ArtistDetail => {
+                findable_page_widget("Find in Artist...", artist::detail_widget())
+            }
+            Route::PlaylistDetail => {
+                findable_page_widget("Find in Playlist...", playlist::detail_widget())
+            }
+            Route::ShowDetail => findable_page_widget("Find in Podcast...", show::detail_widget()),
             Route::Recommendations => {
-                Scroll::new(recommend::results_widget().padding(theme::grid(1.0)))
-                    .vertical()
-                    .boxed()
+                findable_page_widget("Find in Recommendations...", recommend::results_widget())
             }
         },
     )
     .expand()
 }
 
+/// Scrolled page with the finder on top, searching its lists.
+fn findable_page_widget(
+    label: &'static str,
+    page: impl Widget<AppState> + 'static,
+) -> Box<dyn Widget<AppState>> {
+    Flex::column()
+        .with_child(find::finder_widget(label).lens(AppState::finder))
+        .with_flex_child(
+            Scroll::new(page.padding(theme::grid(1.0)))
+                .vertical()
+                .controller(ScrollRestore::new()),
+            1.0,
+        )
+        .boxed()
+}
+
 fn sidebar_menu_widget() -> impl Widget<AppState> {
     Flex::column()
         .with_default_spacer()
         .with_child(sidebar_link_widget("Home", Nav::Home))
+        .with_child(sidebar_link_widget("Browse", Nav::Browse))
+        .with_child(sidebar_link_widget("New Releases", Nav::NewReleases))
         .with_child(sidebar_link_widget("Favourites", Nav::SavedTracks))
         .with_child(sidebar_link_widget("Albums", Nav::SavedAlbums))
         .with_child(sidebar_link_widget("Podcasts", Nav::Shows))
+        .with_child(sidebar_link_widget("Your Episodes", Nav::SavedEpisodes))
+        .with_child(sidebar_link_widget("Local Files", Nav::LocalFiles))
+        .with_child(sidebar_link_widget("History", Nav::History))
+        .with_child(sidebar_link_widget("Stats", Nav::Stats))
         .with_child(search::input_widget().padding((theme::grid(1.0), theme::grid(1.0))))
 }
 
@@ -540,8 +874,13 @@ fn sidebar_link_widget(title: &str, link_nav: Nav) -> impl Widget<AppState> {
 fn volume_slider() -> impl Widget<AppState> {
     const SAVE_DELAY: Duration = Duration::from_millis(100);
     const SAVE_TO_CONFIG: Selector = Selector::new("app.volume.save-to-config");
+    const STEPS: SliderSteps = SliderSteps {
+        fine: 0.01,
+        normal: 0.05,
+        coarse: 0.2,
+    };
 
-    Flex::row()
+    let slider = Flex::row()
         .with_flex_child(
             Slider::new()
                 .with_range(0.0, 1.0)
@@ -570,50 +909,18 @@ fn volume_slider() -> impl Widget<AppState> {
                 data.playback.volume = (data.playback.volume + scaled_delta).clamp(0.0, 1.0);
             },
         )
-}
-
-fn topbar_sort_widget() -> impl Widget<AppState> {
-    let up_icon = icons::UP.scale((10.0, theme::grid(2.0)));
-    let down_icon = icons::DOWN.scale((10.0, theme::grid(2.0)));
-
-    let ascending_icon = up_icon
-        .padding(theme::grid(1.0))
-        .link()
-        .rounded(theme::BUTTON_BORDER_RADIUS)
-        .on_left_click(|ctx, _, _, _| {
-            ctx.submit_command(cmd::TOGGLE_SORT_ORDER);
-        })
-        .context_menu(sorting_menu);
-
-    let descending_icon = down_icon
-        .padding(theme::grid(1.0))
-        .link()
-        .rounded(theme::BUTTON_BORDER_RADIUS)
-        .on_left_click(|ctx, _, _, _| {
-            ctx.submit_command(cmd::TOGGLE_SORT_ORDER);
-        })
-        .context_menu(sorting_menu);
-    let enabled = Either::new(
-        |data: &AppState, _| {
-            // check if the current nav is PlaylistDetail
-            data.config.sort_order == SortOrder::Ascending
-        },
-        ascending_icon,
-        descending_icon,
-    );
-
-    //a "dynamic" widget that is always disabled.
-    let disabled = Either::new(|_, _| true, Empty.boxed(), Empty.boxed());
+        .on_command(SAVE_TO_CONFIG, |_, _, data| {
+            data.save_volume();
+            data.config.save();
+        });
 
-    Either::new(
-        |nav: &AppState, _| {
-            // check if the current nav is PlaylistDetail
-            matches!(nav.nav, Nav::PlaylistDetail(_))
-        },
-        enabled,
-        disabled,
-    )
-    .padding(theme::grid(1.0)) //.lens(AppState::nav)
+    SliderKeys::new(slider, STEPS, |_, data, delta| {
+        data.playback.volume = (data.playback.volume + delta).clamp(0.0, 1.0);
+        Some(format!(
+            "Volume {}%",
+            (data.playback.volume * 100.0).floor()
+        ))
+    })
 }
 
 fn topbar_back_button_widget() -> impl Widget<AppState> {
@@ -656,34 +963,6 @@ fn history_menu(history: &Vector<Nav>) -> Menu<AppState> {
     menu
 }
 
-fn sorting_menu(app_state: &AppState) -> Menu<AppState> {
-    let mut menu = Menu::new("Sort by");
-
-    // Create menu items for sorting options
-    let mut sort_by_title = MenuItem::new("Title").command(cmd::SORT_BY_TITLE);
-    let mut sort_by_album = MenuItem::new("Album").command(cmd::SORT_BY_ALBUM);
-    let mut sort_by_date_added = MenuItem::new("Date Added").command(cmd::SORT_BY_DATE_ADDED);
-    let mut sort_by_duration = MenuItem::new("Duration").command(cmd::SORT_BY_DURATION);
-    let mut sort_by_artist = MenuItem::new("Artist").command(cmd::SORT_BY_ARTIST);
-
-    match app_state.config.sort_criteria {
-        SortCriteria::Title => sort_by_title = sort_by_title.selected(true),
-        SortCriteria::Album => sort_by_album = sort_by_album.selected(true),
-        SortCriteria::DateAdded => sort_by_date_added = sort_by_date_added.selected(true),
-        SortCriteria::Duration => sort_by_duration = sort_by_duration.selected(true),
-        SortCriteria::Artist => sort_by_artist = sort_by_artist.selected(true),
-    };
-
-    // Add the items and checkboxes to the menu
-    menu = menu.entry(sort_by_album);
-    menu = menu.entry(sort_by_artist);
-    menu = menu.entry(sort_by_date_added);
-    menu = menu.entry(sort_by_duration);
-    menu = menu.entry(sort_by_title);
-
-    menu
-}
-
 fn topbar_title_widget() -> impl Widget<AppState> {
     Flex::row()
         .cross_axis_alignment(CrossAxisAlignment::Center)
@@ -699,13 +978,21 @@ fn route_icon_widget() -> impl Widget<Nav> {
         |nav: &Nav, _, _| {
             let icon = |icon: &SvgIcon| icon.scale(theme::ICON_SIZE_MEDIUM);
             match &nav {
-                Nav::Home | Nav::Lyrics | Nav::SavedTracks | Nav::SavedAlbums | Nav::Shows => {
-                    Empty.boxed()
-                }
+                Nav::Home
+                | Nav::Browse
+                | Nav::NewReleases
+                | Nav::Lyrics
+                | Nav::SavedTracks
+                | Nav::SavedAlbums
+                | Nav::Shows
+                | Nav::SavedEpisodes
+                | Nav::LocalFiles
+                | Nav::History
+                | Nav::Stats => Empty.boxed(),
                 Nav::SearchResults(_) | Nav::Recommendations(_) => icon(&icons::SEARCH).boxed(),
                 Nav::AlbumDetail(_, _) => icon(&icons::ALBUM).boxed(),
                 Nav::ArtistDetail(_) => icon(&icons::ARTIST).boxed(),
-                Nav::PlaylistDetail(_) => icon(&icons::PLAYLIST).boxed(),
+                Nav::PlaylistDetail(_) | Nav::BrowseCategory(_) => icon(&icons::PLAYLIST).boxed(),
                 Nav::ShowDetail(_) => icon(&icons::PODCAST).boxed(),
             }
         },
@@ -719,14 +1006,8 @@ fn route_title_widget() -> impl Widget<Nav> {
 }
 
 fn compute_main_window_title(data: &AppState, _env: &Env) -> String {
-    if let Some(now_playing) = &data.playback.now_playing {
-        match &now_playing.item {
-            Playable::Track(track) => {
-                format!("{} - {}", track.artist_name(), track.name)
-            }
-            Playable::Episode(episode) => episode.name.to_string(),
-        }
-    } else {
-        "Psst".to_owned()
+    match &data.playback.now_playing {
+        Some(now_playing) => now_playing.title(),
+        None => "Psst".to_owned(),
     }
 }
@@ -0,0 +1,317 @@
+use std::time::Duration;
+
+use druid::{
+    im::Vector,
+    lens::Map,
+    widget::{
+        prelude::*, Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, Scroll,
+    },
+    LensExt, Rect, TimerToken, WidgetExt,
+};
+use psst_core::audio::spectrum::{Spectrum, SPECTRUM_BANDS};
+
+use crate::{
+    cmd,
+    controller::KeybindsController,
+    data::{AppState, Playable, Playback, PlaybackState, QueueEntry},
+    widget::{icons, Empty, Maybe, MyWidgetExt, RemoteImage},
+};
+
+use super::{full_size_artwork_url, lyrics, playback, queue, theme, utils};
+
+/// Entries of the queue shown under "Up Next".
+const UP_NEXT_SHOWN: usize = 5;
+
+/// Width of the column with the queue and the lyrics.
+const SIDE_WIDTH: f64 = theme::GRID * 60.0;
+
+/// Height of the visualizer bars at full level.
+const VISUALIZER_HEIGHT: f64 = theme::GRID * 10.0;
+/// How often the visualizer reads the spectrum.
+const VISUALIZER_TICK: Duration = Duration::from_millis(33);
+/// Share of its height a bar keeps on each tick once the sound drops.
+const VISUALIZER_FALL: f32 = 0.85;
+
+/// Large artwork of the playing item with its progress and controls, and the
+/// upcoming entries of the queue.  The synced lyrics and a spectrum of the
+/// audio can be turned on.  Meant for a second monitor or a TV.
+pub fn now_playing_widget() -> impl Widget<AppState> {
+    let artwork = RemoteImage::new(utils::placeholder_widget(), |data: &AppState, _| {
+        full_size_artwork_url(data).map(|url| url.into())
+    })
+    .expand();
+
+    let main = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_flex_child(artwork, 1.0)
+        .with_spacer(theme::grid(3.0))
+        .with_child(Visualizer::new())
+        .with_child(info_widget())
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Maybe::or_empty(playback::progress_widget)
+                .lens(AppState::playback.then(Playback::now_playing)),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(controls_widget())
+        .padding(theme::grid(6.0));
+
+    let side = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(up_next_widget())
+        .with_spacer(theme::grid(3.0))
+        .with_flex_child(
+            Either::new(
+                |data: &AppState, _| data.config.now_playing_lyrics,
+                Scroll::new(lyrics::track_lyrics_widget()).vertical(),
+                Empty,
+            ),
+            1.0,
+        )
+        .padding((0.0, theme::grid(6.0), theme::grid(6.0), theme::grid(6.0)))
+        .fix_width(SIDE_WIDTH);
+
+    Flex::row()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_flex_child(main, 1.0)
+        .with_child(side)
+        .background(theme::BACKGROUND_DARK)
+        .controller(KeybindsController)
+        .controller(NowPlayingController)
+}
+
+fn info_widget() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Label::dynamic(|data: &AppState, _| {
+                data.playback.now_playing.as_ref().map_or_else(
+                    || "Nothing is playing".to_string(),
+                    |np| np.item.name().to_string(),
+                )
+            })
+            .with_font(theme::UI_FONT_MEDIUM)
+            .with_text_size(theme::grid(4.0))
+            .with_line_break_mode(LineBreaking::Clip),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::dynamic(|data: &AppState, _| {
+                data.playback
+                    .now_playing
+                    .as_ref()
+                    .map(|np| queue::subtitle(&np.item))
+                    .unwrap_or_default()
+            })
+            .with_text_size(theme::TEXT_SIZE_LARGE)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::Clip),
+        )
+}
+
+fn controls_widget() -> impl Widget<AppState> {
+    let lyrics_button = Label::new("Lyrics")
+        .padding((theme::grid(1.5), theme::grid(0.5)))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .active(|data: &AppState, _| data.config.now_playing_lyrics)
+        .on_left_click(|ctx, _, data: &mut AppState, _| {
+            data.config.now_playing_lyrics = !data.config.now_playing_lyrics;
+            data.config.save();
+            if data.config.now_playing_lyrics {
+                if let Some(np) = &data.playback.now_playing {
+                    ctx.submit_command(lyrics::SHOW_LYRICS.with(np.clone()));
+                }
+            }
+        });
+
+    let visualizer_button = Label::new("Visualizer")
+        .padding((theme::grid(1.5), theme::grid(0.5)))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .active(|data: &AppState, _| data.config.now_playing_visualizer)
+        .on_left_click(|_, _, data: &mut AppState, _| {
+            data.config.now_playing_visualizer = !data.config.now_playing_visualizer;
+            data.config.save();
+        });
+
+    let close_button = icons::CLOSE
+        .scale(theme::ICON_SIZE_SMALL)
+        .padding(theme::grid(1.0))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, _, _| ctx.submit_command(cmd::TOGGLE_NOW_PLAYING));
+
+    Flex::row()
+        .with_child(playback::transport_widget().lens(AppState::playback))
+        .with_flex_spacer(1.0)
+        .with_child(visualizer_button)
+        .with_default_spacer()
+        .with_child(lyrics_button)
+        .with_default_spacer()
+        .with_child(close_button)
+        .expand_width()
+}
+
+fn up_next_widget() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Label::new("Up Next")
+                .with_font(theme::UI_FONT_MEDIUM)
+                .with_text_size(theme::TEXT_SIZE_LARGE)
+                .padding((0.0, 0.0, 0.0, theme::grid(1.0))),
+        )
+        .with_child(Either::new(
+            |entries: &Vector<QueueEntry>, _| entries.is_empty(),
+            Label::new("Nothing is queued up.").with_text_color(theme::PLACEHOLDER_COLOR),
+            List::new(|| up_next_entry_widget().lens(QueueEntry::item)),
+        ))
+        .lens(AppState::playback.then(Map::new(
+            |playback: &Playback| {
+                playback
+                    .up_next
+                    .iter()
+                    .take(UP_NEXT_SHOWN)
+                    .cloned()
+                    .collect()
+            },
+            |_, _| {},
+        )))
+}
+
+fn up_next_entry_widget() -> impl Widget<Playable> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Label::dynamic(|item: &Playable, _| item.name().to_string())
+                .with_line_break_mode(LineBreaking::Clip),
+        )
+        .with_child(
+            Label::dynamic(|item: &Playable, _| queue::subtitle(item))
+                .with_text_size(theme::TEXT_SIZE_SMALL)
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .with_line_break_mode(LineBreaking::Clip),
+        )
+        .padding((0.0, theme::grid(0.5)))
+}
+
+/// Takes the focus when the window opens, so that the keybinds work right
+/// away.
+struct NowPlayingController;
+
+impl<W: Widget<AppState>> Controller<AppState, W> for NowPlayingController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        if let Event::WindowConnected = event {
+            ctx.request_focus();
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
+/// Bars of the spectrum of the audio that is playing, read from the analysis
+/// of the player.  The analysis runs only while the visualizer is turned on.
+struct Visualizer {
+    levels: [f32; SPECTRUM_BANDS],
+    timer: TimerToken,
+}
+
+impl Visualizer {
+    fn new() -> Self {
+        Self {
+            levels: [0.0; SPECTRUM_BANDS],
+            timer: TimerToken::INVALID,
+        }
+    }
+
+    /// Turn the analysis on or off, returns whether it is on.
+    fn set_enabled(&mut self, enabled: bool) -> bool {
+        Spectrum::global().set_enabled(enabled);
+        if !enabled {
+            self.levels = [0.0; SPECTRUM_BANDS];
+        }
+        enabled
+    }
+}
+
+impl Widget<AppState> for Visualizer {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, _env: &Env) {
+        if let Event::Timer(token) = event {
+            if *token == self.timer && data.config.now_playing_visualizer {
+                // The bands stay put while paused, so let the bars fall.
+                let playing = data.playback.state == PlaybackState::Playing;
+                let bands = Spectrum::global().frame().bands;
+                for (level, band) in self.levels.iter_mut().zip(bands) {
+                    let target = if playing { band } else { 0.0 };
+                    *level = target.max(*level * VISUALIZER_FALL);
+                }
+                ctx.request_paint();
+                self.timer = ctx.request_timer(VISUALIZER_TICK);
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &AppState,
+        _env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.set_enabled(data.config.now_playing_visualizer) {
+                self.timer = ctx.request_timer(VISUALIZER_TICK);
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, _env: &Env) {
+        let enabled = data.config.now_playing_visualizer;
+        if old_data.config.now_playing_visualizer != enabled {
+            if self.set_enabled(enabled) {
+                self.timer = ctx.request_timer(VISUALIZER_TICK);
+            }
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &AppState,
+        _env: &Env,
+    ) -> Size {
+        if data.config.now_playing_visualizer {
+            Size::new(bc.max().width, VISUALIZER_HEIGHT + theme::grid(3.0))
+        } else {
+            Size::new(bc.max().width, 0.0)
+        }
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
+        if !data.config.now_playing_visualizer {
+            return;
+        }
+        let slot = ctx.size().width / SPECTRUM_BANDS as f64;
+        let color = env.get(theme::GREY_400);
+        for (index, level) in self.levels.iter().enumerate() {
+            let height = VISUALIZER_HEIGHT * *level as f64;
+            let left = index as f64 * slot;
+            let bar = Rect::new(
+                left + 1.0,
+                VISUALIZER_HEIGHT - height,
+                left + slot - 1.0,
+                VISUALIZER_HEIGHT,
+            );
+            ctx.fill(bar.to_rounded_rect(2.0), &color);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use druid::{
+    im::Vector,
+    lens::Map,
+    widget::{
+        prelude::*, Controller, CrossAxisAlignment, Either, Flex, Label, List, Scroll, TextBox,
+    },
+    KbKey, Selector, WidgetExt,
+};
+
+use crate::{
+    cmd,
+    controller::InputController,
+    data::{AppState, CommandPalette, KeybindAction, Nav, PaletteEntry},
+    ui::theme,
+    widget::{Empty, MyWidgetExt},
+};
+
+/// Run the result at the index.
+const RUN_RESULT: Selector<usize> = Selector::new("app.palette.run-result");
+
+/// Recent searches listed in the palette.
+const RECENT_SEARCHES: usize = 10;
+
+/// Result together with its index, and whether it is selected.
+type PaletteItem = (usize, PaletteEntry, bool);
+
+pub fn palette_widget() -> impl Widget<AppState> {
+    let input_id = WidgetId::next();
+
+    let input = TextBox::new()
+        .with_placeholder("Run a command, or go to a page or playlist...")
+        .controller(InputController::new())
+        .with_id(input_id)
+        .expand_width()
+        .lens(CommandPalette::query);
+
+    let results = Scroll::new(List::new(result_widget).lens(Map::new(
+        |palette: &CommandPalette| {
+            palette
+                .results
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(index, entry)| (index, entry, index == palette.selected))
+                .collect::<Vector<_>>()
+        },
+        |_, _| {},
+    )))
+    .vertical()
+    .fix_height(theme::grid(40.0));
+
+    let palette = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Fill)
+        .with_child(input)
+        .with_spacer(theme::grid(1.0))
+        .with_child(results)
+        .padding(theme::grid(1.0))
+        .background(theme::GREY_600)
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .border(theme::GREY_500, 1.0)
+        .fix_width(theme::grid(60.0))
+        .padding(theme::grid(1.0));
+
+    Either::new(|palette: &CommandPalette, _| palette.show, palette, Empty)
+        .lens(AppState::palette)
+        .controller(PaletteController { input_id })
+}
+
+fn result_widget() -> impl Widget<PaletteItem> {
+    let title = Label::dynamic(|(_, entry, _): &PaletteItem, _| entry.title.to_string())
+        .with_text_size(theme::TEXT_SIZE_SMALL);
+
+    let detail = Label::dynamic(|(_, entry, _): &PaletteItem, _| {
+        if entry.detail.is_empty() {
+            entry.category.to_string()
+        } else {
+            format!("{} · {}", entry.category, entry.detail)
+        }
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR);
+
+    Flex::row()
+        .with_flex_child(title.expand_width(), 1.0)
+        .with_default_spacer()
+        .with_child(detail)
+        .padding(theme::grid(0.75))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .active(|(_, _, selected): &PaletteItem, _| *selected)
+        .on_left_click(|ctx, _, (index, _, _): &mut PaletteItem, _| {
+            ctx.submit_command(RUN_RESULT.with(*index));
+        })
+        .on_update(|ctx, (_, _, was_selected), (_, _, selected), _| {
+            if *selected && !was_selected {
+                ctx.scroll_to_view();
+            }
+        })
+}
+
+/// Everything the palette can run: the keybind actions, the pages, the
+/// playlists, the recent searches, and the entries the controllers
+/// registered.
+fn entries(data: &AppState) -> Vec<PaletteEntry> {
+    let keys = |action| {
+        data.config
+            .keybinds
+            .get(action)
+            .map(|combination| combination.to_string())
+            .unwrap_or_default()
+    };
+    let pages = [
+        (Nav::Home, Some(KeybindAction::GoHome)),
+        (Nav::Browse, None),
+        (Nav::NewReleases, None),
+        (Nav::SavedTracks, Some(KeybindAction::GoSavedTracks)),
+        (Nav::SavedAlbums, Some(KeybindAction::GoSavedAlbums)),
+        (Nav::Shows, Some(KeybindAction::GoShows)),
+        (Nav::SavedEpisodes, None),
+        (Nav::LocalFiles, None),
+        (Nav::History, None),
+        (Nav::Stats, None),
+    ];
+    let page_actions: Vec<KeybindAction> = pages.iter().filter_map(|(_, action)| *action).collect();
+
+    let mut entries: Vec<PaletteEntry> = pages
+        .into_iter()
+        .map(|(nav, action)| {
+            PaletteEntry::new("Go to", nav.full_title(), cmd::NAVIGATE.with(nav))
+                .with_detail(action.map(keys).unwrap_or_default())
+        })
+        .collect();
+    entries.extend(
+        KeybindAction::ALL
+            .into_iter()
+            .filter(|action| {
+                // The palette has the focus, so there is no focused row to queue.
+                !matches!(
+                    action,
+                    KeybindAction::CommandPalette | KeybindAction::QueueFocusedRow
+                ) && !page_actions.contains(action)
+            })
+            .map(|action| {
+                PaletteEntry::new(
+                    action.category().label(),
+                    action.label(),
+                    cmd::RUN_KEYBIND.with(action),
+                )
+                .with_detail(keys(action))
+            }),
+    );
+    entries.extend(data.palette.registered.iter().cloned());
+    if let Some(playlists) = data.library.playlists.resolved() {
+        entries.extend(playlists.iter().map(|playlist| {
+            PaletteEntry::new(
+                "Playlist",
+                playlist.name.clone(),
+                cmd::NAVIGATE.with(Nav::PlaylistDetail(playlist.link())),
+            )
+        }));
+    }
+    entries.extend(
+        data.config
+            .search_history
+            .iter()
+            .take(RECENT_SEARCHES)
+            .map(|query| {
+                PaletteEntry::new(
+                    "Recent search",
+                    query.clone(),
+                    cmd::NAVIGATE.with(Nav::SearchResults(query.clone())),
+                )
+            }),
+    );
+    entries
+}
+
+struct PaletteController {
+    input_id: WidgetId,
+}
+
+impl PaletteController {
+    fn run_selected(&self, ctx: &mut EventCtx, data: &mut AppState) {
+        if let Some(entry) = data.palette.selected_entry().cloned() {
+            data.palette.close();
+            ctx.submit_command(entry.command);
+        }
+    }
+}
+
+impl<W> Controller<AppState, W> for PaletteController
+where
+    W: Widget<AppState>,
+{
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(cmd::TOGGLE_PALETTE) => {
+                if data.palette.show {
+                    data.palette.close();
+                } else {
+                    data.palette.open();
+                    data.palette.update_results(entries(data));
+                    ctx.submit_command(cmd::SET_FOCUS.to(self.input_id));
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(RUN_RESULT) => {
+                data.palette.selected = *cmd.get_unchecked(RUN_RESULT);
+                self.run_selected(ctx, data);
+                ctx.set_handled();
+            }
+            Event::KeyDown(key)
+                if data.palette.show
+                    && matches!(
+                        key.key,
+                        KbKey::Escape | KbKey::ArrowUp | KbKey::ArrowDown | KbKey::Enter
+                    ) =>
+            {
+                match key.key {
+                    KbKey::Escape => data.palette.close(),
+                    KbKey::ArrowUp => data.palette.select_previous(),
+                    KbKey::ArrowDown => data.palette.select_next(),
+                    _ => self.run_selected(ctx, data),
+                }
+                ctx.set_handled();
+            }
+            _ => {
+                // Typed or pasted into the query.
+                let query = data.palette.query.clone();
+                child.event(ctx, event, data, env);
+                if data.palette.show && data.palette.query != query {
+                    data.palette.update_results(entries(data));
+                }
+            }
+        }
+    }
+}
@@ -5,25 +5,27 @@ use druid::{
     kurbo::Line,
     lens::Map,
     piet::StrokeStyle,
-    widget::{Controller, ControllerHost, List, ListIter, Painter, ViewSwitcher},
-    Data, Env, Event, EventCtx, Lens, RenderContext, Selector, Widget, WidgetExt,
+    widget::{
+        prelude::*, Controller, ControllerHost, CrossAxisAlignment, Either, Flex, Label, List,
+        ListIter, Painter, ViewSwitcher,
+    },
+    Data, Insets, KbKey, Lens, Point, RenderContext, Selector, WidgetExt, WidgetPod,
 };
 
 use crate::{
     cmd,
+    controller::DragContext,
     data::{
-        ArtistTracks, CommonCtx, FindQuery, MatchFindQuery, Playable, PlaybackOrigin,
-        PlaybackPayload, PlaylistTracks, Recommendations, SavedTracks, SearchResults, ShowEpisodes,
+        ArtistTracks, CommonCtx, FindQuery, GeneratedPlaylist, KeybindAction, ListeningHistory,
+        LocalFiles, MatchFindQuery, Playable, PlaybackOrigin, PlaybackPayload, PlaylistTracks,
+        QueueEntry, Recommendations, SavedEpisodes, SavedTracks, SearchResults, ShowEpisodes,
         Track, WithCtx,
     },
     ui::theme,
+    widget::Empty,
 };
 
-use super::{
-    episode,
-    find::{Find, Findable},
-    track,
-};
+use super::{episode, find::Findable, track};
 
 #[derive(Copy, Clone)]
 pub struct Display {
@@ -31,27 +33,17 @@ pub struct Display {
 }
 
 pub fn list_widget<T>(display: Display) -> impl Widget<WithCtx<T>>
-where
-    T: PlayableIter + Data,
-{
-    ControllerHost::new(List::new(move || playable_widget(display)), PlayController)
-}
-
-pub fn list_widget_with_find<T>(
-    display: Display,
-    selector: Selector<Find>,
-) -> impl Widget<WithCtx<T>>
 where
     T: PlayableIter + Data,
 {
     ControllerHost::new(
-        List::new(move || Findable::new(playable_widget(display), selector)),
+        List::new(move || Findable::new(playable_widget(display))),
         PlayController,
     )
 }
 
 fn playable_widget(display: Display) -> impl Widget<PlayRow<Playable>> {
-    ViewSwitcher::new(
+    let row = KeyboardRow::new(ViewSwitcher::new(
         |row: &PlayRow<Playable>, _| mem::discriminant(&row.item),
         move |_, row: &PlayRow<Playable>, _| match row.item.clone() {
             // TODO: Do the lenses some other way.
@@ -74,9 +66,157 @@ fn playable_widget(display: Display) -> impl Widget<PlayRow<Playable>> {
                     .boxed()
             }
         },
+    ));
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(group_header_widget())
+        .with_child(row)
+}
+
+fn group_header_widget() -> impl Widget<PlayRow<Playable>> {
+    Either::new(
+        |row: &PlayRow<Playable>, _| row.group_header.is_some(),
+        Label::dynamic(|row: &PlayRow<Playable>, _| {
+            row.group_header.as_deref().unwrap_or_default().to_string()
+        })
+        .with_font(theme::UI_FONT_MEDIUM)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .padding(Insets::new(
+            theme::grid(1.0),
+            theme::grid(2.0),
+            0.0,
+            theme::grid(1.0),
+        )),
+        Empty,
     )
 }
 
+/// Sent by a focused row to move the focus to the row at the given position
+/// of the same list.
+const MOVE_ROW_FOCUS: Selector<usize> = Selector::new("app.play-row.move-focus");
+
+/// Sent by `PlayController` to the rows of its list, the row at the given
+/// position takes the focus.
+const FOCUS_ROW: Selector<usize> = Selector::new("app.play-row.focus");
+
+/// Makes a row focusable, by clicking it or moving the focus with the arrow
+/// keys from a neighbouring row.  The focus stays in the list.  A focused row
+/// plays on Enter, and `KeybindAction::QueueFocusedRow` adds it to the queue
+/// and moves on to the next row, so that a run of tracks can be queued by
+/// holding the key down.
+struct KeyboardRow<W> {
+    inner: WidgetPod<PlayRow<Playable>, W>,
+}
+
+impl<W: Widget<PlayRow<Playable>>> KeyboardRow<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner: WidgetPod::new(inner),
+        }
+    }
+}
+
+impl<W: Widget<PlayRow<Playable>>> Widget<PlayRow<Playable>> for KeyboardRow<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, row: &mut PlayRow<Playable>, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                ctx.request_focus();
+            }
+            Event::KeyDown(key) if ctx.has_focus() && !key.mods.ctrl() && !key.mods.meta() => {
+                match &key.key {
+                    KbKey::Enter => {
+                        ctx.submit_notification(cmd::PLAY.with(row.position));
+                        ctx.set_handled();
+                    }
+                    KbKey::ArrowDown => {
+                        ctx.submit_notification(MOVE_ROW_FOCUS.with(row.position + 1));
+                        ctx.set_handled();
+                    }
+                    KbKey::ArrowUp => {
+                        if let Some(position) = row.position.checked_sub(1) {
+                            ctx.submit_notification(MOVE_ROW_FOCUS.with(position));
+                        }
+                        ctx.set_handled();
+                    }
+                    _ => {}
+                }
+            }
+            Event::Command(cmd)
+                if ctx.has_focus()
+                    && cmd.get(cmd::RUN_KEYBIND) == Some(&KeybindAction::QueueFocusedRow) =>
+            {
+                ctx.submit_command(cmd::QUEUE_ITEM_QUIETLY.with(QueueEntry {
+                    item: row.item.clone(),
+                    origin: row.origin.as_ref().clone(),
+                }));
+                ctx.submit_notification(MOVE_ROW_FOCUS.with(row.position + 1));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.get(FOCUS_ROW) == Some(&row.position) => {
+                ctx.request_focus();
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+        self.inner.event(ctx, event, row, env);
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        row: &PlayRow<Playable>,
+        env: &Env,
+    ) {
+        match event {
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(focused) => {
+                if *focused {
+                    ctx.scroll_to_view();
+                }
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+        self.inner.lifecycle(ctx, event, row, env);
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        _old_row: &PlayRow<Playable>,
+        row: &PlayRow<Playable>,
+        env: &Env,
+    ) {
+        self.inner.update(ctx, row, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        row: &PlayRow<Playable>,
+        env: &Env,
+    ) -> Size {
+        let size = self.inner.layout(ctx, bc, row, env);
+        self.inner.set_origin(ctx, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, row: &PlayRow<Playable>, env: &Env) {
+        self.inner.paint(ctx, row, env);
+        if ctx.has_focus() {
+            let rect = ctx
+                .size()
+                .to_rect()
+                .inset(-0.5)
+                .to_rounded_rect(theme::BUTTON_BORDER_RADIUS);
+            ctx.stroke(rect, &env.get(theme::BLUE_200), 1.0);
+        }
+    }
+}
+
 pub fn is_playing_marker_widget() -> impl Widget<bool> {
     Painter::new(|ctx, is_playing, env| {
         const STYLE: StrokeStyle = StrokeStyle::new().dash_pattern(&[1.0, 2.0]);
@@ -99,6 +239,9 @@ pub struct PlayRow<T> {
     pub origin: Arc<PlaybackOrigin>,
     pub position: usize,
     pub is_playing: bool,
+    /// Title of the group of rows starting with this one.
+    #[data(same_fn = "PartialEq::eq")]
+    pub group_header: Option<Arc<str>>,
 }
 
 impl<T> PlayRow<T> {
@@ -109,10 +252,17 @@ impl<T> PlayRow<T> {
             origin: self.origin.clone(),
             position: self.position,
             is_playing: self.is_playing,
+            group_header: self.group_header.clone(),
         }
     }
 }
 
+impl<T> DragContext for PlayRow<T> {
+    fn dragged_track(&self) -> Option<&Arc<Track>> {
+        self.ctx.dragged_track.as_ref()
+    }
+}
+
 impl MatchFindQuery for PlayRow<Playable> {
     fn matches_query(&self, q: &FindQuery) -> bool {
         match &self.item {
@@ -134,6 +284,32 @@ pub trait PlayableIter {
     fn origin(&self) -> PlaybackOrigin;
     fn count(&self) -> usize;
     fn for_each(&self, cb: impl FnMut(Playable, usize));
+
+    /// Title of a group of items starting at `position`, shown above it.
+    fn group_header(&self, _position: usize) -> Option<Arc<str>> {
+        None
+    }
+
+    /// Shown as a table, with only the tracks that pass the filters in
+    /// `CommonCtx::track_filters`.
+    fn is_table(&self) -> bool {
+        false
+    }
+}
+
+/// Items of the list that are shown, with their position among the shown
+/// items, and among all of them.
+fn for_each_shown<T: PlayableIter>(data: &WithCtx<T>, mut cb: impl FnMut(Playable, usize, usize)) {
+    let filters = &data.ctx.track_filters;
+    let filtered = data.data.is_table() && !filters.is_empty();
+    let mut shown = 0;
+    data.data.for_each(|item, position| {
+        if filtered && !matches!(&item, Playable::Track(track) if filters.matches(track)) {
+            return;
+        }
+        cb(item, shown, position);
+        shown += 1;
+    });
 }
 
 // This should change to a more specific name as it could be confusing for others
@@ -159,6 +335,10 @@ impl PlayableIter for PlaylistTracks {
         PlaybackOrigin::Playlist(self.link())
     }
 
+    fn is_table(&self) -> bool {
+        true
+    }
+
     fn for_each(&self, mut cb: impl FnMut(Playable, usize)) {
         for (position, track) in self.tracks.iter().enumerate() {
             cb(Playable::Track(track.to_owned()), position);
@@ -191,6 +371,66 @@ impl PlayableIter for SavedTracks {
         PlaybackOrigin::Library
     }
 
+    fn group_header(&self, position: usize) -> Option<Arc<str>> {
+        self.month_header(position)
+    }
+
+    fn is_table(&self) -> bool {
+        true
+    }
+
+    fn for_each(&self, mut cb: impl FnMut(Playable, usize)) {
+        for (position, track) in self.tracks.iter().enumerate() {
+            cb(Playable::Track(track.to_owned()), position);
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.tracks.len()
+    }
+}
+
+impl PlayableIter for LocalFiles {
+    fn origin(&self) -> PlaybackOrigin {
+        PlaybackOrigin::LocalFiles
+    }
+
+    fn for_each(&self, mut cb: impl FnMut(Playable, usize)) {
+        for (position, track) in self.tracks.iter().enumerate() {
+            cb(Playable::Track(track.to_owned()), position);
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.tracks.len()
+    }
+}
+
+impl PlayableIter for ListeningHistory {
+    fn origin(&self) -> PlaybackOrigin {
+        PlaybackOrigin::History
+    }
+
+    fn group_header(&self, position: usize) -> Option<Arc<str>> {
+        self.day_header(position)
+    }
+
+    fn for_each(&self, mut cb: impl FnMut(Playable, usize)) {
+        for (position, play) in self.plays.iter().enumerate() {
+            cb(Playable::Track(play.track.to_owned()), position);
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.plays.len()
+    }
+}
+
+impl PlayableIter for GeneratedPlaylist {
+    fn origin(&self) -> PlaybackOrigin {
+        PlaybackOrigin::History
+    }
+
     fn for_each(&self, mut cb: impl FnMut(Playable, usize)) {
         for (position, track) in self.tracks.iter().enumerate() {
             cb(Playable::Track(track.to_owned()), position);
@@ -250,18 +490,35 @@ impl PlayableIter for ShowEpisodes {
     }
 }
 
+impl PlayableIter for SavedEpisodes {
+    fn origin(&self) -> PlaybackOrigin {
+        PlaybackOrigin::SavedEpisodes
+    }
+
+    fn for_each(&self, mut cb: impl FnMut(Playable, usize)) {
+        for (position, episode) in self.episodes.iter().enumerate() {
+            cb(Playable::Episode(episode.to_owned()), position);
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.episodes.len()
+    }
+}
+
 impl<T> ListIter<PlayRow<Playable>> for WithCtx<T>
 where
     T: PlayableIter + Data,
 {
     fn for_each(&self, mut cb: impl FnMut(&PlayRow<Playable>, usize)) {
         let origin = Arc::new(self.data.origin());
-        self.data.for_each(|item, position| {
+        for_each_shown(self, |item, position, index| {
             cb(
                 &PlayRow {
                     is_playing: self.ctx.is_playing(&item),
                     ctx: self.ctx.to_owned(),
                     origin: origin.clone(),
+                    group_header: self.data.group_header(index),
                     item,
                     position,
                 },
@@ -272,12 +529,13 @@ where
 
     fn for_each_mut(&mut self, mut cb: impl FnMut(&mut PlayRow<Playable>, usize)) {
         let origin = Arc::new(self.data.origin());
-        self.data.for_each(|item, position| {
+        for_each_shown(self, |item, position, index| {
             cb(
                 &mut PlayRow {
                     is_playing: self.ctx.is_playing(&item),
                     ctx: self.ctx.to_owned(),
                     origin: origin.clone(),
+                    group_header: self.data.group_header(index),
                     item,
                     position,
                 },
@@ -287,7 +545,13 @@ where
     }
 
     fn data_len(&self) -> usize {
-        self.data.count()
+        if self.data.is_table() && !self.ctx.track_filters.is_empty() {
+            let mut len = 0;
+            for_each_shown(self, |_, _, _| len += 1);
+            len
+        } else {
+            self.data.count()
+        }
     }
 }
 
@@ -310,14 +574,20 @@ where
             Event::Notification(note) => {
                 if let Some(position) = note.get(cmd::PLAY) {
                     let mut items = Vector::new();
-                    data.data.for_each(|item, _| items.push_back(item));
+                    for_each_shown(data, |item, _, _| items.push_back(item));
                     let payload = PlaybackPayload {
                         items,
                         origin: data.data.origin(),
                         position: position.to_owned(),
+                        seek: None,
                     };
                     ctx.submit_command(cmd::PLAY_TRACKS.with(payload));
                     ctx.set_handled();
+                } else if let Some(position) = note.get(MOVE_ROW_FOCUS) {
+                    // Only the rows of this list hear it, so the focus stays
+                    // on the last row instead of moving on to other widgets.
+                    ctx.submit_command(FOCUS_ROW.with(*position).to(ctx.widget_id()));
+                    ctx.set_handled();
                 }
             }
             _ => child.event(ctx, event, data, env),
@@ -1,45 +1,371 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use druid::{
+    im::Vector,
     kurbo::{Affine, BezPath},
-    widget::{CrossAxisAlignment, Either, Flex, Label, LineBreaking, Spinner, ViewSwitcher},
+    widget::{
+        Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, Slider, Spinner,
+        ViewSwitcher,
+    },
     BoxConstraints, Cursor, Data, Env, Event, EventCtx, LayoutCtx, LensExt, LifeCycle,
-    LifeCycleCtx, MouseButton, PaintCtx, Point, Rect, RenderContext, Size, UpdateCtx, Widget,
-    WidgetExt, WidgetPod,
+    LifeCycleCtx, LocalizedString, Menu, MenuItem, MouseButton, PaintCtx, Point, Rect,
+    RenderContext, Selector, Size, TextLayout, TimerToken, UpdateCtx, Widget, WidgetExt, WidgetPod,
 };
 use itertools::Itertools;
+use psst_core::item_id::ItemIdType;
 
 use crate::{
-    cmd::{self, ADD_TO_QUEUE, SHOW_ARTWORK, TOGGLE_LYRICS},
+    cmd::{self, SHOW_ARTWORK, TOGGLE_LYRICS, TOGGLE_NOW_PLAYING},
     controller::PlaybackController,
     data::{
-        AppState, AudioAnalysis, Episode, NowPlaying, Playable, PlayableMatcher, Playback,
-        PlaybackOrigin, PlaybackState, QueueBehavior, ShowLink, Track,
+        AppState, AudioAnalysis, ConnectDevice, Episode, Nav, NowPlaying, Playable,
+        PlayableMatcher, Playback, PlaybackBarLayout, PlaybackClock, PlaybackOrigin,
+        PlaybackPayload, PlaybackState, RemoteCommand, RemotePlayback, Repeat, ShowLink, Shuffle,
+        Track,
     },
+    error::Error,
+    format,
+    webapi::WebApi,
     widget::{
         icons::{self, SvgIcon},
-        Empty, Maybe, MyWidgetExt, RemoteImage,
+        Empty, Maybe, MyWidgetExt, RemoteImage, SliderKeys, SliderSteps,
     },
 };
 
 use super::{episode, library, theme, track, utils};
 
+/// Fetch the Connect devices and show them in a menu at the given window position.
+const SHOW_DEVICES: Selector<Point> = Selector::new("app.playback.show-devices");
+const TRANSFER_PLAYBACK_HERE: Selector = Selector::new("app.playback.transfer-here");
+const CONTROL_REMOTE: Selector = Selector::new("app.playback.control-remote");
+const STOP_CONTROLLING_REMOTE: Selector = Selector::new("app.playback.stop-controlling-remote");
+const REMOTE_COMMAND: Selector<(Arc<str>, RemoteCommand)> =
+    Selector::new("app.playback.remote-command");
+/// Fetch the playback state of the controlled device.
+const REFRESH_REMOTE: Selector = Selector::new("app.playback.refresh-remote");
+/// Refresh the controlled device after `REMOTE_STATE_DELAY`.
+const REFRESH_REMOTE_SOON: Selector = Selector::new("app.playback.refresh-remote-soon");
+
+/// The Web API takes a moment to reflect a command in the playback state.
+const REMOTE_STATE_DELAY: Duration = Duration::from_millis(300);
+/// How often the controlled device is asked for its playback state, to
+/// follow changes made on it.
+const REMOTE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Arrow key steps of the seek bar, in seconds.
+const SEEK_STEPS: SliderSteps = SliderSteps {
+    fine: 1.0,
+    normal: 5.0,
+    coarse: 30.0,
+};
+
+/// The playback bar is compact when narrower than this, whatever the layout
+/// in the preferences.
+const COMPACT_BAR_WIDTH: f64 = theme::GRID * 90.0;
+
 pub fn panel_widget() -> impl Widget<AppState> {
-    let seek_bar = Maybe::or_empty(SeekBar::new).lens(Playback::now_playing);
-    let item_info = Maybe::or_empty(playing_item_widget).lens(Playback::now_playing);
-    let controls = Either::new(
-        |playback, _| playback.now_playing.is_some(),
-        player_widget(),
-        Empty,
+    let seek_bar = SliderKeys::new(
+        Maybe::or_empty(SeekBar::new).lens(AppState::playback.then(Playback::now_playing)),
+        SEEK_STEPS,
+        seek_by,
+    );
+    let bar = AdaptiveBar::new(
+        |data: &AppState| data.config.playback_bar_layout == PlaybackBarLayout::Compact,
+        bar_widget(false).lens(AppState::playback),
+        bar_widget(true).lens(AppState::playback),
     );
     Flex::column()
         .with_child(seek_bar)
-        .with_child(BarLayout::new(item_info, controls))
-        .lens(AppState::playback)
+        .with_child(bar)
         .controller(PlaybackController::new())
-        .on_command(ADD_TO_QUEUE, |_, _, data| {
-            data.info_alert("Track added to queue.")
+        .controller(RemotePolling::new())
+        .on_command_async(
+            cmd::FIND_PLAYABLE_VERSION,
+            |track| WebApi::global().find_playable_version(&track),
+            |_, _, _| {},
+            |ctx, data, (track, result)| match result {
+                Ok(Some(found)) => match &found.album {
+                    Some(album) => {
+                        data.info_alert(format!("Found a playable version on \"{}\".", album.name));
+                        ctx.submit_command(
+                            cmd::NAVIGATE.with(Nav::AlbumDetail(album.clone(), Some(found.id))),
+                        );
+                    }
+                    None => data.info_alert("Found a playable version, but not its album."),
+                },
+                Ok(None) => data.info_alert(format!(
+                    "No playable version of \"{}\" was found.",
+                    track.name
+                )),
+                Err(err) => data.error_alert(format!("Failed to search: {err}")),
+            },
+        )
+        .on_command_async(
+            SHOW_DEVICES,
+            |_| WebApi::global().get_devices(),
+            |_, _, _| {},
+            |ctx, data, (position, result): (Point, Result<Vector<ConnectDevice>, Error>)| {
+                match result {
+                    Ok(devices) => ctx.show_context_menu(
+                        devices_menu(&devices, data.playback.remote.is_some()),
+                        position,
+                    ),
+                    Err(err) => data.error_alert(format!("Failed to load devices: {err}")),
+                }
+            },
+        )
+        .on_command_async(
+            TRANSFER_PLAYBACK_HERE,
+            |_| transfer_playback_here(),
+            |_, _, _| {},
+            |ctx, data, (_, result)| match result {
+                Ok(payload) => {
+                    data.playback.remote = None;
+                    ctx.submit_command(cmd::PLAY_TRACKS.with(payload));
+                }
+                Err(err) => data.error_alert(format!("Failed to transfer playback: {err}")),
+            },
+        )
+        .on_command_async(
+            CONTROL_REMOTE,
+            |_| WebApi::global().get_playback_state(),
+            |_, _, _| {},
+            |_, data, (_, result)| match result {
+                Ok(Some(remote)) => data.playback.remote = Some(remote),
+                Ok(None) => data.info_alert("Nothing is playing on other devices."),
+                Err(err) => data.error_alert(format!("Failed to connect to device: {err}")),
+            },
+        )
+        .on_command(STOP_CONTROLLING_REMOTE, |_, _, data| {
+            data.playback.remote = None;
         })
+        .on_command_async(
+            REMOTE_COMMAND,
+            |(device_id, command)| send_remote_command(&device_id, command),
+            |_, _, _| {},
+            |ctx, data, (_, result)| match result {
+                Ok(()) => ctx.submit_command(REFRESH_REMOTE_SOON),
+                Err(err) => data.error_alert(format!("Failed to control device: {err}")),
+            },
+        )
+        .on_command_async(
+            REFRESH_REMOTE,
+            |_| WebApi::global().get_playback_state(),
+            |_, _, _| {},
+            |_, data, (_, result)| match result {
+                // Stopped controlling the device in the meantime.
+                _ if data.playback.remote.is_none() => {}
+                Ok(remote) => data.playback.remote = remote,
+                Err(err) => log::warn!("failed to refresh the remote playback: {err}"),
+            },
+        )
+}
+
+fn bar_widget(compact: bool) -> impl Widget<Playback> {
+    let item_info = Either::new(
+        |playback: &Playback, _| playback.now_playing.is_some(),
+        Maybe::or_empty(move || {
+            if compact {
+                compact_playing_item_widget().boxed()
+            } else {
+                playing_item_widget().boxed()
+            }
+        })
+        .lens(Playback::now_playing),
+        Maybe::or_empty(move || {
+            if compact {
+                compact_remote_item_widget().boxed()
+            } else {
+                remote_item_widget().boxed()
+            }
+        })
+        .lens(Playback::remote),
+    );
+    let padding = if compact {
+        theme::grid(1.0)
+    } else {
+        theme::grid(2.0)
+    };
+    let controls = ViewSwitcher::new(
+        |playback: &Playback, _| (playback.now_playing.is_some(), playback.remote.is_some()),
+        move |&(playing, remote), _, _| match (playing, remote) {
+            (true, _) if compact => compact_player_widget().boxed(),
+            (true, _) => player_widget().boxed(),
+            (false, true) => Maybe::or_empty(remote_player_widget)
+                .lens(Playback::remote)
+                .padding(padding)
+                .boxed(),
+            (false, false) => devices_button_widget().padding(padding).boxed(),
+        },
+    );
+    BarLayout::new(item_info, controls)
+}
+
+fn devices_menu(devices: &Vector<ConnectDevice>, controlling_remote: bool) -> Menu<AppState> {
+    let mut menu = Menu::empty();
+    for device in devices {
+        menu = menu.entry(
+            MenuItem::new(format!("{} ({})", device.name, device.kind))
+                .selected(device.is_active)
+                .enabled(false),
+        );
+    }
+    if devices.is_empty() {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-no-devices")
+                    .with_placeholder("No Other Devices Found"),
+            )
+            .enabled(false),
+        );
+    }
+    let has_active_device = devices.iter().any(|device| device.is_active);
+    menu = menu.separator().entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-transfer-playback-here")
+                .with_placeholder("Transfer Playback Here"),
+        )
+        .command(TRANSFER_PLAYBACK_HERE)
+        .enabled(has_active_device),
+    );
+    if controlling_remote {
+        menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-stop-controlling-remote")
+                    .with_placeholder("Stop Controlling Device"),
+            )
+            .command(STOP_CONTROLLING_REMOTE),
+        )
+    } else {
+        menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-control-remote")
+                    .with_placeholder("Control Active Device"),
+            )
+            .command(CONTROL_REMOTE)
+            .enabled(has_active_device),
+        )
+    }
+}
+
+fn send_remote_command(device_id: &str, command: RemoteCommand) -> Result<(), Error> {
+    let webapi = WebApi::global();
+    match command {
+        RemoteCommand::Resume => webapi.resume_device(device_id),
+        RemoteCommand::Pause => webapi.pause_device(device_id),
+        RemoteCommand::Previous => webapi.skip_device_to_previous(device_id),
+        RemoteCommand::Next => webapi.skip_device_to_next(device_id),
+        RemoteCommand::SetVolume(percent) => webapi.set_device_volume(device_id, percent),
+    }
+}
+
+/// Keeps the state of the controlled Connect device up to date, polling it
+/// while it is controlled and shortly after each command.
+struct RemotePolling {
+    timer: TimerToken,
+}
+
+impl RemotePolling {
+    fn new() -> Self {
+        Self {
+            timer: TimerToken::INVALID,
+        }
+    }
+}
+
+impl<W: Widget<AppState>> Controller<AppState, W> for RemotePolling {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::WindowConnected => {
+                self.timer = ctx.request_timer(REMOTE_POLL_INTERVAL);
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.timer = ctx.request_timer(REMOTE_POLL_INTERVAL);
+                if data.playback.remote.is_some() {
+                    ctx.submit_command(REFRESH_REMOTE);
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.is(REFRESH_REMOTE_SOON) => {
+                self.timer = ctx.request_timer(REMOTE_STATE_DELAY);
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
+/// Pause the active Connect device and build a local queue from its playback
+/// context, so playback can continue here from the same position.
+fn transfer_playback_here() -> Result<PlaybackPayload, Error> {
+    let webapi = WebApi::global();
+    let remote = webapi
+        .get_playback_state()?
+        .ok_or_else(|| Error::WebApiError("Nothing is playing on other devices".to_string()))?;
+    let item = remote.item.ok_or_else(|| {
+        Error::WebApiError("The active device is not playing a track".to_string())
+    })?;
+
+    let context_uri = remote.context.map(|context| context.uri);
+    // The kind and ID are the last two parts, also in the legacy
+    // `spotify:user:<user>:playlist:<id>` form.
+    let context = context_uri.as_deref().and_then(|uri| {
+        let mut parts = uri.rsplit(':');
+        let id = parts.next()?;
+        Some((parts.next()?, id))
+    });
+    let (origin, tracks) = match context {
+        Some(("album", id)) => {
+            let album = webapi.get_album(id)?.data;
+            (
+                PlaybackOrigin::Album(album.link()),
+                album.into_tracks_with_context(),
+            )
+        }
+        Some(("playlist", id)) => {
+            let playlist = webapi.get_playlist(id)?;
+            (
+                PlaybackOrigin::Playlist(playlist.link()),
+                webapi.get_playlist_tracks(id)?,
+            )
+        }
+        Some(("artist", id)) => {
+            let artist = webapi.get_artist(id)?;
+            (
+                PlaybackOrigin::Artist(artist.link()),
+                webapi.get_artist_top_tracks(id)?,
+            )
+        }
+        _ => (PlaybackOrigin::Home, Vector::unit(item.clone())),
+    };
+    let position = tracks
+        .iter()
+        .position(|track| track.id.0 == item.id.0)
+        .unwrap_or(0);
+
+    if let Some(device_id) = &remote.device.id {
+        if remote.is_playing {
+            webapi.pause_device(device_id)?;
+        }
+    }
+
+    let payload = PlaybackPayload {
+        origin,
+        items: tracks.into_iter().map(Playable::Track).collect(),
+        position,
+        seek: Some(Duration::from_millis(remote.progress_ms)),
+    };
+    Ok(payload)
 }
 
 fn playing_item_widget() -> impl Widget<NowPlaying> {
@@ -93,6 +419,20 @@ fn playing_item_widget() -> impl Widget<NowPlaying> {
     )
     .lens(NowPlaying::origin);
 
+    let quality = Maybe::or_empty(|| {
+        Label::raw()
+            .with_line_break_mode(LineBreaking::Clip)
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+    })
+    .lens(NowPlaying::quality);
+
+    let origin = Flex::row()
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .with_flex_child(origin, 1.0)
+        .with_spacer(theme::grid(1.0))
+        .with_child(quality);
+
     Flex::row()
         .with_child(cover_art)
         .with_flex_child(
@@ -107,21 +447,12 @@ fn playing_item_widget() -> impl Widget<NowPlaying> {
                     .on_click(|ctx, now_playing, _| {
                         ctx.submit_command(cmd::NAVIGATE.with(now_playing.origin.to_nav()));
                     })
-                    .context_menu(|now_playing| match &now_playing.item {
-                        Playable::Track(track) => track::track_menu(
-                            track,
-                            &now_playing.library,
-                            &now_playing.origin,
-                            usize::MAX,
-                        ),
-                        Playable::Episode(episode) => {
-                            episode::episode_menu(episode, &now_playing.library)
-                        }
-                    }),
+                    .context_menu(playing_item_menu),
                 1.0,
             ),
             1.0,
         )
+        .with_child(lastfm_love_widget())
         .with_child(ViewSwitcher::new(
             |now_playing: &NowPlaying, _| {
                 now_playing.item.track().is_some() && now_playing.library.saved_tracks.is_resolved()
@@ -162,10 +493,224 @@ fn playing_item_widget() -> impl Widget<NowPlaying> {
         .link()
 }
 
+/// The playing item on a single line, next to small artwork.
+fn compact_playing_item_widget() -> impl Widget<NowPlaying> {
+    let title = Label::dynamic(|now_playing: &NowPlaying, _| match &now_playing.item {
+        Playable::Track(track) => format!("{} · {}", track.name, track.artist_name()),
+        Playable::Episode(episode) => format!("{} · {}", episode.name, episode.show.name),
+    })
+    .with_line_break_mode(LineBreaking::Clip)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .on_click(|ctx, now_playing, _| {
+        ctx.submit_command(cmd::NAVIGATE.with(now_playing.origin.to_nav()));
+    })
+    .context_menu(playing_item_menu);
+
+    Flex::row()
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .with_child(cover_widget(theme::grid(4.0)))
+        .with_spacer(theme::grid(1.5))
+        .with_flex_child(title, 1.0)
+        .with_child(lastfm_love_widget())
+        .padding(theme::grid(1.0))
+        .link()
+}
+
+fn playing_item_menu(now_playing: &NowPlaying) -> Menu<AppState> {
+    match &now_playing.item {
+        Playable::Track(track) => {
+            track::track_menu(track, &now_playing.library, &now_playing.origin, usize::MAX)
+        }
+        Playable::Episode(episode) => episode::episode_menu(episode, &now_playing.library),
+    }
+}
+
+/// Heart showing whether the playing track is loved on Last.fm, shown only
+/// with a Last.fm session.
+fn lastfm_love_widget() -> impl Widget<NowPlaying> {
+    let is_loved = |now_playing: &NowPlaying| {
+        now_playing
+            .item
+            .track()
+            .is_some_and(|track| now_playing.library.is_lastfm_loved(&track.id))
+    };
+    Either::new(
+        |now_playing: &NowPlaying, _| {
+            now_playing.item.track().is_some() && now_playing.library.lastfm_loved.is_some()
+        },
+        ViewSwitcher::new(
+            move |now_playing: &NowPlaying, _| is_loved(now_playing),
+            |&loved, _, _| {
+                let color = if loved {
+                    theme::RED
+                } else {
+                    theme::PLACEHOLDER_COLOR
+                };
+                icons::HEART
+                    .scale(theme::ICON_SIZE_SMALL)
+                    .with_color(color)
+                    .boxed()
+            },
+        )
+        .on_left_click(move |ctx, _, now_playing, _| {
+            if let Some(track) = now_playing.item.track() {
+                let loved = is_loved(now_playing);
+                ctx.submit_command(cmd::LASTFM_SET_LOVED.with((track.clone(), !loved)));
+            }
+        })
+        .padding(theme::grid(1.0)),
+        Empty,
+    )
+}
+
+fn remote_item_widget() -> impl Widget<RemotePlayback> {
+    let size = theme::grid(8.0);
+    let cover_art = RemoteImage::new(
+        utils::placeholder_widget(),
+        move |remote: &RemotePlayback, _| {
+            let album = remote.item.as_ref()?.album.as_ref()?;
+            Some(album.image(size, size)?.url.clone())
+        },
+    )
+    .with_size((size, size))
+    .fix_size(size, size)
+    .clip(Size::new(size, size).to_rounded_rect(4.0));
+
+    let name = Label::dynamic(|remote: &RemotePlayback, _| {
+        remote
+            .item
+            .as_ref()
+            .map(|track| track.name.to_string())
+            .unwrap_or_else(|| "Nothing playing".to_string())
+    })
+    .with_line_break_mode(LineBreaking::Clip)
+    .with_font(theme::UI_FONT_MEDIUM);
+
+    let detail = Label::dynamic(|remote: &RemotePlayback, _| {
+        remote
+            .item
+            .as_ref()
+            .map(|track| track.artist_name().to_string())
+            .unwrap_or_default()
+    })
+    .with_line_break_mode(LineBreaking::Clip)
+    .with_text_size(theme::TEXT_SIZE_SMALL);
+
+    let device = Flex::row()
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .with_flex_child(
+            Label::dynamic(|remote: &RemotePlayback, _| {
+                format!("Playing on {}", remote.device.name)
+            })
+            .with_line_break_mode(LineBreaking::Clip)
+            .with_text_size(theme::TEXT_SIZE_SMALL),
+            1.0,
+        )
+        .with_spacer(theme::grid(0.25))
+        .with_child(icons::SPEAKER.scale(theme::ICON_SIZE_SMALL));
+
+    Flex::row()
+        .with_child(cover_art)
+        .with_spacer(theme::grid(2.0))
+        .with_flex_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(name)
+                .with_spacer(2.0)
+                .with_child(detail)
+                .with_spacer(2.0)
+                .with_child(device),
+            1.0,
+        )
+        .padding(theme::grid(1.0))
+}
+
+fn compact_remote_item_widget() -> impl Widget<RemotePlayback> {
+    Label::dynamic(|remote: &RemotePlayback, _| match &remote.item {
+        Some(track) => format!("{} · Playing on {}", track.name, remote.device.name),
+        None => format!("Nothing playing on {}", remote.device.name),
+    })
+    .with_line_break_mode(LineBreaking::Clip)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .padding(theme::grid(1.0))
+    .expand_height()
+    .center()
+}
+
+fn remote_player_widget() -> impl Widget<RemotePlayback> {
+    const VOLUME_DELAY: Duration = Duration::from_millis(300);
+
+    let play_pause = ViewSwitcher::new(
+        |remote: &RemotePlayback, _| remote.is_playing,
+        |&is_playing, _, _| {
+            let (icon, command) = if is_playing {
+                (&icons::PAUSE, RemoteCommand::Pause)
+            } else {
+                (&icons::PLAY, RemoteCommand::Resume)
+            };
+            icon.scale((theme::grid(3.0), theme::grid(3.0)))
+                .padding(theme::grid(1.0))
+                .link()
+                .circle()
+                .border(theme::GREY_500, 1.0)
+                .on_left_click(move |ctx, _, remote: &mut RemotePlayback, _| {
+                    send_remote(ctx, remote, command);
+                })
+                .boxed()
+        },
+    );
+
+    let volume = Slider::new()
+        .with_range(0.0, 1.0)
+        .fix_width(theme::grid(12.0))
+        .env_scope(|env, _| {
+            env.set(theme::BASIC_WIDGET_HEIGHT, theme::grid(1.5));
+            env.set(theme::FOREGROUND_LIGHT, env.get(theme::GREY_400));
+            env.set(theme::FOREGROUND_DARK, env.get(theme::GREY_400));
+        })
+        .with_cursor(Cursor::Pointer)
+        .lens(RemotePlayback::volume())
+        .on_debounce(VOLUME_DELAY, |ctx, remote, _| {
+            let percent = remote.device.volume_percent.unwrap_or(0);
+            send_remote(ctx, remote, RemoteCommand::SetVolume(percent));
+        });
+
+    Flex::row()
+        .with_child(
+            small_button_widget(&icons::SKIP_BACK).on_left_click(|ctx, _, remote, _| {
+                send_remote(ctx, remote, RemoteCommand::Previous);
+            }),
+        )
+        .with_default_spacer()
+        .with_child(play_pause)
+        .with_default_spacer()
+        .with_child(
+            small_button_widget(&icons::SKIP_FORWARD).on_left_click(|ctx, _, remote, _| {
+                send_remote(ctx, remote, RemoteCommand::Next);
+            }),
+        )
+        .with_default_spacer()
+        .with_child(volume)
+        .with_child(devices_button_widget())
+}
+
+fn send_remote(ctx: &mut EventCtx, remote: &RemotePlayback, command: RemoteCommand) {
+    if let Some(device_id) = &remote.device.id {
+        ctx.submit_command(REMOTE_COMMAND.with((device_id.clone(), command)));
+    }
+}
+
+fn devices_button_widget<T: Data>() -> impl Widget<T> {
+    small_button_widget(&icons::SPEAKER).on_left_click(|ctx, event, _, _| {
+        ctx.submit_command(SHOW_DEVICES.with(event.window_pos));
+    })
+}
+
 fn cover_widget(size: f64) -> impl Widget<NowPlaying> {
     RemoteImage::new(utils::placeholder_widget(), move |np: &NowPlaying, _| {
         np.cover_image_url(size, size).map(|url| url.into())
     })
+    .with_size((size, size))
     .fix_size(size, size)
     .clip(Size::new(size, size).to_rounded_rect(4.0))
     .on_left_click(|ctx, _, _, _| {
@@ -177,12 +722,16 @@ fn playback_origin_icon(origin: &PlaybackOrigin) -> &'static SvgIcon {
     match origin {
         PlaybackOrigin::Home => &icons::HOME,
         PlaybackOrigin::Library => &icons::HEART,
+        PlaybackOrigin::LocalFiles => &icons::MUSIC_NOTE,
+        PlaybackOrigin::History => &icons::MUSIC_NOTE,
+        PlaybackOrigin::SavedEpisodes => &icons::PODCAST,
         PlaybackOrigin::Album { .. } => &icons::ALBUM,
         PlaybackOrigin::Artist { .. } => &icons::ARTIST,
         PlaybackOrigin::Playlist { .. } => &icons::PLAYLIST,
         PlaybackOrigin::Show { .. } => &icons::PODCAST,
         PlaybackOrigin::Search { .. } => &icons::SEARCH,
         PlaybackOrigin::Recommendations { .. } => &icons::SEARCH,
+        PlaybackOrigin::Radio { .. } => &icons::SPEAKER,
     }
 }
 
@@ -202,19 +751,108 @@ fn player_widget() -> impl Widget<Playback> {
             }),
         )
         .with_default_spacer()
-        .with_child(queue_behavior_widget())
+        .with_child(shuffle_widget())
+        .with_child(repeat_widget())
         .with_default_spacer()
         .with_child(Maybe::or_empty(durations_widget).lens(Playback::now_playing))
         .with_child(
-            small_button_widget(&icons::MUSIC_NOTE)
-                .align_right()
-                .on_left_click(|ctx, _, _, _| {
-                    ctx.submit_command(TOGGLE_LYRICS);
-                }),
+            Flex::row()
+                .with_child(Either::new(
+                    |playback: &Playback, _| {
+                        playback
+                            .now_playing
+                            .as_ref()
+                            .is_some_and(|np| np.item.id().id_type == ItemIdType::Podcast)
+                    },
+                    podcast_speed_widget(),
+                    Empty,
+                ))
+                .with_child(Either::new(
+                    |playback: &Playback, _| {
+                        playback
+                            .now_playing
+                            .as_ref()
+                            .is_some_and(|np| !np.chapters.is_empty())
+                    },
+                    chapters_widget(),
+                    Empty,
+                ))
+                .with_child(small_button_widget(&icons::MUSIC_NOTE).on_left_click(
+                    |ctx, _, _, _| {
+                        ctx.submit_command(TOGGLE_LYRICS);
+                    },
+                ))
+                .with_child(now_playing_button_widget())
+                .align_right(),
+        )
+        .with_child(
+            small_button_widget(&icons::QUEUE).on_left_click(|ctx, _, _, _| {
+                ctx.submit_command(cmd::TOGGLE_QUEUE);
+            }),
         )
+        .with_child(devices_button_widget())
         .padding(theme::grid(2.0))
 }
 
+/// Skip and play controls with the time, and the buttons to open the queue
+/// and pick a device.
+fn compact_player_widget() -> impl Widget<Playback> {
+    Flex::row()
+        .with_child(
+            small_button_widget(&icons::SKIP_BACK).on_left_click(|ctx, _, _, _| {
+                ctx.submit_command(cmd::PLAY_PREVIOUS);
+            }),
+        )
+        .with_child(player_play_pause_widget())
+        .with_child(
+            small_button_widget(&icons::SKIP_FORWARD).on_left_click(|ctx, _, _, _| {
+                ctx.submit_command(cmd::PLAY_NEXT);
+            }),
+        )
+        .with_default_spacer()
+        .with_child(Maybe::or_empty(durations_widget).lens(Playback::now_playing))
+        .with_child(
+            small_button_widget(&icons::QUEUE).on_left_click(|ctx, _, _, _| {
+                ctx.submit_command(cmd::TOGGLE_QUEUE);
+            }),
+        )
+        .with_child(now_playing_button_widget())
+        .with_child(devices_button_widget())
+        .padding(theme::grid(1.0))
+}
+
+fn now_playing_button_widget() -> impl Widget<Playback> {
+    small_button_widget(&icons::FULL_SCREEN).on_left_click(|ctx, _, _, _| {
+        ctx.submit_command(TOGGLE_NOW_PLAYING);
+    })
+}
+
+/// Skip and play buttons of the now-playing view.
+pub fn transport_widget() -> impl Widget<Playback> {
+    Flex::row()
+        .with_child(
+            small_button_widget(&icons::SKIP_BACK).on_left_click(|ctx, _, _, _| {
+                ctx.submit_command(cmd::PLAY_PREVIOUS);
+            }),
+        )
+        .with_default_spacer()
+        .with_child(player_play_pause_widget())
+        .with_default_spacer()
+        .with_child(
+            small_button_widget(&icons::SKIP_FORWARD).on_left_click(|ctx, _, _, _| {
+                ctx.submit_command(cmd::PLAY_NEXT);
+            }),
+        )
+}
+
+/// Seek bar with the elapsed and total time, for the now-playing view.
+pub fn progress_widget() -> impl Widget<NowPlaying> {
+    Flex::row()
+        .with_flex_child(SeekBar::new(), 1.0)
+        .with_default_spacer()
+        .with_child(durations_widget())
+}
+
 fn player_play_pause_widget() -> impl Widget<Playback> {
     ViewSwitcher::new(
         |playback: &Playback, _| playback.state,
@@ -249,38 +887,169 @@ fn player_play_pause_widget() -> impl Widget<Playback> {
     )
 }
 
-fn queue_behavior_widget() -> impl Widget<Playback> {
+/// Rates offered in the podcast speed menu.
+const PODCAST_SPEEDS: [f64; 9] = [0.5, 0.75, 1.0, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0];
+
+fn podcast_speed_widget() -> impl Widget<Playback> {
+    Label::dynamic(|playback: &Playback, _| format_speed(playback.podcast_speed))
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .padding(theme::grid(1.0))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, event, playback, _| {
+            ctx.show_context_menu(podcast_speed_menu(playback), event.window_pos);
+        })
+}
+
+fn podcast_speed_menu(playback: &Playback) -> Menu<AppState> {
+    let mut menu = Menu::empty();
+    for speed in PODCAST_SPEEDS {
+        menu = menu.entry(
+            MenuItem::new(format_speed(speed))
+                .selected(speed == playback.podcast_speed)
+                .command(cmd::PLAY_PODCAST_SPEED.with(speed)),
+        );
+    }
+    menu = menu.separator();
+    menu = menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-skip-silence").with_placeholder("Skip Silence"),
+        )
+        .selected(playback.skip_silence)
+        .command(cmd::PLAY_SKIP_SILENCE.with(!playback.skip_silence)),
+    );
+    menu
+}
+
+/// Title of the current chapter, opens the chapter list on click.
+fn chapters_widget() -> impl Widget<Playback> {
+    Label::dynamic(|playback: &Playback, _| {
+        playback
+            .now_playing
+            .as_ref()
+            .and_then(|np| np.current_chapter())
+            .map(|chapter| chapter.title.to_string())
+            .unwrap_or_else(|| "Chapters".to_string())
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR)
+    .with_line_break_mode(LineBreaking::Clip)
+    .fix_width(theme::grid(16.0))
+    .padding(theme::grid(1.0))
+    .link()
+    .rounded(theme::BUTTON_BORDER_RADIUS)
+    .on_left_click(|ctx, event, playback, _| {
+        if let Some(now_playing) = &playback.now_playing {
+            ctx.show_context_menu(chapters_menu(now_playing), event.window_pos);
+        }
+    })
+}
+
+fn chapters_menu(now_playing: &NowPlaying) -> Menu<AppState> {
+    let mut menu = Menu::empty();
+    let current = now_playing.current_chapter();
+    for chapter in &now_playing.chapters {
+        menu = menu.entry(
+            MenuItem::new(format!(
+                "{}  {}",
+                format::duration(chapter.start),
+                chapter.title
+            ))
+            .selected(current == Some(chapter))
+            .command(cmd::SKIP_TO_POSITION.with(chapter.start.as_millis() as u64)),
+        );
+    }
+    menu = menu.separator();
+    menu = menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-previous-chapter").with_placeholder("Previous Chapter"),
+        )
+        .command(cmd::PLAY_PREVIOUS_CHAPTER),
+    );
+    menu = menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-next-chapter").with_placeholder("Next Chapter"),
+        )
+        .command(cmd::PLAY_NEXT_CHAPTER),
+    );
+    menu
+}
+
+fn format_speed(speed: f64) -> String {
+    format!("{speed}×")
+}
+
+fn shuffle_widget() -> impl Widget<Playback> {
     ViewSwitcher::new(
-        |playback: &Playback, _| playback.queue_behavior,
-        |behavior, _, _| {
-            faded_button_widget(queue_behavior_icon(behavior))
+        |playback: &Playback, _| playback.shuffle,
+        |shuffle, _, _| {
+            let icon = match shuffle {
+                Shuffle::Smart => &icons::PLAY_SMART_SHUFFLE,
+                Shuffle::Off | Shuffle::Random => &icons::PLAY_SHUFFLE,
+            };
+            mode_button_widget(icon, *shuffle != Shuffle::Off)
                 .on_left_click(|ctx, _, playback: &mut Playback, _| {
-                    ctx.submit_command(
-                        cmd::PLAY_QUEUE_BEHAVIOR
-                            .with(cycle_queue_behavior(&playback.queue_behavior)),
-                    );
+                    let shuffle = match playback.shuffle {
+                        Shuffle::Off => Shuffle::Random,
+                        Shuffle::Random | Shuffle::Smart => Shuffle::Off,
+                    };
+                    ctx.submit_command(cmd::PLAY_SHUFFLE.with(shuffle));
                 })
+                .context_menu(|playback: &Playback| shuffle_menu(playback.shuffle))
                 .boxed()
         },
     )
 }
 
-fn cycle_queue_behavior(qb: &QueueBehavior) -> QueueBehavior {
-    match qb {
-        QueueBehavior::Sequential => QueueBehavior::Random,
-        QueueBehavior::Random => QueueBehavior::LoopTrack,
-        QueueBehavior::LoopTrack => QueueBehavior::LoopAll,
-        QueueBehavior::LoopAll => QueueBehavior::Sequential,
+fn shuffle_menu(current: Shuffle) -> Menu<AppState> {
+    let mut menu = Menu::empty();
+    for (shuffle, id, label) in [
+        (Shuffle::Off, "menu-item-play-in-order", "Play in Order"),
+        (Shuffle::Random, "menu-item-shuffle", "Shuffle"),
+        (Shuffle::Smart, "menu-item-smart-shuffle", "Smart Shuffle"),
+    ] {
+        menu = menu.entry(
+            MenuItem::new(LocalizedString::new(id).with_placeholder(label))
+                .selected(shuffle == current)
+                .command(cmd::PLAY_SHUFFLE.with(shuffle)),
+        );
     }
+    menu
+}
+
+fn repeat_widget() -> impl Widget<Playback> {
+    ViewSwitcher::new(
+        |playback: &Playback, _| playback.repeat,
+        |repeat, _, _| {
+            let icon = match repeat {
+                Repeat::One => &icons::PLAY_LOOP_TRACK,
+                Repeat::Off | Repeat::All => &icons::PLAY_LOOP_ALL,
+            };
+            mode_button_widget(icon, *repeat != Repeat::Off)
+                .on_left_click(|ctx, _, playback: &mut Playback, _| {
+                    ctx.submit_command(cmd::PLAY_REPEAT.with(playback.repeat.cycle()));
+                })
+                .context_menu(|playback: &Playback| repeat_menu(playback.repeat))
+                .boxed()
+        },
+    )
 }
 
-fn queue_behavior_icon(qb: &QueueBehavior) -> &'static SvgIcon {
-    match qb {
-        QueueBehavior::Sequential => &icons::PLAY_SEQUENTIAL,
-        QueueBehavior::Random => &icons::PLAY_SHUFFLE,
-        QueueBehavior::LoopTrack => &icons::PLAY_LOOP_TRACK,
-        QueueBehavior::LoopAll => &icons::PLAY_LOOP_ALL,
+fn repeat_menu(current: Repeat) -> Menu<AppState> {
+    let mut menu = Menu::empty();
+    for (repeat, id, label) in [
+        (Repeat::Off, "menu-item-repeat-off", "Repeat Off"),
+        (Repeat::All, "menu-item-repeat-all", "Repeat All"),
+        (Repeat::One, "menu-item-repeat-track", "Repeat Track"),
+    ] {
+        menu = menu.entry(
+            MenuItem::new(LocalizedString::new(id).with_placeholder(label))
+                .selected(repeat == current)
+                .command(cmd::PLAY_REPEAT.with(repeat)),
+        );
     }
+    menu
 }
 
 fn small_button_widget<T: Data>(svg: &SvgIcon) -> impl Widget<T> {
@@ -290,25 +1059,121 @@ fn small_button_widget<T: Data>(svg: &SvgIcon) -> impl Widget<T> {
         .rounded(theme::BUTTON_BORDER_RADIUS)
 }
 
-fn faded_button_widget<T: Data>(svg: &SvgIcon) -> impl Widget<T> {
-    svg.scale((theme::grid(2.0), theme::grid(2.0)))
-        .with_color(theme::PLACEHOLDER_COLOR)
-        .padding(theme::grid(1.0))
+/// Button of a playback mode, faded while the mode is off.
+fn mode_button_widget<T: Data>(svg: &SvgIcon, on: bool) -> impl Widget<T> {
+    let icon = svg.scale((theme::grid(2.0), theme::grid(2.0)));
+    let icon = if on {
+        icon
+    } else {
+        icon.with_color(theme::PLACEHOLDER_COLOR)
+    };
+    icon.padding(theme::grid(1.0))
         .link()
         .rounded(theme::BUTTON_BORDER_RADIUS)
+        .active(move |_, _| on)
 }
 
 fn durations_widget() -> impl Widget<NowPlaying> {
-    Label::dynamic(|now_playing: &NowPlaying, _| {
+    DurationsLabel::new().fix_width(theme::grid(8.0))
+}
+
+/// How often the time label checks the position.
+const DURATIONS_TICK: Duration = Duration::from_millis(250);
+
+/// Elapsed and total time of the playing item.  The elapsed time is read from
+/// the `PlaybackClock` on a timer, and the label repaints only when the shown
+/// second changes.
+struct DurationsLabel {
+    layout: TextLayout<String>,
+    timer: TimerToken,
+}
+
+impl DurationsLabel {
+    fn new() -> Self {
+        let mut layout = TextLayout::new();
+        layout.set_text_size(theme::TEXT_SIZE_SMALL);
+        layout.set_text_color(theme::PLACEHOLDER_COLOR);
+        Self {
+            layout,
+            timer: TimerToken::INVALID,
+        }
+    }
+
+    fn text(now_playing: &NowPlaying) -> String {
         format!(
             "{} / {}",
-            utils::as_minutes_and_seconds(now_playing.progress),
-            utils::as_minutes_and_seconds(now_playing.item.duration())
+            format::duration(PlaybackClock::global().position()),
+            format::duration(now_playing.item.duration())
         )
-    })
-    .with_text_size(theme::TEXT_SIZE_SMALL)
-    .with_text_color(theme::PLACEHOLDER_COLOR)
-    .fix_width(theme::grid(8.0))
+    }
+
+    fn set_text(&mut self, now_playing: &NowPlaying) -> bool {
+        let text = Self::text(now_playing);
+        let changed = self.layout.text() != Some(&text);
+        if changed {
+            self.layout.set_text(text);
+        }
+        changed
+    }
+}
+
+impl Widget<NowPlaying> for DurationsLabel {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut NowPlaying, _env: &Env) {
+        if let Event::Timer(token) = event {
+            if *token == self.timer {
+                if PlaybackClock::global().is_running() && self.set_text(data) {
+                    ctx.request_paint();
+                }
+                self.timer = ctx.request_timer(DURATIONS_TICK);
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &NowPlaying,
+        _env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.set_text(data);
+            self.timer = ctx.request_timer(DURATIONS_TICK);
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        _old_data: &NowPlaying,
+        data: &NowPlaying,
+        _env: &Env,
+    ) {
+        if self.set_text(data) {
+            ctx.request_paint();
+        }
+        if self.layout.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &NowPlaying,
+        env: &Env,
+    ) -> Size {
+        self.layout.rebuild_if_needed(ctx.text(), env);
+        bc.constrain(self.layout.size())
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &NowPlaying, env: &Env) {
+        // The text can change without a layout pass, see `event`.
+        self.layout.rebuild_if_needed(ctx.text(), env);
+        PlaybackClock::global().count_repaint();
+        self.layout.draw(ctx, Point::ORIGIN);
+    }
 }
 
 struct BarLayout<T, I, P> {
@@ -398,21 +1263,130 @@ where
     }
 }
 
+/// Shows the expanded bar, or the compact one when `compact` asks for it or
+/// the bar is narrower than `COMPACT_BAR_WIDTH`.  Only the shown bar gets
+/// laid out, painted, and the events that skip hidden widgets.
+struct AdaptiveBar<T> {
+    compact: Box<dyn Fn(&T) -> bool>,
+    expanded_bar: WidgetPod<T, Box<dyn Widget<T>>>,
+    compact_bar: WidgetPod<T, Box<dyn Widget<T>>>,
+    showing_compact: bool,
+}
+
+impl<T: Data> AdaptiveBar<T> {
+    fn new(
+        compact: impl Fn(&T) -> bool + 'static,
+        expanded_bar: impl Widget<T> + 'static,
+        compact_bar: impl Widget<T> + 'static,
+    ) -> Self {
+        Self {
+            compact: Box::new(compact),
+            expanded_bar: WidgetPod::new(expanded_bar).boxed(),
+            compact_bar: WidgetPod::new(compact_bar).boxed(),
+            showing_compact: false,
+        }
+    }
+
+    fn shown(&mut self) -> &mut WidgetPod<T, Box<dyn Widget<T>>> {
+        if self.showing_compact {
+            &mut self.compact_bar
+        } else {
+            &mut self.expanded_bar
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for AdaptiveBar<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if event.should_propagate_to_hidden() {
+            self.expanded_bar.event(ctx, event, data, env);
+            self.compact_bar.event(ctx, event, data, env);
+        } else {
+            self.shown().event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if event.should_propagate_to_hidden() {
+            self.expanded_bar.lifecycle(ctx, event, data, env);
+            self.compact_bar.lifecycle(ctx, event, data, env);
+        } else {
+            self.shown().lifecycle(ctx, event, data, env);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        // Both are kept up to date, as the width can switch them in `layout`.
+        self.expanded_bar.update(ctx, data, env);
+        self.compact_bar.update(ctx, data, env);
+        if (self.compact)(old_data) != (self.compact)(data) {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.showing_compact = (self.compact)(data) || bc.max().width < COMPACT_BAR_WIDTH;
+        let bar = self.shown();
+        let size = bar.layout(ctx, bc, data, env);
+        bar.set_origin(ctx, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.shown().paint(ctx, data, env);
+    }
+}
+
+/// Seek by `delta` seconds from the current position, announcing the new one.
+fn seek_by(ctx: &mut EventCtx, data: &mut AppState, delta: f64) -> Option<String> {
+    let duration = data.playback.now_playing.as_ref()?.item.duration();
+    if duration.is_zero() {
+        return None;
+    }
+    let total = duration.as_secs_f64();
+    let position = (PlaybackClock::global().position().as_secs_f64() + delta).clamp(0.0, total);
+    ctx.submit_command(cmd::PLAY_SEEK.with(position / total));
+    Some(format!(
+        "{} of {}",
+        format::duration(Duration::from_secs_f64(position)),
+        format::duration(duration)
+    ))
+}
+
+/// Shortest and longest time between two repaints of the seek bar.
+const SEEK_BAR_MIN_TICK: Duration = Duration::from_millis(50);
+const SEEK_BAR_MAX_TICK: Duration = Duration::from_secs(1);
+
 struct SeekBar {
     loudness_path: BezPath,
+    timer: TimerToken,
 }
 
 impl SeekBar {
     fn new() -> Self {
         Self {
             loudness_path: BezPath::new(),
+            timer: TimerToken::INVALID,
         }
     }
+
+    /// Repaint about once for every pixel the elapsed part grows by, which
+    /// is rarely for long items on a narrow bar.
+    fn tick(width: f64, data: &NowPlaying) -> Duration {
+        let per_pixel = data.item.duration().as_secs_f64() / width.max(1.0);
+        Duration::from_secs_f64(per_pixel).clamp(SEEK_BAR_MIN_TICK, SEEK_BAR_MAX_TICK)
+    }
 }
 
 impl Widget<NowPlaying> for SeekBar {
-    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut NowPlaying, _env: &Env) {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut NowPlaying, _env: &Env) {
         match event {
+            Event::Timer(token) if *token == self.timer => {
+                if PlaybackClock::global().is_running() {
+                    ctx.request_paint();
+                }
+                self.timer = ctx.request_timer(Self::tick(ctx.size().width, data));
+            }
             Event::MouseMove(_) => {
                 ctx.set_cursor(&Cursor::Pointer);
             }
@@ -442,6 +1416,9 @@ impl Widget<NowPlaying> for SeekBar {
         _env: &Env,
     ) {
         match &event {
+            LifeCycle::WidgetAdded => {
+                self.timer = ctx.request_timer(SEEK_BAR_MIN_TICK);
+            }
             LifeCycle::Size(_bounds) => {
                 // self.loudness_path = compute_loudness_path(bounds, &data);
             }
@@ -475,6 +1452,7 @@ impl Widget<NowPlaying> for SeekBar {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &NowPlaying, env: &Env) {
+        PlaybackClock::global().count_repaint();
         if self.loudness_path.is_empty() {
             paint_progress_bar(ctx, data, env)
         } else {
@@ -547,9 +1525,9 @@ fn _compute_loudness_path_from_analysis(
 fn paint_audio_analysis(ctx: &mut PaintCtx, data: &NowPlaying, path: &BezPath, env: &Env) {
     let bounds = ctx.size();
 
-    let elapsed_time = data.progress.as_secs_f64();
+    let elapsed_time = PlaybackClock::global().position().as_secs_f64();
     let total_time = data.item.duration().as_secs_f64();
-    let elapsed_frac = elapsed_time / total_time;
+    let elapsed_frac = (elapsed_time / total_time).min(1.0);
     let elapsed_width = bounds.width * elapsed_frac;
     let elapsed = Size::new(elapsed_width, bounds.height).to_rect();
 
@@ -567,7 +1545,7 @@ fn paint_audio_analysis(ctx: &mut PaintCtx, data: &NowPlaying, path: &BezPath, e
 }
 
 fn paint_progress_bar(ctx: &mut PaintCtx, data: &NowPlaying, env: &Env) {
-    let elapsed_time = data.progress.as_secs_f64();
+    let elapsed_time = PlaybackClock::global().position().as_secs_f64();
     let total_time = data.item.duration().as_secs_f64();
 
     let (elapsed_color, remaining_color) = if ctx.is_hot() {
@@ -577,7 +1555,7 @@ fn paint_progress_bar(ctx: &mut PaintCtx, data: &NowPlaying, env: &Env) {
     };
     let bounds = ctx.size();
 
-    let elapsed_frac = elapsed_time / total_time;
+    let elapsed_frac = (elapsed_time / total_time).min(1.0);
     let elapsed_width = bounds.width * elapsed_frac;
     let remaining_width = bounds.width - elapsed_width;
     let elapsed = Size::new(elapsed_width, bounds.height).round();
@@ -1,47 +1,74 @@
-use std::{cell::RefCell, cmp::Ordering, rc::Rc, sync::Arc};
+use std::{any::Any, cell::RefCell, rc::Rc, sync::Arc};
 
 use druid::{
     im::Vector,
+    lens::Map,
     widget::{
-        Button, CrossAxisAlignment, Either, Flex, Label, LensWrap, LineBreaking, List, TextBox,
+        Button, CrossAxisAlignment, Either, Flex, Label, LensWrap, LineBreaking, List, Maybe,
+        Scroll, TextBox,
     },
     Insets, Lens, LensExt, LocalizedString, Menu, MenuItem, Selector, Size, UnitPoint, Widget,
     WidgetExt, WindowDesc,
 };
 use itertools::Itertools;
+use psst_core::offline::SyncStatus;
 
 use crate::{
     cmd,
+    controller::DropTrack,
     data::{
-        config::{SortCriteria, SortOrder},
-        AppState, Ctx, Library, Nav, Playlist, PlaylistAddTrack, PlaylistDetail, PlaylistLink,
-        PlaylistRemoveTrack, PlaylistTracks, Track, WithCtx,
+        AppState, Config, Ctx, Library, Nav, NewPlaylist, Playlist, PlaylistAddTracks,
+        PlaylistDetail, PlaylistEdit, PlaylistLink, PlaylistMoveTrack, PlaylistRemoveTrack,
+        PlaylistTracks, PlaylistUndoAdd, PlaylistVisibility, PublicUserProfile, QueuePlacement,
+        SkipReview, SkippedTrack, SortOrder, TableSort, Track, TrackColumn, TrackId, WithCtx,
     },
     error::Error,
+    format,
     ui::menu,
     webapi::WebApi,
-    widget::{Async, Empty, MyWidgetExt, RemoteImage, ThemeScope},
+    widget::{icons, Async, Checkbox, Empty, MyWidgetExt, RemoteImage, ThemeScope},
 };
 
-use super::{playable, theme, track, utils};
+use super::{playable, theme, track, user, utils};
 
 pub const LOAD_LIST: Selector = Selector::new("app.playlist.load-list");
 pub const LOAD_DETAIL: Selector<(PlaylistLink, AppState)> =
     Selector::new("app.playlist.load-detail");
-pub const ADD_TRACK: Selector<PlaylistAddTrack> = Selector::new("app.playlist.add-track");
+pub const ADD_TRACKS: Selector<PlaylistAddTracks> = Selector::new("app.playlist.add-tracks");
+pub const UNDO_ADD_TRACKS: Selector<PlaylistUndoAdd> =
+    Selector::new("app.playlist.undo-add-tracks");
 pub const REMOVE_TRACK: Selector<PlaylistRemoveTrack> = Selector::new("app.playlist.remove-track");
+pub const MOVE_TRACK: Selector<PlaylistMoveTrack> = Selector::new("app.playlist.move-track");
+/// Track dropped on the row at the position of the playlist.
+pub const DROP_TRACK: Selector<(PlaylistLink, Arc<Track>, usize)> =
+    Selector::new("app.playlist.drop-track");
 
 pub const FOLLOW_PLAYLIST: Selector<Playlist> = Selector::new("app.playlist.follow");
 pub const UNFOLLOW_PLAYLIST: Selector<PlaylistLink> = Selector::new("app.playlist.unfollow");
 pub const UNFOLLOW_PLAYLIST_CONFIRM: Selector<PlaylistLink> =
     Selector::new("app.playlist.unfollow-confirm");
 
-pub const RENAME_PLAYLIST: Selector<PlaylistLink> = Selector::new("app.playlist.rename");
-pub const RENAME_PLAYLIST_CONFIRM: Selector<PlaylistLink> =
-    Selector::new("app.playlist.rename-confirm");
+pub const EDIT_PLAYLIST: Selector<PlaylistEdit> = Selector::new("app.playlist.edit");
+pub const EDIT_PLAYLIST_CONFIRM: Selector<PlaylistEdit> =
+    Selector::new("app.playlist.edit-confirm");
 
-const SHOW_RENAME_PLAYLIST_CONFIRM: Selector<PlaylistLink> =
-    Selector::new("app.playlist.show-rename");
+pub const CREATE_PLAYLIST: Selector<NewPlaylist> = Selector::new("app.playlist.create");
+pub const CREATE_PLAYLIST_CONFIRM: Selector<Arc<str>> =
+    Selector::new("app.playlist.create-confirm");
+
+pub const REMOVE_SKIPPED_TRACKS: Selector<SkipReview> =
+    Selector::new("app.playlist.remove-skipped-tracks");
+/// Remove the tracks ticked in `AppState::skip_review`.
+pub const REMOVE_SKIPPED_TRACKS_CONFIRM: Selector =
+    Selector::new("app.playlist.remove-skipped-tracks-confirm");
+pub const SHOW_SKIP_REVIEW: Selector<SkipReview> = Selector::new("app.playlist.show-skip-review");
+
+const SAVE_PLAYLIST_DETAILS: Selector<PlaylistEdit> = Selector::new("app.playlist.save-details");
+const SET_PLAYLIST_VISIBILITY: Selector<(PlaylistLink, PlaylistVisibility)> =
+    Selector::new("app.playlist.set-visibility");
+
+const SHOW_EDIT_PLAYLIST: Selector<PlaylistEdit> = Selector::new("app.playlist.show-edit");
+const SHOW_CREATE_PLAYLIST: Selector = Selector::new("app.playlist.show-create");
 const SHOW_UNFOLLOW_PLAYLIST_CONFIRM: Selector<UnfollowPlaylist> =
     Selector::new("app.playlist.show-unfollow-confirm");
 
@@ -77,9 +104,16 @@ pub fn list_widget() -> impl Widget<AppState> {
                     .cross_axis_alignment(CrossAxisAlignment::Center)
                     .with_child(cover_section)
                     .with_flex_child(title, 1.0)
+                    .with_child(collaborative_badge_widget().lens(Ctx::data()))
+                    .with_child(sync_badge_widget(true))
                     .expand_width()
                     .padding(Insets::uniform_xy(theme::grid(2.0), theme::grid(0.6)))
                     .link()
+                    // Highlight the playlists a dragged track can be dropped on.
+                    .active(|playlist: &WithCtx<Playlist>, _| {
+                        playlist.ctx.dragged_track.is_some()
+                            && playlist.ctx.library.is_playlist_writable(&playlist.data)
+                    })
                     .on_left_click(|ctx, _event, playlist, _| {
                         ctx.submit_command(
                             cmd::NAVIGATE.with(Nav::PlaylistDetail(playlist.data.link())),
@@ -89,7 +123,29 @@ pub fn list_widget() -> impl Widget<AppState> {
                         ctx.submit_command(cmd::PLAY_PLAYLIST.with(playlist.data.link()));
                         ctx.set_handled();
                     })
-                    .context_menu(playlist_menu_ctx)
+                    .context_menu(sidebar_playlist_menu_ctx)
+                    .controller(DropTrack::new(
+                        |ctx, playlist: &mut WithCtx<Playlist>, _| {
+                            if !playlist.ctx.library.is_playlist_writable(&playlist.data) {
+                                return;
+                            }
+                            // The dragged track, or the selection it is part of.
+                            let track_ids: Vector<TrackId> = playlist
+                                .ctx
+                                .dragged_tracks
+                                .iter()
+                                .filter(|track| !track.is_local)
+                                .map(|track| track.id)
+                                .collect();
+                            if !track_ids.is_empty() {
+                                ctx.submit_command(ADD_TRACKS.with(PlaylistAddTracks {
+                                    link: playlist.data.link(),
+                                    track_ids,
+                                }));
+                                ctx.submit_command(cmd::CLEAR_TRACK_SELECTION);
+                            }
+                        },
+                    ))
             })
         },
         utils::error_widget,
@@ -108,34 +164,94 @@ pub fn list_widget() -> impl Widget<AppState> {
         |_, data, r| data.with_library_mut(|l| l.playlists.update(r)),
     )
     .on_command_async(
-        ADD_TRACK,
+        ADD_TRACKS,
         |d| {
-            WebApi::global().add_track_to_playlist(
-                &d.link.id,
-                &d.track_id
-                    .0
-                    .to_uri()
-                    .ok_or_else(|| Error::WebApiError("Item doesn't have URI".to_string()))?,
-            )
+            let uris = d
+                .track_ids
+                .iter()
+                .map(|id| id.0.to_uri().map(Arc::from))
+                .collect::<Option<Vec<Arc<str>>>>()
+                .ok_or_else(|| Error::WebApiError("Item doesn't have URI".to_string()))?;
+            let snapshot_id = WebApi::global().add_tracks_to_playlist(&d.link.id, &uris)?;
+            Ok((snapshot_id, uris))
         },
         |_, data, d| {
-            data.with_library_mut(|library| library.increment_playlist_track_count(&d.link))
+            data.with_library_mut(|library| {
+                library.increment_playlist_track_count(&d.link, d.track_ids.len())
+            })
         },
-        |_, data, (_, r)| {
+        |ctx, data, (d, r): (PlaylistAddTracks, Result<(Arc<str>, Vec<Arc<str>>), Error>)| {
+            if data.playlist_detail.tracks.contains(&d.link) {
+                ctx.submit_command(LOAD_DETAIL.with((d.link.clone(), data.clone())));
+            }
+            match r {
+                Err(err) => {
+                    data.error_alert(err);
+                }
+                Ok((snapshot_id, uris)) => {
+                    let message = match uris.len() {
+                        1 => format!("Added to {}.", d.link.name),
+                        n => format!("Added {n} tracks to {}.", d.link.name),
+                    };
+                    // The tracks were appended, undo takes them out at the end
+                    // of the version of the playlist that has them.  Should
+                    // the count be off, the API refuses the removal rather
+                    // than taking out other tracks.
+                    match data.library.playlist_track_count(&d.link) {
+                        Some(count) => data.info_alert_with_action(
+                            message,
+                            "Undo",
+                            UNDO_ADD_TRACKS.with(PlaylistUndoAdd {
+                                link: d.link,
+                                snapshot_id,
+                                position: count.saturating_sub(uris.len()),
+                                uris: uris.into_iter().collect(),
+                            }),
+                        ),
+                        None => data.info_alert(message),
+                    }
+                }
+            }
+        },
+    )
+    .on_command_async(
+        UNDO_ADD_TRACKS,
+        |d| {
+            let uris: Vec<Arc<str>> = d.uris.iter().cloned().collect();
+            WebApi::global().remove_added_tracks(&d.link.id, &d.snapshot_id, &uris, d.position)
+        },
+        |_, data, d| {
+            data.with_library_mut(|library| {
+                library.decrement_playlist_track_count(&d.link, d.uris.len())
+            })
+        },
+        |ctx, data, (d, r)| {
             if let Err(err) = r {
                 data.error_alert(err);
+                // Bring the track count back.
+                ctx.submit_command(LOAD_LIST);
             } else {
-                data.info_alert("Added to playlist.");
+                data.info_alert("Removed from playlist.");
+            }
+            if data.playlist_detail.tracks.contains(&d.link) {
+                ctx.submit_command(LOAD_DETAIL.with((d.link, data.clone())));
             }
         },
     )
     .on_command_async(
         UNFOLLOW_PLAYLIST,
         |link| WebApi::global().unfollow_playlist(link.id.as_ref()),
-        |_, data: &mut AppState, d| data.with_library_mut(|l| l.remove_from_playlist(&d.id)),
-        |_, data, (_, r)| {
+        |ctx, data: &mut AppState, d| {
+            if matches!(&data.nav, Nav::PlaylistDetail(link) if link.id == d.id) {
+                ctx.submit_command(cmd::NAVIGATE.with(Nav::Home));
+            }
+            data.with_library_mut(|l| l.remove_from_playlist(&d.id));
+        },
+        |ctx, data, (_, r)| {
             if let Err(err) = r {
                 data.error_alert(err);
+                // Bring the playlist back.
+                ctx.submit_command(LOAD_LIST);
             } else {
                 data.info_alert("Playlist removed from library.");
             }
@@ -154,14 +270,38 @@ pub fn list_widget() -> impl Widget<AppState> {
         },
     )
     .on_command_async(
-        RENAME_PLAYLIST,
-        |link| WebApi::global().change_playlist_details(link.id.as_ref(), link.name.as_ref()),
-        |_, data: &mut AppState, link| data.with_library_mut(|l| l.rename_playlist(link)),
-        |_, data: &mut AppState, (_, r)| {
+        EDIT_PLAYLIST,
+        |edit| {
+            WebApi::global().change_playlist_details(
+                &edit.id,
+                edit.name.trim(),
+                Some(edit.description.trim()),
+            )
+        },
+        |_, data: &mut AppState, edit| data.apply_playlist_edit(&edit),
+        |ctx, data: &mut AppState, (_, r)| {
             if let Err(err) = r {
                 data.error_alert(err);
+                // Reload to show the details that are actually saved.
+                ctx.submit_command(LOAD_LIST);
             } else {
-                data.info_alert("Playlist renamed.")
+                data.info_alert("Playlist details saved.")
+            }
+        },
+    )
+    .on_command_async(
+        CREATE_PLAYLIST,
+        |new| WebApi::global().create_playlist(&new.owner.id, &new.name, ""),
+        |_, data: &mut AppState, new| data.with_library_mut(|l| l.add_playlist(new.placeholder())),
+        |ctx, data: &mut AppState, (new, r)| match r {
+            Ok(playlist) => {
+                let link = playlist.link();
+                data.with_library_mut(|l| l.replace_playlist(&new.placeholder_id(), playlist));
+                ctx.submit_command(cmd::NAVIGATE.with(Nav::PlaylistDetail(link)));
+            }
+            Err(err) => {
+                data.with_library_mut(|l| l.remove_from_playlist(&new.placeholder_id()));
+                data.error_alert(err);
             }
         },
     )
@@ -169,15 +309,50 @@ pub fn list_widget() -> impl Widget<AppState> {
         let window = unfollow_confirm_window(msg.clone());
         ctx.new_window(window);
     })
-    .on_command(SHOW_RENAME_PLAYLIST_CONFIRM, |ctx, link, _| {
-        let window = rename_playlist_window(link.clone());
+    .on_command(SHOW_EDIT_PLAYLIST, |ctx, edit, _| {
+        let window = edit_playlist_window(edit.clone());
         ctx.new_window(window);
     })
+    .on_command(SHOW_CREATE_PLAYLIST, |ctx, _, _| {
+        ctx.new_window(create_playlist_window());
+    })
+    .on_command(SHOW_SKIP_REVIEW, |ctx, review, data| {
+        data.skip_review = Some(review.clone());
+        ctx.new_window(skip_review_window());
+    })
+    .on_command_async(
+        REMOVE_SKIPPED_TRACKS,
+        |review| {
+            let uris: Vec<_> = review
+                .tracks
+                .iter()
+                .map(|track| track.uri.clone())
+                .collect();
+            WebApi::global().remove_tracks_from_playlist(&review.link.id, &uris)
+        },
+        |_, _, _| {},
+        |ctx, data: &mut AppState, (review, r)| {
+            if let Err(err) = r {
+                data.error_alert(err);
+                return;
+            }
+            let songs = match review.tracks.len() {
+                1 => "1 song".to_string(),
+                count => format!("{count} songs"),
+            };
+            data.info_alert(format!("Removed {songs} from {}.", review.link.name));
+            // Tracks are removed with all their occurrences, reload the counts.
+            ctx.submit_command(LOAD_LIST);
+            if data.playlist_detail.tracks.contains(&review.link) {
+                ctx.submit_command(LOAD_DETAIL.with((review.link, data.clone())));
+            }
+        },
+    )
     .on_command_async(
         REMOVE_TRACK,
         |d| WebApi::global().remove_track_from_playlist(&d.link.id, d.track_pos),
         |_, data, d| {
-            data.with_library_mut(|library| library.decrement_playlist_track_count(&d.link))
+            data.with_library_mut(|library| library.decrement_playlist_track_count(&d.link, 1))
         },
         |e, data, (p, r)| {
             if let Err(err) = r {
@@ -236,10 +411,71 @@ fn unfollow_playlist_confirm_widget(msg: UnfollowPlaylist) -> impl Widget<AppSta
     )
 }
 
-fn rename_playlist_window(link: PlaylistLink) -> WindowDesc<AppState> {
-    let win = WindowDesc::new(rename_playlist_widget(link))
+/// Sidebar entry opening the window to create a playlist.
+pub fn new_playlist_widget() -> impl Widget<AppState> {
+    Flex::row()
+        .with_child(
+            icons::CIRCLE_PLUS
+                .scale(theme::ICON_SIZE_SMALL)
+                .with_color(theme::PLACEHOLDER_COLOR),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(Label::new("New Playlist").with_text_size(theme::TEXT_SIZE_SMALL))
+        .expand_width()
+        .padding(Insets::uniform_xy(theme::grid(2.0), theme::grid(0.6)))
+        .link()
+        .on_left_click(|ctx, _, _, _| ctx.submit_command(SHOW_CREATE_PLAYLIST))
+}
+
+fn create_playlist_window() -> WindowDesc<AppState> {
+    let win = WindowDesc::new(create_playlist_widget())
         .window_size((theme::grid(45.0), theme::grid(30.0)))
-        .title("Rename playlist")
+        .title("New playlist")
+        .resizable(false)
+        .show_title(false)
+        .transparent_titlebar(true);
+    if cfg!(target_os = "macos") {
+        win.menu(menu::main_menu)
+    } else {
+        win
+    }
+}
+
+fn create_playlist_widget() -> impl Widget<AppState> {
+    let name = TextInput {
+        input: Rc::new(RefCell::new(String::new())),
+    };
+
+    let information_section =
+        information_section("New playlist", "Please enter a name for your playlist");
+    let input_section = LensWrap::new(
+        TextBox::new()
+            .with_placeholder("Name")
+            .padding_horizontal(theme::grid(2.0))
+            .expand_width(),
+        name.clone(),
+    );
+    let button_section = button_section(
+        "Create",
+        CREATE_PLAYLIST_CONFIRM,
+        Box::new(move || name.input.borrow().trim().into()),
+    );
+
+    ThemeScope::new(
+        Flex::column()
+            .with_child(information_section)
+            .with_child(input_section)
+            .with_flex_spacer(2.0)
+            .with_child(button_section)
+            .with_flex_spacer(2.0)
+            .background(theme::BACKGROUND_DARK),
+    )
+}
+
+fn edit_playlist_window(edit: PlaylistEdit) -> WindowDesc<AppState> {
+    let win = WindowDesc::new(edit_playlist_widget(edit))
+        .window_size((theme::grid(45.0), theme::grid(42.0)))
+        .title("Edit playlist")
         .resizable(false)
         .show_title(false)
         .transparent_titlebar(true);
@@ -265,27 +501,39 @@ impl Lens<AppState, String> for TextInput {
     }
 }
 
-fn rename_playlist_widget(link: PlaylistLink) -> impl Widget<AppState> {
-    let text_input = TextInput {
-        input: Rc::new(RefCell::new(link.name.to_string())),
+fn edit_playlist_widget(edit: PlaylistEdit) -> impl Widget<AppState> {
+    let name = TextInput {
+        input: Rc::new(RefCell::new(edit.name.clone())),
+    };
+    let description = TextInput {
+        input: Rc::new(RefCell::new(edit.description.clone())),
     };
 
     let information_section = information_section(
-        "Rename playlist?",
-        "Please enter a new name for your playlist",
-    );
-    let input_section = LensWrap::new(
-        TextBox::new()
-            .padding_horizontal(theme::grid(2.0))
-            .expand_width(),
-        text_input.clone(),
+        "Edit playlist",
+        "Please enter a new name, and an optional description, for your playlist",
     );
+    let input_section = Flex::column()
+        .with_child(LensWrap::new(
+            TextBox::new().with_placeholder("Name").expand_width(),
+            name.clone(),
+        ))
+        .with_default_spacer()
+        .with_child(LensWrap::new(
+            TextBox::multiline()
+                .with_placeholder("Add an optional description")
+                .expand_width()
+                .fix_height(theme::grid(8.0)),
+            description.clone(),
+        ))
+        .padding_horizontal(theme::grid(2.0));
     let button_section = button_section(
-        "Rename",
-        RENAME_PLAYLIST_CONFIRM,
-        Box::new(move || PlaylistLink {
-            id: link.id.clone(),
-            name: Arc::from(text_input.input.borrow().clone().into_boxed_str()),
+        "Save",
+        EDIT_PLAYLIST_CONFIRM,
+        Box::new(move || PlaylistEdit {
+            id: edit.id.clone(),
+            name: name.input.borrow().clone(),
+            description: description.input.borrow().clone(),
         }),
     );
 
@@ -300,10 +548,75 @@ fn rename_playlist_widget(link: PlaylistLink) -> impl Widget<AppState> {
     )
 }
 
-fn button_section(
+fn skip_review_window() -> WindowDesc<AppState> {
+    let win = WindowDesc::new(skip_review_widget())
+        .window_size((theme::grid(50.0), theme::grid(60.0)))
+        .title("Review skipped songs")
+        .resizable(false)
+        .show_title(false)
+        .transparent_titlebar(true);
+    if cfg!(target_os = "macos") {
+        win.menu(menu::main_menu)
+    } else {
+        win
+    }
+}
+
+fn skip_review_widget() -> impl Widget<AppState> {
+    let information_section = information_section(
+        "Review skipped songs",
+        "You skipped these songs within seconds every time they came up lately. Untick the \
+         ones to keep, the rest is removed from the playlist.",
+    );
+    let tracks = Maybe::or_empty(|| {
+        Scroll::new(List::new(skipped_track_widget))
+            .vertical()
+            .lens(SkipReview::tracks)
+    })
+    .lens(AppState::skip_review)
+    .padding_horizontal(theme::grid(2.0));
+    let button_section = button_section("Remove", REMOVE_SKIPPED_TRACKS_CONFIRM, Box::new(|| ()));
+
+    ThemeScope::new(
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Fill)
+            .with_child(information_section)
+            .with_flex_child(tracks, 1.0)
+            .with_spacer(theme::grid(2.0))
+            .with_child(button_section)
+            .with_spacer(theme::grid(2.0))
+            .background(theme::BACKGROUND_DARK),
+    )
+}
+
+fn skipped_track_widget() -> impl Widget<SkippedTrack> {
+    let title =
+        Label::dynamic(|track: &SkippedTrack, _| format!("{} · {}", track.name, track.artist))
+            .with_line_break_mode(LineBreaking::Clip);
+    let skips =
+        Label::dynamic(|track: &SkippedTrack, _| format!("Skipped {} times", track.skip_count))
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR);
+
+    Flex::row()
+        .with_child(Checkbox::new("").lens(SkippedTrack::remove))
+        .with_default_spacer()
+        .with_flex_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(title)
+                .with_child(skips),
+            1.0,
+        )
+        .padding(theme::grid(0.5))
+}
+
+/// Buttons of a confirmation window, the action submits `selector` with the
+/// payload of `link_extractor`.  Both close the window.
+pub fn button_section<T: Any>(
     action_button_name: &str,
-    selector: Selector<PlaylistLink>,
-    link_extractor: Box<dyn Fn() -> PlaylistLink>,
+    selector: Selector<T>,
+    link_extractor: Box<dyn Fn() -> T>,
 ) -> impl Widget<AppState> {
     let action_button = Button::new(action_button_name)
         .fix_height(theme::grid(5.0))
@@ -325,7 +638,8 @@ fn button_section(
         .align_right()
 }
 
-fn information_section(title_msg: &str, description_msg: &str) -> impl Widget<AppState> {
+/// Title and description of a confirmation window.
+pub fn information_section(title_msg: &str, description_msg: &str) -> impl Widget<AppState> {
     let title_label = Label::new(title_msg)
         .with_text_size(theme::TEXT_SIZE_LARGE)
         .align_left()
@@ -354,10 +668,16 @@ pub fn playlist_widget(horizontal: bool) -> impl Widget<WithCtx<Playlist>> {
         })
         .lens(Ctx::data());
 
-    let playlist_name = Label::raw()
-        .with_font(theme::UI_FONT_MEDIUM)
-        .with_line_break_mode(LineBreaking::Clip)
-        .lens(Ctx::data().then(Playlist::name));
+    let playlist_name = Flex::row()
+        .with_flex_child(
+            Label::raw()
+                .with_font(theme::UI_FONT_MEDIUM)
+                .with_line_break_mode(LineBreaking::Clip)
+                .lens(Playlist::name),
+            1.0,
+        )
+        .with_child(collaborative_badge_widget())
+        .lens(Ctx::data());
 
     let playlist_description = Label::raw()
         .with_line_break_mode(LineBreaking::WordWrap)
@@ -416,11 +736,23 @@ pub fn playlist_widget(horizontal: bool) -> impl Widget<WithCtx<Playlist>> {
         .context_menu(playlist_menu_ctx)
 }
 
+/// Marks the playlists that others can add tracks to.
+fn collaborative_badge_widget() -> impl Widget<Playlist> {
+    Either::new(
+        |playlist: &Playlist, _| playlist.collaborative,
+        icons::ARTIST
+            .scale(theme::ICON_SIZE_TINY)
+            .padding_left(theme::grid(0.5)),
+        Empty,
+    )
+}
+
 fn cover_widget(size: f64) -> impl Widget<Playlist> {
     RemoteImage::new(
         utils::placeholder_widget(),
         move |playlist: &Playlist, _| playlist.image(size, size).map(|image| image.url.clone()),
     )
+    .with_size((size, size))
     .fix_size(size, size)
 }
 
@@ -439,11 +771,265 @@ pub fn detail_widget() -> impl Widget<AppState> {
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_spacer(theme::grid(1.0))
+        .with_child(details_widget().padding(theme::grid(1.0)))
         .with_child(playlist_top)
+        .with_child(intro_skip_widget())
         .with_spacer(theme::grid(1.0))
+        .with_child(track::table_header_widget(|config| config.playlist_sort))
         .with_child(playlist_tracks)
 }
 
+/// Intro lengths offered in the skip intro menu, in seconds.
+const INTRO_SKIP_OPTIONS: [u64; 7] = [0, 5, 10, 15, 20, 30, 60];
+
+/// Length of the intro skipped in every track of the playlist, opens a menu
+/// to change it on click.
+fn intro_skip_widget() -> impl Widget<AppState> {
+    Label::dynamic(|data: &AppState, _| {
+        let seconds = data.playlist_detail.playlist.deferred().map_or(0, |link| {
+            data.config.playlist_intro_skip(&link.id).as_secs()
+        });
+        format!("Skip Intro: {}", format_intro_skip(seconds))
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR)
+    .padding(theme::grid(1.0))
+    .link()
+    .rounded(theme::BUTTON_BORDER_RADIUS)
+    .on_left_click(|ctx, event, data: &mut AppState, _| {
+        if let Some(link) = data.playlist_detail.playlist.deferred() {
+            ctx.show_context_menu(intro_skip_menu(link, &data.config), event.window_pos);
+        }
+    })
+    .padding(theme::grid(1.0))
+}
+
+fn intro_skip_menu(link: &PlaylistLink, config: &Config) -> Menu<AppState> {
+    let current = config.playlist_intro_skip(&link.id).as_secs();
+    let mut menu = Menu::empty();
+    for seconds in INTRO_SKIP_OPTIONS {
+        menu = menu.entry(
+            MenuItem::new(format_intro_skip(seconds))
+                .selected(seconds == current)
+                .command(cmd::SET_PLAYLIST_INTRO_SKIP.with((link.clone(), seconds))),
+        );
+    }
+    menu
+}
+
+fn format_intro_skip(seconds: u64) -> String {
+    if seconds == 0 {
+        "Off".to_string()
+    } else {
+        format!("{seconds} s")
+    }
+}
+
+/// Name and description of the playlist, editable in place for playlists
+/// owned by the user.
+fn details_widget() -> impl Widget<AppState> {
+    Either::new(
+        |data: &AppState, _| data.playlist_detail.edit.is_some(),
+        details_editor_widget(),
+        details_display_widget(),
+    )
+    .on_command_async(
+        SAVE_PLAYLIST_DETAILS,
+        |edit| {
+            WebApi::global().change_playlist_details(
+                &edit.id,
+                edit.name.trim(),
+                Some(edit.description.trim()),
+            )
+        },
+        |_, data: &mut AppState, edit| {
+            data.playlist_detail.edit = None;
+            data.apply_playlist_edit(&edit);
+        },
+        |ctx, data: &mut AppState, (_, r)| {
+            if let Err(err) = r {
+                data.error_alert(err);
+                // Reload to show the details that are actually saved.
+                ctx.submit_command(LOAD_LIST);
+                if let Nav::PlaylistDetail(link) = &data.nav {
+                    ctx.submit_command(LOAD_DETAIL.with((link.clone(), data.clone())));
+                }
+            } else {
+                data.info_alert("Playlist details saved.");
+            }
+        },
+    )
+    .on_command_async(
+        SET_PLAYLIST_VISIBILITY,
+        |(link, visibility)| WebApi::global().change_playlist_visibility(&link.id, visibility),
+        |_, data: &mut AppState, (link, visibility)| {
+            data.apply_playlist_visibility(&link.id, visibility);
+        },
+        |ctx, data: &mut AppState, ((link, visibility), r)| {
+            if let Err(err) = r {
+                data.error_alert(err);
+                // Reload to show the visibility that is actually saved.
+                ctx.submit_command(LOAD_LIST);
+                if let Nav::PlaylistDetail(current) = &data.nav {
+                    if current.id == link.id {
+                        ctx.submit_command(LOAD_DETAIL.with((current.clone(), data.clone())));
+                    }
+                }
+            } else {
+                data.info_alert(format!(
+                    "{} is now {}.",
+                    link.name,
+                    visibility.label().to_lowercase()
+                ));
+            }
+        },
+    )
+}
+
+fn details_display_widget() -> impl Widget<AppState> {
+    let name_label = Label::dynamic(|data: &AppState, _| {
+        data.playlist_detail
+            .playlist
+            .resolved()
+            .map(|playlist| playlist.name.to_string())
+            .unwrap_or_default()
+    })
+    .with_font(theme::UI_FONT_MEDIUM)
+    .with_text_size(theme::TEXT_SIZE_LARGE)
+    .with_line_break_mode(LineBreaking::WordWrap);
+
+    let visibility_button = Label::dynamic(|data: &AppState, _| {
+        data.playlist_detail
+            .playlist
+            .resolved()
+            .map(|playlist| format!("{} \u{25BE}", playlist.visibility().label()))
+            .unwrap_or_default()
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .padding(theme::grid(1.0))
+    .link()
+    .rounded(theme::BUTTON_BORDER_RADIUS)
+    .on_left_click(|ctx, event, data: &mut AppState, _| {
+        if let Some(playlist) = data.playlist_detail.playlist.resolved() {
+            ctx.show_context_menu(visibility_menu(playlist), event.window_pos);
+        }
+    });
+
+    let edit_button = Either::new(
+        |data: &AppState, _| {
+            data.playlist_detail
+                .playlist
+                .resolved()
+                .is_some_and(|playlist| data.library.is_created_by_user(playlist))
+        },
+        Flex::row()
+            .with_child(visibility_button)
+            .with_default_spacer()
+            .with_child(Button::new("Edit").on_click(|_, data: &mut AppState, _| {
+                data.playlist_detail.edit = data
+                    .playlist_detail
+                    .playlist
+                    .resolved()
+                    .map(PlaylistEdit::new);
+            })),
+        Empty,
+    );
+
+    let description_label = Label::dynamic(|data: &AppState, _| {
+        data.playlist_detail
+            .playlist
+            .resolved()
+            .map(|playlist| playlist.description.to_string())
+            .unwrap_or_default()
+    })
+    .with_line_break_mode(LineBreaking::WordWrap)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR);
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .with_flex_child(name_label, 1.0)
+                .with_default_spacer()
+                .with_child(edit_button),
+        )
+        .with_child(Either::new(
+            |data: &AppState, _| {
+                data.playlist_detail
+                    .playlist
+                    .resolved()
+                    .is_some_and(|playlist| !playlist.description.is_empty())
+            },
+            Flex::column()
+                .with_default_spacer()
+                .with_child(description_label),
+            Empty,
+        ))
+}
+
+fn visibility_menu(playlist: &Playlist) -> Menu<AppState> {
+    let current = playlist.visibility();
+    let mut menu = Menu::empty();
+    for visibility in PlaylistVisibility::ALL {
+        menu = menu.entry(
+            MenuItem::new(visibility.label())
+                .selected(visibility == current)
+                .command(SET_PLAYLIST_VISIBILITY.with((playlist.link(), visibility))),
+        );
+    }
+    menu
+}
+
+fn details_editor_widget() -> impl Widget<AppState> {
+    let fields = Maybe::or_empty(|| {
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(
+                TextBox::new()
+                    .with_placeholder("Name")
+                    .with_text_size(theme::TEXT_SIZE_LARGE)
+                    .expand_width()
+                    .lens(PlaylistEdit::name),
+            )
+            .with_default_spacer()
+            .with_child(
+                TextBox::multiline()
+                    .with_placeholder("Add an optional description")
+                    .expand_width()
+                    .lens(PlaylistEdit::description),
+            )
+    })
+    .lens(AppState::playlist_detail.then(PlaylistDetail::edit));
+
+    let save_button = Button::new("Save")
+        .on_click(|ctx, data: &mut AppState, _| {
+            if let Some(edit) = &data.playlist_detail.edit {
+                ctx.submit_command(SAVE_PLAYLIST_DETAILS.with(edit.clone()));
+            }
+        })
+        .disabled_if(|data: &AppState, _| {
+            data.playlist_detail
+                .edit
+                .as_ref()
+                .map_or(true, |edit| edit.name.trim().is_empty())
+        });
+    let cancel_button = Button::new("Cancel").on_click(|_, data: &mut AppState, _| {
+        data.playlist_detail.edit = None;
+    });
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(fields)
+        .with_default_spacer()
+        .with_child(
+            Flex::row()
+                .with_child(save_button)
+                .with_default_spacer()
+                .with_child(cancel_button),
+        )
+}
+
 fn async_playlist_info_widget() -> impl Widget<AppState> {
     Async::new(utils::spinner_widget, playlist_info_widget, || Empty)
         .lens(
@@ -456,7 +1042,10 @@ fn async_playlist_info_widget() -> impl Widget<AppState> {
         .on_command_async(
             LOAD_DETAIL,
             |d| WebApi::global().get_playlist(&d.0.id),
-            |_, data, d| data.playlist_detail.playlist.defer(d.0),
+            |_, data, d| {
+                data.playlist_detail.edit = None;
+                data.playlist_detail.playlist.defer(d.0)
+            },
             |_, data, (d, r)| data.playlist_detail.playlist.update((d.0, r)),
         )
 }
@@ -475,27 +1064,6 @@ fn playlist_info_widget() -> impl Widget<WithCtx<Playlist>> {
 
     let owner_label = Label::dynamic(|p: &Playlist, _| p.owner.display_name.as_ref().to_string());
 
-    let track_count_label = Label::dynamic(|p: &Playlist, _| {
-        let count = p.track_count.unwrap_or(0);
-        if count == 1 {
-            "1 song".to_string()
-        } else {
-            format!("{count} songs")
-        }
-    })
-    .with_text_size(theme::TEXT_SIZE_SMALL);
-
-    let description_widget = Either::new(
-        |p: &Playlist, _| !p.description.is_empty(),
-        Flex::column().with_default_spacer().with_child(
-            Label::dynamic(|p: &Playlist, _| p.description.to_string())
-                .with_line_break_mode(LineBreaking::WordWrap)
-                .with_text_size(theme::TEXT_SIZE_SMALL)
-                .with_text_color(theme::PLACEHOLDER_COLOR),
-        ),
-        Empty,
-    );
-
     let visibility_widget = Either::new(
         |p: &Playlist, _| p.public.is_some() || p.collaborative,
         Flex::column().with_default_spacer().with_child(
@@ -521,16 +1089,57 @@ fn playlist_info_widget() -> impl Widget<WithCtx<Playlist>> {
     let playlist_info = Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_child(owner_label)
-        .with_default_spacer()
-        .with_child(track_count_label)
-        .with_child(description_widget)
         .with_child(visibility_widget)
-        .padding(theme::grid(1.0));
+        .lens(Ctx::data());
 
     Flex::row()
         .with_child(playlist_cover)
         .with_default_spacer()
-        .with_child(playlist_info.lens(Ctx::data()))
+        .with_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(playlist_info)
+                .with_child(sync_badge_widget(false))
+                .padding(theme::grid(1.0)),
+        )
+}
+
+/// Download state of a playlist marked for offline listening, `compact` for
+/// the sidebar.  Empty for the other playlists.
+fn sync_badge_widget(compact: bool) -> impl Widget<WithCtx<Playlist>> {
+    let badge = Label::dynamic(move |playlist: &WithCtx<Playlist>, _| {
+        let Some(offline) = playlist.ctx.offline_playlists.get(&playlist.data.id) else {
+            return String::new();
+        };
+        let status = offline.status(&playlist.data);
+        match (status, compact) {
+            (SyncStatus::Synced, true) => "\u{2713}".to_string(),
+            (SyncStatus::Synced, false) => "Downloaded".to_string(),
+            (SyncStatus::Syncing { .. }, true) => format!("{}%", status.percent().unwrap_or(0)),
+            (SyncStatus::Syncing { .. }, false) => {
+                format!("Downloading\u{2026} {}%", status.percent().unwrap_or(0))
+            }
+            (SyncStatus::OutOfDate, true) => "\u{21bb}".to_string(),
+            (SyncStatus::OutOfDate, false) => "Download out of date".to_string(),
+        }
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR);
+
+    Either::new(
+        |playlist: &WithCtx<Playlist>, _| {
+            playlist
+                .ctx
+                .offline_playlists
+                .contains_key(&playlist.data.id)
+        },
+        if compact {
+            badge.padding((theme::grid(0.5), 0.0, 0.0, 0.0)).boxed()
+        } else {
+            badge.padding((0.0, theme::grid(1.0), 0.0, 0.0)).boxed()
+        },
+        Empty,
+    )
 }
 
 fn async_tracks_widget() -> impl Widget<AppState> {
@@ -547,7 +1156,12 @@ fn async_tracks_widget() -> impl Widget<AppState> {
             |arg: (PlaylistLink, AppState)| {
                 let d = arg.0;
                 let data = arg.1;
-                sort_playlist(&data, WebApi::global().get_playlist_tracks(&d.id))
+                let webapi = WebApi::global();
+                let result = webapi.get_playlist_tracks(&d.id).map(|mut tracks| {
+                    webapi.load_playlist_members(&mut tracks);
+                    tracks
+                });
+                sort_playlist(&data, result)
             },
             |_, data, d| data.playlist_detail.tracks.defer(d.0),
             |_, data, (d, r)| {
@@ -559,57 +1173,157 @@ fn async_tracks_widget() -> impl Widget<AppState> {
                 data.playlist_detail.tracks.update((d.0, Ok(tracks)))
             },
         )
+        .on_command(DROP_TRACK, |ctx, (link, track, to), data| {
+            let writable = data
+                .playlist_detail
+                .playlist
+                .resolved()
+                .is_some_and(|playlist| {
+                    playlist.id == link.id && data.library.is_playlist_writable(playlist)
+                });
+            if !writable {
+                return;
+            }
+            let from = data.playlist_detail.tracks.resolved().and_then(|tracks| {
+                tracks
+                    .tracks
+                    .iter()
+                    .find(|t| Arc::ptr_eq(t, track))
+                    .map(|t| t.track_pos)
+            });
+            match from {
+                // Dragged within the playlist, the rows have to be in the
+                // playlist order for the drop position to mean anything.
+                Some(_) if data.config.playlist_sort != TableSort::PLAYLIST_ORDER => {
+                    data.info_alert(
+                        "Sort the playlist by date added, oldest first, to reorder it.",
+                    );
+                }
+                Some(from) if from != *to => {
+                    ctx.submit_command(MOVE_TRACK.with(PlaylistMoveTrack {
+                        link: link.clone(),
+                        from,
+                        to: *to,
+                    }));
+                }
+                Some(_) => {}
+                None => ctx.submit_command(ADD_TRACKS.with(PlaylistAddTracks {
+                    link: link.clone(),
+                    track_ids: Vector::unit(track.id),
+                })),
+            }
+        })
+        .on_command_async(
+            MOVE_TRACK,
+            |mv| WebApi::global().reorder_playlist_track(&mv.link.id, mv.from, mv.insert_before()),
+            |_, data, mv| {
+                if let Some(tracks) = data.playlist_detail.tracks.resolved_mut() {
+                    if tracks.id == mv.link.id {
+                        tracks.move_track(mv.from, mv.to);
+                    }
+                }
+            },
+            |ctx, data, (mv, r)| {
+                if let Err(err) = r {
+                    data.error_alert(err);
+                    // Reload to show the order that is actually saved.
+                    ctx.submit_command(LOAD_DETAIL.with((mv.link, data.clone())));
+                } else {
+                    data.info_alert_with_action(
+                        format!("Moved in {}.", mv.link.name),
+                        "Undo",
+                        MOVE_TRACK.with(mv.undo()),
+                    );
+                }
+            },
+        )
 }
 
 fn tracks_widget() -> impl Widget<WithCtx<PlaylistTracks>> {
-    playable::list_widget_with_find(
-        playable::Display {
-            track: track::Display {
-                title: true,
-                artist: true,
-                album: true,
-                cover: true,
-                ..track::Display::empty()
-            },
-        },
-        cmd::FIND_IN_PLAYLIST,
-    )
-}
+    // Computed from the loaded tracks, so that it follows tracks being added
+    // and removed.
+    let summary = Label::dynamic(|tracks: &PlaylistTracks, _| {
+        let summary = utils::track_list_summary(&tracks.tracks);
+        match tracks.last_updated() {
+            Some(date) => format!("{summary} • Updated {}", format::date(date)),
+            None => summary,
+        }
+    })
+    .with_line_break_mode(LineBreaking::WordWrap)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR)
+    .padding((theme::grid(2.0), 0.0, theme::grid(2.0), theme::grid(1.0)))
+    .lens(Ctx::data());
 
-fn sort_playlist(data: &AppState, result: Result<Vector<Arc<Track>>, Error>) -> Vector<Arc<Track>> {
-    let sort_criteria = data.config.sort_criteria;
-    let sort_order = data.config.sort_order;
+    let members = Either::new(
+        |tracks: &PlaylistTracks, _| tracks.tracks.iter().any(|t| t.added_by.is_some()),
+        List::new(member_widget)
+            .horizontal()
+            .with_spacing(theme::grid(1.5))
+            .padding((theme::grid(2.0), 0.0, theme::grid(2.0), theme::grid(1.0)))
+            .lens(Map::new(
+                |tracks: &PlaylistTracks| tracks.members(),
+                |_, _| {},
+            )),
+        Empty,
+    )
+    .lens(Ctx::data());
 
-    let playlist = result.unwrap_or_else(|_| Vector::new());
+    let tracks = playable::list_widget(playable::Display {
+        track: track::Display {
+            title: true,
+            artist: true,
+            album: true,
+            added_by: true,
+            table: true,
+            ..track::Display::empty()
+        },
+    });
 
-    let sorted_playlist: Vector<Arc<Track>> = playlist
-        .into_iter()
-        .sorted_by(|a, b| {
-            let method = match sort_criteria {
-                SortCriteria::Title => a.name.cmp(&b.name),
-                SortCriteria::Artist => a.artist_name().cmp(&b.artist_name()),
-                SortCriteria::Album => a.album_name().cmp(&b.album_name()),
-                SortCriteria::Duration => a.duration.cmp(&b.duration),
-                SortCriteria::DateAdded => Ordering::Equal,
-            };
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(summary)
+        .with_child(members)
+        .with_child(tracks)
+}
 
-            if sort_order == SortOrder::Descending {
-                method.reverse()
-            } else {
-                method
-            }
-        })
-        .collect();
+/// Member of a collaborative playlist, with the count of tracks they added.
+fn member_widget() -> impl Widget<(Arc<PublicUserProfile>, usize)> {
+    Flex::row()
+        .with_child(user::avatar_widget(theme::grid(3.0)).lens(Map::new(
+            |(user, _): &(Arc<PublicUserProfile>, usize)| user.clone(),
+            |_, _| {},
+        )))
+        .with_spacer(theme::grid(0.75))
+        .with_child(
+            Label::dynamic(|(user, count): &(Arc<PublicUserProfile>, usize), _| {
+                format!("{} \u{2022} {count}", user.name())
+            })
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR),
+        )
+}
 
-    if sort_criteria == SortCriteria::DateAdded && sort_order == SortOrder::Descending {
-        sorted_playlist.into_iter().rev().collect()
-    } else {
-        sorted_playlist
+fn sort_playlist(data: &AppState, result: Result<Vector<Arc<Track>>, Error>) -> Vector<Arc<Track>> {
+    let sort = data.config.playlist_sort;
+    let playlist = result.unwrap_or_else(|_| Vector::new());
+    match sort.column {
+        // Sorted by position, which follows the date added, but also the
+        // tracks moved by hand.
+        TrackColumn::DateAdded if sort.order == SortOrder::Descending => {
+            playlist.into_iter().rev().collect()
+        }
+        TrackColumn::DateAdded => playlist,
+        _ => playlist
+            .into_iter()
+            .sorted_by(|a, b| sort.compare(a, b))
+            .collect(),
     }
 }
 
 fn playlist_menu_ctx(playlist: &WithCtx<Playlist>) -> Menu<AppState> {
     let library = &playlist.ctx.library;
+    let offline_playlists = &playlist.ctx.offline_playlists;
     let playlist = &playlist.data;
 
     let mut menu = Menu::empty();
@@ -621,9 +1335,46 @@ fn playlist_menu_ctx(playlist: &WithCtx<Playlist>) -> Menu<AppState> {
         .command(cmd::COPY.with(playlist.url())),
     );
 
+    menu = menu.entry(
+        MenuItem::new(LocalizedString::new("menu-item-play-next").with_placeholder("Play Next"))
+            .command(cmd::QUEUE_PLAYLIST.with((playlist.link(), QueuePlacement::Next))),
+    );
+    menu = menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-add-to-queue")
+                .with_placeholder("Add Playlist to Queue"),
+        )
+        .command(cmd::QUEUE_PLAYLIST.with((playlist.link(), QueuePlacement::Last))),
+    );
+    menu = menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-show-playlist-radio")
+                .with_placeholder("Go to Playlist Radio"),
+        )
+        .command(cmd::SHOW_PLAYLIST_RADIO.with(playlist.link())),
+    );
+
     if library.contains_playlist(playlist) {
         let created_by_user = library.is_created_by_user(playlist);
 
+        if offline_playlists.contains_key(&playlist.id) {
+            menu = menu.entry(
+                MenuItem::new(
+                    LocalizedString::new("menu-remove-playlist-download")
+                        .with_placeholder("Remove Download"),
+                )
+                .command(cmd::SET_PLAYLIST_OFFLINE.with((playlist.clone(), false))),
+            );
+        } else {
+            menu = menu.entry(
+                MenuItem::new(
+                    LocalizedString::new("menu-download-playlist")
+                        .with_placeholder("Download for Offline Listening"),
+                )
+                .command(cmd::SET_PLAYLIST_OFFLINE.with((playlist.clone(), true))),
+            );
+        }
+
         if created_by_user {
             let unfollow_msg = UnfollowPlaylist {
                 link: playlist.link(),
@@ -638,10 +1389,10 @@ fn playlist_menu_ctx(playlist: &WithCtx<Playlist>) -> Menu<AppState> {
             );
             menu = menu.entry(
                 MenuItem::new(
-                    LocalizedString::new("menu-rename-playlist")
-                        .with_placeholder("Rename playlist"),
+                    LocalizedString::new("menu-edit-playlist")
+                        .with_placeholder("Rename or edit description"),
                 )
-                .command(SHOW_RENAME_PLAYLIST_CONFIRM.with(playlist.link())),
+                .command(SHOW_EDIT_PLAYLIST.with(PlaylistEdit::new(playlist))),
             );
         } else {
             let unfollow_msg = UnfollowPlaylist {
@@ -668,6 +1419,14 @@ fn playlist_menu_ctx(playlist: &WithCtx<Playlist>) -> Menu<AppState> {
     menu
 }
 
+/// Menu of a playlist in the sidebar, which also creates playlists.
+fn sidebar_playlist_menu_ctx(playlist: &WithCtx<Playlist>) -> Menu<AppState> {
+    playlist_menu_ctx(playlist).separator().entry(
+        MenuItem::new(LocalizedString::new("menu-new-playlist").with_placeholder("New Playlist…"))
+            .command(SHOW_CREATE_PLAYLIST),
+    )
+}
+
 #[derive(Clone)]
 struct UnfollowPlaylist {
     link: PlaylistLink,
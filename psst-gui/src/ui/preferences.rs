@@ -1,16 +1,25 @@
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
     thread::{self, JoinHandle},
     time::Duration,
 };
 
 use crate::{
     cmd,
+    controller::DragToReorder,
     data::{
-        AppState, AudioQuality, Authentication, Config, CustomTheme, Preferences, PreferencesTab,
-        Promise, SliderScrollScale, Theme, UpdatePreferences,
+        AppState, AudioQuality, Authentication, ClockStats, Config, Crossfade, CustomTheme,
+        GeneratedPlaylists, InterruptionBehavior, KeyCombination, KeybindAction, KeybindCategory,
+        MeteredBehavior, NotificationSettings, NotificationSound, OutputLatency, PlaybackBarLayout,
+        PlaybackClock, Preferences, PreferencesTab, PresenceIdle, Promise, ProxyKind,
+        ProxySettings, ScrobbleAfter, ScrobbleSettings, SliderScrollScale, Theme, TimeOfDay,
+        UpdatePreferences,
     },
-    widget::{icons, Async, Border, Checkbox, MyWidgetExt},
+    discord::DiscordState,
+    format, notifications,
+    webapi::{ApiFeature, ApiStatus},
+    widget::{icons, Async, Border, Checkbox, MyWidgetExt, ThemeScope},
 };
 use druid::{
     text::ParseFormatter,
@@ -18,14 +27,49 @@ use druid::{
         Button, Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking,
         MainAxisAlignment, Painter, RadioGroup, Scroll, SizedBox, Slider, TextBox, ViewSwitcher,
     },
-    Color, Data, Env, Event, EventCtx, Insets, Lens, LensExt, LifeCycle, LifeCycleCtx,
-    RenderContext, Selector, Target, Widget, WidgetExt,
+    Color, Data, Env, Event, EventCtx, Insets, KbKey, Lens, LensExt, LifeCycle, LifeCycleCtx,
+    RenderContext, Selector, Target, Widget, WidgetExt, WindowDesc,
+};
+use psst_core::{
+    audio::{
+        generator::TestSignal, output::DefaultAudioOutput, processor::DspStage,
+        resample::ResamplingQuality,
+    },
+    cache::{
+        CacheCategory, CacheHandle, CacheScan, CacheScanUpdate, CacheUsage, CacheVerification,
+    },
+    connection::{Credentials, Transport},
+    lastfm, oauth,
+    session::SessionConfig,
 };
-use psst_core::{connection::Credentials, lastfm, oauth, session::SessionConfig};
 
-use super::{icons::SvgIcon, theme};
+use super::{icons::SvgIcon, menu, playlist, theme};
 
 const CLEAR_CACHE: Selector = Selector::new("app.preferences.clear-cache");
+const CLEAR_CACHE_CATEGORY: Selector<CacheCategory> =
+    Selector::new("app.preferences.clear-cache-category");
+const VERIFY_CACHE: Selector = Selector::new("app.preferences.verify-cache");
+const TEST_PROXY: Selector<Option<String>> = Selector::new("app.preferences.test-proxy");
+const CAPTURE_KEYBIND: Selector<KeybindAction> = Selector::new("app.preferences.capture-keybind");
+
+/// Resampler qualities offered in the preferences.
+const RESAMPLING_QUALITIES: [(&str, ResamplingQuality); 3] = [
+    ("Fast (lowest CPU usage)", ResamplingQuality::SincFastest),
+    ("Medium", ResamplingQuality::SincMediumQuality),
+    (
+        "High (best interpolation)",
+        ResamplingQuality::SincBestQuality,
+    ),
+];
+
+/// Position of `quality` in `RESAMPLING_QUALITIES`.  Qualities only set in the
+/// config file show up as the default one.
+fn resampling_quality_index(quality: ResamplingQuality) -> usize {
+    let position = |quality| RESAMPLING_QUALITIES.iter().position(|(_, q)| *q == quality);
+    position(quality)
+        .or_else(|| position(ResamplingQuality::default()))
+        .unwrap_or(0)
+}
 
 // Helper function for creating a labeled input row
 fn make_input_row<L>(
@@ -72,6 +116,8 @@ pub fn account_setup_widget() -> impl Widget<AppState> {
         )
         .with_spacer(theme::grid(6.0))
         .with_child(account_tab_widget(AccountTab::FirstSetup).expand_width())
+        .with_spacer(theme::grid(6.0))
+        .with_child(test_signals_widget())
         .padding(theme::grid(4.0))
 }
 
@@ -98,7 +144,10 @@ pub fn preferences_widget() -> impl Widget<AppState> {
                         account_tab_widget(AccountTab::InPreferences).boxed()
                     }
                     PreferencesTab::DiscordPresence => discord_presence_tab_widget().boxed(),
+                    PreferencesTab::Keybinds => keybinds_tab_widget().boxed(),
+                    PreferencesTab::Network => network_tab_widget().boxed(),
                     PreferencesTab::Cache => cache_tab_widget().boxed(),
+                    PreferencesTab::Diagnostics => diagnostics_tab_widget().boxed(),
                     PreferencesTab::Updates => updates_tab_widget().boxed(),
                     PreferencesTab::About => about_tab_widget().boxed(),
                 },
@@ -173,12 +222,30 @@ fn tabs_widget() -> impl Widget<AppState> {
             PreferencesTab::DiscordPresence,
         ))
         .with_default_spacer()
+        .with_child(tab_link_widget(
+            "Keybinds",
+            &icons::PREFERENCES,
+            PreferencesTab::Keybinds,
+        ))
+        .with_default_spacer()
+        .with_child(tab_link_widget(
+            "Network",
+            &icons::PREFERENCES,
+            PreferencesTab::Network,
+        ))
+        .with_default_spacer()
         .with_child(tab_link_widget(
             "Cache",
             &icons::STORAGE,
             PreferencesTab::Cache,
         ))
         .with_default_spacer()
+        .with_child(tab_link_widget(
+            "Diagnostics",
+            &icons::PREFERENCES,
+            PreferencesTab::Diagnostics,
+        ))
+        .with_default_spacer()
         .with_child(tab_link_widget(
             "Updates",
             &icons::CIRCLE_PLUS,
@@ -221,18 +288,273 @@ fn general_tab_widget() -> impl Widget<AppState> {
     // Audio quality
     col = col
         .with_child(Label::new("Audio quality").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Items not offered in the chosen quality play in the closest one available.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
         .with_spacer(theme::grid(2.0))
         .with_child(
             RadioGroup::column(vec![
+                ("Automatic (by connection speed)", AudioQuality::Auto),
                 ("Low (96kbit)", AudioQuality::Low),
                 ("Normal (160kbit)", AudioQuality::Normal),
                 ("High (320kbit)", AudioQuality::High),
+                ("Lossless (FLAC, when available)", AudioQuality::Lossless),
             ])
             .lens(AppState::config.then(Config::audio_quality)),
         );
 
     col = col.with_spacer(theme::grid(3.0));
 
+    // Resampling quality
+    col = col
+        .with_child(Label::new("Resampling quality").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Used only when the output device cannot switch to the sample rate of the track.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(
+                RESAMPLING_QUALITIES
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (label, _))| (*label, index))
+                    .collect::<Vec<_>>(),
+            )
+            .lens(AppState::config.then(Config::resampling_quality).map(
+                |quality| resampling_quality_index(*quality),
+                |quality, index| *quality = RESAMPLING_QUALITIES[index].1,
+            )),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Crossfade
+    col = col
+        .with_child(Label::new("Crossfade").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "The next track starts while the current one fades out.  When Spotify has an \
+                 analysis of the tracks, the fades are kept to their quiet intro and outro, \
+                 otherwise they last the full time chosen below.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("Off", Crossfade::Off),
+                ("Up to 3 seconds", Crossfade::Short),
+                ("Up to 6 seconds", Crossfade::Medium),
+                ("Up to 10 seconds", Crossfade::Long),
+            ])
+            .lens(AppState::config.then(Config::crossfade)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Autoplay
+    col = col
+        .with_child(Label::new("Autoplay").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "When the queue is about to end in sequential mode, recommended tracks based \
+                 on the last one are added so that the music keeps playing.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.5))
+        .with_child(
+            Checkbox::new("Autoplay similar tracks").lens(AppState::config.then(Config::autoplay)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Duplicates
+    col = col
+        .with_child(Label::new("Duplicates").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Tracks that are already playing or up next are left out when queueing. \
+                 Duplicates already in the queue can be removed from the Up Next panel.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.5))
+        .with_child(
+            Checkbox::new("Skip tracks already in the queue")
+                .lens(AppState::config.then(Config::deduplicate_queue)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Previous
+    col = col
+        .with_child(Label::new("Previous").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Previous restarts the track after its first few seconds, and goes to the \
+                 previous track before that. Pressing it twice quickly can instead go back to \
+                 the first track of the album or playlist.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.5))
+        .with_child(
+            Checkbox::new("Double-press Previous to restart the album or playlist")
+                .lens(AppState::config.then(Config::double_press_previous)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Sleep
+    col = col
+        .with_child(Label::new("Sleep").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "While music is playing, the computer does not go to sleep on its own. The \
+                 display can still turn off.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.5))
+        .with_child(
+            Checkbox::new("Prevent sleep while playing")
+                .lens(AppState::config.then(Config::prevent_sleep)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // System tray
+    col = col
+        .with_child(Label::new("System tray").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "The tray icon on Windows, or the menu bar icon on macOS, controls the playback \
+                 and shows the playing track.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.5))
+        .with_child(
+            Checkbox::new("Keep playing in the background when the window is closed")
+                .lens(AppState::config.then(Config::play_in_background)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::dynamic(|data: &AppState, _| {
+                let quit = match data.config.keybinds.get(KeybindAction::Quit) {
+                    Some(combination) => format!(", or with {combination}"),
+                    None => String::new(),
+                };
+                format!(
+                    "Without a tray, as on Linux, the window stays in the taskbar instead. Quit \
+                     Psst from the tray{quit}."
+                )
+            })
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Interruptions
+    col = col
+        .with_child(Label::new("Other applications").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Playback can step aside when another application starts playing audio or a \
+                 call starts. Available on Windows and macOS 14 or later.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("Keep playing", InterruptionBehavior::KeepPlaying),
+                ("Pause", InterruptionBehavior::Pause),
+                (
+                    "Pause and resume afterwards",
+                    InterruptionBehavior::PauseAndResume,
+                ),
+            ])
+            .lens(AppState::config.then(Config::interruption_behavior)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Output latency
+    col = col
+        .with_child(
+            Label::new("Output latency (requires restart)").with_font(theme::UI_FONT_MEDIUM),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Smaller buffers react faster, larger ones are more robust on slow hardware. \
+                 The size is clamped to what the output device supports.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("Device default", OutputLatency::Default),
+                ("Low (20 ms)", OutputLatency::Low),
+                ("Medium (100 ms)", OutputLatency::Medium),
+                ("High (500 ms)", OutputLatency::High),
+            ])
+            .lens(AppState::config.then(Config::output_latency)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Output devices
+    col = col
+        .with_child(Label::new("Output devices").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "The volume is remembered for every output device, and restored when \
+                 playing to it again.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(output_device_widget())
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Label::dynamic(|data: &AppState, _| format_device_volumes(data))
+                .with_line_break_mode(LineBreaking::WordWrap),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
     // Sliders
     col = col
         .with_child(Label::new("Slider Scrolling").with_font(theme::UI_FONT_MEDIUM))
@@ -265,31 +587,268 @@ fn general_tab_widget() -> impl Widget<AppState> {
         .with_child(
             Flex::row()
                 .with_child(
-                    TextBox::new().with_formatter(ParseFormatter::with_format_fn(
-                        |usize: &usize| usize.to_string(),
-                    )),
+                    TextBox::new().with_formatter(ParseFormatter::with_format_fn(
+                        |usize: &usize| usize.to_string(),
+                    )),
+                )
+                .lens(AppState::config.then(Config::seek_duration)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col.with_child(notifications_widget());
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col.with_child(proxy_widget());
+
+    col
+}
+
+/// Choice of the output device, listing the devices connected when the
+/// preferences are opened.
+fn output_device_widget() -> impl Widget<AppState> {
+    let devices = DefaultAudioOutput::devices().unwrap_or_else(|err| {
+        log::error!("failed to list the output devices: {err}");
+        Vec::new()
+    });
+    let mut choices = vec![("System default".to_string(), None)];
+    choices.extend(
+        devices
+            .into_iter()
+            .map(|device| (device.name, Some(device.id))),
+    );
+    ViewSwitcher::new(
+        |data: &AppState, _| data.config.output_device.clone(),
+        move |chosen, _, _| {
+            let mut choices = choices.clone();
+            // Keep a chosen device that is not connected right now selectable.
+            if let Some(id) = chosen {
+                if !choices
+                    .iter()
+                    .any(|(_, device)| device.as_ref() == Some(id))
+                {
+                    choices.push((format!("{id} (not connected)"), Some(id.clone())));
+                }
+            }
+            RadioGroup::column(choices)
+                .lens(AppState::config.then(Config::output_device))
+                .boxed()
+        },
+    )
+}
+
+fn format_device_volumes(data: &AppState) -> String {
+    if data.config.device_volumes.is_empty() {
+        return "No device volumes remembered yet.".to_string();
+    }
+    let mut devices: Vec<_> = data.config.device_volumes.iter().collect();
+    devices.sort_by(|(a, _), (b, _)| a.cmp(b));
+    devices
+        .into_iter()
+        .map(|(device, volume)| {
+            let percent = (volume * 100.0).floor();
+            if data.playback.output_device.as_deref() == Some(device.as_str()) {
+                format!("{device}: {percent}% (in use)")
+            } else {
+                format!("{device}: {percent}%")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn notifications_widget() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Notifications").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Short, quiet sounds can play over the music when something happens. During \
+                 the quiet hours, no sounds play, and the alerts of downloads and suggestions \
+                 are held back until they are over.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(notification_sound_widget(
+            "Track change",
+            NotificationSettings::track_change_sound,
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(notification_sound_widget(
+            "Download complete",
+            NotificationSettings::download_complete_sound,
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(notification_sound_widget(
+            "Error",
+            NotificationSettings::error_sound,
+        ))
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Checkbox::new("Quiet hours").lens(
+                AppState::config
+                    .then(Config::notifications)
+                    .then(NotificationSettings::quiet_hours),
+            ),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Flex::row()
+                .with_child(quiet_time_widget("From", NotificationSettings::quiet_from))
+                .with_spacer(theme::grid(2.0))
+                .with_child(quiet_time_widget(
+                    "until",
+                    NotificationSettings::quiet_until,
+                ))
+                .disabled_if(|data: &AppState, _| !data.config.notifications.quiet_hours),
+        )
+}
+
+fn quiet_time_widget(
+    label: &'static str,
+    time: impl Lens<NotificationSettings, TimeOfDay> + 'static,
+) -> impl Widget<AppState> {
+    Flex::row()
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .with_child(Label::new(label))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            TextBox::new()
+                .with_formatter(ParseFormatter::new())
+                .fix_width(theme::grid(8.0)),
+        )
+        .lens(AppState::config.then(Config::notifications).then(time))
+}
+
+fn notification_sound_widget(
+    name: &'static str,
+    sound: impl Lens<NotificationSettings, NotificationSound> + Copy + 'static,
+) -> impl Widget<AppState> {
+    let chime = move |data: &AppState| sound.get(&data.config.notifications).chime();
+    Flex::row()
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .with_child(
+            SizedBox::new(Label::new(name))
+                .width(theme::grid(18.0))
+                .align_left(),
+        )
+        .with_child(
+            RadioGroup::row(vec![
+                ("None", NotificationSound::None),
+                ("Ping", NotificationSound::Ping),
+                ("Rising", NotificationSound::Rising),
+                ("Falling", NotificationSound::Falling),
+            ])
+            .lens(AppState::config.then(Config::notifications).then(sound)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Button::new("Preview")
+                .on_click(move |_, data: &mut AppState, _| {
+                    if let Some(chime) = chime(data) {
+                        notifications::play_sound(chime, data.config.output_device.clone());
+                    }
+                })
+                .disabled_if(move |data, _| chime(data).is_none()),
+        )
+}
+
+fn proxy_widget() -> impl Widget<AppState> {
+    let proxy = || AppState::config.then(Config::proxy);
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Proxy").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Used for the connection to Spotify, the audio downloads and the Web API. \
+                 The SOCKS_PROXY environment variable takes precedence over these settings.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("No proxy", ProxyKind::None),
+                ("HTTP", ProxyKind::Http),
+                ("SOCKS5", ProxyKind::Socks5),
+            ])
+            .lens(proxy().then(ProxySettings::kind)),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(make_input_row(
+            "Host",
+            "proxy.example.com",
+            proxy().then(ProxySettings::host),
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Flex::row()
+                .with_child(
+                    SizedBox::new(Label::new("Port"))
+                        .width(theme::grid(12.0))
+                        .align_left(),
                 )
-                .lens(AppState::config.then(Config::seek_duration)),
-        );
-
-    col = col.with_spacer(theme::grid(3.0));
-
-    col = col
-        .with_child(
-            Label::new("Max Loaded Tracks (requires restart)").with_font(theme::UI_FONT_MEDIUM),
+                .with_child(
+                    TextBox::new()
+                        .with_formatter(ParseFormatter::with_format_fn(|port: &u16| {
+                            port.to_string()
+                        }))
+                        .fix_width(theme::grid(10.0)),
+                )
+                .lens(proxy().then(ProxySettings::port)),
         )
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Username",
+            "Optional",
+            proxy().then(ProxySettings::username),
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Password",
+            "Optional",
+            proxy().then(ProxySettings::password),
+        ))
         .with_spacer(theme::grid(2.0))
         .with_child(
             Flex::row()
+                .with_child(Button::new("Apply").on_click(|ctx, _, _| {
+                    ctx.submit_command(cmd::APPLY_PROXY);
+                }))
+                .with_spacer(theme::grid(1.0))
+                .with_child(Button::new("Test Connection").on_click(
+                    |ctx, data: &mut AppState, _| {
+                        ctx.submit_command(TEST_PROXY.with(data.config.proxy_url()));
+                    },
+                ))
+                .with_spacer(theme::grid(1.0))
                 .with_child(
-                    TextBox::new().with_formatter(ParseFormatter::with_format_fn(
-                        |usize: &usize| usize.to_string(),
-                    )),
-                )
-                .lens(AppState::config.then(Config::paginated_limit)),
-        );
-
-    col
+                    Label::dynamic(|data: &AppState, _| match &data.preferences.proxy_test {
+                        Promise::Empty => String::new(),
+                        Promise::Deferred { .. } => "Connecting...".to_string(),
+                        Promise::Resolved { val, .. } => format!("Connected to {val}"),
+                        Promise::Rejected { err, .. } => format!("Failed: {err}"),
+                    })
+                    .with_text_color(theme::PLACEHOLDER_COLOR),
+                ),
+        )
+        .on_command_async(
+            TEST_PROXY,
+            |proxy_url| {
+                Transport::test_connection(proxy_url.as_deref())
+                    .map(Arc::<str>::from)
+                    .map_err(|err| err.to_string())
+            },
+            |_, data, proxy_url| data.preferences.proxy_test.defer(proxy_url),
+            |_, data, result| data.preferences.proxy_test.update(result),
+        )
 }
 
 fn appearance_tab_widget() -> impl Widget<AppState> {
@@ -328,6 +887,60 @@ fn appearance_tab_widget() -> impl Widget<AppState> {
                 .lens(AppState::config.then(Config::show_playlist_images)),
         );
 
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(Label::new("Playback bar").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                (
+                    "Expanded, with artwork and all controls",
+                    PlaybackBarLayout::Expanded,
+                ),
+                ("Compact, on a single row", PlaybackBarLayout::Compact),
+            ])
+            .lens(AppState::config.then(Config::playback_bar_layout)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new("The bar turns compact in narrow windows.")
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .with_line_break_mode(LineBreaking::WordWrap),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Generated playlists
+    col = col
+        .with_child(Label::new("Generated playlists").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "\"Your Top 50 This Month\" and \"Rediscover\" are built from the tracks played \
+                 in Psst and shown on the History page. They can also be saved as private \
+                 playlists in your Spotify account.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.5))
+        .with_child(
+            Checkbox::new("Save them to Spotify every week").lens(
+                AppState::config
+                    .then(Config::generated_playlists.then(GeneratedPlaylists::sync_to_spotify)),
+            ),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Button::new("Update Now")
+                .on_click(|ctx, _, _| ctx.submit_command(cmd::SYNC_GENERATED_PLAYLISTS))
+                .disabled_if(|data: &AppState, _| {
+                    !data.config.generated_playlists.sync_to_spotify
+                        || data.preferences.syncing_playlists
+                }),
+        );
+
     col
 }
 
@@ -580,25 +1193,50 @@ where
 }
 
 struct CacheController {
-    thread: Option<JoinHandle<()>>,
+    scan: Option<CacheScan>,
 }
 
 impl CacheController {
-    const RESULT: Selector<Option<u64>> = Selector::new("app.preferences.measure-cache-size");
+    const PROGRESS: Selector<Arc<CacheUsage>> =
+        Selector::new("app.preferences.measure-cache-usage-progress");
+    const RESULT: Selector<Option<Arc<CacheUsage>>> =
+        Selector::new("app.preferences.measure-cache-usage");
+    const VERIFIED: Selector<Result<Arc<CacheVerification>, String>> =
+        Selector::new("app.preferences.verify-cache-result");
 
     fn new() -> Self {
-        Self { thread: None }
+        Self { scan: None }
     }
 
-    fn start_measuring(&mut self, sink: druid::ExtEventSink, widget_id: druid::WidgetId) {
-        if self.thread.is_some() {
+    /// Scan the cache in the background, replacing any running scan.  The
+    /// scan stops when the preferences window closes.
+    fn start_measuring(
+        &mut self,
+        cache: Option<CacheHandle>,
+        sink: druid::ExtEventSink,
+        widget_id: druid::WidgetId,
+    ) {
+        let Some(cache) = cache else {
+            sink.submit_command(Self::RESULT, None, widget_id).unwrap();
             return;
-        }
-        let handle = thread::spawn(move || {
-            let size = Preferences::measure_cache_usage();
-            sink.submit_command(Self::RESULT, size, widget_id).unwrap();
-        });
-        self.thread.replace(handle);
+        };
+        self.scan = Some(cache.scan_usage(move |update| {
+            let result = match update {
+                CacheScanUpdate::Progress(usage) => {
+                    sink.submit_command(Self::PROGRESS, Arc::new(usage), widget_id)
+                }
+                CacheScanUpdate::Done(Ok(usage)) => {
+                    sink.submit_command(Self::RESULT, Some(Arc::new(usage)), widget_id)
+                }
+                CacheScanUpdate::Done(Err(err)) => {
+                    log::error!("Failed to measure cache usage: {err}");
+                    sink.submit_command(Self::RESULT, None, widget_id)
+                }
+            };
+            if let Err(err) = result {
+                log::error!("failed to submit cache usage: {err:?}");
+            }
+        }));
     }
 }
 
@@ -673,15 +1311,76 @@ impl<W: Widget<Preferences>> Controller<Preferences, W> for CacheController {
                         log::error!("Failed to clear cache: {err}");
                     } else {
                         // After clearing, re-measure the cache size.
-                        self.start_measuring(ctx.get_external_handle(), ctx.widget_id());
+                        data.cache_usage.defer(());
+                        self.start_measuring(
+                            data.cache.clone(),
+                            ctx.get_external_handle(),
+                            ctx.widget_id(),
+                        );
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(CLEAR_CACHE_CATEGORY) => {
+                let category = *cmd.get_unchecked(CLEAR_CACHE_CATEGORY);
+                if let Some(cache) = &data.cache {
+                    if let Err(err) = cache.clear_category(category) {
+                        log::error!("Failed to clear {} from cache: {err}", category.name());
+                    } else {
+                        data.cache_usage.defer(());
+                        self.start_measuring(
+                            data.cache.clone(),
+                            ctx.get_external_handle(),
+                            ctx.widget_id(),
+                        );
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(VERIFY_CACHE) => {
+                if let Some(cache) = data.cache.clone() {
+                    if !data.cache_verification.is_deferred(&()) {
+                        data.cache_verification.defer(());
+                        let sink = ctx.get_external_handle();
+                        let widget_id = ctx.widget_id();
+                        thread::spawn(move || {
+                            let result =
+                                cache.verify().map(Arc::new).map_err(|err| err.to_string());
+                            sink.submit_command(Self::VERIFIED, result, widget_id)
+                                .unwrap();
+                        });
                     }
                 }
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(Self::VERIFIED) => {
+                let result = cmd.get_unchecked(Self::VERIFIED).to_owned();
+                if let Err(err) = &result {
+                    log::error!("Failed to verify cache: {err}");
+                }
+                data.cache_verification.resolve_or_reject((), result);
+                // Broken files were removed, re-measure the cache size.
+                data.cache_usage.defer(());
+                self.start_measuring(
+                    data.cache.clone(),
+                    ctx.get_external_handle(),
+                    ctx.widget_id(),
+                );
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(Self::PROGRESS) => {
+                let usage = cmd.get_unchecked(Self::PROGRESS);
+                if !data.cache_usage.is_deferred(&()) {
+                    data.cache_usage.defer(());
+                }
+                data.cache_usage_progress = Some(usage.clone());
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(Self::RESULT) => {
                 let result = cmd.get_unchecked(Self::RESULT).to_owned();
-                data.cache_size.resolve_or_reject((), result.ok_or(()));
-                self.thread.take();
+                data.cache_usage.resolve_or_reject((), result.ok_or(()));
+                data.cache_usage_progress = None;
+                self.scan.take();
                 ctx.set_handled();
             }
             _ => {
@@ -699,7 +1398,11 @@ impl<W: Widget<Preferences>> Controller<Preferences, W> for CacheController {
         env: &Env,
     ) {
         if let LifeCycle::WidgetAdded = &event {
-            self.start_measuring(ctx.get_external_handle(), ctx.widget_id());
+            self.start_measuring(
+                data.cache.clone(),
+                ctx.get_external_handle(),
+                ctx.widget_id(),
+            );
         }
         child.lifecycle(ctx, event, data, env);
     }
@@ -791,6 +1494,15 @@ fn account_tab_widget(tab: AccountTab) -> impl Widget<AppState> {
 }
 
 fn lastfm_connected_view() -> impl Widget<AppState> {
+    let pending_scrobbles = Label::dynamic(|data: &AppState, _| match data.pending_scrobbles {
+        0 => "No scrobbles waiting to be sent.".to_string(),
+        1 => "1 scrobble waiting to be sent, it is retried automatically.".to_string(),
+        count => format!("{count} scrobbles waiting to be sent, they are retried automatically."),
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR)
+    .with_line_break_mode(LineBreaking::WordWrap);
+
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_child(
@@ -805,13 +1517,78 @@ fn lastfm_connected_view() -> impl Widget<AppState> {
                         data.config.lastfm_session_key = None;
                         data.config.lastfm_api_key = None;
                         data.config.lastfm_api_secret = None;
+                        data.config.lastfm_username = None;
                         data.config.save();
+                        data.with_library_mut(|library| library.lastfm_loved = None);
                         data.preferences.lastfm_auth_result = None;
                         data.preferences.auth.lastfm_api_key_input.clear();
                         data.preferences.auth.lastfm_api_secret_input.clear();
                     }),
                 ),
         )
+        .with_spacer(theme::grid(1.0))
+        .with_child(pending_scrobbles)
+        .with_spacer(theme::grid(2.0))
+        .with_child(scrobble_settings_widget())
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Checkbox::new("Show what friends listen to on the home page")
+                .lens(AppState::config.then(Config::lastfm_show_friends)),
+        )
+}
+
+fn scrobble_settings_widget() -> impl Widget<AppState> {
+    let scrobbling = || AppState::config.then(Config::scrobbling);
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Scrobble after").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("A share of the track", ScrobbleAfter::Percent),
+                ("A fixed time", ScrobbleAfter::Seconds),
+            ])
+            .lens(scrobbling().then(ScrobbleSettings::after)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(ViewSwitcher::new(
+            |data: &AppState, _| data.config.scrobbling.after,
+            move |after, _, _| match after {
+                ScrobbleAfter::Percent => amount_widget(
+                    "percent played",
+                    scrobbling().then(ScrobbleSettings::percent),
+                )
+                .boxed(),
+                ScrobbleAfter::Seconds => amount_widget(
+                    "seconds played, or half of shorter tracks",
+                    scrobbling().then(ScrobbleSettings::seconds),
+                )
+                .boxed(),
+            },
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Checkbox::new("Send \"now playing\" updates")
+                .lens(scrobbling().then(ScrobbleSettings::now_playing)),
+        )
+}
+
+fn amount_widget(
+    unit: &'static str,
+    lens: impl Lens<AppState, usize> + 'static,
+) -> impl Widget<AppState> {
+    Flex::row()
+        .with_child(
+            TextBox::new()
+                .with_formatter(ParseFormatter::with_format_fn(|amount: &usize| {
+                    amount.to_string()
+                }))
+                .fix_width(theme::grid(8.0)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(Label::new(unit))
+        .lens(lens)
 }
 
 fn lastfm_disconnected_view() -> impl Widget<AppState> {
@@ -926,7 +1703,10 @@ impl Authenticate {
 
         // Generate auth URL and store PKCE verifier
         let (auth_url, pkce_verifier) = oauth::generate_auth_url(8888);
-        let config = data.preferences.auth.session_config(); // Keep config local
+        let config = data
+            .preferences
+            .auth
+            .session_config(data.config.proxy_url()); // Keep config local
 
         // Spawn authentication thread
         self.spotify_thread = Authenticate::spawn_auth_thread(
@@ -990,7 +1770,7 @@ impl Authenticate {
     // Last.fm selectors
     pub const LASTFM_REQUEST: Selector =
         Selector::new("app.preferences.lastfm.authenticate-request");
-    pub const LASTFM_RESPONSE: Selector<Result<String, String>> =
+    pub const LASTFM_RESPONSE: Selector<Result<(String, String), String>> =
         Selector::new("app.preferences.lastfm.authenticate-response");
 }
 
@@ -1056,6 +1836,7 @@ impl<W: Widget<AppState>> Controller<AppState, W> for Authenticate {
                                 .map_err(|e| e.to_string())?;
                                 log::info!("received Last.fm token, exchanging...");
                                 lastfm::exchange_token_for_session(&api_key, &api_secret, &token)
+                                    .map(|session| (session.key, session.username))
                                     .map_err(|e| format!("Token exchange failed: {e}"))
                             },
                             Self::LASTFM_RESPONSE,
@@ -1101,7 +1882,7 @@ impl<W: Widget<AppState>> Controller<AppState, W> for Authenticate {
                         log::info!("OAuth: updating session config with new credentials");
                         data.session.update_config(SessionConfig {
                             login_creds: credentials.clone(),
-                            proxy_url: Config::proxy(),
+                            proxy_url: data.config.proxy_url(),
                         });
                         data.config.store_credentials(credentials.clone());
                         data.config.save();
@@ -1124,13 +1905,17 @@ impl<W: Widget<AppState>> Controller<AppState, W> for Authenticate {
             Event::Command(cmd) if cmd.is(Self::LASTFM_RESPONSE) => {
                 let result = cmd.get_unchecked(Self::LASTFM_RESPONSE);
                 match result {
-                    Ok(session_key) => {
+                    Ok((session_key, username)) => {
                         // On success, store the validated key/secret in config and save.
                         data.config.lastfm_api_key =
                             Some(data.preferences.auth.lastfm_api_key_input.clone());
                         data.config.lastfm_api_secret =
                             Some(data.preferences.auth.lastfm_api_secret_input.clone());
                         data.config.lastfm_session_key = Some(session_key.clone());
+                        data.config.lastfm_username = Some(username.clone());
+                        data.with_library_mut(|library| {
+                            library.lastfm_loved.get_or_insert_with(Default::default);
+                        });
                         data.config.save();
 
                         log::info!("Last.fm session key stored successfully.");
@@ -1151,19 +1936,248 @@ impl<W: Widget<AppState>> Controller<AppState, W> for Authenticate {
         }
     }
 
-    fn lifecycle(
-        &mut self,
-        child: &mut W,
-        ctx: &mut LifeCycleCtx,
-        event: &LifeCycle,
-        data: &AppState,
-        env: &Env,
-    ) {
-        if let LifeCycle::WidgetAdded = event {
-            ctx.submit_command(Self::INITIALIZE_LASTFM_FIELDS);
-        }
-        child.lifecycle(ctx, event, data, env);
-    }
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.submit_command(Self::INITIALIZE_LASTFM_FIELDS);
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
+}
+
+fn keybinds_tab_widget() -> impl Widget<AppState> {
+    let mut col = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .must_fill_main_axis(true);
+
+    col = col
+        .with_child(Label::new("Keyboard Shortcuts").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Click a shortcut and press the new keys. Escape cancels, Backspace removes the \
+                 shortcut.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        );
+
+    for category in KeybindCategory::ALL {
+        col = col
+            .with_spacer(theme::grid(3.0))
+            .with_child(Label::new(category.label()).with_font(theme::UI_FONT_MEDIUM))
+            .with_spacer(theme::grid(1.0));
+        for action in KeybindAction::ALL {
+            if action.category() == category {
+                col = col
+                    .with_child(keybind_row_widget(action))
+                    .with_spacer(theme::grid(0.5));
+            }
+        }
+    }
+
+    col.with_spacer(theme::grid(3.0))
+        .with_child(
+            Button::new("Restore Defaults").on_click(|_, data: &mut AppState, _| {
+                data.config.keybinds.reset_all();
+                data.preferences.capturing_keybind = None;
+            }),
+        )
+        .controller(KeybindCapture)
+}
+
+fn keybind_row_widget(action: KeybindAction) -> impl Widget<AppState> {
+    let combination = Label::dynamic(move |data: &AppState, _| {
+        if data.preferences.capturing_keybind == Some(action) {
+            "Press keys...".to_string()
+        } else {
+            match data.config.keybinds.get(action) {
+                Some(combination) => combination.to_string(),
+                None => "None".to_string(),
+            }
+        }
+    })
+    .padding((theme::grid(1.0), theme::grid(0.5)))
+    .link()
+    .border(theme::GREY_500, 1.0)
+    .rounded(theme::BUTTON_BORDER_RADIUS)
+    .active(move |data: &AppState, _| data.preferences.capturing_keybind == Some(action))
+    .on_left_click(move |ctx, _, _, _| {
+        ctx.submit_command(CAPTURE_KEYBIND.with(action));
+    });
+
+    let reset = Either::new(
+        move |data: &AppState, _| data.config.keybinds.is_changed(action),
+        Button::new("Reset").on_click(move |_, data: &mut AppState, _| {
+            data.config.keybinds.reset(action);
+        }),
+        SizedBox::empty(),
+    );
+
+    let conflict = Label::dynamic(move |data: &AppState, _| {
+        match data.config.keybinds.conflicting_action(action) {
+            Some(other) => format!("Also used for \"{}\"", other.label()),
+            None => String::new(),
+        }
+    })
+    .with_text_color(theme::RED)
+    .with_text_size(theme::TEXT_SIZE_SMALL);
+
+    Flex::row()
+        .with_child(Label::new(action.label()).fix_width(theme::grid(20.0)))
+        .with_child(combination)
+        .with_default_spacer()
+        .with_child(reset)
+        .with_default_spacer()
+        .with_child(conflict)
+}
+
+/// Takes the keys pressed while a shortcut in the Keybinds tab waits for its
+/// new combination.
+struct KeybindCapture;
+
+impl<W: Widget<AppState>> Controller<AppState, W> for KeybindCapture {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(CAPTURE_KEYBIND) => {
+                data.preferences.capturing_keybind = Some(*cmd.get_unchecked(CAPTURE_KEYBIND));
+                ctx.request_focus();
+                ctx.set_handled();
+            }
+            Event::KeyDown(key) if ctx.is_focused() => {
+                if let Some(action) = data.preferences.capturing_keybind {
+                    match key.key {
+                        KbKey::Escape => {}
+                        KbKey::Backspace => data.config.keybinds.set(action, None),
+                        _ => match KeyCombination::from_key_event(key) {
+                            Some(combination) => {
+                                data.config.keybinds.set(action, Some(combination));
+                            }
+                            // Only a modifier so far, wait for the key.
+                            None => return,
+                        },
+                    }
+                    data.preferences.capturing_keybind = None;
+                    ctx.resign_focus();
+                }
+                ctx.set_handled();
+            }
+            Event::MouseDown(_) if data.preferences.capturing_keybind.is_some() => {
+                data.preferences.capturing_keybind = None;
+                child.event(ctx, event, data, env);
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
+
+fn network_tab_widget() -> impl Widget<AppState> {
+    let mut col = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .must_fill_main_axis(true);
+
+    // Metered connections
+    col = col
+        .with_child(Label::new("Metered connections").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Mobile hotspots and other connections with a data plan can be marked as \
+                 metered in the network settings of your system.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::dynamic(|metered: &Option<bool>, _| {
+                match metered {
+                    Some(true) => "The current connection is metered.",
+                    Some(false) => "The current connection is not metered.",
+                    None => "Your system does not tell if the current connection is metered.",
+                }
+                .to_string()
+            })
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .lens(AppState::network_metered),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("Stream normally", MeteredBehavior::Ignore),
+                ("Warn before playing", MeteredBehavior::Warn),
+                (
+                    "Data saver (stream in low quality)",
+                    MeteredBehavior::DataSaver,
+                ),
+            ])
+            .lens(AppState::config.then(Config::metered_behavior)),
+        );
+
+    // Parallel downloads
+    col = col
+        .with_spacer(theme::grid(3.0))
+        .with_child(Label::new("Parallel downloads").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Tracks are downloaded in chunks over a few reused connections. Fetching more \
+                 chunks at once starts playback and seeking sooner on high-latency links.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("1 chunk", 1),
+                ("2 chunks", 2),
+                ("4 chunks (recommended)", 4),
+                ("8 chunks", 8),
+            ])
+            .lens(AppState::config.then(Config::download_parallelism)),
+        );
+
+    // Prefetch
+    col = col
+        .with_spacer(theme::grid(3.0))
+        .with_child(Label::new("Prefetch next track").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Once the current track has played this far, the next one is downloaded in \
+                 full, so the change of tracks is instant even on a slow connection. Not \
+                 used in data saver mode.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("Off (download while playing)", 0),
+                ("After 25%", 25),
+                ("After 50% (recommended)", 50),
+                ("After 75%", 75),
+            ])
+            .lens(AppState::config.then(Config::prefetch_percent)),
+        );
+
+    col
 }
 
 fn discord_presence_tab_widget() -> impl Widget<AppState> {
@@ -1187,6 +2201,20 @@ fn discord_presence_tab_widget() -> impl Widget<AppState> {
             Checkbox::new("Enable Discord Rich Presence")
                 .lens(AppState::config.then(Config::enable_discord_presence)),
         )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::dynamic(|state: &DiscordState, _| match state {
+                DiscordState::Disabled => "Not connected".to_string(),
+                DiscordState::Connected => "Connected to Discord".to_string(),
+                DiscordState::Reconnecting { attempt, retry_in } => format!(
+                    "Discord is not reachable, retrying in {} s (attempt {attempt})",
+                    retry_in.as_secs().max(1)
+                ),
+            })
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .lens(AppState::discord_state),
+        )
         .with_spacer(theme::grid(2.0));
 
     // Discord App ID input
@@ -1240,6 +2268,35 @@ fn discord_presence_tab_widget() -> impl Widget<AppState> {
 
     col = col.with_spacer(theme::grid(3.0));
 
+    col = col
+        .with_child(Label::new("When Paused").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new("A paused track stays in the presence for a while, and is then replaced.")
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.5))
+        .with_child(
+            Flex::row()
+                .with_child(Label::new("After"))
+                .with_spacer(theme::grid(1.0))
+                .with_child(amount_widget(
+                    "minutes",
+                    AppState::config.then(Config::presence_idle_minutes),
+                )),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("Clear the presence", PresenceIdle::Clear),
+                ("Show \"Idle\"", PresenceIdle::Idle),
+            ])
+            .lens(AppState::config.then(Config::presence_idle)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
     col = col
         .with_child(Label::new("Artwork Options").with_font(theme::UI_FONT_MEDIUM))
         .with_spacer(theme::grid(1.0))
@@ -1263,6 +2320,221 @@ fn discord_presence_tab_widget() -> impl Widget<AppState> {
     col
 }
 
+/// Buttons playing the test signals.  Also shown in the account setup, where
+/// the delegate plays them, as there is no player yet.
+fn test_signals_widget() -> impl Widget<AppState> {
+    let mut col = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Test signals").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Play a generated signal to check the output device, the channel mapping and \
+                 the equalizer. Nothing is streamed. The channel test names each channel in \
+                 turn with the speech synthesizer of the system. Without one, it beeps once on \
+                 the first channel, twice on the second, and so on.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0));
+
+    let mut signals = Flex::row();
+    for (name, signal) in [
+        ("Sine 440 Hz", TestSignal::Sine { frequency: 440.0 }),
+        ("Sine 1 kHz", TestSignal::Sine { frequency: 1000.0 }),
+        ("White Noise", TestSignal::WhiteNoise),
+        ("Pink Noise", TestSignal::PinkNoise),
+        ("Channel Test", TestSignal::ChannelIdentification),
+    ] {
+        signals = signals
+            .with_child(Button::new(name).on_click(move |ctx, _, _| {
+                ctx.submit_command(cmd::PLAY_TEST_SIGNAL.with(signal).to(Target::Global));
+            }))
+            .with_spacer(theme::grid(1.0));
+    }
+    col = col
+        .with_child(signals)
+        .with_spacer(theme::grid(1.0))
+        .with_child(Button::new("Stop").on_click(|ctx, _, _| {
+            ctx.submit_command(cmd::PLAY_STOP.to(Target::Global));
+        }));
+
+    col
+}
+
+fn diagnostics_tab_widget() -> impl Widget<AppState> {
+    let mut col = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .must_fill_main_axis(true);
+
+    col = col.with_child(test_signals_widget());
+
+    // Playback position updates
+    col = col
+        .with_spacer(theme::grid(3.0))
+        .with_child(Label::new("Playback position").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "The seek bar and the time label follow the position on their own. Only a part \
+                 of the position reports is applied to the rest of the app.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(Label::dynamic(|_: &AppState, _| {
+            format_clock_stats(PlaybackClock::global().stats())
+        }));
+
+    // Output latency
+    col = col
+        .with_spacer(theme::grid(3.0))
+        .with_child(Label::new("Output latency").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Latency the output device gave us, which might differ from the buffer size \
+                 asked for in the General tab.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(Label::dynamic(|data: &AppState, _| {
+            match data.playback.output_latency {
+                Some(latency) => format!("{} ms", latency.as_millis()),
+                None => "Not reported yet".to_string(),
+            }
+        }));
+
+    // Listening history export
+    col = col
+        .with_spacer(theme::grid(3.0))
+        .with_child(Label::new("Listening history").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Plays are recorded on this computer only and never uploaded. They are listed \
+                 on the History page, and can be exported as CSV or JSON to analyze them in \
+                 other tools.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Checkbox::new("Record my listening history")
+                .lens(AppState::config.then(Config::history_enable)),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Flex::row()
+                .with_child(Button::new("Export History").on_click(|ctx, _, _| {
+                    export_history(ctx);
+                }))
+                .with_default_spacer()
+                .with_child(Button::new("Clear History").on_click(|ctx, _, _| {
+                    ctx.new_window(clear_history_confirm_window());
+                })),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Checkbox::new("Log plays to a file")
+                .lens(AppState::config.then(Config::play_log_enable)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            TextBox::new()
+                .with_placeholder("/path/to/plays.csv")
+                .expand_width()
+                .lens(AppState::config.then(Config::play_log_path))
+                .disabled_if(|data: &AppState, _| !data.config.play_log_enable),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Every play is appended to the file as it is recorded, as CSV for a .csv file \
+                 and as JSON Lines otherwise. It is kept whether Last.fm is connected or not.",
+            )
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Checkbox::new("Offer to remove songs I always skip")
+                .lens(AppState::config.then(Config::suggest_skip_removals)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Songs skipped in their first 20 seconds are recorded in the history. When a few \
+                 songs of one of your playlists keep being skipped, you are offered to review and \
+                 remove them, at most once a week per playlist.",
+            )
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        );
+
+    col
+}
+
+fn clear_history_confirm_window() -> WindowDesc<AppState> {
+    let widget = Flex::column()
+        .with_child(playlist::information_section(
+            "Clear the listening history?",
+            "All plays and skips recorded on this computer are deleted. This cannot be undone.",
+        ))
+        .with_flex_spacer(2.0)
+        .with_child(playlist::button_section(
+            "Clear",
+            cmd::CLEAR_HISTORY,
+            Box::new(|| ()),
+        ))
+        .with_flex_spacer(2.0)
+        .background(theme::BACKGROUND_DARK);
+    let win = WindowDesc::new(ThemeScope::new(widget))
+        .window_size((theme::grid(45.0), theme::grid(25.0)))
+        .title("Clear History")
+        .resizable(false)
+        .show_title(false)
+        .transparent_titlebar(true);
+    if cfg!(target_os = "macos") {
+        win.menu(menu::main_menu)
+    } else {
+        win
+    }
+}
+
+fn export_history(ctx: &mut EventCtx) {
+    use druid::{FileDialogOptions, FileSpec};
+
+    ctx.submit_command(cmd::BEGIN_HISTORY_EXPORT);
+
+    let options = FileDialogOptions::new()
+        .default_name("psst-history.csv")
+        .allowed_types(vec![
+            FileSpec::new("CSV File", &["csv"]),
+            FileSpec::new("JSON File", &["json"]),
+        ]);
+
+    ctx.submit_command(
+        druid::commands::SHOW_SAVE_PANEL
+            .with(options)
+            .to(Target::Auto),
+    );
+}
+
+fn format_clock_stats(stats: ClockStats) -> String {
+    format!(
+        "{} reports received, {} applied to the app state, {} repaints of the seek bar and time",
+        stats.reports, stats.applied, stats.repaints
+    )
+}
+
 fn cache_tab_widget() -> impl Widget<AppState> {
     let mut col = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
 
@@ -1284,16 +2556,40 @@ fn cache_tab_widget() -> impl Widget<AppState> {
         .with_child(Label::new("Size").with_font(theme::UI_FONT_MEDIUM))
         .with_spacer(theme::grid(2.0))
         .with_child(Label::dynamic(
-            |preferences: &Preferences, _| match preferences.cache_size {
+            |preferences: &Preferences, _| match &preferences.cache_usage {
                 Promise::Empty | Promise::Rejected { .. } => "Unknown".to_string(),
-                Promise::Deferred { .. } => "Computing...".to_string(),
-                Promise::Resolved { val: 0, .. } => "Empty".to_string(),
-                Promise::Resolved { val, .. } => {
-                    format!("{:.2} MB", val as f64 / 1e6_f64)
-                }
+                Promise::Deferred { .. } => match &preferences.cache_usage_progress {
+                    Some(usage) => format!("{} so far...", format_cache_size(usage.total())),
+                    None => "Computing...".to_string(),
+                },
+                Promise::Resolved { val, .. } => format_cache_size(val.total()),
             },
         ));
 
+    // Usage of each category, each clearable on its own.
+    col = col.with_spacer(theme::grid(2.0));
+    for category in CacheCategory::ALL {
+        col = col
+            .with_child(
+                Flex::row()
+                    .with_child(Label::new(category.name()).fix_width(theme::grid(20.0)))
+                    .with_child(
+                        Label::dynamic(move |preferences: &Preferences, _| {
+                            match preferences.cache_usage_so_far() {
+                                Some(usage) => format_cache_size(usage.get(category)),
+                                None => "-".to_string(),
+                            }
+                        })
+                        .with_text_color(theme::PLACEHOLDER_COLOR)
+                        .fix_width(theme::grid(12.0)),
+                    )
+                    .with_child(Button::new("Clear").on_left_click(move |ctx, _, _, _| {
+                        ctx.submit_command(CLEAR_CACHE_CATEGORY.with(category));
+                    })),
+            )
+            .with_spacer(theme::grid(1.0));
+    }
+
     // Clear cache button
     col = col
         .with_spacer(theme::grid(2.0))
@@ -1301,10 +2597,66 @@ fn cache_tab_widget() -> impl Widget<AppState> {
             ctx.submit_command(CLEAR_CACHE);
         }));
 
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(Label::new("Integrity").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Label::new(
+                "Checks the cached audio files for truncated or corrupted downloads, \
+                 and removes them so they are downloaded again.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Flex::row()
+                .with_child(Button::new("Verify Cache").on_left_click(|ctx, _, _, _| {
+                    ctx.submit_command(VERIFY_CACHE);
+                }))
+                .with_spacer(theme::grid(1.0))
+                .with_child(
+                    Label::dynamic(|preferences: &Preferences, _| {
+                        match &preferences.cache_verification {
+                            Promise::Empty => String::new(),
+                            Promise::Deferred { .. } => "Verifying...".to_string(),
+                            Promise::Resolved { val, .. } => format_cache_verification(val),
+                            Promise::Rejected { err, .. } => format!("Failed: {err}"),
+                        }
+                    })
+                    .with_text_color(theme::PLACEHOLDER_COLOR),
+                ),
+        );
+
     col.controller(CacheController::new())
         .lens(AppState::preferences)
 }
 
+fn format_cache_verification(verification: &CacheVerification) -> String {
+    let mut text = match verification.removed {
+        0 => format!("Checked {} files, all intact", verification.checked),
+        removed => format!(
+            "Checked {} files, removed {removed} broken ({})",
+            verification.checked,
+            format_cache_size(verification.removed_bytes)
+        ),
+    };
+    if verification.unverified > 0 {
+        text += &format!(", {} without a cached key", verification.unverified);
+    }
+    text
+}
+
+fn format_cache_size(bytes: u64) -> String {
+    if bytes == 0 {
+        "Empty".to_string()
+    } else {
+        format::size(bytes)
+    }
+}
+
 fn equalizer_tab_widget() -> impl Widget<AppState> {
     use psst_core::audio::equalizer::EqualizerPreset;
 
@@ -1383,9 +2735,73 @@ fn equalizer_tab_widget() -> impl Widget<AppState> {
         col = col.with_child(equalizer_band_slider(band_index));
     }
 
+    col = col.with_spacer(theme::grid(3.0));
+
+    // Bass mono
+    col = col
+        .with_child(Label::new("Bass Mono").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Play the frequencies below 120 Hz the same on both channels. Helps with \
+                 recordings whose bass is panned hard to one side.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.5))
+        .with_child(
+            Checkbox::new("Fold bass into mono").lens(AppState::config.then(Config::bass_mono)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    // DSP chain order
+    col = col
+        .with_child(Label::new("Processing order").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "Drag the stages to change the order they are applied in. Keep the limiter \
+                 last to prevent clipping when the equalizer boosts the signal.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(2.0));
+
+    for index in 0..DspStage::ALL.len() {
+        col = col.with_child(dsp_stage_widget(index));
+    }
+    col = col
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Button::new("Reset Order").on_click(|_, data: &mut AppState, _| {
+                data.config.dsp_order = DspStage::default_order();
+            }),
+        );
+
     col.controller(EqualizerConfigNotifier)
 }
 
+fn dsp_stage_widget(index: usize) -> impl Widget<AppState> {
+    Label::dynamic(move |data: &AppState, _| {
+        let order = DspStage::complete_order(&data.config.dsp_order);
+        format!("{}. {}", index + 1, order[index].name())
+    })
+    .padding(theme::grid(1.0))
+    .fix_width(theme::grid(30.0))
+    .link()
+    .rounded(theme::BUTTON_BORDER_RADIUS)
+    .controller(DragToReorder::new(move |_, data: &mut AppState, rows| {
+        let mut order = DspStage::complete_order(&data.config.dsp_order);
+        let target = (index as isize + rows).clamp(0, order.len() as isize - 1) as usize;
+        let stage = order.remove(index);
+        order.insert(target, stage);
+        data.config.dsp_order = order;
+    }))
+}
+
 struct EqualizerConfigNotifier;
 
 impl<W> Controller<AppState, W> for EqualizerConfigNotifier
@@ -1622,6 +3038,24 @@ fn about_tab_widget() -> impl Widget<AppState> {
             }),
     );
 
+    let compatibility = ApiFeature::ALL
+        .into_iter()
+        .fold(Flex::column(), |column, feature| {
+            column.with_child(
+                Flex::row()
+                    .with_child(Label::new(format!("{}:   ", feature.label())))
+                    .with_child(
+                        Label::dynamic(move |data: &AppState, _| {
+                            data.api_status
+                                .get(&feature)
+                                .map_or("Not used yet".to_string(), ApiStatus::describe)
+                        })
+                        .with_text_color(theme::DISABLED_TEXT_COLOR),
+                    ),
+            )
+        })
+        .cross_axis_alignment(CrossAxisAlignment::Start);
+
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .must_fill_main_axis(true)
@@ -1630,4 +3064,17 @@ fn about_tab_widget() -> impl Widget<AppState> {
         .with_child(commit_hash)
         .with_child(build_time)
         .with_child(remote_url)
+        .with_spacer(theme::grid(3.0))
+        .with_child(Label::new("Spotify API Compatibility").with_font(theme::UI_FONT_MEDIUM))
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Label::new(
+                "Spotify removes or changes parts of its API from time to time. Features whose \
+                 endpoints stopped working are turned off and listed here.",
+            )
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .with_text_color(theme::PLACEHOLDER_COLOR),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(compatibility)
 }
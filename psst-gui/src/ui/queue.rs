@@ -0,0 +1,176 @@
+use druid::{
+    im::Vector,
+    lens::Map,
+    widget::{Button, CrossAxisAlignment, Either, Empty, Flex, Label, LineBreaking, List, Scroll},
+    Widget, WidgetExt,
+};
+
+use crate::{
+    cmd,
+    controller::DragToReorder,
+    data::{AppState, Playable, Playback, PlaybackOrigin, QueueEntry},
+    widget::{icons, Border, MyWidgetExt},
+};
+
+use super::theme;
+
+/// Up-next entry together with its index in the player queue, and whether it
+/// is the first one added by autoplay.
+type UpNextItem = (usize, QueueEntry, bool);
+
+pub fn queue_widget() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(header_widget())
+        .with_child(radio_widget())
+        .with_spacer(theme::grid(1.0))
+        .with_flex_child(
+            Either::new(
+                |playback: &Playback, _| playback.up_next.is_empty(),
+                Label::new("Nothing is queued up.")
+                    .with_text_color(theme::PLACEHOLDER_COLOR)
+                    .padding(theme::grid(1.0)),
+                Scroll::new(List::new(up_next_item_widget).lens(Map::new(
+                    |playback: &Playback| {
+                        playback
+                            .up_next
+                            .iter()
+                            .cloned()
+                            .enumerate()
+                            .map(|(index, entry)| {
+                                let autoplay = playback.autoplay_from == Some(entry.item.id());
+                                (index, entry, autoplay)
+                            })
+                            .collect::<Vector<_>>()
+                    },
+                    |_, _| {},
+                )))
+                .vertical(),
+            )
+            .lens(AppState::playback),
+            1.0,
+        )
+}
+
+fn header_widget() -> impl Widget<AppState> {
+    Flex::row()
+        .with_flex_child(
+            Label::new("Up Next")
+                .with_font(theme::UI_FONT_MEDIUM)
+                .with_text_size(theme::TEXT_SIZE_LARGE)
+                .expand_width(),
+            1.0,
+        )
+        .with_child(
+            Button::new("Remove Duplicates")
+                .on_click(|ctx, _, _| ctx.submit_command(cmd::REMOVE_QUEUE_DUPLICATES))
+                .disabled_if(|data: &AppState, _| data.playback.up_next.is_empty()),
+        )
+        .with_default_spacer()
+        .with_child(
+            Button::new("Clear")
+                .on_click(|ctx, _, _| ctx.submit_command(cmd::CLEAR_UP_NEXT))
+                .disabled_if(|data: &AppState, _| data.playback.up_next.is_empty()),
+        )
+        .padding(theme::grid(1.0))
+}
+
+/// Title of the radio playing, if the current item was started as one.
+fn radio_title(data: &AppState) -> Option<String> {
+    let origin = &data.playback.now_playing.as_ref()?.origin;
+    matches!(origin, PlaybackOrigin::Radio(_)).then(|| origin.to_string())
+}
+
+fn radio_widget() -> impl Widget<AppState> {
+    Either::new(
+        |data: &AppState, _| radio_title(data).is_some(),
+        Flex::row()
+            .with_child(icons::SPEAKER.scale(theme::ICON_SIZE_SMALL))
+            .with_default_spacer()
+            .with_flex_child(
+                Label::dynamic(|data: &AppState, _| {
+                    format!("Playing {}", radio_title(data).unwrap_or_default())
+                })
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .with_line_break_mode(LineBreaking::Clip)
+                .expand_width(),
+                1.0,
+            )
+            .padding((theme::grid(1.0), 0.0)),
+        Empty,
+    )
+}
+
+fn up_next_item_widget() -> impl Widget<UpNextItem> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Fill)
+        .with_child(Either::new(
+            |(_, _, autoplay): &UpNextItem, _| *autoplay,
+            autoplay_divider_widget(),
+            Empty,
+        ))
+        .with_child(up_next_entry_widget())
+}
+
+fn autoplay_divider_widget() -> impl Widget<UpNextItem> {
+    Label::new("Autoplay")
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .padding((
+            theme::grid(1.0),
+            theme::grid(1.5),
+            theme::grid(1.0),
+            theme::grid(0.5),
+        ))
+        .expand_width()
+        .background(Border::Top.with_color(theme::GREY_500))
+}
+
+fn up_next_entry_widget() -> impl Widget<UpNextItem> {
+    Flex::row()
+        .with_flex_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    Label::dynamic(|(_, entry, _): &UpNextItem, _| entry.item.name().to_string())
+                        .with_font(theme::UI_FONT_MEDIUM)
+                        .with_line_break_mode(LineBreaking::Clip),
+                )
+                .with_spacer(2.0)
+                .with_child(
+                    Label::dynamic(|(_, entry, _): &UpNextItem, _| subtitle(&entry.item))
+                        .with_text_size(theme::TEXT_SIZE_SMALL)
+                        .with_text_color(theme::PLACEHOLDER_COLOR)
+                        .with_line_break_mode(LineBreaking::Clip),
+                ),
+            1.0,
+        )
+        .with_default_spacer()
+        .with_child(
+            icons::CLOSE
+                .scale(theme::ICON_SIZE_SMALL)
+                .with_color(theme::PLACEHOLDER_COLOR)
+                .padding(theme::grid(0.5))
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|ctx, _, (index, _, _), _| {
+                    ctx.submit_command(cmd::REMOVE_QUEUE_ITEM.with(*index));
+                }),
+        )
+        .padding(theme::grid(1.0))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .controller(DragToReorder::new(
+            |ctx, (index, _, _): &mut UpNextItem, rows| {
+                let target = (*index as isize + rows).max(0) as usize;
+                ctx.submit_command(cmd::MOVE_QUEUE_ITEM.with((*index, target)));
+            },
+        ))
+}
+
+pub fn subtitle(item: &Playable) -> String {
+    match item {
+        Playable::Track(track) => format!("{} • {}", track.artist_names(), track.album_name()),
+        Playable::Episode(episode) => episode.show.name.to_string(),
+    }
+}
@@ -1,32 +1,46 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use druid::{
     im::Vector,
-    widget::{CrossAxisAlignment, Either, Flex, Label, LabelText, List, TextBox},
-    Data, LensExt, Selector, Widget, WidgetExt,
+    lens::Map,
+    widget::{
+        prelude::*, Controller, CrossAxisAlignment, Either, Flex, Label, LabelText, List, TextBox,
+    },
+    KbKey, LensExt, Selector, Target, TimerToken, WidgetExt,
 };
 
 use crate::{
     cmd,
     controller::InputController,
     data::{
-        Album, AppState, Artist, Ctx, Nav, Search, SearchResults, SearchTopic, Show, SpotifyUrl,
-        WithCtx,
+        Album, AppState, Artist, Ctx, Nav, Search, SearchEntry, SearchResults, SearchTopic, Show,
+        SpotifyUrl, WithCtx,
     },
     ui::show,
     webapi::WebApi,
     widget::{Async, Empty, MyWidgetExt},
 };
 
-use super::{album, artist, playable, playlist, theme, track, utils};
+use super::{album, artist, find::Findable, playable, playlist, theme, track, utils};
 
 const NUMBER_OF_RESULTS_PER_TOPIC: usize = 5;
+const NUMBER_OF_SUGGESTIONS_PER_TOPIC: usize = 3;
+
+/// Pause in the typing after which the suggestions are loaded.
+const SUGGESTIONS_DELAY: Duration = Duration::from_millis(300);
 
 pub const LOAD_RESULTS: Selector<Arc<str>> = Selector::new("app.search.load-results");
 pub const OPEN_LINK: Selector<SpotifyUrl> = Selector::new("app.search.open-link");
 
+const LOAD_SUGGESTIONS: Selector<Arc<str>> = Selector::new("app.search.load-suggestions");
+/// Search for the entry of the dropdown at the index.
+const RUN_ENTRY: Selector<usize> = Selector::new("app.search.run-entry");
+
+/// Entry of the dropdown together with its index, and whether it is selected.
+type DropdownItem = (usize, SearchEntry, bool);
+
 pub fn input_widget() -> impl Widget<AppState> {
-    TextBox::new()
+    let input = TextBox::new()
         .with_placeholder("Search")
         .controller(InputController::new().on_submit(|ctx, query, _| {
             if query.trim().is_empty() {
@@ -36,7 +50,187 @@ pub fn input_widget() -> impl Widget<AppState> {
         }))
         .with_id(cmd::WIDGET_SEARCH_INPUT)
         .expand_width()
-        .lens(AppState::search.then(Search::input))
+        .lens(AppState::search.then(Search::input));
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Fill)
+        .with_child(input)
+        .with_child(dropdown_widget())
+        .controller(DropdownController {
+            timer: TimerToken::INVALID,
+        })
+        .on_command_async(
+            LOAD_SUGGESTIONS,
+            |q| {
+                let topics = [SearchTopic::Artist, SearchTopic::Track, SearchTopic::Album];
+                WebApi::global()
+                    .search(&q, &topics, NUMBER_OF_SUGGESTIONS_PER_TOPIC)
+                    .map(|results| results.suggestions())
+            },
+            |_, data, q| data.search.suggestions.defer(q),
+            |_, data, r| data.search.suggestions.update(r),
+        )
+}
+
+/// Recent searches and suggestions under the search box.
+fn dropdown_widget() -> impl Widget<AppState> {
+    let entries = List::new(entry_widget).lens(Map::new(
+        |data: &AppState| {
+            data.search
+                .entries(&data.config.search_history)
+                .into_iter()
+                .enumerate()
+                .map(|(index, entry)| (index, entry, data.search.selected == Some(index)))
+                .collect::<Vector<_>>()
+        },
+        |_, _| {},
+    ));
+
+    let clear = Either::new(
+        |data: &AppState, _| data.config.search_history.is_empty(),
+        Empty,
+        Label::new("Clear search history")
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .padding(theme::grid(0.75))
+            .link()
+            .rounded(theme::BUTTON_BORDER_RADIUS)
+            .on_left_click(|_, _, data: &mut AppState, _| {
+                data.config.search_history.clear();
+                data.config.save();
+            }),
+    );
+
+    Either::new(
+        |data: &AppState, _| {
+            data.search.dropdown && !data.search.entries(&data.config.search_history).is_empty()
+        },
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Fill)
+            .with_child(entries)
+            .with_child(clear)
+            .padding(theme::grid(0.5))
+            .background(theme::GREY_600)
+            .rounded(theme::BUTTON_BORDER_RADIUS)
+            .padding((0.0, theme::grid(0.5), 0.0, 0.0)),
+        Empty,
+    )
+}
+
+fn entry_widget() -> impl Widget<DropdownItem> {
+    let query = Label::dynamic(|(_, entry, _): &DropdownItem, _| entry.query.to_string())
+        .with_text_size(theme::TEXT_SIZE_SMALL);
+
+    let recent = Either::new(
+        |(_, entry, _): &DropdownItem, _| entry.recent,
+        Label::new("Recent")
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR),
+        Empty,
+    );
+
+    Flex::row()
+        .with_flex_child(query.expand_width(), 1.0)
+        .with_child(recent)
+        .padding(theme::grid(0.75))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .active(|(_, _, selected): &DropdownItem, _| *selected)
+        .on_left_click(|ctx, _, (index, _, _): &mut DropdownItem, _| {
+            ctx.submit_command(RUN_ENTRY.with(*index));
+        })
+}
+
+/// Opens the dropdown while the query is typed, loads the suggestions once
+/// the typing pauses, and moves through the entries with the arrow keys.
+struct DropdownController {
+    timer: TimerToken,
+}
+
+impl DropdownController {
+    fn run_entry(&self, ctx: &mut EventCtx, data: &mut AppState, index: usize) {
+        let entries = data.search.entries(&data.config.search_history);
+        data.search.close_dropdown();
+        if let Some(entry) = entries.get(index) {
+            data.search.input = entry.query.to_string();
+            ctx.submit_command(cmd::NAVIGATE.with(Nav::SearchResults(entry.query.clone())));
+        }
+    }
+}
+
+impl<W> Controller<AppState, W> for DropdownController
+where
+    W: Widget<AppState>,
+{
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(RUN_ENTRY) => {
+                let index = *cmd.get_unchecked(RUN_ENTRY);
+                self.run_entry(ctx, data, index);
+                ctx.set_handled();
+            }
+            Event::Command(cmd)
+                if cmd.is(cmd::SET_FOCUS)
+                    && cmd.target() == Target::Widget(cmd::WIDGET_SEARCH_INPUT) =>
+            {
+                data.search.open_dropdown();
+                child.event(ctx, event, data, env);
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.timer = TimerToken::INVALID;
+                let query = data.search.query();
+                if !query.is_empty()
+                    && !data.search.suggestions.contains(&query)
+                    && !data.search.suggestions.is_deferred(&query)
+                {
+                    ctx.submit_command(LOAD_SUGGESTIONS.with(query));
+                }
+            }
+            Event::KeyDown(key)
+                if data.search.dropdown
+                    && matches!(
+                        key.key,
+                        KbKey::Escape | KbKey::ArrowUp | KbKey::ArrowDown | KbKey::Enter
+                    ) =>
+            {
+                let count = data.search.entries(&data.config.search_history).len();
+                match (&key.key, data.search.selected) {
+                    (KbKey::ArrowUp, _) => data.search.select_previous(),
+                    (KbKey::ArrowDown, _) => data.search.select_next(count),
+                    (KbKey::Enter, Some(index)) => {
+                        ctx.resign_focus();
+                        self.run_entry(ctx, data, index);
+                    }
+                    _ => {
+                        // Escape, or the typed query submitted.
+                        data.search.close_dropdown();
+                        child.event(ctx, event, data, env);
+                        return;
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::MouseDown(_) if ctx.is_hot() && !data.search.dropdown => {
+                data.search.open_dropdown();
+                child.event(ctx, event, data, env);
+            }
+            _ => {
+                let input = data.search.input.clone();
+                child.event(ctx, event, data, env);
+                if data.search.input != input {
+                    data.search.open_dropdown();
+                    self.timer = ctx.request_timer(SUGGESTIONS_DELAY);
+                }
+            }
+        }
+    }
 }
 
 pub fn results_widget() -> impl Widget<AppState> {
@@ -101,7 +295,7 @@ fn artist_results_widget() -> impl Widget<WithCtx<SearchResults>> {
         Empty,
         Flex::column()
             .with_child(header_widget("Artists"))
-            .with_child(List::new(|| artist::artist_widget(false))),
+            .with_child(List::new(|| Findable::new(artist::artist_widget(false)))),
     )
     .lens(Ctx::data().then(SearchResults::artists))
 }
@@ -112,7 +306,7 @@ fn album_results_widget() -> impl Widget<WithCtx<SearchResults>> {
         Empty,
         Flex::column()
             .with_child(header_widget("Albums"))
-            .with_child(List::new(|| album::album_widget(false))),
+            .with_child(List::new(|| Findable::new(album::album_widget(false)))),
     )
     .lens(Ctx::map(SearchResults::albums))
 }
@@ -142,7 +336,7 @@ fn playlist_results_widget() -> impl Widget<WithCtx<SearchResults>> {
         Flex::column()
             .with_child(header_widget("Playlists"))
             .with_child(
-                List::new(|| playlist::playlist_widget(false))
+                List::new(|| Findable::new(playlist::playlist_widget(false)))
                     .lens(Ctx::map(SearchResults::playlists)),
             ),
     )
@@ -154,7 +348,7 @@ fn show_results_widget() -> impl Widget<WithCtx<SearchResults>> {
         Empty,
         Flex::column()
             .with_child(header_widget("Podcasts"))
-            .with_child(List::new(|| show::show_widget(false))),
+            .with_child(List::new(|| Findable::new(show::show_widget(false)))),
     )
     .lens(Ctx::map(SearchResults::shows))
 }
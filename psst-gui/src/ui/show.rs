@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use druid::{
-    widget::{CrossAxisAlignment, Flex, Label, LineBreaking, Scroll},
+    widget::{CrossAxisAlignment, Either, Flex, Label, LineBreaking, Scroll},
     LensExt, LocalizedString, Menu, MenuItem, Selector, Size, UnitPoint, Widget, WidgetExt,
 };
 
@@ -10,7 +10,7 @@ use crate::{
     data::{AppState, Ctx, Library, Nav, Show, ShowDetail, ShowEpisodes, ShowLink, WithCtx},
     ui::utils::{stat_row, InfoLayout},
     webapi::WebApi,
-    widget::{Async, MyWidgetExt, RemoteImage},
+    widget::{Async, Empty, MyWidgetExt, RemoteImage},
 };
 
 use super::{library, playable, theme, track, utils};
@@ -21,6 +21,7 @@ pub fn detail_widget() -> impl Widget<AppState> {
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_child(async_info_widget())
+        .with_child(follow_widget())
         .with_default_spacer()
         .with_child(async_episodes_widget())
 }
@@ -39,6 +40,9 @@ fn async_info_widget() -> impl Widget<AppState> {
             |d| WebApi::global().get_show(&d.id),
             |_, data, d| data.show_detail.show.defer(d),
             |_, data, (d, r)| {
+                if let Ok(cached) = &r {
+                    data.mark_episodes_seen([&*cached.data], false);
+                }
                 data.show_detail
                     .show
                     .update((d, r.map(|cached| cached.data)))
@@ -46,6 +50,82 @@ fn async_info_widget() -> impl Widget<AppState> {
         )
 }
 
+fn follow_widget() -> impl Widget<AppState> {
+    let is_following = |data: &AppState| data.show_detail.following.resolved() == Some(&true);
+
+    let button = Label::dynamic(move |data: &AppState, _| {
+        if is_following(data) {
+            "Following".to_string()
+        } else {
+            "Follow".to_string()
+        }
+    })
+    .with_font(theme::UI_FONT_MEDIUM)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .padding((theme::grid(1.5), theme::grid(0.5)))
+    .link()
+    .rounded(theme::BUTTON_BORDER_RADIUS)
+    .active(move |data: &AppState, _| is_following(data))
+    .on_left_click(move |ctx, _, data: &mut AppState, _| {
+        if let Some(show) = data.show_detail.show.resolved() {
+            if is_following(data) {
+                ctx.submit_command(library::UNSAVE_SHOW.with(show.link()));
+            } else {
+                ctx.submit_command(library::SAVE_SHOW.with(show.clone()));
+            }
+        }
+    })
+    .padding((theme::grid(1.0), 0.0));
+
+    Either::new(
+        |data: &AppState, _| {
+            data.show_detail.following.is_resolved() && data.show_detail.show.is_resolved()
+        },
+        button,
+        Empty,
+    )
+    .on_command_async(
+        LOAD_DETAIL,
+        |d| WebApi::global().is_show_saved(&d.id),
+        |_, data, d| data.show_detail.following.defer(d),
+        |_, data, r| data.show_detail.following.update(r),
+    )
+    .on_command_async(
+        library::SAVE_SHOW,
+        |show| WebApi::global().save_show(&show.id),
+        |_, data, show| {
+            data.mark_episodes_seen([&*show], false);
+            data.show_detail.following.resolve(show.link(), true);
+            data.with_library_mut(|library| library.add_show(show));
+        },
+        |_, data, (show, r)| {
+            if let Err(err) = r {
+                data.show_detail.following.resolve(show.link(), false);
+                data.with_library_mut(|library| library.remove_show(&show.id));
+                data.error_alert(err);
+            } else {
+                data.info_alert(format!("Following {}.", show.name));
+            }
+        },
+    )
+    .on_command_async(
+        library::UNSAVE_SHOW,
+        |link| WebApi::global().unsave_show(&link.id),
+        |_, data, link| {
+            data.show_detail.following.resolve(link.clone(), false);
+            data.with_library_mut(|library| library.remove_show(&link.id));
+        },
+        |_, data, (link, r)| {
+            if let Err(err) = r {
+                data.show_detail.following.resolve(link, true);
+                data.error_alert(err);
+            } else {
+                data.info_alert(format!("Stopped following {}.", link.name));
+            }
+        },
+    )
+}
+
 fn info_widget() -> impl Widget<WithCtx<Arc<Show>>> {
     let size = theme::grid(16.0);
 
@@ -110,9 +190,23 @@ fn async_episodes_widget() -> impl Widget<AppState> {
         |d| WebApi::global().get_show_episodes(&d.id),
         |_, data, d| data.show_detail.episodes.defer(d),
         |_, data, (d, r)| {
+            let cache = data.preferences.cache.clone();
             let r = r.map(|episodes| ShowEpisodes {
                 show: d.clone(),
-                episodes,
+                episodes: episodes
+                    .into_iter()
+                    .map(|mut episode| {
+                        // Progress saved locally is what we last listened to,
+                        // so it wins over Spotify's resume point.
+                        if let Some(progress) = cache
+                            .as_ref()
+                            .and_then(|cache| cache.get_episode_progress(episode.id.0))
+                        {
+                            Arc::make_mut(&mut episode).resume_point = Some(progress.into());
+                        }
+                        episode
+                    })
+                    .collect(),
             });
             data.show_detail.episodes.update((d, r))
         },
@@ -150,34 +244,36 @@ pub fn show_widget(horizontal: bool) -> impl Widget<WithCtx<Arc<Show>>> {
     .with_text_color(theme::PLACEHOLDER_COLOR)
     .align_left();
 
+    let new_episodes = new_episodes_badge_widget();
+
     let show = if horizontal {
         Flex::column()
-            .with_child(show_image)
+            .with_child(show_image.lens(Ctx::data()))
             .with_default_spacer()
             .with_child(
                 Flex::column()
-                    .with_child(show_name)
-                    .with_child(show_publisher)
-                    .with_child(show_episodes)
+                    .with_child(show_name.lens(Ctx::data()))
+                    .with_child(show_publisher.lens(Ctx::data()))
+                    .with_child(show_episodes.lens(Ctx::data()))
+                    .with_child(new_episodes)
                     .align_horizontal(UnitPoint::CENTER)
                     .align_vertical(UnitPoint::TOP)
                     .fix_size(theme::grid(16.0), theme::grid(8.0)),
             )
             .padding(theme::grid(1.0))
-            .lens(Ctx::data())
     } else {
         Flex::row()
-            .with_child(show_image)
+            .with_child(show_image.lens(Ctx::data()))
             .with_default_spacer()
             .with_flex_child(
                 Flex::column()
-                    .with_child(show_name)
-                    .with_child(show_publisher)
-                    .with_child(show_episodes),
+                    .with_child(show_name.lens(Ctx::data()))
+                    .with_child(show_publisher.lens(Ctx::data()))
+                    .with_child(show_episodes.lens(Ctx::data()))
+                    .with_child(new_episodes),
                 1.0,
             )
             .padding(theme::grid(1.0))
-            .lens(Ctx::data())
     };
 
     show.align_left()
@@ -189,10 +285,29 @@ pub fn show_widget(horizontal: bool) -> impl Widget<WithCtx<Arc<Show>>> {
         .context_menu(show_ctx_menu)
 }
 
+/// Episodes released since a followed show was last opened.
+fn new_episodes_badge_widget() -> impl Widget<WithCtx<Arc<Show>>> {
+    let new_episodes = |show: &WithCtx<Arc<Show>>| show.ctx.library.new_episodes(&show.data);
+
+    Either::new(
+        move |show: &WithCtx<Arc<Show>>, _| new_episodes(show) > 0,
+        Label::dynamic(move |show: &WithCtx<Arc<Show>>, _| format!("{} new", new_episodes(show)))
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::GREY_700)
+            .padding((theme::grid(0.75), 0.0))
+            .background(theme::BLUE_100)
+            .rounded(theme::BUTTON_BORDER_RADIUS)
+            .padding((0.0, theme::grid(0.5), 0.0, 0.0))
+            .align_left(),
+        Empty,
+    )
+}
+
 fn cover_widget(size: f64) -> impl Widget<Arc<Show>> {
     RemoteImage::new(utils::placeholder_widget(), move |show: &Arc<Show>, _| {
         show.image(size, size).map(|image| image.url.clone())
     })
+    .with_size((size, size))
     .fix_size(size, size)
 }
 
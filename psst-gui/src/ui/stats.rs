@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use druid::{
+    im::Vector,
+    widget::{CrossAxisAlignment, Flex, Label, List, Painter, Scroll},
+    LensExt, Rect, RenderContext, Selector, Widget, WidgetExt,
+};
+
+use crate::{
+    data::{AppState, Ctx, ListeningActivity, Stats, TimeRange, Track, WithCtx},
+    format,
+    webapi::WebApi,
+    widget::{Async, MyWidgetExt},
+};
+
+use super::{artist, playable, theme, track, utils};
+
+pub const LOAD_TOP_TRACKS: Selector<TimeRange> = Selector::new("app.stats.load-top-tracks");
+pub const LOAD_TOP_ARTISTS: Selector<TimeRange> = Selector::new("app.stats.load-top-artists");
+
+pub fn stats_widget() -> impl Widget<AppState> {
+    let top_artists = Async::new(
+        utils::spinner_widget,
+        || {
+            Scroll::new(List::new(|| artist::artist_widget(true)).horizontal())
+                .horizontal()
+                .align_left()
+        },
+        utils::error_widget,
+    )
+    .lens(AppState::stats.then(Stats::top_artists))
+    .on_command_async(
+        LOAD_TOP_ARTISTS,
+        |range| WebApi::global().get_user_top_artist(range),
+        |_, data, range| data.stats.top_artists.defer(range),
+        |_, data, r| data.stats.top_artists.update(r),
+    );
+
+    let top_tracks = Async::new(
+        utils::spinner_widget,
+        top_tracks_widget,
+        utils::error_widget,
+    )
+    .lens(
+        Ctx::make(
+            AppState::common_ctx,
+            AppState::stats.then(Stats::top_tracks),
+        )
+        .then(Ctx::in_promise()),
+    )
+    .on_command_async(
+        LOAD_TOP_TRACKS,
+        |range| WebApi::global().get_user_top_tracks(range),
+        |_, data, range| data.stats.top_tracks.defer(range),
+        |_, data, r| data.stats.top_tracks.update(r),
+    );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(range_widget())
+        .with_child(title_widget("Top Artists"))
+        .with_child(top_artists)
+        .with_child(title_widget("Top Tracks"))
+        .with_child(top_tracks)
+        .with_child(title_widget("Listening Activity"))
+        .with_child(activity_widget().lens(AppState::stats.then(Stats::activity)))
+}
+
+fn range_widget() -> impl Widget<AppState> {
+    let mut row = Flex::row();
+    for range in TimeRange::ALL {
+        row.add_child(
+            Label::new(range.label())
+                .padding((theme::grid(1.5), theme::grid(0.5)))
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .active(move |data: &AppState, _| data.stats.range == range)
+                .on_left_click(move |ctx, _, data: &mut AppState, _| {
+                    if data.stats.range != range {
+                        data.stats.range = range;
+                        ctx.submit_command(LOAD_TOP_ARTISTS.with(range));
+                        ctx.submit_command(LOAD_TOP_TRACKS.with(range));
+                    }
+                }),
+        );
+        row.add_spacer(theme::grid(0.5));
+    }
+    row.padding((theme::grid(0.5), 0.0))
+}
+
+fn top_tracks_widget() -> impl Widget<WithCtx<Vector<Arc<Track>>>> {
+    playable::list_widget(playable::Display {
+        track: track::Display {
+            title: true,
+            artist: true,
+            album: true,
+            cover: true,
+            ..track::Display::empty()
+        },
+    })
+}
+
+/// Charts of the plays recorded in the local history.
+fn activity_widget() -> impl Widget<ListeningActivity> {
+    let hours = ["00:00", "06:00", "12:00", "18:00"]
+        .into_iter()
+        .fold(Flex::row(), |row, hour| {
+            row.with_flex_child(
+                Label::new(hour)
+                    .with_text_size(theme::TEXT_SIZE_SMALL)
+                    .with_text_color(theme::PLACEHOLDER_COLOR)
+                    .align_left(),
+                1.0,
+            )
+        });
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Label::dynamic(|activity: &ListeningActivity, _| {
+                format!(
+                    "{} plays in the last 30 days, recorded on this computer.",
+                    format::number(activity.total() as i64)
+                )
+            })
+            .with_text_color(theme::PLACEHOLDER_COLOR),
+        )
+        .with_spacer(theme::grid(2.0))
+        .with_child(chart_title_widget("Plays per day"))
+        .with_child(bar_chart_widget().lens(ListeningActivity::daily))
+        .with_spacer(theme::grid(2.0))
+        .with_child(chart_title_widget("Plays by hour of the day"))
+        .with_child(bar_chart_widget().lens(ListeningActivity::hourly))
+        .with_child(hours.expand_width())
+        .padding((theme::grid(1.5), 0.0))
+}
+
+fn bar_chart_widget() -> impl Widget<Vector<usize>> {
+    Painter::new(|ctx, counts: &Vector<usize>, env| {
+        let size = ctx.size();
+        let max = counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+        let slot = size.width / counts.len().max(1) as f64;
+        let color = env.get(theme::GREY_400);
+        for (index, count) in counts.iter().enumerate() {
+            let height = size.height * *count as f64 / max;
+            let left = index as f64 * slot;
+            let bar = Rect::new(
+                left + 1.0,
+                size.height - height,
+                left + slot - 1.0,
+                size.height,
+            );
+            ctx.fill(bar.to_rounded_rect(2.0), &color);
+        }
+    })
+    .fix_height(theme::grid(12.0))
+    .expand_width()
+}
+
+fn chart_title_widget(title: &str) -> impl Widget<ListeningActivity> {
+    Label::new(title)
+        .with_font(theme::UI_FONT_MEDIUM)
+        .padding((0.0, 0.0, 0.0, theme::grid(1.0)))
+}
+
+fn title_widget(title: &str) -> impl Widget<AppState> {
+    Label::new(title).with_text_size(theme::grid(2.5)).padding((
+        theme::grid(1.0),
+        theme::grid(2.0),
+        0.0,
+        theme::grid(1.0),
+    ))
+}
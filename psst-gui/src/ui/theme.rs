@@ -39,6 +39,11 @@ pub const UI_FONT_MONO: Key<FontDescriptor> = Key::new("app.ui-font-mono");
 pub const TEXT_SIZE_SMALL: Key<f64> = Key::new("app.text-size-small");
 
 pub const ICON_COLOR: Key<Color> = Key::new("app.icon-color");
+/// Colors of the icons in links that are hovered, pressed or selected, and
+/// disabled.
+pub const ICON_COLOR_HOT: Key<Color> = Key::new("app.icon-color-hot");
+pub const ICON_COLOR_ACTIVE: Key<Color> = Key::new("app.icon-color-active");
+pub const ICON_COLOR_DISABLED: Key<Color> = Key::new("app.icon-color-disabled");
 pub const ICON_SIZE_TINY: Size = Size::new(12.0, 12.0);
 pub const ICON_SIZE_SMALL: Size = Size::new(14.0, 14.0);
 pub const ICON_SIZE_MEDIUM: Size = Size::new(16.0, 16.0);
@@ -58,6 +63,9 @@ pub fn setup(env: &mut Env, state: &AppState) {
     env.set(WINDOW_BACKGROUND_COLOR, env.get(GREY_700));
     env.set(TEXT_COLOR, env.get(GREY_100));
     env.set(ICON_COLOR, env.get(GREY_400));
+    env.set(ICON_COLOR_HOT, env.get(GREY_200));
+    env.set(ICON_COLOR_ACTIVE, env.get(BLUE_100));
+    env.set(ICON_COLOR_DISABLED, env.get(GREY_400).with_alpha(0.4));
     env.set(PLACEHOLDER_COLOR, env.get(GREY_300));
     env.set(PRIMARY_LIGHT, env.get(BLUE_100));
     env.set(PRIMARY_DARK, env.get(BLUE_200));
@@ -1,21 +1,23 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use druid::{
-    widget::{CrossAxisAlignment, Either, Flex, Label, LineBreaking, ViewSwitcher},
-    Env, Lens, LensExt, LocalizedString, Menu, MenuItem, Size, TextAlignment, Widget, WidgetExt,
-};
-use psst_core::{
-    audio::normalize::NormalizationLevel,
-    item_id::{ItemId, ItemIdType},
-    player::item::PlaybackItem,
+    im::Vector,
+    lens::Map,
+    widget::{CrossAxisAlignment, Either, Flex, Label, LineBreaking, Maybe, TextBox, ViewSwitcher},
+    Data, Env, EventCtx, Lens, LensExt, LocalizedString, Menu, MenuItem, MouseEvent, Size,
+    TextAlignment, Widget, WidgetExt,
 };
+use psst_core::{audio::normalize::NormalizationLevel, player::item::PlaybackItem};
 
 use crate::{
     cmd,
+    controller::{DragTrack, DropTrack},
     data::{
-        AppState, Library, Nav, Playable, PlaybackOrigin, PlaylistAddTrack, PlaylistRemoveTrack,
-        QueueEntry, RecommendationsRequest, Track,
+        AppState, Config, Library, Nav, Playable, PlaybackOrigin, PlaylistAddTracks,
+        PlaylistRemoveTrack, QueueEntry, QueuePlacement, RecommendationsRequest, SortOrder,
+        TableSort, Track, TrackColumn,
     },
+    format,
     ui::playlist,
     widget::{fill_between::FillBetween, icons, Empty, MyWidgetExt, RemoteImage},
 };
@@ -23,8 +25,8 @@ use crate::{
 use super::{
     library,
     playable::{self, PlayRow},
-    theme,
-    utils::{self, placeholder_widget},
+    theme, user,
+    utils::placeholder_widget,
 };
 
 #[derive(Copy, Clone)]
@@ -35,6 +37,13 @@ pub struct Display {
     pub album: bool,
     pub cover: bool,
     pub popularity: bool,
+    /// When the track was added to its playlist, or saved to the library.
+    pub added_at: bool,
+    /// Avatar of the user who added the track to a collaborative playlist.
+    pub added_by: bool,
+    /// Cells lined up in the columns of `TrackColumn`, under
+    /// `table_header_widget`, instead of the details above.
+    pub table: bool,
 }
 
 impl Display {
@@ -46,11 +55,90 @@ impl Display {
             album: false,
             cover: false,
             popularity: false,
+            added_at: false,
+            added_by: false,
+            table: false,
         }
     }
 }
 
 pub fn playable_widget(track: &Track, display: Display) -> impl Widget<PlayRow<Arc<Track>>> {
+    let main_row = if display.table {
+        Flex::row().with_flex_child(table_cells_widget(display), 1.0)
+    } else {
+        list_row_widget(track, display)
+    };
+
+    let saved = ViewSwitcher::new(
+        |row: &PlayRow<Arc<Track>>, _| {
+            row.ctx.library.saved_tracks.is_resolved() && !row.item.is_local
+        },
+        |selector: &bool, _, _| match selector {
+            true => ViewSwitcher::new(
+                |row: &PlayRow<Arc<Track>>, _| row.ctx.library.contains_track(&row.item),
+                |selector: &bool, _, _| {
+                    match selector {
+                        true => &icons::CIRCLE_CHECK,
+                        false => &icons::CIRCLE_PLUS,
+                    }
+                    .scale(theme::ICON_SIZE_SMALL)
+                    .boxed()
+                },
+            )
+            .on_left_click(|ctx, _, row, _| {
+                let track = &row.item;
+                if row.ctx.library.contains_track(track) {
+                    ctx.submit_command(library::UNSAVE_TRACK.with(track.id))
+                } else {
+                    ctx.submit_command(library::SAVE_TRACK.with(track.clone()))
+                }
+            })
+            .boxed(),
+            false => Box::new(Flex::column()),
+        },
+    );
+
+    main_row
+        .with_default_spacer()
+        .with_child(saved.center())
+        .padding(theme::grid(1.0))
+        .link()
+        .active(|row: &PlayRow<Arc<Track>>, _env: &Env| {
+            if row.ctx.is_track_selected(&row.item) {
+                return true;
+            }
+            // Check if this track is the target of album detail navigation
+            if let Nav::AlbumDetail(_, Some(target_id)) = &row.ctx.nav {
+                return *target_id == row.item.id;
+            }
+            // Otherwise check if it's playing or is the current track
+            row.is_playing || row.ctx.now_playing.as_ref().is_some_and(|playable| {
+                matches!(playable, Playable::Track(track) if track.id == row.item.id)
+            })
+        })
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .context_menu(track_row_menu)
+        // Inside `DragTrack`, which keeps the release of a row dropped on
+        // itself, as there is nothing to move.
+        .controller(DropTrack::new(
+            |ctx, row: &mut PlayRow<Arc<Track>>, track| {
+                if let PlaybackOrigin::Playlist(link) = row.origin.as_ref() {
+                    ctx.submit_command(playlist::DROP_TRACK.with((
+                        link.clone(),
+                        track,
+                        row.item.track_pos,
+                    )));
+                }
+            },
+        ))
+        .controller(DragTrack::new(|row: &PlayRow<Arc<Track>>| {
+            (!row.item.is_local).then(|| row.item.clone())
+        }))
+}
+
+/// Title, with the artist and album below it, and the other details the
+/// display asks for.
+fn list_row_widget(track: &Track, display: Display) -> Flex<PlayRow<Arc<Track>>> {
     let mut main_row = Flex::row();
     let mut major = Flex::row().cross_axis_alignment(CrossAxisAlignment::Center);
     let mut minor = Flex::row();
@@ -111,10 +199,15 @@ pub fn playable_widget(track: &Track, display: Display) -> impl Widget<PlayRow<A
         } else {
             String::new()
         };
-        if !artist.is_empty() && !album.is_empty() {
+        let text = if !artist.is_empty() && !album.is_empty() {
             format!("{artist} • {album}")
         } else {
             format!("{artist}{album}")
+        };
+        match unavailable_reason(row) {
+            Some(explanation) if text.is_empty() => explanation.to_string(),
+            Some(explanation) => format!("{explanation} • {text}"),
+            None => text,
         }
     })
     .with_line_break_mode(LineBreaking::Clip)
@@ -135,69 +228,193 @@ pub fn playable_widget(track: &Track, display: Display) -> impl Widget<PlayRow<A
         major.add_child(track_popularity);
     }
 
-    let track_duration =
-        Label::<Arc<Track>>::dynamic(|track, _| utils::as_minutes_and_seconds(track.duration))
-            .with_text_size(theme::TEXT_SIZE_SMALL)
-            .with_text_color(theme::PLACEHOLDER_COLOR)
-            .lens(PlayRow::item);
+    if display.added_at {
+        let track_added_at = Label::<Arc<Track>>::dynamic(|track, _| {
+            track.added_at.map(format::date).unwrap_or_default()
+        })
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .lens(PlayRow::item);
+        major.add_default_spacer();
+        major.add_child(track_added_at);
+    }
+
+    if display.added_by {
+        let avatar = Maybe::or_empty(|| user::avatar_widget(theme::grid(2.5)))
+            .lens(PlayRow::item.then(Track::added_by.in_arc()));
+        major.add_default_spacer();
+        major.add_child(avatar);
+    }
+
+    let track_duration = Label::<Arc<Track>>::dynamic(|track, _| format::duration(track.duration))
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .lens(PlayRow::item);
     major.add_default_spacer();
     major.add_child(track_duration);
 
-    let saved = ViewSwitcher::new(
-        |row: &PlayRow<Arc<Track>>, _| row.ctx.library.saved_tracks.is_resolved(),
-        |selector: &bool, _, _| match selector {
-            true => ViewSwitcher::new(
-                |row: &PlayRow<Arc<Track>>, _| row.ctx.library.contains_track(&row.item),
-                |selector: &bool, _, _| {
-                    match selector {
-                        true => &icons::CIRCLE_CHECK,
-                        false => &icons::CIRCLE_PLUS,
-                    }
-                    .scale(theme::ICON_SIZE_SMALL)
-                    .boxed()
-                },
-            )
-            .on_left_click(|ctx, _, row, _| {
-                let track = &row.item;
-                if row.ctx.library.contains_track(track) {
-                    ctx.submit_command(library::UNSAVE_TRACK.with(track.id))
-                } else {
-                    ctx.submit_command(library::SAVE_TRACK.with(track.clone()))
+    main_row.with_flex_child(
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(major)
+            .with_spacer(2.0)
+            .with_child(minor)
+            .on_left_click(row_clicked),
+        1.0,
+    )
+}
+
+/// Why the track cannot be played, if it cannot.
+fn unavailable_reason(row: &PlayRow<Arc<Track>>) -> Option<&'static str> {
+    if !row.ctx.is_available(row.item.id.0) {
+        Some("Not available offline")
+    } else {
+        row.item.restriction().map(|reason| reason.explanation())
+    }
+}
+
+/// Add the `cell` of `column` to a row of the table, so that it lines up with
+/// the header.
+fn add_cell<T: Data>(row: &mut Flex<T>, column: TrackColumn, cell: impl Widget<T> + 'static) {
+    if column != TrackColumn::ALL[0] {
+        row.add_default_spacer();
+    }
+    match column {
+        TrackColumn::Title => row.add_flex_child(cell, 2.0),
+        TrackColumn::Artist | TrackColumn::Album => row.add_flex_child(cell, 1.0),
+        TrackColumn::Duration => row.add_child(cell.fix_width(theme::grid(6.0))),
+        TrackColumn::DateAdded => row.add_child(cell.fix_width(theme::grid(14.0))),
+        TrackColumn::Popularity => row.add_child(cell.fix_width(theme::grid(8.0))),
+    }
+}
+
+fn table_cells_widget(display: Display) -> impl Widget<PlayRow<Arc<Track>>> {
+    let detail = |text: fn(&Track) -> String| {
+        Label::<Arc<Track>>::dynamic(move |track, _| text(track))
+            .with_line_break_mode(LineBreaking::Clip)
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .expand_width()
+            .lens(PlayRow::item)
+    };
+
+    let mut row = Flex::row().cross_axis_alignment(CrossAxisAlignment::Center);
+    for column in TrackColumn::ALL {
+        match column {
+            TrackColumn::Title => {
+                let title = Flex::column()
+                    .cross_axis_alignment(CrossAxisAlignment::Start)
+                    .with_child(
+                        Label::raw()
+                            .with_font(theme::UI_FONT_MEDIUM)
+                            .with_line_break_mode(LineBreaking::Clip)
+                            .lens(PlayRow::item.then(Track::name.in_arc())),
+                    )
+                    .with_child(Either::new(
+                        |row: &PlayRow<Arc<Track>>, _| unavailable_reason(row).is_some(),
+                        Label::dynamic(|row: &PlayRow<Arc<Track>>, _| {
+                            unavailable_reason(row).unwrap_or_default().to_string()
+                        })
+                        .with_text_size(theme::TEXT_SIZE_SMALL)
+                        .with_text_color(theme::PLACEHOLDER_COLOR),
+                        Empty,
+                    ));
+                add_cell(&mut row, column, title.expand_width());
+            }
+            TrackColumn::Artist => add_cell(&mut row, column, detail(Track::artist_names)),
+            TrackColumn::Album => {
+                add_cell(&mut row, column, detail(|t| t.album_name().to_string()))
+            }
+            TrackColumn::Duration => {
+                add_cell(&mut row, column, detail(|t| format::duration(t.duration)))
+            }
+            TrackColumn::DateAdded => {
+                let mut added = Flex::row().with_flex_child(
+                    detail(|t| t.added_at.map(format::date).unwrap_or_default()),
+                    1.0,
+                );
+                if display.added_by {
+                    added.add_child(
+                        Maybe::or_empty(|| user::avatar_widget(theme::grid(2.5)))
+                            .lens(PlayRow::item.then(Track::added_by.in_arc())),
+                    );
                 }
-            })
-            .boxed(),
-            false => Box::new(Flex::column()),
-        },
-    );
+                add_cell(&mut row, column, added);
+            }
+            TrackColumn::Popularity => add_cell(
+                &mut row,
+                column,
+                detail(|t| t.popularity.map(popularity_stars).unwrap_or_default()),
+            ),
+        }
+    }
+    row.on_left_click(row_clicked)
+}
 
-    main_row
-        .with_flex_child(
-            Flex::column()
-                .cross_axis_alignment(CrossAxisAlignment::Start)
-                .with_child(major)
-                .with_spacer(2.0)
-                .with_child(minor)
-                .on_left_click(|ctx, _, row, _| {
-                    ctx.submit_notification(cmd::PLAY.with(row.position))
-                }),
-            1.0,
-        )
-        .with_default_spacer()
-        .with_child(saved.center())
-        .padding(theme::grid(1.0))
-        .link()
-        .active(|row: &PlayRow<Arc<Track>>, _env: &Env| {
-            // Check if this track is the target of album detail navigation
-            if let Nav::AlbumDetail(_, Some(target_id)) = &row.ctx.nav {
-                return *target_id == row.item.id;
+/// Plays the row, or with Ctrl (Cmd on macOS) held, adds it to the selection
+/// or takes it out.  Dragging a selected row drags the whole selection.
+fn row_clicked(ctx: &mut EventCtx, mouse: &MouseEvent, row: &mut PlayRow<Arc<Track>>, _: &Env) {
+    let select = if cfg!(target_os = "macos") {
+        mouse.mods.meta()
+    } else {
+        mouse.mods.ctrl()
+    };
+    if select {
+        ctx.submit_command(cmd::TOGGLE_TRACK_SELECTION.with(row.item.clone()));
+    } else {
+        if !row.ctx.selected_tracks.is_empty() {
+            ctx.submit_command(cmd::CLEAR_TRACK_SELECTION);
+        }
+        ctx.submit_notification(cmd::PLAY.with(row.position));
+    }
+}
+
+/// Headers of the columns of a track table, sorting by their column when
+/// clicked, with a row of filters below them.
+pub fn table_header_widget(sort: fn(&Config) -> TableSort) -> impl Widget<AppState> {
+    let mut headers = Flex::row();
+    let mut filters = Flex::row();
+    for column in TrackColumn::ALL {
+        let header = Label::dynamic(move |data: &AppState, _| {
+            let sort = sort(&data.config);
+            match sort.order {
+                _ if sort.column != column => column.label().to_string(),
+                SortOrder::Ascending => format!("{} ▲", column.label()),
+                SortOrder::Descending => format!("{} ▼", column.label()),
             }
-            // Otherwise check if it's playing or is the current track
-            row.is_playing || row.ctx.now_playing.as_ref().is_some_and(|playable| {
-                matches!(playable, Playable::Track(track) if track.id == row.item.id)
-            })
         })
+        .with_font(theme::UI_FONT_MEDIUM)
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .with_line_break_mode(LineBreaking::Clip)
+        .expand_width()
+        .link()
         .rounded(theme::BUTTON_BORDER_RADIUS)
-        .context_menu(track_row_menu)
+        .on_left_click(move |ctx, _, _, _| ctx.submit_command(cmd::SORT_TRACKS.with(column)));
+        add_cell(&mut headers, column, header);
+
+        let filter = TextBox::new()
+            .with_placeholder(column.filter_hint())
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .expand_width()
+            .lens(Map::new(
+                move |data: &AppState| data.common_ctx.track_filters.get(column).to_string(),
+                move |data: &mut AppState, filter: String| {
+                    if data.common_ctx.track_filters.get(column) != filter {
+                        data.common_ctx_mut().track_filters.set(column, filter);
+                    }
+                },
+            ));
+        add_cell(&mut filters, column, filter);
+    }
+    // Room for the saved mark at the end of the rows.
+    let mark = theme::grid(1.0) + theme::ICON_SIZE_SMALL.width;
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Fill)
+        .with_child(headers.padding((0.0, 0.0, mark, 0.0)))
+        .with_spacer(theme::grid(0.5))
+        .with_child(filters.padding((0.0, 0.0, mark, 0.0)))
+        .padding(theme::grid(1.0))
 }
 
 fn cover_widget(size: f64) -> impl Widget<Arc<Track>> {
@@ -207,6 +424,7 @@ fn cover_widget(size: f64) -> impl Widget<Arc<Track>> {
             .as_ref()
             .and_then(|al| al.image(size, size).map(|image| image.url.clone()))
     })
+    .with_size((size, size))
     .fix_size(size, size)
 }
 
@@ -243,70 +461,114 @@ pub fn track_menu(
 ) -> Menu<AppState> {
     let mut menu = Menu::empty();
 
-    for artist_link in &track.artists {
-        let more_than_one_artist = track.artists.len() > 1;
-        let title = if more_than_one_artist {
-            LocalizedString::new("menu-item-show-artist-name")
-                .with_placeholder(format!("Go to Artist \"{}\"", artist_link.name))
-        } else {
-            LocalizedString::new("menu-item-show-artist").with_placeholder("Go to Artist")
-        };
+    // Local files are not known to Spotify.
+    if !track.is_local {
+        for artist_link in &track.artists {
+            let more_than_one_artist = track.artists.len() > 1;
+            let title = if more_than_one_artist {
+                LocalizedString::new("menu-item-show-artist-name")
+                    .with_placeholder(format!("Go to Artist \"{}\"", artist_link.name))
+            } else {
+                LocalizedString::new("menu-item-show-artist").with_placeholder("Go to Artist")
+            };
+            menu = menu.entry(
+                MenuItem::new(title)
+                    .command(cmd::NAVIGATE.with(Nav::ArtistDetail(artist_link.to_owned()))),
+            );
+        }
+
+        if let Some(album_link) = track.album.as_ref() {
+            menu = menu.entry(
+                MenuItem::new(
+                    LocalizedString::new("menu-item-show-album").with_placeholder("Go to Album"),
+                )
+                .command(cmd::NAVIGATE.with(Nav::AlbumDetail(album_link.to_owned(), None))),
+            );
+        }
+
         menu = menu.entry(
-            MenuItem::new(title)
-                .command(cmd::NAVIGATE.with(Nav::ArtistDetail(artist_link.to_owned()))),
+            MenuItem::new(
+                LocalizedString::new("menu-item-start-radio").with_placeholder("Start Radio"),
+            )
+            .command(cmd::START_RADIO.with(Arc::new(RecommendationsRequest::track_radio(track)))),
         );
-    }
 
-    if let Some(album_link) = track.album.as_ref() {
         menu = menu.entry(
             MenuItem::new(
-                LocalizedString::new("menu-item-show-album").with_placeholder("Go to Album"),
+                LocalizedString::new("menu-item-show-recommended")
+                    .with_placeholder("Show Similar Tracks"),
             )
-            .command(cmd::NAVIGATE.with(Nav::AlbumDetail(album_link.to_owned(), None))),
+            .command(cmd::NAVIGATE.with(Nav::Recommendations(Arc::new(
+                RecommendationsRequest::for_track(track.id),
+            )))),
+        );
+
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-show-credits").with_placeholder("Show Song Info"),
+            )
+            .command(cmd::SHOW_CREDITS_WINDOW.with(track.clone())),
         );
     }
 
-    menu = menu.entry(
-        MenuItem::new(
-            LocalizedString::new("menu-item-show-recommended")
-                .with_placeholder("Show Similar Tracks"),
-        )
-        .command(cmd::NAVIGATE.with(Nav::Recommendations(Arc::new(
-            RecommendationsRequest::for_track(track.id),
-        )))),
-    );
+    if track.local_path.is_some() {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-export-replay-gain")
+                    .with_placeholder("Export ReplayGain Tags"),
+            )
+            .command(cmd::EXPORT_REPLAY_GAIN.with(track.clone())),
+        );
+    }
 
-    menu = menu.entry(
-        MenuItem::new(
-            LocalizedString::new("menu-item-show-credits").with_placeholder("Show Track Credits"),
-        )
-        .command(cmd::SHOW_CREDITS_WINDOW.with(track.clone())),
-    );
+    if track.restriction().is_some() {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-find-playable-version")
+                    .with_placeholder("Find Playable Version"),
+            )
+            .command(cmd::FIND_PLAYABLE_VERSION.with(track.clone())),
+        );
+    }
 
     menu = menu.separator();
 
-    menu = menu.entry(
-        MenuItem::new(
-            LocalizedString::new("menu-item-copy-link").with_placeholder("Copy Link to Track"),
-        )
-        .command(cmd::COPY.with(track.url())),
-    );
-
-    if library.contains_track(track) {
+    if !track.is_local {
         menu = menu.entry(
             MenuItem::new(
-                LocalizedString::new("menu-item-remove-from-library")
-                    .with_placeholder("Remove Track from Library"),
+                LocalizedString::new("menu-item-copy-link").with_placeholder("Copy Link to Track"),
             )
-            .command(library::UNSAVE_TRACK.with(track.id)),
+            .command(cmd::COPY.with(track.url())),
         );
-    } else {
+
+        if library.contains_track(track) {
+            menu = menu.entry(
+                MenuItem::new(
+                    LocalizedString::new("menu-item-remove-from-library")
+                        .with_placeholder("Remove Track from Library"),
+                )
+                .command(library::UNSAVE_TRACK.with(track.id)),
+            );
+        } else {
+            menu = menu.entry(
+                MenuItem::new(
+                    LocalizedString::new("menu-item-save-to-library")
+                        .with_placeholder("Save Track to Library"),
+                )
+                .command(library::SAVE_TRACK.with(track.clone())),
+            );
+        }
+    }
+
+    if library.lastfm_loved.is_some() {
+        let loved = library.is_lastfm_loved(&track.id);
+        let title = if loved {
+            LocalizedString::new("menu-item-lastfm-unlove").with_placeholder("Unlove on Last.fm")
+        } else {
+            LocalizedString::new("menu-item-lastfm-love").with_placeholder("Love on Last.fm")
+        };
         menu = menu.entry(
-            MenuItem::new(
-                LocalizedString::new("menu-item-save-to-library")
-                    .with_placeholder("Save Track to Library"),
-            )
-            .command(library::SAVE_TRACK.with(track.clone())),
+            MenuItem::new(title).command(cmd::LASTFM_SET_LOVED.with((track.clone(), !loved))),
         );
     }
 
@@ -353,6 +615,17 @@ pub fn track_menu(
         }
     }
 
+    menu = menu.entry(
+        MenuItem::new(LocalizedString::new("menu-item-play-next").with_placeholder("Play Next"))
+            .command(cmd::QUEUE_ITEMS.with((
+                QueuePlacement::Next,
+                vec![QueueEntry {
+                    item: Playable::Track(track.clone()),
+                    origin: origin.clone(),
+                }],
+            ))),
+    );
+
     menu = menu.entry(
         MenuItem::new(
             LocalizedString::new("menu-item-add-to-queue").with_placeholder("Add Track to Queue"),
@@ -364,12 +637,19 @@ pub fn track_menu(
                 origin: origin.clone(),
             },
             PlaybackItem {
-                item_id: ItemId::from_base62(&String::from(track.id), ItemIdType::Track).unwrap(),
+                item_id: track.id.0,
                 norm_level: NormalizationLevel::Track,
+                group: Playable::Track(track.clone()).item_group(),
+                // Filled in from the origin when queued.
+                skip_intro: Duration::ZERO,
             },
         ))),
     );
 
+    if track.is_local {
+        return menu;
+    }
+
     let mut playlist_menu = Menu::new(
         LocalizedString::new("menu-item-add-to-playlist").with_placeholder("Add to Playlist"),
     );
@@ -379,9 +659,9 @@ pub fn track_menu(
                 LocalizedString::new("menu-item-save-to-playlist")
                     .with_placeholder(format!("{}", playlist.name)),
             )
-            .command(playlist::ADD_TRACK.with(PlaylistAddTrack {
+            .command(playlist::ADD_TRACKS.with(PlaylistAddTracks {
                 link: playlist.link(),
-                track_id: track.id,
+                track_ids: Vector::unit(track.id),
             })),
         );
     }
@@ -1,30 +1,50 @@
+use std::sync::Arc;
+
 use druid::{
     commands,
-    widget::{Either, Flex, Label},
+    kurbo::Circle,
+    widget::{Flex, Label},
     Data, LensExt, Selector, Widget, WidgetExt,
 };
 
+use psst_core::session::ConnectionState;
+
 use crate::{
-    data::{AppState, Library, UserProfile},
+    cmd,
+    data::{AppState, Library, PublicUserProfile, UserProfile},
     webapi::WebApi,
-    widget::{icons, icons::SvgIcon, Async, Empty, MyWidgetExt},
+    widget::{icons, icons::SvgIcon, Async, Empty, MyWidgetExt, RemoteImage},
 };
 
-use super::theme;
+use super::{theme, utils};
 
 pub const LOAD_PROFILE: Selector = Selector::new("app.user.load-profile");
 
 pub fn user_widget() -> impl Widget<AppState> {
-    let is_connected = Either::new(
-        // TODO: Avoid the locking here.
-        |state: &AppState, _| state.session.is_connected(),
-        Label::new("Connected")
-            .with_text_color(theme::PLACEHOLDER_COLOR)
-            .with_text_size(theme::TEXT_SIZE_SMALL),
-        Label::new("Disconnected")
-            .with_text_color(theme::PLACEHOLDER_COLOR)
-            .with_text_size(theme::TEXT_SIZE_SMALL),
-    );
+    let is_connected = Label::dynamic(|state: &AppState, _| match &state.connection_state {
+        _ if state.config.offline_mode => "Offline".to_string(),
+        _ if state.lost_connection => "Offline, connection lost".to_string(),
+        ConnectionState::Connected => "Connected".to_string(),
+        ConnectionState::Disconnected => "Disconnected".to_string(),
+        ConnectionState::Reconnecting { attempt, .. } => {
+            format!("Reconnecting (attempt {attempt})...")
+        }
+    })
+    .with_text_color(theme::PLACEHOLDER_COLOR)
+    .with_text_size(theme::TEXT_SIZE_SMALL);
+
+    let toggle_offline = Label::dynamic(|state: &AppState, _| {
+        if state.is_offline() {
+            "Go online".to_string()
+        } else {
+            "Go offline".to_string()
+        }
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .padding((theme::grid(0.5), theme::grid(0.25)))
+    .link()
+    .rounded(theme::BUTTON_BORDER_RADIUS)
+    .on_left_click(|ctx, _, _, _| ctx.submit_command(cmd::TOGGLE_OFFLINE_MODE));
 
     let user_profile = Async::new(
         || Empty,
@@ -47,6 +67,7 @@ pub fn user_widget() -> impl Widget<AppState> {
         .with_child(
             Flex::column()
                 .with_child(is_connected)
+                .with_child(toggle_offline)
                 .with_default_spacer()
                 .with_child(user_profile)
                 .padding(theme::grid(1.0)),
@@ -61,3 +82,17 @@ fn preferences_widget<T: Data>(svg: &SvgIcon) -> impl Widget<T> {
         .rounded(theme::BUTTON_BORDER_RADIUS)
         .on_left_click(|ctx, _, _, _| ctx.submit_command(commands::SHOW_PREFERENCES))
 }
+
+/// Round profile picture of another user.
+pub fn avatar_widget(size: f64) -> impl Widget<Arc<PublicUserProfile>> {
+    let radius = size / 2.0;
+    RemoteImage::new(
+        utils::placeholder_widget(),
+        move |user: &Arc<PublicUserProfile>, _| {
+            user.image(size, size).map(|image| image.url.clone())
+        },
+    )
+    .with_size((size, size))
+    .fix_size(size, size)
+    .clip(Circle::new((radius, radius), radius))
+}
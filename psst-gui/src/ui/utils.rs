@@ -1,13 +1,19 @@
-use std::{f64::consts::PI, time::Duration};
+use std::{f64::consts::PI, sync::Arc, time::Duration};
 
 use druid::{
+    im::Vector,
     kurbo::Circle,
     widget::{prelude::*, CrossAxisAlignment, Flex, Label, SizedBox},
     Data, Point, Vec2, Widget, WidgetExt, WidgetPod,
 };
 use time_humanize::HumanTime;
 
-use crate::{data::WithCtx, error::Error, widget::icons};
+use crate::{
+    data::{Track, WithCtx},
+    error::Error,
+    format,
+    widget::icons,
+};
 
 use super::theme;
 
@@ -121,10 +127,17 @@ pub fn error_widget() -> impl Widget<Error> {
         .center()
 }
 
-pub fn as_minutes_and_seconds(dur: Duration) -> String {
-    let minutes = dur.as_secs() / 60;
-    let seconds = dur.as_secs() % 60;
-    format!("{minutes}∶{seconds:02}")
+/// Number of songs and their total length, shown under the header of a track
+/// list.
+pub fn track_list_summary(tracks: &Vector<Arc<Track>>) -> String {
+    let count = tracks.len();
+    let songs = if count == 1 {
+        "1 song".to_string()
+    } else {
+        format!("{count} songs")
+    };
+    let duration: Duration = tracks.iter().map(|track| track.duration).sum();
+    format!("{songs} • {}", format::total_duration(duration))
 }
 
 pub fn as_human(dur: Duration) -> String {
@@ -134,24 +147,6 @@ pub fn as_human(dur: Duration) -> String {
     )
 }
 
-pub fn format_number_with_commas(n: i64) -> String {
-    let s = n.to_string();
-    if s.len() <= 3 {
-        return s;
-    }
-    // Reverse the string, chunk it, then reverse the chunks to process from left to right.
-    s.chars()
-        .rev()
-        .collect::<Vec<_>>()
-        .chunks(3)
-        .rev()
-        // Reverse the characters in each chunk back to their original order and collect into a string.
-        .map(|chunk| chunk.iter().rev().collect::<String>())
-        .collect::<Vec<_>>()
-        // Join the chunks with commas.
-        .join(",")
-}
-
 pub struct InfoLayout<T, B, S> {
     biography: WidgetPod<T, B>,
     stats: WidgetPod<T, S>,
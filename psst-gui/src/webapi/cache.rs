@@ -7,15 +7,18 @@ use std::{
     sync::Arc,
 };
 
-use druid::image;
 use druid::ImageBuf;
 use lru::LruCache;
 use parking_lot::Mutex;
 use psst_core::cache::mkdir_if_not_exists;
 
+/// Decoded images are kept in memory per requested size, in pixels.  `None`
+/// is the original size.
+type ImageKey = (Arc<str>, Option<(u32, u32)>);
+
 pub struct WebApiCache {
     base: Option<PathBuf>,
-    images: Mutex<LruCache<Arc<str>, ImageBuf>>,
+    images: Mutex<LruCache<ImageKey, ImageBuf>>,
 }
 
 impl WebApiCache {
@@ -27,20 +30,19 @@ impl WebApiCache {
         }
     }
 
-    pub fn get_image(&self, uri: &Arc<str>) -> Option<ImageBuf> {
-        self.images.lock().get(uri).cloned()
+    pub fn get_image(&self, uri: &Arc<str>, size: Option<(u32, u32)>) -> Option<ImageBuf> {
+        self.images.lock().get(&(uri.clone(), size)).cloned()
     }
 
-    pub fn set_image(&self, uri: Arc<str>, image: ImageBuf) {
-        self.images.lock().put(uri, image);
+    pub fn set_image(&self, uri: Arc<str>, size: Option<(u32, u32)>, image: ImageBuf) {
+        self.images.lock().put((uri, size), image);
     }
 
-    pub fn get_image_from_disk(&self, uri: &Arc<str>) -> Option<ImageBuf> {
+    /// Original encoded bytes of a downloaded image.
+    pub fn get_image_data_from_disk(&self, uri: &Arc<str>) -> Option<Vec<u8>> {
         let hash = Self::hash_uri(uri);
         self.key("images", &format!("{hash:016x}"))
             .and_then(|path| std::fs::read(path).ok())
-            .and_then(|bytes| image::load_from_memory(&bytes).ok())
-            .map(ImageBuf::from_dynamic_image)
     }
 
     pub fn save_image_to_disk(&self, uri: &Arc<str>, data: &[u8]) {
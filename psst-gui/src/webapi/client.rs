@@ -3,9 +3,12 @@ use std::{
     fmt::Display,
     io::{self, Read},
     path::PathBuf,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
 use druid::{
@@ -19,7 +22,7 @@ use log::info;
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
 use serde::{de::DeserializeOwned, Deserialize};
-use serde_json::json;
+use serde_json::{json, Value};
 
 use ureq::{
     http::{Response, StatusCode},
@@ -30,49 +33,75 @@ use crate::{
     cmd,
     data::{
         self, utils::sanitize_html_string, Album, AlbumType, Artist, ArtistAlbums, ArtistInfo,
-        ArtistLink, ArtistStats, AudioAnalysis, Cached, Episode, EpisodeId, EpisodeLink, Image,
-        MixedView, Nav, Page, Playlist, PublicUser, Range, Recommendations, RecommendationsRequest,
-        SearchResults, SearchTopic, Show, SpotifyUrl, Track, TrackLines, UserProfile,
+        ArtistLink, ArtistReleases, ArtistStats, AudioAnalysis, AudioFeatures, Cached, Category,
+        ConnectDevice, Episode, EpisodeId, EpisodeLink, Image, MadeForYou, MixedView, Nav, Page,
+        Playlist, PlaylistVisibility, PublicUser, PublicUserProfile, Range, Recommendations,
+        RecommendationsRequest, RemotePlayback, SearchResults, SearchTopic, Show, SpotifyUrl,
+        TimeRange, Track, TrackId, TrackLines, UserProfile, MADE_FOR_YOU_REFRESH, PAGE_SIZE,
     },
     error::Error,
+    test_mode::TestMode,
     ui::credits::TrackCredits,
 };
 
-use super::{cache::WebApiCache, local::LocalTrackManager};
+use super::{
+    cache::WebApiCache,
+    compat::{ApiFeature, ApiStatus, Compatibility},
+    library_index::{self, DeltaSync, LibraryIndex},
+    local::LocalTrackManager,
+};
 use psst_core::oauth::refresh_access_token;
 use sanitize_html::rules::predefined::DEFAULT;
 use sanitize_html::sanitize_str;
 
 pub struct WebApi {
-    agent: Agent,
+    agent: Mutex<Agent>,
     cache: WebApiCache,
     oauth_bearer: Mutex<Option<String>>,
     oauth_refresh_token: Mutex<Option<String>>,
     local_track_manager: Mutex<LocalTrackManager>,
     event_sink: Mutex<Option<ExtEventSink>>,
-    paginated_limit: usize,
+    compat: Compatibility,
+    library_index: LibraryIndex,
+    /// Saved tracks as of the last sync, pages are served from them.
+    saved_tracks: Mutex<Option<Vector<Arc<Track>>>>,
+    /// Fail every request without sending it, see `set_offline`.
+    offline: AtomicBool,
 }
 
 impl WebApi {
-    pub fn new(
-        proxy_url: Option<&str>,
-        cache_base: Option<PathBuf>,
-        paginated_limit: usize,
-    ) -> Self {
-        let mut agent = Agent::config_builder().timeout_global(Some(Duration::from_secs(5)));
-        if let Some(proxy_url) = proxy_url {
-            let proxy = ureq::Proxy::new(proxy_url).ok();
-            agent = agent.proxy(proxy);
-        }
+    pub fn new(proxy_url: Option<&str>, cache_base: Option<PathBuf>) -> Self {
         Self {
-            agent: agent.build().into(),
-            cache: WebApiCache::new(cache_base),
+            agent: Mutex::new(Self::build_agent(proxy_url)),
+            cache: WebApiCache::new(cache_base.clone()),
             oauth_bearer: Mutex::new(None),
             oauth_refresh_token: Mutex::new(None),
             local_track_manager: Mutex::new(LocalTrackManager::new()),
             event_sink: Mutex::new(None),
-            paginated_limit,
+            compat: Compatibility::default(),
+            library_index: LibraryIndex::new(cache_base),
+            saved_tracks: Mutex::new(None),
+            offline: AtomicBool::new(false),
+        }
+    }
+
+    fn build_agent(proxy_url: Option<&str>) -> Agent {
+        let mut agent = Agent::config_builder().timeout_global(Some(Duration::from_secs(5)));
+        if let Some(proxy_url) = proxy_url {
+            let proxy = ureq::Proxy::new(proxy_url).ok();
+            agent = agent.proxy(proxy);
         }
+        agent.build().into()
+    }
+
+    /// Send the following requests through the proxy at `proxy_url`, or
+    /// directly if it is `None`.
+    pub fn set_proxy(&self, proxy_url: Option<&str>) {
+        *self.agent.lock() = Self::build_agent(proxy_url);
+    }
+
+    fn agent(&self) -> Agent {
+        self.agent.lock().clone()
     }
 
     fn access_token(&self) -> Result<String, Error> {
@@ -96,15 +125,36 @@ impl WebApi {
         *self.event_sink.lock() = Some(sink);
     }
 
+    /// While offline, only what is cached or indexed is loaded, everything
+    /// else fails.
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::SeqCst);
+    }
+
     fn request(&self, request: &RequestBuilder) -> Result<Response<Body>, Error> {
         let request = request.clone().query("market", "from_token");
 
+        if let Some(test_mode) = TestMode::global() {
+            return Self::fixture_response(test_mode, &request);
+        }
+
+        if self.offline.load(Ordering::SeqCst) {
+            return Err(Error::WebApiError("Not available offline".to_string()));
+        }
+
+        // Removed endpoints are not asked again until the app restarts.
+        if let Some(feature) = request.api_feature() {
+            if self.compat.status(feature) == Some(ApiStatus::Removed) {
+                return Err(self.api_error(feature, ApiStatus::Removed));
+            }
+        }
+
         // Use the current OAuth bearer for the request.
         let token = self.access_token()?;
         let call = |token: &str| match request.get_method() {
             Method::Get => {
                 let mut req = self
-                    .agent
+                    .agent()
                     .get(request.build())
                     .header("Authorization", &format!("Bearer {token}"));
                 for header in request.get_headers() {
@@ -113,17 +163,17 @@ impl WebApi {
                 req.call()
             }
             Method::Post => self
-                .agent
+                .agent()
                 .post(request.build())
                 .header("Authorization", &format!("Bearer {token}"))
                 .send_json(request.get_body()),
             Method::Put => self
-                .agent
+                .agent()
                 .put(request.build())
                 .header("Authorization", &format!("Bearer {token}"))
                 .send_json(request.get_body()),
             Method::Delete => self
-                .agent
+                .agent()
                 .delete(request.build())
                 .header("Authorization", &format!("Bearer {token}"))
                 .force_send_body()
@@ -160,12 +210,86 @@ impl WebApi {
                     return Err(Error::WebApiError("Missing refresh token".to_string()));
                 }
             }
+            Err(err @ ureq::Error::StatusCode(code)) => {
+                return Err(match request.api_feature() {
+                    Some(feature) if feature.is_removal(code) => {
+                        self.api_error(feature, ApiStatus::Removed)
+                    }
+                    _ => Error::WebApiError(err.to_string()),
+                });
+            }
             Err(err) => return Err(Error::WebApiError(err.to_string())),
         };
 
         Ok(response)
     }
 
+    /// Record the status of an optional feature, and tell the UI when it
+    /// changed.
+    fn set_api_status(&self, feature: ApiFeature, status: ApiStatus) {
+        if !self.compat.record(feature, status.clone()) {
+            return;
+        }
+        if !status.is_available() {
+            log::warn!("{}: {}", feature.label(), status.describe());
+        }
+        let alert = self.compat.first_failure(feature, &status);
+        if let Some(sink) = self.event_sink.lock().as_ref() {
+            sink.submit_command(
+                cmd::API_STATUS_CHANGED,
+                (feature, status, alert),
+                Target::Global,
+            )
+            .ok();
+        }
+    }
+
+    /// Error for a request to `feature`, which is not usable with `status`.
+    fn api_error(&self, feature: ApiFeature, status: ApiStatus) -> Error {
+        let error = Error::WebApiError(format!("{}: {}", feature.label(), status.describe()));
+        self.set_api_status(feature, status);
+        error
+    }
+
+    /// Pass on a decoded response, recording whether the response to an
+    /// optional feature could still be read.
+    fn decoded<T>(&self, request: &RequestBuilder, result: Result<T, String>) -> Result<T, Error> {
+        match (request.api_feature(), result) {
+            (Some(feature), Ok(value)) => {
+                self.set_api_status(feature, ApiStatus::Available);
+                Ok(value)
+            }
+            (Some(feature), Err(err)) => {
+                Err(self.api_error(feature, ApiStatus::Changed(err.into())))
+            }
+            (None, result) => result.map_err(Error::WebApiError),
+        }
+    }
+
+    /// Answer `request` from the fixtures of the test mode.  Changes are
+    /// accepted, and forgotten.
+    fn fixture_response(
+        test_mode: &TestMode,
+        request: &RequestBuilder,
+    ) -> Result<Response<Body>, Error> {
+        let body = match request.get_method() {
+            Method::Get => {
+                let query = |key: &str| request.queries.get(key).and_then(|v| v.parse().ok());
+                test_mode.fixture(
+                    &request.base_uri,
+                    &request.path,
+                    query("offset"),
+                    query("limit"),
+                )?
+            }
+            _ => b"{}".to_vec(),
+        };
+        Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::builder().mime_type("application/json").data(body))
+            .map_err(|err| Error::WebApiError(err.to_string()))
+    }
+
     fn with_retry(f: impl Fn() -> Result<Response<Body>, Error>) -> Result<Response<Body>, Error> {
         loop {
             match f() {
@@ -198,10 +322,8 @@ impl WebApi {
     /// requests.
     fn load<T: DeserializeOwned>(&self, request: &RequestBuilder) -> Result<T, Error> {
         let mut response = Self::with_retry(|| self.request(request))?;
-        response
-            .body_mut()
-            .read_json()
-            .map_err(|err| Error::WebApiError(err.to_string()))
+        let result = response.body_mut().read_json();
+        self.decoded(request, result.map_err(|err| err.to_string()))
     }
 
     /// Send a request using `self.load()`, but only if it isn't already present
@@ -224,21 +346,64 @@ impl WebApi {
                 reader.read_to_end(&mut body)?;
                 body
             };
-            let value = serde_json::from_slice(&body)?;
+            let value = serde_json::from_slice(&body).map_err(|err| err.to_string());
+            let value = self.decoded(request, value)?;
             self.cache.set(bucket, key, &body);
             Ok(Cached::fresh(value))
         }
     }
 
+    /// Like `load_cached`, but a response cached more than `max_age` ago is
+    /// loaded again.  Returns the time the response was cached at, `None` if
+    /// it was just loaded.
+    fn load_cached_for<T: DeserializeOwned>(
+        &self,
+        request: &RequestBuilder,
+        bucket: &str,
+        key: &str,
+        max_age: Duration,
+    ) -> Result<(T, Option<SystemTime>), Error> {
+        if let Some(file) = self.cache.get(bucket, key) {
+            let cached_at = file.metadata()?.modified()?;
+            if cached_at.elapsed().unwrap_or_default() < max_age {
+                if let Ok(value) = serde_json::from_reader(file) {
+                    return Ok((value, Some(cached_at)));
+                }
+            }
+        }
+        let response = Self::with_retry(|| self.request(request))?;
+        let body = {
+            let mut reader = response.into_body().into_reader();
+            let mut body = Vec::new();
+            reader.read_to_end(&mut body)?;
+            body
+        };
+        let value = serde_json::from_slice(&body).map_err(|err| err.to_string());
+        let value = self.decoded(request, value)?;
+        self.cache.set(bucket, key, &body);
+        Ok((value, None))
+    }
+
     /// Iterate a paginated result set by sending `request` with added
     /// pagination parameters.  Mostly used through `load_all_pages`.
     fn for_all_pages<T: DeserializeOwned + Clone>(
         &self,
         request: &RequestBuilder,
+        func: impl FnMut(Page<T>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.for_pages_to(request, usize::MAX, func)
+    }
+
+    /// Like `for_all_pages`, but stops at `max_offset`, for the endpoints
+    /// that refuse pages past it.
+    fn for_pages_to<T: DeserializeOwned + Clone>(
+        &self,
+        request: &RequestBuilder,
+        max_offset: usize,
         mut func: impl FnMut(Page<T>) -> Result<(), Error>,
     ) -> Result<(), Error> {
-        // TODO: Some result sets, like very long playlists and saved tracks/albums can
-        // be very big.  Implement virtualized scrolling and lazy-loading of results.
+        // TODO: Some result sets, like very long playlists and saved albums can be
+        // very big.  Load them in pages as they are scrolled to, like the saved tracks.
         let mut limit = 50;
         let mut offset = 0;
         loop {
@@ -253,7 +418,7 @@ impl WebApi {
             let page_limit = page.limit;
             func(page)?;
 
-            if page_total > offset && offset < self.paginated_limit {
+            if page_total > offset && page_offset + page_limit <= max_offset {
                 limit = page_limit;
                 offset = page_offset + page_limit;
             } else {
@@ -295,7 +460,7 @@ impl WebApi {
                 let page_limit = page.limit;
                 func(page)?;
 
-                if page_total > offset && offset < self.paginated_limit {
+                if page_total > offset {
                     limit = page_limit;
                     offset = page_offset + page_limit;
                 } else {
@@ -305,15 +470,80 @@ impl WebApi {
         }
         Ok(())
     }
+
+    /// Saved items of `request`, the newest first, synced with the `list` of
+    /// the index, see `DeltaSync`.  The entries hold the items in `field`.
+    /// Without a connection, the indexed items are returned.
+    fn load_synced<T: DeserializeOwned>(
+        &self,
+        request: &RequestBuilder,
+        list: &str,
+        field: &'static str,
+    ) -> Result<Vec<T>, Error> {
+        let synced = match self.library_index.load(list) {
+            Some(indexed) => match self.load_delta(request, DeltaSync::new(field, indexed)) {
+                Ok(synced) => synced,
+                Err(err) => {
+                    log::warn!("failed to sync {list}, using the index: {err}");
+                    return Self::decode_indexed(self.library_index.load(list).unwrap_or_default());
+                }
+            },
+            None => None,
+        };
+        let items = match synced {
+            Some(items) => items,
+            None => self.load_all_pages::<Value>(request)?.into_iter().collect(),
+        };
+        self.library_index.save(list, &items);
+        Self::decode_indexed(items)
+    }
+
+    /// Read the pages of `request` into `sync` until it reaches the indexed
+    /// items, `None` if the list has to be loaded whole.
+    fn load_delta(
+        &self,
+        request: &RequestBuilder,
+        mut sync: DeltaSync,
+    ) -> Result<Option<Vec<Value>>, Error> {
+        let mut offset = 0;
+        loop {
+            let req = request
+                .clone()
+                .query("limit", PAGE_SIZE)
+                .query("offset", offset);
+            let page: Page<Value> = self.load(&req)?;
+            offset += page.items.len();
+            if sync.add_page(page.items) || offset >= page.total {
+                return Ok(sync.finish(page.total));
+            }
+        }
+    }
+
+    fn decode_indexed<T: DeserializeOwned>(items: Vec<Value>) -> Result<Vec<T>, Error> {
+        items
+            .into_iter()
+            .map(|item| serde_json::from_value(item).map_err(Error::from))
+            .collect()
+    }
+
     /// Load a paginated result set by sending `request` with added pagination
     /// parameters and return the aggregated results.  Use with GET requests.
     fn load_all_pages<T: DeserializeOwned + Clone>(
         &self,
         request: &RequestBuilder,
+    ) -> Result<Vector<T>, Error> {
+        self.load_pages_to(request, usize::MAX)
+    }
+
+    /// Like `load_all_pages`, but stops at `max_offset`, see `for_pages_to`.
+    fn load_pages_to<T: DeserializeOwned + Clone>(
+        &self,
+        request: &RequestBuilder,
+        max_offset: usize,
     ) -> Result<Vector<T>, Error> {
         let mut results = Vector::new();
 
-        self.for_all_pages(request, |page| {
+        self.for_pages_to(request, max_offset, |page| {
             results.append(page.items);
             Ok(())
         })?;
@@ -348,7 +578,13 @@ impl WebApi {
         }
     }
 
-    fn load_and_return_home_section(&self, request: &RequestBuilder) -> Result<MixedView, Error> {
+    /// Load a home section, from the cache if `cached` gives its key and
+    /// the age it can be used until.
+    fn load_and_return_home_section(
+        &self,
+        request: &RequestBuilder,
+        cached: Option<(&str, Duration)>,
+    ) -> Result<Cached<MixedView>, Error> {
         #[derive(Deserialize)]
         pub struct Welcome {
             data: WelcomeData,
@@ -509,7 +745,11 @@ impl WebApi {
         }
 
         // Extract the playlists
-        let result: Welcome = match self.load(request) {
+        let result = match cached {
+            Some((key, max_age)) => self.load_cached_for(request, "home-section", key, max_age),
+            None => self.load(request).map(|value| (value, None)),
+        };
+        let (result, cached_at): (Welcome, _) = match result {
             Ok(res) => res,
             Err(e) => {
                 info!("Error loading home section: {e}");
@@ -596,6 +836,7 @@ impl WebApi {
                                 },
                                 collaborative: false,
                                 public: None,
+                                snapshot_id: "".into(),
                             });
                         }
                         DataTypename::Artist => artist.push_back(Artist {
@@ -660,8 +901,11 @@ impl WebApi {
                             copyrights: Vector::new(),
                             label: "".into(),
                             tracks: Vector::new(),
+                            total_tracks: 0,
                             release_date: None,
                             release_date_precision: None,
+                            restrictions: None,
+                            saved_at: None,
                         })),
                         DataTypename::Podcast => show.push_back(Arc::new(Show {
                             id: id.into(),
@@ -697,12 +941,15 @@ impl WebApi {
                 });
             });
 
-        Ok(MixedView {
-            title,
-            playlists: playlist,
-            artists: artist,
-            albums: album,
-            shows: show,
+        Ok(Cached {
+            data: MixedView {
+                title,
+                playlists: playlist,
+                artists: artist,
+                albums: album,
+                shows: show,
+            },
+            cached_at,
         })
     }
 }
@@ -731,22 +978,33 @@ impl WebApi {
         Ok(result)
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/get-users-profile
+    pub fn get_public_user_profile(&self, id: &str) -> Result<PublicUserProfile, Error> {
+        let request = &RequestBuilder::new(format!("v1/users/{id}"), Method::Get, None);
+        let result = self.load_cached(request, "user", id)?;
+        Ok(result.data)
+    }
+
     // https://developer.spotify.com/documentation/web-api/reference/get-users-top-artists-and-tracks
-    pub fn get_user_top_tracks(&self) -> Result<Vector<Arc<Track>>, Error> {
+    /// Top tracks of the user over `range`, the top one first.
+    pub fn get_user_top_tracks(&self, range: TimeRange) -> Result<Vector<Arc<Track>>, Error> {
         let request = &RequestBuilder::new("v1/me/top/tracks".to_string(), Method::Get, None)
+            .query("time_range", range.api_value())
             .query("market", "from_token");
         let result: Vector<Arc<Track>> = self.load_some_pages(request, 30)?;
 
         Ok(result)
     }
 
-    pub fn get_user_top_artist(&self) -> Result<Vector<Artist>, Error> {
+    /// Top artists of the user over `range`, the top one first.
+    pub fn get_user_top_artist(&self, range: TimeRange) -> Result<Vector<Artist>, Error> {
         #[allow(dead_code)]
         #[derive(Clone, Data, Deserialize)]
         struct Artists {
             artists: Artist,
         }
-        let request = &RequestBuilder::new("v1/me/top/artists", Method::Get, None);
+        let request = &RequestBuilder::new("v1/me/top/artists", Method::Get, None)
+            .query("time_range", range.api_value());
 
         Ok(self
             .load_some_pages(request, 10)?
@@ -756,6 +1014,9 @@ impl WebApi {
     }
 }
 
+/// Releases asked for per group on an artist page, until all of them are.
+const ARTIST_RELEASES_PREVIEW: usize = 6;
+
 /// Artist endpoints.
 impl WebApi {
     // https://developer.spotify.com/documentation/web-api/reference/get-artist/
@@ -767,47 +1028,53 @@ impl WebApi {
 
     // https://developer.spotify.com/documentation/web-api/reference/get-an-artists-albums/
     pub fn get_artist_albums(&self, id: &str) -> Result<ArtistAlbums, Error> {
-        let request = &RequestBuilder::new(format!("v1/artists/{id}/albums"), Method::Get, None)
-            .query("market", "from_token");
-        let result: Vector<Arc<Album>> = self.load_all_pages(request)?;
+        Ok(ArtistAlbums {
+            albums: self.get_artist_releases_preview(id, AlbumType::Album)?,
+            singles: self.get_artist_releases_preview(id, AlbumType::Single)?,
+            compilations: self.get_artist_releases_preview(id, AlbumType::Compilation)?,
+            appears_on: self.get_artist_releases_preview(id, AlbumType::AppearsOn)?,
+        })
+    }
 
-        let mut artist_albums = ArtistAlbums {
-            albums: Vector::new(),
-            singles: Vector::new(),
-            compilations: Vector::new(),
-            appears_on: Vector::new(),
+    fn artist_releases_request(id: &str, group: &AlbumType) -> RequestBuilder {
+        let group = match group {
+            AlbumType::Album => "album",
+            AlbumType::Single => "single",
+            AlbumType::Compilation => "compilation",
+            AlbumType::AppearsOn => "appears_on",
         };
+        RequestBuilder::new(format!("v1/artists/{id}/albums"), Method::Get, None)
+            .query("market", "from_token")
+            .query("include_groups", group)
+    }
 
-        let mut last_album_release_year = usize::MAX;
-        let mut last_single_release_year = usize::MAX;
-
-        for album in result {
-            match album.album_type {
-                // Spotify is labeling albums and singles that should be labeled `appears_on` as `album` or `single`.
-                // They are still ordered properly though, with the most recent first, then 'appears_on'.
-                // So we just wait until they are no longer descending, then start putting them in the 'appears_on' Vec.
-                // NOTE: This will break if an artist has released 'appears_on' albums/singles before their first actual album/single.
-                AlbumType::Album => {
-                    if album.release_year_int() > last_album_release_year {
-                        artist_albums.appears_on.push_back(album)
-                    } else {
-                        last_album_release_year = album.release_year_int();
-                        artist_albums.albums.push_back(album)
-                    }
-                }
-                AlbumType::Single => {
-                    if album.release_year_int() > last_single_release_year {
-                        artist_albums.appears_on.push_back(album);
-                    } else {
-                        last_single_release_year = album.release_year_int();
-                        artist_albums.singles.push_back(album);
-                    }
-                }
-                AlbumType::Compilation => artist_albums.compilations.push_back(album),
-                AlbumType::AppearsOn => artist_albums.appears_on.push_back(album),
-            }
-        }
-        Ok(artist_albums)
+    /// The first releases of the artist in `group`, with the total count.
+    fn get_artist_releases_preview(
+        &self,
+        id: &str,
+        group: AlbumType,
+    ) -> Result<ArtistReleases, Error> {
+        let request =
+            &Self::artist_releases_request(id, &group).query("limit", ARTIST_RELEASES_PREVIEW);
+        let page: Page<Arc<Album>> = self.load(request)?;
+        Ok(ArtistReleases {
+            group,
+            albums: page.items,
+            total: page.total,
+            loading: false,
+        })
+    }
+
+    /// All releases of the artist in `group`.
+    pub fn get_artist_releases(&self, id: &str, group: AlbumType) -> Result<ArtistReleases, Error> {
+        let request = &Self::artist_releases_request(id, &group);
+        let albums: Vector<Arc<Album>> = self.load_all_pages(request)?;
+        Ok(ArtistReleases {
+            group,
+            total: albums.len(),
+            albums,
+            loading: false,
+        })
     }
 
     // https://developer.spotify.com/documentation/web-api/reference/get-an-artists-top-tracks
@@ -838,6 +1105,33 @@ impl WebApi {
         Ok(result.map(|result| result.artists))
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/check-current-user-follows
+    pub fn is_following_artist(&self, id: &str) -> Result<bool, Error> {
+        let request = &RequestBuilder::new("v1/me/following/contains", Method::Get, None)
+            .query("type", "artist")
+            .query("ids", id);
+        let result: Vec<bool> = self.load(request)?;
+        Ok(result.first().copied().unwrap_or(false))
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/follow-artists-users
+    pub fn follow_artist(&self, id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/following", Method::Put, None)
+            .query("type", "artist")
+            .query("ids", id);
+        self.request(request)?;
+        Ok(())
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/unfollow-artists-users
+    pub fn unfollow_artist(&self, id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/following", Method::Delete, None)
+            .query("type", "artist")
+            .query("ids", id);
+        self.request(request)?;
+        Ok(())
+    }
+
     pub fn get_artist_info(&self, id: &str) -> Result<ArtistInfo, Error> {
         #[derive(Clone, Data, Deserialize)]
         pub struct Welcome {
@@ -953,13 +1247,85 @@ impl WebApi {
     }
 }
 
+/// Player endpoints.
+impl WebApi {
+    // https://developer.spotify.com/documentation/web-api/reference/get-a-users-available-devices
+    pub fn get_devices(&self) -> Result<Vector<ConnectDevice>, Error> {
+        #[derive(Deserialize)]
+        struct Devices {
+            devices: Vector<ConnectDevice>,
+        }
+
+        let request = &RequestBuilder::new("v1/me/player/devices", Method::Get, None);
+        let result: Devices = self.load(request)?;
+        Ok(result.devices)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-information-about-the-users-current-playback
+    pub fn get_playback_state(&self) -> Result<Option<RemotePlayback>, Error> {
+        let request = &RequestBuilder::new("v1/me/player", Method::Get, None);
+        let mut response = Self::with_retry(|| self.request(request))?;
+        // Nothing is playing on any device.
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        response
+            .body_mut()
+            .read_json()
+            .map(Some)
+            .map_err(|err| Error::WebApiError(err.to_string()))
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/pause-a-users-playback
+    pub fn pause_device(&self, device_id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/player/pause", Method::Put, None)
+            .query("device_id", device_id);
+        self.send_empty_json(request)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/start-a-users-playback
+    pub fn resume_device(&self, device_id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/player/play", Method::Put, None)
+            .query("device_id", device_id);
+        self.send_empty_json(request)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/skip-users-playback-to-next-track
+    pub fn skip_device_to_next(&self, device_id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/player/next", Method::Post, None)
+            .query("device_id", device_id);
+        self.send_empty_json(request)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/skip-users-playback-to-previous-track
+    pub fn skip_device_to_previous(&self, device_id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/player/previous", Method::Post, None)
+            .query("device_id", device_id);
+        self.send_empty_json(request)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/set-volume-for-users-playback
+    pub fn set_device_volume(&self, device_id: &str, volume_percent: u32) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/player/volume", Method::Put, None)
+            .query("device_id", device_id)
+            .query("volume_percent", volume_percent.min(100));
+        self.send_empty_json(request)
+    }
+}
+
 /// Album endpoints.
 impl WebApi {
     // https://developer.spotify.com/documentation/web-api/reference/get-an-album/
     pub fn get_album(&self, id: &str) -> Result<Cached<Arc<Album>>, Error> {
         let request = &RequestBuilder::new(format!("v1/albums/{id}"), Method::Get, None)
             .query("market", "from_token");
-        let result = self.load_cached(request, "album", id)?;
+        let mut result: Cached<Arc<Album>> = self.load_cached(request, "album", id)?;
+        // Long albums, like box sets, need the rest of their tracks paged in.
+        if result.data.tracks.len() < result.data.total_tracks {
+            let request = &RequestBuilder::new(format!("v1/albums/{id}/tracks"), Method::Get, None)
+                .query("market", "from_token");
+            Arc::make_mut(&mut result.data).tracks = self.load_all_pages(request)?;
+        }
         Ok(result)
     }
 }
@@ -1024,6 +1390,47 @@ impl WebApi {
         self.load(request)
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/get-several-tracks
+    /// Tracks with `ids`, without the unavailable ones.  Each track is cached
+    /// on its own, only the tracks not loaded before are requested.
+    pub fn get_tracks(&self, ids: &[&str]) -> Result<Vector<Arc<Track>>, Error> {
+        #[derive(Deserialize)]
+        struct Tracks {
+            tracks: Vec<Option<serde_json::Value>>,
+        }
+
+        let mut tracks = Vector::new();
+        let mut missing = Vec::new();
+        for &id in ids {
+            let cached = self
+                .cache
+                .get("track", id)
+                .and_then(|file| serde_json::from_reader(file).ok());
+            match cached {
+                Some(track) => tracks.push_back(track),
+                None => missing.push(id),
+            }
+        }
+        // The endpoint accepts at most 50 ids per request.
+        for chunk in missing.chunks(50) {
+            let request = &RequestBuilder::new("v1/tracks", Method::Get, None)
+                .query("ids", chunk.join(","))
+                .query("market", "from_token");
+            let result: Tracks = self.load(request)?;
+            // Tracks come in the order of their ids, relinked ones with another
+            // id, so they are cached by the id asked for.
+            for (id, value) in chunk.iter().zip(result.tracks) {
+                let Some(value) = value else {
+                    continue;
+                };
+                let track: Arc<Track> = serde_json::from_value(value.clone())?;
+                self.cache.set("track", id, value.to_string().as_bytes());
+                tracks.push_back(track);
+            }
+        }
+        Ok(tracks)
+    }
+
     pub fn get_track_credits(&self, track_id: &str) -> Result<TrackCredits, Error> {
         let request = &RequestBuilder::new(
             format!("track-credits-view/v0/experimental/{track_id}/credits"),
@@ -1073,15 +1480,21 @@ impl WebApi {
         #[derive(Clone, Deserialize)]
         struct SavedAlbum {
             album: Arc<Album>,
+            #[serde(default, deserialize_with = "data::utils::deserialize_date_option")]
+            added_at: Option<time::Date>,
         }
 
         let request =
             &RequestBuilder::new("v1/me/albums", Method::Get, None).query("market", "from_token");
 
         Ok(self
-            .load_all_pages(request)?
+            .load_synced(request, library_index::SAVED_ALBUMS, "album")?
             .into_iter()
-            .map(|item: SavedAlbum| item.album)
+            .map(|item: SavedAlbum| {
+                let mut album = item.album;
+                Arc::make_mut(&mut album).saved_at = item.added_at;
+                album
+            })
             .collect())
     }
 
@@ -1099,19 +1512,52 @@ impl WebApi {
         self.send_empty_json(request)
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/remove-albums-user/
+    pub fn unsave_albums(&self, ids: &[Arc<str>]) -> Result<(), Error> {
+        // The endpoint accepts at most 20 ids per request.
+        for chunk in ids.chunks(20) {
+            let request = &RequestBuilder::new(
+                "v1/me/albums",
+                Method::Delete,
+                Some(json!({ "ids": chunk })),
+            );
+            self.send_empty_json(request)?;
+        }
+        Ok(())
+    }
+
     // https://developer.spotify.com/documentation/web-api/reference/get-users-saved-tracks/
     pub fn get_saved_tracks(&self) -> Result<Vector<Arc<Track>>, Error> {
-        #[derive(Clone, Deserialize)]
-        struct SavedTrack {
-            track: Arc<Track>,
-        }
         let request =
             &RequestBuilder::new("v1/me/tracks", Method::Get, None).query("market", "from_token");
-        Ok(self
-            .load_all_pages(request)?
+        let tracks: Vector<Arc<Track>> = self
+            .load_synced(request, library_index::SAVED_TRACKS, "track")?
             .into_iter()
-            .map(|item: SavedTrack| item.track)
-            .collect())
+            .map(SavedTrack::into_track)
+            .collect();
+        *self.saved_tracks.lock() = Some(tracks.clone());
+        Ok(tracks)
+    }
+
+    /// Page of at most `limit` saved tracks from `offset`, the most recently
+    /// saved first, and the count of all saved tracks.  Served from the last
+    /// sync, if there was one since the tracks were changed.
+    pub fn get_saved_tracks_page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vector<Arc<Track>>, usize), Error> {
+        if let Some(tracks) = &*self.saved_tracks.lock() {
+            let page = tracks.iter().skip(offset).take(limit).cloned().collect();
+            return Ok((page, tracks.len()));
+        }
+        let request = &RequestBuilder::new("v1/me/tracks", Method::Get, None)
+            .query("market", "from_token")
+            .query("limit", limit)
+            .query("offset", offset);
+        let page: Page<SavedTrack> = self.load(request)?;
+        let tracks = page.items.into_iter().map(SavedTrack::into_track).collect();
+        Ok((tracks, page.total))
     }
 
     // https://developer.spotify.com/documentation/web-api/reference/get-users-saved-shows
@@ -1131,18 +1577,64 @@ impl WebApi {
             .collect())
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/get-users-saved-episodes
+    pub fn get_saved_episodes(&self) -> Result<Vector<Arc<Episode>>, Error> {
+        #[derive(Clone, Deserialize)]
+        struct SavedEpisode {
+            episode: Arc<Episode>,
+        }
+
+        let request =
+            &RequestBuilder::new("v1/me/episodes", Method::Get, None).query("market", "from_token");
+
+        Ok(self
+            .load_all_pages(request)?
+            .into_iter()
+            .map(|item: SavedEpisode| item.episode)
+            .collect())
+    }
+
+    /// Ids of the saved tracks as of the last sync, empty without one.
+    pub fn synced_saved_track_ids(&self) -> Vec<TrackId> {
+        self.saved_tracks
+            .lock()
+            .iter()
+            .flatten()
+            .map(|track| track.id)
+            .collect()
+    }
+
+    /// Sync the indexed saved tracks and albums, loading only what was saved
+    /// since the last sync.
+    pub fn sync_library(&self) -> Result<(), Error> {
+        self.get_saved_tracks()?;
+        self.get_saved_albums()?;
+        Ok(())
+    }
+
     // https://developer.spotify.com/documentation/web-api/reference/save-tracks-user/
     pub fn save_track(&self, id: &str) -> Result<(), Error> {
+        // Pages are loaded from Spotify until the next sync picks it up.
+        *self.saved_tracks.lock() = None;
         let request = &RequestBuilder::new("v1/me/tracks", Method::Put, None).query("ids", id);
         self.send_empty_json(request)
     }
 
     // https://developer.spotify.com/documentation/web-api/reference/remove-tracks-user/
     pub fn unsave_track(&self, id: &str) -> Result<(), Error> {
+        *self.saved_tracks.lock() = None;
         let request = &RequestBuilder::new("v1/me/tracks", Method::Delete, None).query("ids", id);
         self.send_empty_json(request)
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/check-users-saved-shows
+    pub fn is_show_saved(&self, id: &str) -> Result<bool, Error> {
+        let request =
+            &RequestBuilder::new("v1/me/shows/contains", Method::Get, None).query("ids", id);
+        let result: Vec<bool> = self.load(request)?;
+        Ok(result.first().copied().unwrap_or(false))
+    }
+
     // https://developer.spotify.com/documentation/web-api/reference/save-shows-user
     pub fn save_show(&self, id: &str) -> Result<(), Error> {
         let request = &RequestBuilder::new("v1/me/shows", Method::Put, None).query("ids", id);
@@ -1154,6 +1646,90 @@ impl WebApi {
         let request = &RequestBuilder::new("v1/me/shows", Method::Delete, None).query("ids", id);
         self.send_empty_json(request)
     }
+
+    // https://developer.spotify.com/documentation/web-api/reference/save-episodes-user
+    pub fn save_episode(&self, id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/episodes", Method::Put, None).query("ids", id);
+        self.send_empty_json(request)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/remove-episodes-user
+    pub fn unsave_episode(&self, id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/episodes", Method::Delete, None).query("ids", id);
+        self.send_empty_json(request)
+    }
+}
+
+/// Browse endpoints.
+impl WebApi {
+    // https://developer.spotify.com/documentation/web-api/reference/get-featured-playlists
+    pub fn get_featured_playlists(
+        &self,
+        offset: usize,
+    ) -> Result<(Vector<Playlist>, usize), Error> {
+        #[derive(Deserialize)]
+        struct Featured {
+            playlists: Page<Playlist>,
+        }
+
+        let request = &RequestBuilder::new("v1/browse/featured-playlists", Method::Get, None)
+            .query("limit", PAGE_SIZE)
+            .query("offset", offset);
+        let result: Featured = self.load(request)?;
+        Ok((result.playlists.items, result.playlists.total))
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-new-releases
+    pub fn get_new_releases(&self, offset: usize) -> Result<(Vector<Arc<Album>>, usize), Error> {
+        #[derive(Deserialize)]
+        struct NewReleases {
+            albums: Page<Arc<Album>>,
+        }
+
+        let request = &RequestBuilder::new("v1/browse/new-releases", Method::Get, None)
+            .query("limit", PAGE_SIZE)
+            .query("offset", offset);
+        let result: NewReleases = self.load(request)?;
+        Ok((result.albums.items, result.albums.total))
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-categories
+    pub fn get_categories(&self, offset: usize) -> Result<(Vector<Category>, usize), Error> {
+        #[derive(Deserialize)]
+        struct Categories {
+            categories: Page<Category>,
+        }
+
+        let request = &RequestBuilder::new("v1/browse/categories", Method::Get, None)
+            .query("limit", PAGE_SIZE)
+            .query("offset", offset);
+        let result: Categories = self.load(request)?;
+        Ok((result.categories.items, result.categories.total))
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-a-categories-playlists
+    pub fn get_category_playlists(
+        &self,
+        id: &str,
+        offset: usize,
+    ) -> Result<(Vector<Playlist>, usize), Error> {
+        #[derive(Deserialize)]
+        struct CategoryPlaylists {
+            playlists: Page<Option<Playlist>>,
+        }
+
+        let request = &RequestBuilder::new(
+            format!("v1/browse/categories/{id}/playlists"),
+            Method::Get,
+            None,
+        )
+        .query("limit", PAGE_SIZE)
+        .query("offset", offset);
+        let result: CategoryPlaylists = self.load(request)?;
+        // Playlists that were taken down are listed as nulls.
+        let page = result.playlists;
+        Ok((page.items.into_iter().flatten().collect(), page.total))
+    }
 }
 
 /// View endpoints.
@@ -1178,6 +1754,15 @@ impl WebApi {
     }
 
     pub fn get_section(&self, section_uri: &str) -> Result<MixedView, Error> {
+        let request = &self.home_section_request(section_uri, 20)?;
+        Ok(self.load_and_return_home_section(request, None)?.data)
+    }
+
+    fn home_section_request(
+        &self,
+        section_uri: &str,
+        items_limit: usize,
+    ) -> Result<RequestBuilder, Error> {
         let (country, time_zone) = self.get_user_info()?;
         let access_token = self.access_token()?;
 
@@ -1192,7 +1777,7 @@ impl WebApi {
             },
             "operationName": "homeSection",
             "variables":  {
-                "sectionItemsLimit": 20,
+                "sectionItemsLimit": items_limit,
                 "sectionItemsOffset": 0,
                 "sp_t": access_token,
                 "timeZone": time_zone,
@@ -1202,18 +1787,22 @@ impl WebApi {
         });
 
         let request =
-            &RequestBuilder::new("pathfinder/v2/query".to_string(), Method::Post, Some(json))
+            RequestBuilder::new("pathfinder/v2/query".to_string(), Method::Post, Some(json))
                 .set_base_uri("api-partner.spotify.com")
                 .header("app-platform", "WebPlayer")
                 .header("Content-Type", "application/json");
-
-        // Extract the playlists
-        self.load_and_return_home_section(request)
+        Ok(request)
     }
 
-    pub fn get_made_for_you(&self) -> Result<MixedView, Error> {
+    /// The Made For You section, with all its mixes, kept in the cache until
+    /// it is due to be refreshed.
+    pub fn get_made_for_you(&self) -> Result<MadeForYou, Error> {
         // 0JQ5DAqAJXkJGsa2DyEjKi -> Made for you
-        self.get_section("spotify:section:0JQ5DAqAJXkJGsa2DyEjKi")
+        const SECTION: &str = "0JQ5DAqAJXkJGsa2DyEjKi";
+        let request = &self.home_section_request(&format!("spotify:section:{SECTION}"), 50)?;
+        let section =
+            self.load_and_return_home_section(request, Some((SECTION, MADE_FOR_YOU_REFRESH)))?;
+        Ok(MadeForYou::new(section.data.playlists, section.cached_at))
     }
 
     pub fn get_top_mixes(&self) -> Result<MixedView, Error> {
@@ -1254,13 +1843,28 @@ impl WebApi {
     }
 }
 
+/// Offset past which `v1/me/playlists` refuses pages.
+const PLAYLISTS_MAX_OFFSET: usize = 100_000;
+
 /// Playlist endpoints.
 impl WebApi {
     // https://developer.spotify.com/documentation/web-api/reference/get-a-list-of-current-users-playlists
     pub fn get_playlists(&self) -> Result<Vector<Playlist>, Error> {
         let request = &RequestBuilder::new("v1/me/playlists", Method::Get, None);
-        let result: Vector<Playlist> = self.load_all_pages(request)?;
-        Ok(result)
+        // The playlists are few, they are loaded whole and only indexed for
+        // browsing without a connection.
+        let items: Vec<Value> = match self.load_pages_to::<Value>(request, PLAYLISTS_MAX_OFFSET) {
+            Ok(items) => items.into_iter().collect(),
+            Err(err) => match self.library_index.load(library_index::PLAYLISTS) {
+                Some(items) => {
+                    log::warn!("failed to load playlists, using the index: {err}");
+                    items
+                }
+                None => return Err(err),
+            },
+        };
+        self.library_index.save(library_index::PLAYLISTS, &items);
+        Ok(Self::decode_indexed::<Playlist>(items)?.into())
     }
 
     pub fn follow_playlist(&self, id: &str) -> Result<(), Error> {
@@ -1290,6 +1894,10 @@ impl WebApi {
         #[derive(Clone, Deserialize)]
         struct PlaylistItem {
             track: OptionalTrack,
+            #[serde(default, deserialize_with = "data::utils::deserialize_date_option")]
+            added_at: Option<time::Date>,
+            #[serde(default)]
+            added_by: Option<PublicUserProfile>,
         }
 
         // Spotify API likes to return _really_ bogus data for local tracks. Much better
@@ -1310,6 +1918,17 @@ impl WebApi {
 
         let local_track_manager = self.local_track_manager.lock();
 
+        // Who added a track is only worth showing with more than one
+        // contributor.
+        let collaborative = result
+            .iter()
+            .filter_map(|item| item.added_by.as_ref())
+            .map(|user| &user.id)
+            .unique()
+            .nth(1)
+            .is_some();
+        let mut members: HashMap<Arc<str>, Arc<PublicUserProfile>> = HashMap::new();
+
         Ok(result
             .into_iter()
             .enumerate()
@@ -1318,28 +1937,214 @@ impl WebApi {
                     OptionalTrack::Track(track) => track,
                     OptionalTrack::Json(json) => local_track_manager.find_local_track(json)?,
                 };
-                Arc::make_mut(&mut track).track_pos = index;
+                let track_mut = Arc::make_mut(&mut track);
+                track_mut.track_pos = index;
+                track_mut.added_at = item.added_at;
+                track_mut.added_by = item.added_by.filter(|_| collaborative).map(|user| {
+                    members
+                        .entry(user.id.clone())
+                        .or_insert_with(|| Arc::new(user))
+                        .clone()
+                });
                 Some(track)
             })
             .collect())
     }
 
-    pub fn change_playlist_details(&self, id: &str, name: &str) -> Result<(), Error> {
-        let request = &RequestBuilder::new(format!("v1/playlists/{id}/tracks"), Method::Get, None)
-            .set_body(Some(json!({ "name": name })));
-        self.request(request)?;
+    /// Load the profiles of the users who added the `tracks` of a
+    /// collaborative playlist, for their names and avatars.  Only the first
+    /// `MAX_MEMBERS` are loaded, the others keep just their ID.
+    pub fn load_playlist_members(&self, tracks: &mut Vector<Arc<Track>>) {
+        const MAX_MEMBERS: usize = 20;
+
+        let ids: Vec<Arc<str>> = tracks
+            .iter()
+            .filter_map(|track| track.added_by.as_ref())
+            .map(|user| user.id.clone())
+            .unique()
+            .take(MAX_MEMBERS)
+            .collect();
+        let mut profiles = HashMap::new();
+        for id in ids {
+            match self.get_public_user_profile(&id) {
+                Ok(profile) => {
+                    profiles.insert(id, Arc::new(profile));
+                }
+                Err(err) => log::warn!("failed to load the profile of {id}: {err}"),
+            }
+        }
+        for track in tracks.iter_mut() {
+            let profile = track
+                .added_by
+                .as_ref()
+                .and_then(|user| profiles.get(&user.id));
+            if let Some(profile) = profile {
+                Arc::make_mut(track).added_by = Some(profile.clone());
+            }
+        }
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/create-playlist
+    pub fn create_playlist(
+        &self,
+        user_id: &str,
+        name: &str,
+        description: &str,
+    ) -> Result<Playlist, Error> {
+        let request =
+            &RequestBuilder::new(format!("v1/users/{user_id}/playlists"), Method::Post, None)
+                .set_body(Some(json!({
+                    "name": name,
+                    "description": description,
+                    "public": false,
+                })));
+        let result: Playlist = self.load(request)?;
+        Ok(result)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/remove-tracks-playlist
+    pub fn remove_tracks_from_playlist(
+        &self,
+        playlist_id: &str,
+        uris: &[Arc<str>],
+    ) -> Result<(), Error> {
+        // The endpoint accepts at most 100 tracks per request.
+        for chunk in uris.chunks(100) {
+            let tracks: Vec<_> = chunk.iter().map(|uri| json!({ "uri": uri })).collect();
+            let request = &RequestBuilder::new(
+                format!("v1/playlists/{playlist_id}/tracks"),
+                Method::Delete,
+                None,
+            )
+            .set_body(Some(json!({ "tracks": tracks })));
+            self.request(request)?;
+        }
         Ok(())
     }
 
-    // https://developer.spotify.com/documentation/web-api/reference/add-tracks-to-playlist
-    pub fn add_track_to_playlist(&self, playlist_id: &str, track_uri: &str) -> Result<(), Error> {
+    // https://developer.spotify.com/documentation/web-api/reference/reorder-or-replace-playlists-tracks
+    pub fn replace_playlist_tracks(
+        &self,
+        playlist_id: &str,
+        track_uris: &[String],
+    ) -> Result<(), Error> {
+        // Both replacing and adding are limited to 100 tracks per request, so
+        // replace with the first chunk and append the rest.
+        const MAX_TRACKS_PER_REQUEST: usize = 100;
+
+        let mut chunks = track_uris.chunks(MAX_TRACKS_PER_REQUEST);
+        let first = chunks.next().unwrap_or_default();
         let request = &RequestBuilder::new(
             format!("v1/playlists/{playlist_id}/tracks"),
-            Method::Post,
+            Method::Put,
             None,
         )
-        .query("uris", track_uri);
-        self.request(request).map(|_| ())
+        .set_body(Some(json!({ "uris": first })));
+        self.request(request)?;
+
+        for chunk in chunks {
+            let request = &RequestBuilder::new(
+                format!("v1/playlists/{playlist_id}/tracks"),
+                Method::Post,
+                None,
+            )
+            .set_body(Some(json!({ "uris": chunk })));
+            self.request(request)?;
+        }
+        Ok(())
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/change-playlist-details
+    pub fn change_playlist_details(
+        &self,
+        id: &str,
+        name: &str,
+        description: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut body = json!({ "name": name });
+        if let Some(description) = description {
+            body["description"] = json!(description);
+        }
+        let request = &RequestBuilder::new(format!("v1/playlists/{id}"), Method::Put, None)
+            .set_body(Some(body));
+        self.request(request)?;
+        Ok(())
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/change-playlist-details
+    pub fn change_playlist_visibility(
+        &self,
+        id: &str,
+        visibility: PlaylistVisibility,
+    ) -> Result<(), Error> {
+        let body = json!({
+            "public": visibility.is_public(),
+            "collaborative": visibility == PlaylistVisibility::Collaborative,
+        });
+        let request = &RequestBuilder::new(format!("v1/playlists/{id}"), Method::Put, None)
+            .set_body(Some(body));
+        self.request(request)?;
+        Ok(())
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/add-tracks-to-playlist
+    /// Append the tracks, and return the version of the playlist that has
+    /// them.
+    pub fn add_tracks_to_playlist(
+        &self,
+        playlist_id: &str,
+        uris: &[Arc<str>],
+    ) -> Result<Arc<str>, Error> {
+        #[derive(Deserialize)]
+        struct Snapshot {
+            snapshot_id: Arc<str>,
+        }
+
+        // The endpoint accepts at most 100 tracks per request.
+        let mut snapshot_id = None;
+        for chunk in uris.chunks(100) {
+            let request = &RequestBuilder::new(
+                format!("v1/playlists/{playlist_id}/tracks"),
+                Method::Post,
+                None,
+            )
+            .set_body(Some(json!({ "uris": chunk })));
+            let result: Snapshot = self.load(request)?;
+            snapshot_id = Some(result.snapshot_id);
+        }
+        snapshot_id.ok_or_else(|| Error::WebApiError("No tracks to add".to_string()))
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/remove-tracks-playlist
+    /// Take out the tracks appended by `add_tracks_to_playlist`, at their
+    /// positions in the version of the playlist it returned.
+    pub fn remove_added_tracks(
+        &self,
+        playlist_id: &str,
+        snapshot_id: &str,
+        uris: &[Arc<str>],
+        position: usize,
+    ) -> Result<(), Error> {
+        let tracks: Vec<_> = uris
+            .iter()
+            .enumerate()
+            .map(|(index, uri)| json!({ "uri": uri, "positions": [position + index] }))
+            .collect();
+        // From the end, so that the positions of the chunks still to go stay
+        // where they were.
+        for chunk in tracks.rchunks(100) {
+            let request = &RequestBuilder::new(
+                format!("v1/playlists/{playlist_id}/tracks"),
+                Method::Delete,
+                None,
+            )
+            .set_body(Some(json!({
+                "tracks": chunk,
+                "snapshot_id": snapshot_id,
+            })));
+            self.request(request)?;
+        }
+        Ok(())
     }
 
     // https://developer.spotify.com/documentation/web-api/reference/remove-tracks-playlist
@@ -1356,6 +2161,25 @@ impl WebApi {
         .set_body(Some(json!({ "positions": [track_pos] })));
         self.request(request).map(|_| ())
     }
+
+    // https://developer.spotify.com/documentation/web-api/reference/reorder-or-replace-playlists-tracks
+    pub fn reorder_playlist_track(
+        &self,
+        playlist_id: &str,
+        range_start: usize,
+        insert_before: usize,
+    ) -> Result<(), Error> {
+        let request = &RequestBuilder::new(
+            format!("v1/playlists/{playlist_id}/tracks"),
+            Method::Put,
+            None,
+        )
+        .set_body(Some(json!({
+            "range_start": range_start,
+            "insert_before": insert_before,
+        })));
+        self.request(request).map(|_| ())
+    }
 }
 
 /// Search endpoints.
@@ -1380,7 +2204,7 @@ impl WebApi {
         let request = &RequestBuilder::new("v1/search", Method::Get, None)
             .query("q", query.replace(" ", "%20"))
             .query("type", &topics)
-            .query("limit", limit.to_string())
+            .query("limit", limit)
             .query("marker", "from_token");
 
         let result: ApiSearchResults = self.load(request)?;
@@ -1401,6 +2225,42 @@ impl WebApi {
         })
     }
 
+    /// Looks for a playable release of the same recording, first by its ISRC,
+    /// then by the name and the first artist.
+    pub fn find_playable_version(&self, track: &Track) -> Result<Option<Arc<Track>>, Error> {
+        #[derive(Deserialize)]
+        struct ApiSearchResults {
+            tracks: Page<Arc<Track>>,
+        }
+
+        let by_isrc = track
+            .external_ids
+            .isrc
+            .as_ref()
+            .map(|isrc| (format!("isrc:{isrc}"), false));
+        let by_name = (
+            format!("track:{} artist:{}", track.name, track.artist_name()),
+            true,
+        );
+        for (query, match_name) in by_isrc.into_iter().chain([by_name]) {
+            let request = &RequestBuilder::new("v1/search", Method::Get, None)
+                .query("q", query.replace(" ", "%20"))
+                .query("type", "track")
+                .query("limit", "10")
+                .query("market", "from_token");
+            let result: ApiSearchResults = self.load(request)?;
+            let found = result.tracks.items.into_iter().find(|found| {
+                found.id != track.id
+                    && found.is_playable != Some(false)
+                    && (!match_name || found.name.eq_ignore_ascii_case(&track.name))
+            });
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+        Ok(None)
+    }
+
     pub fn load_spotify_link(&self, link: &SpotifyUrl) -> Result<Nav, Error> {
         let nav = match link {
             SpotifyUrl::Playlist(id) => Nav::PlaylistDetail(self.get_playlist(id)?.link()),
@@ -1421,10 +2281,29 @@ impl WebApi {
 
 /// Recommendation endpoints.
 impl WebApi {
-    // https://developer.spotify.com/documentation/web-api/reference/get-recommendations
+    /// Requests with more seeds than the endpoint takes are split, and their
+    /// results merged.
     pub fn get_recommendations(
         &self,
         data: Arc<RecommendationsRequest>,
+    ) -> Result<Recommendations, Error> {
+        let requests = data.split_seeds();
+        if requests.len() == 1 {
+            let mut result = self.load_recommendations(&data)?;
+            result.request = data;
+            return Ok(result);
+        }
+        let parts = requests
+            .iter()
+            .map(|request| self.load_recommendations(request))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Recommendations::merge(data, parts))
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-recommendations
+    fn load_recommendations(
+        &self,
+        data: &RecommendationsRequest,
     ) -> Result<Recommendations, Error> {
         let seed_artists = data.seed_artists.iter().map(|link| &link.id).join(", ");
         let seed_tracks = data
@@ -1472,52 +2351,82 @@ impl WebApi {
         request = add_range_param(request, data.params.speechiness, "speechiness");
         request = add_range_param(request, data.params.valence, "valence");
 
-        let mut result: Recommendations = self.load(&request)?;
-        result.request = data;
-        Ok(result)
+        self.load(&request)
     }
 }
 
 /// Track endpoints.
 impl WebApi {
     // https://developer.spotify.com/documentation/web-api/reference/get-audio-analysis/
-    pub fn _get_audio_analysis(&self, track_id: &str) -> Result<AudioAnalysis, Error> {
+    pub fn get_audio_analysis(&self, track_id: &str) -> Result<AudioAnalysis, Error> {
         let request =
             &RequestBuilder::new(format!("v1/audio-analysis/{track_id}"), Method::Get, None);
         let result = self.load_cached(request, "audio-analysis", track_id)?;
         Ok(result.data)
     }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-audio-features/
+    pub fn get_audio_features(&self, track_id: &str) -> Result<AudioFeatures, Error> {
+        let request =
+            &RequestBuilder::new(format!("v1/audio-features/{track_id}"), Method::Get, None);
+        let result = self.load_cached(request, "audio-features", track_id)?;
+        Ok(result.data)
+    }
 }
 
 /// Image endpoints.
 impl WebApi {
-    pub fn get_cached_image(&self, uri: &Arc<str>) -> Option<ImageBuf> {
-        self.cache.get_image(uri)
+    pub fn get_cached_image(&self, uri: &Arc<str>, size: Option<(u32, u32)>) -> Option<ImageBuf> {
+        self.cache.get_image(uri, size)
     }
 
-    pub fn get_image(&self, uri: Arc<str>) -> Result<ImageBuf, Error> {
-        if let Some(cached_image) = self.cache.get_image(&uri) {
+    /// Decoded image at `uri`.  With a `size`, in pixels, larger images are
+    /// scaled down to fit it once here, instead of on every paint.
+    pub fn get_image(&self, uri: Arc<str>, size: Option<(u32, u32)>) -> Result<ImageBuf, Error> {
+        if let Some(cached_image) = self.cache.get_image(&uri, size) {
             return Ok(cached_image);
         }
 
-        if let Some(disk_cached_image) = self.cache.get_image_from_disk(&uri) {
-            self.cache.set_image(uri.clone(), disk_cached_image.clone());
-            return Ok(disk_cached_image);
+        let body = self.get_image_data(&uri)?;
+
+        let format = match infer::get(body.as_slice()) {
+            Some(kind) if kind.mime_type() == "image/jpeg" => Some(ImageFormat::Jpeg),
+            Some(kind) if kind.mime_type() == "image/png" => Some(ImageFormat::Png),
+            Some(kind) if kind.mime_type() == "image/webp" => Some(ImageFormat::WebP),
+            _ => None,
+        };
+
+        let mut image = if let Some(format) = format {
+            image::load_from_memory_with_format(&body, format)?
+        } else {
+            image::load_from_memory(&body)?
+        };
+        if let Some((width, height)) = size {
+            if image.width() > width || image.height() > height {
+                image = image.thumbnail(width, height);
+            }
+        }
+        let image_buf = ImageBuf::from_dynamic_image(image);
+        self.cache.set_image(uri, size, image_buf.clone());
+        Ok(image_buf)
+    }
+
+    /// Original, full-size bytes of the image at `uri`, as served by the CDN.
+    /// Taken from the disk cache when available.
+    pub fn get_image_data(&self, uri: &Arc<str>) -> Result<Vec<u8>, Error> {
+        if let Some(data) = self.cache.get_image_data_from_disk(uri) {
+            return Ok(data);
         }
 
         // Split the URI into its components
-        let uri_clone = uri.clone();
-        let parsed = url::Url::parse(&uri_clone).unwrap();
+        let parsed = url::Url::parse(uri).map_err(|err| Error::WebApiError(err.to_string()))?;
 
         let protocol = parsed.scheme();
-        let base_uri = parsed.host_str().unwrap();
+        let base_uri = parsed
+            .host_str()
+            .ok_or_else(|| Error::WebApiError(format!("Invalid image URI: {uri}")))?;
         let path = parsed.path().trim_start_matches('/');
 
-        let mut queries = std::collections::HashMap::new();
-        for (k, v) in parsed.query_pairs() {
-            queries.insert(k.to_string(), v.to_string());
-        }
-
         let request = RequestBuilder::new(path, Method::Get, None)
             .set_protocol(protocol)
             .set_base_uri(base_uri);
@@ -1526,24 +2435,10 @@ impl WebApi {
         let mut body = Vec::new();
         response.into_body().into_reader().read_to_end(&mut body)?;
 
-        let format = match infer::get(body.as_slice()) {
-            Some(kind) if kind.mime_type() == "image/jpeg" => Some(ImageFormat::Jpeg),
-            Some(kind) if kind.mime_type() == "image/png" => Some(ImageFormat::Png),
-            Some(kind) if kind.mime_type() == "image/webp" => Some(ImageFormat::WebP),
-            _ => None,
-        };
-
         // Save raw image data to disk cache
-        self.cache.save_image_to_disk(&uri, &body);
+        self.cache.save_image_to_disk(uri, &body);
 
-        let image = if let Some(format) = format {
-            image::load_from_memory_with_format(&body, format)?
-        } else {
-            image::load_from_memory(&body)?
-        };
-        let image_buf = ImageBuf::from_dynamic_image(image);
-        self.cache.set_image(uri, image_buf.clone());
-        Ok(image_buf)
+        Ok(body)
     }
 }
 
@@ -1571,6 +2466,22 @@ impl From<image::ImageError> for Error {
     }
 }
 
+/// Item of the saved tracks endpoint.
+#[derive(Clone, Deserialize)]
+struct SavedTrack {
+    track: Arc<Track>,
+    #[serde(default, deserialize_with = "data::utils::deserialize_date_option")]
+    added_at: Option<time::Date>,
+}
+
+impl SavedTrack {
+    fn into_track(self) -> Arc<Track> {
+        let mut track = self.track;
+        Arc::make_mut(&mut track).added_at = self.added_at;
+        track
+    }
+}
+
 #[derive(Debug, Clone)]
 enum Method {
     Post,
@@ -1632,6 +2543,15 @@ impl RequestBuilder {
     fn get_method(&self) -> &Method {
         &self.method
     }
+    /// Optional feature served by the request.
+    fn api_feature(&self) -> Option<ApiFeature> {
+        let operation = self
+            .body
+            .as_ref()
+            .and_then(|body| body.get("operationName"))
+            .and_then(|operation| operation.as_str());
+        ApiFeature::for_request(&self.base_uri, &self.path, operation)
+    }
     #[allow(dead_code)]
     fn set_method(mut self, method: Method) -> Self {
         self.method = method;
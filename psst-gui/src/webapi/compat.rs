@@ -0,0 +1,220 @@
+//! Spotify keeps removing and reshaping the endpoints that the optional
+//! features are built on.  Each request is classified by the feature it
+//! serves, and the outcome is recorded, so that a removed endpoint or a
+//! changed response turns the feature off with a notice, instead of showing
+//! a JSON error on every page that uses it.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use druid::Data;
+use parking_lot::Mutex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Data)]
+pub enum ApiFeature {
+    Home,
+    ArtistInfo,
+    RelatedArtists,
+    Recommendations,
+    AudioAnalysis,
+    AudioFeatures,
+    Lyrics,
+    TrackCredits,
+    BrowsePlaylists,
+}
+
+impl ApiFeature {
+    pub const ALL: [Self; 9] = [
+        Self::Home,
+        Self::ArtistInfo,
+        Self::RelatedArtists,
+        Self::Recommendations,
+        Self::AudioAnalysis,
+        Self::AudioFeatures,
+        Self::Lyrics,
+        Self::TrackCredits,
+        Self::BrowsePlaylists,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Home => "Home sections",
+            Self::ArtistInfo => "Artist biographies",
+            Self::RelatedArtists => "Related artists",
+            Self::Recommendations => "Recommendations",
+            Self::AudioAnalysis => "Audio analysis",
+            Self::AudioFeatures => "Audio features",
+            Self::Lyrics => "Lyrics",
+            Self::TrackCredits => "Track credits",
+            Self::BrowsePlaylists => "Featured and genre playlists",
+        }
+    }
+
+    /// Feature served by a request to `path` on `host`, with the GraphQL
+    /// `operation` of the partner API.  `None` for the endpoints the app
+    /// cannot do without, like the library and playback ones.
+    pub fn for_request(host: &str, path: &str, operation: Option<&str>) -> Option<Self> {
+        match host {
+            "api-partner.spotify.com" => match operation? {
+                "homeSection" => Some(Self::Home),
+                "queryArtistOverview" => Some(Self::ArtistInfo),
+                _ => None,
+            },
+            "spclient.wg.spotify.com" if path.starts_with("color-lyrics/") => Some(Self::Lyrics),
+            "spclient.wg.spotify.com" if path.starts_with("track-credits-view/") => {
+                Some(Self::TrackCredits)
+            }
+            "api.spotify.com" if path.starts_with("v1/recommendations") => {
+                Some(Self::Recommendations)
+            }
+            "api.spotify.com" if path.starts_with("v1/audio-analysis/") => {
+                Some(Self::AudioAnalysis)
+            }
+            "api.spotify.com" if path.starts_with("v1/audio-features/") => {
+                Some(Self::AudioFeatures)
+            }
+            "api.spotify.com" if path.ends_with("/related-artists") => Some(Self::RelatedArtists),
+            "api.spotify.com"
+                if path.starts_with("v1/browse/featured-playlists")
+                    || (path.starts_with("v1/browse/categories/")
+                        && path.ends_with("/playlists")) =>
+            {
+                Some(Self::BrowsePlaylists)
+            }
+            _ => None,
+        }
+    }
+
+    /// Does a response with the status `code` mean the endpoint was taken
+    /// away, or closed to third-party apps.  Lyrics and credits are missing
+    /// for many tracks, so only a 410 counts for them.
+    pub fn is_removal(self, code: u16) -> bool {
+        match self {
+            Self::Lyrics | Self::TrackCredits => code == 410,
+            _ => code == 404 || code == 410,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Data)]
+pub enum ApiStatus {
+    Available,
+    /// The endpoint is gone, see `ApiFeature::is_removal`.
+    Removed,
+    /// The response could not be read, with the reason.
+    Changed(Arc<str>),
+}
+
+impl ApiStatus {
+    pub fn is_available(&self) -> bool {
+        matches!(self, Self::Available)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Available => "Available".to_string(),
+            Self::Removed => "No longer offered by Spotify".to_string(),
+            Self::Changed(reason) => format!("Changed on Spotify's side: {reason}"),
+        }
+    }
+}
+
+/// Last known status of every feature used so far.
+#[derive(Default)]
+pub struct Compatibility {
+    statuses: Mutex<HashMap<ApiFeature, ApiStatus>>,
+    /// Features that were already reported as unavailable this session.
+    reported: Mutex<HashSet<ApiFeature>>,
+}
+
+impl Compatibility {
+    /// Record the outcome of a request, `true` if the status of the feature
+    /// changed.
+    pub fn record(&self, feature: ApiFeature, status: ApiStatus) -> bool {
+        self.statuses.lock().insert(feature, status.clone()) != Some(status)
+    }
+
+    pub fn status(&self, feature: ApiFeature) -> Option<ApiStatus> {
+        self.statuses.lock().get(&feature).cloned()
+    }
+
+    /// `true` only the first time `feature` turns unavailable in this
+    /// session, so that a feature flapping between statuses is reported
+    /// once, and not with every change.
+    pub fn first_failure(&self, feature: ApiFeature, status: &ApiStatus) -> bool {
+        !status.is_available() && self.reported.lock().insert(feature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_requests() {
+        let feature = |host, path, operation| ApiFeature::for_request(host, path, operation);
+        assert_eq!(
+            feature("api.spotify.com", "v1/recommendations", None),
+            Some(ApiFeature::Recommendations)
+        );
+        assert_eq!(
+            feature("api.spotify.com", "v1/audio-features/abc", None),
+            Some(ApiFeature::AudioFeatures)
+        );
+        assert_eq!(
+            feature("api.spotify.com", "v1/artists/abc/related-artists", None),
+            Some(ApiFeature::RelatedArtists)
+        );
+        assert_eq!(
+            feature(
+                "api-partner.spotify.com",
+                "pathfinder/v2/query",
+                Some("homeSection")
+            ),
+            Some(ApiFeature::Home)
+        );
+        assert_eq!(
+            feature(
+                "api.spotify.com",
+                "v1/browse/categories/pop/playlists",
+                None
+            ),
+            Some(ApiFeature::BrowsePlaylists)
+        );
+        assert_eq!(
+            feature("api.spotify.com", "v1/browse/categories", None),
+            None
+        );
+        assert_eq!(feature("api.spotify.com", "v1/me/tracks", None), None);
+        assert_eq!(
+            feature("api-partner.spotify.com", "pathfinder/v2/query", None),
+            None
+        );
+
+        assert!(ApiFeature::Recommendations.is_removal(404));
+        assert!(!ApiFeature::Lyrics.is_removal(404));
+        assert!(ApiFeature::Lyrics.is_removal(410));
+    }
+
+    #[test]
+    fn records_only_changes() {
+        let compat = Compatibility::default();
+        assert!(compat.record(ApiFeature::Lyrics, ApiStatus::Available));
+        assert!(!compat.record(ApiFeature::Lyrics, ApiStatus::Available));
+        assert!(compat.record(ApiFeature::Lyrics, ApiStatus::Removed));
+        assert!(compat.record(ApiFeature::Home, ApiStatus::Changed("missing field".into())));
+    }
+
+    #[test]
+    fn reports_each_failure_once() {
+        let compat = Compatibility::default();
+        let changed = ApiStatus::Changed("missing field".into());
+        assert!(!compat.first_failure(ApiFeature::Lyrics, &ApiStatus::Available));
+        assert!(compat.first_failure(ApiFeature::Lyrics, &ApiStatus::Removed));
+        assert!(!compat.first_failure(ApiFeature::Lyrics, &ApiStatus::Available));
+        assert!(!compat.first_failure(ApiFeature::Lyrics, &changed));
+        assert!(compat.first_failure(ApiFeature::Home, &changed));
+    }
+}
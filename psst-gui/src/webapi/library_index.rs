@@ -0,0 +1,181 @@
+//! Copy of the library in the cache directory.  The saved tracks and albums
+//! are synced by loading only what was saved since the last sync, and the
+//! library can be browsed without a connection.  Items are kept in the JSON
+//! the API returned, and are read back like fresh responses.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::BufReader,
+    path::PathBuf,
+};
+
+use serde_json::Value;
+
+pub const SAVED_TRACKS: &str = "saved_tracks";
+pub const SAVED_ALBUMS: &str = "saved_albums";
+pub const PLAYLISTS: &str = "playlists";
+
+pub struct LibraryIndex {
+    dir: Option<PathBuf>,
+}
+
+impl LibraryIndex {
+    pub fn new(cache_base: Option<PathBuf>) -> Self {
+        Self {
+            dir: cache_base.map(|base| base.join("library")),
+        }
+    }
+
+    fn path(&self, list: &str) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(format!("{list}.json")))
+    }
+
+    /// Indexed items of `list`, `None` if it was never synced.
+    pub fn load(&self, list: &str) -> Option<Vec<Value>> {
+        let file = File::open(self.path(list)?).ok()?;
+        match serde_json::from_reader(BufReader::new(file)) {
+            Ok(items) => Some(items),
+            Err(err) => {
+                log::warn!("ignoring the broken library index {list}: {err}");
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, list: &str, items: &[Value]) {
+        let (Some(dir), Some(path)) = (&self.dir, self.path(list)) else {
+            return;
+        };
+        // Written aside and moved in place, so that a crash never leaves a
+        // half-written index.
+        let partial = path.with_extension("json.part");
+        let result = fs::create_dir_all(dir)
+            .and_then(|_| fs::write(&partial, serde_json::to_vec(items)?))
+            .and_then(|_| fs::rename(&partial, &path));
+        if let Err(err) = result {
+            log::error!("failed to save the library index {list}: {err}");
+        }
+    }
+}
+
+/// Syncs a list that the API orders by the date the items were saved, the
+/// newest first, with its indexed items.  Pages are read from the start
+/// until an item is found that was indexed with the same date.
+pub struct DeltaSync {
+    /// Field of the entries holding the saved item, like "track".
+    field: &'static str,
+    indexed: Vec<Value>,
+    known: HashSet<(String, String)>,
+    newest: Vec<Value>,
+}
+
+impl DeltaSync {
+    pub fn new(field: &'static str, indexed: Vec<Value>) -> Self {
+        let known = indexed
+            .iter()
+            .filter_map(|entry| Self::key(field, entry))
+            .collect();
+        Self {
+            field,
+            indexed,
+            known,
+            newest: Vec::new(),
+        }
+    }
+
+    fn key(field: &str, entry: &Value) -> Option<(String, String)> {
+        let id = Self::id(field, entry)?;
+        let added_at = entry["added_at"].as_str()?;
+        Some((id.to_owned(), added_at.to_owned()))
+    }
+
+    fn id<'a>(field: &str, entry: &'a Value) -> Option<&'a str> {
+        entry[field]["id"].as_str()
+    }
+
+    /// Take in the next page, `true` once it reached the indexed items, or
+    /// the end of the list.
+    pub fn add_page(&mut self, entries: impl IntoIterator<Item = Value>) -> bool {
+        let mut empty = true;
+        for entry in entries {
+            empty = false;
+            if Self::key(self.field, &entry).is_some_and(|key| self.known.contains(&key)) {
+                return true;
+            }
+            self.newest.push(entry);
+        }
+        empty
+    }
+
+    /// The synced items, the new ones followed by the indexed ones that were
+    /// not saved again.  `None` if they do not add up to the `total` of the
+    /// API, because items were removed, and the list has to be loaded whole.
+    pub fn finish(self, total: usize) -> Option<Vec<Value>> {
+        let field = self.field;
+        let saved_again: HashSet<String> = self
+            .newest
+            .iter()
+            .filter_map(|entry| Self::id(field, entry).map(str::to_owned))
+            .collect();
+        let mut items = self.newest;
+        items.extend(
+            self.indexed
+                .into_iter()
+                .filter(|entry| !Self::id(field, entry).is_some_and(|id| saved_again.contains(id))),
+        );
+        (items.len() == total).then_some(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn saved(id: &str, added_at: &str) -> Value {
+        json!({ "added_at": added_at, "track": { "id": id } })
+    }
+
+    fn ids(items: &[Value]) -> Vec<&str> {
+        items
+            .iter()
+            .map(|item| item["track"]["id"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn syncs_only_the_newly_saved_items() {
+        let indexed = vec![saved("b", "2024-02-01"), saved("a", "2024-01-01")];
+
+        let mut sync = DeltaSync::new("track", indexed.clone());
+        assert!(sync.add_page([saved("c", "2024-03-01"), saved("b", "2024-02-01")]));
+        assert_eq!(ids(&sync.finish(3).unwrap()), ["c", "b", "a"]);
+
+        let mut sync = DeltaSync::new("track", indexed.clone());
+        assert!(sync.add_page([saved("b", "2024-02-01")]));
+        assert_eq!(ids(&sync.finish(2).unwrap()), ["b", "a"]);
+
+        let mut sync = DeltaSync::new("track", indexed.clone());
+        assert!(!sync.add_page([saved("d", "2024-04-01"), saved("c", "2024-03-01")]));
+        assert!(sync.add_page([]));
+        assert_eq!(ids(&sync.finish(4).unwrap()), ["d", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn saving_again_moves_items_to_the_top() {
+        let indexed = vec![saved("b", "2024-02-01"), saved("a", "2024-01-01")];
+        let mut sync = DeltaSync::new("track", indexed);
+        assert!(sync.add_page([saved("a", "2024-03-01"), saved("b", "2024-02-01")]));
+        assert_eq!(ids(&sync.finish(2).unwrap()), ["a", "b"]);
+    }
+
+    #[test]
+    fn removals_need_a_full_load() {
+        let indexed = vec![saved("b", "2024-02-01"), saved("a", "2024-01-01")];
+        let mut sync = DeltaSync::new("track", indexed);
+        assert!(sync.add_page([saved("c", "2024-03-01"), saved("b", "2024-02-01")]));
+        assert_eq!(sync.finish(2), None);
+    }
+}
@@ -170,9 +170,13 @@ impl LocalTrackManager {
                     local_path: Some(parsed_track.path.clone()),
                     // TODO: Change this to true once playback is supported.
                     is_playable: Some(false),
+                    restrictions: None,
+                    external_ids: Default::default(),
                     popularity: local_track.popularity,
                     track_pos: 0,
                     lyrics: None,
+                    added_at: None,
+                    added_by: None,
                 }));
             }
         }
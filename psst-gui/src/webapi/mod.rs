@@ -1,5 +1,8 @@
 mod cache;
 mod client;
+mod compat;
+mod library_index;
 mod local;
 
 pub use client::WebApi;
+pub use compat::{ApiFeature, ApiStatus};
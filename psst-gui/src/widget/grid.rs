@@ -0,0 +1,111 @@
+use std::cmp::Ordering;
+
+use druid::{widget::prelude::*, widget::ListIter, Point, WidgetPod};
+
+/// Lays out the items of a list in rows, starting a new row when the next
+/// item does not fit, like a grid of cards.
+pub struct Grid<T> {
+    closure: Box<dyn Fn() -> Box<dyn Widget<T>>>,
+    children: Vec<WidgetPod<T, Box<dyn Widget<T>>>>,
+    spacing: f64,
+}
+
+impl<T: Data> Grid<T> {
+    pub fn new<W: Widget<T> + 'static>(closure: impl Fn() -> W + 'static) -> Self {
+        Self {
+            closure: Box::new(move || Box::new(closure())),
+            children: Vec::new(),
+            spacing: 0.0,
+        }
+    }
+
+    pub fn with_spacing(mut self, spacing: f64) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Add or remove children to match the items, `true` if any were.
+    fn update_child_count(&mut self, data: &impl ListIter<T>) -> bool {
+        let len = self.children.len();
+        match len.cmp(&data.data_len()) {
+            Ordering::Greater => self.children.truncate(data.data_len()),
+            Ordering::Less => data.for_each(|_, index| {
+                if index >= len {
+                    self.children.push(WidgetPod::new((self.closure)()));
+                }
+            }),
+            Ordering::Equal => {}
+        }
+        len != data.data_len()
+    }
+}
+
+impl<C: Data, T: ListIter<C>> Widget<T> for Grid<C> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let mut children = self.children.iter_mut();
+        data.for_each_mut(|child_data, _| {
+            if let Some(child) = children.next() {
+                child.event(ctx, event, child_data, env);
+            }
+        });
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.update_child_count(data) {
+                ctx.children_changed();
+            }
+        }
+        let mut children = self.children.iter_mut();
+        data.for_each(|child_data, _| {
+            if let Some(child) = children.next() {
+                child.lifecycle(ctx, event, child_data, env);
+            }
+        });
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        let mut children = self.children.iter_mut();
+        data.for_each(|child_data, _| {
+            if let Some(child) = children.next() {
+                child.update(ctx, child_data, env);
+            }
+        });
+        if self.update_child_count(data) {
+            ctx.children_changed();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let max_width = bc.max().width;
+        let child_bc = BoxConstraints::new(Size::ZERO, Size::new(max_width, f64::INFINITY));
+        let mut children = self.children.iter_mut();
+        let mut position = Point::ORIGIN;
+        let mut row_height: f64 = 0.0;
+        let mut width: f64 = 0.0;
+        data.for_each(|child_data, _| {
+            let Some(child) = children.next() else {
+                return;
+            };
+            let size = child.layout(ctx, &child_bc, child_data, env);
+            if position.x > 0.0 && position.x + size.width > max_width {
+                position = Point::new(0.0, position.y + row_height + self.spacing);
+                row_height = 0.0;
+            }
+            child.set_origin(ctx, position);
+            width = width.max(position.x + size.width);
+            row_height = row_height.max(size.height);
+            position.x += size.width + self.spacing;
+        });
+        bc.constrain(Size::new(width, position.y + row_height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let mut children = self.children.iter_mut();
+        data.for_each(|child_data, _| {
+            if let Some(child) = children.next() {
+                child.paint(ctx, child_data, env);
+            }
+        });
+    }
+}
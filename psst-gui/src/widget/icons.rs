@@ -1,5 +1,5 @@
 use crate::ui::theme;
-use druid::{kurbo::BezPath, widget::prelude::*, Affine, Color, KeyOrValue, Size};
+use druid::{kurbo::BezPath, widget::prelude::*, Affine, Color, KeyOrValue, Point, Size};
 
 #[allow(dead_code)]
 pub static LOGO: SvgIcon = SvgIcon {
@@ -69,18 +69,18 @@ pub static SKIP_FORWARD: SvgIcon = SvgIcon {
     svg_size: Size::new(28.0, 28.0),
     op: PaintOp::Fill,
 };
-// ...
-pub static PLAY_SEQUENTIAL: SvgIcon = SvgIcon {
-    svg_path: "M3 8C3 8.55228 2.55228 9 2 9C1.44772 9 1 8.55228 1 8C1 7.44772 1.44772 7 2 7C2.55228 7 3 7.44772 3 8Z M7 8C7 8.55228 6.55228 9 6 9C5.44772 9 5 8.55228 5 8C5 7.44772 5.44772 7 6 7C6.55228 7 7 7.44772 7 8Z M11 8C11 8.55228 10.5523 9 10 9C9.44772 9 9 8.55228 9 8C9 7.44772 9.44772 7 10 7C10.5523 7 11 7.44772 11 8Z M15 8C15 8.55228 14.5523 9 14 9C13.4477 9 13 8.55228 13 8C13 7.44772 13.4477 7 14 7C14.5523 7 15 7.44772 15 8Z",
-    svg_size: Size::new(16.0, 16.0),
-    op: PaintOp::Fill,
-};
 // SF Pro Regular - shuffle
 pub static PLAY_SHUFFLE: SvgIcon = SvgIcon {
     svg_path: "M1.90792 10.4894C1.90792 10.846 2.17411 11.0971 2.5558 11.0971H3.74107C4.63504 11.0971 5.17746 10.8309 5.78516 10.1077L6.92522 8.75167L8.0452 10.0876C8.66797 10.8309 9.28069 11.1021 10.1797 11.1021H11.0737V12.202C11.0737 12.5033 11.2645 12.6942 11.5709 12.6942C11.7065 12.6942 11.832 12.644 11.9325 12.5636L13.9113 10.9163C14.1574 10.7154 14.1523 10.389 13.9113 10.1881L11.9325 8.53572C11.832 8.45034 11.7065 8.40011 11.5709 8.40011C11.2645 8.40011 11.0737 8.59096 11.0737 8.8923V9.87668H10.2048C9.63728 9.87668 9.28571 9.69085 8.87388 9.20368L7.70871 7.82255L8.87891 6.43136C9.29576 5.93415 9.61719 5.76339 10.1747 5.76339H11.0737V6.76284C11.0737 7.06417 11.2645 7.25502 11.5709 7.25502C11.7065 7.25502 11.832 7.2048 11.9325 7.12444L13.9113 5.47712C14.1574 5.27623 14.1523 4.94978 13.9113 4.74888L11.9325 3.09654C11.832 3.01116 11.7065 2.96094 11.5709 2.96094C11.2645 2.96094 11.0737 3.15179 11.0737 3.45313V4.53795H10.1847C9.25558 4.53795 8.66797 4.79911 8.01004 5.59263L6.92522 6.88337L5.78516 5.53237C5.17746 4.80915 4.59989 4.53795 3.70592 4.53795H2.5558C2.17411 4.53795 1.90792 4.79409 1.90792 5.15067C1.90792 5.50725 2.17913 5.76339 2.5558 5.76339H3.66071C4.20313 5.76339 4.56473 5.9442 4.97656 6.43638L6.13672 7.81752L4.97656 9.20368C4.55971 9.69587 4.23326 9.87668 3.69587 9.87668H2.5558C2.17913 9.87668 1.90792 10.1328 1.90792 10.4894Z",
     svg_size: Size::new(16.0, 20.0),
     op: PaintOp::Fill,
 };
+// SF Pro Regular - shuffle, with a dot underneath
+pub static PLAY_SMART_SHUFFLE: SvgIcon = SvgIcon {
+    svg_path: "M1.90792 10.4894C1.90792 10.846 2.17411 11.0971 2.5558 11.0971H3.74107C4.63504 11.0971 5.17746 10.8309 5.78516 10.1077L6.92522 8.75167L8.0452 10.0876C8.66797 10.8309 9.28069 11.1021 10.1797 11.1021H11.0737V12.202C11.0737 12.5033 11.2645 12.6942 11.5709 12.6942C11.7065 12.6942 11.832 12.644 11.9325 12.5636L13.9113 10.9163C14.1574 10.7154 14.1523 10.389 13.9113 10.1881L11.9325 8.53572C11.832 8.45034 11.7065 8.40011 11.5709 8.40011C11.2645 8.40011 11.0737 8.59096 11.0737 8.8923V9.87668H10.2048C9.63728 9.87668 9.28571 9.69085 8.87388 9.20368L7.70871 7.82255L8.87891 6.43136C9.29576 5.93415 9.61719 5.76339 10.1747 5.76339H11.0737V6.76284C11.0737 7.06417 11.2645 7.25502 11.5709 7.25502C11.7065 7.25502 11.832 7.2048 11.9325 7.12444L13.9113 5.47712C14.1574 5.27623 14.1523 4.94978 13.9113 4.74888L11.9325 3.09654C11.832 3.01116 11.7065 2.96094 11.5709 2.96094C11.2645 2.96094 11.0737 3.15179 11.0737 3.45313V4.53795H10.1847C9.25558 4.53795 8.66797 4.79911 8.01004 5.59263L6.92522 6.88337L5.78516 5.53237C5.17746 4.80915 4.59989 4.53795 3.70592 4.53795H2.5558C2.17411 4.53795 1.90792 4.79409 1.90792 5.15067C1.90792 5.50725 2.17913 5.76339 2.5558 5.76339H3.66071C4.20313 5.76339 4.56473 5.9442 4.97656 6.43638L6.13672 7.81752L4.97656 9.20368C4.55971 9.69587 4.23326 9.87668 3.69587 9.87668H2.5558C2.17913 9.87668 1.90792 10.1328 1.90792 10.4894Z M9 16C9 16.5523 8.55228 17 8 17C7.44772 17 7 16.5523 7 16C7 15.4477 7.44772 15 8 15C8.55228 15 9 15.4477 9 16Z",
+    svg_size: Size::new(16.0, 20.0),
+    op: PaintOp::Fill,
+};
 // SF Pro Regular - repeat.1
 pub static PLAY_LOOP_TRACK: SvgIcon = SvgIcon {
     svg_path: "M13.0273 6.49665C13.3789 6.49665 13.5848 6.30581 13.5848 5.92411V3.56864C13.5848 3.16686 13.3186 2.90067 12.9219 2.90067C12.6004 2.90067 12.4046 3.00112 12.1484 3.19197L11.5006 3.68416C11.3499 3.79967 11.2946 3.91016 11.2946 4.04576C11.2946 4.24665 11.4403 4.40235 11.6663 4.40235C11.7667 4.40235 11.8521 4.36719 11.9375 4.3019L12.4196 3.91016H12.4598V5.92411C12.4598 6.30581 12.6708 6.49665 13.0273 6.49665ZM2.35491 7.37054C2.35491 7.72712 2.62612 7.99833 2.9827 7.99833C3.34431 7.99833 3.61049 7.72712 3.61049 7.37054V7.04409C3.61049 6.22545 4.17299 5.69811 5.02679 5.69811H7.56808V6.74777C7.56808 7.04911 7.75893 7.23996 8.06027 7.23996C8.19587 7.23996 8.32645 7.18974 8.4269 7.10938L10.4057 5.46206C10.6468 5.26116 10.6468 4.93471 10.4057 4.73382L8.4269 3.08148C8.32645 2.9961 8.19587 2.94587 8.06027 2.94587C7.75893 2.94587 7.56808 3.13672 7.56808 3.43806V4.46261H5.12723C3.42467 4.46261 2.35491 5.41183 2.35491 6.92355V7.37054ZM7.19141 8.74665C7.19141 8.44532 7.00056 8.24944 6.69922 8.24944C6.56362 8.24944 6.43304 8.30469 6.33259 8.38505L4.35882 10.0324C4.11272 10.2282 4.11272 10.5547 4.35882 10.7606L6.33259 12.4129C6.43304 12.4983 6.56362 12.5486 6.69922 12.5486C7.00056 12.5486 7.19141 12.3577 7.19141 12.0564V11.0268H10.8677C12.5703 11.0268 13.635 10.0725 13.635 8.56585V8.11886C13.635 7.75726 13.3638 7.48605 13.0073 7.48605C12.6507 7.48605 12.3795 7.75726 12.3795 8.11886V8.44532C12.3795 9.25893 11.822 9.7913 10.9632 9.7913H7.19141V8.74665Z",
@@ -101,6 +101,34 @@ pub static MUSIC_NOTE: SvgIcon = SvgIcon {
     op: PaintOp::Fill,
 };
 
+// Speaker with a single sound wave
+pub static SPEAKER: SvgIcon = SvgIcon {
+    svg_path: "M3 9H7L12 5V19L7 15H3Z M15 8.5C16.3 9.4 17 10.6 17 12C17 13.4 16.3 14.6 15 15.5L14.2 14.3C15.1 13.7 15.5 12.9 15.5 12C15.5 11.1 15.1 10.3 14.2 9.7Z",
+    svg_size: Size::new(20.0, 24.0),
+    op: PaintOp::Fill,
+};
+
+// Four corners
+pub static FULL_SCREEN: SvgIcon = SvgIcon {
+    svg_path: "M3 3H8V4.5H4.5V8H3Z M12 3H17V8H15.5V4.5H12Z M17 12V17H12V15.5H15.5V12Z M3 12H4.5V15.5H8V17H3Z",
+    svg_size: Size::new(20.0, 20.0),
+    op: PaintOp::Fill,
+};
+
+// List with a play marker
+pub static QUEUE: SvgIcon = SvgIcon {
+    svg_path: "M3 5H17V6.5H3Z M3 9.25H17V10.75H3Z M3 13.5H11V15H3Z M13 12V17L17 14.5Z",
+    svg_size: Size::new(20.0, 20.0),
+    op: PaintOp::Fill,
+};
+
+// Cross
+pub static CLOSE: SvgIcon = SvgIcon {
+    svg_path: "M5.5 4.4L10 8.9L14.5 4.4L15.6 5.5L11.1 10L15.6 14.5L14.5 15.6L10 11.1L5.5 15.6L4.4 14.5L8.9 10L4.4 5.5Z",
+    svg_size: Size::new(20.0, 20.0),
+    op: PaintOp::Fill,
+};
+
 // SF Pro Regular - exclamationmark.circle
 pub static ERROR: SvgIcon = SvgIcon {
     svg_path: "M13.9912 22.7422C18.9746 22.7422 23.0879 18.6289 23.0879 13.6543C23.0879 8.67969 18.9658 4.56641 13.9824 4.56641C9.00781 4.56641 4.90332 8.67969 4.90332 13.6543C4.90332 18.6289 9.0166 22.7422 13.9912 22.7422ZM13.9912 20.9316C9.95703 20.9316 6.73145 17.6885 6.73145 13.6543C6.73145 9.62012 9.95703 6.38574 13.9824 6.38574C18.0166 6.38574 21.2598 9.62012 21.2686 13.6543C21.2773 17.6885 18.0254 20.9316 13.9912 20.9316ZM13.9824 15.1133C14.4658 15.1133 14.7471 14.8408 14.7559 14.3311L14.8877 10.1035C14.9053 9.58496 14.5186 9.20703 13.9736 9.20703C13.4287 9.20703 13.0508 9.57617 13.0684 10.0947L13.1914 14.3311C13.209 14.832 13.4902 15.1133 13.9824 15.1133ZM13.9824 18.0312C14.5537 18.0312 15.0195 17.6182 15.0195 17.0557C15.0195 16.502 14.5625 16.0889 13.9824 16.0889C13.4111 16.0889 12.9453 16.502 12.9453 17.0557C12.9453 17.6094 13.4199 18.0312 13.9824 18.0312Z",
@@ -245,8 +273,17 @@ impl<T> Widget<T> for Icon {
 
     fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, env: &Env) {
         let color = self.color.resolve(env);
+        // Layout can place the icon between device pixels at fractional
+        // display scales, which blurs every edge of the path, so move it onto
+        // the pixel grid first.
+        let scale = ctx.window().get_scale().unwrap_or_default();
+        let origin = ctx.window_origin();
+        let snapped = Point::new(
+            (origin.x * scale.x()).round() / scale.x(),
+            (origin.y * scale.y()).round() / scale.y(),
+        );
         ctx.with_save(|ctx| {
-            ctx.transform(self.scale);
+            ctx.transform(Affine::translate(snapped - origin) * self.scale);
             match self.op {
                 PaintOp::Fill => ctx.fill(&self.bez_path, &color),
             }
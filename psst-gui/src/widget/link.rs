@@ -52,7 +52,7 @@ impl<T: Data> Widget<T> for Link<T> {
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
-        if let LifeCycle::HotChanged(_) = event {
+        if let LifeCycle::HotChanged(_) | LifeCycle::DisabledChanged(_) = event {
             ctx.request_paint();
         }
         self.inner.lifecycle(ctx, event, data, env)
@@ -69,18 +69,27 @@ impl<T: Data> Widget<T> for Link<T> {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let is_active = self
+            .is_active
+            .as_ref()
+            .is_some_and(|predicate| predicate(data, env));
         let background = if ctx.is_hot() {
             env.get(theme::LINK_HOT_COLOR)
+        } else if is_active {
+            env.get(theme::LINK_ACTIVE_COLOR)
         } else {
-            let is_active = self
-                .is_active
-                .as_ref()
-                .is_some_and(|predicate| predicate(data, env));
-            if is_active {
-                env.get(theme::LINK_ACTIVE_COLOR)
-            } else {
-                env.get(theme::LINK_COLD_COLOR)
-            }
+            env.get(theme::LINK_COLD_COLOR)
+        };
+        // Icons in the theme color are tinted with the state of the link,
+        // icons with colors of their own keep them.
+        let icon_color = if ctx.is_disabled() {
+            Some(theme::ICON_COLOR_DISABLED)
+        } else if ctx.is_active() || is_active {
+            Some(theme::ICON_COLOR_ACTIVE)
+        } else if ctx.is_hot() {
+            Some(theme::ICON_COLOR_HOT)
+        } else {
+            None
         };
         let border_color = self.border_color.resolve(env);
         let border_width = self.border_width.resolve(env);
@@ -100,6 +109,12 @@ impl<T: Data> Widget<T> for Link<T> {
                 ctx.fill(rounded_rect, &background);
             }
         }
-        self.inner.paint(ctx, data, env);
+        match icon_color {
+            Some(key) => {
+                let env = env.clone().adding(theme::ICON_COLOR, env.get(key));
+                self.inner.paint(ctx, data, &env);
+            }
+            None => self.inner.paint(ctx, data, env),
+        }
     }
 }
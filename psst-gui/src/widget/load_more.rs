@@ -0,0 +1,54 @@
+use druid::{widget::prelude::*, Selector};
+
+/// Placed after a list that is loaded a page at a time, asks for the next
+/// page with the `load` command once it is scrolled into view.  `next`
+/// gives the payload of the command, `None` when there is nothing more to
+/// load, or while a page is being loaded.
+pub struct LoadMore<T, U> {
+    next: Box<dyn Fn(&T) -> Option<U>>,
+    load: Selector<U>,
+    /// Page asked for and not loaded yet.
+    requested: Option<U>,
+}
+
+impl<T: Data, U: Clone + PartialEq + 'static> LoadMore<T, U> {
+    pub fn new(next: impl Fn(&T) -> Option<U> + 'static, load: Selector<U>) -> Self {
+        Self {
+            next: Box::new(next),
+            load,
+            requested: None,
+        }
+    }
+}
+
+impl<T: Data, U: Clone + PartialEq + 'static> Widget<T> for LoadMore<T, U> {
+    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut T, _env: &Env) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &T, _env: &Env) {}
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, _env: &Env) {
+        let Some(next) = (self.next)(data) else {
+            return;
+        };
+        if (self.next)(old_data).is_none() && self.requested.as_ref() == Some(&next) {
+            // The page failed to load.  It is asked for again with the next
+            // scroll, and not right away, so that a failing request does not
+            // repeat on its own.
+            self.requested = None;
+            return;
+        }
+        // Scrolling changes the data of the app, so this runs on every
+        // scroll, like in `Paged`.
+        let visible = ctx.window_origin().y < ctx.window().get_size().height;
+        if visible && self.requested.as_ref() != Some(&next) {
+            self.requested = Some(next.clone());
+            ctx.submit_command(self.load.with(next));
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, _env: &Env) -> Size {
+        bc.constrain(Size::new(0.0, 1.0))
+    }
+
+    fn paint(&mut self, _ctx: &mut PaintCtx, _data: &T, _env: &Env) {}
+}
@@ -2,14 +2,19 @@ mod checkbox;
 mod dispatcher;
 mod empty;
 pub mod fill_between;
+mod grid;
 pub mod icons;
 mod link;
+mod load_more;
 mod maybe;
 mod overlay;
+mod paged;
 mod promise;
 pub mod remote_image;
+mod slider_keys;
 mod theme;
 mod utils;
+pub mod zoom;
 
 use std::{sync::Arc, time::Duration};
 
@@ -22,13 +27,18 @@ pub use checkbox::Checkbox;
 pub use dispatcher::ViewDispatcher;
 use druid_shell::Cursor;
 pub use empty::Empty;
+pub use grid::Grid;
 pub use link::Link;
+pub use load_more::LoadMore;
 pub use maybe::Maybe;
 pub use overlay::Overlay;
+pub use paged::Paged;
 pub use promise::Async;
 pub use remote_image::RemoteImage;
+pub use slider_keys::{SliderKeys, SliderSteps};
 pub use theme::ThemeScope;
-pub use utils::{Border, Clip, FadeOut, Logger};
+pub use utils::{Border, Clip, FadeOut, Logger, ShowOnHover};
+pub use zoom::Zoom;
 
 use crate::{
     controller::{
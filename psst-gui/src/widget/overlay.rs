@@ -2,6 +2,8 @@ use druid::{widget::prelude::*, Data, Point, Vec2, WidgetPod};
 
 pub enum OverlayPosition {
     Bottom,
+    /// Centered at the top, sized by the overlay itself.
+    Top,
 }
 
 pub struct Overlay<T, W, O> {
@@ -21,6 +23,14 @@ where
             position: OverlayPosition::Bottom,
         }
     }
+
+    pub fn top(inner: W, overlay: O) -> Self {
+        Self {
+            inner,
+            overlay: WidgetPod::new(overlay),
+            position: OverlayPosition::Top,
+        }
+    }
 }
 
 impl<T, W, O> Widget<T> for Overlay<T, W, O>
@@ -46,11 +56,15 @@ where
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
         let inner_size = self.inner.layout(ctx, bc, data, env);
-        let over_size = self.overlay.layout(ctx, bc, data, env);
         let pos = match self.position {
             OverlayPosition::Bottom => {
+                let over_size = self.overlay.layout(ctx, bc, data, env);
                 Point::ORIGIN + Vec2::new(0.0, inner_size.height - over_size.height)
             }
+            OverlayPosition::Top => {
+                let over_size = self.overlay.layout(ctx, &bc.loosen(), data, env);
+                Point::ORIGIN + Vec2::new((inner_size.width - over_size.width) / 2.0, 0.0)
+            }
         };
         self.overlay.set_origin(ctx, pos);
         inner_size
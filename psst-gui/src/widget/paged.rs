@@ -0,0 +1,100 @@
+use druid::{widget::prelude::*, Point, Selector, WidgetPod};
+
+use crate::data::PageWindow;
+
+/// Height of an item until the loaded ones are laid out.
+const DEFAULT_ITEM_HEIGHT: f64 = 40.0;
+
+/// Lays out the loaded items of a list that is loaded in pages, leaving room
+/// for the items before and after them, so that the scroll bar spans the
+/// whole list.  As the list is scrolled, the page that the visible items, or
+/// the ones around them, are in is asked for with the `load` command, with
+/// its offset and limit.
+pub struct Paged<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    window: Box<dyn Fn(&T) -> Option<PageWindow>>,
+    load: Selector<(usize, usize)>,
+    /// Average height of the loaded items.
+    item_height: f64,
+    /// Page asked for and not loaded yet.
+    requested: Option<(usize, usize)>,
+}
+
+impl<T: Data> Paged<T> {
+    pub fn new(
+        child: impl Widget<T> + 'static,
+        window: impl Fn(&T) -> Option<PageWindow> + 'static,
+        load: Selector<(usize, usize)>,
+    ) -> Self {
+        Self {
+            child: WidgetPod::new(child).boxed(),
+            window: Box::new(window),
+            load,
+            item_height: DEFAULT_ITEM_HEIGHT,
+            requested: None,
+        }
+    }
+
+    /// Indices of the first and the last visible item.  The part of the list
+    /// inside the window is taken as visible, as the size of the scrolled
+    /// viewport is not known here.
+    fn visible(&self, ctx: &UpdateCtx, window: &PageWindow) -> (usize, usize) {
+        let top = (-ctx.window_origin().y).max(0.0);
+        let bottom = top + ctx.window().get_size().height;
+        let index = |y: f64| ((y / self.item_height) as usize).min(window.total);
+        (index(top), index(bottom))
+    }
+}
+
+impl<T: Data> Widget<T> for Paged<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+
+        let Some(window) = (self.window)(data) else {
+            return;
+        };
+        if (self.window)(old_data) != Some(window) {
+            self.requested = None;
+            ctx.request_layout();
+        }
+        // Scrolling changes the data of the app, so this runs on every
+        // scroll.
+        if let Some(page) = window.page_to_load(self.visible(ctx, &window)) {
+            if self.requested != Some(page) {
+                self.requested = Some(page);
+                ctx.submit_command(self.load.with(page));
+            }
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let child_bc = BoxConstraints::new(
+            Size::new(bc.min().width, 0.0),
+            Size::new(bc.max().width, f64::INFINITY),
+        );
+        let size = self.child.layout(ctx, &child_bc, data, env);
+        let Some(window) = (self.window)(data) else {
+            self.child.set_origin(ctx, Point::ORIGIN);
+            return bc.constrain(size);
+        };
+        if window.len > 0 {
+            self.item_height = size.height / window.len as f64;
+        }
+        let before = window.offset as f64 * self.item_height;
+        let after = window.total.saturating_sub(window.end()) as f64 * self.item_height;
+        self.child.set_origin(ctx, Point::new(0.0, before));
+        bc.constrain(Size::new(size.width, before + size.height + after))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+    }
+}
@@ -2,12 +2,20 @@ use std::sync::Arc;
 
 use druid::{
     widget::{prelude::*, Image},
-    Data, ImageBuf, Point, Selector, WidgetPod,
+    Data, ImageBuf, Point, Scale, Selector, WidgetPod,
 };
 
-pub const REQUEST_DATA: Selector<Arc<str>> = Selector::new("remote-image.request-data");
+pub const REQUEST_DATA: Selector<ImageRequest> = Selector::new("remote-image.request-data");
 pub const PROVIDE_DATA: Selector<ImagePayload> = Selector::new("remote-image.provide-data");
 
+#[derive(Clone)]
+pub struct ImageRequest {
+    pub location: Arc<str>,
+    /// Size the image is displayed at, in pixels.  Larger images are scaled
+    /// down to it before they are provided.
+    pub size: Option<(u32, u32)>,
+}
+
 #[derive(Clone)]
 pub struct ImagePayload {
     pub location: Arc<str>,
@@ -19,6 +27,7 @@ pub struct RemoteImage<T> {
     image: Option<WidgetPod<T, Image>>,
     locator: Box<dyn Fn(&T, &Env) -> Option<Arc<str>>>,
     location: Option<Arc<str>>,
+    size: Option<Size>,
 }
 
 impl<T: Data> RemoteImage<T> {
@@ -31,8 +40,26 @@ impl<T: Data> RemoteImage<T> {
             locator: Box::new(locator),
             location: None,
             image: None,
+            size: None,
         }
     }
+
+    /// Request the image scaled down to `size`, the size it is displayed at.
+    pub fn with_size(mut self, size: impl Into<Size>) -> Self {
+        self.size = Some(size.into());
+        self
+    }
+
+    fn request(&self, location: Arc<str>, scale: Option<Scale>) -> ImageRequest {
+        let (scale_x, scale_y) = scale.map_or((1.0, 1.0), |scale| (scale.x(), scale.y()));
+        let size = self.size.map(|size| {
+            (
+                (size.width * scale_x).ceil() as u32,
+                (size.height * scale_y).ceil() as u32,
+            )
+        });
+        ImageRequest { location, size }
+    }
 }
 
 impl<T: Data> Widget<T> for RemoteImage<T> {
@@ -60,7 +87,8 @@ impl<T: Data> Widget<T> for RemoteImage<T> {
             self.image = None;
             self.location.clone_from(&location);
             if let Some(location) = location {
-                ctx.submit_command(REQUEST_DATA.with(location).to(ctx.widget_id()));
+                let request = self.request(location, ctx.window().get_scale().ok());
+                ctx.submit_command(REQUEST_DATA.with(request).to(ctx.widget_id()));
             }
         }
         if let Some(image) = self.image.as_mut() {
@@ -76,7 +104,8 @@ impl<T: Data> Widget<T> for RemoteImage<T> {
             self.image = None;
             self.location.clone_from(&location);
             if let Some(location) = location {
-                ctx.submit_command(REQUEST_DATA.with(location).to(ctx.widget_id()));
+                let request = self.request(location, ctx.window().get_scale().ok());
+                ctx.submit_command(REQUEST_DATA.with(request).to(ctx.widget_id()));
             }
             ctx.children_changed();
         }
@@ -0,0 +1,140 @@
+use druid::{widget::prelude::*, Code, Modifiers, Point, WidgetPod};
+
+use crate::{data::AppState, ui::theme};
+
+/// Distances a slider moves for one arrow key press, in the units of the
+/// slider.
+#[derive(Clone, Copy, Debug)]
+pub struct SliderSteps {
+    /// With Shift held.
+    pub fine: f64,
+    pub normal: f64,
+    /// With Ctrl held.
+    pub coarse: f64,
+}
+
+impl SliderSteps {
+    pub fn for_mods(&self, mods: Modifiers) -> f64 {
+        if mods.shift() {
+            self.fine
+        } else if mods.ctrl() {
+            self.coarse
+        } else {
+            self.normal
+        }
+    }
+}
+
+type SliderAction = Box<dyn Fn(&mut EventCtx, &mut AppState, f64) -> Option<String>>;
+
+/// Makes a slider focusable and moves it with the arrow keys, Home and End
+/// jumping to the ends of its range.  The action gets the signed step and
+/// returns the new value of the slider, which is announced in an alert.
+pub struct SliderKeys {
+    inner: WidgetPod<AppState, Box<dyn Widget<AppState>>>,
+    steps: SliderSteps,
+    action: SliderAction,
+    /// Alert showing the value, refreshed while the keys are held.
+    alert: Option<usize>,
+}
+
+impl SliderKeys {
+    pub fn new(
+        inner: impl Widget<AppState> + 'static,
+        steps: SliderSteps,
+        action: impl Fn(&mut EventCtx, &mut AppState, f64) -> Option<String> + 'static,
+    ) -> Self {
+        Self {
+            inner: WidgetPod::new(inner).boxed(),
+            steps,
+            action: Box::new(action),
+            alert: None,
+        }
+    }
+
+    fn announce(&mut self, data: &mut AppState, message: String) {
+        self.alert = match self.alert {
+            Some(id) if data.refresh_alert(id, &message) => Some(id),
+            _ => {
+                data.info_alert(message);
+                data.alerts.back().map(|alert| alert.id)
+            }
+        };
+    }
+}
+
+impl Widget<AppState> for SliderKeys {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, env: &Env) {
+        match event {
+            Event::MouseDown(_) => {
+                ctx.request_focus();
+            }
+            Event::KeyDown(key) if ctx.is_focused() => {
+                let step = self.steps.for_mods(key.mods);
+                let delta = match key.code {
+                    Code::ArrowRight | Code::ArrowUp => step,
+                    Code::ArrowLeft | Code::ArrowDown => -step,
+                    Code::End => f64::INFINITY,
+                    Code::Home => f64::NEG_INFINITY,
+                    Code::Tab if key.mods.shift() => {
+                        ctx.focus_prev();
+                        ctx.set_handled();
+                        return;
+                    }
+                    Code::Tab => {
+                        ctx.focus_next();
+                        ctx.set_handled();
+                        return;
+                    }
+                    // Give the keys back to the playback shortcuts.
+                    Code::Escape => {
+                        ctx.resign_focus();
+                        ctx.set_handled();
+                        return;
+                    }
+                    _ => return,
+                };
+                if let Some(message) = (self.action)(ctx, data, delta) {
+                    self.announce(data, message);
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+        self.inner.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &AppState, env: &Env) {
+        match event {
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(_) => ctx.request_paint(),
+            _ => {}
+        }
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &AppState, data: &AppState, env: &Env) {
+        self.inner.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &AppState,
+        env: &Env,
+    ) -> Size {
+        let size = self.inner.layout(ctx, bc, data, env);
+        self.inner.set_origin(ctx, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
+        self.inner.paint(ctx, data, env);
+        if ctx.is_focused() {
+            let ring = ctx.size().to_rect().inset(-0.5);
+            ctx.stroke(ring, &env.get(theme::PRIMARY_LIGHT), 1.0);
+        }
+    }
+}
@@ -167,6 +167,45 @@ impl<T: Data, S: Shape, W: Widget<T>> Widget<T> for Clip<S, W> {
     }
 }
 
+/// Paints the inner widget only while the mouse is over it.  It still gets
+/// the events otherwise.
+pub struct ShowOnHover<W> {
+    inner: W,
+}
+
+impl<W> ShowOnHover<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: Data, W: Widget<T>> Widget<T> for ShowOnHover<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.inner.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::HotChanged(_) = event {
+            ctx.request_paint();
+        }
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.inner.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        if ctx.is_hot() {
+            self.inner.paint(ctx, data, env);
+        }
+    }
+}
+
 pub enum Border {
     Top,
     Bottom,
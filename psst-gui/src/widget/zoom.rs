@@ -0,0 +1,126 @@
+use druid::{widget::prelude::*, Affine, Data, Point, Selector, Vec2, WidgetPod};
+
+pub const ZOOM: Selector<ZoomAction> = Selector::new("app.zoom");
+
+const MAX_SCALE: f64 = 8.0;
+const ZOOM_STEP: f64 = 1.25;
+const WHEEL_SENSITIVITY: f64 = 0.005;
+
+#[derive(Clone, Copy, Debug)]
+pub enum ZoomAction {
+    In,
+    Out,
+    Reset,
+}
+
+/// Magnifies its child.  Scrolling zooms around the mouse, dragging pans the
+/// zoomed content and double-clicking resets the view.  The child does not
+/// receive any mouse events.
+pub struct Zoom<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    scale: f64,
+    offset: Vec2,
+    drag_start: Option<(Point, Vec2)>,
+}
+
+impl<T: Data> Zoom<T> {
+    pub fn new(child: impl Widget<T> + 'static) -> Self {
+        Self {
+            child: WidgetPod::new(child).boxed(),
+            scale: 1.0,
+            offset: Vec2::ZERO,
+            drag_start: None,
+        }
+    }
+
+    fn zoom_at(&mut self, anchor: Point, scale: f64, size: Size) {
+        let scale = scale.clamp(1.0, MAX_SCALE);
+        // Keep the content under `anchor` in place.
+        let content = (anchor.to_vec2() - self.offset) / self.scale;
+        self.offset = anchor.to_vec2() - content * scale;
+        self.scale = scale;
+        self.clamp_offset(size);
+    }
+
+    fn clamp_offset(&mut self, size: Size) {
+        self.offset.x = self.offset.x.clamp(size.width * (1.0 - self.scale), 0.0);
+        self.offset.y = self.offset.y.clamp(size.height * (1.0 - self.scale), 0.0);
+    }
+
+    fn reset(&mut self) {
+        self.scale = 1.0;
+        self.offset = Vec2::ZERO;
+    }
+}
+
+impl<T: Data> Widget<T> for Zoom<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let size = ctx.size();
+        match event {
+            Event::Command(cmd) if cmd.is(ZOOM) => {
+                let center = size.to_rect().center();
+                match cmd.get_unchecked(ZOOM) {
+                    ZoomAction::In => self.zoom_at(center, self.scale * ZOOM_STEP, size),
+                    ZoomAction::Out => self.zoom_at(center, self.scale / ZOOM_STEP, size),
+                    ZoomAction::Reset => self.reset(),
+                }
+                ctx.request_paint();
+            }
+            Event::Wheel(mouse) => {
+                let factor = (-mouse.wheel_delta.y * WHEEL_SENSITIVITY).exp();
+                self.zoom_at(mouse.pos, self.scale * factor, size);
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                if mouse.count == 2 {
+                    self.reset();
+                    ctx.request_paint();
+                } else {
+                    self.drag_start = Some((mouse.pos, self.offset));
+                    ctx.set_active(true);
+                }
+                ctx.set_handled();
+            }
+            Event::MouseMove(mouse) => {
+                if let Some((start, offset)) = self.drag_start {
+                    self.offset = offset + (mouse.pos - start);
+                    self.clamp_offset(size);
+                    ctx.request_paint();
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseUp(mouse) if mouse.button.is_left() => {
+                self.drag_start = None;
+                ctx.set_active(false);
+                ctx.set_handled();
+            }
+            Event::MouseDown(_) | Event::MouseUp(_) => {}
+            _ => self.child.event(ctx, event, data, env),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, Point::ORIGIN);
+        self.clamp_offset(size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let clip = ctx.size().to_rect();
+        ctx.with_save(|ctx| {
+            ctx.clip(clip);
+            ctx.transform(Affine::translate(self.offset) * Affine::scale(self.scale));
+            self.child.paint(ctx, data, env);
+        });
+    }
+}
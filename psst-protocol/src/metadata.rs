@@ -1037,6 +1037,11 @@ pub enum Format {
     AAC_320 = 11,
     MP4_128 = 12,
     OTHER5 = 13,
+    FLAC_FLAC = 16,
+    XHE_AAC_24 = 18,
+    XHE_AAC_16 = 19,
+    XHE_AAC_12 = 20,
+    FLAC_FLAC_24BIT = 22,
 }
 
 impl Default for Format {
@@ -1062,6 +1067,11 @@ impl From<i32> for Format {
             11 => Format::AAC_320,
             12 => Format::MP4_128,
             13 => Format::OTHER5,
+            16 => Format::FLAC_FLAC,
+            18 => Format::XHE_AAC_24,
+            19 => Format::XHE_AAC_16,
+            20 => Format::XHE_AAC_12,
+            22 => Format::FLAC_FLAC_24BIT,
             _ => Self::default(),
         }
     }
@@ -1084,6 +1094,11 @@ impl<'a> From<&'a str> for Format {
             "AAC_320" => Format::AAC_320,
             "MP4_128" => Format::MP4_128,
             "OTHER5" => Format::OTHER5,
+            "FLAC_FLAC" => Format::FLAC_FLAC,
+            "XHE_AAC_24" => Format::XHE_AAC_24,
+            "XHE_AAC_16" => Format::XHE_AAC_16,
+            "XHE_AAC_12" => Format::XHE_AAC_12,
+            "FLAC_FLAC_24BIT" => Format::FLAC_FLAC_24BIT,
             _ => Self::default(),
         }
     }